@@ -0,0 +1,62 @@
+//! compare the time of diffing with each `EqualityCheck` at both extremes:
+//! a huge keyed table which is unchanged, and a table whose every row changed
+use mt_dom::{diff::*, *};
+use std::time::Instant;
+
+pub type MyNode = Node<&'static str, &'static str, String, String, String>;
+
+fn row(id: usize, generation: usize) -> MyNode {
+    element(
+        "tr",
+        vec![
+            attr("class".to_string(), "row".to_string()),
+            attr("key".to_string(), id.to_string()),
+        ],
+        (0..10).map(|col| {
+            element(
+                "td",
+                vec![attr("class".to_string(), "cell".to_string())],
+                vec![leaf(format!("cell {id}:{col} of {generation}"))],
+            )
+        }),
+    )
+}
+
+fn table(count: usize, generation: usize) -> MyNode {
+    element(
+        "tbody",
+        vec![attr("key".to_string(), "table".to_string())],
+        (0..count).map(|id| row(id, generation)),
+    )
+}
+
+fn time(label: &str, old: &MyNode, new: &MyNode) {
+    let key = "key".to_string();
+    for check in [
+        EqualityCheck::AfterReplace,
+        EqualityCheck::BeforeReplace,
+        EqualityCheck::Never,
+    ] {
+        let options = DiffOptions {
+            equality_check: check,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let patches = diff_with_options(old, new, &key, &options);
+        println!(
+            "{label}, {check:?}: {:?}, {} patches",
+            start.elapsed(),
+            patches.len()
+        );
+    }
+}
+
+fn main() {
+    let count = 5_000;
+    let old = table(count, 0);
+
+    // the equality walk skips the whole unchanged table at once
+    time("unchanged", &old, &table(count, 0));
+    // every cell changed, so the equality walk of each subtree is wasted
+    time("all changed", &old, &table(count, 1));
+}
@@ -0,0 +1,59 @@
+//! compare the size and the time of encoding and decoding the patches of a large diff
+//! with the binary `patch::codec` against the JSON of `patch::to_json`
+use mt_dom::{
+    patch::{codec::*, *},
+    *,
+};
+use std::time::Instant;
+
+pub type MyNode = Node<String, String, String, String, String>;
+pub type MyOwnedPatch = OwnedPatch<String, String, String, String, String>;
+
+/// a keyed list of rows rotated by `rotate`, where every other row has the `text`
+fn rows(count: usize, rotate: usize, text: &str) -> MyNode {
+    element(
+        "tbody".to_string(),
+        vec![],
+        (0..count).map(|i| {
+            let id = (i + rotate) % count;
+            let text = if id.is_multiple_of(2) { text } else { "row" };
+            element(
+                "tr".to_string(),
+                vec![
+                    attr("class".to_string(), "row".to_string()),
+                    attr("key".to_string(), format!("row-{}", id)),
+                ],
+                vec![leaf(format!("{} {}", text, id))],
+            )
+        }),
+    )
+}
+
+fn main() {
+    let count = 10_000;
+    let old = rows(count, 0, "old");
+    let new = rows(count, 1, "new");
+    let patches = diff_with_key(&old, &new, &"key".to_string());
+    println!("{} patches", patches.len());
+
+    let start = Instant::now();
+    let bytes = encode_patches(&patches);
+    println!(
+        "binary encode: {:?}, {} bytes",
+        start.elapsed(),
+        bytes.len()
+    );
+    let start = Instant::now();
+    let decoded: Vec<MyOwnedPatch> = decode_patches(&bytes).unwrap();
+    println!("binary decode: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let json = serde_json::to_string(&to_json(&patches)).unwrap();
+    println!("json encode: {:?}, {} bytes", start.elapsed(), json.len());
+    let start = Instant::now();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let from_json: Vec<MyOwnedPatch> = from_json(&value).unwrap();
+    println!("json decode: {:?}", start.elapsed());
+
+    assert_eq!(decoded, from_json);
+}
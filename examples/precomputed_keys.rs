@@ -0,0 +1,56 @@
+//! compare the time of diffing a massive keyed list using the keys from the attributes
+//! against using the keys which are already known
+use mt_dom::{diff::*, *};
+use std::time::Instant;
+
+pub type MyNode = Node<&'static str, &'static str, String, String, String>;
+
+fn row(id: usize) -> MyNode {
+    element(
+        "tr",
+        vec![
+            attr("class".to_string(), "row".to_string()),
+            attr("data-index".to_string(), id.to_string()),
+            attr("key".to_string(), id.to_string()),
+        ],
+        vec![leaf(format!("row {}", id))],
+    )
+}
+
+fn main() {
+    let count = 10_000;
+    let old_ids: Vec<usize> = (0..count).collect();
+    // move a few rows around and remove every 100th row
+    let mut new_ids: Vec<usize> =
+        (0..count).filter(|id| id % 100 != 0).collect();
+    new_ids.swap(1, count - 200);
+    new_ids.swap(500, 5000);
+
+    let old: Vec<MyNode> = old_ids.iter().map(|id| row(*id)).collect();
+    let new: Vec<MyNode> = new_ids.iter().map(|id| row(*id)).collect();
+    let old_keys: Vec<String> =
+        old_ids.iter().map(|id| id.to_string()).collect();
+    let new_keys: Vec<String> =
+        new_ids.iter().map(|id| id.to_string()).collect();
+
+    let key = "key".to_string();
+    let old_parent = element("tbody", vec![], old.clone());
+    let new_parent = element("tbody", vec![], new.clone());
+
+    let start = Instant::now();
+    let from_attributes = diff_with_key(&old_parent, &new_parent, &key);
+    println!("keys from attributes: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let precomputed = diff_keyed_children_with_keys(
+        &old,
+        &old_keys,
+        &new,
+        &new_keys,
+        &TreePath::root(),
+        &key,
+        &DiffOptions::default(),
+    );
+    println!("precomputed keys: {:?}", start.elapsed());
+    assert_eq!(from_attributes.len(), precomputed.len());
+}
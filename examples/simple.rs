@@ -4,6 +4,7 @@ use mt_dom::*;
 #[derive(Clone)]
 enum Value<'a> {
     Simple(String),
+    #[allow(dead_code)]
     Callback(&'a dyn FnMut(usize) -> String),
 }
 
@@ -0,0 +1,61 @@
+//! compare the time of matching a massive keyed list by scanning the old keys with
+//! `DiffOptions::scan_keys`, against the default matching by the sorted hashes of the keys
+//! and sorting the old keys and binary searching them with `with_ordered_keys`
+use mt_dom::{diff::*, *};
+use std::time::Instant;
+
+pub type MyNode = Node<&'static str, &'static str, String, String, String>;
+
+fn row(id: usize) -> MyNode {
+    element(
+        "tr",
+        vec![
+            attr("class".to_string(), "row".to_string()),
+            attr("key".to_string(), format!("row-{}", id)),
+        ],
+        vec![leaf(format!("row {}", id))],
+    )
+}
+
+fn main() {
+    let count = 10_000;
+    // reverse the rows, which is the worst case of scanning the old keys,
+    // and remove every 100th row
+    let new_ids: Vec<usize> =
+        (0..count).rev().filter(|id| id % 100 != 0).collect();
+
+    let key = "key".to_string();
+    let old: MyNode = element("tbody", vec![], (0..count).map(row));
+    let new: MyNode = element("tbody", vec![], new_ids.into_iter().map(row));
+
+    let options = DiffOptions {
+        scan_keys: true,
+        ..Default::default()
+    };
+    let start = Instant::now();
+    let scanned = diff_with_options(&old, &new, &key, &options);
+    println!("scanned keys: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let hashed = diff_with_key(&old, &new, &key);
+    println!("hashed keys (default): {:?}", start.elapsed());
+
+    let options = DiffOptions::default().with_ordered_keys();
+    let start = Instant::now();
+    let sorted = diff_with_options(&old, &new, &key, &options);
+    println!("sorted keys: {:?}", start.elapsed());
+
+    let scratch = std::cell::RefCell::new(DiffScratch::new());
+    let options = DiffOptions {
+        scratch: Some(&scratch),
+        ..Default::default()
+    };
+    diff_with_options(&old, &new, &key, &options);
+    let start = Instant::now();
+    let reused = diff_with_options(&old, &new, &key, &options);
+    println!("hashed keys with reused buffers: {:?}", start.elapsed());
+
+    assert_eq!(scanned, hashed);
+    assert_eq!(hashed, sorted);
+    assert_eq!(hashed, reused);
+}
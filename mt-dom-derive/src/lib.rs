@@ -0,0 +1,201 @@
+#![deny(warnings, missing_docs)]
+//! Derive macros for building mt-dom nodes from structs
+//!
+//! `#[derive(IntoNode)]` implements `From<YourStruct>` for `mt_dom::Node`, by mapping the
+//! fields of the struct into the attributes and children of an element.
+//!
+//! The tag of the element is specified with `#[node(tag = "li")]` on the struct, and each
+//! field is mapped with one of the field attributes:
+//! - `#[node(attr)]` the field is an attribute named after the field
+//! - `#[node(attr = "data-id")]` the field is an attribute with the given name
+//! - `#[node(leaf)]` the field is a leaf child
+//! - `#[node(child)]` the field is converted into a child node
+//! - `#[node(children)]` the field is an iterator of values which are converted into child nodes
+//!
+//! The fields without a `#[node(..)]` attribute are not included in the node.
+//!
+//! The tag and attribute names are converted with `From<&'static str>`,
+//! the attribute values with `From` the type of the field and so on,
+//! so the same struct can be converted into nodes of different types.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Fields, LitStr, Type,
+};
+
+/// Implement `From<Self>` for `mt_dom::Node`, see the crate documentation for the attributes
+#[proc_macro_derive(IntoNode, attributes(node))]
+pub fn derive_into_node(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match into_node(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// how a field is mapped into the node
+enum Mapping {
+    Attr(LitStr),
+    Leaf,
+    Child,
+    Children,
+}
+
+fn into_node(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let tag = struct_tag(input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(
+                input,
+                "IntoNode can only be derived for structs with named fields",
+            )),
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "IntoNode can only be derived for structs",
+            ))
+        }
+    };
+
+    let ns = format_ident!("__Ns");
+    let tag_ty = format_ident!("__Tag");
+    let leaf = format_ident!("__Leaf");
+    let att = format_ident!("__Att");
+    let val = format_ident!("__Val");
+    let node: Type =
+        parse_quote!(::mt_dom::Node<#ns, #tag_ty, #leaf, #att, #val>);
+
+    let mut attrs = vec![];
+    let mut children = vec![];
+    let mut bounds: Vec<TokenStream2> = vec![];
+    for field in fields {
+        let Some(mapping) = field_mapping(field)? else {
+            continue;
+        };
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        match mapping {
+            Mapping::Attr(name) => {
+                attrs.push(quote! {
+                    ::mt_dom::attr(#att::from(#name), #val::from(value.#ident))
+                });
+                bounds.push(quote!(#val: ::core::convert::From<#ty>));
+            }
+            Mapping::Leaf => {
+                children.push(quote! {
+                    children.push(::mt_dom::leaf(#leaf::from(value.#ident)));
+                });
+                bounds.push(quote!(#leaf: ::core::convert::From<#ty>));
+            }
+            Mapping::Child => {
+                children.push(quote! {
+                    children.push(::core::convert::Into::<#node>::into(value.#ident));
+                });
+                bounds.push(quote!(#ty: ::core::convert::Into<#node>));
+            }
+            Mapping::Children => {
+                children.push(quote! {
+                    children.extend(
+                        ::core::iter::IntoIterator::into_iter(value.#ident)
+                            .map(::core::convert::Into::<#node>::into),
+                    );
+                });
+                bounds.push(quote!(#ty: ::core::iter::IntoIterator));
+                bounds.push(quote! {
+                    <#ty as ::core::iter::IntoIterator>::Item: ::core::convert::Into<#node>
+                });
+            }
+        }
+    }
+
+    let name = &input.ident;
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let params = input.generics.params.iter();
+    let predicates = where_clause.map(|w| &w.predicates);
+    Ok(quote! {
+        impl<#(#params,)* #ns, #tag_ty, #leaf, #att, #val>
+            ::core::convert::From<#name #ty_generics> for #node
+        where
+            #ns: ::core::cmp::PartialEq + ::core::clone::Clone + ::core::fmt::Debug,
+            #tag_ty: ::core::cmp::PartialEq + ::core::fmt::Debug
+                + ::core::convert::From<&'static str>,
+            #leaf: ::core::cmp::PartialEq + ::core::clone::Clone + ::core::fmt::Debug,
+            #att: ::core::cmp::PartialEq + ::core::cmp::Eq + ::core::hash::Hash
+                + ::core::clone::Clone + ::core::fmt::Debug
+                + ::core::convert::From<&'static str>,
+            #val: ::core::cmp::PartialEq + ::core::clone::Clone + ::core::fmt::Debug,
+            #(#bounds,)*
+            #predicates
+        {
+            fn from(value: #name #ty_generics) -> Self {
+                #[allow(unused_mut)]
+                let mut children: ::std::vec::Vec<#node> = ::std::vec::Vec::new();
+                #(#children)*
+                ::mt_dom::element(
+                    #tag_ty::from(#tag),
+                    [#(#attrs),*],
+                    children,
+                )
+            }
+        }
+    })
+}
+
+/// the tag from `#[node(tag = "..")]` of the struct
+fn struct_tag(input: &DeriveInput) -> syn::Result<LitStr> {
+    let mut tag = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("node")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expecting `tag = \"..\"`"))
+            }
+        })?;
+    }
+    tag.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "missing the tag of the element, ie: `#[node(tag = \"div\")]`",
+        )
+    })
+}
+
+/// the mapping from the `#[node(..)]` attribute of the field
+fn field_mapping(field: &syn::Field) -> syn::Result<Option<Mapping>> {
+    let mut mapping = None;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("node")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("attr") {
+                let name = if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<LitStr>()?
+                } else {
+                    let ident =
+                        field.ident.as_ref().expect("named field").to_string();
+                    LitStr::new(ident.trim_start_matches("r#"), meta.path.span())
+                };
+                mapping = Some(Mapping::Attr(name));
+                Ok(())
+            } else if meta.path.is_ident("leaf") {
+                mapping = Some(Mapping::Leaf);
+                Ok(())
+            } else if meta.path.is_ident("child") {
+                mapping = Some(Mapping::Child);
+                Ok(())
+            } else if meta.path.is_ident("children") {
+                mapping = Some(Mapping::Children);
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "expecting one of `attr`, `attr = \"..\"`, `leaf`, `child` or `children`",
+                ))
+            }
+        })?;
+    }
+    Ok(mapping)
+}
@@ -0,0 +1,1067 @@
+//! apply patches to a virtual node tree
+//!
+//! This is mainly used for verifying the patches produced by the differ,
+//! such that applying the patches of `diff(old, new)` into `old` will result to `new`.
+use crate::patch::{validate, InvalidPatch, Placement, TreeId};
+use crate::{Element, Node, Patch, PatchType, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::mem;
+
+pub use key_index::{apply_patches_with_index, KeyIndex};
+
+mod key_index;
+
+/// Apply the patches into the `root` node.
+///
+/// The paths of the patches are all pointing to the nodes of the original tree,
+/// so the target nodes are all resolved first before any of the patches are applied.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{apply_patches::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![], vec![leaf("hello")]);
+/// let new: MyNode = element("main", vec![attr("class", "container")], vec![leaf("world")]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// let mut root = old.clone();
+/// apply_patches(&mut root, &patches);
+/// assert_eq!(root, new);
+/// ```
+///
+/// The `root` node itself can be replaced, wrapped or unwrapped regardless of its variant.
+/// When the root node is replaced by more than 1 node, or unwrapped with more than 1 child,
+/// the new root becomes a fragment of those nodes.
+///
+/// A patch whose target is replaced or unwrapped by a previous patch is applied to
+/// the nodes which took its place, such as the `InsertBeforeNode` after a `ReplaceNode`
+/// of the same path, which inserts the nodes before the replacement.
+///
+/// # Panics
+/// Panics if the path of a patch can not be resolved in the `root` node,
+/// if the target of a patch is removed by a previous patch,
+/// or if there is an `AdoptNode` patch, see `apply_patches_with_trees`
+pub fn apply_patches<Ns, Tag, Leaf, Att, Val>(
+    root: &mut Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    apply_patches_with_trees(root, patches, &mut []);
+}
+
+/// Apply the patches into the `root` node the same as `apply_patches`, where the nodes
+/// adopted by the `AdoptNode` patches are moved out of the auxiliary `trees`.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{apply_patches::*, diff::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let todo: MyNode = element("ul", vec![], vec![
+///     element("li", vec![attr("key", "1")], vec![leaf("one")]),
+///     element("li", vec![attr("key", "2")], vec![leaf("two")]),
+/// ]);
+/// let done: MyNode = element("ul", vec![], vec![]);
+/// let new_done: MyNode = element("ul", vec![], vec![
+///     element("li", vec![attr("key", "2")], vec![leaf("two")]),
+/// ]);
+///
+/// // the item 2 is dragged from the todo list into the done list
+/// let mut todo_tree = todo.clone();
+/// let options = DiffOptions {
+///     adoptable_trees: &[(TreeId(1), &todo)],
+///     ..Default::default()
+/// };
+/// let patches = diff_with_options(&done, &new_done, &"key", &options);
+/// assert_eq!(
+///     patches,
+///     vec![Patch::adopt_node(Some(&"ul"), TreePath::root(), TreeId(1), TreePath::new([1]), Placement::Append)]
+/// );
+/// let mut done_tree = done.clone();
+/// apply_patches_with_trees(&mut done_tree, &patches, &mut [(TreeId(1), &mut todo_tree)]);
+/// assert_eq!(done_tree, new_done);
+/// assert_eq!(todo_tree, element("ul", vec![], vec![
+///     element("li", vec![attr("key", "1")], vec![leaf("one")]),
+/// ]));
+/// ```
+///
+/// # Panics
+/// Panics if the path of a patch can not be resolved in the `root` node,
+/// if the target of a patch is removed by a previous patch,
+/// or if the adopted node of a patch can not be resolved in the `trees`
+pub fn apply_patches_with_trees<Ns, Tag, Leaf, Att, Val>(
+    root: &mut Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+    trees: &mut [(TreeId, &mut Node<Ns, Tag, Leaf, Att, Val>)],
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if let Err(e) = apply_in_arena(root, patches, trees) {
+        panic!("{}", e);
+    }
+}
+
+/// apply the patches into the `root` node, which is only modified
+/// when all of the patches are applied
+fn apply_in_arena<Ns, Tag, Leaf, Att, Val>(
+    root: &mut Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+    trees: &mut [(TreeId, &mut Node<Ns, Tag, Leaf, Att, Val>)],
+) -> Result<(), InvalidPatch>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut arena = Arena::from_node(root);
+    let mut targets: Vec<(usize, Vec<usize>)> = vec![];
+    let mut adopted: Vec<(TreeId, &TreePath)> = vec![];
+    for patch in patches {
+        let target = arena.resolve(patch.path());
+        let mut nodes: Vec<usize> = patch
+            .node_paths()
+            .iter()
+            .map(|path| arena.resolve(path))
+            .collect();
+        if let PatchType::AdoptNode {
+            from_tree,
+            from_path,
+            ..
+        } = &patch.patch_type
+        {
+            let node = trees
+                .iter()
+                .find(|(tree, _)| tree == from_tree)
+                .and_then(|(_, tree)| from_path.find_node_by_path(tree))
+                .unwrap_or_else(|| {
+                    panic!(
+                        "the adopted node at {:?} of the tree {:?} must resolve to a node",
+                        from_path, from_tree
+                    )
+                });
+            nodes.push(arena.insert(node, None));
+            adopted.push((*from_tree, from_path));
+        }
+        targets.push((target, nodes));
+    }
+
+    for (patch_index, (patch, (target, nodes))) in
+        patches.iter().zip(targets).enumerate()
+    {
+        trace!("applying patch at {}", patch.path().display());
+        arena
+            .apply(&patch.patch_type, target, &nodes)
+            .map_err(|e| e.into_invalid_patch(patch_index, patch.path()))?;
+    }
+    mem::replace(root, arena.into_node()).dismantle();
+
+    // the adopted nodes are removed from their trees after all of them are resolved
+    for (id, tree) in trees.iter_mut() {
+        let paths: Vec<&TreePath> = adopted
+            .iter()
+            .filter(|(from_tree, _)| from_tree == id)
+            .map(|(_, from_path)| *from_path)
+            .collect();
+        if !paths.is_empty() {
+            let mut arena = Arena::from_node(tree);
+            let ids: Vec<usize> =
+                paths.iter().map(|path| arena.resolve(path)).collect();
+            for id in ids {
+                arena.detach(id).expect(
+                    "the adopted node must not be the root of its tree",
+                );
+            }
+            mem::replace(*tree, arena.into_node()).dismantle();
+        }
+    }
+    Ok(())
+}
+
+/// Apply the patches into the `root` node the same as `apply_patches`, but the patches
+/// are checked with `validate` first, so the malformed patches, such as the ones from an
+/// external patch source, are rejected without modifying the `root` instead of panicking.
+///
+/// The patches which pass `validate` can still fail to apply, such as a patch whose target
+/// is removed by a previous patch, which is rejected with `InvalidPatch::DetachedTarget`.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{apply_patches::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![], vec![leaf("hello")]);
+/// let mut root = old.clone();
+/// let patches = vec![Patch::remove_node(None, TreePath::new([0, 3]))];
+/// assert_eq!(
+///     try_apply_patches(&mut root, &patches),
+///     Err(InvalidPatch::UnresolvedPath {
+///         patch_index: 0,
+///         path: TreePath::new([0, 3])
+///     })
+/// );
+/// assert_eq!(root, old);
+/// ```
+pub fn try_apply_patches<Ns, Tag, Leaf, Att, Val>(
+    root: &mut Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+) -> Result<(), InvalidPatch>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    validate(root, patches)?;
+    // there are no auxiliary trees to adopt the nodes from
+    let adoption = patches.iter().enumerate().find_map(
+        |(patch_index, patch)| match &patch.patch_type {
+            PatchType::AdoptNode {
+                from_tree,
+                from_path,
+                ..
+            } => Some(InvalidPatch::UnresolvedAdoption {
+                patch_index,
+                from_tree: *from_tree,
+                from_path: from_path.clone(),
+            }),
+            _ => None,
+        },
+    );
+    if let Some(e) = adoption {
+        return Err(e);
+    }
+    apply_in_arena(root, patches, &mut [])
+}
+
+/// Rewrite the paths of the patches, so each patch points to its nodes in the tree
+/// as it is after all of the previous patches are applied, instead of in the `root` node.
+///
+/// The patches of the differ all point to the nodes of the old tree, which needs
+/// the appliers to resolve every target before applying any of the patches.
+/// The remapped patches can instead be applied one by one against a live tree,
+/// such as a backend which can only look up the nodes by their current path.
+///
+/// The `from_path` of the `AdoptNode` patches points into the auxiliary trees,
+/// so it is kept as is.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("ul", vec![], vec![leaf("a"), leaf("b"), leaf("c")]);
+/// let new: MyNode = element("ul", vec![], vec![leaf("c")]);
+///
+/// let patches = vec![
+///     Patch::remove_node(None, TreePath::new([0])),
+///     Patch::remove_node(None, TreePath::new([1])),
+/// ];
+/// let remapped = remap_paths(&old, &patches);
+/// // `b` is the first child once `a` is removed
+/// assert_eq!(remapped[1].path(), &TreePath::new([0]));
+///
+/// let mut root = old.clone();
+/// for patch in remapped.iter() {
+///     apply_patches(&mut root, core::slice::from_ref(patch));
+/// }
+/// assert_eq!(root, new);
+/// ```
+///
+/// The patches whose target is replaced or unwrapped by a previous patch are remapped
+/// to the nodes which took its place, the same as in `apply_patches`.
+///
+/// # Panics
+/// Panics if the path of a patch can not be resolved in the `root` node,
+/// or if the target of a patch is removed by a previous patch
+pub fn remap_paths<'a, Ns, Tag, Leaf, Att, Val>(
+    root: &Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[Patch<'a, Ns, Tag, Leaf, Att, Val>],
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut arena = Arena::from_node(root);
+    let targets: Vec<(usize, Vec<usize>)> = patches
+        .iter()
+        .map(|patch| {
+            let target = arena.resolve(patch.path());
+            let nodes = patch
+                .node_paths()
+                .iter()
+                .map(|path| arena.resolve(path))
+                .collect();
+            (target, nodes)
+        })
+        .collect();
+
+    let mut remapped = Vec::with_capacity(patches.len());
+    for (patch_index, (patch, (target, mut nodes))) in
+        patches.iter().zip(targets).enumerate()
+    {
+        let invalid = |e: ArenaError| -> InvalidPatch {
+            e.into_invalid_patch(patch_index, patch.path())
+        };
+        let remap = |arena: &Arena<Ns, Tag, Leaf, Att, Val>| {
+            let mut patch_type = patch.patch_type.clone();
+            if let PatchType::MoveBeforeNode { nodes_path }
+            | PatchType::MoveAfterNode { nodes_path }
+            | PatchType::MoveIntoNode { nodes_path } = &mut patch_type
+            {
+                *nodes_path = nodes
+                    .iter()
+                    .map(|id| arena.path_of(arena.forward(*id, false)?))
+                    .collect::<Result<_, _>>()?;
+            }
+            let target = arena.forward_target(&patch.patch_type, target)?;
+            Ok(Patch {
+                tag: patch.tag,
+                patch_path: arena.path_of(target)?,
+                patch_type,
+            })
+        };
+        match remap(&arena) {
+            Ok(patch) => remapped.push(patch),
+            Err(e) => panic!("{}", invalid(e)),
+        }
+        // only the shape of the adopted node matters for the paths
+        if let PatchType::AdoptNode { .. } = patch.patch_type {
+            nodes.push(arena.insert_shallow(&Node::Fragment(vec![]), None));
+        }
+        if let Err(e) = arena.apply(&patch.patch_type, target, &nodes) {
+            panic!("{}", invalid(e));
+        }
+    }
+    remapped
+}
+
+/// Compile the patches into a `CompiledPatch`, which can be applied to many identical trees
+/// such as the cached trees of a multi-tenant server side renderer.
+///
+/// The paths of the patches are deduplicated and sorted ahead of time, so each application
+/// resolves all of the targets in a single walk which reuses the common prefix of the
+/// consecutive paths, instead of walking from the root for each path of each patch.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![], vec![leaf("hello")]);
+/// let new: MyNode = element("main", vec![attr("class", "container")], vec![leaf("world")]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// let compiled = compile(&patches);
+/// for _tenant in 0..3 {
+///     let mut root = old.clone();
+///     compiled.apply(&mut root);
+///     assert_eq!(root, new);
+/// }
+/// ```
+pub fn compile<'p, 'a, Ns, Tag, Leaf, Att, Val>(
+    patches: &'p [Patch<'a, Ns, Tag, Leaf, Att, Val>],
+) -> CompiledPatch<'p, 'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut paths: Vec<&TreePath> = patches
+        .iter()
+        .flat_map(|patch| {
+            core::iter::once(patch.path()).chain(patch.node_paths())
+        })
+        .collect();
+    paths.sort();
+    paths.dedup();
+    let position = |path: &TreePath| {
+        paths
+            .binary_search(&path)
+            .expect("path must be in the sorted paths")
+    };
+    let targets = patches
+        .iter()
+        .map(|patch| {
+            let nodes = patch.node_paths().iter().map(&position).collect();
+            (position(patch.path()), nodes)
+        })
+        .collect();
+    let paths = paths.into_iter().cloned().collect();
+    CompiledPatch {
+        patches,
+        paths,
+        targets,
+    }
+}
+
+/// The patches together with their pre-resolved traversal plan, created with `compile`
+#[derive(Debug)]
+pub struct CompiledPatch<'p, 'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    patches: &'p [Patch<'a, Ns, Tag, Leaf, Att, Val>],
+    /// the distinct paths used by the patches, sorted
+    paths: Vec<TreePath>,
+    /// the index into `paths` of the target and the node paths of each patch
+    targets: Vec<(usize, Vec<usize>)>,
+}
+
+impl<'p, 'a, Ns, Tag, Leaf, Att, Val>
+    CompiledPatch<'p, 'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// Apply the compiled patches into the `root` node, the same as `apply_patches`.
+    ///
+    /// # Panics
+    /// Panics if the path of a patch can not be resolved in the `root` node,
+    /// if the target of a patch is removed by a previous patch,
+    /// or if there is an `AdoptNode` patch, which needs `apply_patches_with_trees`
+    pub fn apply(&self, root: &mut Node<Ns, Tag, Leaf, Att, Val>) {
+        let mut arena = Arena::from_node(root);
+        let resolved = arena.resolve_sorted(&self.paths);
+        for (patch_index, (patch, (target, nodes))) in
+            self.patches.iter().zip(&self.targets).enumerate()
+        {
+            let nodes: Vec<usize> =
+                nodes.iter().map(|node| resolved[*node]).collect();
+            if let Err(e) =
+                arena.apply(&patch.patch_type, resolved[*target], &nodes)
+            {
+                panic!("{}", e.into_invalid_patch(patch_index, patch.path()));
+            }
+        }
+        mem::replace(root, arena.into_node()).dismantle();
+    }
+
+    /// the patches which are compiled
+    pub fn patches(&self) -> &'p [Patch<'a, Ns, Tag, Leaf, Att, Val>] {
+        self.patches
+    }
+}
+
+/// A node in the arena, the children are stored in the arena and
+/// referred here by their index in the arena
+struct Slot<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the node without its children
+    node: Node<Ns, Tag, Leaf, Att, Val>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// the nodes which took the place of this node when it is replaced or unwrapped,
+    /// so the later patches which are placed relative to this node use them instead
+    replacement: Vec<usize>,
+}
+
+/// The reason why a patch can not be applied to the arena
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArenaError {
+    /// the target is removed from the tree by a previous patch,
+    /// without any node which took its place
+    Detached,
+    /// the run of siblings starting at the target goes past the end of the children
+    OutOfRange(usize),
+    /// the target of the patch must be an element
+    NotAnElement,
+}
+
+impl ArenaError {
+    /// the `InvalidPatch` of the patch at `patch_index`
+    fn into_invalid_patch(
+        self,
+        patch_index: usize,
+        path: &TreePath,
+    ) -> InvalidPatch {
+        let path = path.clone();
+        match self {
+            Self::Detached => {
+                InvalidPatch::DetachedTarget { patch_index, path }
+            }
+            Self::OutOfRange(count) => InvalidPatch::OutOfRange {
+                patch_index,
+                path,
+                count,
+            },
+            Self::NotAnElement => {
+                InvalidPatch::NotAnElement { patch_index, path }
+            }
+        }
+    }
+}
+
+/// The nodes are flattened into an arena, so the target nodes can be
+/// resolved before the tree is modified by the patches
+struct Arena<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    slots: Vec<Slot<Ns, Tag, Leaf, Att, Val>>,
+    root: usize,
+}
+
+impl<Ns, Tag, Leaf, Att, Val> Arena<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn from_node(node: &Node<Ns, Tag, Leaf, Att, Val>) -> Self {
+        let mut arena = Arena {
+            slots: vec![],
+            root: 0,
+        };
+        arena.root = arena.insert(node, None);
+        arena
+    }
+
+    /// insert the node and all of its descendants into the arena,
+    /// with an explicit stack so the deep trees don't overflow the stack
+    fn insert(
+        &mut self,
+        node: &Node<Ns, Tag, Leaf, Att, Val>,
+        parent: Option<usize>,
+    ) -> usize {
+        let id = self.insert_shallow(node, parent);
+        let mut pending = vec![(node, id)];
+        while let Some((node, id)) = pending.pop() {
+            let children = node
+                .child_nodes()
+                .iter()
+                .map(|child| {
+                    let child_id = self.insert_shallow(child, Some(id));
+                    pending.push((child, child_id));
+                    child_id
+                })
+                .collect();
+            self.slots[id].children = children;
+        }
+        id
+    }
+
+    /// return the index of the node pointed by `path`
+    fn resolve(&self, path: &TreePath) -> usize {
+        path.indices().fold(self.root, |id, idx| {
+            *self.slots[id].children.get(idx).unwrap_or_else(|| {
+                panic!("path {:?} must resolve to a node", path)
+            })
+        })
+    }
+
+    /// return the indexes of the nodes pointed by the sorted `paths`,
+    /// the ancestors resolved for the previous path are reused for the next path
+    fn resolve_sorted(&self, paths: &[TreePath]) -> Vec<usize> {
+        // the resolved ancestors of the previous path, starting at the root
+        let mut ancestors: Vec<usize> = vec![self.root];
+        let mut previous: Option<&TreePath> = None;
+        paths
+            .iter()
+            .map(|path| {
+                let common = previous.map_or(0, |previous| {
+                    previous
+                        .indices()
+                        .zip(path.indices())
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                });
+                ancestors.truncate(common + 1);
+                for idx in path.indices().skip(common) {
+                    let id = *ancestors.last().expect("must have the root");
+                    let child =
+                        *self.slots[id].children.get(idx).unwrap_or_else(
+                            || panic!("path {:?} must resolve to a node", path),
+                        );
+                    ancestors.push(child);
+                }
+                previous = Some(path);
+                *ancestors.last().expect("must have the root")
+            })
+            .collect()
+    }
+
+    /// return the current path of this node, walking up to the root
+    fn path_of(&self, id: usize) -> Result<TreePath, ArenaError> {
+        let mut path = TreePath::root();
+        let mut id = id;
+        while id != self.root {
+            let (parent, position) = self.position(id)?;
+            path.push(position);
+            id = parent;
+        }
+        path.path.reverse();
+        Ok(path)
+    }
+
+    /// the parent and the position of this node in the parent
+    fn position(&self, id: usize) -> Result<(usize, usize), ArenaError> {
+        let parent = self.slots[id].parent.ok_or(ArenaError::Detached)?;
+        let position = self.slots[parent]
+            .children
+            .iter()
+            .position(|child| *child == id)
+            .expect("must be a child of its parent");
+        Ok((parent, position))
+    }
+
+    /// the node which is in place of this node, following the nodes which are replaced
+    /// or unwrapped by the previous patches to the nodes which took their place,
+    /// the `last` of them for the patches which are placed after the node
+    fn forward(&self, mut id: usize, last: bool) -> Result<usize, ArenaError> {
+        while id != self.root && self.slots[id].parent.is_none() {
+            let replacement = &self.slots[id].replacement;
+            let next = if last {
+                replacement.last()
+            } else {
+                replacement.first()
+            };
+            id = *next.ok_or(ArenaError::Detached)?;
+        }
+        Ok(id)
+    }
+
+    /// the node which the patch is applied to, in place of the `target` node
+    /// of the old tree when it is replaced or unwrapped by a previous patch
+    fn forward_target(
+        &self,
+        patch_type: &PatchType<Ns, Tag, Leaf, Att, Val>,
+        target: usize,
+    ) -> Result<usize, ArenaError> {
+        let last = matches!(
+            patch_type,
+            PatchType::InsertAfterNode { .. }
+                | PatchType::MoveAfterNode { .. }
+                | PatchType::AdoptNode {
+                    placement: Placement::After,
+                    ..
+                }
+        );
+        self.forward(target, last)
+    }
+
+    /// remove this node from its parent
+    fn detach(&mut self, id: usize) -> Result<(), ArenaError> {
+        let (parent, position) = self.position(id)?;
+        self.slots[parent].children.remove(position);
+        self.slots[id].parent = None;
+        Ok(())
+    }
+
+    /// put the nodes into the children of the parent at this position
+    fn attach(&mut self, parent: usize, position: usize, ids: &[usize]) {
+        for id in ids {
+            self.slots[*id].parent = Some(parent);
+        }
+        self.slots[parent]
+            .children
+            .splice(position..position, ids.iter().copied());
+    }
+
+    /// insert the node without its children
+    fn insert_shallow(
+        &mut self,
+        node: &Node<Ns, Tag, Leaf, Att, Val>,
+        parent: Option<usize>,
+    ) -> usize {
+        let id = self.slots.len();
+        let shallow = match node {
+            // the children are not cloned, since they are inserted separately
+            Node::Element(element) => Node::Element(Element {
+                namespace: element.namespace.clone(),
+                tag: element.tag.clone(),
+                attrs: element.attrs.clone(),
+                children: vec![],
+                self_closing: element.self_closing,
+                slots: element.slots.clone(),
+                memo: element.memo,
+            }),
+            Node::Fragment(_) => Node::Fragment(vec![]),
+            Node::NodeList(_) => Node::NodeList(vec![]),
+            Node::Leaf(_) => node.clone(),
+        };
+        self.slots.push(Slot {
+            node: shallow,
+            parent,
+            children: vec![],
+            replacement: vec![],
+        });
+        id
+    }
+
+    fn insert_all(
+        &mut self,
+        nodes: &[&Node<Ns, Tag, Leaf, Att, Val>],
+    ) -> Vec<usize> {
+        nodes.iter().map(|node| self.insert(node, None)).collect()
+    }
+
+    fn element_mut(
+        &mut self,
+        id: usize,
+    ) -> Result<&mut crate::Element<Ns, Tag, Leaf, Att, Val>, ArenaError> {
+        self.slots[id]
+            .node
+            .element_mut()
+            .ok_or(ArenaError::NotAnElement)
+    }
+
+    /// the run of `count` siblings starting at this node
+    fn siblings(
+        &self,
+        id: usize,
+        count: usize,
+    ) -> Result<(usize, usize, Vec<usize>), ArenaError> {
+        let (parent, position) = self.position(id)?;
+        let run = self.slots[parent]
+            .children
+            .get(position..position + count)
+            .ok_or(ArenaError::OutOfRange(count))?;
+        Ok((parent, position, run.to_vec()))
+    }
+
+    /// apply the patch to the `target` node, where the `nodes` are the nodes
+    /// of the node paths of the patch
+    fn apply(
+        &mut self,
+        patch_type: &PatchType<Ns, Tag, Leaf, Att, Val>,
+        target: usize,
+        nodes: &[usize],
+    ) -> Result<(), ArenaError> {
+        let target = self.forward_target(patch_type, target)?;
+        if let PatchType::MoveBeforeNode { .. }
+        | PatchType::MoveAfterNode { .. }
+        | PatchType::MoveIntoNode { .. } = patch_type
+        {
+            let nodes = nodes
+                .iter()
+                .map(|id| self.forward(*id, false))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.apply_to(patch_type, target, &nodes)
+        } else {
+            self.apply_to(patch_type, target, nodes)
+        }
+    }
+
+    fn apply_to(
+        &mut self,
+        patch_type: &PatchType<Ns, Tag, Leaf, Att, Val>,
+        target: usize,
+        nodes: &[usize],
+    ) -> Result<(), ArenaError> {
+        match patch_type {
+            PatchType::InsertBeforeNode { nodes: new_nodes } => {
+                let (parent, position) = self.position(target)?;
+                let ids = self.insert_all(new_nodes);
+                self.attach(parent, position, &ids);
+            }
+            PatchType::InsertAfterNode { nodes: new_nodes } => {
+                let (parent, position) = self.position(target)?;
+                let ids = self.insert_all(new_nodes);
+                self.attach(parent, position + 1, &ids);
+            }
+            PatchType::AppendChildren { children } => {
+                let ids = self.insert_all(children);
+                let position = self.slots[target].children.len();
+                self.attach(target, position, &ids);
+            }
+            PatchType::RemoveNode => {
+                self.detach(target)?;
+            }
+            PatchType::MoveBeforeNode { .. } => {
+                self.position(target)?;
+                for id in nodes {
+                    self.detach(*id)?;
+                }
+                let (parent, position) = self.position(target)?;
+                self.attach(parent, position, nodes);
+            }
+            PatchType::MoveAfterNode { .. } => {
+                self.position(target)?;
+                for id in nodes {
+                    self.detach(*id)?;
+                }
+                let (parent, position) = self.position(target)?;
+                self.attach(parent, position + 1, nodes);
+            }
+            PatchType::MoveIntoNode { .. } => {
+                for id in nodes {
+                    self.detach(*id)?;
+                }
+                let position = self.slots[target].children.len();
+                self.attach(target, position, nodes);
+            }
+            PatchType::ReplaceNode { replacement } => {
+                let position = if target == self.root {
+                    None
+                } else {
+                    Some(self.position(target)?)
+                };
+                let ids = self.insert_all(replacement);
+                match position {
+                    None => self.replace_root(&ids),
+                    Some((parent, position)) => {
+                        self.detach(target)?;
+                        self.attach(parent, position, &ids);
+                    }
+                }
+                self.slots[target].replacement = ids;
+            }
+            PatchType::WrapNode { wrapper } if target == self.root => {
+                let wrapper_id = self.insert_shallow(wrapper, None);
+                self.attach(wrapper_id, 0, &[target]);
+                self.root = wrapper_id;
+            }
+            PatchType::WrapNode { wrapper } => {
+                let (parent, position, wrapped) =
+                    self.siblings(target, wrapper.children().len())?;
+                for id in wrapped.iter() {
+                    self.detach(*id)?;
+                }
+                let wrapper_id = self.insert_shallow(wrapper, None);
+                self.attach(wrapper_id, 0, &wrapped);
+                self.attach(parent, position, &[wrapper_id]);
+            }
+            PatchType::UnwrapNode if target == self.root => {
+                let children = mem::take(&mut self.slots[target].children);
+                self.replace_root(&children);
+                self.slots[target].replacement = children;
+            }
+            PatchType::UnwrapNode => {
+                let (parent, position) = self.position(target)?;
+                let children = mem::take(&mut self.slots[target].children);
+                self.detach(target)?;
+                self.attach(parent, position, &children);
+                self.slots[target].replacement = children;
+            }
+            PatchType::ChangeLeaf { new, .. }
+            | PatchType::SpliceLeaf { new, .. } => {
+                self.slots[target].node = Node::Leaf((*new).clone());
+            }
+            PatchType::SyncIsland => (),
+            PatchType::ForEachChild { patch } => {
+                for child in self.slots[target].children.clone() {
+                    self.apply_to(patch, child, &[])?;
+                }
+            }
+            PatchType::Broadcast { selector, patch } => {
+                let mut stack = vec![target];
+                let mut selected = vec![];
+                while let Some(id) = stack.pop() {
+                    if selector.matches(&self.slots[id].node) {
+                        selected.push(id);
+                    }
+                    stack.extend(self.slots[id].children.iter().rev());
+                }
+                for id in selected {
+                    self.apply_to(patch, id, &[])?;
+                }
+            }
+            PatchType::ChangeTag { new, .. } => {
+                self.element_mut(target)?.tag = (*new).clone();
+            }
+            PatchType::ChangeLeafRun { count, leaves } => {
+                let (parent, position, run) = self.siblings(target, *count)?;
+                for id in run {
+                    self.detach(id)?;
+                }
+                let ids: Vec<usize> = leaves
+                    .iter()
+                    .map(|leaf| self.insert(&Node::Leaf((*leaf).clone()), None))
+                    .collect();
+                self.attach(parent, position, &ids);
+            }
+            PatchType::AddAttributes { attrs } => {
+                // the changed attributes are replaced in place, so the order of
+                // the attributes is kept, the new attributes are appended
+                let element = self.element_mut(target)?;
+                for (i, attr) in attrs.iter().enumerate() {
+                    if attrs[..i].iter().any(|prev| prev.name == attr.name) {
+                        continue;
+                    }
+                    let same_name = attrs
+                        .iter()
+                        .filter(|att| att.name == attr.name)
+                        .map(|att| (*att).clone());
+                    match element
+                        .attrs
+                        .iter()
+                        .position(|att| att.name == attr.name)
+                    {
+                        Some(position) => {
+                            element.remove_attribute(&attr.name);
+                            element.attrs.splice(position..position, same_name);
+                        }
+                        None => element.add_attributes(same_name),
+                    }
+                }
+            }
+            PatchType::RemoveAttributes { attrs } => {
+                let element = self.element_mut(target)?;
+                for attr in attrs.iter() {
+                    element.remove_attribute(&attr.name);
+                }
+            }
+            PatchType::UpdateAttributes { add, remove } => {
+                let remove = PatchType::RemoveAttributes {
+                    attrs: remove.clone(),
+                };
+                let add = PatchType::AddAttributes { attrs: add.clone() };
+                self.apply_to(&remove, target, nodes)?;
+                self.apply_to(&add, target, nodes)?;
+            }
+            PatchType::UpdateAttributeParts { attr, .. } => {
+                let add = PatchType::AddAttributes { attrs: vec![*attr] };
+                self.apply_to(&add, target, nodes)?;
+            }
+            PatchType::SetSlot { slot, node } => {
+                self.clear_slot(target, slot)?;
+                let id = self.insert(node, None);
+                self.element_mut(target)?
+                    .slots
+                    .get_or_insert_with(Vec::new)
+                    .push((*slot).clone());
+                let position = self.slots[target].children.len();
+                self.attach(target, position, &[id]);
+            }
+            PatchType::ClearSlot { slot } => {
+                self.clear_slot(target, slot)?;
+            }
+            PatchType::AdoptNode { placement, .. } => {
+                let adopted = *nodes.first().expect(
+                    "the adopted node must be resolved with apply_patches_with_trees",
+                );
+                match placement {
+                    Placement::Before => {
+                        let (parent, position) = self.position(target)?;
+                        self.attach(parent, position, &[adopted]);
+                    }
+                    Placement::After => {
+                        let (parent, position) = self.position(target)?;
+                        self.attach(parent, position + 1, &[adopted]);
+                    }
+                    Placement::Append => {
+                        let position = self.slots[target].children.len();
+                        self.attach(target, position, &[adopted]);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// remove the child in the slot of the target element, if there is any
+    fn clear_slot(
+        &mut self,
+        target: usize,
+        slot: &Att,
+    ) -> Result<(), ArenaError> {
+        let element = self.element_mut(target)?;
+        if let Some(position) = element.slot_position(slot) {
+            if let Some(slots) = element.slots.as_mut() {
+                slots.remove(position);
+            }
+            let child = self.slots[target].children[position];
+            self.detach(child)?;
+        }
+        Ok(())
+    }
+
+    /// the root is replaced by the node, or by a fragment when there are
+    /// zero or more than 1 nodes
+    fn replace_root(&mut self, ids: &[usize]) {
+        if let [id] = ids {
+            self.slots[*id].parent = None;
+            self.root = *id;
+        } else {
+            let fragment = self.insert_shallow(&Node::Fragment(vec![]), None);
+            self.attach(fragment, 0, ids);
+            self.root = fragment;
+        }
+    }
+
+    /// rebuild the node tree from the arena
+    fn into_node(mut self) -> Node<Ns, Tag, Leaf, Att, Val> {
+        let root = self.root;
+        self.take_node(root)
+    }
+
+    /// take the node and its descendants out of the arena, with an explicit stack of
+    /// the nodes with their children which are not taken yet and the taken children
+    fn take_node(&mut self, id: usize) -> Node<Ns, Tag, Leaf, Att, Val> {
+        let children = mem::take(&mut self.slots[id].children);
+        let mut pending = vec![(id, children.into_iter(), vec![])];
+        loop {
+            let (_, rest, _) = pending.last_mut().expect("must have the node");
+            if let Some(child) = rest.next() {
+                let children = mem::take(&mut self.slots[child].children);
+                let taken = Vec::with_capacity(children.len());
+                pending.push((child, children.into_iter(), taken));
+                continue;
+            }
+            let (id, _, children) = pending.pop().expect("must have the node");
+            let mut node =
+                mem::replace(&mut self.slots[id].node, Node::NodeList(vec![]));
+            match &mut node {
+                Node::Element(element) => element.children = children,
+                Node::Fragment(nodes) | Node::NodeList(nodes) => {
+                    *nodes = children
+                }
+                Node::Leaf(_) => (),
+            }
+            match pending.last_mut() {
+                Some((_, _, taken)) => taken.push(node),
+                None => return node,
+            }
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! the index of the keyed nodes of a tree, which is updated by `apply_patches_with_index`
+use super::apply_patches;
+use crate::patch::Placement;
+use crate::{Node, Patch, PatchType, TreePath};
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use std::collections::HashMap;
+
+/// The paths of the keyed nodes of a tree by their key, for the frameworks which look up
+/// the nodes by their key between the frames.
+///
+/// The index is updated by `apply_patches_with_index` together with the tree, where only
+/// the siblings which are inserted, removed or moved around and the nodes whose
+/// attributes are changed are indexed again, instead of the whole tree.
+///
+/// The keys are the values of the `key` attribute of the elements. When the same key is
+/// used by several nodes of the tree, such as in different lists, `get` returns the first
+/// of them in pre-order and `get_all` returns all of them.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{apply_patches::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("ul", vec![], vec![
+///     element("li", vec![attr("key", "1")], vec![]),
+///     element("li", vec![attr("key", "2")], vec![]),
+/// ]);
+/// let new: MyNode = element("ul", vec![], vec![
+///     element("li", vec![attr("key", "2")], vec![]),
+/// ]);
+///
+/// let mut index = KeyIndex::new(&old, "key");
+/// assert_eq!(index.get(&["2"]), Some(&TreePath::new([1])));
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// let mut root = old.clone();
+/// apply_patches_with_index(&mut root, &patches, &mut index);
+/// assert_eq!(root, new);
+/// assert_eq!(index.get(&["1"]), None);
+/// assert_eq!(index.get(&["2"]), Some(&TreePath::new([0])));
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyIndex<Att, Val> {
+    /// the attribute which is used as the key of the nodes
+    key: Att,
+    /// the key of each keyed node, sorted by their paths in pre-order
+    keys: BTreeMap<TreePath, Vec<Val>>,
+    /// the paths of the nodes of each key, sorted in pre-order
+    paths: HashMap<Vec<Val>, Vec<TreePath>>,
+}
+
+impl<Att, Val> PartialEq for KeyIndex<Att, Val>
+where
+    Att: PartialEq,
+    Val: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        // the paths are derived from the keys
+        self.key == other.key && self.keys == other.keys
+    }
+}
+
+impl<Att, Val> KeyIndex<Att, Val>
+where
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Eq + Hash + Clone + Debug,
+{
+    /// index the keyed nodes of the tree of `root`, which are keyed by the `key` attribute
+    pub fn new<Ns, Tag, Leaf>(
+        root: &Node<Ns, Tag, Leaf, Att, Val>,
+        key: Att,
+    ) -> Self
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+    {
+        let mut index = KeyIndex {
+            key,
+            keys: BTreeMap::new(),
+            paths: HashMap::new(),
+        };
+        index.index_subtree(root, TreePath::root());
+        index
+    }
+
+    /// the attribute which is used as the key of the nodes
+    pub fn key(&self) -> &Att {
+        &self.key
+    }
+
+    /// the path of the first node in pre-order which has this key
+    pub fn get(&self, key: &[Val]) -> Option<&TreePath> {
+        self.get_all(key).first()
+    }
+
+    /// the paths of all of the nodes which have this key, in pre-order
+    pub fn get_all(&self, key: &[Val]) -> &[TreePath] {
+        self.paths.get(key).map_or(&[], |paths| paths)
+    }
+
+    /// the key of the node at `path`, if it is keyed
+    pub fn key_at(&self, path: &TreePath) -> Option<&[Val]> {
+        self.keys.get(path).map(|key| key.as_slice())
+    }
+
+    /// the number of the keyed nodes
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// whether there are no keyed nodes
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// index the node at `path` and all of its descendants
+    fn index_subtree<Ns, Tag, Leaf>(
+        &mut self,
+        node: &Node<Ns, Tag, Leaf, Att, Val>,
+        path: TreePath,
+    ) where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+    {
+        let mut pending = vec![(node, path)];
+        while let Some((node, path)) = pending.pop() {
+            for (index, child) in node.child_nodes().iter().enumerate().rev() {
+                pending.push((child, path.traverse(index)));
+            }
+            self.index_node(node, path);
+        }
+    }
+
+    /// index the node at `path` without its descendants
+    fn index_node<Ns, Tag, Leaf>(
+        &mut self,
+        node: &Node<Ns, Tag, Leaf, Att, Val>,
+        path: TreePath,
+    ) where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+    {
+        let Some(key) = node.attribute_value(&self.key) else {
+            return;
+        };
+        let key: Vec<Val> = key.into_iter().cloned().collect();
+        let paths = self.paths.entry(key.clone()).or_default();
+        if let Err(position) = paths.binary_search(&path) {
+            paths.insert(position, path.clone());
+        }
+        self.keys.insert(path, key);
+    }
+
+    /// remove the node at `path` from the index, its descendants are kept
+    fn remove(&mut self, path: &TreePath) {
+        let Some(key) = self.keys.remove(path) else {
+            return;
+        };
+        if let Some(paths) = self.paths.get_mut(&key) {
+            paths.retain(|other| other != path);
+            if paths.is_empty() {
+                self.paths.remove(&key);
+            }
+        }
+    }
+
+    /// remove the children from `start` onwards of the node at `parent`,
+    /// with all of their descendants
+    fn remove_children_from(&mut self, parent: &TreePath, start: usize) {
+        // the descendants of the children from `start` onwards are contiguous in pre-order
+        let removed: Vec<TreePath> = self
+            .keys
+            .range(parent.traverse(start)..)
+            .map(|(path, _)| path)
+            .take_while(|path| path.path.starts_with(&parent.path))
+            .cloned()
+            .collect();
+        for path in removed.iter() {
+            self.remove(path);
+        }
+    }
+}
+
+/// Apply the patches into the `root` node the same as `apply_patches`, and update the `index`
+/// of the keyed nodes of `root` to the patched tree.
+///
+/// Only the children of the parents whose children are changed by the patches,
+/// starting at the first changed child, and the nodes whose attributes are changed are
+/// indexed again, so the index is updated without walking the whole tree.
+/// The index is rebuilt when the root node itself is replaced, wrapped or unwrapped.
+///
+/// # Panics
+/// Panics if the path of a patch can not be resolved in the `root` node,
+/// or if there is an `AdoptNode` patch, see `apply_patches_with_trees`
+pub fn apply_patches_with_index<Ns, Tag, Leaf, Att, Val>(
+    root: &mut Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+    index: &mut KeyIndex<Att, Val>,
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Eq + Hash + Clone + Debug,
+{
+    let changes = Changes::of(root, patches);
+    apply_patches(root, patches);
+
+    if changes.is_root_changed {
+        *index = KeyIndex::new(root, index.key.clone());
+        return;
+    }
+    // the changed regions inside of the other changed regions are indexed with them
+    let parents: Vec<(&TreePath, usize)> = changes
+        .parents
+        .iter()
+        .map(|(parent, start)| (parent, *start))
+        .filter(|(parent, _)| !changes.is_in_changed_children(parent))
+        .collect();
+    let nodes: Vec<&TreePath> = changes
+        .nodes
+        .iter()
+        .filter(|path| !changes.is_in_changed_children(path))
+        .collect();
+
+    // the paths of the changed regions are the same in the old and the patched tree,
+    // since the siblings before them and their ancestors are not changed
+    for (parent, start) in parents.iter() {
+        index.remove_children_from(parent, *start);
+    }
+    for path in nodes.iter() {
+        index.remove(path);
+    }
+    for (parent, start) in parents {
+        let node = parent
+            .find_node_by_path(root)
+            .expect("the changed parent must be in the patched tree");
+        for (i, child) in node.child_nodes().iter().enumerate().skip(start) {
+            index.index_subtree(child, parent.traverse(i));
+        }
+    }
+    for path in nodes {
+        let node = path
+            .find_node_by_path(root)
+            .expect("the changed node must be in the patched tree");
+        index.index_node(node, path.clone());
+    }
+}
+
+/// The parts of the old tree which are changed by the patches
+struct Changes {
+    /// the parents whose children are changed, with the position of the first changed child
+    parents: BTreeMap<TreePath, usize>,
+    /// the nodes whose attributes are changed
+    nodes: Vec<TreePath>,
+    /// the root node itself is replaced, wrapped or unwrapped
+    is_root_changed: bool,
+}
+
+impl Changes {
+    fn of<Ns, Tag, Leaf, Att, Val>(
+        root: &Node<Ns, Tag, Leaf, Att, Val>,
+        patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+    ) -> Self
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        let mut changes = Changes {
+            parents: BTreeMap::new(),
+            nodes: vec![],
+            is_root_changed: false,
+        };
+        // the old number of children, since the appended children are after them
+        let child_count = |path: &TreePath| {
+            path.find_node_by_path(root)
+                .map_or(0, |node| node.child_nodes().len())
+        };
+        for patch in patches {
+            let path = patch.path();
+            for moved in patch.node_paths() {
+                changes.change_sibling(moved, 0);
+            }
+            match &patch.patch_type {
+                PatchType::InsertBeforeNode { .. }
+                | PatchType::RemoveNode
+                | PatchType::MoveBeforeNode { .. }
+                | PatchType::ReplaceNode { .. }
+                | PatchType::WrapNode { .. }
+                | PatchType::UnwrapNode
+                | PatchType::ChangeLeafRun { .. } => {
+                    changes.change_sibling(path, 0)
+                }
+                PatchType::InsertAfterNode { .. }
+                | PatchType::MoveAfterNode { .. } => {
+                    changes.change_sibling(path, 1)
+                }
+                PatchType::AppendChildren { .. }
+                | PatchType::MoveIntoNode { .. } => {
+                    changes.change_children(path, child_count(path))
+                }
+                PatchType::AdoptNode { placement, .. } => match placement {
+                    Placement::Before => changes.change_sibling(path, 0),
+                    Placement::After => changes.change_sibling(path, 1),
+                    Placement::Append => {
+                        changes.change_children(path, child_count(path))
+                    }
+                },
+                PatchType::SetSlot { .. }
+                | PatchType::ClearSlot { .. }
+                | PatchType::ForEachChild { .. } => {
+                    changes.change_children(path, 0)
+                }
+                PatchType::Broadcast { .. } => {
+                    changes.change_children(path, 0);
+                    changes.nodes.push(path.clone());
+                }
+                PatchType::AddAttributes { .. }
+                | PatchType::RemoveAttributes { .. }
+                | PatchType::UpdateAttributes { .. }
+                | PatchType::UpdateAttributeParts { .. } => {
+                    changes.nodes.push(path.clone())
+                }
+                PatchType::ChangeLeaf { .. }
+                | PatchType::SpliceLeaf { .. }
+                | PatchType::ChangeTag { .. }
+                | PatchType::SyncIsland => (),
+            }
+        }
+        changes
+    }
+
+    /// the children of the parent of `path` are changed, starting at the node at `path`
+    /// plus `offset`. The root node has no parent, so the whole tree is changed.
+    fn change_sibling(&mut self, path: &TreePath, offset: usize) {
+        match path.indices().last() {
+            Some(last) => {
+                self.change_children(&path.backtrack(), last + offset)
+            }
+            None => self.is_root_changed = true,
+        }
+    }
+
+    /// the children of the node at `path` are changed, starting at `start`
+    fn change_children(&mut self, path: &TreePath, start: usize) {
+        let first = self.parents.entry(path.clone()).or_insert(start);
+        *first = (*first).min(start);
+    }
+
+    /// whether the node at `path` is a descendant of the changed children of a parent
+    fn is_in_changed_children(&self, path: &TreePath) -> bool {
+        self.parents.iter().any(|(parent, start)| {
+            path.path.starts_with(&parent.path)
+                && path
+                    .indices()
+                    .nth(parent.path.len())
+                    .is_some_and(|index| index >= *start)
+        })
+    }
+}
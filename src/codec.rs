@@ -0,0 +1,521 @@
+//! A compact binary encoding of the node trees
+//!
+//! The lengths are encoded as LEB128 varints, the numbers are encoded in little endian
+//! and the strings are encoded as their length followed by their UTF-8 bytes.
+//! The `Element::memo` of the memoized elements is not encoded, since it is not
+//! part of the content of the element.
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{codec::*, *};
+//!
+//! pub type MyNode = Node<String, String, String, String, String>;
+//!
+//! let node: MyNode = element(
+//!     "div".to_string(),
+//!     vec![attr("class".to_string(), "container".to_string())],
+//!     vec![leaf("hello".to_string())],
+//! );
+//! let bytes = encode_node(&node);
+//! assert_eq!(decode_node(&bytes), Ok(node));
+//! ```
+use crate::node::{Attribute, Element, Node};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// the marker of the node variants in the encoded form
+const ELEMENT: u8 = 0;
+const NODE_LIST: u8 = 1;
+const FRAGMENT: u8 = 2;
+const LEAF: u8 = 3;
+/// an element whose children are keyed by their slots, followed by the slot names
+const SLOTTED_ELEMENT: u8 = 4;
+
+/// The deepest nesting of the nodes which is decoded, the same limit the html
+/// parsers of the browsers put on the nesting of the elements.
+/// The nodes are decoded without recursion, but the deeper nodes are rejected as
+/// [`DecodeError::Invalid`], since dropping and cloning the node tree recurse into
+/// the children and a small malicious or corrupted input would overflow the stack.
+pub const MAX_DEPTH: usize = 512;
+
+/// The reason why the bytes can not be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// the bytes ended before the value is completely decoded
+    UnexpectedEnd,
+    /// the bytes has an invalid marker or value at this offset
+    /// from the end of the bytes
+    Invalid {
+        /// the number of the remaining bytes where the invalid value is found
+        remaining: usize,
+    },
+    /// there are remaining bytes after the value is decoded
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of the bytes"),
+            Self::Invalid { remaining } => {
+                write!(f, "invalid value with {} remaining bytes", remaining)
+            }
+            Self::TrailingBytes => {
+                write!(f, "there are remaining bytes after the value")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A value which can be written in the compact binary encoding
+pub trait Encode {
+    /// append the encoded bytes of this value into `buf`
+    fn encode(&self, buf: &mut Vec<u8>);
+}
+
+/// A value which can be read from the compact binary encoding
+pub trait Decode: Sized {
+    /// read the value from the start of `bytes`, advancing `bytes` past the decoded value
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError>;
+}
+
+/// append `value` as a LEB128 varint into `buf`
+pub fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// read a LEB128 varint from the start of `bytes`
+pub fn decode_varint(bytes: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = take_byte(bytes)?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid(bytes))
+}
+
+/// read a varint length, which can not be more than the remaining bytes,
+/// so corrupted bytes can not cause huge allocations
+pub(crate) fn decode_len(bytes: &mut &[u8]) -> Result<usize, DecodeError> {
+    let len = decode_varint(bytes)?;
+    usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= bytes.len())
+        .ok_or(DecodeError::UnexpectedEnd)
+}
+
+pub(crate) fn take_byte(bytes: &mut &[u8]) -> Result<u8, DecodeError> {
+    let (first, rest) =
+        bytes.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+    *bytes = rest;
+    Ok(*first)
+}
+
+pub(crate) fn take_bytes<'b>(
+    bytes: &mut &'b [u8],
+    len: usize,
+) -> Result<&'b [u8], DecodeError> {
+    if len > bytes.len() {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
+
+pub(crate) fn invalid(bytes: &[u8]) -> DecodeError {
+    DecodeError::Invalid {
+        remaining: bytes.len(),
+    }
+}
+
+/// encode the node tree into bytes
+pub fn encode_node<Ns, Tag, Leaf, Att, Val>(
+    node: &Node<Ns, Tag, Leaf, Att, Val>,
+) -> Vec<u8>
+where
+    Ns: PartialEq + Clone + Debug + Encode,
+    Tag: PartialEq + Debug + Encode,
+    Leaf: PartialEq + Clone + Debug + Encode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Encode,
+    Val: PartialEq + Clone + Debug + Encode,
+{
+    let mut buf = Vec::new();
+    node.encode(&mut buf);
+    buf
+}
+
+/// decode the node tree from all of the bytes
+pub fn decode_node<Ns, Tag, Leaf, Att, Val>(
+    mut bytes: &[u8],
+) -> Result<Node<Ns, Tag, Leaf, Att, Val>, DecodeError>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    let node = Node::decode(&mut bytes)?;
+    if bytes.is_empty() {
+        Ok(node)
+    } else {
+        Err(DecodeError::TrailingBytes)
+    }
+}
+
+impl<Ns, Tag, Leaf, Att, Val> Encode for Node<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Encode,
+    Tag: PartialEq + Debug + Encode,
+    Leaf: PartialEq + Clone + Debug + Encode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Encode,
+    Val: PartialEq + Clone + Debug + Encode,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Node::Element(element) => {
+                buf.push(if element.is_slotted() {
+                    SLOTTED_ELEMENT
+                } else {
+                    ELEMENT
+                });
+                element.namespace.encode(buf);
+                element.tag.encode(buf);
+                element.attrs.encode(buf);
+                element.self_closing.encode(buf);
+                element.children.encode(buf);
+                if let Some(slots) = &element.slots {
+                    slots.encode(buf);
+                }
+            }
+            Node::NodeList(nodes) => {
+                buf.push(NODE_LIST);
+                nodes.encode(buf);
+            }
+            Node::Fragment(nodes) => {
+                buf.push(FRAGMENT);
+                nodes.encode(buf);
+            }
+            Node::Leaf(leaf) => {
+                buf.push(LEAF);
+                leaf.encode(buf);
+            }
+        }
+    }
+}
+
+impl<Ns, Tag, Leaf, Att, Val> Decode for Node<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        decode_nested(bytes)
+    }
+}
+
+/// A node whose children are still being decoded
+enum Parent<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the element, with whether it is followed by its slot names
+    Element(Element<Ns, Tag, Leaf, Att, Val>, bool),
+    NodeList(Vec<Node<Ns, Tag, Leaf, Att, Val>>),
+    Fragment(Vec<Node<Ns, Tag, Leaf, Att, Val>>),
+}
+
+impl<Ns, Tag, Leaf, Att, Val> Parent<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    fn children(&mut self) -> &mut Vec<Node<Ns, Tag, Leaf, Att, Val>> {
+        match self {
+            Self::Element(element, _) => &mut element.children,
+            Self::NodeList(nodes) | Self::Fragment(nodes) => nodes,
+        }
+    }
+
+    /// the node, once all of its children are decoded
+    fn finish(
+        self,
+        bytes: &mut &[u8],
+    ) -> Result<Node<Ns, Tag, Leaf, Att, Val>, DecodeError> {
+        match self {
+            Self::Element(mut element, slotted) => {
+                if slotted {
+                    let slots: Vec<Att> = Decode::decode(bytes)?;
+                    if slots.len() != element.children.len() {
+                        return Err(invalid(bytes));
+                    }
+                    element.slots = Some(slots);
+                }
+                Ok(Node::Element(element))
+            }
+            Self::NodeList(nodes) => Ok(Node::NodeList(nodes)),
+            Self::Fragment(nodes) => Ok(Node::Fragment(nodes)),
+        }
+    }
+}
+
+/// decode the node with an explicit stack of the parents whose children are
+/// still being decoded, so the deep trees don't overflow the stack of the decoder,
+/// rejecting the nodes which are nested deeper than [`MAX_DEPTH`]
+fn decode_nested<Ns, Tag, Leaf, Att, Val>(
+    bytes: &mut &[u8],
+) -> Result<Node<Ns, Tag, Leaf, Att, Val>, DecodeError>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    // the parents with the number of their children which are not decoded yet
+    let mut parents: Vec<(Parent<Ns, Tag, Leaf, Att, Val>, usize)> = vec![];
+    loop {
+        if parents.len() >= MAX_DEPTH {
+            return Err(invalid(bytes));
+        }
+        let parent = match take_byte(bytes)? {
+            marker @ (ELEMENT | SLOTTED_ELEMENT) => Parent::Element(
+                Element {
+                    namespace: Decode::decode(bytes)?,
+                    tag: Decode::decode(bytes)?,
+                    attrs: Decode::decode(bytes)?,
+                    self_closing: Decode::decode(bytes)?,
+                    children: vec![],
+                    slots: None,
+                    memo: None,
+                },
+                marker == SLOTTED_ELEMENT,
+            ),
+            NODE_LIST => Parent::NodeList(vec![]),
+            FRAGMENT => Parent::Fragment(vec![]),
+            LEAF => {
+                let leaf = Node::Leaf(Decode::decode(bytes)?);
+                match complete(&mut parents, leaf, bytes)? {
+                    Some(node) => return Ok(node),
+                    None => continue,
+                }
+            }
+            _ => return Err(invalid(bytes)),
+        };
+        let len = decode_len(bytes)?;
+        if len > 0 {
+            parents.push((parent, len));
+        } else {
+            let node = parent.finish(bytes)?;
+            if let Some(node) = complete(&mut parents, node, bytes)? {
+                return Ok(node);
+            }
+        }
+    }
+}
+
+/// add the decoded `node` to its parent, finishing the parents whose last
+/// child it is, and return the root node once it is finished
+fn complete<Ns, Tag, Leaf, Att, Val>(
+    parents: &mut Vec<(Parent<Ns, Tag, Leaf, Att, Val>, usize)>,
+    mut node: Node<Ns, Tag, Leaf, Att, Val>,
+    bytes: &mut &[u8],
+) -> Result<Option<Node<Ns, Tag, Leaf, Att, Val>>, DecodeError>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    loop {
+        let Some((parent, remaining)) = parents.last_mut() else {
+            return Ok(Some(node));
+        };
+        parent.children().push(node);
+        *remaining -= 1;
+        if *remaining > 0 {
+            return Ok(None);
+        }
+        let (parent, _) = parents.pop().expect("the parent");
+        node = parent.finish(bytes)?;
+    }
+}
+
+impl<Ns, Att, Val> Encode for Attribute<Ns, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Encode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Encode,
+    Val: PartialEq + Clone + Debug + Encode,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.namespace.encode(buf);
+        self.name.encode(buf);
+        self.value.encode(buf);
+    }
+}
+
+impl<Ns, Att, Val> Decode for Attribute<Ns, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(Attribute {
+            namespace: Decode::decode(bytes)?,
+            name: Decode::decode(bytes)?,
+            value: Decode::decode(bytes)?,
+        })
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_varint(self.len() as u64, buf);
+        for item in self {
+            item.encode(buf);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = decode_len(bytes)?;
+        (0..len).map(|_| T::decode(bytes)).collect()
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.push(0),
+            Some(value) => {
+                buf.push(1);
+                value.encode(buf);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take_byte(bytes)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(bytes)?)),
+            _ => Err(invalid(bytes)),
+        }
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_varint(self.len() as u64, buf);
+        buf.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Encode for &str {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        str::encode(self, buf)
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        str::encode(self, buf)
+    }
+}
+
+impl Decode for String {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = decode_len(bytes)?;
+        let taken = take_bytes(bytes, len)?;
+        String::from_utf8(taken.to_vec()).map_err(|_| invalid(bytes))
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(u8::from(*self));
+    }
+}
+
+impl Decode for bool {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take_byte(bytes)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid(bytes)),
+        }
+    }
+}
+
+impl Encode for () {
+    fn encode(&self, _buf: &mut Vec<u8>) {}
+}
+
+impl Decode for () {
+    fn decode(_bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(())
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        u32::from(*self).encode(buf);
+    }
+}
+
+impl Decode for char {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let value = u32::decode(bytes)?;
+        char::from_u32(value).ok_or_else(|| invalid(bytes))
+    }
+}
+
+macro_rules! impl_codec_for_numbers {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+                    let taken = take_bytes(bytes, core::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_le_bytes(
+                        taken.try_into().expect("must have the size of the number"),
+                    ))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_for_numbers!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
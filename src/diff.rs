@@ -1,14 +1,94 @@
 //! provides diffing algorithm which returns patches
-use crate::{
-    node::attribute::group_attributes_per_name, Attribute, Element, Node,
-    Patch, TreePath,
-};
+use crate::{patch, Attribute, Element, Node, Patch, PatchType, TreePath};
+use alloc::format;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::cell::RefCell;
 use core::fmt::Debug;
 use core::hash::Hash;
 use core::{cmp, mem};
 
+pub use context::{DiffContext, InternStats};
+pub use default_key::{default_key, set_default_key, DefaultKey};
+pub use differ::Differ;
+pub use encoded::diff_against_encoded;
+pub use engine::DiffEngine;
+use equality::Equality;
+#[cfg(feature = "keyed")]
+pub(crate) use key_of::debug_hash;
+pub use key_of::KeyOf;
+pub(crate) use key_of::NodeKey;
+pub use leaf_diff::{
+    text_splices, LeafDiff, LeafSimilarity, TEXT_SPLICE_EDITS,
+};
+pub use options::{
+    DiffAlgorithm, DiffCancelled, DiffOptions, DiffWarning, DuplicateKeyPolicy,
+    EqualityCheck, MoveScope, VariantChange,
+};
+#[cfg(feature = "rayon")]
+pub use parallel::diff_parallel;
+pub use scratch::DiffScratch;
+pub use stats::{DiffProfile, DiffStats, ListStrategy};
+pub use stepper::{diff_stepwise, DiffProgress, DiffStepper};
+pub use table::diff_table;
+use work::{Finish, Step, WorkContext};
+
+pub mod attributes;
+mod context;
+mod default_key;
+mod differ;
+mod encoded;
+mod engine;
+mod equality;
+mod hydrate;
+mod key_of;
+mod leaf_diff;
+mod moves;
+mod options;
+#[cfg(feature = "rayon")]
+mod parallel;
+pub(crate) mod scratch;
+#[cfg(feature = "keyed")]
+mod similar;
+mod slots;
+pub(crate) mod small;
+mod stats;
+mod stepper;
+mod table;
+pub(crate) mod work;
+
+/// Return the patches needed for `old_node` to have the same DOM as `new_node`,
+/// using the configured default key attribute, which is `"key"` unless changed with
+/// `set_default_key`.
+///
+/// Use `diff_with_key` to use a different key attribute for just this call.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("div", vec![attr("key", "1")], vec![]);
+/// let new: MyNode = element("div", vec![attr("key", "2")], vec![]);
+///
+/// assert_eq!(diff(&old, &new), diff_with_key(&old, &new, &"key"));
+/// ```
+pub fn diff<'a, Ns, Tag, Leaf, Att, Val>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug + DefaultKey,
+    Val: PartialEq + Clone + Debug,
+{
+    diff_with_key(old_node, new_node, &Att::default_key())
+}
+
 /// Return the patches needed for `old_node` to have the same DOM as `new_node`
 ///
 /// # Agruments
@@ -39,6 +119,8 @@ use core::{cmp, mem};
 /// );
 ///
 /// let diff = diff_with_key(&old, &new, &"key");
+/// // the children are matched by their keys
+/// #[cfg(feature = "keyed")]
 /// assert_eq!(
 ///     diff,
 ///     vec![Patch::remove_node(
@@ -60,14 +142,272 @@ where
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
 {
-    diff_recursive(
+    diff_with_options(old_node, new_node, key, &DiffOptions::default())
+}
+
+/// Return the patches the same as `diff_with_key`, with the `DiffStats` of the diff,
+/// such as the number of the visited and skipped nodes and the number of patches
+/// of each kind, for profiling why a render produced a big list of patches.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element(
+///     "main",
+///     vec![],
+///     vec![element("p", vec![], vec![leaf("same")]), leaf("old")],
+/// );
+/// let new: MyNode = element(
+///     "main",
+///     vec![],
+///     vec![element("p", vec![], vec![leaf("same")]), leaf("new")],
+/// );
+///
+/// let (patches, stats) = diff_with_stats(&old, &new, &"key");
+/// assert_eq!(patches.len(), 1);
+/// assert_eq!(stats.nodes_visited, 3);
+/// assert_eq!(stats.nodes_skipped, 1);
+/// assert_eq!(stats.patch_count(PatchKind::ReplaceNode), 1);
+/// assert_eq!(stats.max_depth, 1);
+/// ```
+pub fn diff_with_stats<'a, Ns, Tag, Leaf, Att, Val>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    key: &Att,
+) -> (Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>, DiffStats)
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let stats = RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        stats: Some(&stats),
+        ..Default::default()
+    };
+    let patches = diff_with_options(old_node, new_node, key, &options);
+    (patches, stats.into_inner())
+}
+
+/// Return the patches needed for `old_node` to have the same DOM as `new_node`,
+/// where the `attr_differ` returns the changed parts of the changed attributes,
+/// such as the changed properties of a `style` attribute, which are patched with
+/// `UpdateAttributeParts` instead of adding the whole attribute.
+/// See `DiffOptions::attr_differ`.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("div", vec![attr("style", "color:red")], vec![]);
+/// let new: MyNode = element("div", vec![attr("style", "color:blue")], vec![]);
+///
+/// let style_differ = |_old: &Attribute<_, _, _>, new: &Attribute<_, _, &'static str>| {
+///     let (key, value) = new.value()[0].split_once(':')?;
+///     Some(vec![AttributePart::Set { key, value }])
+/// };
+/// let diff = diff_with_attr_differ(&old, &new, &"key", &style_differ);
+/// assert_eq!(
+///     diff,
+///     vec![Patch::update_attribute_parts(
+///         &"div",
+///         TreePath::root(),
+///         &new.attributes().unwrap()[0],
+///         [AttributePart::Set { key: "color", value: "blue" }],
+///     )]
+/// );
+/// ```
+pub fn diff_with_attr_differ<'a, Ns, Tag, Leaf, Att, Val>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    key: &Att,
+    attr_differ: &dyn Fn(
+        &Attribute<Ns, Att, Val>,
+        &Attribute<Ns, Att, Val>,
+    ) -> Option<Vec<patch::AttributePart<Val>>>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let options = DiffOptions {
+        attr_differ: Some(attr_differ),
+        ..Default::default()
+    };
+    diff_with_options(old_node, new_node, key, &options)
+}
+
+/// Return the patches needed for `old_node` to have the same DOM as `new_node`,
+/// using the `options` to control the behavior of the differ
+pub fn diff_with_options<'a, Ns, Tag, Leaf, Att, Val>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    // only the behavioral attributes are diffed against the pre-rendered tree
+    if let Some(is_hydrated) = options.hydrate {
+        let patches = hydrate::hydrate_node(
+            old_node,
+            new_node,
+            &TreePath::root(),
+            is_hydrated,
+        );
+        let patches = if options.update_attributes {
+            attributes::group_attribute_updates(patches)
+        } else {
+            patches
+        };
+        debug_assert_valid(old_node, &patches);
+        record_patches(&patches, options);
+        return if options.path_prefix.is_empty() {
+            patches
+        } else {
+            prefix_paths(patches, &options.path_prefix)
+        };
+    }
+    let patches = diff_node(
         old_node,
         new_node,
         &TreePath::root(),
         key,
+        options,
         &|_old, _new| false,
         &|_old, _new| false,
-    )
+    );
+    finish_patches(OldRoot::Node(old_node), patches, key, options)
+}
+
+/// Return the patches the same as `diff_with_options`, or `DiffCancelled` when the diff
+/// is cancelled with `DiffOptions::cancel` before it is complete, so the partial patches
+/// are never applied by mistake.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, *};
+/// use std::sync::atomic::AtomicBool;
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![], vec![leaf("hello")]);
+/// let new: MyNode = element("main", vec![], vec![leaf("world")]);
+///
+/// // a newer state arrived before the diff of this one is done
+/// let cancel = AtomicBool::new(true);
+/// let options = DiffOptions {
+///     cancel: Some(&cancel),
+///     ..Default::default()
+/// };
+/// assert_eq!(try_diff_with_options(&old, &new, &"key", &options), Err(DiffCancelled));
+/// ```
+pub fn try_diff_with_options<'a, Ns, Tag, Leaf, Att, Val>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Result<Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>, DiffCancelled>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let patches = diff_with_options(old_node, new_node, key, options);
+    // the flag is never reset, so it is still set when the diff stopped early
+    if options.is_cancelled() {
+        Err(DiffCancelled)
+    } else {
+        Ok(patches)
+    }
+}
+
+/// the passes over the patches of the whole tree after the nodes are diffed,
+/// such as the moves across the parents and prefixing the paths
+fn finish_patches<'a, Ns, Tag, Leaf, Att, Val>(
+    old_root: OldRoot<'a, Ns, Tag, Leaf, Att, Val>,
+    patches: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    // the partial patches of a cancelled diff are returned as they are
+    if options.is_cancelled() {
+        return patches;
+    }
+    let patches = match options.cross_parent_moves {
+        Some(scope) => {
+            moves::move_across_parents(&old_root, patches, key, options, scope)
+        }
+        None => patches,
+    };
+    let patches = if options.adoptable_trees.is_empty() {
+        patches
+    } else {
+        patch::adopt_inserted(patches, options.adoptable_trees, |node| {
+            options.key_of(node, key).is_some()
+        })
+    };
+    let patches = if options.update_attributes {
+        attributes::group_attribute_updates(patches)
+    } else {
+        patches
+    };
+    trace!("diffed {} patch(es)", patches.len());
+    // the element of `diff_element` is not a node which the patches can be validated against
+    if let OldRoot::Node(old_node) = old_root {
+        debug_assert_valid(old_node, &patches);
+    }
+    record_patches(&patches, options);
+    if options.path_prefix.is_empty() {
+        patches
+    } else {
+        prefix_paths(patches, &options.path_prefix)
+    }
+}
+
+/// count the kinds of the diffed patches in the `DiffStats`
+fn record_patches<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if let Some(stats) = options.stats {
+        stats
+            .borrow_mut()
+            .record_patches(patches.iter().map(Patch::kind));
+    }
 }
 
 /// calculate the difference of 2 nodes
@@ -104,12 +444,115 @@ where
         &'a Node<Ns, Tag, Leaf, Att, Val>,
     ) -> bool,
 {
-    diff_recursive(old_node, new_node, &TreePath::root(), key, skip, rep)
+    let patches = diff_node(
+        old_node,
+        new_node,
+        &TreePath::root(),
+        key,
+        &DiffOptions::default(),
+        skip,
+        rep,
+    );
+    debug_assert_valid(old_node, &patches);
+    patches
+}
+
+/// check that the paths of the patches are valid for the old node,
+/// only with the `validate-diffs` feature since it walks the old tree for every patch
+fn debug_assert_valid<Ns, Tag, Leaf, Att, Val>(
+    old_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if cfg!(feature = "validate-diffs") {
+        if let Err(e) = patch::validate(old_node, patches) {
+            panic!("invalid patches: {}, patches: {:#?}", e, patches);
+        }
+    }
+}
+
+/// The old tree which the patches of a diff are finished against,
+/// which is an element at `path` for `diff_element`
+pub(crate) enum OldRoot<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    Node(&'a Node<Ns, Tag, Leaf, Att, Val>),
+    Element {
+        element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+        path: TreePath,
+    },
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> OldRoot<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the old node at the path of a patch, which is never the element itself
+    /// for `diff_element`, since a `Node` is needed to return it
+    pub(crate) fn find_node_by_path(
+        &self,
+        path: &TreePath,
+    ) -> Option<&'a Node<Ns, Tag, Leaf, Att, Val>> {
+        match self {
+            Self::Node(node) => path.find_node_by_path(node),
+            Self::Element { element, path: at } => {
+                if !path.starts_with(at) {
+                    return None;
+                }
+                let mut rest = path.indices().skip(at.len());
+                let child = element.children.get(rest.next()?)?;
+                TreePath::new(rest).find_node_by_path(child)
+            }
+        }
+    }
+}
+
+/// prepend the `prefix` to the paths of the patches
+fn prefix_paths<'a, Ns, Tag, Leaf, Att, Val>(
+    mut patches: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+    prefix: &TreePath,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let prefixed = |path: &mut TreePath| {
+        path.path.splice(0..0, prefix.path.iter().copied());
+    };
+    for patch in patches.iter_mut() {
+        prefixed(&mut patch.patch_path);
+        if let PatchType::MoveBeforeNode { nodes_path }
+        | PatchType::MoveAfterNode { nodes_path }
+        | PatchType::MoveIntoNode { nodes_path } = &mut patch.patch_type
+        {
+            nodes_path.iter_mut().for_each(prefixed);
+        }
+    }
+    patches
 }
 
+#[cfg(feature = "keyed")]
 fn is_any_keyed<Ns, Tag, Leaf, Att, Val>(
     nodes: &[Node<Ns, Tag, Leaf, Att, Val>],
     key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
 ) -> bool
 where
     Ns: PartialEq + Clone + Debug,
@@ -118,10 +561,15 @@ where
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
 {
-    nodes.iter().any(|child| is_keyed_node(child, key))
+    if let Some(key_of) = options.key_of {
+        nodes.iter().any(|child| key_of(child).is_some())
+    } else {
+        nodes.iter().any(|child| is_keyed_node(child, key))
+    }
 }
 
 /// returns true any attributes of this node attribute has key in it
+#[cfg(feature = "keyed")]
 fn is_keyed_node<Ns, Tag, Leaf, Att, Val>(
     node: &Node<Ns, Tag, Leaf, Att, Val>,
     key: &Att,
@@ -144,6 +592,7 @@ fn should_replace<'a, Ns, Tag, Leaf, Att, Val, Rep>(
     old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
     new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
     key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
     rep: &Rep,
 ) -> bool
 where
@@ -168,25 +617,89 @@ where
     }
 
     // replace if the old key does not match the new key
+    let mut key_matched = false;
     if let (Some(old_key), Some(new_key)) =
-        (old_node.attribute_value(key), new_node.attribute_value(key))
+        (options.key_of(old_node, key), options.key_of(new_node, key))
     {
         if old_key != new_key {
             return true;
         }
+        key_matched = true;
     }
     // replace if they have different element tag
     if let (Node::Element(old_element), Node::Element(new_element)) =
         (old_node, new_node)
     {
-        // Replace if there are different element tags
-        if old_element.tag != new_element.tag {
+        // Replace if there are different element tags,
+        // unless the matching key wins over the tag or the element can be morphed
+        if old_element.tag != new_element.tag
+            && !(options.keyed_tag_change && key_matched)
+            && !options
+                .can_morph
+                .is_some_and(|can_morph| can_morph(old_node, new_node))
+        {
+            return true;
+        }
+        // the children can not be matched when only one of the elements is slotted,
+        // or when the slots are not one per child
+        if old_element.is_slotted() != new_element.is_slotted()
+            || !old_element.has_slot_per_child()
+            || !new_element.has_slot_per_child()
+        {
             return true;
         }
     }
     false
 }
 
+/// returns true if the differ should check if the new node wraps the old node or vice versa
+fn should_wrap_or_unwrap<Ns, Tag, Leaf, Att, Val>(
+    old_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> bool
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if mem::discriminant(old_node) != mem::discriminant(new_node) {
+        // let the user decide what to do with nodes of different variant
+        if let Some(variant_change) = options.variant_change {
+            variant_change(old_node, new_node) == VariantChange::WrapOrUnwrap
+        } else {
+            options.detect_wrapping
+        }
+    } else {
+        options.detect_wrapping && old_node.tag() != new_node.tag()
+    }
+}
+
+/// return a `WrapNode` patch if the new node is an element which only wraps the old node,
+/// or an `UnwrapNode` patch if the old node is an element which only wraps the new node
+fn wrap_or_unwrap<'a, Ns, Tag, Leaf, Att, Val>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+) -> Option<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if new_node.children() == core::slice::from_ref(old_node) {
+        Some(Patch::wrap_node(old_node.tag(), path.clone(), new_node))
+    } else if old_node.children() == core::slice::from_ref(new_node) {
+        Some(Patch::unwrap_node(old_node.tag(), path.clone()))
+    } else {
+        None
+    }
+}
+
 /// diff the nodes recursively
 pub fn diff_recursive<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
     old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
@@ -211,60 +724,236 @@ where
         &'a Node<Ns, Tag, Leaf, Att, Val>,
     ) -> bool,
 {
-    // skip diffing if the function evaluates to true
-    if skip(old_node, new_node) {
-        return vec![];
-    }
+    diff_node(
+        old_node,
+        new_node,
+        path,
+        key,
+        &DiffOptions::default(),
+        skip,
+        rep,
+    )
+}
 
-    // replace node and return early
-    if should_replace(old_node, new_node, key, rep) {
-        return vec![Patch::replace_node(
-            old_node.tag(),
+/// diff the nodes using the `options`, the descendants are diffed with the work stack
+/// of `work::resolve` instead of recursing, so very deep trees do not overflow the stack
+pub(crate) fn diff_node<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    skip: &Skip,
+    rep: &Rep,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+    Skip: Fn(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool,
+    Rep: Fn(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool,
+{
+    let cx = WorkContext::new(key, options, skip, rep);
+    let steps = expand_node(old_node, new_node, Equality::Unknown, path, &cx);
+    work::resolve(steps, path, &cx)
+}
+
+/// return the patches of the old and new node, with the pairs of their children
+/// which are still to be diffed as the `Step::Diff` steps.
+/// `equality` is what is known about the equality of the subtrees of the nodes,
+/// so the nodes are compared only when it is not known from comparing their parents
+fn expand_node<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    mut equality: Equality,
+    path: &TreePath,
+    cx: &WorkContext<'_, '_, Ns, Tag, Leaf, Att, Val, Skip, Rep>,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+    Skip: Fn(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool,
+    Rep: Fn(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool,
+{
+    let &WorkContext {
+        key,
+        options,
+        skip,
+        rep,
+    } = cx;
+    if let Some(stats) = options.stats {
+        stats.borrow_mut().record_visit(path.path.len());
+    }
+
+    // the very same node, such as a shared node from a `NodeCache`
+    if core::ptr::eq(old_node, new_node) {
+        return skipped(options);
+    }
+
+    // the memoized subtrees with the same hash are unchanged
+    if old_node.memo().is_some() && old_node.memo() == new_node.memo() {
+        return skipped(options);
+    }
+
+    // the ignored subtrees are treated as equal regardless of their content
+    if options.is_ignored(old_node, path) {
+        return skipped(options);
+    }
+
+    // skip diffing if the function evaluates to true
+    if skip(old_node, new_node) {
+        return skipped(options);
+    }
+
+    // skip the key and tag checks of the equal nodes, unless they are explicitly replaced
+    if options.equality_check == EqualityCheck::BeforeReplace
+        && equality.resolve(old_node, new_node, options)
+        && !rep(old_node, new_node)
+    {
+        return skipped(options);
+    }
+
+    // wrap or unwrap the old node instead of replacing it
+    if should_wrap_or_unwrap(old_node, new_node, options)
+        && !rep(old_node, new_node)
+    {
+        if let Some(patch) = wrap_or_unwrap(old_node, new_node, path) {
+            return vec![patch.into()];
+        }
+    }
+
+    // replace node and return early
+    if should_replace(old_node, new_node, key, options, rep) {
+        return vec![Patch::replace_node(
+            old_node.tag(),
             path.clone(),
             vec![new_node],
-        )];
+        )
+        .into()];
     }
 
     // skip diffing if they are essentially the same node
-    if old_node == new_node {
-        return vec![];
+    if options.equality_check == EqualityCheck::AfterReplace
+        && equality.resolve(old_node, new_node, options)
+    {
+        return skipped(options);
     }
 
-    let mut patches = vec![];
+    // replace the changed node instead of descending past the maximum depth,
+    // fragments are not real nodes so their children are diffed instead
+    if !old_node.is_fragment()
+        && options
+            .max_depth
+            .is_some_and(|max_depth| path.path.len() >= max_depth)
+    {
+        // the equality is only checked here when it is not checked above
+        if options.equality_check == EqualityCheck::Never
+            && equality.resolve(old_node, new_node, options)
+        {
+            return skipped(options);
+        }
+        return vec![Patch::replace_node(
+            old_node.tag(),
+            path.clone(),
+            vec![new_node],
+        )
+        .into()];
+    }
 
     // The following comparison can only contain identical variants, other
     // cases have already been handled above by comparing variant
     // discriminants.
     match (old_node, new_node) {
         (Node::Leaf(old_leaf), Node::Leaf(new_leaf)) => {
-            if old_leaf != new_leaf {
-                let ct = Patch::replace_node(
+            if old_leaf == new_leaf {
+                return skipped(options);
+            }
+            let splices = options
+                .leaf_diff
+                .and_then(|leaf_diff| leaf_diff(old_leaf, new_leaf));
+            let ct = if let Some(splices) = splices {
+                Patch::splice_leaf(path.clone(), old_leaf, new_leaf, splices)
+            } else if options.change_leaf {
+                Patch::change_leaf(path.clone(), old_leaf, new_leaf)
+            } else {
+                Patch::replace_node(
                     old_node.tag(),
                     path.clone(),
                     vec![new_node],
-                );
-                patches.push(ct);
-            }
+                )
+            };
+            vec![ct.into()]
         }
         // We're comparing two element nodes
         (Node::Element(old_element), Node::Element(new_element)) => {
-            let patch =
-                diff_element(old_element, new_element, key, path, skip, rep);
-            patches.extend(patch);
+            let mut steps = vec![];
+            // the tag changed on keyed elements with `keyed_tag_change`
+            if old_element.tag != new_element.tag {
+                steps.push(
+                    Patch::change_tag(
+                        path.clone(),
+                        &old_element.tag,
+                        &new_element.tag,
+                    )
+                    .into(),
+                );
+            }
+            steps.extend(element_steps(
+                old_element,
+                new_element,
+                path,
+                key,
+                options,
+            ));
+            equality.hint_children(
+                &mut steps,
+                &old_element.children,
+                &new_element.children,
+            );
+            // replace the element when its patches are over the budget
+            if options.max_patches.is_some() {
+                vec![Step::Group {
+                    steps,
+                    finish: Finish::Element {
+                        old_node,
+                        new_node,
+                        path_len: path.path.len(),
+                    },
+                }]
+            } else {
+                steps
+            }
         }
         (Node::Fragment(old_nodes), Node::Fragment(new_nodes)) => {
             // we back track since Fragment is not a real node, but it would still
             // be traversed from the prior call
-            let patch = diff_nodes(
+            let mut steps = vec![diff_nodes(
                 None,
                 old_nodes,
                 new_nodes,
                 key,
                 &path.backtrack(),
-                skip,
-                rep,
-            );
-            patches.extend(patch);
+                options,
+            )];
+            equality.hint_children(&mut steps, old_nodes, new_nodes);
+            steps
         }
         (Node::NodeList(_old_elements), Node::NodeList(_new_elements)) => {
             panic!(
@@ -274,16 +963,89 @@ where
         _ => {
             unreachable!("Unequal variant discriminants should already have been handled");
         }
-    };
+    }
+}
 
-    patches
+/// the steps of a pair of nodes which is skipped, counted in `DiffStats::nodes_skipped`
+fn skipped<'a, Ns, Tag, Leaf, Att, Val>(
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if let Some(stats) = options.stats {
+        stats.borrow_mut().nodes_skipped += 1;
+    }
+    vec![]
 }
 
-fn diff_element<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+/// diff the attributes and the children of 2 elements at `path`,
+/// using the configured default key attribute the same as `diff`
+///
+/// This is useful for component systems which holds `Element`s at their boundaries,
+/// so they don't have to wrap them into `Node::Element` just to diff them.
+/// The patches are finished the same as `diff_with_options`, so `options` such as
+/// `update_attributes`, `cross_parent_moves`, `adoptable_trees`, `path_prefix` and `stats`
+/// are used, except `hydrate`.
+///
+/// Note: the caller is responsible for checking whether the elements should be
+/// replaced instead, ie: when they have different tags or keys, or only one of them
+/// has its children keyed by their slots,
+/// since a `ReplaceNode` patch needs to reference a `Node`.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyElement =
+///    Element<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyElement = Element::new(None, "div", vec![attr("class", "old")], vec![], false);
+/// let new: MyElement = Element::new(None, "div", vec![attr("class", "new")], vec![], false);
+///
+/// let diff = diff_element(&old, &new, &TreePath::root(), &DiffOptions::default());
+/// assert_eq!(
+///     diff,
+///     vec![Patch::add_attributes(&"div", TreePath::root(), vec![&attr("class", "new")])]
+/// );
+/// ```
+pub fn diff_element<'a, Ns, Tag, Leaf, Att, Val>(
     old_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
     new_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
-    key: &Att,
     path: &TreePath,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug + DefaultKey,
+    Val: PartialEq + Clone + Debug,
+{
+    diff_element_with_functions(
+        old_element,
+        new_element,
+        path,
+        &Att::default_key(),
+        options,
+        &|_old, _new| false,
+        &|_old, _new| false,
+    )
+}
+
+/// diff the elements the same as `diff_element`, with the literal name of the key
+/// attribute, and the `skip` and `rep` functions of the descendants
+/// the same as `diff_with_functions`
+pub fn diff_element_with_functions<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+    old_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    new_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
     skip: &Skip,
     rep: &Rep,
 ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
@@ -302,71 +1064,498 @@ where
         &'a Node<Ns, Tag, Leaf, Att, Val>,
     ) -> bool,
 {
-    let mut patches = create_attribute_patches(old_element, new_element, path);
+    let steps = element_steps(old_element, new_element, path, key, options);
+    let patches =
+        work::resolve(steps, path, &WorkContext::new(key, options, skip, rep));
+    finish_patches(
+        OldRoot::Element {
+            element: old_element,
+            path: path.clone(),
+        },
+        patches,
+        key,
+        options,
+    )
+}
+
+/// the attribute patches of the elements, followed by the steps of their children
+fn element_steps<'a, Ns, Tag, Leaf, Att, Val>(
+    old_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    new_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if let Some(stats) = options.stats {
+        if let Some(profile) = stats.borrow_mut().profile.as_mut() {
+            profile.record_element(format!("{:?}", new_element.tag()));
+        }
+    }
+    let mut steps: Vec<_> =
+        attributes::attribute_patches(old_element, new_element, path, options)
+            .into_iter()
+            .map(Step::Patch)
+            .collect();
 
-    let more_patches = diff_nodes(
+    // the slotted children are matched by their slots instead of their positions
+    if old_element.is_slotted() && new_element.is_slotted() {
+        steps.extend(slots::diff_slots(old_element, new_element, path));
+        return steps;
+    }
+    steps.push(diff_nodes(
         Some(old_element.tag()),
         &old_element.children,
         &new_element.children,
         key,
         path,
-        skip,
-        rep,
-    );
+        options,
+    ));
+    steps
+}
 
-    patches.extend(more_patches);
-    patches
+/// diff the keyed children using the keys which are already known by the caller,
+/// such as frameworks which generated the keys, so the keys are not looked up
+/// from the attributes of the children. The keys of the descendants are still looked up
+/// with `key` and `options`.
+///
+/// `path` is the path of the parent of the children, the parent is not known here
+/// so the `AppendChildren` patch has no tag.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: Vec<MyNode> = vec![
+///     element("li", vec![attr("key", "1")], vec![]),
+///     element("li", vec![attr("key", "2")], vec![]),
+/// ];
+/// let new: Vec<MyNode> = vec![element("li", vec![attr("key", "2")], vec![])];
+///
+/// let diff = diff_keyed_children_with_keys(
+///     &old,
+///     &["1", "2"],
+///     &new,
+///     &["2"],
+///     &TreePath::root(),
+///     &"key",
+///     &DiffOptions::default(),
+/// );
+/// assert_eq!(diff, vec![Patch::remove_node(Some(&"li"), TreePath::new([0]))]);
+/// ```
+///
+/// # Panics
+/// Panics if the number of keys is not the same as the number of children
+#[cfg(feature = "keyed")]
+pub fn diff_keyed_children_with_keys<'a, Ns, Tag, Leaf, Att, Val>(
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    old_keys: &[Val],
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    new_keys: &[Val],
+    path: &TreePath,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    assert_eq!(old_children.len(), old_keys.len(), "one key per old child");
+    assert_eq!(new_children.len(), new_keys.len(), "one key per new child");
+    let old_keys: Vec<_> = old_keys.iter().map(Some).collect();
+    let new_keys: Vec<_> = new_keys.iter().map(Some).collect();
+    let numeric_key = options
+        .numeric_key
+        .map(|numeric_key| move |value: &&Val| numeric_key(value));
+    let key_order = options
+        .key_order
+        .map(|key_order| move |a: &&Val, b: &&Val| key_order(a, b));
+    let steps = crate::diff_lis::diff_keyed_nodes_with_keys(
+        None,
+        old_children,
+        new_children,
+        crate::diff_lis::ChildKeys {
+            numeric_key: match &numeric_key {
+                Some(numeric_key) => Some(numeric_key),
+                None => None,
+            },
+            key_order: match &key_order {
+                Some(key_order) => Some(key_order),
+                None => None,
+            },
+            ..crate::diff_lis::ChildKeys::new(&old_keys, &new_keys)
+        },
+        path,
+        options,
+    );
+    work::resolve(
+        steps,
+        path,
+        &WorkContext::new(key, options, &|_old, _new| false, &|_old, _new| {
+            false
+        }),
+    )
 }
 
-fn diff_nodes<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+fn diff_nodes<'a, Ns, Tag, Leaf, Att, Val>(
     old_tag: Option<&'a Tag>,
     old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
     new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
     key: &Att,
     path: &TreePath,
-    skip: &Skip,
-    rep: &Rep,
-) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Step<'a, Ns, Tag, Leaf, Att, Val>
 where
     Ns: PartialEq + Clone + Debug,
     Tag: PartialEq + Debug,
     Leaf: PartialEq + Clone + Debug,
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
-    Skip: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
-    Rep: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
 {
-    let diff_as_keyed =
-        is_any_keyed(old_children, key) || is_any_keyed(new_children, key);
+    // the changed children past the maximum depth are replaced as a whole, so they are
+    // diffed by their position instead of being the anchors of the keyed moves and inserts
+    let strategy = if options
+        .max_depth
+        .is_some_and(|max_depth| path.path.len() + 1 >= max_depth)
+    {
+        ListStrategy::Positional
+    } else {
+        list_strategy(old_children, new_children, key, options)
+    };
+    #[cfg(feature = "keyed")]
+    let strategy = if strategy == ListStrategy::Keyed
+        && options.duplicate_keys == DuplicateKeyPolicy::Positional
+        && (has_duplicate_keys(old_children, key, options)
+            || has_duplicate_keys(new_children, key, options))
+    {
+        if let Some(on_warning) = options.on_warning {
+            on_warning(DiffWarning::DuplicateKeys { path: path.clone() });
+        }
+        ListStrategy::Positional
+    } else {
+        strategy
+    };
+    if let Some(stats) = options.stats {
+        let mut stats = stats.borrow_mut();
+        stats.lists.push((path.clone(), strategy));
+        if let Some(profile) = stats.profile.as_mut() {
+            let keys = (strategy == ListStrategy::Keyed).then(|| {
+                new_children
+                    .iter()
+                    .filter_map(|child| options.key_of(child, key))
+                    .map(|key| format!("{key:?}"))
+                    .collect()
+            });
+            profile.record_list(new_children.len(), keys);
+        }
+    }
 
-    if diff_as_keyed {
-        let keyed_patches = crate::diff_lis::diff_keyed_nodes(
+    let steps = match strategy {
+        #[cfg(feature = "keyed")]
+        ListStrategy::Keyed => crate::diff_lis::diff_keyed_nodes(
             old_tag,
             old_children,
             new_children,
             key,
             path,
-            skip,
-            rep,
-        );
-        keyed_patches
-    } else {
-        let non_keyed_patches = diff_non_keyed_nodes(
+            options,
+        ),
+        _ => diff_non_keyed_nodes(
             old_tag,
             old_children,
             new_children,
             key,
             path,
-            skip,
-            rep,
-        );
-        non_keyed_patches
+            options,
+        ),
+    };
+
+    // the islands and the grouping need the patches of the children
+    let finish = if options.sync_islands || options.for_each_child {
+        Finish::Children {
+            old_tag,
+            old_children,
+            path_len: path.path.len(),
+        }
+    } else {
+        Finish::None
+    };
+    Step::Group { steps, finish }
+}
+
+/// replace the same attribute patches on each of the children at `path`
+/// with `ForEachChild` patches
+fn group_for_each_child<'a, Ns, Tag, Leaf, Att, Val>(
+    old_tag: Option<&'a Tag>,
+    child_count: usize,
+    patches: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+    path: &TreePath,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let is_child_path = |child_path: &TreePath| child_path.backtrack() == *path;
+    let shape_changed = patches.iter().any(|patch| {
+        patch.changes_siblings()
+            && (is_child_path(patch.path())
+                || patch.node_paths().iter().any(is_child_path))
+    });
+    // the appended children would also receive the `ForEachChild` patches
+    let appended = patches.iter().any(|patch| {
+        patch.path() == path
+            && matches!(patch.patch_type, PatchType::AppendChildren { .. })
+    });
+    if child_count < 2 || shape_changed || appended {
+        return patches;
+    }
+    let is_child_attribute_patch =
+        |patch: &Patch<'a, Ns, Tag, Leaf, Att, Val>| {
+            is_child_path(patch.path())
+                && matches!(
+                    patch.patch_type,
+                    PatchType::AddAttributes { .. }
+                        | PatchType::RemoveAttributes { .. }
+                )
+        };
+    let attribute_patches = |index: usize| -> Vec<
+        &PatchType<'a, Ns, Tag, Leaf, Att, Val>,
+    > {
+        let child_path = path.traverse(index);
+        patches
+            .iter()
+            .filter(|patch| {
+                is_child_attribute_patch(patch) && patch.path() == &child_path
+            })
+            .map(|patch| &patch.patch_type)
+            .collect()
+    };
+    let first = attribute_patches(0);
+    let all_same = !first.is_empty()
+        && (1..child_count).all(|index| attribute_patches(index) == first);
+    if !all_same {
+        return patches;
+    }
+    // the patches of the first child are turned into the `ForEachChild` patches,
+    // the same patches of the rest of the children are dropped
+    let first_child = path.traverse(0);
+    patches
+        .into_iter()
+        .filter_map(|patch| {
+            if !is_child_attribute_patch(&patch) {
+                Some(patch)
+            } else if patch.path() == &first_child {
+                Some(Patch::for_each_child(
+                    old_tag,
+                    path.clone(),
+                    patch.patch_type,
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// emit a `SyncIsland` patch for each of the ignored children which are still in place,
+/// when the patches of the children inserted, removed or moved any of the children
+fn sync_islands<'a, Ns, Tag, Leaf, Att, Val>(
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    patches: &[Patch<'a, Ns, Tag, Leaf, Att, Val>],
+    path: &TreePath,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let is_child_path = |child_path: &TreePath| child_path.backtrack() == *path;
+    let shape_changed = patches.iter().any(|patch| {
+        patch.changes_siblings()
+            && (is_child_path(patch.path())
+                || patch.node_paths().iter().any(is_child_path))
+    });
+    if !shape_changed {
+        return vec![];
+    }
+    old_children
+        .iter()
+        .enumerate()
+        .map(|(index, child)| (child, path.traverse(index)))
+        .filter(|(child, child_path)| options.is_ignored(child, child_path))
+        // the islands which are removed or replaced are gone
+        .filter(|(_, child_path)| {
+            !patches.iter().any(|patch| {
+                patch.path() == child_path
+                    && matches!(
+                        patch.patch_type,
+                        PatchType::RemoveNode | PatchType::ReplaceNode { .. }
+                    )
+            })
+        })
+        .map(|(child, child_path)| Patch::sync_island(child.tag(), child_path))
+        .collect()
+}
+
+/// pick the strategy for diffing the children using the configured `DiffAlgorithm`
+#[cfg(feature = "keyed")]
+fn list_strategy<Ns, Tag, Leaf, Att, Val>(
+    old_children: &[Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &[Node<Ns, Tag, Leaf, Att, Val>],
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> ListStrategy
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    match options.algorithm {
+        DiffAlgorithm::KeyedIfAny => {
+            if is_any_keyed(old_children, key, options)
+                || is_any_keyed(new_children, key, options)
+            {
+                ListStrategy::Keyed
+            } else {
+                ListStrategy::Positional
+            }
+        }
+        DiffAlgorithm::Positional => ListStrategy::Positional,
+        DiffAlgorithm::Auto => {
+            auto_list_strategy(old_children, new_children, key, options)
+        }
+    }
+}
+
+/// the children are always diffed by their position when the `keyed` feature is disabled
+#[cfg(not(feature = "keyed"))]
+fn list_strategy<Ns, Tag, Leaf, Att, Val>(
+    _old_children: &[Node<Ns, Tag, Leaf, Att, Val>],
+    _new_children: &[Node<Ns, Tag, Leaf, Att, Val>],
+    _key: &Att,
+    _options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> ListStrategy
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    ListStrategy::Positional
+}
+
+/// whether any of the keys of the children is used by more than one of them
+#[cfg(feature = "keyed")]
+fn has_duplicate_keys<Ns, Tag, Leaf, Att, Val>(
+    children: &[Node<Ns, Tag, Leaf, Att, Val>],
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> bool
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut keys: Vec<_> = children
+        .iter()
+        .filter_map(|child| options.key_of(child, key))
+        .collect();
+    match options.key_order {
+        Some(order) => {
+            keys.sort_by(|a, b| a.cmp_by(b, order));
+            keys.windows(2).any(|pair| pair[0] == pair[1])
+        }
+        None if options.scan_keys => keys
+            .iter()
+            .enumerate()
+            .any(|(index, key)| keys[index + 1..].contains(key)),
+        // only the keys with the same hash are compared
+        None => {
+            let mut hashes: Vec<_> = keys
+                .iter()
+                .enumerate()
+                .map(|(index, key)| (debug_hash(key), index))
+                .collect();
+            hashes.sort_unstable();
+            hashes.chunk_by(|a, b| a.0 == b.0).any(|group| {
+                group.iter().enumerate().any(|(i, (_, a))| {
+                    group[i + 1..].iter().any(|(_, b)| keys[*a] == keys[*b])
+                })
+            })
+        }
+    }
+}
+
+/// The keyed matching is only worth it when most of the children are keyed,
+/// since the unkeyed children are recreated instead of being diffed,
+/// and when some of the keys are not at the same position anymore.
+#[cfg(feature = "keyed")]
+fn auto_list_strategy<Ns, Tag, Leaf, Att, Val>(
+    old_children: &[Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &[Node<Ns, Tag, Leaf, Att, Val>],
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> ListStrategy
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let old_keys: Vec<_> = old_children
+        .iter()
+        .map(|c| options.key_of(c, key))
+        .collect();
+    let new_keys: Vec<_> = new_children
+        .iter()
+        .map(|c| options.key_of(c, key))
+        .collect();
+
+    let total = old_keys.len() + new_keys.len();
+    let keyed = old_keys
+        .iter()
+        .chain(new_keys.iter())
+        .filter(|k| k.is_some())
+        .count();
+    if keyed == 0 || keyed * 2 < total {
+        return ListStrategy::Positional;
+    }
+
+    // the keys of the shorter list are all matched at the same position in the
+    // prefix, so nothing moved and the children are just appended or removed at the end
+    let prefix = old_keys
+        .iter()
+        .zip(new_keys.iter())
+        .take_while(|(old, new)| old == new)
+        .count();
+    if prefix == cmp::min(old_keys.len(), new_keys.len()) {
+        ListStrategy::Positional
+    } else {
+        ListStrategy::Keyed
     }
 }
 
@@ -380,56 +1569,87 @@ where
 ///
 ///  If there are more children in the new_element than the old_element
 ///  it will be all appended in the old_element.
-fn diff_non_keyed_nodes<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+fn diff_non_keyed_nodes<'a, Ns, Tag, Leaf, Att, Val>(
     old_element_tag: Option<&'a Tag>,
     old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
     new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
-    key: &Att,
+    #[cfg_attr(not(feature = "keyed"), allow(unused_variables))] key: &Att,
     path: &TreePath,
-    skip: &Skip,
-    rep: &Rep,
-) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
 where
     Ns: PartialEq + Clone + Debug,
     Tag: PartialEq + Debug,
     Leaf: PartialEq + Clone + Debug,
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
-    Skip: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
-    Rep: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
 {
-    let mut patches = vec![];
+    if options.detect_wrapping {
+        if let Some(patch) =
+            hoist_or_lower_children(old_children, new_children, path)
+        {
+            return vec![patch.into()];
+        }
+    }
+
+    if options.merge_text_runs {
+        if let Some(steps) = diff_leaf_runs(old_children, new_children, path) {
+            return steps;
+        }
+    }
+
+    #[cfg(feature = "keyed")]
+    if options.detect_moves {
+        if let Some(steps) = crate::diff_lis::diff_moved_nodes(
+            old_element_tag,
+            old_children,
+            new_children,
+            path,
+            options,
+        ) {
+            return steps;
+        }
+    }
+
+    #[cfg(feature = "keyed")]
+    if let Some(threshold) = options.match_similar {
+        if let Some(steps) = similar::diff_similar_nodes(
+            old_element_tag,
+            old_children,
+            new_children,
+            threshold,
+            key,
+            path,
+            options,
+        ) {
+            return steps;
+        }
+    }
+
+    let mut steps = vec![];
     let old_child_count = old_children.len();
     let new_child_count = new_children.len();
 
     let min_count = cmp::min(old_child_count, new_child_count);
     for index in 0..min_count {
-        // if we iterate trough the old elements, a new child_path is created for that iteration
-        let child_path = path.traverse(index);
-
         let old_child =
-            &old_children.get(index).expect("No old_node child node");
-        let new_child = &new_children.get(index).expect("No new child node");
+            old_children.get(index).expect("No old_node child node");
+        let new_child = new_children.get(index).expect("No new child node");
 
-        let more_patches =
-            diff_recursive(old_child, new_child, &child_path, key, skip, rep);
-        patches.extend(more_patches);
+        steps.push(Step::child(old_child, new_child, path, index));
     }
 
     // If there are more new child than old_node child, we make a patch to append the excess element
     // starting from old_child_count to the last item of the new_elements
     if new_child_count > old_child_count {
-        patches.push(Patch::append_children(
-            old_element_tag,
-            path.clone(),
-            new_children.iter().skip(old_child_count).collect(),
-        ));
+        steps.push(
+            Patch::append_children(
+                old_element_tag,
+                path.clone(),
+                new_children.iter().skip(old_child_count).collect(),
+            )
+            .into(),
+        );
     }
 
     if new_child_count < old_child_count {
@@ -442,92 +1662,139 @@ where
                     old_child.tag(),
                     path.traverse(new_child_count + i),
                 )
+                .into()
             })
             .collect::<Vec<_>>();
 
-        patches.extend(remove_node_patches);
+        steps.extend(remove_node_patches);
     }
 
-    patches
+    steps
 }
 
+/// detect if a level was removed, ie: the children of the only old child are hoisted
+/// to become the new children, which will be an `UnwrapNode` patch.
+/// Or if a level was added, ie: the old children are lowered into the only new child,
+/// which will be a `WrapNode` patch.
 ///
-/// Note: The performance bottlenecks
-///     - allocating new vec
-///     - merging attributes of the same name
-fn create_attribute_patches<'a, Ns, Tag, Leaf, Att, Val>(
-    old_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
-    new_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+/// A single child on both sides is already handled when diffing the child nodes.
+fn hoist_or_lower_children<'a, Ns, Tag, Leaf, Att, Val>(
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
     path: &TreePath,
-) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+) -> Option<Patch<'a, Ns, Tag, Leaf, Att, Val>>
 where
     Ns: PartialEq + Clone + Debug,
-    Leaf: PartialEq + Clone + Debug,
     Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
 {
-    let new_attributes = new_element.attributes();
-    let old_attributes = old_element.attributes();
-
-    // skip diffing if they the same attributes
-    if old_attributes == new_attributes {
-        return vec![];
+    match (old_children, new_children) {
+        ([old_child], new_children)
+            if new_children.len() > 1
+                && old_child.children() == new_children =>
+        {
+            Some(Patch::unwrap_node(old_child.tag(), path.traverse(0)))
+        }
+        (old_children, [new_child])
+            if old_children.len() > 1
+                && new_child.children() == old_children =>
+        {
+            Some(Patch::wrap_node(
+                old_children[0].tag(),
+                path.traverse(0),
+                new_child,
+            ))
+        }
+        _ => None,
     }
-    let mut patches = vec![];
+}
 
-    let mut add_attributes: Vec<&Attribute<Ns, Att, Val>> = vec![];
-    let mut remove_attributes: Vec<&Attribute<Ns, Att, Val>> = vec![];
+/// split the children into segments of adjacent leaves and single non-leaf nodes,
+/// returned as (start, len, is_leaf_run)
+fn leaf_runs<Ns, Tag, Leaf, Att, Val>(
+    children: &[Node<Ns, Tag, Leaf, Att, Val>],
+) -> Vec<(usize, usize, bool)>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut segments: Vec<(usize, usize, bool)> = vec![];
+    for (index, child) in children.iter().enumerate() {
+        match segments.last_mut() {
+            Some((_, len, true)) if child.is_leaf() => *len += 1,
+            _ => segments.push((index, 1, child.is_leaf())),
+        }
+    }
+    segments
+}
 
-    let new_attributes_grouped = group_attributes_per_name(new_attributes);
-    let old_attributes_grouped = group_attributes_per_name(old_attributes);
+/// When the old and new children only differ in how their adjacent leaves are split,
+/// such as a text which is split into 2 text leaves, each of the changed runs of leaves
+/// is changed with a single `ChangeLeafRun` patch instead of pairs of remove and insert patches.
+///
+/// Returns None if the children don't line up when grouped by runs of leaves,
+/// or if none of the runs changed in length.
+fn diff_leaf_runs<'a, Ns, Tag, Leaf, Att, Val>(
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    path: &TreePath,
+) -> Option<Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let old_runs = leaf_runs(old_children);
+    let new_runs = leaf_runs(new_children);
 
-    // for all new elements that doesn't exist in the old elements
-    // or the values differ
-    // add it to the AddAttribute patches
-    for (new_attr_name, new_attrs) in new_attributes_grouped.iter() {
-        let old_attr_values =
-            old_attributes_grouped.get(new_attr_name).map(|attrs| {
-                attrs.iter().map(|attr| &attr.value).collect::<Vec<_>>()
-            });
+    let lined_up = old_runs.len() == new_runs.len()
+        && old_runs
+            .iter()
+            .zip(new_runs.iter())
+            .all(|(old, new)| old.2 == new.2);
+    let any_run_changed = old_runs
+        .iter()
+        .zip(new_runs.iter())
+        .any(|(old, new)| old.1 != new.1);
 
-        let new_attr_values =
-            new_attributes_grouped.get(new_attr_name).map(|attrs| {
-                attrs.iter().map(|attr| &attr.value).collect::<Vec<_>>()
-            });
+    if !lined_up || !any_run_changed {
+        return None;
+    }
 
-        if let Some(old_attr_values) = old_attr_values {
-            let new_attr_values =
-                new_attr_values.expect("must have new attr values");
-            if old_attr_values != new_attr_values {
-                add_attributes.extend(new_attrs);
+    let mut steps = vec![];
+    for ((old_start, old_len, _), (new_start, new_len, _)) in
+        old_runs.into_iter().zip(new_runs)
+    {
+        let old_run = &old_children[old_start..old_start + old_len];
+        let new_run = &new_children[new_start..new_start + new_len];
+        if old_len == new_len {
+            for (index, (old_child, new_child)) in
+                old_run.iter().zip(new_run.iter()).enumerate()
+            {
+                steps.push(Step::child(
+                    old_child,
+                    new_child,
+                    path,
+                    old_start + index,
+                ));
             }
         } else {
-            add_attributes.extend(new_attrs);
-        }
-    }
-
-    // if this attribute name does not exist anymore
-    // to the new element, remove it
-    for (old_attr_name, old_attrs) in old_attributes_grouped.iter() {
-        if !new_attributes_grouped.contains_key(old_attr_name) {
-            remove_attributes.extend(old_attrs);
+            steps.push(
+                Patch::change_leaf_run(
+                    path.traverse(old_start),
+                    old_len,
+                    new_run.iter().filter_map(|new_child| new_child.leaf()),
+                )
+                .into(),
+            );
         }
     }
-
-    if !add_attributes.is_empty() {
-        patches.push(Patch::add_attributes(
-            &old_element.tag,
-            path.clone(),
-            add_attributes,
-        ));
-    }
-    if !remove_attributes.is_empty() {
-        patches.push(Patch::remove_attributes(
-            &old_element.tag,
-            path.clone(),
-            remove_attributes,
-        ));
-    }
-    patches
+    Some(steps)
 }
@@ -0,0 +1,250 @@
+//! diffing the attributes of the elements, for the frameworks which reconcile
+//! the children of their components themselves but still diff the attributes
+//! of the boundary elements the same as the differ
+use super::DiffOptions;
+use crate::{
+    node::attribute::group_attributes_per_name, Attribute, Element, Patch,
+    PatchType, TreePath,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::mem;
+
+/// Return the patches of the attributes which are added, changed and removed
+/// from `old_element` to `new_element` at `path`, the same as the patches of the attributes
+/// of the elements which are diffed by `diff_with_key`. The children are not diffed.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::attributes::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("div", vec![attr("class", "a"), attr("id", "x")], vec![]);
+/// let new: MyNode = element("div", vec![attr("class", "b")], vec![leaf("ignored")]);
+///
+/// let patches = create_attribute_patches(
+///     old.element_ref().unwrap(),
+///     new.element_ref().unwrap(),
+///     &TreePath::root(),
+/// );
+/// assert_eq!(
+///     patches.iter().map(Patch::kind).collect::<Vec<_>>(),
+///     [PatchKind::AddAttributes, PatchKind::RemoveAttributes]
+/// );
+/// ```
+pub fn create_attribute_patches<'a, Ns, Tag, Leaf, Att, Val>(
+    old_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    new_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    attribute_patches(old_element, new_element, path, &DiffOptions::default())
+}
+
+/// `create_attribute_patches` using the `options` of the differ, such as the
+/// `attr_differ` and `update_attributes`. The `path` is used as it is,
+/// without the `path_prefix` of the `options`.
+pub fn create_attribute_patches_with_options<'a, Ns, Tag, Leaf, Att, Val>(
+    old_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    new_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let patches = attribute_patches(old_element, new_element, path, options);
+    if options.update_attributes {
+        group_attribute_updates(patches)
+    } else {
+        patches
+    }
+}
+
+/// the patches of the attributes of the elements, where the `AddAttributes` and
+/// `RemoveAttributes` patches are grouped later for the whole tree
+///
+/// Note: The performance bottlenecks
+///     - allocating new vec
+///     - merging attributes of the same name
+pub(crate) fn attribute_patches<'a, Ns, Tag, Leaf, Att, Val>(
+    old_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    new_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let new_attributes = new_element.attributes();
+    let old_attributes = old_element.attributes();
+
+    // skip diffing if they the same attributes
+    if old_attributes == new_attributes {
+        return vec![];
+    }
+    let mut patches = vec![];
+
+    // the small elements are diffed without grouping their attributes
+    let (mut add_attributes, remove_attributes) =
+        super::small::diff_small_attributes::<{ super::small::SMALL }, _, _, _>(
+            old_attributes,
+            new_attributes,
+        )
+        .unwrap_or_else(|| {
+            grouped_attribute_changes(old_attributes, new_attributes)
+        });
+
+    // the changed attributes whose parts are returned by the `attr_differ`
+    let mut part_patches = vec![];
+    if let Some(attr_differ) = options.attr_differ {
+        let single = |attributes: &'a [Attribute<Ns, Att, Val>], name: &Att| {
+            let mut named = attributes.iter().filter(|attr| attr.name == *name);
+            match (named.next(), named.next()) {
+                (Some(attr), None) => Some(attr),
+                _ => None,
+            }
+        };
+        add_attributes.retain(|new_attr| {
+            let parts = single(old_attributes, &new_attr.name)
+                .filter(|_| single(new_attributes, &new_attr.name).is_some())
+                .and_then(|old_attr| attr_differ(old_attr, new_attr));
+            match parts {
+                Some(parts) => {
+                    part_patches.push(Patch::update_attribute_parts(
+                        &old_element.tag,
+                        path.clone(),
+                        new_attr,
+                        parts,
+                    ));
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+
+    if !add_attributes.is_empty() {
+        patches.push(Patch::add_attributes(
+            &old_element.tag,
+            path.clone(),
+            add_attributes,
+        ));
+    }
+    if !remove_attributes.is_empty() {
+        patches.push(Patch::remove_attributes(
+            &old_element.tag,
+            path.clone(),
+            remove_attributes,
+        ));
+    }
+    patches.extend(part_patches);
+    patches
+}
+
+/// merge the `AddAttributes` patch which is followed by a `RemoveAttributes` patch
+/// of the same element into a single `UpdateAttributes` patch
+pub(crate) fn group_attribute_updates<'a, Ns, Tag, Leaf, Att, Val>(
+    patches: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut grouped: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>> =
+        Vec::with_capacity(patches.len());
+    for patch in patches {
+        if let Some(last) = grouped.last_mut() {
+            if last.patch_path == patch.patch_path {
+                if let (
+                    PatchType::AddAttributes { attrs: add },
+                    PatchType::RemoveAttributes { attrs: remove },
+                ) = (&mut last.patch_type, &patch.patch_type)
+                {
+                    last.patch_type = PatchType::UpdateAttributes {
+                        add: mem::take(add),
+                        remove: remove.clone(),
+                    };
+                    continue;
+                }
+            }
+        }
+        grouped.push(patch);
+    }
+    grouped
+}
+
+/// return the attributes to be added and removed by grouping the attributes per name
+fn grouped_attribute_changes<'a, Ns, Att, Val>(
+    old_attributes: &'a [Attribute<Ns, Att, Val>],
+    new_attributes: &'a [Attribute<Ns, Att, Val>],
+) -> (
+    Vec<&'a Attribute<Ns, Att, Val>>,
+    Vec<&'a Attribute<Ns, Att, Val>>,
+)
+where
+    Ns: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut add_attributes: Vec<&Attribute<Ns, Att, Val>> = vec![];
+    let mut remove_attributes: Vec<&Attribute<Ns, Att, Val>> = vec![];
+
+    let new_attributes_grouped = group_attributes_per_name(new_attributes);
+    let old_attributes_grouped = group_attributes_per_name(old_attributes);
+
+    // for all new elements that doesn't exist in the old elements
+    // or the values differ
+    // add it to the AddAttribute patches
+    for (new_attr_name, new_attrs) in new_attributes_grouped.iter() {
+        let old_attr_values =
+            old_attributes_grouped.get(new_attr_name).map(|attrs| {
+                attrs.iter().map(|attr| &attr.value).collect::<Vec<_>>()
+            });
+
+        let new_attr_values =
+            new_attributes_grouped.get(new_attr_name).map(|attrs| {
+                attrs.iter().map(|attr| &attr.value).collect::<Vec<_>>()
+            });
+
+        if let Some(old_attr_values) = old_attr_values {
+            let new_attr_values =
+                new_attr_values.expect("must have new attr values");
+            if old_attr_values != new_attr_values {
+                add_attributes.extend(new_attrs);
+            }
+        } else {
+            add_attributes.extend(new_attrs);
+        }
+    }
+
+    // if this attribute name does not exist anymore
+    // to the new element, remove it
+    for (old_attr_name, old_attrs) in old_attributes_grouped.iter() {
+        if !new_attributes_grouped.contains_key(old_attr_name) {
+            remove_attributes.extend(old_attrs);
+        }
+    }
+    (add_attributes, remove_attributes)
+}
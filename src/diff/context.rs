@@ -0,0 +1,245 @@
+//! interning of the attribute values of the patches in a diff session
+use super::{diff_with_options, DiffOptions};
+use crate::{Node, Patch, PatchType};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use std::collections::HashMap;
+
+/// A diff session which interns the attribute values of the emitted patches,
+/// so the repeated values, such as the same class string on thousands of rows,
+/// are assigned the same index and serializers can encode each of them just once.
+///
+/// The values of the added attributes and the attributes of the inserted or replacement
+/// nodes are interned, the values of the removed attributes are not since only their names
+/// are needed to remove them.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("ul", vec![], vec![]);
+/// let new: MyNode = element("ul", vec![], (0..3).map(|_| {
+///     element("li", vec![attr("class", "row")], vec![])
+/// }));
+///
+/// let mut context = DiffContext::new();
+/// let patches = context.diff(&old, &new, &"key");
+/// assert_eq!(patches.len(), 1);
+/// assert_eq!(context.values(), &[&"row"]);
+/// assert_eq!(context.index_of(&"row"), Some(0));
+/// assert_eq!(context.stats(), InternStats { total: 3, unique: 1 });
+/// ```
+#[derive(Debug)]
+pub struct DiffContext<'a, Val>
+where
+    Val: PartialEq + Eq + Hash + Clone + Debug,
+{
+    /// the index of each distinct value in `values`
+    indexes: HashMap<&'a Val, usize>,
+    /// the distinct values in the order they are first interned
+    values: Vec<&'a Val>,
+    /// the number of values interned, including the repeated ones
+    total: usize,
+}
+
+/// The statistics of the values interned by a `DiffContext`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InternStats {
+    /// the number of values interned, including the repeated ones
+    pub total: usize,
+    /// the number of distinct values
+    pub unique: usize,
+}
+
+impl InternStats {
+    /// the fraction of the interned values which are repeated values,
+    /// 0.0 when all of the values are distinct or when there are no values
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.total - self.unique) as f64 / self.total as f64
+        }
+    }
+}
+
+impl<'a, Val> DiffContext<'a, Val>
+where
+    Val: PartialEq + Eq + Hash + Clone + Debug,
+{
+    /// create a diff session with no interned values
+    pub fn new() -> Self {
+        Self {
+            indexes: HashMap::new(),
+            values: vec![],
+            total: 0,
+        }
+    }
+
+    /// diff the nodes the same as `diff_with_key` and intern the values of the patches
+    pub fn diff<Ns, Tag, Leaf, Att>(
+        &mut self,
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        key: &Att,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+    {
+        self.diff_with_options(old_node, new_node, key, &DiffOptions::default())
+    }
+
+    /// diff the nodes the same as `diff_with_options` and intern the values of the patches
+    pub fn diff_with_options<Ns, Tag, Leaf, Att>(
+        &mut self,
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        key: &Att,
+        options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+    {
+        let patches = diff_with_options(old_node, new_node, key, options);
+        self.intern_patches(&patches);
+        patches
+    }
+
+    /// intern the values of the added attributes and the inserted nodes of the patches
+    pub fn intern_patches<Ns, Tag, Leaf, Att>(
+        &mut self,
+        patches: &[Patch<'a, Ns, Tag, Leaf, Att, Val>],
+    ) where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+    {
+        for patch in patches {
+            self.intern_patch_type(&patch.patch_type);
+        }
+    }
+
+    fn intern_patch_type<Ns, Tag, Leaf, Att>(
+        &mut self,
+        patch_type: &PatchType<'a, Ns, Tag, Leaf, Att, Val>,
+    ) where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+    {
+        match patch_type {
+            PatchType::InsertBeforeNode { nodes }
+            | PatchType::InsertAfterNode { nodes }
+            | PatchType::AppendChildren { children: nodes }
+            | PatchType::ReplaceNode { replacement: nodes } => {
+                for node in nodes {
+                    self.intern_node(node);
+                }
+            }
+            PatchType::WrapNode { wrapper } => self.intern_node(wrapper),
+            PatchType::SetSlot { node, .. } => self.intern_node(node),
+            PatchType::ForEachChild { patch }
+            | PatchType::Broadcast { patch, .. } => {
+                self.intern_patch_type(patch)
+            }
+            PatchType::AddAttributes { attrs }
+            | PatchType::UpdateAttributes { add: attrs, .. } => {
+                for attr in attrs {
+                    for value in attr.value() {
+                        self.intern(value);
+                    }
+                }
+            }
+            PatchType::UpdateAttributeParts { attr, .. } => {
+                for value in attr.value() {
+                    self.intern(value);
+                }
+            }
+            PatchType::RemoveNode
+            | PatchType::MoveBeforeNode { .. }
+            | PatchType::MoveAfterNode { .. }
+            | PatchType::MoveIntoNode { .. }
+            | PatchType::UnwrapNode
+            | PatchType::ChangeLeaf { .. }
+            | PatchType::SpliceLeaf { .. }
+            | PatchType::ChangeTag { .. }
+            | PatchType::SyncIsland
+            | PatchType::ChangeLeafRun { .. }
+            | PatchType::RemoveAttributes { .. }
+            | PatchType::AdoptNode { .. }
+            | PatchType::ClearSlot { .. } => (),
+        }
+    }
+
+    /// intern the attribute values of the node and its descendants
+    fn intern_node<Ns, Tag, Leaf, Att>(
+        &mut self,
+        node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+    {
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            for attr in node.attributes().unwrap_or(&[]) {
+                for value in attr.value() {
+                    self.intern(value);
+                }
+            }
+            stack.extend(node.child_nodes().iter().rev());
+        }
+    }
+
+    /// intern the value, returning the index of the value in `values`
+    pub fn intern(&mut self, value: &'a Val) -> usize {
+        self.total += 1;
+        let next = self.values.len();
+        let index = *self.indexes.entry(value).or_insert(next);
+        if index == next {
+            self.values.push(value);
+        }
+        index
+    }
+
+    /// return the index of the interned value which is equal to `value`
+    pub fn index_of(&self, value: &Val) -> Option<usize> {
+        self.indexes.get(value).copied()
+    }
+
+    /// the distinct values in the order they are first interned
+    pub fn values(&self) -> &[&'a Val] {
+        &self.values
+    }
+
+    /// the number of the interned and the distinct values
+    pub fn stats(&self) -> InternStats {
+        InternStats {
+            total: self.total,
+            unique: self.values.len(),
+        }
+    }
+}
+
+impl<'a, Val> Default for DiffContext<'a, Val>
+where
+    Val: PartialEq + Eq + Hash + Clone + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
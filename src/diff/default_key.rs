@@ -0,0 +1,39 @@
+use alloc::string::{String, ToString};
+use std::sync::RwLock;
+
+/// the name of the key attribute used by `diff` when it is not configured
+const KEY: &str = "key";
+
+static DEFAULT_KEY: RwLock<&'static str> = RwLock::new(KEY);
+
+/// Set the name of the key attribute used by `diff`, the default is `"key"`
+///
+/// This is useful for hosts that can not use a literal `key` attribute, such as
+/// `data-key` in html.
+pub fn set_default_key(key: &'static str) {
+    *DEFAULT_KEY.write().expect("must acquire the default key") = key;
+}
+
+/// return the name of the key attribute used by `diff`
+pub fn default_key() -> &'static str {
+    *DEFAULT_KEY.read().expect("must acquire the default key")
+}
+
+/// Attribute names which can be created from the configured default key,
+/// see `set_default_key`.
+pub trait DefaultKey {
+    /// return the name of the key attribute that is used by `diff`
+    fn default_key() -> Self;
+}
+
+impl DefaultKey for &'static str {
+    fn default_key() -> Self {
+        default_key()
+    }
+}
+
+impl DefaultKey for String {
+    fn default_key() -> Self {
+        default_key().to_string()
+    }
+}
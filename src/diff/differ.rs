@@ -0,0 +1,104 @@
+//! a differ which reuses its buffers across the diffs
+use super::{diff_with_options, DiffOptions, DiffScratch};
+use crate::{Node, Patch};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// A differ which owns the scratch buffers of the keyed differ, such as the matched indexes
+/// and the buffers of the longest increasing subsequence, so diffing the same component on
+/// every frame reuses them instead of allocating them for every list of children.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let list = |keys: &[&'static str]| -> MyNode {
+///     element("ul", vec![], keys.iter().map(|key| element("li", vec![attr("key", *key)], vec![])))
+/// };
+/// let frames = [list(&["1", "2", "3"]), list(&["3", "1", "2"]), list(&["2", "3", "1"])];
+/// let mut differ = Differ::new("key");
+/// for pair in frames.windows(2) {
+///     assert_eq!(differ.diff(&pair[0], &pair[1]), diff_with_key(&pair[0], &pair[1], &"key"));
+/// }
+/// #[cfg(feature = "keyed")]
+/// assert!(differ.allocated_bytes() > 0);
+/// ```
+#[derive(Debug)]
+pub struct Differ<Att>
+where
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+{
+    /// the literal name of the key attribute
+    key: Att,
+    /// the buffers which are shared with the keyed differ through `DiffOptions::scratch`
+    scratch: RefCell<DiffScratch>,
+}
+
+impl<Att> Differ<Att>
+where
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+{
+    /// create a differ with empty buffers, which are allocated on their first use
+    pub fn new(key: Att) -> Self {
+        Self {
+            key,
+            scratch: RefCell::new(DiffScratch::new()),
+        }
+    }
+
+    /// the literal name of the key attribute
+    pub fn key(&self) -> &Att {
+        &self.key
+    }
+
+    /// diff the nodes the same as `diff_with_key`, reusing the buffers
+    pub fn diff<'a, Ns, Tag, Leaf, Val>(
+        &mut self,
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        self.diff_with_options(old_node, new_node, &DiffOptions::default())
+    }
+
+    /// diff the nodes the same as `diff_with_options`, reusing the buffers.
+    /// The buffers of the differ are used instead of `options.scratch`
+    pub fn diff_with_options<'a, Ns, Tag, Leaf, Val>(
+        &mut self,
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        let options = DiffOptions {
+            scratch: Some(&self.scratch),
+            ..options.clone()
+        };
+        diff_with_options(old_node, new_node, &self.key, &options)
+    }
+
+    /// the number of bytes allocated by the buffers
+    pub fn allocated_bytes(&self) -> usize {
+        self.scratch.borrow().allocated_bytes()
+    }
+
+    /// release the memory of the buffers
+    pub fn clear(&mut self) {
+        self.scratch.get_mut().clear();
+    }
+}
@@ -0,0 +1,51 @@
+//! diffing against a previous node tree which is kept in its encoded form
+use super::diff_with_key;
+use crate::codec::{decode_node, Decode, DecodeError};
+use crate::{Node, Patch};
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// Diff the `new_node` against the previous node tree which is encoded with
+/// `codec::encode_node`, so memory-constrained servers can keep just the encoded bytes
+/// of the previous tree between the requests instead of the full tree.
+///
+/// This only saves the memory between the diffs: the whole old tree is decoded for
+/// each diff, so the memory at its peak holds both of the trees, the same as `diff`,
+/// along with the encoded bytes. The old tree is dropped right after `f` is called
+/// with the patches, since the patches borrows from both of the old and the new tree.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{codec::*, diff::*, *};
+///
+/// pub type MyNode = Node<String, String, String, String, String>;
+///
+/// let view = |text: &str| -> MyNode {
+///     element("main".to_string(), vec![], vec![leaf(text.to_string())])
+/// };
+/// let old_bytes = encode_node(&view("hello"));
+///
+/// let new = view("world");
+/// let count = diff_against_encoded(&old_bytes, &new, &"key".to_string(), |patches| {
+///     patches.len()
+/// });
+/// assert_eq!(count, Ok(1));
+/// ```
+pub fn diff_against_encoded<Ns, Tag, Leaf, Att, Val, R>(
+    old_bytes: &[u8],
+    new_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    key: &Att,
+    f: impl FnOnce(Vec<Patch<'_, Ns, Tag, Leaf, Att, Val>>) -> R,
+) -> Result<R, DecodeError>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    let old_node = decode_node(old_bytes)?;
+    let patches = diff_with_key(&old_node, new_node, key);
+    Ok(f(patches))
+}
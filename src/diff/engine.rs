@@ -0,0 +1,290 @@
+//! a long-lived differ which keeps its caches across the diffs
+use super::{DiffContext, DiffOptions, DiffStats, Differ, ListStrategy};
+use crate::{patch::PathIndex, Node, Patch, TreePath};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use core::hash::{BuildHasher, Hash};
+use core::mem;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+
+/// the end of a chain of the interned values with the same hash
+const NO_VALUE: usize = usize::MAX;
+
+/// A differ which owns its caches across the diffs, with an explicit memory limit.
+///
+/// This is the recommended entry point for the frameworks which diff every frame of a
+/// long running application or serve the diffs of many sessions. Unlike the free functions,
+/// which start from scratch on every call, the engine keeps:
+/// - the interned attribute values of the patches, so the indices stay the same across the
+///   diffs and serializers only need to send the values which are new since the last frame
+/// - the scratch buffers of the keyed differ, with a `Differ`
+/// - the `DiffStats` of the diffs, when enabled with `with_stats`
+///
+/// The caches are cleared before a diff when they exceed the memory limit, and once more
+/// after the values of a diff are interned when they exceed it again, keeping only the values
+/// of the latest patches, so their indices are always valid. `generation` is incremented
+/// whenever the caches are cleared, which tells the serializers to resend their value tables.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let mut engine = DiffEngine::new("key").with_memory_limit(64 * 1024);
+///
+/// let old: MyNode = element("ul", vec![], vec![]);
+/// let new: MyNode = element("ul", vec![], vec![element("li", vec![attr("class", "row")], vec![])]);
+/// let patches = engine.diff(&old, &new);
+/// assert_eq!(patches.len(), 1);
+/// assert_eq!(engine.index_of(&"row"), Some(0));
+///
+/// let newer: MyNode = element("ul", vec![], vec![element("li", vec![attr("class", "done")], vec![])]);
+/// engine.diff(&new, &newer);
+/// // the values interned by the previous diffs keep their indices
+/// assert_eq!(engine.values(), &["row", "done"]);
+///
+/// engine.clear();
+/// assert!(engine.values().is_empty());
+/// assert_eq!(engine.generation(), 1);
+/// ```
+#[derive(Debug)]
+pub struct DiffEngine<Att, Val>
+where
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Eq + Hash + Clone + Debug,
+{
+    /// the differ which owns the scratch buffers
+    differ: Differ<Att>,
+    /// the index of the last interned value of each hash of the values,
+    /// so the values are only stored in `values`
+    indexes: HashMap<u64, usize>,
+    /// the index of the previous interned value with the same hash as each value,
+    /// or `NO_VALUE`
+    same_hash: Vec<usize>,
+    /// the hasher of the values
+    hasher: RandomState,
+    /// the distinct values in the order they are first interned
+    values: Vec<Val>,
+    /// the number of bytes of the heap data owned by the interned values,
+    /// as counted by `heap_size`
+    heap_bytes: usize,
+    /// the number of bytes of the heap data owned by a value, such as the contents
+    /// of a `String`
+    heap_size: Option<fn(&Val) -> usize>,
+    /// the statistics of the diffs since the last clear, when enabled
+    stats: Option<DiffStats>,
+    /// the number of bytes the caches may use before they are cleared
+    memory_limit: Option<usize>,
+    /// the number of times the caches were cleared
+    generation: u64,
+}
+
+impl<Att, Val> DiffEngine<Att, Val>
+where
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Eq + Hash + Clone + Debug,
+{
+    /// create an engine with empty caches and no memory limit
+    pub fn new(key: Att) -> Self {
+        Self {
+            differ: Differ::new(key),
+            indexes: HashMap::new(),
+            same_hash: Vec::new(),
+            hasher: RandomState::new(),
+            values: Vec::new(),
+            heap_bytes: 0,
+            heap_size: None,
+            stats: None,
+            memory_limit: None,
+            generation: 0,
+        }
+    }
+
+    /// clear the caches before a diff once they use more than `bytes`, as estimated
+    /// by `memory_usage`
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// count the heap data owned by the interned values in `memory_usage`
+    /// with `heap_size`, such as `|value: &String| value.capacity()`
+    pub fn with_heap_size(mut self, heap_size: fn(&Val) -> usize) -> Self {
+        self.heap_size = Some(heap_size);
+        self
+    }
+
+    /// collect the `DiffStats` of the diffs, which are kept until the caches are cleared
+    pub fn with_stats(mut self, stats: DiffStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// the literal name of the key attribute
+    pub fn key(&self) -> &Att {
+        self.differ.key()
+    }
+
+    /// the number of bytes the caches may use before they are cleared
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// the number of times the caches were cleared, either with `clear`
+    /// or by exceeding the memory limit
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// diff the nodes the same as `diff_with_key` and intern the values of the patches
+    pub fn diff<'a, Ns, Tag, Leaf>(
+        &mut self,
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+    {
+        self.diff_with_options(old_node, new_node, &DiffOptions::default())
+    }
+
+    /// diff the nodes the same as `diff_with_options` and intern the values of the patches.
+    /// The statistics of the engine are used instead of `options.stats`
+    /// when the engine collects them.
+    pub fn diff_with_options<'a, Ns, Tag, Leaf>(
+        &mut self,
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+    {
+        if self.is_over_memory_limit() {
+            self.clear();
+        }
+        let patches = match self.stats.as_mut() {
+            Some(stats) => {
+                let cell = RefCell::new(mem::take(stats));
+                let options = DiffOptions {
+                    stats: Some(&cell),
+                    ..options.clone()
+                };
+                let patches =
+                    self.differ.diff_with_options(old_node, new_node, &options);
+                *stats = cell.into_inner();
+                patches
+            }
+            None => self.differ.diff_with_options(old_node, new_node, options),
+        };
+        let mut context = DiffContext::new();
+        context.intern_patches(&patches);
+        for value in context.values() {
+            self.intern(value);
+        }
+        // only the values of the latest patches are kept when they exceed the limit
+        if self.is_over_memory_limit() {
+            self.clear();
+            for value in context.values() {
+                self.intern(value);
+            }
+        }
+        patches
+    }
+
+    fn is_over_memory_limit(&self) -> bool {
+        self.memory_limit
+            .is_some_and(|limit| self.memory_usage() > limit)
+    }
+
+    /// intern the value, returning its index in `values`
+    pub fn intern(&mut self, value: &Val) -> usize {
+        let hash = self.hasher.hash_one(value);
+        if let Some(index) = self.find(hash, value) {
+            return index;
+        }
+        let index = self.values.len();
+        let previous = self.indexes.insert(hash, index);
+        self.same_hash.push(previous.unwrap_or(NO_VALUE));
+        self.heap_bytes +=
+            self.heap_size.map_or(0, |heap_size| heap_size(value));
+        self.values.push(value.clone());
+        index
+    }
+
+    /// return the index of the interned value which is equal to `value`
+    pub fn index_of(&self, value: &Val) -> Option<usize> {
+        self.find(self.hasher.hash_one(value), value)
+    }
+
+    /// find the value among the interned values with the same hash
+    fn find(&self, hash: u64, value: &Val) -> Option<usize> {
+        let mut index = self.indexes.get(&hash).copied().unwrap_or(NO_VALUE);
+        while index != NO_VALUE {
+            if self.values[index] == *value {
+                return Some(index);
+            }
+            index = self.same_hash[index];
+        }
+        None
+    }
+
+    /// the distinct values interned since the last clear, in the order they are first interned
+    pub fn values(&self) -> &[Val] {
+        &self.values
+    }
+
+    /// the statistics of the diffs since the last clear, None when not collected
+    pub fn stats(&self) -> Option<&DiffStats> {
+        self.stats.as_ref()
+    }
+
+    /// An estimate of the number of bytes used by the caches and the scratch buffers.
+    /// The heap data owned by the interned values, such as the contents of a `String`,
+    /// is only counted with `with_heap_size`, otherwise only the sizes of the values
+    /// themselves are counted.
+    pub fn memory_usage(&self) -> usize {
+        let values = self.values.capacity() * mem::size_of::<Val>()
+            + self.heap_bytes
+            + self.same_hash.capacity() * mem::size_of::<usize>()
+            + self.indexes.capacity()
+                * (mem::size_of::<u64>() + mem::size_of::<usize>());
+        let stats = self.stats.as_ref().map_or(0, |stats| {
+            stats.lists.capacity() * mem::size_of::<(TreePath, ListStrategy)>()
+                + stats
+                    .lists
+                    .iter()
+                    .map(|(path, _)| {
+                        path.path.capacity() * mem::size_of::<PathIndex>()
+                    })
+                    .sum::<usize>()
+        });
+        values + stats + self.differ.allocated_bytes()
+    }
+
+    /// drop the contents of the caches and increment the generation,
+    /// the statistics are reset but are still collected
+    pub fn clear(&mut self) {
+        self.indexes = HashMap::new();
+        self.same_hash = Vec::new();
+        self.values = Vec::new();
+        self.heap_bytes = 0;
+        self.differ.clear();
+        if let Some(stats) = self.stats.as_mut() {
+            *stats = if stats.profile.is_some() {
+                DiffStats::with_profile()
+            } else {
+                DiffStats::default()
+            };
+        }
+        self.generation += 1;
+    }
+}
@@ -0,0 +1,325 @@
+//! finding whether the subtrees of the old and new nodes are equal,
+//! where each node is compared at most once in the whole diff
+use super::work::Step;
+use super::{DiffOptions, EqualityCheck};
+use crate::Node;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::ptr;
+
+/// What is known about the equality of the subtrees of a pair of old and new nodes.
+///
+/// When the subtrees of a pair are not equal, the walk which compared them stopped at
+/// the first difference, so the children before the path of the difference are known
+/// to be equal, and the child on the path is known to be unequal.
+/// These are passed to the steps of the children, so their subtrees are not compared again,
+/// which would make the comparisons quadratic in the depth of the trees.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) enum Equality {
+    /// the subtrees are not compared yet
+    #[default]
+    Unknown,
+    /// the subtrees are equal
+    Equal,
+    /// the subtrees are not equal, with the indices of the children down to the first
+    /// pair of nodes which differ, which is empty when the nodes themselves differ
+    /// or the position of the difference is not known, such as with the fingerprints
+    Unequal(Vec<usize>),
+}
+
+impl Equality {
+    /// return true if the subtrees of the nodes are equal, comparing them if it is not
+    /// known yet, using their fingerprints when both of them have one
+    pub(crate) fn resolve<Ns, Tag, Leaf, Att, Val>(
+        &mut self,
+        old_node: &Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &Node<Ns, Tag, Leaf, Att, Val>,
+        options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    ) -> bool
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        if *self == Equality::Unknown {
+            *self = match options.fingerprints_equal(old_node, new_node) {
+                Some(true) => Equality::Equal,
+                Some(false) => Equality::Unequal(vec![]),
+                None => {
+                    let mut budget = usize::MAX;
+                    Comparison::new(old_node, new_node)
+                        .advance(&mut budget, options)
+                        .expect("the comparison is not limited")
+                }
+            };
+        }
+        *self == Equality::Equal
+    }
+
+    /// resolve the equality of the subtrees of the nodes at `depth` ahead of `expand_node`
+    /// when it would compare them, comparing at most `budget` pairs of nodes, which are subtracted
+    /// from the budget. Return false when the budget runs out before the comparison
+    /// is finished, which is then continued from `comparison` with the next budget.
+    pub(crate) fn resolve_within<'a, Ns, Tag, Leaf, Att, Val>(
+        &mut self,
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        depth: usize,
+        comparison: &mut Option<Comparison<'a, Ns, Tag, Leaf, Att, Val>>,
+        budget: &mut usize,
+        options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    ) -> bool
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        // without the equality check, the subtrees are only compared
+        // when they are replaced at the maximum depth
+        let is_compared = options.equality_check != EqualityCheck::Never
+            || options
+                .max_depth
+                .is_some_and(|max_depth| depth >= max_depth);
+        if *self != Equality::Unknown
+            || !is_compared
+            || ptr::eq(old_node, new_node)
+            || options.fingerprints_equal(old_node, new_node).is_some()
+        {
+            return true;
+        }
+        let mut current = comparison
+            .take()
+            .filter(|comparison| comparison.is_of(old_node, new_node))
+            .unwrap_or_else(|| Comparison::new(old_node, new_node));
+        match current.advance(budget, options) {
+            Some(equality) => {
+                *self = equality;
+                true
+            }
+            None => {
+                *comparison = Some(current);
+                false
+            }
+        }
+    }
+
+    /// pass what is known about the pairs of children to the steps which diff them,
+    /// only the pairs of children at the same position are known
+    pub(crate) fn hint_children<'a, Ns, Tag, Leaf, Att, Val>(
+        &self,
+        steps: &mut [Step<'a, Ns, Tag, Leaf, Att, Val>],
+        old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+        new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    ) where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        let Equality::Unequal(path) = self else {
+            return;
+        };
+        let Some((&first, rest)) = path.split_first() else {
+            return;
+        };
+        let mut pending = vec![steps];
+        while let Some(steps) = pending.pop() {
+            for step in steps.iter_mut() {
+                match step {
+                    Step::Diff {
+                        old,
+                        new,
+                        index,
+                        equality,
+                        ..
+                    } => {
+                        let at = *index;
+                        let is_positional = at <= first
+                            && old_children
+                                .get(at)
+                                .zip(new_children.get(at))
+                                .is_some_and(|(old_child, new_child)| {
+                                    ptr::eq(*old, old_child)
+                                        && ptr::eq(*new, new_child)
+                                });
+                        if is_positional {
+                            *equality = if at < first {
+                                Equality::Equal
+                            } else {
+                                Equality::Unequal(rest.to_vec())
+                            };
+                        }
+                    }
+                    Step::Group { steps, .. } => pending.push(steps),
+                    Step::Patch(_) => (),
+                }
+            }
+        }
+    }
+}
+
+/// The comparison of the subtrees of a pair of old and new nodes which can be
+/// advanced a bounded number of pairs of nodes at a time, so a `DiffStepper` does not
+/// compare a large subtree in a single step.
+///
+/// The subtrees are compared in the same order as their `PartialEq`, finding the indices
+/// of the children down to the first pair of nodes which differ.
+pub(crate) struct Comparison<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    /// the children of the pairs which are being compared,
+    /// with the index of the next pair of children to compare
+    levels: Vec<(
+        &'a [Node<Ns, Tag, Leaf, Att, Val>],
+        &'a [Node<Ns, Tag, Leaf, Att, Val>],
+        usize,
+    )>,
+    next: Option<(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    )>,
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> Comparison<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// start the comparison of the subtrees, nothing is compared until it is advanced
+    pub(crate) fn new(
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> Self {
+        Self {
+            old_node,
+            new_node,
+            levels: vec![],
+            next: Some((old_node, new_node)),
+        }
+    }
+
+    /// whether this is the comparison of the subtrees of the nodes
+    pub(crate) fn is_of(
+        &self,
+        old_node: &Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool {
+        ptr::eq(self.old_node, old_node) && ptr::eq(self.new_node, new_node)
+    }
+
+    /// compare at most `budget` more pairs of nodes, subtracting them from the budget
+    /// and counting them in `DiffStats::nodes_compared`, and return the equality of
+    /// the subtrees once all of the pairs are compared or the first difference is found
+    pub(crate) fn advance(
+        &mut self,
+        budget: &mut usize,
+        options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    ) -> Option<Equality> {
+        let mut compared = 0;
+        let equality = self.compare(*budget, &mut compared);
+        *budget -= compared;
+        if let Some(stats) = options.stats {
+            stats.borrow_mut().nodes_compared += compared;
+        }
+        equality
+    }
+
+    fn compare(
+        &mut self,
+        max_nodes: usize,
+        compared: &mut usize,
+    ) -> Option<Equality> {
+        loop {
+            if let Some((old, new)) = self.next.take() {
+                if *compared >= max_nodes {
+                    self.next = Some((old, new));
+                    return None;
+                }
+                *compared += 1;
+                let Some((old_children, new_children)) =
+                    shallow_children(old, new)
+                else {
+                    // the index of each level is past the child which is compared
+                    return Some(Equality::Unequal(
+                        self.levels
+                            .iter()
+                            .map(|(_, _, index)| index - 1)
+                            .collect(),
+                    ));
+                };
+                self.levels.push((old_children, new_children, 0));
+            }
+            let Some((old_children, new_children, index)) =
+                self.levels.last_mut()
+            else {
+                return Some(Equality::Equal);
+            };
+            if *index < old_children.len() {
+                self.next =
+                    Some((&old_children[*index], &new_children[*index]));
+                *index += 1;
+            } else {
+                self.levels.pop();
+                if self.levels.is_empty() {
+                    return Some(Equality::Equal);
+                }
+            }
+        }
+    }
+}
+
+/// the children of the nodes when the nodes are equal without their children,
+/// and they have the same number of children
+fn shallow_children<'n, Ns, Tag, Leaf, Att, Val>(
+    old: &'n Node<Ns, Tag, Leaf, Att, Val>,
+    new: &'n Node<Ns, Tag, Leaf, Att, Val>,
+) -> Option<(
+    &'n [Node<Ns, Tag, Leaf, Att, Val>],
+    &'n [Node<Ns, Tag, Leaf, Att, Val>],
+)>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let (old_children, new_children) = match (old, new) {
+        (Node::Element(old), Node::Element(new)) => {
+            if old.namespace != new.namespace
+                || old.tag != new.tag
+                || old.attrs != new.attrs
+                || old.self_closing != new.self_closing
+                || old.slots != new.slots
+            {
+                return None;
+            }
+            (&old.children, &new.children)
+        }
+        (Node::NodeList(old), Node::NodeList(new))
+        | (Node::Fragment(old), Node::Fragment(new)) => (old, new),
+        (Node::Leaf(old), Node::Leaf(new)) => {
+            return (old == new).then_some((&[][..], &[][..]));
+        }
+        _ => return None,
+    };
+    (old_children.len() == new_children.len())
+        .then_some((old_children.as_slice(), new_children.as_slice()))
+}
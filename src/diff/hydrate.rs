@@ -0,0 +1,123 @@
+//! diffing only the behavioral attributes against a pre-rendered tree
+use crate::{Attribute, Node, Patch, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// return the patches of the attributes which match `is_hydrated`, such as the event
+/// listeners, trusting the old node for the structure, the leaves and the other attributes.
+/// The children are paired by their position, the extra children of either side are skipped.
+pub(crate) fn hydrate_node<'a, Ns, Tag, Leaf, Att, Val>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+    is_hydrated: &dyn Fn(&Att) -> bool,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut patches = vec![];
+    match (old_node, new_node) {
+        (Node::Element(old_element), Node::Element(new_element)) => {
+            let (add_attributes, remove_attributes) =
+                hydrated_attribute_changes(
+                    old_element.attributes(),
+                    new_element.attributes(),
+                    is_hydrated,
+                );
+            if !add_attributes.is_empty() {
+                patches.push(Patch::add_attributes(
+                    &old_element.tag,
+                    path.clone(),
+                    add_attributes,
+                ));
+            }
+            if !remove_attributes.is_empty() {
+                patches.push(Patch::remove_attributes(
+                    &old_element.tag,
+                    path.clone(),
+                    remove_attributes,
+                ));
+            }
+            for (idx, (old_child, new_child)) in old_element
+                .children()
+                .iter()
+                .zip(new_element.children())
+                .enumerate()
+            {
+                patches.extend(hydrate_node(
+                    old_child,
+                    new_child,
+                    &path.traverse(idx),
+                    is_hydrated,
+                ));
+            }
+        }
+        // the same as the differ, the children of a fragment are traversed from its parent
+        (Node::Fragment(old_nodes), Node::Fragment(new_nodes)) => {
+            let parent = path.backtrack();
+            for (idx, (old_child, new_child)) in
+                old_nodes.iter().zip(new_nodes).enumerate()
+            {
+                patches.extend(hydrate_node(
+                    old_child,
+                    new_child,
+                    &parent.traverse(idx),
+                    is_hydrated,
+                ));
+            }
+        }
+        _ => (),
+    }
+    patches
+}
+
+/// return the hydrated attributes to be added and removed,
+/// the attributes of the same name are added together when any of their values changed
+fn hydrated_attribute_changes<'a, Ns, Att, Val>(
+    old_attributes: &'a [Attribute<Ns, Att, Val>],
+    new_attributes: &'a [Attribute<Ns, Att, Val>],
+    is_hydrated: &dyn Fn(&Att) -> bool,
+) -> (
+    Vec<&'a Attribute<Ns, Att, Val>>,
+    Vec<&'a Attribute<Ns, Att, Val>>,
+)
+where
+    Ns: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let named = |attributes: &'a [Attribute<Ns, Att, Val>], name: &Att| {
+        attributes
+            .iter()
+            .filter(|attr| attr.name == *name)
+            .collect::<Vec<_>>()
+    };
+    let mut add_attributes = vec![];
+    let mut remove_attributes = vec![];
+    for (idx, attr) in new_attributes.iter().enumerate() {
+        // each name is checked once, at its first attribute
+        if !is_hydrated(&attr.name)
+            || new_attributes[..idx].iter().any(|a| a.name == attr.name)
+        {
+            continue;
+        }
+        let new_attrs = named(new_attributes, &attr.name);
+        if named(old_attributes, &attr.name) != new_attrs {
+            add_attributes.extend(new_attrs);
+        }
+    }
+    for attr in old_attributes {
+        if is_hydrated(&attr.name)
+            && !new_attributes.iter().any(|a| a.name == attr.name)
+        {
+            remove_attributes.push(attr);
+        }
+    }
+    (add_attributes, remove_attributes)
+}
@@ -0,0 +1,142 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "keyed")]
+use core::cmp::Ordering;
+use core::fmt;
+
+/// Attribute values which can supply a numeric key, such as database ids.
+///
+/// The keyed diffing matches the numeric keys with integer sorting instead of
+/// comparing the key values when `DiffOptions::with_numeric_keys` is used.
+/// Two values must have the same numeric key only if the values are equal.
+pub trait KeyOf {
+    /// return the numeric key of this value, None if this value is not numeric
+    fn numeric_key(&self) -> Option<u64>;
+}
+
+impl KeyOf for u64 {
+    fn numeric_key(&self) -> Option<u64> {
+        Some(*self)
+    }
+}
+
+impl KeyOf for u32 {
+    fn numeric_key(&self) -> Option<u64> {
+        Some(u64::from(*self))
+    }
+}
+
+impl KeyOf for usize {
+    fn numeric_key(&self) -> Option<u64> {
+        u64::try_from(*self).ok()
+    }
+}
+
+/// only the canonical decimal numbers are numeric keys, since "01" and "1" are different keys
+impl KeyOf for &str {
+    fn numeric_key(&self) -> Option<u64> {
+        let canonical =
+            *self == "0" || (!self.starts_with('0') && !self.starts_with('+'));
+        if canonical {
+            self.parse().ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl KeyOf for String {
+    fn numeric_key(&self) -> Option<u64> {
+        self.as_str().numeric_key()
+    }
+}
+
+/// The key of a node, which borrows the values of the key attribute when the node
+/// has a single key attribute, so the keys of large lists are collected without
+/// allocating for each of the children.
+pub(crate) enum NodeKey<'n, Val> {
+    /// the values of the only key attribute of the node
+    Values(&'n [Val]),
+    /// the values returned by `DiffOptions::key_of`, or of the repeated key attributes
+    Collected(Vec<&'n Val>),
+}
+
+impl<'n, Val> NodeKey<'n, Val> {
+    /// iterate over the values of the key
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &'n Val> + '_ {
+        let (values, collected) = match self {
+            Self::Values(values) => (*values, &[][..]),
+            Self::Collected(collected) => (&[][..], collected.as_slice()),
+        };
+        values.iter().chain(collected.iter().copied())
+    }
+
+    /// return the value of the key when it has exactly one value
+    #[cfg(feature = "keyed")]
+    pub(crate) fn single(&self) -> Option<&'n Val> {
+        let mut values = self.iter();
+        match (values.next(), values.next()) {
+            (Some(value), None) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// compare the values of the keys lexicographically using `order`
+    #[cfg(feature = "keyed")]
+    pub(crate) fn cmp_by(
+        &self,
+        other: &Self,
+        order: impl Fn(&Val, &Val) -> Ordering,
+    ) -> Ordering {
+        let mut values = self.iter();
+        let mut others = other.iter();
+        loop {
+            match (values.next(), others.next()) {
+                (Some(value), Some(other)) => match order(value, other) {
+                    Ordering::Equal => continue,
+                    ordering => return ordering,
+                },
+                (Some(_), None) => return Ordering::Greater,
+                (None, Some(_)) => return Ordering::Less,
+                (None, None) => return Ordering::Equal,
+            }
+        }
+    }
+}
+
+impl<Val: PartialEq> PartialEq for NodeKey<'_, Val> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<Val: fmt::Debug> fmt::Debug for NodeKey<'_, Val> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// hash the `Debug` output of a key, the keys are only required to be `PartialEq`,
+/// so the keyed diffing matches them by the hashes of their `Debug` output instead of
+/// comparing each new key against each of the old keys. The equal keys whose `Debug`
+/// output differs, such as `0.0` and `-0.0`, are not matched and are recreated instead.
+#[cfg(feature = "keyed")]
+pub(crate) fn debug_hash<T: fmt::Debug + ?Sized>(value: &T) -> u64 {
+    use core::fmt::Write;
+    use core::hash::Hasher;
+
+    /// feeds the formatted output into the hasher without allocating it
+    struct HashWriter(crate::merkle::Fnv);
+
+    impl Write for HashWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.write(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    let mut writer = HashWriter(crate::merkle::Fnv::new());
+    // a failing `Debug` still hashes the same output for the same value
+    let _ = write!(writer, "{:?}", value);
+    writer.0.finish()
+}
@@ -0,0 +1,108 @@
+//! diffing the changed leaves into splices, and scoring how similar they are
+use crate::patch::LeafSplice;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Leaves which can be diffed into fine-grained splices, such as the text which is
+/// inserted and deleted, instead of replacing the whole leaf.
+///
+/// The differ emits a `SpliceLeaf` patch for a changed leaf when
+/// `DiffOptions::with_leaf_diff` is used. The text is spliced as one splice of its
+/// changed middle, see `text_splices` for splicing the scattered edits.
+pub trait LeafDiff {
+    /// return the splices which turn this leaf into the `new` leaf, in ascending order
+    /// and not overlapping. None if the leaves can not be spliced, so the leaf is changed
+    /// or replaced as a whole.
+    fn diff_leaf(&self, new: &Self) -> Option<Vec<LeafSplice>>;
+}
+
+/// the text is spliced by its chars, keeping the common prefix and suffix
+impl LeafDiff for &str {
+    fn diff_leaf(&self, new: &Self) -> Option<Vec<LeafSplice>> {
+        let old: Vec<char> = self.chars().collect();
+        let new: Vec<char> = new.chars().collect();
+        Some(LeafSplice::between(&old, &new).into_iter().collect())
+    }
+}
+
+impl LeafDiff for String {
+    fn diff_leaf(&self, new: &Self) -> Option<Vec<LeafSplice>> {
+        self.as_str().diff_leaf(&new.as_str())
+    }
+}
+
+/// Leaves which can tell how similar they are to another leaf, such as the text leaves
+/// which share a prefix, for matching the similar children with `DiffOptions::match_similar`
+/// when `DiffOptions::with_leaf_similarity` is used.
+pub trait LeafSimilarity {
+    /// return how similar this leaf is to the `other` leaf as a percentage,
+    /// 100 for the equal leaves
+    fn similarity(&self, other: &Self) -> u8;
+}
+
+/// the text is as similar as the share of the longer text which is their common prefix
+impl LeafSimilarity for &str {
+    fn similarity(&self, other: &Self) -> u8 {
+        let longest = self.chars().count().max(other.chars().count());
+        if longest == 0 {
+            return 100;
+        }
+        let prefix = self
+            .chars()
+            .zip(other.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        (prefix * 100 / longest) as u8
+    }
+}
+
+impl LeafSimilarity for String {
+    fn similarity(&self, other: &Self) -> u8 {
+        self.as_str().similarity(&other.as_str())
+    }
+}
+
+/// the most units which `text_splices` deletes and inserts with the myers diff,
+/// more edits than this are spliced as one splice of the changed middle of the text
+pub const TEXT_SPLICE_EDITS: usize = 256;
+
+/// return the splices of the chars which are deleted and inserted from the `old` text
+/// to the `new` text, using the myers diff so the scattered edits of a large text leaf,
+/// such as the leaf of an editor, are spliced separately. This is used as the
+/// `DiffOptions::leaf_diff` of the text leaves.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = leaf("the cat sat on the mat");
+/// let new: MyNode = leaf("the bat sat on the hat");
+///
+/// let options = DiffOptions {
+///     leaf_diff: Some(text_splices),
+///     ..Default::default()
+/// };
+/// let diff = diff_with_options(&old, &new, &"key", &options);
+/// let PatchType::SpliceLeaf { splices, .. } = &diff[0].patch_type else {
+///     panic!("expecting a SpliceLeaf");
+/// };
+/// assert_eq!(splices.len(), 2);
+/// ```
+pub fn text_splices<Leaf: AsRef<str>>(
+    old: &Leaf,
+    new: &Leaf,
+) -> Option<Vec<LeafSplice>> {
+    let old: Vec<char> = old.as_ref().chars().collect();
+    let new: Vec<char> = new.as_ref().chars().collect();
+    Some(LeafSplice::minimal(&old, &new, TEXT_SPLICE_EDITS))
+}
+
+/// the vectors are spliced by their items, keeping the common prefix and suffix
+impl<T: PartialEq> LeafDiff for Vec<T> {
+    fn diff_leaf(&self, new: &Self) -> Option<Vec<LeafSplice>> {
+        Some(LeafSplice::between(self, new).into_iter().collect())
+    }
+}
@@ -0,0 +1,194 @@
+//! moving the keyed nodes between different parents
+use super::{diff_node, DiffOptions, MoveScope, OldRoot};
+use crate::{Node, Patch, PatchType, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::mem;
+
+/// replace the inserted nodes whose key matches a removed keyed node of another parent
+/// with a move of the removed node, followed by the patches of its changes.
+/// The removal of the moved nodes are dropped, each removed node is moved at most once.
+pub(crate) fn move_across_parents<'a, Ns, Tag, Leaf, Att, Val>(
+    old_root: &OldRoot<'a, Ns, Tag, Leaf, Att, Val>,
+    patches: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    scope: MoveScope,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    // the index of the patch, the path and the node of each removed keyed node
+    let mut removed: Vec<(
+        usize,
+        &TreePath,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    )> = patches
+        .iter()
+        .enumerate()
+        .filter(|(_, patch)| patch.patch_type == PatchType::RemoveNode)
+        .filter_map(|(index, patch)| {
+            let node = old_root.find_node_by_path(&patch.patch_path)?;
+            options
+                .key_of(node, key)
+                .map(|_| (index, &patch.patch_path, node))
+        })
+        .collect();
+    if removed.is_empty() {
+        return patches;
+    }
+
+    let mut moved = vec![];
+    let mut replacements = vec![];
+    for (index, patch) in patches.iter().enumerate() {
+        let (nodes, parent) = match &patch.patch_type {
+            PatchType::InsertBeforeNode { nodes }
+            | PatchType::InsertAfterNode { nodes } => {
+                (nodes, patch.patch_path.backtrack())
+            }
+            PatchType::AppendChildren { children } => {
+                (children, patch.patch_path.clone())
+            }
+            _ => continue,
+        };
+        // the runs of the inserted nodes and the moved nodes with their patches, in their order
+        let mut groups = vec![];
+        let mut run = vec![];
+        for node in nodes {
+            let new_key = options.key_of(node, key);
+            let found = new_key.and_then(|new_key| {
+                removed.iter().position(|(_, from_path, candidate)| {
+                    options.key_of(candidate, key).as_ref() == Some(&new_key)
+                        && candidate.tag() == node.tag()
+                        && mem::discriminant(*candidate)
+                            == mem::discriminant(*node)
+                        && is_in_scope(from_path, &parent, scope)
+                })
+            });
+            if let Some(found) = found {
+                let (removal, from_path, candidate) = removed.remove(found);
+                moved.push(removal);
+                if !run.is_empty() {
+                    groups.push(vec![inserted(patch, mem::take(&mut run))]);
+                }
+                let mut group = vec![moving(patch, from_path.clone())];
+                group.extend(diff_node(
+                    candidate,
+                    node,
+                    from_path,
+                    key,
+                    options,
+                    &|_old, _new| false,
+                    &|_old, _new| false,
+                ));
+                groups.push(group);
+            } else {
+                run.push(*node);
+            }
+        }
+        if groups.is_empty() {
+            continue;
+        }
+        if !run.is_empty() {
+            groups.push(vec![inserted(patch, run)]);
+        }
+        // placing after the same target reverses the order of the groups,
+        // so the last group is placed first
+        if matches!(patch.patch_type, PatchType::InsertAfterNode { .. }) {
+            groups.reverse();
+        }
+        replacements
+            .push((index, groups.into_iter().flatten().collect::<Vec<_>>()));
+    }
+
+    let mut replacements = replacements.into_iter().peekable();
+    let mut moved_patches = Vec::with_capacity(patches.len());
+    for (index, patch) in patches.into_iter().enumerate() {
+        if moved.contains(&index) {
+            continue;
+        }
+        match replacements.next_if(|(replaced, _)| *replaced == index) {
+            Some((_, replacement)) => moved_patches.extend(replacement),
+            None => moved_patches.push(patch),
+        }
+    }
+    moved_patches
+}
+
+/// returns true if the node at `from_path` can be moved into the `parent`
+fn is_in_scope(
+    from_path: &TreePath,
+    parent: &TreePath,
+    scope: MoveScope,
+) -> bool {
+    let from_parent = from_path.backtrack();
+    // a node can not be moved into itself or its descendants
+    if parent.path.starts_with(&from_path.path) {
+        return false;
+    }
+    match scope {
+        MoveScope::Siblings => {
+            from_parent.path.len() == parent.path.len()
+                && from_parent.backtrack() == parent.backtrack()
+        }
+        MoveScope::Anywhere => true,
+    }
+}
+
+/// the patch which inserts the `nodes` the same as the insertion `patch`
+fn inserted<'a, Ns, Tag, Leaf, Att, Val>(
+    patch: &Patch<'a, Ns, Tag, Leaf, Att, Val>,
+    nodes: Vec<&'a Node<Ns, Tag, Leaf, Att, Val>>,
+) -> Patch<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let patch_type = match patch.patch_type {
+        PatchType::InsertBeforeNode { .. } => {
+            PatchType::InsertBeforeNode { nodes }
+        }
+        PatchType::InsertAfterNode { .. } => {
+            PatchType::InsertAfterNode { nodes }
+        }
+        _ => PatchType::AppendChildren { children: nodes },
+    };
+    Patch {
+        tag: patch.tag,
+        patch_path: patch.patch_path.clone(),
+        patch_type,
+    }
+}
+
+/// the patch which moves the node at `from_path` to where the insertion `patch` inserts
+fn moving<'a, Ns, Tag, Leaf, Att, Val>(
+    patch: &Patch<'a, Ns, Tag, Leaf, Att, Val>,
+    from_path: TreePath,
+) -> Patch<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let path = patch.patch_path.clone();
+    match patch.patch_type {
+        PatchType::InsertBeforeNode { .. } => {
+            Patch::move_before_node(patch.tag, path, [from_path])
+        }
+        PatchType::InsertAfterNode { .. } => {
+            Patch::move_after_node(patch.tag, path, [from_path])
+        }
+        _ => Patch::move_into_node(patch.tag, path, [from_path]),
+    }
+}
@@ -0,0 +1,574 @@
+use super::{DiffScratch, DiffStats, KeyOf, LeafDiff, LeafSimilarity, NodeKey};
+use crate::patch::{AttributePart, LeafSplice, TreeId};
+use crate::{Attribute, Node, TreePath};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+
+/// Decides what the differ does when the old and new node are of different variant,
+/// ie: a Leaf which is replaced by an Element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariantChange {
+    /// replace the old node with the new node, this is the default
+    Replace,
+    /// emit a `WrapNode` patch when the new node is an element wrapping the old node,
+    /// or an `UnwrapNode` patch when the old node is an element wrapping the new node,
+    /// so the applier can preserve the identity of the inner node.
+    /// This will fallback to `Replace` when neither of the pattern matched.
+    WrapOrUnwrap,
+}
+
+/// The algorithm used to diff the lists of children
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffAlgorithm {
+    /// match the children by their keys when any of the children has a key,
+    /// otherwise match them by their position. This is the default
+    KeyedIfAny,
+    /// always match the children by their position, keyed children whose key changed
+    /// at a position are replaced
+    Positional,
+    /// pick the strategy for each list of children based on the keyed fraction
+    /// of the children and whether the keys are still in the same order.
+    /// The picked strategies are recorded in `DiffStats::lists`
+    Auto,
+}
+
+/// Where the differ looks for the removed keyed nodes which are moved into another parent,
+/// see `DiffOptions::cross_parent_moves`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveScope {
+    /// the old and the new parent are siblings, such as the columns of a kanban board
+    Siblings,
+    /// the old and the new parent are anywhere in the diffed trees
+    Anywhere,
+}
+
+/// When the differ compares the whole old and new subtrees for equality, which lets it
+/// skip the equal subtrees, see `DiffOptions::equality_check`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqualityCheck {
+    /// compare the subtrees after checking whether the old node is replaced,
+    /// so the replaced nodes are never walked. This is the default
+    AfterReplace,
+    /// compare the subtrees before checking the keys and tags of the nodes,
+    /// so the huge equal subtrees with key attributes skip those checks.
+    /// The equal nodes are still replaced when the replace function evaluates to true
+    BeforeReplace,
+    /// never compare the whole subtrees, the changed nodes are found by descending
+    /// into the children only. This is for the trees which are mostly changed,
+    /// where the full equality walk is wasted before descending anyway
+    Never,
+}
+
+/// What the differ does with the lists of children where several siblings have the same key,
+/// see `DiffOptions::duplicate_keys`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// the new children with a duplicated key are matched with the old children of the same key
+    /// in the order of the children, the rest of them are inserted or removed. This is the default
+    MatchInOrder,
+    /// the whole list of children is diffed by position when any of the keys is duplicated,
+    /// since the keys can not be trusted to identify the children
+    Positional,
+}
+
+/// The error of `try_diff_with_options` when the diff is cancelled with `DiffOptions::cancel`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffCancelled;
+
+impl fmt::Display for DiffCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the diff is cancelled")
+    }
+}
+
+impl std::error::Error for DiffCancelled {}
+
+/// A warning reported by the differ through `DiffOptions::on_warning`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffWarning {
+    /// the keyed children at `path` are more than `DiffOptions::keyed_scan_limit`,
+    /// so the old children are replaced with the new children instead of matching their keys
+    KeyedScanLimitExceeded {
+        /// the path of the parent of the keyed children
+        path: TreePath,
+        /// the number of old children which are not matched by the prefix or suffix
+        old_len: usize,
+        /// the number of new children which are not matched by the prefix or suffix
+        new_len: usize,
+    },
+    /// the keyed children at `path` have duplicate keys, so they are diffed by their position
+    /// as set with `DuplicateKeyPolicy::Positional`
+    DuplicateKeys {
+        /// the path of the parent of the keyed children
+        path: TreePath,
+    },
+}
+
+/// Options to control the behavior of the differ
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = leaf("hello");
+/// let new: MyNode = element("b", vec![], vec![leaf("hello")]);
+///
+/// let options = DiffOptions {
+///     variant_change: Some(&|_old, _new| VariantChange::WrapOrUnwrap),
+///     ..Default::default()
+/// };
+/// let diff = diff_with_options(&old, &new, &"key", &options);
+/// assert_eq!(diff, vec![Patch::wrap_node(None, TreePath::root(), &new)]);
+/// ```
+pub struct DiffOptions<'o, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// called when the old and new node are of different variant,
+    /// the old and new node are replaced when this is None.
+    /// This takes precedence over `detect_wrapping` for nodes of different variant.
+    pub variant_change: Option<
+        &'o dyn Fn(
+            &Node<Ns, Tag, Leaf, Att, Val>,
+            &Node<Ns, Tag, Leaf, Att, Val>,
+        ) -> VariantChange,
+    >,
+    /// emit `WrapNode` or `UnwrapNode` patches instead of `ReplaceNode`
+    /// when the new node is an element wrapping the old node as its only child,
+    /// or when the old node is an element wrapping the new node as its only child.
+    /// This is useful in editors which toggles wrappers such as `<b>` where
+    /// the content is kept as is.
+    pub detect_wrapping: bool,
+    /// emit a `ChangeLeaf` patch instead of `ReplaceNode` when a leaf changed,
+    /// which is a lot cheaper for appliers such as setting the data of an existing text node.
+    pub change_leaf: bool,
+    /// return the splices of a changed leaf, such as the text which is inserted and deleted,
+    /// the differ emits a `SpliceLeaf` patch instead of `ChangeLeaf` or `ReplaceNode`
+    /// when this returns the splices. See `with_leaf_diff`.
+    pub leaf_diff: Option<fn(&Leaf, &Leaf) -> Option<Vec<LeafSplice>>>,
+    /// tolerate adjacent leaves which are split or merged, such as one text leaf which
+    /// is split into 2 text leaves, by emitting a single `ChangeLeafRun` patch for
+    /// the run of leaves instead of pairs of remove and insert patches.
+    pub merge_text_runs: bool,
+    /// match the children without keys which are equal to each other, so the children
+    /// which are relocated in a list are moved with `MoveBeforeNode` or `MoveAfterNode`
+    /// patches instead of being diffed by their position, which replaces each of them.
+    /// The children which are not equal to any old child are inserted, and the old ones
+    /// are removed. This needs the `keyed` feature, which has the moving algorithm.
+    pub detect_moves: bool,
+    /// match the children without keys by their similarity, which is a percentage scored
+    /// from their tags, the overlap of their attribute names and their leaves,
+    /// so the children which are inserted or relocated in a list are moved and diffed
+    /// with the most similar old child instead of replacing the children by their position.
+    /// Each new child is greedily matched with the most similar old child which scores
+    /// at least this percentage. This needs the `keyed` feature, which has the moving algorithm.
+    pub match_similar: Option<u8>,
+    /// return the similarity percentage of the changed leaves for `match_similar`,
+    /// the different leaves are not similar at all without it. See `with_leaf_similarity`.
+    /// The changed leaves in the list itself are only matched with `change_leaf`,
+    /// since they are replaced otherwise.
+    pub leaf_similarity: Option<fn(&Leaf, &Leaf) -> u8>,
+    /// keep a keyed element whose tag changed but its key matched, by emitting a `ChangeTag`
+    /// patch and diffing its attributes and children instead of replacing it.
+    /// This is for hosts such as canvas or custom widgets where the tag is a style-like
+    /// property rather than the identity of the node.
+    pub keyed_tag_change: bool,
+    /// called when the old and new element have different tags, the old element is morphed
+    /// into the new element when this returns true, morphdom-style, by emitting a `ChangeTag`
+    /// patch followed by the attribute and children patches instead of replacing it.
+    /// This preserves the state of the descendants such as inputs and scroll positions.
+    pub can_morph: Option<
+        &'o dyn Fn(
+            &Node<Ns, Tag, Leaf, Att, Val>,
+            &Node<Ns, Tag, Leaf, Att, Val>,
+        ) -> bool,
+    >,
+    /// return the key of any kind of node, such as leaves and fragments which can not
+    /// have attributes, so the keyed diffing can also match those nodes.
+    /// The value of the `key` attribute of elements is used when this is None.
+    pub key_of:
+        Option<&'o dyn Fn(&Node<Ns, Tag, Leaf, Att, Val>) -> Option<Vec<&Val>>>,
+    /// return the numeric key of a key value, the keyed diffing matches the children
+    /// using integer sorting when all of the keys of the children are numeric.
+    /// See `with_numeric_keys`.
+    pub numeric_key: Option<fn(&Val) -> Option<u64>>,
+    /// the ordering of the key values, the keyed diffing matches the children by sorting
+    /// the old keys and binary searching the new keys, instead of the hashes of the `Debug`
+    /// output of the keys. The values must be equal only if they are ordered
+    /// as `Ordering::Equal`. See `with_ordered_keys`.
+    pub key_order: Option<fn(&Val, &Val) -> Ordering>,
+    /// match the keyed children by scanning the old keys for each of the new keys,
+    /// which is `O(m²)` for a list of `m` children. By default the old keys are sorted by
+    /// the hashes of their `Debug` output and each of the new keys is binary searched,
+    /// which does not match the equal keys whose `Debug` output differs, such as `0.0`
+    /// and `-0.0`, so this is only needed for such keys.
+    pub scan_keys: bool,
+    /// returns true if the keyed child is pinned, such as a sticky header row in a keyed list.
+    /// The pinned children are never moved, the other children are moved around them instead.
+    /// A pinned child which is reordered relative to the other pinned children is recreated,
+    /// and pinned children are not protected when the `keyed_scan_limit` is exceeded.
+    pub is_pinned: Option<&'o dyn Fn(&Node<Ns, Tag, Leaf, Att, Val>) -> bool>,
+    /// the paths, in the old tree, of the subtrees which are treated as equal regardless
+    /// of their content, so they never produce patches. This is for subtrees which are
+    /// mutated outside of the virtual dom, such as third-party embeds or contenteditable islands.
+    pub ignore_paths: &'o [TreePath],
+    /// returns true if the subtree of the old node is ignored the same as in `ignore_paths`,
+    /// such as the nodes which has a `contenteditable` attribute
+    pub is_ignored: Option<&'o dyn Fn(&Node<Ns, Tag, Leaf, Att, Val>) -> bool>,
+    /// emit a `SyncIsland` marker patch for each ignored subtree whose siblings changed shape,
+    /// such as when nodes are inserted, removed or moved around it
+    pub sync_islands: bool,
+    /// emit a single `ForEachChild` patch instead of the same `AddAttributes` or
+    /// `RemoveAttributes` patch on each of the children of an element, such as a theme class
+    /// which is changed on every row. This only applies when the children are not
+    /// inserted, removed or moved around.
+    pub for_each_child: bool,
+    /// the maximum number of keyed children, after the matching prefix and suffix,
+    /// which are matched by their keys. More children than this are replaced with the new
+    /// children instead, protecting interactive apps from the worst case latency of huge lists.
+    /// A `DiffWarning::KeyedScanLimitExceeded` is reported when this is triggered.
+    pub keyed_scan_limit: Option<usize>,
+    /// called with the warnings of the differ
+    pub on_warning: Option<&'o dyn Fn(DiffWarning)>,
+    /// the diff stops at the next pair of nodes once this is set, such as when a newer state
+    /// arrives while a long diff is still running. `diff_with_options` then returns the
+    /// patches which are computed so far, which are not the complete diff,
+    /// and `try_diff_with_options` returns `DiffCancelled`.
+    /// The flag is never reset by the differ.
+    pub cancel: Option<&'o AtomicBool>,
+    /// the algorithm used to diff the lists of children
+    pub algorithm: DiffAlgorithm,
+    /// what is done with the keyed children which have duplicate keys.
+    /// A `DiffWarning::DuplicateKeys` is reported when they are diffed by their position.
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// the statistics of the diffing are recorded into this when set
+    pub stats: Option<&'o RefCell<DiffStats>>,
+    /// the buffers of the keyed differ are reused from this when set,
+    /// instead of being allocated for every list of children
+    pub scratch: Option<&'o RefCell<DiffScratch>>,
+    /// the path of the old node in a larger tree which is managed by the host,
+    /// it is prepended to the paths of the patches, so the patches of an embedded subtree
+    /// can be applied into the host tree as is. The `ignore_paths` are still relative
+    /// to the old node.
+    pub path_prefix: TreePath,
+    /// the auxiliary trees whose keyed nodes can be adopted, such as the other list of
+    /// a drag and drop. The inserted nodes which are equal to a keyed node of these trees
+    /// are moved out of the tree with an `AdoptNode` patch instead of being created.
+    /// The patches are applied with `apply_patches_with_trees`, which removes the adopted
+    /// nodes from the auxiliary trees, before the auxiliary trees are diffed.
+    pub adoptable_trees: &'o [(TreeId, &'o Node<Ns, Tag, Leaf, Att, Val>)],
+    /// the depth of the old nodes, counted from the old node as 0, past which the differ
+    /// stops descending. A node at this depth which is not equal to the new node is
+    /// replaced as a whole, trading the granularity of the patches for the latency
+    /// of the diffing on very deep trees. The children at this depth are diffed by
+    /// their position even when they are keyed, so the keyed children are replaced
+    /// in place instead of being moved.
+    pub max_depth: Option<usize>,
+    /// the maximum number of patches of an element subtree, an element whose patches
+    /// are more than this is replaced as a whole instead, so the diff of 2 elements
+    /// never has more patches than this.
+    pub max_patches: Option<usize>,
+    /// move the keyed nodes which are removed from a parent and inserted into another parent
+    /// within the `MoveScope`, with `MoveBeforeNode`, `MoveAfterNode` or `MoveIntoNode`
+    /// patches followed by the patches of their changes, instead of recreating them.
+    /// This keeps the state of the moved nodes, such as focus and media playback,
+    /// when an item is dragged between the columns of a board.
+    pub cross_parent_moves: Option<MoveScope>,
+    /// returns true for the names of the behavioral attributes, such as the event listeners,
+    /// for the first diff against a tree which is pre-rendered on the server.
+    /// When this is set, only the `AddAttributes` and `RemoveAttributes` patches of
+    /// the matching attributes are emitted, the structural patches are suppressed
+    /// since the server markup is trusted. Only `update_attributes`, `stats` and
+    /// `path_prefix` are honoured with it, the other options are not used.
+    pub hydrate: Option<&'o dyn Fn(&Att) -> bool>,
+    /// emit a single `UpdateAttributes` patch instead of an `AddAttributes` and
+    /// a `RemoveAttributes` patch on the same element, so the appliers can change
+    /// the attributes of the element in one atomic update
+    pub update_attributes: bool,
+    /// return the changed parts of an attribute whose value changed, such as the changed
+    /// properties of a `style` attribute, the differ emits an `UpdateAttributeParts` patch
+    /// instead of adding the whole attribute when this returns the parts.
+    /// This is only called for the names which have a single attribute in both elements.
+    pub attr_differ: Option<
+        &'o dyn Fn(
+            &Attribute<Ns, Att, Val>,
+            &Attribute<Ns, Att, Val>,
+        ) -> Option<Vec<AttributePart<Val>>>,
+    >,
+    /// when the differ compares the whole old and new subtrees for equality
+    pub equality_check: EqualityCheck,
+    /// return the fingerprint of a node, such as a hash of its subtree which is cached
+    /// by the host. When both nodes have a fingerprint, the nodes are equal if and only if
+    /// their fingerprints are equal, so the differ does not walk the subtrees to compare them.
+    pub fingerprint:
+        Option<&'o dyn Fn(&Node<Ns, Tag, Leaf, Att, Val>) -> Option<u64>>,
+}
+
+impl<'o, Ns, Tag, Leaf, Att, Val> DiffOptions<'o, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// use the numeric keys supplied by `KeyOf` for matching the keyed children
+    pub fn with_numeric_keys(self) -> Self
+    where
+        Val: KeyOf,
+    {
+        Self {
+            numeric_key: Some(Val::numeric_key),
+            ..self
+        }
+    }
+
+    /// use the splices supplied by `LeafDiff` for the changed leaves
+    pub fn with_leaf_diff(self) -> Self
+    where
+        Leaf: LeafDiff,
+    {
+        Self {
+            leaf_diff: Some(Leaf::diff_leaf),
+            ..self
+        }
+    }
+
+    /// use the similarity supplied by `LeafSimilarity` for matching the similar children
+    pub fn with_leaf_similarity(self) -> Self
+    where
+        Leaf: LeafSimilarity,
+    {
+        Self {
+            leaf_similarity: Some(Leaf::similarity),
+            ..self
+        }
+    }
+
+    /// use the ordering of the key values for matching the keyed children, which sorts
+    /// the keys themselves instead of the hashes of their `Debug` output
+    pub fn with_ordered_keys(self) -> Self
+    where
+        Val: Ord,
+    {
+        Self {
+            key_order: Some(Val::cmp),
+            ..self
+        }
+    }
+
+    /// return true if the diff is cancelled with `cancel`
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel
+            .is_some_and(|cancel| cancel.load(AtomicOrdering::Relaxed))
+    }
+
+    /// return true if the subtree of the old node at `path` is ignored
+    pub(crate) fn is_ignored(
+        &self,
+        old_node: &Node<Ns, Tag, Leaf, Att, Val>,
+        path: &TreePath,
+    ) -> bool {
+        self.ignore_paths.contains(path)
+            || self
+                .is_ignored
+                .is_some_and(|is_ignored| is_ignored(old_node))
+    }
+
+    /// return whether the subtrees of the nodes are equal using their fingerprints,
+    /// or `None` when one of them has no fingerprint
+    pub(crate) fn fingerprints_equal(
+        &self,
+        old_node: &Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> Option<bool> {
+        let fingerprints = self.fingerprint.and_then(|fingerprint| {
+            fingerprint(old_node).zip(fingerprint(new_node))
+        });
+        // the memoized nodes are compared by their hashes the same as the fingerprints
+        let fingerprints =
+            fingerprints.or_else(|| old_node.memo().zip(new_node.memo()));
+        fingerprints.map(|(old, new)| old == new)
+    }
+
+    /// return the key of the node using `key_of`, or the value of the `key` attribute
+    pub(crate) fn key_of<'n>(
+        &self,
+        node: &'n Node<Ns, Tag, Leaf, Att, Val>,
+        key: &Att,
+    ) -> Option<NodeKey<'n, Val>> {
+        match self.key_of {
+            Some(key_of) => key_of(node).map(NodeKey::Collected),
+            None => match node.attribute_values_slice(key) {
+                Some([]) => None,
+                Some(values) => Some(NodeKey::Values(values)),
+                None => node.attribute_value(key).map(NodeKey::Collected),
+            },
+        }
+    }
+}
+
+impl<'o, Ns, Tag, Leaf, Att, Val> Default
+    for DiffOptions<'o, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn default() -> Self {
+        Self {
+            variant_change: None,
+            detect_wrapping: false,
+            change_leaf: false,
+            leaf_diff: None,
+            merge_text_runs: false,
+            detect_moves: false,
+            match_similar: None,
+            leaf_similarity: None,
+            keyed_tag_change: false,
+            can_morph: None,
+            key_of: None,
+            numeric_key: None,
+            key_order: None,
+            scan_keys: false,
+            is_pinned: None,
+            ignore_paths: &[],
+            is_ignored: None,
+            sync_islands: false,
+            for_each_child: false,
+            keyed_scan_limit: None,
+            on_warning: None,
+            cancel: None,
+            algorithm: DiffAlgorithm::KeyedIfAny,
+            duplicate_keys: DuplicateKeyPolicy::MatchInOrder,
+            stats: None,
+            scratch: None,
+            path_prefix: TreePath::root(),
+            adoptable_trees: &[],
+            max_depth: None,
+            max_patches: None,
+            cross_parent_moves: None,
+            hydrate: None,
+            update_attributes: false,
+            attr_differ: None,
+            equality_check: EqualityCheck::AfterReplace,
+            fingerprint: None,
+        }
+    }
+}
+
+impl<'o, Ns, Tag, Leaf, Att, Val> Clone
+    for DiffOptions<'o, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn clone(&self) -> Self {
+        Self {
+            variant_change: self.variant_change,
+            detect_wrapping: self.detect_wrapping,
+            change_leaf: self.change_leaf,
+            leaf_diff: self.leaf_diff,
+            merge_text_runs: self.merge_text_runs,
+            detect_moves: self.detect_moves,
+            match_similar: self.match_similar,
+            leaf_similarity: self.leaf_similarity,
+            keyed_tag_change: self.keyed_tag_change,
+            can_morph: self.can_morph,
+            key_of: self.key_of,
+            numeric_key: self.numeric_key,
+            key_order: self.key_order,
+            scan_keys: self.scan_keys,
+            is_pinned: self.is_pinned,
+            ignore_paths: self.ignore_paths,
+            is_ignored: self.is_ignored,
+            sync_islands: self.sync_islands,
+            for_each_child: self.for_each_child,
+            keyed_scan_limit: self.keyed_scan_limit,
+            on_warning: self.on_warning,
+            cancel: self.cancel,
+            algorithm: self.algorithm,
+            duplicate_keys: self.duplicate_keys,
+            stats: self.stats,
+            scratch: self.scratch,
+            path_prefix: self.path_prefix.clone(),
+            adoptable_trees: self.adoptable_trees,
+            max_depth: self.max_depth,
+            max_patches: self.max_patches,
+            cross_parent_moves: self.cross_parent_moves,
+            hydrate: self.hydrate,
+            update_attributes: self.update_attributes,
+            attr_differ: self.attr_differ,
+            equality_check: self.equality_check,
+            fingerprint: self.fingerprint,
+        }
+    }
+}
+
+impl<'o, Ns, Tag, Leaf, Att, Val> Debug
+    for DiffOptions<'o, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DiffOptions")
+            .field("variant_change", &self.variant_change.is_some())
+            .field("detect_wrapping", &self.detect_wrapping)
+            .field("change_leaf", &self.change_leaf)
+            .field("leaf_diff", &self.leaf_diff.is_some())
+            .field("merge_text_runs", &self.merge_text_runs)
+            .field("detect_moves", &self.detect_moves)
+            .field("match_similar", &self.match_similar)
+            .field("leaf_similarity", &self.leaf_similarity.is_some())
+            .field("keyed_tag_change", &self.keyed_tag_change)
+            .field("can_morph", &self.can_morph.is_some())
+            .field("key_of", &self.key_of.is_some())
+            .field("numeric_key", &self.numeric_key.is_some())
+            .field("key_order", &self.key_order.is_some())
+            .field("scan_keys", &self.scan_keys)
+            .field("is_pinned", &self.is_pinned.is_some())
+            .field("ignore_paths", &self.ignore_paths)
+            .field("is_ignored", &self.is_ignored.is_some())
+            .field("sync_islands", &self.sync_islands)
+            .field("for_each_child", &self.for_each_child)
+            .field("keyed_scan_limit", &self.keyed_scan_limit)
+            .field("on_warning", &self.on_warning.is_some())
+            .field("cancel", &self.cancel)
+            .field("algorithm", &self.algorithm)
+            .field("duplicate_keys", &self.duplicate_keys)
+            .field("stats", &self.stats.is_some())
+            .field("scratch", &self.scratch.is_some())
+            .field("path_prefix", &self.path_prefix)
+            .field("adoptable_trees", &self.adoptable_trees.len())
+            .field("max_depth", &self.max_depth)
+            .field("max_patches", &self.max_patches)
+            .field("cross_parent_moves", &self.cross_parent_moves)
+            .field("hydrate", &self.hydrate.is_some())
+            .field("update_attributes", &self.update_attributes)
+            .field("attr_differ", &self.attr_differ.is_some())
+            .field("equality_check", &self.equality_check)
+            .field("fingerprint", &self.fingerprint.is_some())
+            .finish()
+    }
+}
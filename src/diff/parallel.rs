@@ -0,0 +1,158 @@
+//! diffing the children of the huge sibling lists across the threads of rayon
+use super::equality::Equality;
+use super::work::{self, Step, WorkContext};
+use super::DiffOptions;
+use crate::{Node, Patch, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use rayon::prelude::*;
+
+/// Diff the nodes the same as `diff_with_options`, where the pairs of children of a parent
+/// with at least `threshold` pairs of children to diff, such as a table of 50k rows,
+/// are diffed across the threads of the rayon thread pool.
+/// The patches of the children are merged in order, so the patches are the same
+/// as diffing the nodes in a single thread.
+///
+/// The hooks of `DiffOptions` are not `Sync`, so the options can not be shared
+/// across the threads. Instead, each of the children which is diffed in another thread
+/// uses its own options created with `options`, such as its own `DiffStats`.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, String, &'static str, String>;
+///
+/// fn table(text: &str) -> MyNode {
+///     element("tbody", vec![], (0..100).map(|row| {
+///         element("tr", vec![], vec![leaf(format!("{text} {row}"))])
+///     }))
+/// }
+///
+/// let old = table("old");
+/// let new = table("new");
+/// let patches = diff_parallel(&old, &new, &"key", 16, &DiffOptions::default);
+/// assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+/// ```
+pub fn diff_parallel<'a, 'o, Ns, Tag, Leaf, Att, Val, MakeOptions>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    key: &Att,
+    threshold: usize,
+    options: &MakeOptions,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug + Send + Sync + 'o,
+    Tag: PartialEq + Debug + Send + Sync + 'o,
+    Leaf: PartialEq + Clone + Debug + Send + Sync + 'o,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Send + Sync + 'o,
+    Val: PartialEq + Clone + Debug + Send + Sync + 'o,
+    MakeOptions: Fn() -> DiffOptions<'o, Ns, Tag, Leaf, Att, Val> + Sync,
+{
+    let root_options = options();
+    // the hydration only diffs the attributes, there are no children to diff
+    if root_options.hydrate.is_some() {
+        return super::diff_with_options(
+            old_node,
+            new_node,
+            key,
+            &root_options,
+        );
+    }
+    let parallel = Parallel {
+        key,
+        threshold,
+        options,
+    };
+    let root = TreePath::root();
+    let steps = super::expand_node(
+        old_node,
+        new_node,
+        Equality::Unknown,
+        &root,
+        &WorkContext::new(key, &root_options, &|_, _| false, &|_, _| false),
+    );
+    let patches = parallel.resolve(steps, &root, &root_options);
+    super::finish_patches(
+        super::OldRoot::Node(old_node),
+        patches,
+        key,
+        &root_options,
+    )
+}
+
+struct Parallel<'k, 'm, Att, MakeOptions> {
+    key: &'k Att,
+    threshold: usize,
+    options: &'m MakeOptions,
+}
+
+impl<'k, 'm, 'o, Att, MakeOptions> Parallel<'k, 'm, Att, MakeOptions> {
+    /// resolve the steps, where the pairs of children of the big groups are diffed
+    /// across the threads
+    fn resolve<'a, Ns, Tag, Leaf, Val>(
+        &self,
+        steps: Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+        path: &TreePath,
+        options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    where
+        Ns: PartialEq + Clone + Debug + Send + Sync + 'o,
+        Tag: PartialEq + Debug + Send + Sync + 'o,
+        Leaf: PartialEq + Clone + Debug + Send + Sync + 'o,
+        Att: PartialEq + Eq + Hash + Clone + Debug + Send + Sync + 'o,
+        Val: PartialEq + Clone + Debug + Send + Sync + 'o,
+        MakeOptions: Fn() -> DiffOptions<'o, Ns, Tag, Leaf, Att, Val> + Sync,
+    {
+        work::resolve_with(
+            steps,
+            path,
+            &WorkContext::new(self.key, options, &|_, _| false, &|_, _| false),
+            &|steps, current| self.fan_out(steps, current),
+        )
+    }
+
+    /// diff the pairs of children of the group in parallel when there are at least
+    /// `threshold` of them, and put them back as their patches in the same order
+    fn fan_out<'a, Ns, Tag, Leaf, Val>(
+        &self,
+        steps: Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+        current: &TreePath,
+    ) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
+    where
+        Ns: PartialEq + Clone + Debug + Send + Sync + 'o,
+        Tag: PartialEq + Debug + Send + Sync + 'o,
+        Leaf: PartialEq + Clone + Debug + Send + Sync + 'o,
+        Att: PartialEq + Eq + Hash + Clone + Debug + Send + Sync + 'o,
+        Val: PartialEq + Clone + Debug + Send + Sync + 'o,
+        MakeOptions: Fn() -> DiffOptions<'o, Ns, Tag, Leaf, Att, Val> + Sync,
+    {
+        let pairs = steps
+            .iter()
+            .filter(|step| matches!(step, Step::Diff { .. }))
+            .count();
+        if pairs < self.threshold {
+            return steps;
+        }
+        let resolved: Vec<Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>> = steps
+            .into_par_iter()
+            .map(|step| match step {
+                Step::Diff { parent_len, .. } => {
+                    let parent = TreePath {
+                        path: current.path[..parent_len].to_vec(),
+                    };
+                    let options = (self.options)();
+                    self.resolve(vec![step], &parent, &options)
+                        .into_iter()
+                        .map(Step::Patch)
+                        .collect()
+                }
+                step => vec![step],
+            })
+            .collect();
+        resolved.into_iter().flatten().collect()
+    }
+}
@@ -0,0 +1,112 @@
+//! reusable buffers of the keyed differ
+use alloc::vec::Vec;
+#[cfg(feature = "keyed")]
+use core::cell::RefCell;
+use core::mem;
+
+/// The buffers which the keyed differ fills for each list of children, such as the matched
+/// indexes and the buffers of the longest increasing subsequence. They are reused across
+/// the lists and the diffs when shared through `DiffOptions::scratch`, such as by `Differ`,
+/// instead of being allocated for every list.
+///
+/// The keys of the children are not kept since they borrow the diffed nodes.
+#[derive(Debug, Default)]
+pub struct DiffScratch {
+    /// the index of the old child matched by each new child
+    pub(crate) old_indexes: Vec<Option<usize>>,
+    /// whether each old child is matched by a new child
+    pub(crate) old_matched: Vec<bool>,
+    /// the new and old index of the matched children
+    pub(crate) pairs: Vec<(usize, usize)>,
+    /// whether each of the matched children is pinned
+    pub(crate) pinned: Vec<bool>,
+    /// whether each of the matched children stays in place
+    pub(crate) stable: Vec<bool>,
+    /// whether each of the new children is in the longest increasing subsequence
+    pub(crate) in_lis: Vec<bool>,
+    /// the old indexes sorted by their numeric or ordered keys
+    pub(crate) sorted: Vec<usize>,
+    /// the hashes of the old keys with their old index, sorted by the hash
+    pub(crate) hashes: Vec<(u64, usize)>,
+    /// the buffers of the longest increasing subsequence
+    pub(crate) lis: LisBuffers,
+}
+
+/// the buffers of the longest increasing subsequence
+#[derive(Debug, Default)]
+pub(crate) struct LisBuffers {
+    /// the values whose longest increasing subsequence is computed
+    pub(crate) values: Vec<usize>,
+    /// the indexes of the values in the subsequence
+    pub(crate) sequence: Vec<usize>,
+    /// the index of the previous value in the subsequence ending at each value
+    pub(crate) predecessors: Vec<usize>,
+    /// the index of the last value of the subsequence of each length
+    pub(crate) starts: Vec<usize>,
+}
+
+impl DiffScratch {
+    /// create the scratch buffers, which are allocated on their first use
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the number of bytes allocated by the buffers
+    pub fn allocated_bytes(&self) -> usize {
+        fn bytes<T>(buffer: &Vec<T>) -> usize {
+            buffer.capacity() * mem::size_of::<T>()
+        }
+        bytes(&self.old_indexes)
+            + bytes(&self.old_matched)
+            + bytes(&self.pairs)
+            + bytes(&self.pinned)
+            + bytes(&self.stable)
+            + bytes(&self.in_lis)
+            + bytes(&self.sorted)
+            + bytes(&self.hashes)
+            + bytes(&self.lis.values)
+            + bytes(&self.lis.sequence)
+            + bytes(&self.lis.predecessors)
+            + bytes(&self.lis.starts)
+    }
+
+    /// release the memory of the buffers
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// take the buffers out of the shared scratch, the lists which are diffed while the
+    /// buffers are taken, such as the nested lists, get new buffers
+    #[cfg(feature = "keyed")]
+    pub(crate) fn take(shared: Option<&RefCell<Self>>) -> Self {
+        shared
+            .map(|shared| mem::take(&mut *shared.borrow_mut()))
+            .unwrap_or_default()
+    }
+
+    /// put the buffers back into the shared scratch, keeping the larger of the buffers
+    /// when the shared scratch got buffers of a nested list in the meantime
+    #[cfg(feature = "keyed")]
+    pub(crate) fn give_back(self, shared: Option<&RefCell<Self>>) {
+        fn keep_larger<T>(shared: &mut Vec<T>, taken: Vec<T>) {
+            if taken.capacity() > shared.capacity() {
+                *shared = taken;
+            }
+        }
+        let Some(shared) = shared else {
+            return;
+        };
+        let mut shared = shared.borrow_mut();
+        keep_larger(&mut shared.old_indexes, self.old_indexes);
+        keep_larger(&mut shared.old_matched, self.old_matched);
+        keep_larger(&mut shared.pairs, self.pairs);
+        keep_larger(&mut shared.pinned, self.pinned);
+        keep_larger(&mut shared.stable, self.stable);
+        keep_larger(&mut shared.in_lis, self.in_lis);
+        keep_larger(&mut shared.sorted, self.sorted);
+        keep_larger(&mut shared.lis.values, self.lis.values);
+        keep_larger(&mut shared.lis.sequence, self.lis.sequence);
+        keep_larger(&mut shared.lis.predecessors, self.lis.predecessors);
+        keep_larger(&mut shared.lis.starts, self.lis.starts);
+    }
+}
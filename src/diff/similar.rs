@@ -0,0 +1,245 @@
+//! matching the children without keys by their similarity
+use super::work::Step;
+use super::DiffOptions;
+use crate::{Element, Node, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// the key of a child in the matching of the similar children
+#[derive(Debug, PartialEq)]
+enum SimilarKey {
+    /// the old and new child which are matched, with the index of the old child
+    Matched(usize),
+    /// the old child at this index which is not matched
+    Old(usize),
+    /// the new child at this index which is not matched
+    New(usize),
+}
+
+/// Match the children which have no keys with the most similar old children,
+/// so the similar children are moved and diffed in place.
+/// The pairs of children are matched greedily from the most similar pair,
+/// where the closer pairs are matched first when they are as similar.
+/// Returns None when the matched children are all at the same position,
+/// or there are more children than `keyed_scan_limit`, since diffing them by
+/// their position is the same and cheaper.
+pub(crate) fn diff_similar_nodes<'a, Ns, Tag, Leaf, Att, Val>(
+    old_tag: Option<&'a Tag>,
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    threshold: u8,
+    key: &Att,
+    path: &TreePath,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Option<Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if let Some(limit) = options.keyed_scan_limit {
+        if old_children.len() > limit || new_children.len() > limit {
+            return None;
+        }
+    }
+    let mut pairs = vec![];
+    for (new_index, new_child) in new_children.iter().enumerate() {
+        for (old_index, old_child) in old_children.iter().enumerate() {
+            if !is_diffed_in_place(old_child, new_child, key, options) {
+                continue;
+            }
+            let score = similarity(old_child, new_child, options);
+            if score >= threshold {
+                pairs.push((score, old_index, new_index));
+            }
+        }
+    }
+    pairs.sort_by_key(|(score, old_index, new_index)| {
+        (Reverse(*score), old_index.abs_diff(*new_index), *new_index)
+    });
+
+    // the old index of the matched old child is used as the key of both children
+    let mut old_keys = vec![None; old_children.len()];
+    let mut new_keys = vec![None; new_children.len()];
+    for (_, old_index, new_index) in pairs {
+        if old_keys[old_index].is_none() && new_keys[new_index].is_none() {
+            old_keys[old_index] = Some(old_index);
+            new_keys[new_index] = Some(old_index);
+        }
+    }
+    let is_in_place = new_keys
+        .iter()
+        .enumerate()
+        .all(|(new_index, old_index)| old_index.is_none_or(|i| i == new_index));
+    if is_in_place {
+        return None;
+    }
+    // the children which are not matched have keys of their own, so they are
+    // inserted and removed instead of being diffed by their position
+    let old_keys: Vec<_> = old_keys
+        .into_iter()
+        .enumerate()
+        .map(|(index, key)| {
+            Some(key.map_or(SimilarKey::Old(index), SimilarKey::Matched))
+        })
+        .collect();
+    let new_keys: Vec<_> = new_keys
+        .into_iter()
+        .enumerate()
+        .map(|(index, key)| {
+            Some(key.map_or(SimilarKey::New(index), SimilarKey::Matched))
+        })
+        .collect();
+    Some(crate::diff_lis::diff_keyed_nodes_with_keys(
+        old_tag,
+        old_children,
+        new_children,
+        crate::diff_lis::ChildKeys::new(&old_keys, &new_keys),
+        path,
+        options,
+    ))
+}
+
+/// only the children which are diffed without being replaced are matched, since
+/// the replaced children are not kept, and the other patches of the list can be
+/// relative to them. The changed leaves are replaced unless `change_leaf` is enabled.
+fn is_diffed_in_place<Ns, Tag, Leaf, Att, Val>(
+    old_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> bool
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    match (old_node, new_node) {
+        (Node::Leaf(old_leaf), Node::Leaf(new_leaf)) => {
+            old_leaf == new_leaf || options.change_leaf
+        }
+        _ => {
+            !super::should_replace(old_node, new_node, key, options, &|_, _| {
+                false
+            })
+        }
+    }
+}
+
+/// the similarity percentage of the nodes, the elements of the same tag are scored
+/// from the overlap of their attribute names and the similarity of their first children
+fn similarity<Ns, Tag, Leaf, Att, Val>(
+    old_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &Node<Ns, Tag, Leaf, Att, Val>,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> u8
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    match (old_node, new_node) {
+        (Node::Leaf(old_leaf), Node::Leaf(new_leaf)) => {
+            leaf_similarity(old_leaf, new_leaf, options)
+        }
+        (Node::Element(old_element), Node::Element(new_element))
+            if old_element.tag == new_element.tag
+                && old_element.namespace == new_element.namespace =>
+        {
+            let first_children = match (
+                old_element.children.first(),
+                new_element.children.first(),
+            ) {
+                (None, None) => 100,
+                (Some(Node::Leaf(old_leaf)), Some(Node::Leaf(new_leaf))) => {
+                    leaf_similarity(old_leaf, new_leaf, options)
+                }
+                (
+                    Some(Node::Element(old_child)),
+                    Some(Node::Element(new_child)),
+                ) if old_child.tag == new_child.tag => 100,
+                _ => 0,
+            };
+            let score = 40
+                + 30 * u32::from(attribute_overlap(old_element, new_element))
+                    / 100
+                + 30 * u32::from(first_children) / 100;
+            score as u8
+        }
+        _ => 0,
+    }
+}
+
+fn leaf_similarity<Ns, Tag, Leaf, Att, Val>(
+    old_leaf: &Leaf,
+    new_leaf: &Leaf,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> u8
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if old_leaf == new_leaf {
+        return 100;
+    }
+    options
+        .leaf_similarity
+        .map_or(0, |leaf_similarity| leaf_similarity(old_leaf, new_leaf))
+}
+
+/// the percentage of the attribute names of both elements which are in each of them
+fn attribute_overlap<Ns, Tag, Leaf, Att, Val>(
+    old_element: &Element<Ns, Tag, Leaf, Att, Val>,
+    new_element: &Element<Ns, Tag, Leaf, Att, Val>,
+) -> u8
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let old_names = attribute_names(old_element);
+    let new_names = attribute_names(new_element);
+    let shared = old_names
+        .iter()
+        .filter(|name| new_names.contains(name))
+        .count();
+    let all = old_names.len() + new_names.len() - shared;
+    if all == 0 {
+        return 100;
+    }
+    (shared * 100 / all) as u8
+}
+
+/// the distinct attribute names of the element
+fn attribute_names<Ns, Tag, Leaf, Att, Val>(
+    element: &Element<Ns, Tag, Leaf, Att, Val>,
+) -> Vec<&Att>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut names: Vec<&Att> = Vec::with_capacity(element.attrs.len());
+    for att in element.attrs.iter() {
+        if !names.contains(&&att.name) {
+            names.push(&att.name);
+        }
+    }
+    names
+}
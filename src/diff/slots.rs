@@ -0,0 +1,62 @@
+//! diffing the children of the elements which are keyed by their slots,
+//! where the children are matched by their slot names instead of their positions
+use crate::diff::work::Step;
+use crate::{Element, Patch, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// Return the steps of the slotted children of the elements at `path`.
+///
+/// The children of the same slot are diffed in place as long as their slots are in the
+/// same order in both elements, the rest of the new slots are set with `SetSlot` in the
+/// order of the new element, since setting a slot moves it after the other slots.
+/// The old slots which are not in the new element are cleared with `ClearSlot`.
+///
+/// The elements are replaced instead when their slots are not one per child,
+/// except with `diff_element`, where the new slots whose old slot has no child are set.
+pub(crate) fn diff_slots<'a, Ns, Tag, Leaf, Att, Val>(
+    old_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    new_element: &'a Element<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let tag = &old_element.tag;
+    let mut steps = vec![];
+    let mut set = vec![];
+    // the old index of the last slot which is diffed in place
+    let mut last_in_place: Option<usize> = None;
+    let mut in_place = true;
+    for (slot, new_child) in new_element.slotted_children() {
+        let old_slot = old_element
+            .slot_position(slot)
+            .and_then(|index| Some((index, old_element.children.get(index)?)));
+        match old_slot {
+            Some((index, old_child))
+                if in_place
+                    && last_in_place.is_none_or(|last| index > last) =>
+            {
+                last_in_place = Some(index);
+                steps.push(Step::child(old_child, new_child, path, index));
+            }
+            _ => {
+                in_place = false;
+                set.push(Patch::set_slot(tag, path.clone(), slot, new_child));
+            }
+        }
+    }
+    for (slot, _) in old_element.slotted_children() {
+        if !new_element.has_slot(slot) {
+            steps.push(Patch::clear_slot(tag, path.clone(), slot).into());
+        }
+    }
+    steps.extend(set.into_iter().map(Step::Patch));
+    steps
+}
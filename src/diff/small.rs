@@ -0,0 +1,109 @@
+//! the fast paths for diffing the small elements, which are the majority of the elements
+//! in the real UIs, where grouping the attributes into maps and collecting the keys of
+//! the children costs more than the comparison itself
+use crate::Attribute;
+#[cfg(feature = "keyed")]
+use crate::{
+    diff::{work::Step, DiffOptions},
+    Node, TreePath,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// the maximum number of attributes or children which are diffed with the fast paths
+pub(crate) const SMALL: usize = 4;
+
+/// Return the attributes to be added and removed, the same as grouping the attributes
+/// per name, by scanning the attributes instead.
+/// Returns None when either of the elements has more than `N` attributes.
+pub(crate) fn diff_small_attributes<'a, const N: usize, Ns, Att, Val>(
+    old_attributes: &'a [Attribute<Ns, Att, Val>],
+    new_attributes: &'a [Attribute<Ns, Att, Val>],
+) -> Option<(
+    Vec<&'a Attribute<Ns, Att, Val>>,
+    Vec<&'a Attribute<Ns, Att, Val>>,
+)>
+where
+    Ns: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if old_attributes.len() > N || new_attributes.len() > N {
+        return None;
+    }
+    let named = |attributes: &'a [Attribute<Ns, Att, Val>], name: &'a Att| {
+        attributes.iter().filter(move |attr| attr.name == *name)
+    };
+    // only the first attribute of each name is considered, the rest are in its group
+    let is_first = |attributes: &[Attribute<Ns, Att, Val>], index: usize| {
+        let name = &attributes[index].name;
+        !attributes[..index].iter().any(|attr| attr.name == *name)
+    };
+
+    let mut add_attributes = vec![];
+    for (index, new_attr) in new_attributes.iter().enumerate() {
+        if !is_first(new_attributes, index) {
+            continue;
+        }
+        let mut old_values = named(old_attributes, &new_attr.name)
+            .map(|attr| &attr.value)
+            .peekable();
+        let changed = old_values.peek().is_none()
+            || !old_values
+                .eq(named(new_attributes, &new_attr.name)
+                    .map(|attr| &attr.value));
+        if changed {
+            add_attributes.extend(named(new_attributes, &new_attr.name));
+        }
+    }
+
+    let mut remove_attributes = vec![];
+    for (index, old_attr) in old_attributes.iter().enumerate() {
+        if is_first(old_attributes, index)
+            && !new_attributes.iter().any(|attr| attr.name == old_attr.name)
+        {
+            remove_attributes.extend(named(old_attributes, &old_attr.name));
+        }
+    }
+    Some((add_attributes, remove_attributes))
+}
+
+/// Diff the keyed children pairwise when both lists have the same keys in the same order,
+/// which is what the keyed algorithm does for them, without collecting the keys first.
+/// Returns None when either of the lists has more than `N` children or the keys differ.
+#[cfg(feature = "keyed")]
+pub(crate) fn diff_small_keyed<'a, const N: usize, Ns, Tag, Leaf, Att, Val>(
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    key: &Att,
+    path: &TreePath,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Option<Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if old_children.len() > N || old_children.len() != new_children.len() {
+        return None;
+    }
+    let same_keys = old_children
+        .iter()
+        .zip(new_children)
+        .all(|(old, new)| options.key_of(old, key) == options.key_of(new, key));
+    if !same_keys {
+        return None;
+    }
+    Some(
+        old_children
+            .iter()
+            .zip(new_children)
+            .enumerate()
+            .map(|(index, (old, new))| Step::child(old, new, path, index))
+            .collect(),
+    )
+}
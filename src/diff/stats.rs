@@ -0,0 +1,144 @@
+use crate::patch::PatchKind;
+use crate::TreePath;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The strategy which was used to diff a list of children
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListStrategy {
+    /// the children are matched by their keys and moved in place
+    Keyed,
+    /// the children are matched by their position
+    Positional,
+}
+
+/// Statistics collected by the differ when `DiffOptions::stats` is set
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DiffStats {
+    /// the path of the parent and the strategy used for each of the diffed lists of children,
+    /// in the order they were diffed
+    pub lists: Vec<(TreePath, ListStrategy)>,
+    /// the number of the pairs of old and new nodes which are compared
+    pub nodes_visited: usize,
+    /// the number of the pairs of old and new nodes whose subtrees are compared for equality,
+    /// each pair of nodes is compared at most once in a diff
+    pub nodes_compared: usize,
+    /// the number of the comparisons of the keys, or of the hashes of the keys,
+    /// done for matching the keyed children, which is `O(m·log m)` for a list of `m` children
+    /// unless `DiffOptions::scan_keys` is enabled
+    pub keys_compared: usize,
+    /// the number of the visited pairs which are skipped without descending into them,
+    /// since they are equal or skipped by the skip function, the memo or the ignored paths
+    pub nodes_skipped: usize,
+    /// the number of the diffed patches of each kind
+    pub patches: BTreeMap<PatchKind, usize>,
+    /// the depth of the deepest visited pair of nodes, the root nodes are at depth 0
+    pub max_depth: usize,
+    /// the shape of the diffed trees, only collected when this is set,
+    /// such as with `DiffStats::with_profile`
+    pub profile: Option<DiffProfile>,
+}
+
+/// The shape of the diffed trees, accumulated across the diffs which share the same `DiffStats`.
+/// This is used for tuning the thresholds of the heuristics, such as `keyed_scan_limit`,
+/// based on the real workloads.
+///
+/// The tags and the keys are recorded with their Debug representation,
+/// since the statistics are not generic over the node types.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DiffProfile {
+    /// the number of the diffed elements of each tag
+    pub tags: BTreeMap<String, usize>,
+    /// the number of the diffed lists of children
+    pub lists: usize,
+    /// the total number of the new children in the diffed lists
+    pub children: usize,
+    /// the number of the diffed lists which are keyed
+    pub keyed_lists: usize,
+    /// the total number of the keyed new children in the keyed lists
+    pub keys: usize,
+    /// the total number of the distinct keys in each of the keyed lists
+    pub distinct_keys: usize,
+}
+
+impl DiffProfile {
+    /// the average number of the new children of the diffed lists
+    pub fn average_child_count(&self) -> f64 {
+        average(self.children, self.lists)
+    }
+
+    /// the average number of the distinct keys of the keyed lists
+    pub fn average_key_cardinality(&self) -> f64 {
+        average(self.distinct_keys, self.keyed_lists)
+    }
+
+    /// the fraction of the keyed children whose key is a duplicate
+    /// of another child in the same list
+    pub fn duplicate_key_ratio(&self) -> f64 {
+        average(self.keys - self.distinct_keys, self.keys)
+    }
+
+    pub(crate) fn record_element(&mut self, tag: String) {
+        *self.tags.entry(tag).or_insert(0) += 1;
+    }
+
+    /// record a list of children, with the keys of the children when the list is keyed
+    pub(crate) fn record_list(
+        &mut self,
+        child_count: usize,
+        keys: Option<Vec<String>>,
+    ) {
+        self.lists += 1;
+        self.children += child_count;
+        if let Some(keys) = keys {
+            self.keyed_lists += 1;
+            self.keys += keys.len();
+            self.distinct_keys += keys.iter().collect::<BTreeSet<_>>().len();
+        }
+    }
+}
+
+fn average(total: usize, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total as f64 / count as f64
+    }
+}
+
+impl DiffStats {
+    /// create the statistics which also collects the `DiffProfile`
+    pub fn with_profile() -> Self {
+        Self {
+            profile: Some(DiffProfile::default()),
+            ..Default::default()
+        }
+    }
+
+    /// the number of lists of children diffed with `strategy`
+    pub fn count(&self, strategy: ListStrategy) -> usize {
+        self.lists.iter().filter(|(_, s)| *s == strategy).count()
+    }
+
+    /// the number of the diffed patches of `kind`
+    pub fn patch_count(&self, kind: PatchKind) -> usize {
+        self.patches.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// record a pair of nodes which is compared at `depth`
+    pub(crate) fn record_visit(&mut self, depth: usize) {
+        self.nodes_visited += 1;
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    /// record the kinds of the diffed patches
+    pub(crate) fn record_patches(
+        &mut self,
+        kinds: impl IntoIterator<Item = PatchKind>,
+    ) {
+        for kind in kinds {
+            *self.patches.entry(kind).or_insert(0) += 1;
+        }
+    }
+}
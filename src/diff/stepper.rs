@@ -0,0 +1,234 @@
+//! diffing in bounded portions, so the diff of a large tree can be spread across
+//! the turns of a single threaded event loop
+use super::equality::{Comparison, Equality};
+use super::work::{Step, Work, WorkContext};
+use super::{DefaultKey, DiffOptions};
+use crate::{Node, Patch, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// The progress of a `DiffStepper` after a step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffProgress {
+    /// the number of pairs of old and new nodes which are diffed so far
+    pub nodes_visited: usize,
+    /// the number of patches which are found so far,
+    /// the final patches may differ once they are finished with `DiffStepper::finish`
+    pub patches: usize,
+    /// whether all of the nodes are diffed, so `finish` returns without more diffing
+    pub done: bool,
+}
+
+/// A diff which is advanced by a bounded amount of work with each call to `step`,
+/// so the diff of a large tree does not block the input handling of a single threaded
+/// event loop, such as the browser in wasm, for the whole diff.
+///
+/// The patches are the same as `diff_with_options` once the stepper is finished.
+/// A step diffs or compares at most `max_nodes` pairs of nodes, including the pairs
+/// which are compared to find whether the subtrees are equal, although the children
+/// of a node are still matched all at once, such as the keys of a long keyed list.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, String, &'static str, &'static str>;
+///
+/// let list = |text: &str| -> MyNode {
+///     element("ul", vec![], (0..100).map(|i| element("li", vec![], vec![leaf(format!("{text} {i}"))])))
+/// };
+/// let old = list("old");
+/// let new = list("new");
+///
+/// let options = DiffOptions::default();
+/// let mut stepper = diff_stepwise(&old, &new, &options);
+/// let mut steps = 0;
+/// while !stepper.step(50).done {
+///     // yield to the event loop here
+///     steps += 1;
+/// }
+/// assert!(steps > 1);
+/// assert_eq!(stepper.finish(), diff_with_key(&old, &new, &"key"));
+/// ```
+pub struct DiffStepper<'a, 'o, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    key: Att,
+    options: DiffOptions<'o, Ns, Tag, Leaf, Att, Val>,
+    /// the work stack of the diff, which is created once the root nodes are expanded
+    work: Option<Work<'a, Ns, Tag, Leaf, Att, Val>>,
+    /// what is known about the equality of the subtrees of the root nodes,
+    /// with their unfinished comparison before they are expanded
+    equality: Equality,
+    comparison: Option<Comparison<'a, Ns, Tag, Leaf, Att, Val>>,
+    nodes_visited: usize,
+}
+
+/// Start the diff of `old_node` and `new_node` which is done with the steps of
+/// a `DiffStepper`, using the configured default key attribute the same as `diff`.
+/// Use `DiffStepper::new` for a different key attribute.
+pub fn diff_stepwise<'a, 'o, Ns, Tag, Leaf, Att, Val>(
+    old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    options: &DiffOptions<'o, Ns, Tag, Leaf, Att, Val>,
+) -> DiffStepper<'a, 'o, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug + DefaultKey,
+    Val: PartialEq + Clone + Debug,
+{
+    DiffStepper::new(old_node, new_node, Att::default_key(), options)
+}
+
+impl<'a, 'o, Ns, Tag, Leaf, Att, Val>
+    DiffStepper<'a, 'o, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// start the diff of the nodes with the literal name of the key attribute,
+    /// nothing is diffed until the first step
+    pub fn new(
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        key: Att,
+        options: &DiffOptions<'o, Ns, Tag, Leaf, Att, Val>,
+    ) -> Self {
+        Self {
+            old_node,
+            new_node,
+            key,
+            options: options.clone(),
+            work: None,
+            equality: Equality::Unknown,
+            comparison: None,
+            nodes_visited: 0,
+        }
+    }
+
+    /// diff or compare at most `max_nodes` more pairs of nodes, at least 1, and return
+    /// the progress of the diff. The steps after the diff is done do nothing.
+    pub fn step(&mut self, max_nodes: usize) -> DiffProgress {
+        let mut budget = max_nodes.max(1);
+        let root = TreePath::root();
+        let cx = WorkContext::new(
+            &self.key,
+            &self.options,
+            &|_, _| false,
+            &|_, _| false,
+        );
+        let work = match &mut self.work {
+            Some(work) => work,
+            None => {
+                // the hydration only diffs the attributes, which is done by `finish`
+                let steps = if self.options.hydrate.is_some() {
+                    vec![]
+                } else {
+                    // the subtrees of the root nodes are compared within the budget
+                    // of the steps, before the root nodes are expanded
+                    if !self.equality.resolve_within(
+                        self.old_node,
+                        self.new_node,
+                        0,
+                        &mut self.comparison,
+                        &mut budget,
+                        &self.options,
+                    ) || budget == 0
+                    {
+                        return self.progress();
+                    }
+                    budget -= 1;
+                    self.nodes_visited += 1;
+                    super::expand_node(
+                        self.old_node,
+                        self.new_node,
+                        core::mem::take(&mut self.equality),
+                        &root,
+                        &cx,
+                    )
+                };
+                self.work.insert(Work::new(steps, &root))
+            }
+        };
+        if budget > 0 {
+            self.nodes_visited += work.run(
+                Some(budget),
+                &cx,
+                &|steps: Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>, _| steps,
+            );
+        }
+        self.progress()
+    }
+
+    /// the progress of the diff so far
+    pub fn progress(&self) -> DiffProgress {
+        DiffProgress {
+            nodes_visited: self.nodes_visited,
+            patches: self.work.as_ref().map_or(0, Work::patch_count),
+            done: self.is_done(),
+        }
+    }
+
+    /// whether all of the nodes are diffed
+    pub fn is_done(&self) -> bool {
+        self.work.as_ref().is_some_and(Work::is_done)
+    }
+
+    /// diff the rest of the nodes and return the patches, which are the same as
+    /// `diff_with_options`. The patches are partial when the diff is cancelled
+    /// with `DiffOptions::cancel`
+    pub fn finish(mut self) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>> {
+        if self.options.hydrate.is_some() {
+            return super::diff_with_options(
+                self.old_node,
+                self.new_node,
+                &self.key,
+                &self.options,
+            );
+        }
+        while !self.is_done() {
+            self.step(usize::MAX);
+        }
+        let patches = self.work.take().map(Work::into_patches);
+        super::finish_patches(
+            super::OldRoot::Node(self.old_node),
+            patches.unwrap_or_default(),
+            &self.key,
+            &self.options,
+        )
+    }
+}
+
+impl<Ns, Tag, Leaf, Att, Val> Debug
+    for DiffStepper<'_, '_, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DiffStepper")
+            .field("key", &self.key)
+            .field("options", &self.options)
+            .field("progress", &self.progress())
+            .finish()
+    }
+}
@@ -0,0 +1,138 @@
+//! diffing the tables, whose rows are keyed by one attribute
+//! and whose cells are keyed by another attribute
+use super::equality::Equality;
+use super::work::{self, Step, WorkContext};
+use super::DiffOptions;
+use crate::{Node, Patch, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// Diff the tables such as the grids of a spreadsheet, where the rows of the table
+/// are matched by their `row_key` and the cells of the matched rows are matched
+/// by their `column_key`, so the rows and the columns are moved, inserted and removed
+/// with the row and cell patches instead of changing the cells in place.
+///
+/// Diffing the table with a single key can not match both, since the rows
+/// and the cells are keyed by different attributes, such as the id of the record
+/// and the name of the column.
+/// The contents of the cells are diffed with `row_key` the same as `diff_with_options`.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// fn row(id: &'static str, columns: &[&'static str]) -> MyNode {
+///     element("tr", vec![attr("id", id)], columns.iter().map(|column| {
+///         element("td", vec![attr("column", *column)], vec![leaf(*column)])
+///     }))
+/// }
+///
+/// let old = element("tbody", vec![], vec![row("1", &["a", "b"]), row("2", &["a", "b"])]);
+/// let new = element("tbody", vec![], vec![row("2", &["b", "a"]), row("1", &["b", "a"])]);
+/// let patches = diff_table(&old, &new, &"id", &"column", &DiffOptions::default());
+/// #[cfg(feature = "keyed")]
+/// assert!(patches.iter().all(|patch| patch.kind() == PatchKind::MoveBeforeNode));
+/// ```
+pub fn diff_table<'a, Ns, Tag, Leaf, Att, Val>(
+    old_table: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_table: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    row_key: &Att,
+    column_key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    // the hydration only diffs the attributes, there are no rows to match
+    if options.hydrate.is_some() {
+        return super::diff_with_options(
+            old_table, new_table, row_key, options,
+        );
+    }
+    let root = TreePath::root();
+    let steps = super::expand_node(
+        old_table,
+        new_table,
+        Equality::Unknown,
+        &root,
+        &WorkContext::new(row_key, options, &|_, _| false, &|_, _| false),
+    );
+    let patches = work::resolve_with(
+        steps,
+        &root,
+        &WorkContext::new(row_key, options, &|_, _| false, &|_, _| false),
+        &|steps, current| {
+            // only the children of the table are rows
+            if !current.path.is_empty() {
+                return steps;
+            }
+            steps
+                .into_iter()
+                .flat_map(|step| match step {
+                    Step::Diff {
+                        old,
+                        new,
+                        parent_len,
+                        index,
+                        ..
+                    } => {
+                        let mut row = TreePath {
+                            path: current.path[..parent_len].to_vec(),
+                        };
+                        row.push(index);
+                        diff_row(old, new, &row, row_key, column_key, options)
+                            .into_iter()
+                            .map(Step::Patch)
+                            .collect()
+                    }
+                    step => vec![step],
+                })
+                .collect()
+        },
+    );
+    super::finish_patches(
+        super::OldRoot::Node(old_table),
+        patches,
+        row_key,
+        options,
+    )
+}
+
+/// diff the matched rows at `path`, where the cells are keyed by `column_key`
+fn diff_row<'a, Ns, Tag, Leaf, Att, Val>(
+    old_row: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    new_row: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+    row_key: &Att,
+    column_key: &Att,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let steps = super::expand_node(
+        old_row,
+        new_row,
+        Equality::Unknown,
+        path,
+        &WorkContext::new(column_key, options, &|_, _| false, &|_, _| false),
+    );
+    work::resolve(
+        steps,
+        path,
+        &WorkContext::new(row_key, options, &|_, _| false, &|_, _| false),
+    )
+}
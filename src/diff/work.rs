@@ -0,0 +1,446 @@
+//! the explicit work stack of the differ, so the depth of the diffed trees
+//! is bounded by the heap instead of the call stack
+use super::equality::{Comparison, Equality};
+use super::{group_for_each_child, sync_islands, DiffOptions};
+use crate::{Node, Patch, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// What each pair of nodes of the work is diffed with: the name of the key attribute,
+/// the options, and the `skip` and `rep` functions of `diff_with_functions`
+pub(crate) struct WorkContext<'c, 'o, Ns, Tag, Leaf, Att, Val, Skip, Rep>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    pub(crate) key: &'c Att,
+    pub(crate) options: &'c DiffOptions<'o, Ns, Tag, Leaf, Att, Val>,
+    pub(crate) skip: &'c Skip,
+    pub(crate) rep: &'c Rep,
+}
+
+impl<'c, 'o, Ns, Tag, Leaf, Att, Val, Skip, Rep>
+    WorkContext<'c, 'o, Ns, Tag, Leaf, Att, Val, Skip, Rep>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    pub(crate) fn new(
+        key: &'c Att,
+        options: &'c DiffOptions<'o, Ns, Tag, Leaf, Att, Val>,
+        skip: &'c Skip,
+        rep: &'c Rep,
+    ) -> Self {
+        Self {
+            key,
+            options,
+            skip,
+            rep,
+        }
+    }
+}
+
+/// A patch of the diff, or the work which produces the patches in its place
+pub(crate) enum Step<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// a patch which is already known
+    Patch(Patch<'a, Ns, Tag, Leaf, Att, Val>),
+    /// the old child at `index` of the parent whose path has `parent_len` indices,
+    /// which is still to be diffed against the new child.
+    /// The parent is always an ancestor of the nodes which are diffed before this step,
+    /// so its path is the prefix of the current path of the work stack,
+    /// which saves cloning the whole path for each level of the trees.
+    Diff {
+        old: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        parent_len: usize,
+        index: usize,
+        /// what is known about the equality of the subtrees from comparing their parents
+        equality: Equality,
+    },
+    /// the steps whose patches are finished together, such as the patches of an element
+    Group {
+        steps: Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+        finish: Finish<'a, Ns, Tag, Leaf, Att, Val>,
+    },
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> Step<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the step to diff the old and new child at `index` of the parent at `parent`
+    pub(crate) fn child(
+        old: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        parent: &TreePath,
+        index: usize,
+    ) -> Self {
+        Step::Diff {
+            old,
+            new,
+            parent_len: parent.path.len(),
+            index,
+            equality: Equality::Unknown,
+        }
+    }
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> From<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    for Step<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn from(patch: Patch<'a, Ns, Tag, Leaf, Att, Val>) -> Self {
+        Step::Patch(patch)
+    }
+}
+
+/// What is done with the patches of a group once all of its steps are done.
+/// The paths are kept as their length, the same as `Step::Diff`
+pub(crate) enum Finish<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the patches are used as they are
+    None,
+    /// the element is replaced when its patches are over `DiffOptions::max_patches`
+    Element {
+        old_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        new_node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+        path_len: usize,
+    },
+    /// the `SyncIsland` and `ForEachChild` patches of the children of the parent
+    Children {
+        old_tag: Option<&'a Tag>,
+        old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+        path_len: usize,
+    },
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> Finish<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// finish the `patches` of the group, the path of the group is the prefix of `current`
+    fn apply(
+        self,
+        mut patches: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+        current: &TreePath,
+        options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>> {
+        let prefix = |len: usize| TreePath {
+            path: current.path[..len].to_vec(),
+        };
+        match self {
+            Finish::None => patches,
+            Finish::Element {
+                old_node,
+                new_node,
+                path_len,
+            } => {
+                if options
+                    .max_patches
+                    .is_some_and(|max_patches| patches.len() > max_patches)
+                {
+                    vec![Patch::replace_node(
+                        old_node.tag(),
+                        prefix(path_len),
+                        vec![new_node],
+                    )]
+                } else {
+                    patches
+                }
+            }
+            Finish::Children {
+                old_tag,
+                old_children,
+                path_len,
+            } => {
+                let path = prefix(path_len);
+                if options.sync_islands {
+                    let islands =
+                        sync_islands(old_children, &patches, &path, options);
+                    patches.extend(islands);
+                }
+                if options.for_each_child {
+                    patches = group_for_each_child(
+                        old_tag,
+                        old_children.len(),
+                        patches,
+                        &path,
+                    );
+                }
+                patches
+            }
+        }
+    }
+}
+
+/// a step which is still to be done, or the end of the innermost unfinished group
+enum Pending<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    Step(Step<'a, Ns, Tag, Leaf, Att, Val>),
+    Finish(Finish<'a, Ns, Tag, Leaf, Att, Val>),
+}
+
+/// do the `steps` of the node at `path` in order with a work stack, expanding the pairs
+/// of children into the steps of their patches and children, and return the patches
+/// in the same order as diffing the nodes recursively
+pub(crate) fn resolve<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+    steps: Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+    path: &TreePath,
+    cx: &WorkContext<'_, '_, Ns, Tag, Leaf, Att, Val, Skip, Rep>,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+    Skip: Fn(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool,
+    Rep: Fn(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool,
+{
+    resolve_with(steps, path, cx, &|steps, _| steps)
+}
+
+/// `resolve` where the steps of each group are passed through `fan_out` before they are
+/// done, with the path of the node which is diffed last, so the pairs of children can be
+/// diffed elsewhere, such as across threads, and put back as their patches
+pub(crate) fn resolve_with<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep, FanOut>(
+    steps: Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+    path: &TreePath,
+    cx: &WorkContext<'_, '_, Ns, Tag, Leaf, Att, Val, Skip, Rep>,
+    fan_out: &FanOut,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+    Skip: Fn(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool,
+    Rep: Fn(
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+        &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool,
+    FanOut: Fn(
+        Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+        &TreePath,
+    ) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+{
+    let mut work = Work::new(steps, path);
+    work.run(None, cx, fan_out);
+    work.into_patches()
+}
+
+/// The state of the work stack between the runs, so a diff can be done in bounded
+/// portions, such as by `DiffStepper`
+pub(crate) struct Work<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    patches: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+    /// the path of the node which is diffed last
+    current: TreePath,
+    /// the number of patches before each of the unfinished groups
+    starts: Vec<usize>,
+    pending: Vec<Pending<'a, Ns, Tag, Leaf, Att, Val>>,
+    /// the unfinished comparison of the subtrees of the next pair of nodes,
+    /// when the budget of the last run ran out while comparing them
+    comparison: Option<Comparison<'a, Ns, Tag, Leaf, Att, Val>>,
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> Work<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the work of the `steps` of the node at `path`
+    pub(crate) fn new(
+        steps: Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+        path: &TreePath,
+    ) -> Self {
+        Self {
+            patches: vec![],
+            current: path.clone(),
+            starts: vec![],
+            pending: steps.into_iter().rev().map(Pending::Step).collect(),
+            comparison: None,
+        }
+    }
+
+    /// whether all of the steps are done
+    pub(crate) fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// the number of patches which are done so far, before the groups are finished
+    pub(crate) fn patch_count(&self) -> usize {
+        self.patches.len()
+    }
+
+    /// the patches of the steps, which are partial when the work is not done
+    pub(crate) fn into_patches(
+        self,
+    ) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>> {
+        self.patches
+    }
+
+    /// do the steps until they are all done, or until `max_nodes` pairs of nodes
+    /// are diffed or compared, returning the number of pairs of nodes which are diffed.
+    /// The subtrees of the nodes are compared within the `max_nodes` too, so a large
+    /// subtree is compared across several runs
+    pub(crate) fn run<Skip, Rep, FanOut>(
+        &mut self,
+        max_nodes: Option<usize>,
+        cx: &WorkContext<'_, '_, Ns, Tag, Leaf, Att, Val, Skip, Rep>,
+        fan_out: &FanOut,
+    ) -> usize
+    where
+        Skip: Fn(
+            &'a Node<Ns, Tag, Leaf, Att, Val>,
+            &'a Node<Ns, Tag, Leaf, Att, Val>,
+        ) -> bool,
+        Rep: Fn(
+            &'a Node<Ns, Tag, Leaf, Att, Val>,
+            &'a Node<Ns, Tag, Leaf, Att, Val>,
+        ) -> bool,
+        FanOut: Fn(
+            Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+            &TreePath,
+        ) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
+    {
+        let mut diffed = 0;
+        let mut compared = 0;
+        while let Some(next) = self.pending.pop() {
+            match next {
+                Pending::Step(Step::Patch(patch)) => self.patches.push(patch),
+                Pending::Step(Step::Diff {
+                    old,
+                    new,
+                    parent_len,
+                    index,
+                    mut equality,
+                }) => {
+                    // the rest of the steps are dropped, so the patches are partial
+                    if cx.options.is_cancelled() {
+                        self.pending.clear();
+                        self.comparison = None;
+                        break;
+                    }
+                    let is_within = max_nodes.is_none_or(|max_nodes| {
+                        let mut budget =
+                            max_nodes.saturating_sub(diffed + compared);
+                        let before = budget;
+                        let is_resolved = equality.resolve_within(
+                            old,
+                            new,
+                            parent_len + 1,
+                            &mut self.comparison,
+                            &mut budget,
+                            cx.options,
+                        );
+                        compared += before - budget;
+                        is_resolved && budget > 0
+                    });
+                    if !is_within {
+                        self.pending.push(Pending::Step(Step::Diff {
+                            old,
+                            new,
+                            parent_len,
+                            index,
+                            equality,
+                        }));
+                        break;
+                    }
+                    diffed += 1;
+                    self.current.path.truncate(parent_len);
+                    self.current.push(index);
+                    let steps = super::expand_node(
+                        old,
+                        new,
+                        equality,
+                        &self.current,
+                        cx,
+                    );
+                    self.pending
+                        .extend(steps.into_iter().rev().map(Pending::Step));
+                }
+                Pending::Step(Step::Group { steps, finish }) => {
+                    // the groups which are used as they are need no bookkeeping
+                    if !matches!(finish, Finish::None) {
+                        self.starts.push(self.patches.len());
+                        self.pending.push(Pending::Finish(finish));
+                    }
+                    let steps = fan_out(steps, &self.current);
+                    self.pending
+                        .extend(steps.into_iter().rev().map(Pending::Step));
+                }
+                Pending::Finish(finish) => {
+                    let start =
+                        self.starts.pop().expect("every group has a start");
+                    let group = self.patches.split_off(start);
+                    self.patches.extend(finish.apply(
+                        group,
+                        &self.current,
+                        cx.options,
+                    ));
+                }
+            }
+        }
+        diffed
+    }
+}
@@ -1,45 +1,190 @@
 //! diff with longest increasing subsequence
 
-use crate::diff::diff_recursive;
+use crate::diff::scratch::{DiffScratch, LisBuffers};
+use crate::diff::small::{diff_small_keyed, SMALL};
+use crate::diff::work::Step;
+use crate::diff::{DiffOptions, DiffWarning, NodeKey};
 use crate::{Node, Patch, TreePath};
-use alloc::collections::BTreeMap;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cmp::Ordering;
 use core::fmt::Debug;
 use core::hash::Hash;
 
-pub fn diff_keyed_nodes<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+/// The keys of the old and new children, one for each child, and how they are matched
+pub(crate) struct ChildKeys<'k, K> {
+    pub(crate) old: &'k [Option<K>],
+    pub(crate) new: &'k [Option<K>],
+    /// match the keys by sorting their integers, when all of them are numeric
+    pub(crate) numeric_key: Option<&'k dyn Fn(&K) -> Option<u64>>,
+    /// match the keys by sorting them in this order
+    pub(crate) key_order: Option<&'k dyn Fn(&K, &K) -> Ordering>,
+}
+
+impl<'k, K> ChildKeys<'k, K> {
+    /// the keys which are only matched when they are equal
+    pub(crate) fn new(old: &'k [Option<K>], new: &'k [Option<K>]) -> Self {
+        Self {
+            old,
+            new,
+            numeric_key: None,
+            key_order: None,
+        }
+    }
+}
+
+pub fn diff_keyed_nodes<'a, Ns, Tag, Leaf, Att, Val>(
     old_tag: Option<&'a Tag>,
     old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
     new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
     key: &Att,
     path: &TreePath,
-    skip: &Skip,
-    rep: &Rep,
-) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    // the small lists whose keys are unchanged are diffed without collecting their keys
+    if let Some(steps) = diff_small_keyed::<SMALL, _, _, _, _, _>(
+        old_children,
+        new_children,
+        key,
+        path,
+        options,
+    ) {
+        return steps;
+    }
+    let old_keys: Vec<_> = old_children
+        .iter()
+        .map(|c| options.key_of(c, key))
+        .collect();
+    let new_keys: Vec<_> = new_children
+        .iter()
+        .map(|c| options.key_of(c, key))
+        .collect();
+    // the numeric key of the keys which has a single value
+    let single_numeric_key = |node_key: &NodeKey<Val>| {
+        let numeric_key = options.numeric_key?;
+        numeric_key(node_key.single()?)
+    };
+    let numeric_key: Option<&dyn Fn(&NodeKey<Val>) -> Option<u64>> =
+        match options.numeric_key {
+            Some(_) => Some(&single_numeric_key),
+            None => None,
+        };
+    // the keys are ordered by their values in lexicographic order
+    let node_key_order = options.key_order.map(|key_order| {
+        move |a: &NodeKey<Val>, b: &NodeKey<Val>| a.cmp_by(b, key_order)
+    });
+    let key_order: Option<&dyn Fn(&NodeKey<Val>, &NodeKey<Val>) -> Ordering> =
+        match &node_key_order {
+            Some(node_key_order) => Some(node_key_order),
+            None => None,
+        };
+    diff_keyed_nodes_with_keys(
+        old_tag,
+        old_children,
+        new_children,
+        ChildKeys {
+            numeric_key,
+            key_order,
+            ..ChildKeys::new(&old_keys, &new_keys)
+        },
+        path,
+        options,
+    )
+}
+
+/// Diff the children which have no keys by using the children themselves as their keys,
+/// so the children which are equal to an old child are matched and moved in place.
+/// Returns None when none of the matched children are relocated, or there are more
+/// children than `keyed_scan_limit`, since diffing them by their position is cheaper.
+pub fn diff_moved_nodes<'a, Ns, Tag, Leaf, Att, Val>(
+    old_tag: Option<&'a Tag>,
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    path: &TreePath,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Option<Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>>
 where
     Ns: PartialEq + Clone + Debug,
     Tag: PartialEq + Debug,
     Leaf: PartialEq + Clone + Debug,
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
-    Skip: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
-    Rep: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
 {
+    if let Some(limit) = options.keyed_scan_limit {
+        if old_children.len() > limit || new_children.len() > limit {
+            return None;
+        }
+    }
+    let old_keys: Vec<_> = old_children.iter().map(Some).collect();
+    let new_keys: Vec<_> = new_children.iter().map(Some).collect();
+    let mut scratch = DiffScratch::take(options.scratch);
+    let compared = Cell::new(0);
+    if options.scan_keys {
+        match_keys(&old_keys, &new_keys, &mut scratch, &compared);
+    } else {
+        match_hashed_keys(&old_keys, &new_keys, &mut scratch, &compared);
+    }
+    record_keys_compared(options, compared.get());
+    let mut previous = None;
+    let is_relocated = scratch.old_indexes.iter().flatten().any(|old_index| {
+        let relocated = previous.is_some_and(|previous| previous > *old_index);
+        previous = Some(*old_index);
+        relocated
+    });
+    scratch.give_back(options.scratch);
+    if !is_relocated {
+        return None;
+    }
+    Some(diff_keyed_nodes_with_keys(
+        old_tag,
+        old_children,
+        new_children,
+        ChildKeys::new(&old_keys, &new_keys),
+        path,
+        options,
+    ))
+}
+
+/// diff the keyed nodes using the keys of the children which are already known,
+/// the children without a key are not matched
+pub fn diff_keyed_nodes_with_keys<'a, Ns, Tag, Leaf, Att, Val, K>(
+    old_tag: Option<&'a Tag>,
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    keys: ChildKeys<'_, K>,
+    path: &TreePath,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+    K: PartialEq + Debug,
+{
+    let ChildKeys {
+        old: old_keys,
+        new: new_keys,
+        numeric_key,
+        key_order,
+    } = keys;
     let (patches, offsets) = diff_keyed_ends(
         old_tag,
         old_children,
+        old_keys,
         new_children,
-        key,
+        new_keys,
         path,
-        skip,
-        rep,
+        options,
     );
 
     let (left_offset, right_offset) = match offsets {
@@ -79,7 +224,7 @@ where
                 old.tag(),
                 path.traverse(left_offset + index),
             );
-            all_patches.push(patch);
+            all_patches.push(patch.into());
         }
     } else if old_middle.is_empty() {
         // there were no old element, so just create the new elements
@@ -92,7 +237,7 @@ where
                 path.traverse(foothold),
                 new_middle.iter().collect::<Vec<_>>(),
             );
-            all_patches.push(patch);
+            all_patches.push(patch.into());
         } else if right_offset == 0 {
             // insert at the end of the old list
             let foothold = old_children.len() - 1;
@@ -102,7 +247,7 @@ where
                 path.traverse(foothold),
                 new_middle.iter().collect(),
             );
-            all_patches.push(patch);
+            all_patches.push(patch.into());
         } else {
             // inserting in the middle
             let foothold = left_offset - 1;
@@ -112,33 +257,37 @@ where
                 path.traverse(foothold),
                 new_middle.iter().collect(),
             );
-            all_patches.push(patch);
+            all_patches.push(patch.into());
         }
     } else {
         let patches = diff_keyed_middle(
             old_middle,
             new_middle,
+            ChildKeys {
+                old: &old_keys[left_offset..old_end],
+                new: &new_keys[left_offset..new_end],
+                numeric_key,
+                key_order,
+            },
             left_offset,
-            key,
             path,
-            skip,
-            rep,
+            options,
         );
         all_patches.extend(patches);
     }
     all_patches
 }
 
-fn diff_keyed_ends<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+fn diff_keyed_ends<'a, Ns, Tag, Leaf, Att, Val, K>(
     old_tag: Option<&'a Tag>,
     old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    old_keys: &[Option<K>],
     new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
-    key: &Att,
+    new_keys: &[Option<K>],
     path: &TreePath,
-    skip: &Skip,
-    rep: &Rep,
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
 ) -> (
-    Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+    Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>,
     Option<(usize, usize)>,
 )
 where
@@ -147,34 +296,25 @@ where
     Leaf: PartialEq + Clone + Debug,
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
-    Skip: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
-    Rep: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
+    K: PartialEq,
 {
-    // keep track of the old index that has been matched already
-    let mut old_index_matched = vec![];
     let mut all_patches = vec![];
+    let mut compared = 0;
 
     let mut left_offset = 0;
     for (index, (old, new)) in
         old_children.iter().zip(new_children.iter()).enumerate()
     {
         // abort early if we run into nodes with different keys
-        if old.attribute_value(key) != new.attribute_value(key) {
+        compared += 1;
+        if old_keys[index] != new_keys[index] {
             break;
         }
-        let child_path = path.traverse(index);
-        // diff the children and add to patches
-        let patches = diff_recursive(old, new, &child_path, key, skip, rep);
-        all_patches.extend(patches);
-        old_index_matched.push(index);
+        // the children are diffed after the patches of this list
+        all_patches.push(Step::child(old, new, path, index));
         left_offset += 1;
     }
+    record_keys_compared(options, compared);
 
     // if that was all of the old children, then create and append the remaining
     // new children and we're finished
@@ -185,7 +325,7 @@ where
                 path.clone(),
                 new_children[left_offset..].iter().collect::<Vec<_>>(),
             );
-            all_patches.push(patch);
+            all_patches.push(patch.into());
         }
         return (all_patches, None);
     }
@@ -198,7 +338,7 @@ where
                 old.tag(),
                 path.traverse(left_offset + index),
             );
-            all_patches.push(patch);
+            all_patches.push(patch.into());
         }
         return (all_patches, None);
     }
@@ -212,15 +352,17 @@ where
         .enumerate()
     {
         let old_index = old_children.len() - index - 1;
-        // break if already matched this old_index or did not matched key
-        if old_index_matched.contains(&old_index)
-            || old.attribute_value(key) != new.attribute_value(key)
-        {
+        let new_index = new_children.len() - index - 1;
+        // break if already matched this old_index or new_index, or did not matched key,
+        // the old children before `left_offset` are the ones matched from the start
+        if old_index < left_offset || new_index < left_offset {
             break;
         }
-        let child_path = path.traverse(old_index);
-        let patches = diff_recursive(old, new, &child_path, key, skip, rep);
-        all_patches.extend(patches);
+        record_keys_compared(options, 1);
+        if old_keys[old_index] != new_keys[new_index] {
+            break;
+        }
+        all_patches.push(Step::child(old, new, path, old_index));
         right_offset += 1;
     }
 
@@ -228,287 +370,608 @@ where
 }
 
 /// derived from dioxus core/src/diff.rs
-fn diff_keyed_middle<'a, Ns, Tag, Leaf, Att, Val, Skip, Rep>(
+///
+/// The children in the longest increasing subsequence of the matched old indexes
+/// stays in place, the rest of the matched children are moved and the unmatched new children
+/// are inserted. The anchor of the moves and inserts is always a child in the LIS,
+/// which is neither moved nor removed, so the anchor is still at the same place
+/// regardless of which of the patches are applied first.
+fn diff_keyed_middle<'a, Ns, Tag, Leaf, Att, Val, K>(
     old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
     new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    keys: ChildKeys<'_, K>,
     left_offset: usize,
-    key: &Att,
     path: &TreePath,
-    skip: &Skip,
-    rep: &Rep,
-) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
 where
     Ns: PartialEq + Clone + Debug,
     Tag: PartialEq + Debug,
     Leaf: PartialEq + Clone + Debug,
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
-    Skip: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
-    Rep: Fn(
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-        &'a Node<Ns, Tag, Leaf, Att, Val>,
-    ) -> bool,
+    K: PartialEq + Debug,
 {
-    let mut all_patches = vec![];
-
-    let old_children_keys: Vec<_> = old_children
-        .iter()
-        .map(|c| c.attribute_value(key))
-        .collect();
-
-    let new_children_keys: Vec<_> = new_children
-        .iter()
-        .map(|c| c.attribute_value(key))
-        .collect();
-
-    debug_assert_ne!(new_children_keys.first(), old_children_keys.first());
-    debug_assert_ne!(new_children_keys.last(), old_children_keys.last());
-
-    // make a map of old_index -> old_key
-    let old_key_to_old_index: BTreeMap<usize, &Vec<&Val>> =
-        BTreeMap::from_iter(old_children_keys.iter().enumerate().filter_map(
-            |(old_index, old_key)| {
-                old_key.as_ref().map(|old_key| (old_index, old_key))
-            },
-        ));
-
-    let mut shared_keys: Vec<Vec<&Val>> = vec![];
+    let ChildKeys {
+        old: old_keys,
+        new: new_keys,
+        numeric_key,
+        key_order,
+    } = keys;
+    // avoid building the key maps and the LIS of pathological inputs
+    if let Some(limit) = options.keyed_scan_limit {
+        if old_children.len() > limit || new_children.len() > limit {
+            if let Some(on_warning) = options.on_warning {
+                on_warning(DiffWarning::KeyedScanLimitExceeded {
+                    path: path.clone(),
+                    old_len: old_children.len(),
+                    new_len: new_children.len(),
+                });
+            }
+            return replace_all(old_children, new_children, left_offset, path);
+        }
+    }
 
-    // map each new key to the old key, carrying over the old index
-    let new_index_to_old_index: Vec<usize> = new_children
-        .iter()
-        .map(|new| {
-            if let Some(new_key) = new.attribute_value(key) {
-                let index = old_key_to_old_index.iter().find_map(
-                    |(old_index, old_key)| {
-                        if new_key == **old_key {
-                            Some(*old_index)
-                        } else {
-                            None
-                        }
-                    },
-                );
-                if let Some(index) = index {
-                    shared_keys.push(new_key);
-                    index
-                } else {
-                    u32::MAX as usize
-                }
-            } else {
-                u32::MAX as usize
+    let mut scratch = DiffScratch::take(options.scratch);
+    let compared = Cell::new(0);
+    if !match_numeric_keys(
+        old_keys,
+        new_keys,
+        numeric_key,
+        &mut scratch,
+        &compared,
+    ) {
+        match key_order {
+            Some(key_order) => match_sorted_keys(
+                old_keys,
+                new_keys,
+                key_order,
+                &mut scratch,
+                &compared,
+            ),
+            None if options.scan_keys => {
+                match_keys(old_keys, new_keys, &mut scratch, &compared)
+            }
+            None => {
+                match_hashed_keys(old_keys, new_keys, &mut scratch, &compared)
             }
+        }
+    }
+    record_keys_compared(options, compared.get());
+
+    // a matched child is pinned if either the old or the new child is pinned
+    let is_pinned = |new_index: usize, old_index: usize| {
+        options.is_pinned.is_some_and(|is_pinned| {
+            is_pinned(&old_children[old_index])
+                || is_pinned(&new_children[new_index])
         })
-        .collect();
+    };
+    unmatch_reordered_pinned(&mut scratch, is_pinned);
 
     // if none of the old keys are reused by the new children,
     // then we remove all the remaining old children and create the new children afresh.
-    if shared_keys.is_empty() && old_children.first().is_some() {
-        // skip the first one, so we can use it as our foothold for inserting the new children
-        for (index, old) in old_children.iter().skip(1).enumerate() {
-            let patch = Patch::remove_node(old.tag(), path.traverse(index + 1));
-            all_patches.push(patch);
-        }
-
-        let first = 0;
-
-        let patch = Patch::replace_node(
-            old_children[left_offset + first].tag(),
-            path.traverse(left_offset + first),
-            new_children.iter().collect::<Vec<_>>(),
-        );
-        all_patches.push(patch);
-        return all_patches;
+    if !scratch.old_matched.contains(&true) {
+        scratch.give_back(options.scratch);
+        return replace_all(old_children, new_children, left_offset, path);
     }
 
-    // remove any old children that are not shared
+    let mut all_patches = vec![];
+    // remove any old children that are not matched, including the ones that has no key
     for (index, old_child) in old_children.iter().enumerate() {
-        if let Some(old_key) = old_child.attribute_value(key) {
-            if !shared_keys.contains(&old_key) {
-                let patch = Patch::remove_node(
-                    old_child.tag(),
-                    path.traverse(left_offset + index),
-                );
-                all_patches.push(patch);
-            }
-        } else {
-            // also remove the node that has no key
+        if !scratch.old_matched[index] {
             let patch = Patch::remove_node(
                 old_child.tag(),
                 path.traverse(left_offset + index),
             );
-            all_patches.push(patch);
+            all_patches.push(patch.into());
         }
     }
 
-    // Compute the LIS of this list
-    let mut lis_sequence = Vec::with_capacity(new_index_to_old_index.len());
-
-    let mut predecessors = vec![0; new_index_to_old_index.len()];
-    let mut starts = vec![0; new_index_to_old_index.len()];
-
-    longest_increasing_subsequence::lis_with(
-        &new_index_to_old_index,
-        &mut lis_sequence,
-        |a, b| a < b,
-        &mut predecessors,
-        &mut starts,
+    // Compute the LIS of the old indexes of the matched new children,
+    // which always includes the pinned children
+    scratch.pairs.clear();
+    scratch
+        .pairs
+        .extend(scratch.old_indexes.iter().enumerate().filter_map(
+            |(new_index, old_index)| {
+                old_index.map(|old_index| (new_index, old_index))
+            },
+        ));
+    scratch.pinned.clear();
+    scratch.pinned.extend(
+        scratch
+            .pairs
+            .iter()
+            .map(|(new_index, old_index)| is_pinned(*new_index, *old_index)),
     );
+    stable_children(&mut scratch);
 
-    // the lis_seuqnce came out from high to low, so we just reverse it back to arrange from low to
-    // high
-    lis_sequence.reverse();
-
-    // if a new node gets u32 max and is at the end, then it might be part of our LIS (because u32 max is a valid LIS)
-    if lis_sequence.last().map(|f| new_index_to_old_index[*f])
-        == Some(u32::MAX as usize)
-    {
-        lis_sequence.pop();
+    scratch.in_lis.clear();
+    scratch.in_lis.resize(new_children.len(), false);
+    for (idx, stable) in scratch.stable.iter().enumerate() {
+        if *stable {
+            scratch.in_lis[scratch.pairs[idx].0] = true;
+        }
     }
 
-    for idx in lis_sequence.iter() {
-        let patches = diff_recursive(
-            &old_children[new_index_to_old_index[*idx]],
-            &new_children[*idx],
+    // diff the matched children in the order of the new children
+    for (new_index, old_index) in scratch.pairs.iter() {
+        all_patches.push(Step::child(
+            &old_children[*old_index],
+            &new_children[*new_index],
             path,
-            key,
-            skip,
-            rep,
-        );
-        all_patches.extend(patches);
+            left_offset + old_index,
+        ));
     }
 
-    // add mount instruction for the first items not covered by the lis
-    let mut move_after_nodes = vec![];
-    let last = *lis_sequence.last().unwrap();
-    if last < (new_children.len() - 1) {
-        let mut new_nodes = vec![];
-        let mut node_paths = vec![];
-        for (idx, new_node) in new_children[(last + 1)..].iter().enumerate() {
-            let new_idx = idx + last + 1;
-            let old_index = new_index_to_old_index[new_idx];
-            if old_index == u32::MAX as usize {
-                new_nodes.push(new_node);
-            } else {
-                let patches = diff_recursive(
-                    &old_children[old_index],
-                    new_node,
+    // group the children which are not in the LIS into runs, each run is
+    // anchored before the next child in the LIS, or after the last child in the LIS
+    // for the run at the end.
+    let new_index_to_old_index = &scratch.old_indexes;
+    let mut run_start = 0;
+    let mut last_lis = None;
+    for new_index in 0..=new_children.len() {
+        if new_index < new_children.len() && !scratch.in_lis[new_index] {
+            continue;
+        }
+        if run_start < new_index {
+            let run = &new_index_to_old_index[run_start..new_index];
+            if new_index < new_children.len() {
+                let anchor = new_index_to_old_index[new_index]
+                    .expect("lis child must be matched");
+                let patches = place_run(
+                    &new_children[run_start..new_index],
+                    run,
+                    &old_children[anchor],
+                    path.traverse(left_offset + anchor),
+                    left_offset,
                     path,
-                    key,
-                    skip,
-                    rep,
+                    true,
                 );
-                all_patches.extend(patches);
-
-                node_paths.push(path.traverse(left_offset + old_index));
-            }
-        }
-        if !node_paths.is_empty() {
-            let patch = Patch::move_after_node(
-                old_children[left_offset + last].tag(),
-                path.traverse(left_offset + last), //target element
-                node_paths,
-            );
-            move_after_nodes.push(patch);
-        }
-        let old_index = new_index_to_old_index[last];
-        let tag = old_children[old_index].tag();
-        if !new_nodes.is_empty() {
-            let patch = Patch::insert_after_node(
-                tag,
-                path.traverse(left_offset + old_index),
-                new_nodes,
-            );
-            all_patches.push(patch);
-        }
-    }
-
-    // for each spacing, generate a mount instruction
-    let mut lis_iter = lis_sequence.iter().rev();
-    let last = *lis_iter.next().unwrap();
-    let lowest = lis_iter.min();
-    if let Some(next) = lowest {
-        let mut new_nodes = vec![];
-        for (idx, new_node) in new_children[(next + 1)..last].iter().enumerate()
-        {
-            let new_idx = idx + next + 1;
-            let old_index = new_index_to_old_index[new_idx];
-            if old_index == u32::MAX as usize {
-                new_nodes.push(new_node)
+                all_patches.extend(patches.into_iter().map(Step::Patch));
             } else {
-                let patches = diff_recursive(
-                    &old_children[old_index],
-                    new_node,
+                let anchor: usize = last_lis.expect("must have a lis child");
+                let patches = place_run(
+                    &new_children[run_start..new_index],
+                    run,
+                    &old_children[anchor],
+                    path.traverse(left_offset + anchor),
+                    left_offset,
                     path,
-                    key,
-                    skip,
-                    rep,
+                    false,
                 );
-                all_patches.extend(patches);
+                all_patches.extend(patches.into_iter().map(Step::Patch));
             }
         }
+        if new_index < new_children.len() {
+            last_lis = new_index_to_old_index[new_index];
+        }
+        run_start = new_index + 1;
+    }
+    scratch.give_back(options.scratch);
+    all_patches
+}
 
-        if !new_nodes.is_empty() {
-            let tag = old_children[last].tag();
-            let patch = Patch::insert_before_node(
-                tag,
-                path.traverse(left_offset + last),
-                new_nodes,
-            );
-            all_patches.push(patch);
+/// The pinned children must not be moved, so the pinned children which are reordered
+/// relative to the other pinned children are unmatched, and will be removed and recreated
+/// instead. The most pinned children are kept using the LIS of their old indexes.
+fn unmatch_reordered_pinned(
+    scratch: &mut DiffScratch,
+    is_pinned: impl Fn(usize, usize) -> bool,
+) {
+    let pinned: Vec<(usize, usize)> = scratch
+        .old_indexes
+        .iter()
+        .enumerate()
+        .filter_map(|(new_index, old_index)| {
+            old_index.map(|old_index| (new_index, old_index))
+        })
+        .filter(|(new_index, old_index)| is_pinned(*new_index, *old_index))
+        .collect();
+    if pinned.is_empty() {
+        return;
+    }
+    scratch.lis.values.clear();
+    scratch
+        .lis
+        .values
+        .extend(pinned.iter().map(|(_, old_index)| *old_index));
+    let mut kept = vec![];
+    lis(&mut scratch.lis, &mut kept);
+    for (idx, (new_index, old_index)) in pinned.iter().enumerate() {
+        if !kept[idx] {
+            scratch.old_indexes[*new_index] = None;
+            scratch.old_matched[*old_index] = false;
         }
     }
+}
 
-    // add mount instruction for the last items not covered by the list
-    let mut move_before_nodes = vec![];
-    let first_lis = *lis_sequence.first().unwrap();
-    if first_lis > 0 {
-        let mut new_nodes = vec![];
-        let mut node_paths = vec![];
-        for (idx, new_node) in new_children[..first_lis].iter().enumerate() {
-            let old_index = new_index_to_old_index[idx];
-            if old_index == u32::MAX as usize {
-                new_nodes.push(new_node);
-            } else {
-                let patches = diff_recursive(
-                    &old_children[old_index],
-                    new_node,
-                    path,
-                    key,
-                    skip,
-                    rep,
-                );
-                all_patches.extend(patches);
-                node_paths.push(path.traverse(left_offset + old_index));
-            }
+/// Mark which of the matched `pairs`, in the order of the new children, stays in place
+/// into `stable`.
+///
+/// This is the LIS of their old indexes, but the pinned children always stays in place,
+/// so the LIS is computed for each segment in between the pinned children, using only
+/// the children whose old index is also in between the old indexes of the pinned children.
+/// The pinned children must already be in increasing order of their old indexes.
+fn stable_children(scratch: &mut DiffScratch) {
+    let DiffScratch {
+        pairs: matched,
+        pinned,
+        stable,
+        lis: buffers,
+        ..
+    } = scratch;
+    if !pinned.contains(&true) {
+        buffers.values.clear();
+        buffers
+            .values
+            .extend(matched.iter().map(|(_, old_index)| *old_index));
+        lis(buffers, stable);
+        return;
+    }
+    stable.clear();
+    stable.resize(matched.len(), false);
+    let mut in_segment = vec![];
+    let mut mark_segment = |segment: &[usize], stable: &mut Vec<bool>| {
+        buffers.values.clear();
+        buffers
+            .values
+            .extend(segment.iter().map(|idx| matched[*idx].1));
+        lis(buffers, &mut in_segment);
+        for (i, in_lis) in in_segment.iter().enumerate() {
+            stable[segment[i]] = *in_lis;
         }
-        if !node_paths.is_empty() {
-            // TODO: maybe we can also use after_node (left_offset - 1), or the last element of
-            // matched key
-            let first = 0;
-            let patch = Patch::move_before_node(
-                old_children[left_offset + first].tag(),
-                path.traverse(left_offset + first), //target_element
-                node_paths, //to be move after the target_element
-            );
-            move_before_nodes.push(patch);
+    };
+    let mut segment = vec![];
+    let mut lower = None;
+    for (idx, (_, old_index)) in matched.iter().enumerate() {
+        if pinned[idx] {
+            segment.retain(|i: &usize| matched[*i].1 < *old_index);
+            mark_segment(&segment, stable);
+            segment.clear();
+            stable[idx] = true;
+            lower = Some(*old_index);
+        } else if lower.is_none_or(|lower| *old_index > lower) {
+            segment.push(idx);
         }
+    }
+    mark_segment(&segment, stable);
+}
 
-        if !new_nodes.is_empty() {
-            let old_index = new_index_to_old_index[first_lis];
-            let tag = old_children[old_index].tag();
-            let patch = Patch::insert_before_node(
-                tag,
-                path.traverse(left_offset + old_index),
-                new_nodes,
-            );
-            all_patches.push(patch);
-        }
+/// mark which of the `buffers.values` are in their longest increasing subsequence
+/// into `in_lis`
+fn lis(buffers: &mut LisBuffers, in_lis: &mut Vec<bool>) {
+    let len = buffers.values.len();
+    buffers.sequence.clear();
+    buffers.predecessors.clear();
+    buffers.predecessors.resize(len, 0);
+    buffers.starts.clear();
+    buffers.starts.resize(len, 0);
+
+    longest_increasing_subsequence::lis_with(
+        &buffers.values,
+        &mut buffers.sequence,
+        |a, b| a < b,
+        &mut buffers.predecessors,
+        &mut buffers.starts,
+    );
+
+    in_lis.clear();
+    in_lis.resize(len, false);
+    for idx in buffers.sequence.iter() {
+        in_lis[*idx] = true;
     }
+}
 
-    all_patches.extend(move_before_nodes);
-    all_patches.extend(move_after_nodes);
+/// remove all the old children and create the new children afresh
+fn replace_all<'a, Ns, Tag, Leaf, Att, Val>(
+    old_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    left_offset: usize,
+    path: &TreePath,
+) -> Vec<Step<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut all_patches = vec![];
+    // skip the first one, so we can use it as our foothold for inserting the new children
+    for (index, old) in old_children.iter().enumerate().skip(1) {
+        let patch =
+            Patch::remove_node(old.tag(), path.traverse(left_offset + index));
+        all_patches.push(patch.into());
+    }
+    let patch = Patch::replace_node(
+        old_children[0].tag(),
+        path.traverse(left_offset),
+        new_children.iter().collect::<Vec<_>>(),
+    );
+    all_patches.push(patch.into());
     all_patches
 }
+
+/// count the comparisons of the keys in `DiffStats::keys_compared`
+fn record_keys_compared<Ns, Tag, Leaf, Att, Val>(
+    options: &DiffOptions<'_, Ns, Tag, Leaf, Att, Val>,
+    compared: usize,
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    if let Some(stats) = options.stats {
+        stats.borrow_mut().keys_compared += compared;
+    }
+}
+
+/// map each new child to the first unmatched old child with the same key into
+/// `scratch.old_indexes`, and mark which of the old children are matched
+/// in `scratch.old_matched`
+fn match_keys<K>(
+    old_keys: &[Option<K>],
+    new_keys: &[Option<K>],
+    scratch: &mut DiffScratch,
+    compared: &Cell<usize>,
+) where
+    K: PartialEq,
+{
+    let old_index_matched = &mut scratch.old_matched;
+    old_index_matched.clear();
+    old_index_matched.resize(old_keys.len(), false);
+    scratch.old_indexes.clear();
+    scratch.old_indexes.extend(new_keys.iter().map(|new_key| {
+        let new_key = new_key.as_ref()?;
+        let old_index =
+            old_keys
+                .iter()
+                .enumerate()
+                .position(|(old_index, old_key)| {
+                    !old_index_matched[old_index] && {
+                        compared.set(compared.get() + 1);
+                        old_key.as_ref() == Some(new_key)
+                    }
+                })?;
+        old_index_matched[old_index] = true;
+        Some(old_index)
+    }));
+}
+
+/// the same as `match_keys` but the old keys are sorted by the hashes of their `Debug`
+/// output, and the hash of each of the new keys is binary searched, so the keys which only
+/// implement `PartialEq` are matched in `O(m·log m)` instead of scanning the old keys
+fn match_hashed_keys<K>(
+    old_keys: &[Option<K>],
+    new_keys: &[Option<K>],
+    scratch: &mut DiffScratch,
+    compared: &Cell<usize>,
+) where
+    K: PartialEq + Debug,
+{
+    let count = || compared.set(compared.get() + 1);
+    // sorting the old index with the hash keeps the duplicate keys
+    // in the order of the old children
+    let hashes = &mut scratch.hashes;
+    hashes.clear();
+    hashes.extend(old_keys.iter().enumerate().filter_map(
+        |(old_index, old_key)| {
+            Some((crate::diff::debug_hash(old_key.as_ref()?), old_index))
+        },
+    ));
+    hashes.sort_unstable_by(|a, b| {
+        count();
+        a.cmp(b)
+    });
+
+    let old_index_matched = &mut scratch.old_matched;
+    old_index_matched.clear();
+    old_index_matched.resize(old_keys.len(), false);
+    scratch.old_indexes.clear();
+    scratch.old_indexes.extend(new_keys.iter().map(|new_key| {
+        let new_key = new_key.as_ref()?;
+        let hash = crate::diff::debug_hash(new_key);
+        let start = hashes.partition_point(|(old_hash, _)| {
+            count();
+            *old_hash < hash
+        });
+        let old_index = hashes[start..]
+            .iter()
+            .take_while(|(old_hash, _)| *old_hash == hash)
+            .map(|(_, old_index)| *old_index)
+            .find(|old_index| {
+                !old_index_matched[*old_index] && {
+                    count();
+                    old_keys[*old_index].as_ref() == Some(new_key)
+                }
+            })?;
+        old_index_matched[old_index] = true;
+        Some(old_index)
+    }));
+}
+
+/// the same as `match_keys` but the keys are matched using integer sorting,
+/// returns false if the numeric keys are not enabled or some of the keys are not numeric
+fn match_numeric_keys<K>(
+    old_keys: &[Option<K>],
+    new_keys: &[Option<K>],
+    numeric_key: Option<&dyn Fn(&K) -> Option<u64>>,
+    scratch: &mut DiffScratch,
+    compared: &Cell<usize>,
+) -> bool {
+    let count = || compared.set(compared.get() + 1);
+    let Some(numeric_key) = numeric_key else {
+        return false;
+    };
+    let numeric_key_of = |key: &Option<K>| numeric_key(key.as_ref()?);
+    let Some(old_keys) = old_keys
+        .iter()
+        .map(numeric_key_of)
+        .collect::<Option<Vec<u64>>>()
+    else {
+        return false;
+    };
+    let Some(new_keys) = new_keys
+        .iter()
+        .map(numeric_key_of)
+        .collect::<Option<Vec<u64>>>()
+    else {
+        return false;
+    };
+
+    // the old indexes sorted by their key, the stable sort keeps the
+    // duplicate keys in the order of the old children
+    let sorted_old = &mut scratch.sorted;
+    sorted_old.clear();
+    sorted_old.extend(0..old_keys.len());
+    sorted_old.sort_by(|a, b| {
+        count();
+        old_keys[*a].cmp(&old_keys[*b])
+    });
+
+    let old_index_matched = &mut scratch.old_matched;
+    old_index_matched.clear();
+    old_index_matched.resize(old_keys.len(), false);
+    scratch.old_indexes.clear();
+    scratch.old_indexes.extend(new_keys.iter().map(|new_key| {
+        let start = sorted_old.partition_point(|old| {
+            count();
+            old_keys[*old] < *new_key
+        });
+        let old_index = sorted_old[start..]
+            .iter()
+            .take_while(|old| {
+                count();
+                old_keys[**old] == *new_key
+            })
+            .find(|old| !old_index_matched[**old])
+            .copied()?;
+        old_index_matched[old_index] = true;
+        Some(old_index)
+    }));
+    true
+}
+
+/// the same as `match_keys` but the old keys are sorted using `key_order` and each of the
+/// new keys is binary searched, instead of scanning the old keys for each of the new keys
+fn match_sorted_keys<K>(
+    old_keys: &[Option<K>],
+    new_keys: &[Option<K>],
+    key_order: &dyn Fn(&K, &K) -> Ordering,
+    scratch: &mut DiffScratch,
+    compared: &Cell<usize>,
+) {
+    let count = || compared.set(compared.get() + 1);
+    // the old indexes of the keyed children sorted by their key, the stable sort keeps
+    // the duplicate keys in the order of the old children
+    let sorted_old = &mut scratch.sorted;
+    sorted_old.clear();
+    sorted_old
+        .extend((0..old_keys.len()).filter(|old| old_keys[*old].is_some()));
+    let old_key = |old_index: usize| {
+        old_keys[old_index]
+            .as_ref()
+            .expect("only the keyed children are sorted")
+    };
+    sorted_old.sort_by(|a, b| {
+        count();
+        key_order(old_key(*a), old_key(*b))
+    });
+
+    let old_index_matched = &mut scratch.old_matched;
+    old_index_matched.clear();
+    old_index_matched.resize(old_keys.len(), false);
+    scratch.old_indexes.clear();
+    scratch.old_indexes.extend(new_keys.iter().map(|new_key| {
+        let new_key = new_key.as_ref()?;
+        let start = sorted_old.partition_point(|old| {
+            count();
+            key_order(old_key(*old), new_key).is_lt()
+        });
+        let old_index = sorted_old[start..]
+            .iter()
+            .take_while(|old| {
+                count();
+                key_order(old_key(**old), new_key).is_eq()
+            })
+            .find(|old| !old_index_matched[**old])
+            .copied()?;
+        old_index_matched[old_index] = true;
+        Some(old_index)
+    }));
+}
+
+/// emit the patches to place a run of new children before or after the anchor child,
+/// the run is split into consecutive moved children and consecutive new children
+/// such that their order is preserved.
+fn place_run<'a, Ns, Tag, Leaf, Att, Val>(
+    new_children: &'a [Node<Ns, Tag, Leaf, Att, Val>],
+    old_indexes: &[Option<usize>],
+    anchor: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    anchor_path: TreePath,
+    left_offset: usize,
+    path: &TreePath,
+    before: bool,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut patches = vec![];
+    let mut start = 0;
+    while start < new_children.len() {
+        let is_moved = old_indexes[start].is_some();
+        let end = (start..new_children.len())
+            .find(|i| old_indexes[*i].is_some() != is_moved)
+            .unwrap_or(new_children.len());
+        let patch = if is_moved {
+            let node_paths = old_indexes[start..end]
+                .iter()
+                .flatten()
+                .map(|old_index| path.traverse(left_offset + old_index))
+                .collect::<Vec<_>>();
+            if before {
+                Patch::move_before_node(
+                    anchor.tag(),
+                    anchor_path.clone(),
+                    node_paths,
+                )
+            } else {
+                Patch::move_after_node(
+                    anchor.tag(),
+                    anchor_path.clone(),
+                    node_paths,
+                )
+            }
+        } else {
+            let nodes = new_children[start..end].iter().collect();
+            if before {
+                Patch::insert_before_node(
+                    anchor.tag(),
+                    anchor_path.clone(),
+                    nodes,
+                )
+            } else {
+                Patch::insert_after_node(
+                    anchor.tag(),
+                    anchor_path.clone(),
+                    nodes,
+                )
+            }
+        };
+        patches.push(patch);
+        start = end;
+    }
+    // placing after the same anchor reverses the order of the groups,
+    // so the last group is placed first
+    if !before {
+        patches.reverse();
+    }
+    patches
+}
@@ -0,0 +1,227 @@
+//! Serialize the node trees into html, for rendering the views on the server
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{html::*, *};
+//!
+//! pub type MyNode =
+//!     Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+//!
+//! let node: MyNode = element(
+//!     "ul",
+//!     vec![attr("class", "todo")],
+//!     vec![
+//!         element("li", vec![], vec![leaf("milk & eggs")]),
+//!         element("li", vec![], vec![element("br", vec![], vec![])]),
+//!     ],
+//! );
+//! assert_eq!(
+//!     node_to_html_string(&node, false),
+//!     r#"<ul class="todo"><li>milk &amp; eggs</li><li><br></li></ul>"#
+//! );
+//! assert_eq!(
+//!     node_to_html_string(&node, true),
+//!     "<ul class=\"todo\">\n  <li>\n    milk &amp; eggs\n  </li>\n  <li>\n    <br>\n  </li>\n</ul>\n"
+//! );
+//! ```
+use crate::lint::{HTML_RAW_TEXT_ELEMENTS, HTML_VOID_ELEMENTS};
+use crate::{group_attributes_per_name, Element, Node};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display};
+use core::hash::Hash;
+
+/// Render the node tree into an html string.
+///
+/// - the text of the leaves and the values of the attributes are escaped, except the
+///   text in the raw text elements `script` and `style`, which is rendered as it is,
+///   the same as it is parsed. This text must not have the closing tag of its element,
+///   such as `</script>`, which would end the element early
+/// - the values of the attributes with the same name are joined with a space,
+///   such as the classes, and the attributes without a value are rendered as their name
+/// - the html void elements such as `br` have no closing tag and their children are not
+///   rendered, which `lint::check` reports as `LintFinding::VoidWithChildren`, and the `self_closing`
+///   elements such as the svg shapes are closed with `/>` when they have no children
+/// - the namespace of an element is rendered as its `xmlns` attribute when it is not
+///   the same as the namespace of its parent. The namespaces of the attributes are not
+///   rendered, the names of the attributes should have their prefix, such as `xlink:href`
+/// - the fragments and the node lists are rendered as their children
+///
+/// When `pretty` is true, each element and leaf is put on its own line, indented by its
+/// depth, otherwise nothing is added between the nodes.
+/// The tree is walked with an explicit stack, so very deep trees are rendered
+/// without overflowing the stack.
+pub fn node_to_html_string<Ns, Tag, Leaf, Att, Val>(
+    node: &Node<Ns, Tag, Leaf, Att, Val>,
+    pretty: bool,
+) -> String
+where
+    Ns: PartialEq + Clone + Debug + Display,
+    Tag: PartialEq + Debug + Display,
+    Leaf: PartialEq + Clone + Debug + Display,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Display,
+    Val: PartialEq + Clone + Debug + Display,
+{
+    let mut out = String::new();
+    let mut pending = vec![Pending::Open {
+        node,
+        depth: 0,
+        parent_ns: None,
+        raw: false,
+    }];
+    while let Some(next) = pending.pop() {
+        match next {
+            Pending::Open {
+                node,
+                depth,
+                parent_ns,
+                raw,
+            } => match node {
+                Node::Element(element) => {
+                    line(&mut out, depth, pretty);
+                    open_tag(&mut out, element, parent_ns);
+                    let tag = element.tag.to_string();
+                    if HTML_VOID_ELEMENTS.contains(&tag.as_str()) {
+                        newline(&mut out, pretty);
+                        continue;
+                    }
+                    if element.self_closing && element.children.is_empty() {
+                        // replace the `>` of the opening tag
+                        out.pop();
+                        out.push_str("/>");
+                        newline(&mut out, pretty);
+                        continue;
+                    }
+                    newline(&mut out, pretty);
+                    let raw = HTML_RAW_TEXT_ELEMENTS.contains(&tag.as_str());
+                    pending.push(Pending::Close { tag, depth });
+                    let ns = element.namespace.as_ref().or(parent_ns);
+                    pending.extend(element.children.iter().rev().map(
+                        |child| Pending::Open {
+                            node: child,
+                            depth: depth + 1,
+                            parent_ns: ns,
+                            raw,
+                        },
+                    ));
+                }
+                Node::Fragment(nodes) | Node::NodeList(nodes) => {
+                    pending.extend(nodes.iter().rev().map(|child| {
+                        Pending::Open {
+                            node: child,
+                            depth,
+                            parent_ns,
+                            raw,
+                        }
+                    }));
+                }
+                Node::Leaf(leaf) => {
+                    line(&mut out, depth, pretty);
+                    if raw {
+                        out.push_str(&leaf.to_string());
+                    } else {
+                        escape_into(&mut out, &leaf.to_string(), false);
+                    }
+                    newline(&mut out, pretty);
+                }
+            },
+            Pending::Close { tag, depth } => {
+                line(&mut out, depth, pretty);
+                out.push_str("</");
+                out.push_str(&tag);
+                out.push('>');
+                newline(&mut out, pretty);
+            }
+        }
+    }
+    out
+}
+
+/// the node which is still to be rendered, or the closing tag of an element
+enum Pending<'n, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    Open {
+        node: &'n Node<Ns, Tag, Leaf, Att, Val>,
+        depth: usize,
+        parent_ns: Option<&'n Ns>,
+        /// whether the node is in a raw text element, so its text is not escaped
+        raw: bool,
+    },
+    Close {
+        tag: String,
+        depth: usize,
+    },
+}
+
+/// the opening tag of the element, with its attributes
+fn open_tag<Ns, Tag, Leaf, Att, Val>(
+    out: &mut String,
+    element: &Element<Ns, Tag, Leaf, Att, Val>,
+    parent_ns: Option<&Ns>,
+) where
+    Ns: PartialEq + Clone + Debug + Display,
+    Tag: PartialEq + Debug + Display,
+    Leaf: PartialEq + Clone + Debug + Display,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Display,
+    Val: PartialEq + Clone + Debug + Display,
+{
+    out.push('<');
+    out.push_str(&element.tag.to_string());
+    if let Some(ns) = element.namespace.as_ref() {
+        if Some(ns) != parent_ns {
+            out.push_str(" xmlns=\"");
+            escape_into(out, &ns.to_string(), true);
+            out.push('"');
+        }
+    }
+    for (name, attrs) in group_attributes_per_name(&element.attrs) {
+        out.push(' ');
+        out.push_str(&name.to_string());
+        let values: Vec<String> = attrs
+            .iter()
+            .flat_map(|att| att.value.iter().map(|value| value.to_string()))
+            .filter(|value| !value.is_empty())
+            .collect();
+        if !values.is_empty() {
+            out.push_str("=\"");
+            escape_into(out, &values.join(" "), true);
+            out.push('"');
+        }
+    }
+    out.push('>');
+}
+
+fn line(out: &mut String, depth: usize, pretty: bool) {
+    if pretty {
+        for _ in 0..depth {
+            out.push_str("  ");
+        }
+    }
+}
+
+fn newline(out: &mut String, pretty: bool) {
+    if pretty {
+        out.push('\n');
+    }
+}
+
+/// push the text into `out`, escaping the characters which have a meaning in html.
+/// The quotes are only escaped in the values of the attributes
+fn escape_into(out: &mut String, text: &str, is_attribute: bool) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if is_attribute => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
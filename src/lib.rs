@@ -20,17 +20,133 @@
 //! mt-dom is not limited to be used in html base virtual-dom implementation, but can also be use
 //! for native UI elements.
 //!
+//! Backends such as the html DOM, a terminal UI or a native widget toolkit apply the patches
+//! by implementing `patch::Renderer`, which `patch::apply` drives for each of the patches.
+//!
+//! Frameworks which diff on every frame should use `diff::DiffEngine`, which keeps its caches
+//! across the diffs under a memory limit, instead of the free diffing functions.
+//!
+//! ## Cargo features
+//! - `keyed` (default): match the keyed children with the longest increasing subsequence
+//!   algorithm, after matching their common ends forward and backward, without it the
+//!   children are always diffed by their position and the keyed strategies are compiled out
+//! - `apply-patches` (default): apply the patches into a node tree, with `apply_patches`,
+//!   `apply_patches_with_trees`, `apply_patches_with_index`, `patch::compile`,
+//!   `Node::from_patches` and `patch::SpeculativeState`
+//! - `compact-paths`: store the child indices of `TreePath` as `u32`, while the
+//!   API of `TreePath` stays in `usize`.
+//!   This halves the heap of the paths on 64-bit targets, which is measured in
+//!   `tests/compact_paths_test.rs` as 8000 instead of 16000 bytes for a batch of
+//!   1000 patches, out of about 1.6MB allocated by that diff
+//! - `node-cache`: share structurally identical whole trees with `NodeCache`
+//! - `rayon`: diff the children of the huge sibling lists across threads with
+//!   `diff::diff_parallel`
+//! - `derive`: re-export the `IntoNode` derive macro
+//! - `html`: render the node trees into html strings with `html::node_to_html_string`,
+//!   for the server side rendering
+//! - `html-parser`: parse the server rendered html or xml into node trees with `parse::parse`,
+//!   for diffing them against the virtual trees of the client when hydrating
+//! - `log`: emit trace events of the diffing and the patching with the `log` crate
+//! - `serde`: serialize the nodes and the patches, which are deserialized into `patch::OwnedPatch`
+//!   so a diff computed on a server can be applied on a client
+//! - `json`: convert the patches to and from a stable JSON format with `patch::to_json`
+//!   and `patch::from_json`, for the appliers which are not written in Rust
+//! - `testing`: generate random keyed and non-keyed trees with the proptest strategies
+//!   in `testing::arbitrary`, for property testing the differ and the renderers
+//! - `validate-diffs`: check the patches of every diff with `patch::validate` and panic
+//!   when they are invalid, which walks the old tree for each patch, so it is only for
+//!   testing the differ
+//!
+//! Users which only need the differ can disable the default features to shrink their binary.
+//! The stripped release binary of a small program which diffs a keyed list,
+//! and applies the patches when `apply-patches` is enabled, on x86_64 linux:
+//!
+//! | features                  | size       |
+//! |---------------------------|------------|
+//! | `keyed`, `apply-patches`  | 509,704 B  |
+//! | `keyed`                   | 477,840 B  |
+//! | `apply-patches`           | 454,504 B  |
+//! | none                      | 428,224 B  |
+//!
+//! ## Complexity
+//! With the default `DiffOptions`, where `n` is the number of nodes of the larger tree:
+//! - diffing unchanged trees is `O(n)`, the trees are compared once and no node is diffed
+//! - diffing changed trees is `O(n)` plus the keyed lists, each pair of old and new nodes
+//!   is diffed at most once, and its subtrees are compared for equality at most once
+//!   in the whole diff, counted in `DiffStats::nodes_visited` and `DiffStats::nodes_compared`
+//! - diffing a keyed list of `m` children which are reordered is `O(n + m·log m)`,
+//!   where the keys are matched by sorting the hashes of their `Debug` output and binary
+//!   searching them, counted in `DiffStats::keys_compared`, and the children which stay
+//!   in place are found with the longest increasing subsequence
+//! - with `DiffOptions::fingerprint` or the memoized nodes, the equal subtrees are skipped
+//!   without comparing them, so diffing is `O(changed)`: the changed nodes and their siblings
+//!
+//! The heuristics which are not enabled by default, such as `DiffOptions::match_similar`
+//! and `DiffOptions::cross_parent_moves`, are not bounded by these.
+//! These bounds are checked by the tests of the counted visits in `tests/complexity_test.rs`.
+//!
+//! ## Deep trees
+//! The diff, the `PartialEq` of the nodes, `apply_patches`, `html::node_to_html_string`,
+//! `lint::check` and `Node::dismantle` walk the tree with an explicit stack, so they
+//! handle a chain of 100k nested elements, see `tests/deep_tree_test.rs`.
+//! The other operations still recurse into the children and overflow the stack
+//! on such a chain:
+//! - the `Drop` of the nodes, drop the deep trees with `Node::dismantle` instead
+//! - the derived `Clone` and `Debug`, and the `Hash` of the nodes
+//! - the `serde` impls of the nodes, with the `serde` feature
+//! - `codec::encode_node`, while `codec::decode_node` rejects the nodes deeper
+//!   than `codec::MAX_DEPTH`
+//!
 extern crate alloc;
-pub use diff::{diff_recursive, diff_with_key};
+
+/// emit a trace event with the `log` crate when the `log` feature is enabled,
+/// the library never prints to the stdout
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)+);
+    };
+}
+
+#[cfg(feature = "apply-patches")]
+pub use apply_patches::{
+    apply_patches, apply_patches_with_index, apply_patches_with_trees,
+    try_apply_patches,
+};
+pub use diff::{
+    diff, diff_recursive, diff_stepwise, diff_with_key, diff_with_options,
+    diff_with_stats, try_diff_with_options,
+};
+#[cfg(feature = "node-cache")]
+pub use node::cache::NodeCache;
+pub use node::pool::NodePool;
 pub use node::{
     attribute::{
-        attr, attr_ns, group_attributes_per_name, merge_attributes_of_same_name,
+        attr, attr_ns, empty_attr, group_attributes_per_name,
+        merge_attributes_of_same_name,
     },
-    element, element_ns, fragment, leaf, node_list, Attribute, Element, Node,
+    element, element_ns, fragment, leaf, node_list, slotted_element, Attribute,
+    Element, Node, TokenedLeaf,
 };
 pub use patch::{Patch, PatchType, TreePath};
 
+/// derive `From<YourStruct>` for `Node`, mapping the fields into attributes and children
+#[cfg(feature = "derive")]
+pub use mt_dom_derive::IntoNode;
+
+#[cfg(feature = "apply-patches")]
+pub mod apply_patches;
+pub mod codec;
 pub mod diff;
+#[cfg(feature = "keyed")]
 mod diff_lis;
+#[cfg(feature = "html")]
+pub mod html;
+pub mod lint;
+pub mod merkle;
 mod node;
+#[cfg(feature = "html-parser")]
+pub mod parse;
 pub mod patch;
+pub mod sync;
+pub mod testing;
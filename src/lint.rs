@@ -0,0 +1,269 @@
+//! Lint the virtual trees for the mistakes which make the diffing slow or wrong,
+//! such as duplicate keys among the siblings.
+//!
+//! Frameworks run `check` in debug builds before diffing, and report the findings
+//! to the developers, since the differ silently recovers from these mistakes
+//! with more patches than needed.
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{lint::*, *};
+//!
+//! pub type MyNode =
+//!     Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+//!
+//! let node: MyNode = element(
+//!     "ul",
+//!     vec![],
+//!     vec![
+//!         element("li", vec![attr("key", "1")], vec![]),
+//!         element("li", vec![attr("key", "1")], vec![]),
+//!     ],
+//! );
+//! let options = LintOptions {
+//!     key: Some("key"),
+//!     ..Default::default()
+//! };
+//! assert_eq!(
+//!     check(&node, &options),
+//!     vec![LintFinding::DuplicateKey {
+//!         path: TreePath::new([1]),
+//!         first: TreePath::new([0]),
+//!     }]
+//! );
+//! ```
+use crate::{Node, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// The html elements which can not have children
+pub const HTML_VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "param", "source", "track", "wbr",
+];
+
+/// The html elements whose text is not markup, so it is neither escaped nor parsed
+pub const HTML_RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// The checks done by `check`, the checks whose option is not set are skipped
+pub struct LintOptions<Tag, Att> {
+    /// the attribute which is used as the key of the nodes,
+    /// the siblings with duplicate keys and the partially keyed siblings are reported
+    pub key: Option<Att>,
+    /// the attribute which is used as the id of the nodes,
+    /// the elements with the same id as another element of the tree are reported
+    pub id: Option<Att>,
+    /// whether the element of this tag can not have children,
+    /// such as `|tag| HTML_VOID_ELEMENTS.contains(tag)` for the html tags
+    pub is_void: Option<fn(&Tag) -> bool>,
+    /// the nodes which are nested deeper than this are reported,
+    /// the root node is at depth 0
+    pub max_depth: Option<usize>,
+}
+
+impl<Tag, Att> Default for LintOptions<Tag, Att> {
+    fn default() -> Self {
+        Self {
+            key: None,
+            id: None,
+            is_void: None,
+            max_depth: None,
+        }
+    }
+}
+
+impl<Tag, Att: Clone> Clone for LintOptions<Tag, Att> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            id: self.id.clone(),
+            is_void: self.is_void,
+            max_depth: self.max_depth,
+        }
+    }
+}
+
+impl<Tag, Att: Debug> Debug for LintOptions<Tag, Att> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LintOptions")
+            .field("key", &self.key)
+            .field("id", &self.id)
+            .field("is_void", &self.is_void.is_some())
+            .field("max_depth", &self.max_depth)
+            .finish()
+    }
+}
+
+/// A mistake found in the tree by `check`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintFinding {
+    /// the node at `path` has the same key as its previous sibling at `first`
+    DuplicateKey {
+        /// the path of the node with the duplicate key
+        path: TreePath,
+        /// the path of the first sibling with the same key
+        first: TreePath,
+    },
+    /// only some of the children of the node at `path` have keys,
+    /// so the unkeyed children are recreated instead of being diffed
+    PartiallyKeyed {
+        /// the path of the parent of the children
+        path: TreePath,
+        /// the number of children which have keys
+        keyed: usize,
+        /// the number of children
+        len: usize,
+    },
+    /// the element at `path` has the same id as the element at `first`
+    DuplicateId {
+        /// the path of the element with the duplicate id
+        path: TreePath,
+        /// the path of the first element with the same id, in pre-order
+        first: TreePath,
+    },
+    /// the element at `path` has children, but its tag is a void element
+    VoidWithChildren {
+        /// the path of the void element
+        path: TreePath,
+    },
+    /// the node at `path` is nested deeper than `LintOptions::max_depth`,
+    /// its descendants are not reported
+    TooDeep {
+        /// the path of the node
+        path: TreePath,
+        /// the maximum depth which is exceeded
+        max_depth: usize,
+    },
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateKey { path, first } => write!(
+                f,
+                "the node at {:?} has the same key as its sibling at {:?}, the keys of the siblings must be unique",
+                path.path, first.path
+            ),
+            Self::PartiallyKeyed { path, keyed, len } => write!(
+                f,
+                "only {} of the {} children of the node at {:?} have keys, either all or none of the siblings should have keys",
+                keyed, len, path.path
+            ),
+            Self::DuplicateId { path, first } => write!(
+                f,
+                "the element at {:?} has the same id as the element at {:?}, the ids must be unique",
+                path.path, first.path
+            ),
+            Self::VoidWithChildren { path } => write!(
+                f,
+                "the element at {:?} is a void element, which can not have children",
+                path.path
+            ),
+            Self::TooDeep { path, max_depth } => write!(
+                f,
+                "the node at {:?} is nested deeper than {} levels",
+                path.path, max_depth
+            ),
+        }
+    }
+}
+
+/// Check the tree of `node` for the mistakes which are enabled in `options`,
+/// returning the findings in the pre-order of the nodes.
+/// The tree is walked with an explicit stack, so very deep trees are checked
+/// without overflowing the stack.
+pub fn check<Ns, Tag, Leaf, Att, Val>(
+    node: &Node<Ns, Tag, Leaf, Att, Val>,
+    options: &LintOptions<Tag, Att>,
+) -> Vec<LintFinding>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut findings = vec![];
+    // the ids of the elements which are already walked, with their paths
+    let mut ids: Vec<(Vec<&Val>, TreePath)> = vec![];
+    let mut pending = vec![(node, TreePath::root())];
+    while let Some((node, path)) = pending.pop() {
+        if let Some(max_depth) = options.max_depth {
+            if path.path.len() > max_depth {
+                findings.push(LintFinding::TooDeep { path, max_depth });
+                continue;
+            }
+        }
+        if let (Some(id), Node::Element(element)) = (&options.id, node) {
+            if let Some(value) = element.attribute_value(id) {
+                match ids.iter().find(|(other, _)| *other == value) {
+                    Some((_, first)) => {
+                        findings.push(LintFinding::DuplicateId {
+                            path: path.clone(),
+                            first: first.clone(),
+                        })
+                    }
+                    None => ids.push((value, path.clone())),
+                }
+            }
+        }
+        let children = node.child_nodes();
+        if let (Some(is_void), Node::Element(element)) = (options.is_void, node)
+        {
+            if !children.is_empty() && is_void(&element.tag) {
+                findings
+                    .push(LintFinding::VoidWithChildren { path: path.clone() });
+            }
+        }
+        if let Some(key) = &options.key {
+            check_keys(children, key, &path, &mut findings);
+        }
+        for (index, child) in children.iter().enumerate().rev() {
+            pending.push((child, path.traverse(index)));
+        }
+    }
+    findings
+}
+
+/// report the duplicate keys of the children of the node at `path`,
+/// and whether only some of them are keyed
+fn check_keys<Ns, Tag, Leaf, Att, Val>(
+    children: &[Node<Ns, Tag, Leaf, Att, Val>],
+    key: &Att,
+    path: &TreePath,
+    findings: &mut Vec<LintFinding>,
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let keys: Vec<_> = children
+        .iter()
+        .map(|child| child.attribute_value(key))
+        .collect();
+    let keyed = keys.iter().filter(|key| key.is_some()).count();
+    if keyed > 0 && keyed < children.len() {
+        findings.push(LintFinding::PartiallyKeyed {
+            path: path.clone(),
+            keyed,
+            len: children.len(),
+        });
+    }
+    for (index, key) in keys.iter().enumerate() {
+        let Some(key) = key else { continue };
+        if let Some(first) = keys[..index]
+            .iter()
+            .position(|other| other.as_ref() == Some(key))
+        {
+            findings.push(LintFinding::DuplicateKey {
+                path: path.traverse(index),
+                first: path.traverse(first),
+            });
+        }
+    }
+}
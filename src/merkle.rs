@@ -0,0 +1,250 @@
+//! Merkle-style hash trees of the node trees, for coarse change detection between processes
+//!
+//! Each node of a `MerkleTree` has the hash of the node itself and the hash of its whole
+//! subtree, so 2 processes can compare their trees by exchanging the hashes only,
+//! then request only the changed subtrees, which are addressed by the same `TreePath`s
+//! as the patches.
+//!
+//! The hashes are computed with 64-bit FNV-1a, where the integers are hashed in little endian,
+//! so the hashes of the same node tree are the same across processes and platforms.
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{merkle::*, *};
+//!
+//! pub type MyNode =
+//!     Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+//!
+//! let old: MyNode = element(
+//!     "main",
+//!     vec![],
+//!     vec![
+//!         element("header", vec![], vec![leaf("title")]),
+//!         element("article", vec![], vec![leaf("old content")]),
+//!     ],
+//! );
+//! let new: MyNode = element(
+//!     "main",
+//!     vec![],
+//!     vec![
+//!         element("header", vec![], vec![leaf("title")]),
+//!         element("article", vec![], vec![leaf("new content")]),
+//!     ],
+//! );
+//!
+//! let changed = changed_paths(&hash_tree(&old), &hash_tree(&new));
+//! assert_eq!(changed, vec![TreePath::new([1, 0])]);
+//! ```
+use crate::{Node, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::{Hash, Hasher};
+
+/// the markers of the node variants in the hash of a node
+const ELEMENT: u8 = 0;
+const NODE_LIST: u8 = 1;
+const FRAGMENT: u8 = 2;
+const LEAF: u8 = 3;
+
+/// The hashes of a node and its descendants, in the same shape as the node tree.
+///
+/// The children are the nodes which are addressed by the `TreePath`s,
+/// which includes the nodes of the fragments and node lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MerkleTree {
+    /// the hash of the node itself, such as its tag and attributes, without its children
+    pub node_hash: u64,
+    /// the hash of the whole subtree, combining the `node_hash` and the hashes of the children
+    pub hash: u64,
+    /// the hash trees of the children
+    pub children: Vec<MerkleTree>,
+}
+
+impl MerkleTree {
+    /// return the hash tree of the node at `path`, None if there is no node at `path`
+    pub fn get(&self, path: &TreePath) -> Option<&MerkleTree> {
+        path.indices()
+            .try_fold(self, |tree, idx| tree.children.get(idx))
+    }
+}
+
+/// compute the hash tree of the `node`
+pub fn hash_tree<Ns, Tag, Leaf, Att, Val>(
+    node: &Node<Ns, Tag, Leaf, Att, Val>,
+) -> MerkleTree
+where
+    Ns: PartialEq + Clone + Debug + Hash,
+    Tag: PartialEq + Debug + Hash,
+    Leaf: PartialEq + Clone + Debug + Hash,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug + Hash,
+{
+    let mut hasher = Fnv::new();
+    match node {
+        Node::Element(element) => {
+            hasher.write_u8(ELEMENT);
+            element.namespace.hash(&mut hasher);
+            element.tag.hash(&mut hasher);
+            element.attrs.hash(&mut hasher);
+            element.self_closing.hash(&mut hasher);
+            if let Some(slots) = &element.slots {
+                slots.hash(&mut hasher);
+            }
+        }
+        Node::NodeList(_) => hasher.write_u8(NODE_LIST),
+        Node::Fragment(_) => hasher.write_u8(FRAGMENT),
+        Node::Leaf(leaf) => {
+            hasher.write_u8(LEAF);
+            leaf.hash(&mut hasher);
+        }
+    }
+    let node_hash = hasher.finish();
+
+    let children: Vec<MerkleTree> =
+        node.child_nodes().iter().map(hash_tree).collect();
+    let mut hasher = Fnv::new();
+    hasher.write_u64(node_hash);
+    hasher.write_usize(children.len());
+    for child in children.iter() {
+        hasher.write_u64(child.hash);
+    }
+    MerkleTree {
+        node_hash,
+        hash: hasher.finish(),
+        children,
+    }
+}
+
+/// compute the structural hash of the whole subtree of `node`, see `Node::structural_hash`
+pub(crate) fn structural_hash<Ns, Tag, Leaf, Att, Val>(
+    node: &Node<Ns, Tag, Leaf, Att, Val>,
+    excluded: &[Att],
+) -> u64
+where
+    Ns: PartialEq + Clone + Debug + Hash,
+    Tag: PartialEq + Debug + Hash,
+    Leaf: PartialEq + Clone + Debug + Hash,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug + Hash,
+{
+    let mut hasher = Fnv::new();
+    // the nodes are hashed in pre-order followed by their number of children,
+    // so the trees of different shapes are hashed from different sequences
+    let mut pending = vec![node];
+    while let Some(node) = pending.pop() {
+        match node {
+            Node::Element(element) => {
+                hasher.write_u8(ELEMENT);
+                element.namespace.hash(&mut hasher);
+                element.tag.hash(&mut hasher);
+                // the hashes of the attributes are summed, so their order does not matter
+                let attrs = element
+                    .attrs
+                    .iter()
+                    .filter(|att| !excluded.contains(&att.name))
+                    .fold(0u64, |sum, att| {
+                        let mut hasher = Fnv::new();
+                        att.hash(&mut hasher);
+                        sum.wrapping_add(hasher.finish())
+                    });
+                hasher.write_u64(attrs);
+                element.self_closing.hash(&mut hasher);
+                if let Some(slots) = &element.slots {
+                    slots.hash(&mut hasher);
+                }
+            }
+            Node::NodeList(_) => hasher.write_u8(NODE_LIST),
+            Node::Fragment(_) => hasher.write_u8(FRAGMENT),
+            Node::Leaf(leaf) => {
+                hasher.write_u8(LEAF);
+                leaf.hash(&mut hasher);
+            }
+        }
+        let children = node.child_nodes();
+        hasher.write_usize(children.len());
+        pending.extend(children.iter().rev());
+    }
+    hasher.finish()
+}
+
+/// return the paths of the top-most subtrees which are changed from the `old` hash tree
+/// to the `new` hash tree, in the order of the traversal.
+///
+/// A node whose own hash or number of children changed is returned as a whole,
+/// otherwise only its changed children are returned.
+pub fn changed_paths(old: &MerkleTree, new: &MerkleTree) -> Vec<TreePath> {
+    let mut changed = vec![];
+    collect_changed(old, new, &TreePath::root(), &mut changed);
+    changed
+}
+
+fn collect_changed(
+    old: &MerkleTree,
+    new: &MerkleTree,
+    path: &TreePath,
+    changed: &mut Vec<TreePath>,
+) {
+    if old.hash == new.hash {
+        return;
+    }
+    if old.node_hash != new.node_hash
+        || old.children.len() != new.children.len()
+    {
+        changed.push(path.clone());
+        return;
+    }
+    for (idx, (old_child, new_child)) in
+        old.children.iter().zip(new.children.iter()).enumerate()
+    {
+        collect_changed(old_child, new_child, &path.traverse(idx), changed);
+    }
+}
+
+/// the 64-bit FNV-1a hasher, which hashes the integers in little endian
+/// so the hashes do not depend on the platform
+#[derive(Debug)]
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub(crate) fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
@@ -1,12 +1,21 @@
+#[cfg(feature = "apply-patches")]
+use crate::{apply_patches::apply_patches, patch::OwnedPatch};
+use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 pub use attribute::Attribute;
 use core::fmt;
 use core::fmt::{Debug, Formatter};
 use core::hash::Hash;
 pub use element::Element;
+pub use tokened_leaf::TokenedLeaf;
 
 pub(crate) mod attribute;
+#[cfg(feature = "node-cache")]
+pub(crate) mod cache;
 mod element;
+pub(crate) mod pool;
+mod tokened_leaf;
 
 /// represents a node in a virtual dom
 /// A node could be an element which can contain one or more children of nodes.
@@ -21,7 +30,10 @@ mod element;
 /// virtual dom implementation
 /// Val - is the type for the value of the attribute, this will be String, f64, or just another
 /// generics that suits the implementing library which used mt-dom for just dom-diffing purposes
-#[derive(Clone, Debug, PartialEq)]
+// the manual `PartialEq` compares the same as the derived one, so it agrees with `Hash`
+#[allow(clippy::derived_hash_with_manual_eq)]
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Node<Ns, Tag, Leaf, Att, Val>
 where
     Ns: PartialEq + Clone + Debug,
@@ -41,6 +53,95 @@ where
     Leaf(Leaf),
 }
 
+/// The nodes are compared with an explicit stack instead of recursing into the children,
+/// so comparing very deep trees does not overflow the stack
+impl<Ns, Tag, Leaf, Att, Val> PartialEq for Node<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some((this, other)) = pending.pop() {
+            let (these, others) = match (this, other) {
+                (Node::Element(this), Node::Element(other)) => {
+                    if this.namespace != other.namespace
+                        || this.tag != other.tag
+                        || this.attrs != other.attrs
+                        || this.self_closing != other.self_closing
+                        || this.slots != other.slots
+                    {
+                        return false;
+                    }
+                    (&this.children, &other.children)
+                }
+                (Node::NodeList(these), Node::NodeList(others))
+                | (Node::Fragment(these), Node::Fragment(others)) => {
+                    (these, others)
+                }
+                (Node::Leaf(this), Node::Leaf(other)) => {
+                    if this != other {
+                        return false;
+                    }
+                    continue;
+                }
+                _ => return false,
+            };
+            if these.len() != others.len() {
+                return false;
+            }
+            // compare the children in order, the first child is popped first
+            pending.extend(these.iter().zip(others.iter()).rev());
+        }
+        true
+    }
+}
+
+/// the structural hashes need the parts of the nodes to be hashable
+impl<Ns, Tag, Leaf, Att, Val> Node<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Hash,
+    Tag: PartialEq + Debug + Hash,
+    Leaf: PartialEq + Clone + Debug + Hash,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug + Hash,
+{
+    /// Return the hash of the structure of the whole subtree of this node,
+    /// which are the tags, the attributes and the leaves of the nodes,
+    /// where the order of the attributes of an element does not change the hash.
+    /// This can be used for memoization, deduplication and change detection,
+    /// such as the hash of `Node::memoized`.
+    ///
+    /// The hash is computed with the same 64-bit FNV-1a as `merkle::hash_tree`,
+    /// so it is the same across processes and platforms.
+    /// The `Element::memo` is not part of the structure, so it is not hashed.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mt_dom::*;
+    ///
+    /// pub type MyNode =
+    ///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+    ///
+    /// let a: MyNode = element("div", vec![attr("id", "a"), attr("class", "b")], vec![]);
+    /// let b: MyNode = element("div", vec![attr("class", "b"), attr("id", "a")], vec![]);
+    /// assert_eq!(a.structural_hash(), b.structural_hash());
+    /// ```
+    pub fn structural_hash(&self) -> u64 {
+        self.structural_hash_excluding(&[])
+    }
+
+    /// Return the `structural_hash` of this node, where the attributes whose names are
+    /// in `excluded` are not hashed, such as the event listeners which are created anew
+    /// on each render
+    pub fn structural_hash_excluding(&self, excluded: &[Att]) -> u64 {
+        crate::merkle::structural_hash(self, excluded)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Error {
     AddChildrenNotAllowed,
@@ -124,6 +225,22 @@ where
         }
     }
 
+    /// Mark the element `node` with the `hash` of its whole subtree, such as the hash
+    /// of the props of the component which rendered it, so diffing the memoized elements
+    /// with the same hash skips their subtrees without comparing them.
+    /// Only the elements are memoized, the other nodes are returned as they are.
+    pub fn memoized(hash: u64, mut node: Self) -> Self {
+        if let Some(element) = node.element_mut() {
+            element.memo = Some(hash);
+        }
+        node
+    }
+
+    /// returns the hash of the subtree if this node is a memoized element
+    pub fn memo(&self) -> Option<u64> {
+        self.element_ref().and_then(|element| element.memo)
+    }
+
     /// Consume a mutable self and add a children to this node it if is an element
     /// will have no effect if it is a text node.
     /// This is used in building the nodes in a builder pattern
@@ -139,16 +256,36 @@ where
         self
     }
 
-    /// add children but not consume self
+    /// Drop the node tree one level at a time. The `Drop` of the node recurses into
+    /// the children, which overflows the stack for the very deep trees, such as a chain
+    /// of 100k nested elements.
+    pub fn dismantle(self) {
+        let mut pending = vec![self];
+        while let Some(node) = pending.pop() {
+            match node {
+                Node::Element(mut element) => {
+                    pending.append(&mut element.children)
+                }
+                Node::Fragment(mut nodes) | Node::NodeList(mut nodes) => {
+                    pending.append(&mut nodes)
+                }
+                Node::Leaf(_) => (),
+            }
+        }
+    }
+
+    /// add children but not consume self, which is not allowed on the leaves and the
+    /// elements whose children are keyed by their slots
     pub fn add_children(
         &mut self,
         children: impl IntoIterator<Item = Node<Ns, Tag, Leaf, Att, Val>>,
     ) -> Result<(), Error> {
-        if let Some(element) = self.element_mut() {
-            element.add_children(children);
-            Ok(())
-        } else {
-            Err(Error::AddChildrenNotAllowed)
+        match self.element_mut() {
+            Some(element) if !element.is_slotted() => {
+                element.add_children(children);
+                Ok(())
+            }
+            _ => Err(Error::AddChildrenNotAllowed),
         }
     }
 
@@ -208,6 +345,15 @@ where
         }
     }
 
+    /// return the children of this node, including the nodes of a fragment or a node list
+    pub(crate) fn child_nodes(&self) -> &[Node<Ns, Tag, Leaf, Att, Val>] {
+        match self {
+            Node::Element(element) => element.children(),
+            Node::Fragment(nodes) | Node::NodeList(nodes) => nodes,
+            Node::Leaf(_) => &[],
+        }
+    }
+
     /// Return the count of the children of this node
     pub fn children_count(&self) -> usize {
         self.children().len()
@@ -275,6 +421,55 @@ where
         cnt
     }
 
+    /// Returns the number of leaves in this node tree, including the leaves in fragments
+    /// and node lists. This is useful for deciding whether to virtualize a subtree.
+    ///
+    /// This walks the node tree on each call, since the tree can be mutated freely
+    /// through its public fields, cache the result if it is needed more than once.
+    pub fn leaf_count(&self) -> usize {
+        match self {
+            Node::Leaf(_) => 1,
+            _ => self.child_nodes().iter().map(Node::leaf_count).sum(),
+        }
+    }
+
+    /// Returns the total length, in bytes, of the text of the leaves in this node tree.
+    ///
+    /// Just like `leaf_count`, this walks the node tree on each call.
+    pub fn text_len(&self) -> usize
+    where
+        Leaf: AsRef<str>,
+    {
+        match self {
+            Node::Leaf(leaf) => leaf.as_ref().len(),
+            _ => self.child_nodes().iter().map(Node::text_len).sum(),
+        }
+    }
+
+    /// Render the node tree with a line for each node, indented by its depth,
+    /// where the elements are rendered with their tag and attributes and the leaves
+    /// with their `Debug` output. This is intended for debugging, the same as
+    /// `patch::render_patches`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mt_dom::*;
+    ///
+    /// pub type MyNode =
+    ///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+    ///
+    /// let node: MyNode = element("ul", vec![attr("class", "list")], vec![
+    ///     element("li", vec![attr("key", "1")], vec![leaf("one")]),
+    /// ]);
+    /// assert_eq!(
+    ///     node.render_tree(),
+    ///     "<ul class=\"list\">\n  <li key=\"1\">\n    \"one\"\n"
+    /// );
+    /// ```
+    pub fn render_tree(&self) -> String {
+        crate::patch::display::render_tree(self)
+    }
+
     /// remove the existing attributes and set with the new value
     pub fn set_attributes(
         &mut self,
@@ -299,6 +494,15 @@ where
         self
     }
 
+    /// returns true if this node is an element which has an attribute with the name `name`
+    pub fn has_attribute(&self, name: &Att) -> bool {
+        if let Some(elm) = self.element_ref() {
+            elm.has_attribute(name)
+        } else {
+            false
+        }
+    }
+
     /// returh the attribute values of this node which match the attribute name `name`
     pub fn attribute_value(&self, name: &Att) -> Option<Vec<&Val>> {
         if let Some(elm) = self.element_ref() {
@@ -307,6 +511,81 @@ where
             None
         }
     }
+
+    /// iterate over the attribute values of this node which match the attribute name `name`,
+    /// without allocating
+    pub fn attribute_values_iter<'n>(
+        &'n self,
+        name: &'n Att,
+    ) -> impl Iterator<Item = &'n Val> + 'n {
+        self.element_ref()
+            .into_iter()
+            .flat_map(move |elm| elm.attribute_values_iter(name))
+    }
+
+    /// return the values of the attribute of this node with the name `name` as a slice,
+    /// when this node is an element which has exactly one attribute with that name
+    pub fn attribute_values_slice(&self, name: &Att) -> Option<&[Val]> {
+        self.element_ref()?.attribute_values_slice(name)
+    }
+}
+
+#[cfg(feature = "apply-patches")]
+impl<Ns, Tag, Leaf, Att, Val> Node<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// Construct the node tree by applying a batch of owned patches into `base`,
+    /// so a thin client can maintain the full virtual tree from the patch stream of a server
+    /// and query it locally.
+    ///
+    /// When `base` is `None` the tree starts as an empty fragment, and the batch is
+    /// expected to start with the bootstrap patch created with `OwnedPatch::bootstrap`.
+    /// The paths of the patches in a batch all point to the tree before the batch is applied,
+    /// so each batch of the stream is applied with a separate call.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mt_dom::{patch::*, *};
+    ///
+    /// pub type MyNode =
+    ///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+    ///
+    /// let first: MyNode = element("main", vec![], vec![leaf("hello")]);
+    /// let second: MyNode = element("main", vec![attr("class", "container")], vec![leaf("world")]);
+    ///
+    /// // the server sends the bootstrap batch followed by the batch of each update
+    /// let stream = vec![
+    ///     vec![OwnedPatch::bootstrap(first.clone())],
+    ///     diff_with_key(&first, &second, &"key")
+    ///         .into_iter()
+    ///         .map(Patch::into_owned)
+    ///         .collect(),
+    /// ];
+    ///
+    /// let mut tree = None;
+    /// for batch in stream.iter() {
+    ///     tree = Some(Node::from_patches(tree, batch));
+    /// }
+    /// assert_eq!(tree, Some(second));
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the path of a patch can not be resolved in the tree
+    pub fn from_patches(
+        base: Option<Self>,
+        patches: &[OwnedPatch<Ns, Tag, Leaf, Att, Val>],
+    ) -> Self {
+        let mut root = base.unwrap_or_else(|| Node::Fragment(Vec::new()));
+        let patches: Vec<_> =
+            patches.iter().map(OwnedPatch::as_patch).collect();
+        apply_patches(&mut root, &patches);
+        root
+    }
 }
 
 /// create a virtual node with tag, attrs and children
@@ -368,6 +647,37 @@ where
     Node::Element(Element::new(namespace, tag, attrs, children, self_closing))
 }
 
+/// create a virtual node with tag, attrs and the children keyed by their slots
+/// # Example
+/// ```rust
+/// use mt_dom::{Node,slotted_element,leaf};
+///
+/// let split:Node<&'static str, &'static str, &'static str, &'static str, &'static str> =
+///     slotted_element(
+///          "split",
+///          vec![],
+///          vec![("north", leaf("top")), ("south", leaf("bottom"))],
+///      );
+/// assert_eq!(split.element_ref().unwrap().slot(&"south"), Some(&leaf("bottom")));
+/// ```
+///
+/// # Panics
+/// Panics if a slot name is used more than once
+pub fn slotted_element<Ns, Tag, Leaf, Att, Val>(
+    tag: Tag,
+    attrs: impl IntoIterator<Item = Attribute<Ns, Att, Val>>,
+    slotted: impl IntoIterator<Item = (Att, Node<Ns, Tag, Leaf, Att, Val>)>,
+) -> Node<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    Node::Element(Element::new_slotted(None, tag, attrs, slotted, false))
+}
+
 /// create a leaf node
 pub fn leaf<Ns, Tag, Leaf, Att, Val>(
     leaf: Leaf,
@@ -6,7 +6,8 @@ use core::hash::Hash;
 use indexmap::IndexMap;
 
 /// These are the plain attributes of an element
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Attribute<Ns, Att, Val>
 where
     Ns: PartialEq + Clone + Debug,
@@ -52,6 +53,21 @@ where
         }
     }
 
+    /// create a presence-only attribute which has no value,
+    /// such as `disabled` in `<input disabled>`
+    pub fn empty(namespace: Option<Ns>, name: Att) -> Self {
+        Attribute {
+            name,
+            value: vec![],
+            namespace,
+        }
+    }
+
+    /// returns true if this is a presence-only attribute which has no value
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
     /// return the name of this attribute
     pub fn name(&self) -> &Att {
         &self.name
@@ -107,6 +123,29 @@ where
     Attribute::new(namespace, name, value)
 }
 
+/// Create a presence-only attribute which has no value
+///
+/// Adding or removing an empty attribute produces an `AddAttributes` or
+/// `RemoveAttributes` patch, while an empty attribute which is present in
+/// both the old and new element will not produce any patch.
+/// # Example
+/// ```rust
+/// use mt_dom::{Attribute,empty_attr};
+///
+/// let disabled: Attribute<&'static str, &'static str, &'static str> =
+///     empty_attr("disabled");
+/// assert!(disabled.is_empty());
+/// ```
+#[inline]
+pub fn empty_attr<Ns, Att, Val>(name: Att) -> Attribute<Ns, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    Attribute::empty(None, name)
+}
+
 /// merge the values of attributes with the same name
 #[doc(hidden)]
 pub fn merge_attributes_of_same_name<Ns, Att, Val>(
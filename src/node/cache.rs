@@ -0,0 +1,134 @@
+//! hash-consing of structurally identical nodes
+use crate::Node;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+/// A cache which deduplicates structurally identical whole trees.
+///
+/// Interning a node which is equal to a node that is already in the cache
+/// returns the shared `Arc` of the cached node instead of storing a new one.
+/// Since the diffing functions checks for pointer equality first, diffing
+/// two renders of a static view which are interned from the same cache
+/// skips the diff entirely.
+///
+/// Only the interned root is shared, the children of an element are owned by it,
+/// so two trees which differ in one leaf share nothing and are diffed as usual.
+/// Each call to `intern` hashes the whole tree and compares it against the cached
+/// trees with the same hash, so it costs as much as one diff of the tree, and is
+/// only worth it when the shared tree is diffed or kept around more than once.
+///
+/// The cache keeps every interned tree until `clear` or `evict_unused` is called.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff_with_key, element, leaf, attr, Node, NodeCache};
+/// use std::sync::Arc;
+///
+/// type MyNode =
+///     Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let mut cache = NodeCache::new();
+/// let view = || -> MyNode { element("div", [attr("class", "static")], [leaf("hello")]) };
+///
+/// let old = cache.intern(view());
+/// let new = cache.intern(view());
+/// assert!(Arc::ptr_eq(&old, &new));
+/// assert!(diff_with_key(&old, &new, &"key").is_empty());
+/// ```
+#[derive(Debug)]
+pub struct NodeCache<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Hash,
+    Tag: PartialEq + Debug + Hash,
+    Leaf: PartialEq + Clone + Debug + Hash,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug + Hash,
+{
+    /// the interned nodes bucketed by their hash
+    buckets: BTreeMap<u64, Vec<Arc<Node<Ns, Tag, Leaf, Att, Val>>>>,
+}
+
+impl<Ns, Tag, Leaf, Att, Val> NodeCache<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Hash,
+    Tag: PartialEq + Debug + Hash,
+    Leaf: PartialEq + Clone + Debug + Hash,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug + Hash,
+{
+    /// create an empty node cache
+    pub fn new() -> Self {
+        Self {
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// return the shared node which is structurally identical to `node`,
+    /// `node` is stored in the cache if there is none yet.
+    ///
+    /// This is O(n) in the size of `node`, for the hash and the equality check.
+    pub fn intern(
+        &mut self,
+        node: Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> Arc<Node<Ns, Tag, Leaf, Att, Val>> {
+        let mut hasher = DefaultHasher::new();
+        node.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if let Some(bucket) = self.buckets.get_mut(&hash) {
+            if let Some(existing) = bucket.iter().find(|n| ***n == node) {
+                Arc::clone(existing)
+            } else {
+                let node = Arc::new(node);
+                bucket.push(Arc::clone(&node));
+                node
+            }
+        } else {
+            let node = Arc::new(node);
+            self.buckets.insert(hash, vec![Arc::clone(&node)]);
+            node
+        }
+    }
+
+    /// return the number of distinct nodes in this cache
+    pub fn len(&self) -> usize {
+        self.buckets.values().map(|bucket| bucket.len()).sum()
+    }
+
+    /// returns true if there are no nodes in this cache
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// remove all the nodes in this cache
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+
+    /// remove the nodes which are no longer used outside of this cache,
+    /// such as the views of the previous renders which are dropped.
+    pub fn evict_unused(&mut self) {
+        self.buckets.retain(|_, bucket| {
+            bucket.retain(|node| Arc::strong_count(node) > 1);
+            !bucket.is_empty()
+        });
+    }
+}
+
+impl<Ns, Tag, Leaf, Att, Val> Default for NodeCache<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Hash,
+    Tag: PartialEq + Debug + Hash,
+    Leaf: PartialEq + Clone + Debug + Hash,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,7 +2,7 @@ use crate::node::{Attribute, Node};
 use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::Debug;
-use core::hash::Hash;
+use core::hash::{Hash, Hasher};
 
 /// Represents an element of the virtual node
 /// An element has a generic tag, this tag could be a static str tag, such as usage in html dom.
@@ -17,7 +17,8 @@ use core::hash::Hash;
 ///
 /// The namespace is also needed in attributes where namespace are necessary such as `xlink:href`
 /// where the namespace `xlink` is needed in order for the linked element in an svg image to work.
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Element<Ns, Tag, Leaf, Att, Val>
 where
     Ns: PartialEq + Clone + Debug,
@@ -37,6 +38,66 @@ where
     pub children: Vec<Node<Ns, Tag, Leaf, Att, Val>>,
     /// is the element has a self closing tag
     pub self_closing: bool,
+    /// the slot names of the children, in the same order as the children,
+    /// when the children are keyed by their slots instead of their positions,
+    /// such as the `north` and `south` panes of a native split view.
+    /// The slotted children are diffed by their slot names, see `PatchType::SetSlot`
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default = "Option::default",
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub slots: Option<Vec<Att>>,
+    /// the hash of the whole subtree which is supplied by the framework,
+    /// such as the hash of the props of the component which rendered this element.
+    /// The memoized elements with the same hash are not diffed, see `Node::memoized`.
+    /// The memo is not compared nor hashed with the rest of the element,
+    /// since it only stands for the content of the element
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub memo: Option<u64>,
+}
+
+/// the same as the derived `PartialEq`, except the `memo` is not compared
+impl<Ns, Tag, Leaf, Att, Val> PartialEq for Element<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.namespace == other.namespace
+            && self.tag == other.tag
+            && self.attrs == other.attrs
+            && self.children == other.children
+            && self.self_closing == other.self_closing
+            && self.slots == other.slots
+    }
+}
+
+/// the same as the derived `Hash`, except the `memo` is not hashed, so it agrees with `PartialEq`
+impl<Ns, Tag, Leaf, Att, Val> Hash for Element<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug + Hash,
+    Tag: PartialEq + Debug + Hash,
+    Leaf: PartialEq + Clone + Debug + Hash,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.namespace.hash(state);
+        self.tag.hash(state);
+        self.attrs.hash(state);
+        self.children.hash(state);
+        self.self_closing.hash(state);
+        self.slots.hash(state);
+    }
 }
 
 impl<Ns, Tag, Leaf, Att, Val> Element<Ns, Tag, Leaf, Att, Val>
@@ -69,9 +130,32 @@ where
             attrs: attrs.into_iter().collect(),
             children,
             self_closing,
+            slots: None,
+            memo: None,
         }
     }
 
+    /// create a new instance of an element whose children are keyed by their slots,
+    /// the children are kept in the order of the `slotted` pairs
+    ///
+    /// # Panics
+    /// Panics if a slot name is used more than once
+    pub fn new_slotted(
+        namespace: Option<Ns>,
+        tag: Tag,
+        attrs: impl IntoIterator<Item = Attribute<Ns, Att, Val>>,
+        slotted: impl IntoIterator<Item = (Att, Node<Ns, Tag, Leaf, Att, Val>)>,
+        self_closing: bool,
+    ) -> Self {
+        let mut element = Self::new(namespace, tag, attrs, [], self_closing);
+        element.slots = Some(vec![]);
+        for (slot, child) in slotted {
+            assert!(!element.has_slot(&slot), "duplicate slot {:?}", slot);
+            element.set_slot(slot, child);
+        }
+        element
+    }
+
     /// add attributes to this element
     pub fn add_attributes(
         &mut self,
@@ -81,11 +165,19 @@ where
     }
 
     /// add children virtual node to this element
+    ///
+    /// # Panics
+    /// Panics if the children of the element are keyed by their slots,
+    /// since the added children would have no slots, use `set_slot` instead
     pub fn add_children(
         &mut self,
         children: impl IntoIterator<Item = Node<Ns, Tag, Leaf, Att, Val>>,
     ) {
-        self.children.extend(children.into_iter());
+        assert!(
+            !self.is_slotted(),
+            "the children are keyed by their slots, use `set_slot` instead"
+        );
+        self.children.extend(children);
     }
 
     /// returns a refernce to the children of this node
@@ -109,6 +201,9 @@ where
         &mut self,
         index: usize,
     ) -> Node<Ns, Tag, Leaf, Att, Val> {
+        if let Some(slots) = self.slots.as_mut() {
+            slots.swap_remove(index);
+        }
         self.children.swap_remove(index)
     }
 
@@ -122,9 +217,85 @@ where
     /// Panics if both `a` and `b` are out of bounds
     ///
     pub fn swap_children(&mut self, a: usize, b: usize) {
+        if let Some(slots) = self.slots.as_mut() {
+            slots.swap(a, b);
+        }
         self.children.swap(a, b)
     }
 
+    /// returns true if the children of this element are keyed by their slots
+    pub fn is_slotted(&self) -> bool {
+        self.slots.is_some()
+    }
+
+    /// returns true if there is a slot name for each of the children when the children
+    /// are keyed by their slots, which is only broken by changing `slots` or `children`
+    /// directly
+    pub(crate) fn has_slot_per_child(&self) -> bool {
+        self.slots
+            .as_ref()
+            .is_none_or(|slots| slots.len() == self.children.len())
+    }
+
+    /// iterate over the slot names and the children of this element,
+    /// this is empty if the children are not keyed by their slots
+    pub fn slotted_children(
+        &self,
+    ) -> impl Iterator<Item = (&Att, &Node<Ns, Tag, Leaf, Att, Val>)> {
+        self.slots.iter().flatten().zip(self.children.iter())
+    }
+
+    /// returns true if there is a child in the `slot`
+    pub fn has_slot(&self, slot: &Att) -> bool {
+        self.slot_position(slot).is_some()
+    }
+
+    /// return the child in the `slot`
+    pub fn slot(&self, slot: &Att) -> Option<&Node<Ns, Tag, Leaf, Att, Val>> {
+        self.slot_position(slot)
+            .and_then(|index| self.children.get(index))
+    }
+
+    /// set the `child` into the `slot`, replacing the child which is in the slot.
+    /// The slot is moved after the other slots, the same as applying a `SetSlot` patch
+    ///
+    /// # Panics
+    /// Panics if the element has children which are not keyed by their slots
+    pub fn set_slot(
+        &mut self,
+        slot: Att,
+        child: Node<Ns, Tag, Leaf, Att, Val>,
+    ) {
+        self.clear_slot(&slot);
+        let slots = self.slots.get_or_insert_with(Vec::new);
+        assert_eq!(
+            slots.len(),
+            self.children.len(),
+            "the children must be keyed by their slots"
+        );
+        slots.push(slot);
+        self.children.push(child);
+    }
+
+    /// remove the child in the `slot` and return it
+    pub fn clear_slot(
+        &mut self,
+        slot: &Att,
+    ) -> Option<Node<Ns, Tag, Leaf, Att, Val>> {
+        let index = self.slot_position(slot)?;
+        if let Some(slots) = self.slots.as_mut() {
+            slots.remove(index);
+        }
+        Some(self.children.remove(index))
+    }
+
+    /// the index of the child in the `slot`
+    pub(crate) fn slot_position(&self, slot: &Att) -> Option<usize> {
+        self.slots
+            .as_ref()
+            .and_then(|slots| slots.iter().position(|name| name == slot))
+    }
+
     /// consume self and return the children
     pub fn take_children(self) -> Vec<Node<Ns, Tag, Leaf, Att, Val>> {
         self.children
@@ -193,6 +364,12 @@ where
         }
     }
 
+    /// returns true if this element has an attribute with the name `name`,
+    /// this includes presence-only attributes which has no values
+    pub fn has_attribute(&self, name: &Att) -> bool {
+        self.attrs.iter().any(|att| att.name == *name)
+    }
+
     /// return all the attribute values which the name &Att
     pub fn attribute_value(&self, name: &Att) -> Option<Vec<&Val>> {
         let result: Vec<&Val> = self
@@ -208,4 +385,26 @@ where
             Some(result)
         }
     }
+
+    /// iterate over all the attribute values which the name &Att, without allocating
+    pub fn attribute_values_iter<'e>(
+        &'e self,
+        name: &'e Att,
+    ) -> impl Iterator<Item = &'e Val> + 'e {
+        self.attrs
+            .iter()
+            .filter(move |att| att.name == *name)
+            .flat_map(|att| att.value())
+    }
+
+    /// return the values of the attribute with the name &Att as a slice, when the element
+    /// has exactly one attribute with that name, which is the case for the keys.
+    /// Use `attribute_values_iter` to also get the values of the repeated attributes.
+    pub fn attribute_values_slice(&self, name: &Att) -> Option<&[Val]> {
+        let mut named = self.attrs.iter().filter(|att| att.name == *name);
+        match (named.next(), named.next()) {
+            (Some(att), None) => Some(att.value()),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,222 @@
+//! recycling of the allocations of dropped node trees
+use crate::node::{Attribute, Element, Node};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// A pool which recycles the vectors of the node trees which are no longer needed,
+/// such as the previous frame after it is diffed, and reuses their capacity when
+/// constructing new nodes with the pooled constructors.
+///
+/// This cuts the allocation churn of frameworks which rebuild the entire virtual tree every frame.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{Node, NodePool};
+///
+/// type MyNode =
+///     Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let mut pool = NodePool::new();
+/// let view = |pool: &mut NodePool<_, _, _, _, _>| -> MyNode {
+///     let label = pool.leaf("hello");
+///     let class = pool.attr("class", "container");
+///     pool.element("div", [class], [label])
+/// };
+/// let old = view(&mut pool);
+/// let new = view(&mut pool);
+/// assert_eq!(old, new);
+///
+/// // the vectors of `old` are reused by the next frame
+/// pool.recycle(old);
+/// assert!(!pool.is_empty());
+/// let next = view(&mut pool);
+/// assert_eq!(next, new);
+/// ```
+#[derive(Debug)]
+pub struct NodePool<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the empty vectors for the children of the elements, fragments and node lists
+    children: Vec<Vec<Node<Ns, Tag, Leaf, Att, Val>>>,
+    /// the empty vectors for the attributes of the elements
+    attrs: Vec<Vec<Attribute<Ns, Att, Val>>>,
+    /// the empty vectors for the values of the attributes
+    values: Vec<Vec<Val>>,
+}
+
+impl<Ns, Tag, Leaf, Att, Val> NodePool<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// create an empty node pool
+    pub fn new() -> Self {
+        Self {
+            children: vec![],
+            attrs: vec![],
+            values: vec![],
+        }
+    }
+
+    /// take the vectors of the node tree into this pool, the content of the node tree is dropped
+    pub fn recycle(&mut self, node: Node<Ns, Tag, Leaf, Att, Val>) {
+        // use a stack instead of recursion, so deep trees can be recycled
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            let children = match node {
+                Node::Element(element) => {
+                    let mut attrs = element.attrs;
+                    for attr in attrs.drain(..) {
+                        self.recycle_values(attr.value);
+                    }
+                    if attrs.capacity() > 0 {
+                        self.attrs.push(attrs);
+                    }
+                    element.children
+                }
+                Node::Fragment(nodes) | Node::NodeList(nodes) => nodes,
+                Node::Leaf(_) => continue,
+            };
+            self.recycle_children(children, &mut stack);
+        }
+    }
+
+    fn recycle_children(
+        &mut self,
+        mut children: Vec<Node<Ns, Tag, Leaf, Att, Val>>,
+        stack: &mut Vec<Node<Ns, Tag, Leaf, Att, Val>>,
+    ) {
+        stack.append(&mut children);
+        if children.capacity() > 0 {
+            self.children.push(children);
+        }
+    }
+
+    fn recycle_values(&mut self, mut values: Vec<Val>) {
+        if values.capacity() > 0 {
+            values.clear();
+            self.values.push(values);
+        }
+    }
+
+    /// create an element using the pooled vectors, the same as `mt_dom::element`
+    pub fn element(
+        &mut self,
+        tag: Tag,
+        attrs: impl IntoIterator<Item = Attribute<Ns, Att, Val>>,
+        children: impl IntoIterator<Item = Node<Ns, Tag, Leaf, Att, Val>>,
+    ) -> Node<Ns, Tag, Leaf, Att, Val> {
+        self.element_ns(None, tag, attrs, children, false)
+    }
+
+    /// create an element with namespace using the pooled vectors, the same as `mt_dom::element_ns`
+    pub fn element_ns(
+        &mut self,
+        namespace: Option<Ns>,
+        tag: Tag,
+        attrs: impl IntoIterator<Item = Attribute<Ns, Att, Val>>,
+        children: impl IntoIterator<Item = Node<Ns, Tag, Leaf, Att, Val>>,
+        self_closing: bool,
+    ) -> Node<Ns, Tag, Leaf, Att, Val> {
+        let mut pooled_attrs = self.attrs.pop().unwrap_or_default();
+        pooled_attrs.extend(attrs);
+        let mut pooled_children = self.children.pop().unwrap_or_default();
+        for child in children {
+            //unroll the nodelist
+            match child {
+                Node::NodeList(mut node_list) => {
+                    pooled_children.append(&mut node_list);
+                    if node_list.capacity() > 0 {
+                        self.children.push(node_list);
+                    }
+                }
+                _ => pooled_children.push(child),
+            }
+        }
+        Node::Element(Element {
+            namespace,
+            tag,
+            attrs: pooled_attrs,
+            children: pooled_children,
+            self_closing,
+            slots: None,
+            memo: None,
+        })
+    }
+
+    /// create a fragment using the pooled vectors, the same as `mt_dom::fragment`
+    pub fn fragment(
+        &mut self,
+        nodes: impl IntoIterator<Item = Node<Ns, Tag, Leaf, Att, Val>>,
+    ) -> Node<Ns, Tag, Leaf, Att, Val> {
+        let mut pooled = self.children.pop().unwrap_or_default();
+        pooled.extend(nodes);
+        Node::Fragment(pooled)
+    }
+
+    /// create a leaf node, the same as `mt_dom::leaf`
+    pub fn leaf(&mut self, leaf: Leaf) -> Node<Ns, Tag, Leaf, Att, Val> {
+        Node::Leaf(leaf)
+    }
+
+    /// create an attribute using the pooled vectors, the same as `mt_dom::attr`
+    pub fn attr(&mut self, name: Att, value: Val) -> Attribute<Ns, Att, Val> {
+        self.attr_ns(None, name, value)
+    }
+
+    /// create an attribute with namespace using the pooled vectors, the same as `mt_dom::attr_ns`
+    pub fn attr_ns(
+        &mut self,
+        namespace: Option<Ns>,
+        name: Att,
+        value: Val,
+    ) -> Attribute<Ns, Att, Val> {
+        let mut pooled = self.values.pop().unwrap_or_default();
+        pooled.push(value);
+        Attribute {
+            namespace,
+            name,
+            value: pooled,
+        }
+    }
+
+    /// return the number of the vectors in this pool
+    pub fn len(&self) -> usize {
+        self.children.len() + self.attrs.len() + self.values.len()
+    }
+
+    /// returns true if there are no vectors in this pool
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// drop all of the vectors in this pool, releasing their memory
+    pub fn clear(&mut self) {
+        self.children.clear();
+        self.attrs.clear();
+        self.values.clear();
+    }
+}
+
+impl<Ns, Tag, Leaf, Att, Val> Default for NodePool<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,95 @@
+use core::fmt::Debug;
+use core::hash::{Hash, Hasher};
+
+/// A leaf which carries an optional token, such as a handle into a text measurement
+/// or layout cache, which is ignored by the equality and hash of the leaf.
+///
+/// Since the token is ignored, leaves which only differ in their tokens produce no patches,
+/// and the `ChangeLeaf` patch of a changed leaf references both the old and the new leaf,
+/// so the host can migrate the cached measurement of the old token instead of recomputing it.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{diff::*, patch::*, *};
+///
+/// pub type MyNode = Node<
+///     &'static str,
+///     &'static str,
+///     TokenedLeaf<&'static str, u32>,
+///     &'static str,
+///     &'static str,
+/// >;
+///
+/// let old: MyNode = leaf(TokenedLeaf::with_token("hello", 7));
+/// let new: MyNode = leaf(TokenedLeaf::new("hello!"));
+///
+/// let options = DiffOptions {
+///     change_leaf: true,
+///     ..Default::default()
+/// };
+/// let patches = diff_with_options(&old, &new, &"key", &options);
+/// match &patches[0].patch_type {
+///     PatchType::ChangeLeaf { old, new } => {
+///         assert_eq!(old.token, Some(7));
+///         assert_eq!(new.leaf, "hello!");
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokenedLeaf<Leaf, Token> {
+    /// the leaf
+    pub leaf: Leaf,
+    /// the token which is ignored by equality
+    pub token: Option<Token>,
+}
+
+impl<Leaf, Token> TokenedLeaf<Leaf, Token> {
+    /// create a leaf without a token
+    pub fn new(leaf: Leaf) -> Self {
+        Self { leaf, token: None }
+    }
+
+    /// create a leaf with a token
+    pub fn with_token(leaf: Leaf, token: Token) -> Self {
+        Self {
+            leaf,
+            token: Some(token),
+        }
+    }
+}
+
+impl<Leaf, Token> PartialEq for TokenedLeaf<Leaf, Token>
+where
+    Leaf: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.leaf == other.leaf
+    }
+}
+
+impl<Leaf, Token> Eq for TokenedLeaf<Leaf, Token> where Leaf: Eq {}
+
+impl<Leaf, Token> Hash for TokenedLeaf<Leaf, Token>
+where
+    Leaf: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.leaf.hash(state);
+    }
+}
+
+impl<Leaf, Token> From<Leaf> for TokenedLeaf<Leaf, Token> {
+    fn from(leaf: Leaf) -> Self {
+        Self::new(leaf)
+    }
+}
+
+impl<Leaf, Token> AsRef<str> for TokenedLeaf<Leaf, Token>
+where
+    Leaf: AsRef<str>,
+{
+    fn as_ref(&self) -> &str {
+        self.leaf.as_ref()
+    }
+}
@@ -0,0 +1,468 @@
+//! Parse html or xml markup into node trees, for hydrating the server rendered markup:
+//! the parsed tree is diffed against the virtual tree of the client,
+//! so only the differences are patched instead of rendering the whole page again.
+//!
+//! This is a small parser for the markup of the renderers, such as
+//! `html::node_to_html_string`, and not a conforming html5 parser:
+//! - the comments, the doctype and the processing instructions are skipped
+//! - the `xmlns` attribute sets the namespace of the element and its descendants,
+//!   and is not kept as an attribute
+//! - in html, the void elements such as `br` have no closing tag, and the content of
+//!   `script` and `style` is a single leaf which is not parsed
+//! - the optional closing tags of html, such as of `p` and `li`, are not inferred
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{parse::*, *};
+//!
+//! let server = r#"<ul class="todo"><li>milk &amp; eggs</li><li><br></li></ul>"#;
+//! let nodes = parse(server, &ParseOptions::default()).unwrap();
+//!
+//! let client: Node<&str, &str, &str, &str, &str> = element(
+//!     "ul",
+//!     vec![attr("class", "todo")],
+//!     vec![
+//!         element("li", vec![], vec![leaf("milk &amp; eggs")]),
+//!         element("li", vec![], vec![element("br", vec![], vec![])]),
+//!     ],
+//! );
+//! assert!(diff_with_key(&nodes[0], &client, &"key").is_empty());
+//!
+//! // the owned nodes have their entities decoded
+//! let nodes = parse_owned(server, &ParseOptions::default()).unwrap();
+//! assert_eq!(nodes[0].children()[0].children()[0], leaf("milk & eggs".to_string()));
+//! ```
+use crate::lint::{HTML_RAW_TEXT_ELEMENTS, HTML_VOID_ELEMENTS};
+use crate::{element_ns, leaf, Attribute, Node};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// The options of `parse` and `parse_owned`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// parse the markup as xml, where any element can be empty with `/>`
+    /// and there are no void elements nor raw text elements
+    pub xml: bool,
+    /// keep the text which is only whitespace, such as the indentation of the elements,
+    /// which is skipped by default
+    pub keep_whitespace: bool,
+}
+
+/// The error of parsing the markup, with the byte offset in the markup where it is found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// the element which is opened at `position` is never closed
+    UnclosedElement {
+        /// the tag of the element
+        tag: String,
+        /// the offset of the start tag
+        position: usize,
+    },
+    /// the closing tag at `position` does not close the innermost open element
+    MismatchedCloseTag {
+        /// the tag of the innermost open element
+        expected: String,
+        /// the tag which is closed
+        found: String,
+        /// the offset of the closing tag
+        position: usize,
+    },
+    /// the closing tag at `position` has no open element
+    UnexpectedCloseTag {
+        /// the tag which is closed
+        tag: String,
+        /// the offset of the closing tag
+        position: usize,
+    },
+    /// the markup ends inside the tag, comment or quoted value which starts at `position`
+    UnexpectedEnd {
+        /// the offset of the unfinished markup
+        position: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnclosedElement { tag, position } => write!(
+                f,
+                "the element <{}> at {} is never closed",
+                tag, position
+            ),
+            Self::MismatchedCloseTag {
+                expected,
+                found,
+                position,
+            } => write!(
+                f,
+                "expecting </{}> but found </{}> at {}",
+                expected, found, position
+            ),
+            Self::UnexpectedCloseTag { tag, position } => write!(
+                f,
+                "the closing tag </{}> at {} has no open element",
+                tag, position
+            ),
+            Self::UnexpectedEnd { position } => write!(
+                f,
+                "the markup ends before the markup at {} is finished",
+                position
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse the markup into the nodes at its top level, which borrow their tags,
+/// attributes and text from the markup.
+/// The text and the attribute values are kept as they are in the markup,
+/// so their entities such as `&amp;` are not decoded, use `parse_owned` to decode them.
+pub fn parse<'a>(
+    markup: &'a str,
+    options: &ParseOptions,
+) -> Result<Vec<Node<&'a str, &'a str, &'a str, &'a str, &'a str>>, ParseError>
+{
+    parse_nodes(markup, options, |name| name, |text| text)
+}
+
+/// Parse the markup into the nodes at its top level, the same as `parse`,
+/// where the entities of the text and the attribute values are decoded
+pub fn parse_owned(
+    markup: &str,
+    options: &ParseOptions,
+) -> Result<Vec<Node<String, String, String, String, String>>, ParseError> {
+    parse_nodes(markup, options, ToString::to_string, decode_entities)
+}
+
+/// an element whose closing tag is not parsed yet
+struct Open<'a, T>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug,
+{
+    tag: &'a str,
+    position: usize,
+    namespace: Option<T>,
+    attrs: Vec<Attribute<T, T, T>>,
+    children: Vec<Node<T, T, T, T, T>>,
+}
+
+/// parse the markup with a stack of the open elements, where the names are converted
+/// with `name`, and the text and the attribute values with `text`
+fn parse_nodes<'a, T>(
+    markup: &'a str,
+    options: &ParseOptions,
+    name: impl Fn(&'a str) -> T,
+    text: impl Fn(&'a str) -> T,
+) -> Result<Vec<Node<T, T, T, T, T>>, ParseError>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug,
+{
+    let mut cursor = Cursor { markup, pos: 0 };
+    let mut top = vec![];
+    let mut open: Vec<Open<'a, T>> = vec![];
+    while !cursor.is_end() {
+        let start = cursor.pos;
+        if cursor.eat("<!--") {
+            cursor.skip_past("-->", start)?;
+        } else if cursor.eat("<!") || cursor.eat("<?") {
+            cursor.skip_past(">", start)?;
+        } else if cursor.eat("</") {
+            let tag = cursor.name();
+            cursor.skip_whitespace();
+            if !cursor.eat(">") {
+                return Err(ParseError::UnexpectedEnd { position: start });
+            }
+            let Some(element) = open.pop() else {
+                return Err(ParseError::UnexpectedCloseTag {
+                    tag: tag.to_string(),
+                    position: start,
+                });
+            };
+            if element.tag != tag {
+                return Err(ParseError::MismatchedCloseTag {
+                    expected: element.tag.to_string(),
+                    found: tag.to_string(),
+                    position: start,
+                });
+            }
+            let node = element_ns(
+                element.namespace,
+                name(element.tag),
+                element.attrs,
+                element.children,
+                false,
+            );
+            push_node(&mut open, &mut top, node);
+        } else if cursor.is_start_tag() {
+            cursor.eat("<");
+            let tag = cursor.name();
+            let parent_ns =
+                open.last().and_then(|parent| parent.namespace.clone());
+            let mut namespace = parent_ns;
+            let mut attrs = vec![];
+            let self_closing = loop {
+                cursor.skip_whitespace();
+                if cursor.eat("/>") {
+                    break true;
+                }
+                if cursor.eat(">") {
+                    break false;
+                }
+                let att = cursor.name();
+                if att.is_empty() {
+                    return Err(ParseError::UnexpectedEnd { position: start });
+                }
+                cursor.skip_whitespace();
+                let value = if cursor.eat("=") {
+                    cursor.skip_whitespace();
+                    Some(cursor.value(start)?)
+                } else {
+                    None
+                };
+                match value {
+                    Some(value) if att == "xmlns" => {
+                        namespace = Some(text(value))
+                    }
+                    Some(value) => {
+                        attrs.push(Attribute::new(None, name(att), text(value)))
+                    }
+                    None => attrs.push(Attribute::empty(None, name(att))),
+                }
+            };
+            let is_html = !options.xml && namespace.is_none();
+            if self_closing || (is_html && HTML_VOID_ELEMENTS.contains(&tag)) {
+                push_node(
+                    &mut open,
+                    &mut top,
+                    element_ns(
+                        namespace,
+                        name(tag),
+                        attrs,
+                        vec![],
+                        self_closing,
+                    ),
+                );
+            } else if is_html && HTML_RAW_TEXT_ELEMENTS.contains(&tag) {
+                let content = cursor.raw_text(tag, start)?;
+                let content = if content.is_empty() {
+                    vec![]
+                } else {
+                    vec![leaf(name(content))]
+                };
+                push_node(
+                    &mut open,
+                    &mut top,
+                    element_ns(namespace, name(tag), attrs, content, false),
+                );
+            } else {
+                open.push(Open {
+                    tag,
+                    position: start,
+                    namespace,
+                    attrs,
+                    children: vec![],
+                });
+            }
+        } else {
+            let content = cursor.text();
+            if options.keep_whitespace || !content.trim().is_empty() {
+                push_node(&mut open, &mut top, leaf(text(content)));
+            }
+        }
+    }
+    match open.pop() {
+        Some(element) => Err(ParseError::UnclosedElement {
+            tag: element.tag.to_string(),
+            position: element.position,
+        }),
+        None => Ok(top),
+    }
+}
+
+/// add the node to the innermost open element, or to the top level nodes
+fn push_node<T>(
+    open: &mut [Open<'_, T>],
+    top: &mut Vec<Node<T, T, T, T, T>>,
+    node: Node<T, T, T, T, T>,
+) where
+    T: PartialEq + Eq + Hash + Clone + Debug,
+{
+    match open.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => top.push(node),
+    }
+}
+
+/// the position in the markup which is parsed next
+struct Cursor<'a> {
+    markup: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.markup[self.pos..]
+    }
+
+    fn is_end(&self) -> bool {
+        self.pos >= self.markup.len()
+    }
+
+    /// advance past `prefix` if the rest of the markup starts with it
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// advance past the next `end`, the markup which starts at `start` is unfinished
+    /// when there is none
+    fn skip_past(&mut self, end: &str, start: usize) -> Result<(), ParseError> {
+        match self.rest().find(end) {
+            Some(offset) => {
+                self.pos += offset + end.len();
+                Ok(())
+            }
+            None => Err(ParseError::UnexpectedEnd { position: start }),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// whether the rest of the markup starts with the start tag of an element
+    fn is_start_tag(&self) -> bool {
+        let mut chars = self.rest().chars();
+        chars.next() == Some('<')
+            && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+    }
+
+    /// the name of a tag or an attribute
+    fn name(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| {
+                c.is_whitespace() || matches!(c, '=' | '>' | '/' | '"' | '\'')
+            })
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// the quoted or unquoted value of an attribute, without its quotes
+    fn value(&mut self, start: usize) -> Result<&'a str, ParseError> {
+        let rest = self.rest();
+        match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => match rest[1..].find(quote) {
+                Some(len) => {
+                    self.pos += len + 2;
+                    Ok(&rest[1..len + 1])
+                }
+                None => Err(ParseError::UnexpectedEnd { position: start }),
+            },
+            _ => {
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .unwrap_or(rest.len());
+                self.pos += len;
+                Ok(&rest[..len])
+            }
+        }
+    }
+
+    /// the text until the next markup
+    fn text(&mut self) -> &'a str {
+        let rest = self.rest();
+        // a `<` which does not start any markup is a part of the text
+        let len = rest
+            .char_indices()
+            .skip(1)
+            .find(|(offset, c)| {
+                *c == '<' && {
+                    let next = &rest[offset + 1..];
+                    next.starts_with(|c: char| {
+                        c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')
+                    })
+                }
+            })
+            .map_or(rest.len(), |(offset, _)| offset);
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// the content of the raw text element `tag` and its closing tag,
+    /// the element starts at `start`
+    fn raw_text(
+        &mut self,
+        tag: &str,
+        start: usize,
+    ) -> Result<&'a str, ParseError> {
+        let rest = self.rest();
+        let close = alloc::format!("</{}", tag);
+        let Some(len) = rest.find(&close) else {
+            return Err(ParseError::UnclosedElement {
+                tag: tag.to_string(),
+                position: start,
+            });
+        };
+        self.pos += len + close.len();
+        self.skip_whitespace();
+        if !self.eat(">") {
+            return Err(ParseError::UnexpectedEnd { position: start });
+        }
+        Ok(&rest[..len])
+    }
+}
+
+/// decode the character references of the text, such as `&amp;` and `&#60;`,
+/// the unknown references are kept as they are
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = match entity.strip_prefix('#')? {
+                        hex if hex.starts_with(['x', 'X']) => {
+                            u32::from_str_radix(&hex[1..], 16).ok()?
+                        }
+                        decimal => decimal.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
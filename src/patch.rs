@@ -1,13 +1,62 @@
 //! patch module
 
 use crate::{Attribute, Node};
+use alloc::boxed::Box;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::Debug;
 use core::hash::Hash;
 
-pub use tree_path::TreePath;
+#[cfg(feature = "apply-patches")]
+pub use crate::apply_patches::{compile, remap_paths, CompiledPatch};
+pub(crate) use adopt::adopt_inserted;
+pub use adopt::{Placement, TreeId};
+pub use attribute_part::AttributePart;
+pub use audit::{audit, AuditFinding, AuditRule};
+pub use broadcast::Selector;
+pub use display::render_patches;
+pub use expand::{expand_creations, Instruction};
+pub use focus::{will_remove, will_replace};
+#[cfg(feature = "json")]
+pub use json::{from_json, to_json, JsonPatchError};
+pub use kind::{ParsePatchKindError, PatchKind};
+pub use leaf_splice::LeafSplice;
+pub use order::sort_patches_for_application;
+pub use owned::{OwnedPatch, OwnedPatchType, OwnedSelector};
+#[cfg(feature = "apply-patches")]
+pub use patch_log::PatchLog;
+pub use region::{affected_region, aggregate_to_depth};
+pub use renderer::{apply, Renderer};
+#[cfg(feature = "apply-patches")]
+pub use speculative::{Reconciliation, SpeculativeState};
+pub use summary::PatchBatchSummary;
+pub(crate) use tree_path::PathIndex;
+pub use tree_path::{PathDisplay, PathIndexOverflow, TraverseError, TreePath};
+pub use validate::{validate, InvalidPatch};
 
+mod adopt;
+mod attribute_part;
+mod audit;
+mod broadcast;
+pub mod codec;
+pub(crate) mod display;
+mod expand;
+mod focus;
+#[cfg(feature = "json")]
+mod json;
+mod kind;
+mod leaf_splice;
+mod order;
+mod owned;
+#[cfg(feature = "apply-patches")]
+mod patch_log;
+mod region;
+mod renderer;
+#[cfg(feature = "apply-patches")]
+mod speculative;
+mod summary;
 mod tree_path;
+mod validate;
 
 /// A Patch encodes an operation that modifies a real DOM element or native UI element
 ///
@@ -59,7 +108,11 @@ mod tree_path;
 /// 0 - is the root element which is always zero.
 /// 1 - is the `footer` element since it is the 2nd element of the body.
 /// 2 - is the `nav` element since it is the 3rd node in the `footer` element.
+///
+/// `Patch`, `PatchType` and `TreePath` are `Send` and `Sync` when the generic types are `Sync`,
+/// so the patches can be computed in a worker thread and then applied in the main thread.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Patch<'a, Ns, Tag, Leaf, Att, Val>
 where
     Ns: PartialEq + Clone + Debug,
@@ -78,6 +131,7 @@ where
 
 /// the patch variant
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PatchType<'a, Ns, Tag, Leaf, Att, Val>
 where
     Ns: PartialEq + Clone + Debug,
@@ -117,13 +171,83 @@ where
         /// after this target location
         nodes_path: Vec<TreePath>,
     },
+    /// remove the nodes pointed at these `nodes_path`
+    /// and append them to the children of the `target_element` pointed at `patch_path`.
+    /// This is emitted for the keyed nodes which are moved into a parent where there is
+    /// no sibling to move them before or after, with `DiffOptions::cross_parent_moves`
+    MoveIntoNode {
+        /// into this target location
+        nodes_path: Vec<TreePath>,
+    },
 
     /// ReplaceNode a node with another node. This typically happens when a node's tag changes.
     /// ex: <div> becomes <span>
+    ///
+    /// The later patches which are placed before or after the replaced node, such as an
+    /// `InsertBeforeNode` of the same path, are placed before the first or after the last
+    /// of the replacement nodes.
     ReplaceNode {
         /// the node that will replace the target node
         replacement: Vec<&'a Node<Ns, Tag, Leaf, Att, Val>>,
     },
+    /// Wrap the target node with the `wrapper` node,
+    /// the target node is kept as is and becomes the child of the wrapper.
+    /// When the `wrapper` has more than 1 child, the next siblings of the target
+    /// node are also moved into the wrapper, as many as the children of the `wrapper`.
+    WrapNode {
+        /// the node which will wrap the target node
+        wrapper: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    },
+    /// Remove the target node but keep its children,
+    /// the children is put in place of the target node
+    UnwrapNode,
+    /// Change the leaf of the target leaf node,
+    /// this is emitted instead of `ReplaceNode` when `DiffOptions::change_leaf` is enabled.
+    /// Both leaves are referenced, so the tokens of a `TokenedLeaf` can be migrated
+    ChangeLeaf {
+        /// the old leaf
+        old: &'a Leaf,
+        /// the new leaf which will replace the old leaf
+        new: &'a Leaf,
+    },
+    /// Splice the leaf of the target leaf node, such as inserting and deleting text,
+    /// this is emitted instead of `ChangeLeaf` and `ReplaceNode` when `DiffOptions::leaf_diff`
+    /// returns the splices of a changed leaf. Appliers which can not splice the leaf
+    /// can set the new leaf the same as `ChangeLeaf`
+    SpliceLeaf {
+        /// the old leaf
+        old: &'a Leaf,
+        /// the new leaf which is the result of the splices
+        new: &'a Leaf,
+        /// the splices of the old leaf, in ascending order and not overlapping
+        splices: Vec<LeafSplice>,
+    },
+    /// Change the tag of the target element, keeping its attributes and children.
+    /// This is emitted instead of `ReplaceNode` when `DiffOptions::keyed_tag_change`
+    /// is enabled and a keyed element changed its tag, or when `DiffOptions::can_morph`
+    /// allows the element to be morphed. It is followed by the attribute and children
+    /// patches of the element, so appliers which can not change the tag in place
+    /// can create the new element and move the existing children into it.
+    ChangeTag {
+        /// the old tag
+        old: &'a Tag,
+        /// the new tag which will replace the old tag
+        new: &'a Tag,
+    },
+    /// A marker which does not change anything, emitted when `DiffOptions::sync_islands`
+    /// is enabled and the siblings of the ignored subtree at patch_path changed shape,
+    /// so the host can re-anchor the imperative management of the island
+    /// after the patches are applied.
+    SyncIsland,
+    /// Change the run of `count` adjacent leaves starting at the target node into `leaves`.
+    /// This is emitted when `DiffOptions::merge_text_runs` is enabled, such as when
+    /// a text leaf is split into 2 text leaves or vice versa.
+    ChangeLeafRun {
+        /// the number of adjacent leaves, starting at the target node, to be changed
+        count: usize,
+        /// the leaves that will replace the run of leaves
+        leaves: Vec<&'a Leaf>,
+    },
     /// Add attributes that the new node has that the old node does not
     /// Note: the attributes is not a reference since attributes of same
     /// name are merged to produce a new unify attribute
@@ -136,8 +260,88 @@ where
         /// attributes that are to be removed from this target node
         attrs: Vec<&'a Attribute<Ns, Att, Val>>,
     },
+    /// Add and remove the attributes of the target element in one update,
+    /// this is emitted instead of a pair of `AddAttributes` and `RemoveAttributes`
+    /// patches when `DiffOptions::update_attributes` is enabled, so the appliers can
+    /// change the attributes atomically, such as batching the recalculation of the styles.
+    /// The added and removed attributes never have the same name.
+    UpdateAttributes {
+        /// the attributes to be patched into the target node
+        add: Vec<&'a Attribute<Ns, Att, Val>>,
+        /// attributes that are to be removed from this target node
+        remove: Vec<&'a Attribute<Ns, Att, Val>>,
+    },
+    /// Update the parts of the attribute of the target element, such as the changed
+    /// properties of a `style` attribute, instead of adding the whole attribute.
+    /// This is emitted when the `DiffOptions::attr_differ` returns the parts of a changed
+    /// attribute. Appliers which can not update the parts can add the new `attr`
+    /// the same as `AddAttributes`
+    UpdateAttributeParts {
+        /// the new attribute, which is the result of the parts
+        attr: &'a Attribute<Ns, Att, Val>,
+        /// the changed parts of the attribute
+        parts: Vec<AttributePart<Val>>,
+    },
+    /// Apply the same `patch` to each of the children of the target node.
+    /// This is emitted when `DiffOptions::for_each_child` is enabled and every child
+    /// of the target node receives the same `AddAttributes` or `RemoveAttributes` patch,
+    /// such as a theme class which is changed on every row of a table.
+    ForEachChild {
+        /// the patch which is applied to each of the children
+        patch: Box<PatchType<'a, Ns, Tag, Leaf, Att, Val>>,
+    },
+    /// Apply the same `patch` to every node selected by the `selector` in the subtree
+    /// of the target node, including the target node itself. This is created with
+    /// `Patch::broadcast` for global changes such as a dark-mode toggle, where diffing
+    /// the whole tree just to change the same attribute everywhere is wasteful.
+    Broadcast {
+        /// selects the nodes which receive the patch
+        selector: Selector<'a, Tag, Att, Val>,
+        /// the patch which is applied to each of the selected nodes,
+        /// such as `AddAttributes` or `RemoveAttributes`
+        patch: Box<PatchType<'a, Ns, Tag, Leaf, Att, Val>>,
+    },
+    /// Remove the node at `from_path` of the auxiliary tree `from_tree` and place it
+    /// relative to the target node, keeping the node instead of recreating it,
+    /// such as a keyed item which is dragged from a list of another tree.
+    /// This is emitted for the inserted nodes which are equal to a keyed node of the trees
+    /// registered with `DiffOptions::adoptable_trees`.
+    AdoptNode {
+        /// the auxiliary tree which the node is adopted from
+        from_tree: TreeId,
+        /// the path of the adopted node in the auxiliary tree
+        from_path: TreePath,
+        /// where the adopted node is placed relative to the target node
+        placement: Placement,
+    },
+    /// Set the `node` into the `slot` of the target element, replacing the child which
+    /// is in the slot. The slot is moved after the other slots of the element, so the slots
+    /// end up in the order of the new element.
+    /// This is emitted for the elements whose children are keyed by their slots,
+    /// see `Element::slots`
+    SetSlot {
+        /// the slot name
+        slot: &'a Att,
+        /// the node which is set into the slot
+        node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    },
+    /// Remove the child in the `slot` of the target element, this does nothing
+    /// when the slot is empty
+    ClearSlot {
+        /// the slot name
+        slot: &'a Att,
+    },
 }
 
+// static assertions that the patches can be sent and shared across threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    type Str = &'static str;
+    assert_send_sync::<TreePath>();
+    assert_send_sync::<Patch<'static, Str, Str, Str, Str, Str>>();
+    assert_send_sync::<PatchType<'static, Str, Str, Str, Str, Str>>();
+};
+
 impl<'a, Ns, Tag, Leaf, Att, Val> Patch<'a, Ns, Tag, Leaf, Att, Val>
 where
     Ns: PartialEq + Clone + Debug,
@@ -156,10 +360,86 @@ where
         match &self.patch_type {
             PatchType::MoveBeforeNode { nodes_path } => nodes_path,
             PatchType::MoveAfterNode { nodes_path } => nodes_path,
+            PatchType::MoveIntoNode { nodes_path } => nodes_path,
             _ => &[],
         }
     }
 
+    /// returns true if this patch changes the siblings of the target node,
+    /// such as inserting, removing or moving nodes
+    pub(crate) fn changes_siblings(&self) -> bool {
+        match &self.patch_type {
+            PatchType::InsertBeforeNode { .. }
+            | PatchType::InsertAfterNode { .. }
+            | PatchType::RemoveNode
+            | PatchType::MoveBeforeNode { .. }
+            | PatchType::MoveAfterNode { .. }
+            | PatchType::WrapNode { .. }
+            | PatchType::UnwrapNode
+            | PatchType::ChangeLeafRun { .. } => true,
+            PatchType::AdoptNode { placement, .. } => {
+                *placement != Placement::Append
+            }
+            PatchType::ReplaceNode { replacement } => replacement.len() != 1,
+            PatchType::AppendChildren { .. }
+            | PatchType::MoveIntoNode { .. }
+            | PatchType::SetSlot { .. }
+            | PatchType::ClearSlot { .. }
+            | PatchType::ChangeLeaf { .. }
+            | PatchType::SpliceLeaf { .. }
+            | PatchType::ChangeTag { .. }
+            | PatchType::SyncIsland
+            | PatchType::AddAttributes { .. }
+            | PatchType::RemoveAttributes { .. }
+            | PatchType::UpdateAttributes { .. }
+            | PatchType::UpdateAttributeParts { .. }
+            | PatchType::ForEachChild { .. }
+            | PatchType::Broadcast { .. } => false,
+        }
+    }
+
+    /// return owned copies of the nodes referenced by this patch, such as the nodes
+    /// to be inserted, appended, the replacement or the wrapper.
+    /// This is useful for appliers which stores the pending patches, such as batching
+    /// across frames, where the patches can not borrow the new node tree.
+    pub fn cloned_nodes(&self) -> Vec<Node<Ns, Tag, Leaf, Att, Val>>
+    where
+        Tag: Clone,
+    {
+        match &self.patch_type {
+            PatchType::InsertBeforeNode { nodes }
+            | PatchType::InsertAfterNode { nodes } => {
+                nodes.iter().map(|node| (*node).clone()).collect()
+            }
+            PatchType::AppendChildren { children } => {
+                children.iter().map(|node| (*node).clone()).collect()
+            }
+            PatchType::ReplaceNode { replacement } => {
+                replacement.iter().map(|node| (*node).clone()).collect()
+            }
+            PatchType::WrapNode { wrapper } => vec![(*wrapper).clone()],
+            PatchType::SetSlot { node, .. } => vec![(*node).clone()],
+            PatchType::RemoveNode
+            | PatchType::MoveBeforeNode { .. }
+            | PatchType::MoveAfterNode { .. }
+            | PatchType::MoveIntoNode { .. }
+            | PatchType::UnwrapNode
+            | PatchType::ChangeLeaf { .. }
+            | PatchType::SpliceLeaf { .. }
+            | PatchType::ChangeTag { .. }
+            | PatchType::SyncIsland
+            | PatchType::ChangeLeafRun { .. }
+            | PatchType::AddAttributes { .. }
+            | PatchType::RemoveAttributes { .. }
+            | PatchType::UpdateAttributes { .. }
+            | PatchType::UpdateAttributeParts { .. }
+            | PatchType::ForEachChild { .. }
+            | PatchType::Broadcast { .. }
+            | PatchType::AdoptNode { .. }
+            | PatchType::ClearSlot { .. } => vec![],
+        }
+    }
+
     /// return the tag of this patch
     pub fn tag(&self) -> Option<&Tag> {
         self.tag
@@ -251,6 +531,22 @@ where
         }
     }
 
+    /// remove the nodes pointed at the `nodes_path` and append them to the children
+    /// of the target element pointed at patch_path
+    pub fn move_into_node(
+        tag: Option<&'a Tag>,
+        patch_path: TreePath,
+        nodes_path: impl IntoIterator<Item = TreePath>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag,
+            patch_path,
+            patch_type: PatchType::MoveIntoNode {
+                nodes_path: nodes_path.into_iter().collect(),
+            },
+        }
+    }
+
     /// create a patch where a node is replaced by the `replacement` node.
     /// The target node to be replace is traverse using the `patch_path`
     pub fn replace_node(
@@ -267,6 +563,104 @@ where
         }
     }
 
+    /// create a patch where the target node is wrapped with the `wrapper` node
+    pub fn wrap_node(
+        tag: Option<&'a Tag>,
+        patch_path: TreePath,
+        wrapper: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag,
+            patch_path,
+            patch_type: PatchType::WrapNode { wrapper },
+        }
+    }
+
+    /// create a patch where the target node is removed and its children are put in its place
+    pub fn unwrap_node(
+        tag: Option<&'a Tag>,
+        patch_path: TreePath,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag,
+            patch_path,
+            patch_type: PatchType::UnwrapNode,
+        }
+    }
+
+    /// create a patch where the leaf of the target leaf node is changed
+    pub fn change_leaf(
+        patch_path: TreePath,
+        old: &'a Leaf,
+        new: &'a Leaf,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: None,
+            patch_path,
+            patch_type: PatchType::ChangeLeaf { old, new },
+        }
+    }
+
+    /// create a patch where the leaf of the target leaf node is spliced into the new leaf
+    pub fn splice_leaf(
+        patch_path: TreePath,
+        old: &'a Leaf,
+        new: &'a Leaf,
+        splices: impl IntoIterator<Item = LeafSplice>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: None,
+            patch_path,
+            patch_type: PatchType::SpliceLeaf {
+                old,
+                new,
+                splices: splices.into_iter().collect(),
+            },
+        }
+    }
+
+    /// create a patch where the tag of the target element is changed
+    pub fn change_tag(
+        patch_path: TreePath,
+        old: &'a Tag,
+        new: &'a Tag,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: Some(old),
+            patch_path,
+            patch_type: PatchType::ChangeTag { old, new },
+        }
+    }
+
+    /// create a marker patch for the ignored subtree at `patch_path` whose siblings changed
+    pub fn sync_island(
+        tag: Option<&'a Tag>,
+        patch_path: TreePath,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag,
+            patch_path,
+            patch_type: PatchType::SyncIsland,
+        }
+    }
+
+    /// create a patch where the run of `count` adjacent leaves starting at the target node
+    /// is changed into `leaves`
+    pub fn change_leaf_run(
+        patch_path: TreePath,
+        count: usize,
+        leaves: impl IntoIterator<Item = &'a Leaf>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: None,
+            patch_path,
+            patch_type: PatchType::ChangeLeafRun {
+                count,
+                leaves: leaves.into_iter().collect(),
+            },
+        }
+    }
+
     /// create a patch where a new attribute is added to the target element
     pub fn add_attributes(
         tag: &'a Tag,
@@ -295,4 +689,120 @@ where
             patch_type: PatchType::RemoveAttributes { attrs },
         }
     }
+
+    /// create a patch where the attributes of the target element are added and removed
+    /// in one update
+    pub fn update_attributes(
+        tag: &'a Tag,
+        patch_path: TreePath,
+        add: impl IntoIterator<Item = &'a Attribute<Ns, Att, Val>>,
+        remove: impl IntoIterator<Item = &'a Attribute<Ns, Att, Val>>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: Some(tag),
+            patch_path,
+            patch_type: PatchType::UpdateAttributes {
+                add: add.into_iter().collect(),
+                remove: remove.into_iter().collect(),
+            },
+        }
+    }
+
+    /// create a patch where the parts of the attribute of the target element are updated
+    /// into the new `attr`
+    pub fn update_attribute_parts(
+        tag: &'a Tag,
+        patch_path: TreePath,
+        attr: &'a Attribute<Ns, Att, Val>,
+        parts: impl IntoIterator<Item = AttributePart<Val>>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: Some(tag),
+            patch_path,
+            patch_type: PatchType::UpdateAttributeParts {
+                attr,
+                parts: parts.into_iter().collect(),
+            },
+        }
+    }
+
+    /// create a patch where the `patch` is applied to each of the children of the target node
+    pub fn for_each_child(
+        tag: Option<&'a Tag>,
+        patch_path: TreePath,
+        patch: PatchType<'a, Ns, Tag, Leaf, Att, Val>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag,
+            patch_path,
+            patch_type: PatchType::ForEachChild {
+                patch: Box::new(patch),
+            },
+        }
+    }
+
+    /// create a patch where the `patch` is applied to every node selected by the `selector`
+    /// in the subtree of the target node, without diffing the subtree
+    pub fn broadcast(
+        tag: Option<&'a Tag>,
+        patch_path: TreePath,
+        selector: Selector<'a, Tag, Att, Val>,
+        patch: PatchType<'a, Ns, Tag, Leaf, Att, Val>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag,
+            patch_path,
+            patch_type: PatchType::Broadcast {
+                selector,
+                patch: Box::new(patch),
+            },
+        }
+    }
+
+    /// create a patch where the node at `from_path` of the auxiliary tree `from_tree`
+    /// is adopted and placed relative to the target node
+    pub fn adopt_node(
+        tag: Option<&'a Tag>,
+        patch_path: TreePath,
+        from_tree: TreeId,
+        from_path: TreePath,
+        placement: Placement,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag,
+            patch_path,
+            patch_type: PatchType::AdoptNode {
+                from_tree,
+                from_path,
+                placement,
+            },
+        }
+    }
+
+    /// create a patch where the `node` is set into the `slot` of the target element
+    pub fn set_slot(
+        tag: &'a Tag,
+        patch_path: TreePath,
+        slot: &'a Att,
+        node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: Some(tag),
+            patch_path,
+            patch_type: PatchType::SetSlot { slot, node },
+        }
+    }
+
+    /// create a patch where the child in the `slot` of the target element is removed
+    pub fn clear_slot(
+        tag: &'a Tag,
+        patch_path: TreePath,
+        slot: &'a Att,
+    ) -> Patch<'a, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: Some(tag),
+            patch_path,
+            patch_type: PatchType::ClearSlot { slot },
+        }
+    }
 }
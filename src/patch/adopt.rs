@@ -0,0 +1,146 @@
+//! adopting the nodes of the auxiliary trees
+use crate::{Node, Patch, PatchType, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::mem;
+
+/// The id of an auxiliary tree, such as the sibling list of a drag and drop,
+/// whose nodes can be adopted by the `AdoptNode` patches.
+///
+/// The ids are chosen by the host, the differ registers the auxiliary trees
+/// with `DiffOptions::adoptable_trees` and `apply_patches_with_trees` takes
+/// the same trees when the patches are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeId(pub usize);
+
+/// Where the adopted node of an `AdoptNode` patch is placed relative to the target node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Placement {
+    /// insert the adopted node before the target node
+    Before,
+    /// insert the adopted node after the target node
+    After,
+    /// append the adopted node to the children of the target node
+    Append,
+}
+
+/// replace the inserted nodes which are equal to a keyed node of the auxiliary `trees`
+/// with `AdoptNode` patches, each node of the trees is adopted at most once
+pub(crate) fn adopt_inserted<'a, Ns, Tag, Leaf, Att, Val>(
+    patches: Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>,
+    trees: &[(TreeId, &Node<Ns, Tag, Leaf, Att, Val>)],
+    is_keyed: impl Fn(&Node<Ns, Tag, Leaf, Att, Val>) -> bool,
+) -> Vec<Patch<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut adoptable = adoptable_nodes(trees, is_keyed);
+    let mut adopted_patches = Vec::with_capacity(patches.len());
+    for patch in patches {
+        let (nodes, placement) = match &patch.patch_type {
+            PatchType::InsertBeforeNode { nodes } => (nodes, Placement::Before),
+            PatchType::InsertAfterNode { nodes } => (nodes, Placement::After),
+            PatchType::AppendChildren { children } => {
+                (children, Placement::Append)
+            }
+            _ => {
+                adopted_patches.push(patch);
+                continue;
+            }
+        };
+        // the runs of the inserted nodes and the adopted nodes, in their order
+        let mut groups = vec![];
+        let mut run = vec![];
+        for node in nodes {
+            let found = adoptable
+                .iter()
+                .position(|(_, _, candidate)| *candidate == *node);
+            if let Some(found) = found {
+                let (from_tree, from_path, _) = adoptable.remove(found);
+                if !run.is_empty() {
+                    groups.push(inserted(placement, mem::take(&mut run)));
+                }
+                groups.push(PatchType::AdoptNode {
+                    from_tree,
+                    from_path,
+                    placement,
+                });
+            } else {
+                run.push(*node);
+            }
+        }
+        if groups.is_empty() {
+            adopted_patches.push(patch);
+            continue;
+        }
+        if !run.is_empty() {
+            groups.push(inserted(placement, run));
+        }
+        // placing after the same target reverses the order of the groups,
+        // so the last group is placed first
+        if placement == Placement::After {
+            groups.reverse();
+        }
+        adopted_patches.extend(groups.into_iter().map(|patch_type| Patch {
+            tag: patch.tag,
+            patch_path: patch.patch_path.clone(),
+            patch_type,
+        }));
+    }
+    adopted_patches
+}
+
+/// the patch type which inserts the nodes at the placement
+fn inserted<'a, Ns, Tag, Leaf, Att, Val>(
+    placement: Placement,
+    nodes: Vec<&'a Node<Ns, Tag, Leaf, Att, Val>>,
+) -> PatchType<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    match placement {
+        Placement::Before => PatchType::InsertBeforeNode { nodes },
+        Placement::After => PatchType::InsertAfterNode { nodes },
+        Placement::Append => PatchType::AppendChildren { children: nodes },
+    }
+}
+
+/// return the path and the node of every keyed node in the auxiliary trees
+fn adoptable_nodes<'t, Ns, Tag, Leaf, Att, Val>(
+    trees: &[(TreeId, &'t Node<Ns, Tag, Leaf, Att, Val>)],
+    is_keyed: impl Fn(&Node<Ns, Tag, Leaf, Att, Val>) -> bool,
+) -> Vec<(TreeId, TreePath, &'t Node<Ns, Tag, Leaf, Att, Val>)>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut adoptable = vec![];
+    for (tree, root) in trees {
+        let mut stack = vec![(TreePath::root(), *root)];
+        while let Some((path, node)) = stack.pop() {
+            // the root of a tree has no parent to be adopted from
+            if !path.is_empty() && is_keyed(node) {
+                adoptable.push((*tree, path.clone(), node));
+            }
+            for (idx, child) in node.child_nodes().iter().enumerate().rev() {
+                stack.push((path.traverse(idx), child));
+            }
+        }
+    }
+    adoptable
+}
@@ -0,0 +1,25 @@
+//! the parts of a structured attribute value, such as the properties of a style
+use core::fmt::Debug;
+
+/// A change of a part of a structured attribute value, such as a property of
+/// the `style` attribute, returned by the `DiffOptions::attr_differ` of the user
+/// for the `UpdateAttributeParts` patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttributePart<Val>
+where
+    Val: PartialEq + Clone + Debug,
+{
+    /// set the part `key` of the attribute value to `value`, such as `color: red`
+    Set {
+        /// the key of the part, such as the name of a style property
+        key: Val,
+        /// the new value of the part
+        value: Val,
+    },
+    /// remove the part `key` of the attribute value
+    Remove {
+        /// the key of the part, such as the name of a style property
+        key: Val,
+    },
+}
@@ -0,0 +1,210 @@
+//! auditing the accessibility of the patches before they are applied
+use super::focus;
+use crate::{Patch, TreePath};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// A rule which `audit` checks the patches against
+pub enum AuditRule<'r, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// report the patches which remove or replace the focused element at this path,
+    /// or any of its ancestors, so the focus of the keyboard and screen reader users is lost
+    FocusedRemoval(TreePath),
+    /// report the aria-live regions at these paths which are changed by more than
+    /// `max_patches` patches, including the patches of their descendants,
+    /// since the screen readers announce every change of a live region
+    LiveRegionChurn {
+        /// the paths of the aria-live regions in the old tree
+        regions: &'r [TreePath],
+        /// the maximum number of patches for each of the regions
+        max_patches: usize,
+    },
+    /// a rule of the user, which returns its findings for the patches
+    Custom(
+        &'r dyn Fn(&[Patch<'_, Ns, Tag, Leaf, Att, Val>]) -> Vec<AuditFinding>,
+    ),
+}
+
+impl<'r, Ns, Tag, Leaf, Att, Val> Debug
+    for AuditRule<'r, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FocusedRemoval(focused) => {
+                f.debug_tuple("FocusedRemoval").field(focused).finish()
+            }
+            Self::LiveRegionChurn {
+                regions,
+                max_patches,
+            } => f
+                .debug_struct("LiveRegionChurn")
+                .field("regions", regions)
+                .field("max_patches", max_patches)
+                .finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// A problem found by `audit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditFinding {
+    /// the name of the rule, such as `"focused_removal"` and `"live_region_churn"`
+    pub rule: &'static str,
+    /// the index of the patch which caused the problem
+    pub patch_index: usize,
+    /// the path of the node which has the problem
+    pub path: TreePath,
+    /// the description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for AuditFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {} (patch {}): {}",
+            self.rule,
+            self.path.display(),
+            self.patch_index,
+            self.message
+        )
+    }
+}
+
+/// Check the patches against the `rules`, returning the findings of all the rules
+/// in the order of the rules.
+///
+/// The patches are the single choke point of the mutations of the UI,
+/// so the accessibility regressions can be caught here, before the patches are applied.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("form", vec![], vec![element("input", vec![], vec![])]);
+/// let new: MyNode = element("form", vec![], vec![]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// let findings = audit(&patches, &[AuditRule::FocusedRemoval(TreePath::new([0]))]);
+/// assert_eq!(findings.len(), 1);
+/// assert_eq!(findings[0].rule, "focused_removal");
+/// ```
+pub fn audit<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+    rules: &[AuditRule<'_, Ns, Tag, Leaf, Att, Val>],
+) -> Vec<AuditFinding>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    rules
+        .iter()
+        .flat_map(|rule| match rule {
+            AuditRule::FocusedRemoval(focused) => {
+                focused_removal(patches, focused)
+            }
+            AuditRule::LiveRegionChurn {
+                regions,
+                max_patches,
+            } => live_region_churn(patches, regions, *max_patches),
+            AuditRule::Custom(custom) => custom(patches),
+        })
+        .collect()
+}
+
+/// the findings of the patches which remove or replace the focused node
+fn focused_removal<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+    focused: &TreePath,
+) -> Vec<AuditFinding>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    patches
+        .iter()
+        .enumerate()
+        .filter_map(|(patch_index, patch)| {
+            let action = focus::destruction(patch, focused)?.as_str();
+            let path = patch.path();
+            Some(AuditFinding {
+                rule: "focused_removal",
+                patch_index,
+                path: focused.clone(),
+                message: if path.is_ancestor_of(focused) {
+                    format!(
+                        "the ancestor {} of the focused element is {action}",
+                        path.display()
+                    )
+                } else {
+                    format!("the focused element is {action}")
+                },
+            })
+        })
+        .collect()
+}
+
+/// the findings of the regions which are changed by more than `max_patches` patches
+fn live_region_churn<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+    regions: &[TreePath],
+    max_patches: usize,
+) -> Vec<AuditFinding>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut findings = vec![];
+    for region in regions {
+        let changes: Vec<usize> = patches
+            .iter()
+            .enumerate()
+            .filter(|(_, patch)| {
+                patch.path() == region || patch.path().is_descendant_of(region)
+            })
+            .map(|(patch_index, _)| patch_index)
+            .collect();
+        if let Some(patch_index) = changes.get(max_patches) {
+            findings.push(AuditFinding {
+                rule: "live_region_churn",
+                patch_index: *patch_index,
+                path: region.clone(),
+                message: format!(
+                    "the live region is changed by {} patches, more than {max_patches}",
+                    changes.len()
+                ),
+            });
+        }
+    }
+    findings
+}
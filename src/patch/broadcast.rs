@@ -0,0 +1,81 @@
+//! selectors for the broadcast patches
+use crate::Node;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// Selects the elements which receive the patch of a `Broadcast` patch,
+/// such as every element with a `class` attribute for a dark-mode toggle.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![attr("theme", "light")], vec![
+///     element("div", vec![attr("theme", "light")], vec![leaf("hello")]),
+///     element("div", vec![], vec![]),
+/// ]);
+///
+/// let dark = attr("theme", "dark");
+/// let patch: Patch<&'static str, &'static str, &'static str, &'static str, &'static str> = Patch::broadcast(
+///     Some(&"main"),
+///     TreePath::root(),
+///     Selector::HasAttribute(&"theme"),
+///     PatchType::AddAttributes { attrs: vec![&dark] },
+/// );
+/// #[cfg(feature = "apply-patches")]
+/// {
+///     let mut root = old.clone();
+///     apply_patches(&mut root, &[patch]);
+///     assert_eq!(root, element("main", vec![attr("theme", "dark")], vec![
+///         element("div", vec![attr("theme", "dark")], vec![leaf("hello")]),
+///         element("div", vec![], vec![]),
+///     ]));
+/// }
+/// ```
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Selector<'a, Tag, Att, Val> {
+    /// every element
+    AllElements,
+    /// the elements with this tag
+    Tag(&'a Tag),
+    /// the elements which has this attribute
+    HasAttribute(&'a Att),
+    /// the elements which has this attribute with this value
+    AttributeValue(&'a Att, &'a Val),
+}
+
+impl<'a, Tag, Att, Val> Clone for Selector<'a, Tag, Att, Val> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, Tag, Att, Val> Copy for Selector<'a, Tag, Att, Val> {}
+
+impl<'a, Tag, Att, Val> Selector<'a, Tag, Att, Val> {
+    /// returns true if the node is selected by this selector
+    pub fn matches<Ns, Leaf>(
+        &self,
+        node: &Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> bool
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        match self {
+            Self::AllElements => node.is_element(),
+            Self::Tag(tag) => node.tag() == Some(*tag),
+            Self::HasAttribute(name) => node.has_attribute(name),
+            Self::AttributeValue(name, value) => {
+                node.attribute_values_iter(name).any(|v| v == *value)
+            }
+        }
+    }
+}
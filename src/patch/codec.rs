@@ -0,0 +1,551 @@
+//! A compact binary encoding of the patches, such as for sending the patches of
+//! a live view over a websocket
+//!
+//! The patches are encoded as their count followed by each patch, where each patch
+//! is prefixed with the length of its bytes, so a reader can skip a patch without
+//! decoding it. A patch is the marker of its patch type, its path, its tag and
+//! the fields of the patch type in the same order as `PatchType`.
+//! The paths are the number of their child indices followed by each index,
+//! all of them encoded as LEB128 varints, the same as the counts and the lengths.
+//! The nodes, attributes, leaves and tags are encoded with the `crate::codec` encoding.
+//!
+//! The encoded patches of a keyed list of 100 items where every other item is changed
+//! and the items are rotated by one, with `String` for each of the generic types,
+//! are 757 bytes, which is about 5 times smaller than the 4,108 bytes of the same patches
+//! as the text of `patch::to_json`. The encoding and decoding times of both are compared
+//! with `cargo run --release --features json --example patch_encoding`.
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{patch::{codec::*, OwnedPatch}, *};
+//!
+//! pub type MyNode = Node<String, String, String, String, String>;
+//!
+//! let old: MyNode = element("div".to_string(), vec![], vec![leaf("old".to_string())]);
+//! let new: MyNode = element("div".to_string(), vec![], vec![leaf("new".to_string())]);
+//! let patches = diff(&old, &new);
+//!
+//! let bytes = encode_patches(&patches);
+//! let decoded: Vec<OwnedPatch<String, String, String, String, String>> =
+//!     decode_patches(&bytes).unwrap();
+//! assert_eq!(decoded, patches.into_iter().map(Patch::into_owned).collect::<Vec<_>>());
+//! ```
+use super::{
+    AttributePart, LeafSplice, OwnedPatch, OwnedPatchType, OwnedSelector,
+    Patch, PatchType, PathIndex, Placement, Selector, TreeId, TreePath,
+};
+use crate::codec::{
+    decode_len, decode_varint, encode_varint, invalid, take_byte, take_bytes,
+    Decode, DecodeError, Encode,
+};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// the markers of the patch types in the encoded form
+const INSERT_BEFORE_NODE: u8 = 0;
+const INSERT_AFTER_NODE: u8 = 1;
+const APPEND_CHILDREN: u8 = 2;
+const REMOVE_NODE: u8 = 3;
+const MOVE_BEFORE_NODE: u8 = 4;
+const MOVE_AFTER_NODE: u8 = 5;
+const MOVE_INTO_NODE: u8 = 6;
+const REPLACE_NODE: u8 = 7;
+const WRAP_NODE: u8 = 8;
+const UNWRAP_NODE: u8 = 9;
+const CHANGE_LEAF: u8 = 10;
+const SPLICE_LEAF: u8 = 11;
+const CHANGE_TAG: u8 = 12;
+const SYNC_ISLAND: u8 = 13;
+const CHANGE_LEAF_RUN: u8 = 14;
+const ADD_ATTRIBUTES: u8 = 15;
+const REMOVE_ATTRIBUTES: u8 = 16;
+const UPDATE_ATTRIBUTES: u8 = 17;
+const UPDATE_ATTRIBUTE_PARTS: u8 = 18;
+const FOR_EACH_CHILD: u8 = 19;
+const BROADCAST: u8 = 20;
+const ADOPT_NODE: u8 = 21;
+const SET_SLOT: u8 = 22;
+const CLEAR_SLOT: u8 = 23;
+
+/// the maximum nesting of the `ForEachChild` and `Broadcast` patches, the differ never
+/// nests them and a `Broadcast` of a `ForEachChild` is 2 levels. Decoding is recursive,
+/// so the deeper nesting of the untrusted bytes is rejected instead of overflowing the stack
+const MAX_NESTED_PATCHES: usize = 8;
+
+/// encode the patches into bytes
+pub fn encode_patches<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+) -> Vec<u8>
+where
+    Ns: PartialEq + Clone + Debug + Encode,
+    Tag: PartialEq + Debug + Encode,
+    Leaf: PartialEq + Clone + Debug + Encode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Encode,
+    Val: PartialEq + Clone + Debug + Encode,
+{
+    let mut buf = Vec::new();
+    let mut patch_buf = Vec::new();
+    encode_varint(patches.len() as u64, &mut buf);
+    for patch in patches {
+        patch_buf.clear();
+        patch_buf.push(marker(&patch.patch_type));
+        patch.patch_path.encode(&mut patch_buf);
+        // the same as an `Option` of the tag
+        match patch.tag {
+            None => patch_buf.push(0),
+            Some(tag) => {
+                patch_buf.push(1);
+                tag.encode(&mut patch_buf);
+            }
+        }
+        encode_fields(&patch.patch_type, &mut patch_buf);
+        encode_varint(patch_buf.len() as u64, &mut buf);
+        buf.extend_from_slice(&patch_buf);
+    }
+    buf
+}
+
+/// decode the owned patches from all of the bytes
+pub fn decode_patches<Ns, Tag, Leaf, Att, Val>(
+    mut bytes: &[u8],
+) -> Result<Vec<OwnedPatch<Ns, Tag, Leaf, Att, Val>>, DecodeError>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    let count = decode_len(&mut bytes)?;
+    let mut patches = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len = decode_len(&mut bytes)?;
+        let mut patch_bytes = take_bytes(&mut bytes, len)?;
+        let marker = take_byte(&mut patch_bytes)?;
+        let patch = OwnedPatch {
+            patch_path: Decode::decode(&mut patch_bytes)?,
+            tag: Decode::decode(&mut patch_bytes)?,
+            patch_type: decode_patch_type(marker, &mut patch_bytes, 0)?,
+        };
+        // the fields must fill the length of the patch
+        if !patch_bytes.is_empty() {
+            return Err(invalid(bytes));
+        }
+        patches.push(patch);
+    }
+    if bytes.is_empty() {
+        Ok(patches)
+    } else {
+        Err(DecodeError::TrailingBytes)
+    }
+}
+
+/// the marker of the patch type in the encoded form
+fn marker<Ns, Tag, Leaf, Att, Val>(
+    patch_type: &PatchType<'_, Ns, Tag, Leaf, Att, Val>,
+) -> u8
+where
+    Ns: PartialEq + Clone + Debug + Encode,
+    Tag: PartialEq + Debug + Encode,
+    Leaf: PartialEq + Clone + Debug + Encode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Encode,
+    Val: PartialEq + Clone + Debug + Encode,
+{
+    match patch_type {
+        PatchType::InsertBeforeNode { .. } => INSERT_BEFORE_NODE,
+        PatchType::InsertAfterNode { .. } => INSERT_AFTER_NODE,
+        PatchType::AppendChildren { .. } => APPEND_CHILDREN,
+        PatchType::RemoveNode => REMOVE_NODE,
+        PatchType::MoveBeforeNode { .. } => MOVE_BEFORE_NODE,
+        PatchType::MoveAfterNode { .. } => MOVE_AFTER_NODE,
+        PatchType::MoveIntoNode { .. } => MOVE_INTO_NODE,
+        PatchType::ReplaceNode { .. } => REPLACE_NODE,
+        PatchType::WrapNode { .. } => WRAP_NODE,
+        PatchType::UnwrapNode => UNWRAP_NODE,
+        PatchType::ChangeLeaf { .. } => CHANGE_LEAF,
+        PatchType::SpliceLeaf { .. } => SPLICE_LEAF,
+        PatchType::ChangeTag { .. } => CHANGE_TAG,
+        PatchType::SyncIsland => SYNC_ISLAND,
+        PatchType::ChangeLeafRun { .. } => CHANGE_LEAF_RUN,
+        PatchType::AddAttributes { .. } => ADD_ATTRIBUTES,
+        PatchType::RemoveAttributes { .. } => REMOVE_ATTRIBUTES,
+        PatchType::UpdateAttributes { .. } => UPDATE_ATTRIBUTES,
+        PatchType::UpdateAttributeParts { .. } => UPDATE_ATTRIBUTE_PARTS,
+        PatchType::ForEachChild { .. } => FOR_EACH_CHILD,
+        PatchType::Broadcast { .. } => BROADCAST,
+        PatchType::AdoptNode { .. } => ADOPT_NODE,
+        PatchType::SetSlot { .. } => SET_SLOT,
+        PatchType::ClearSlot { .. } => CLEAR_SLOT,
+    }
+}
+
+/// encode the fields of the patch type, after its marker
+fn encode_fields<Ns, Tag, Leaf, Att, Val>(
+    patch_type: &PatchType<'_, Ns, Tag, Leaf, Att, Val>,
+    buf: &mut Vec<u8>,
+) where
+    Ns: PartialEq + Clone + Debug + Encode,
+    Tag: PartialEq + Debug + Encode,
+    Leaf: PartialEq + Clone + Debug + Encode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Encode,
+    Val: PartialEq + Clone + Debug + Encode,
+{
+    match patch_type {
+        PatchType::InsertBeforeNode { nodes }
+        | PatchType::InsertAfterNode { nodes }
+        | PatchType::AppendChildren { children: nodes }
+        | PatchType::ReplaceNode { replacement: nodes } => {
+            encode_refs(nodes, buf)
+        }
+        PatchType::RemoveNode
+        | PatchType::UnwrapNode
+        | PatchType::SyncIsland => (),
+        PatchType::MoveBeforeNode { nodes_path }
+        | PatchType::MoveAfterNode { nodes_path }
+        | PatchType::MoveIntoNode { nodes_path } => nodes_path.encode(buf),
+        PatchType::WrapNode { wrapper } => wrapper.encode(buf),
+        PatchType::ChangeLeaf { old, new } => {
+            old.encode(buf);
+            new.encode(buf);
+        }
+        PatchType::SpliceLeaf { old, new, splices } => {
+            old.encode(buf);
+            new.encode(buf);
+            splices.encode(buf);
+        }
+        PatchType::ChangeTag { old, new } => {
+            old.encode(buf);
+            new.encode(buf);
+        }
+        PatchType::ChangeLeafRun { count, leaves } => {
+            encode_varint(*count as u64, buf);
+            encode_refs(leaves, buf);
+        }
+        PatchType::AddAttributes { attrs }
+        | PatchType::RemoveAttributes { attrs } => encode_refs(attrs, buf),
+        PatchType::UpdateAttributes { add, remove } => {
+            encode_refs(add, buf);
+            encode_refs(remove, buf);
+        }
+        PatchType::UpdateAttributeParts { attr, parts } => {
+            attr.encode(buf);
+            parts.encode(buf);
+        }
+        PatchType::ForEachChild { patch } => {
+            buf.push(marker(patch));
+            encode_fields(patch, buf);
+        }
+        PatchType::Broadcast { selector, patch } => {
+            selector.encode(buf);
+            buf.push(marker(patch));
+            encode_fields(patch, buf);
+        }
+        PatchType::AdoptNode {
+            from_tree,
+            from_path,
+            placement,
+        } => {
+            from_tree.encode(buf);
+            from_path.encode(buf);
+            placement.encode(buf);
+        }
+        PatchType::SetSlot { slot, node } => {
+            slot.encode(buf);
+            node.encode(buf);
+        }
+        PatchType::ClearSlot { slot } => slot.encode(buf),
+    }
+}
+
+/// decode the fields of the patch type of the `marker`,
+/// nested in `depth` of the `ForEachChild` and `Broadcast` patches
+fn decode_patch_type<Ns, Tag, Leaf, Att, Val>(
+    marker: u8,
+    bytes: &mut &[u8],
+    depth: usize,
+) -> Result<OwnedPatchType<Ns, Tag, Leaf, Att, Val>, DecodeError>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    let patch_type = match marker {
+        INSERT_BEFORE_NODE => OwnedPatchType::InsertBeforeNode {
+            nodes: Decode::decode(bytes)?,
+        },
+        INSERT_AFTER_NODE => OwnedPatchType::InsertAfterNode {
+            nodes: Decode::decode(bytes)?,
+        },
+        APPEND_CHILDREN => OwnedPatchType::AppendChildren {
+            children: Decode::decode(bytes)?,
+        },
+        REMOVE_NODE => OwnedPatchType::RemoveNode,
+        MOVE_BEFORE_NODE => OwnedPatchType::MoveBeforeNode {
+            nodes_path: Decode::decode(bytes)?,
+        },
+        MOVE_AFTER_NODE => OwnedPatchType::MoveAfterNode {
+            nodes_path: Decode::decode(bytes)?,
+        },
+        MOVE_INTO_NODE => OwnedPatchType::MoveIntoNode {
+            nodes_path: Decode::decode(bytes)?,
+        },
+        REPLACE_NODE => OwnedPatchType::ReplaceNode {
+            replacement: Decode::decode(bytes)?,
+        },
+        WRAP_NODE => OwnedPatchType::WrapNode {
+            wrapper: Decode::decode(bytes)?,
+        },
+        UNWRAP_NODE => OwnedPatchType::UnwrapNode,
+        CHANGE_LEAF => OwnedPatchType::ChangeLeaf {
+            old: Decode::decode(bytes)?,
+            new: Decode::decode(bytes)?,
+        },
+        SPLICE_LEAF => OwnedPatchType::SpliceLeaf {
+            old: Decode::decode(bytes)?,
+            new: Decode::decode(bytes)?,
+            splices: Decode::decode(bytes)?,
+        },
+        CHANGE_TAG => OwnedPatchType::ChangeTag {
+            old: Decode::decode(bytes)?,
+            new: Decode::decode(bytes)?,
+        },
+        SYNC_ISLAND => OwnedPatchType::SyncIsland,
+        CHANGE_LEAF_RUN => OwnedPatchType::ChangeLeafRun {
+            count: decode_usize(bytes)?,
+            leaves: Decode::decode(bytes)?,
+        },
+        ADD_ATTRIBUTES => OwnedPatchType::AddAttributes {
+            attrs: Decode::decode(bytes)?,
+        },
+        REMOVE_ATTRIBUTES => OwnedPatchType::RemoveAttributes {
+            attrs: Decode::decode(bytes)?,
+        },
+        UPDATE_ATTRIBUTES => OwnedPatchType::UpdateAttributes {
+            add: Decode::decode(bytes)?,
+            remove: Decode::decode(bytes)?,
+        },
+        UPDATE_ATTRIBUTE_PARTS => OwnedPatchType::UpdateAttributeParts {
+            attr: Decode::decode(bytes)?,
+            parts: Decode::decode(bytes)?,
+        },
+        FOR_EACH_CHILD => OwnedPatchType::ForEachChild {
+            patch: decode_inner_patch_type(bytes, depth)?,
+        },
+        BROADCAST => OwnedPatchType::Broadcast {
+            selector: Decode::decode(bytes)?,
+            patch: decode_inner_patch_type(bytes, depth)?,
+        },
+        ADOPT_NODE => OwnedPatchType::AdoptNode {
+            from_tree: Decode::decode(bytes)?,
+            from_path: Decode::decode(bytes)?,
+            placement: Decode::decode(bytes)?,
+        },
+        SET_SLOT => OwnedPatchType::SetSlot {
+            slot: Decode::decode(bytes)?,
+            node: Decode::decode(bytes)?,
+        },
+        CLEAR_SLOT => OwnedPatchType::ClearSlot {
+            slot: Decode::decode(bytes)?,
+        },
+        _ => return Err(invalid(bytes)),
+    };
+    Ok(patch_type)
+}
+
+/// decode the patch type of `ForEachChild` and `Broadcast`, which has no path and tag,
+/// nested in `depth` of them
+fn decode_inner_patch_type<Ns, Tag, Leaf, Att, Val>(
+    bytes: &mut &[u8],
+    depth: usize,
+) -> Result<Box<OwnedPatchType<Ns, Tag, Leaf, Att, Val>>, DecodeError>
+where
+    Ns: PartialEq + Clone + Debug + Decode,
+    Tag: PartialEq + Debug + Decode,
+    Leaf: PartialEq + Clone + Debug + Decode,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Decode,
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    if depth >= MAX_NESTED_PATCHES {
+        return Err(invalid(bytes));
+    }
+    let marker = take_byte(bytes)?;
+    decode_patch_type(marker, bytes, depth + 1).map(Box::new)
+}
+
+/// encode the referenced items the same as a `Vec` of the items,
+/// so they are decoded as a `Vec`
+fn encode_refs<T: Encode>(items: &[&T], buf: &mut Vec<u8>) {
+    encode_varint(items.len() as u64, buf);
+    for item in items {
+        item.encode(buf);
+    }
+}
+
+fn decode_usize(bytes: &mut &[u8]) -> Result<usize, DecodeError> {
+    let value = decode_varint(bytes)?;
+    usize::try_from(value).map_err(|_| invalid(bytes))
+}
+
+impl Encode for TreePath {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_varint(self.path.len() as u64, buf);
+        for index in &self.path {
+            encode_varint(*index as u64, buf);
+        }
+    }
+}
+
+impl Decode for TreePath {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = decode_len(bytes)?;
+        let path = (0..len)
+            .map(|_| {
+                let index = decode_varint(bytes)?;
+                PathIndex::try_from(index).map_err(|_| invalid(bytes))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(TreePath { path })
+    }
+}
+
+impl Encode for LeafSplice {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for value in
+            [self.old_start, self.old_len, self.new_start, self.new_len]
+        {
+            encode_varint(value as u64, buf);
+        }
+    }
+}
+
+impl Decode for LeafSplice {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(LeafSplice {
+            old_start: decode_usize(bytes)?,
+            old_len: decode_usize(bytes)?,
+            new_start: decode_usize(bytes)?,
+            new_len: decode_usize(bytes)?,
+        })
+    }
+}
+
+impl<Val> Encode for AttributePart<Val>
+where
+    Val: PartialEq + Clone + Debug + Encode,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            AttributePart::Set { key, value } => {
+                buf.push(0);
+                key.encode(buf);
+                value.encode(buf);
+            }
+            AttributePart::Remove { key } => {
+                buf.push(1);
+                key.encode(buf);
+            }
+        }
+    }
+}
+
+impl<Val> Decode for AttributePart<Val>
+where
+    Val: PartialEq + Clone + Debug + Decode,
+{
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take_byte(bytes)? {
+            0 => Ok(AttributePart::Set {
+                key: Decode::decode(bytes)?,
+                value: Decode::decode(bytes)?,
+            }),
+            1 => Ok(AttributePart::Remove {
+                key: Decode::decode(bytes)?,
+            }),
+            _ => Err(invalid(bytes)),
+        }
+    }
+}
+
+/// the selector is decoded as an `OwnedSelector`
+impl<Tag, Att, Val> Encode for Selector<'_, Tag, Att, Val>
+where
+    Tag: Encode,
+    Att: Encode,
+    Val: Encode,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Selector::AllElements => buf.push(0),
+            Selector::Tag(tag) => {
+                buf.push(1);
+                tag.encode(buf);
+            }
+            Selector::HasAttribute(name) => {
+                buf.push(2);
+                name.encode(buf);
+            }
+            Selector::AttributeValue(name, value) => {
+                buf.push(3);
+                name.encode(buf);
+                value.encode(buf);
+            }
+        }
+    }
+}
+
+impl<Tag, Att, Val> Decode for OwnedSelector<Tag, Att, Val>
+where
+    Tag: Decode,
+    Att: Decode,
+    Val: Decode,
+{
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take_byte(bytes)? {
+            0 => Ok(OwnedSelector::AllElements),
+            1 => Ok(OwnedSelector::Tag(Decode::decode(bytes)?)),
+            2 => Ok(OwnedSelector::HasAttribute(Decode::decode(bytes)?)),
+            3 => Ok(OwnedSelector::AttributeValue(
+                Decode::decode(bytes)?,
+                Decode::decode(bytes)?,
+            )),
+            _ => Err(invalid(bytes)),
+        }
+    }
+}
+
+impl Encode for TreeId {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_varint(self.0 as u64, buf);
+    }
+}
+
+impl Decode for TreeId {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        decode_usize(bytes).map(TreeId)
+    }
+}
+
+impl Encode for Placement {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(match self {
+            Placement::Before => 0,
+            Placement::After => 1,
+            Placement::Append => 2,
+        });
+    }
+}
+
+impl Decode for Placement {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take_byte(bytes)? {
+            0 => Ok(Placement::Before),
+            1 => Ok(Placement::After),
+            2 => Ok(Placement::Append),
+            _ => Err(invalid(bytes)),
+        }
+    }
+}
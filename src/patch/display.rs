@@ -0,0 +1,303 @@
+//! the human readable rendering of the patches and the node trees, for debugging
+use super::{PatchType, Selector};
+use crate::{Attribute, Element, Node, Patch};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::fmt::Write;
+use core::hash::Hash;
+
+/// the maximum number of characters of the leaves in the previews of the nodes
+const PREVIEW_LEN: usize = 40;
+
+/// Render the patches as an indented list, with a line for each patch showing its path,
+/// its kind, the tag of its target and a short preview of its nodes or attributes.
+/// The patches are indented by the depth of their path, so the patches of the
+/// nested nodes stand out from the patches of their ancestors.
+///
+/// This is intended for debugging large diffs, where the `Debug` output of the patches
+/// is unreadable.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![attr("class", "a")], vec![leaf("hello")]);
+/// let new: MyNode = element("main", vec![attr("class", "b")], vec![
+///     element("p", vec![], vec![leaf("world")]),
+/// ]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// assert_eq!(
+///     render_patches(&patches),
+///     "[] add_attributes <main> class=\"b\"\n  [0] replace_node <p> (1 child)\n"
+/// );
+/// ```
+pub fn render_patches<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut out = String::new();
+    for patch in patches {
+        let path = patch.path();
+        indent(&mut out, path.path.len());
+        out.push_str(&format!("{} {}", path.display(), patch.kind()));
+        if let Some(tag) = patch.tag() {
+            out.push_str(&format!(" <{}>", plain(tag)));
+        }
+        let details = details(&patch.patch_type);
+        if !details.is_empty() {
+            out.push(' ');
+            out.push_str(&details);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// render the node tree with a line for each node, indented by its depth.
+/// The tree is walked with an explicit stack, so very deep trees are rendered
+/// without overflowing the stack.
+pub(crate) fn render_tree<Ns, Tag, Leaf, Att, Val>(
+    node: &Node<Ns, Tag, Leaf, Att, Val>,
+) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut out = String::new();
+    let mut pending = vec![(node, 0)];
+    while let Some((node, depth)) = pending.pop() {
+        indent(&mut out, depth);
+        match node {
+            Node::Element(element) => out.push_str(&open_tag(element)),
+            Node::Fragment(_) => out.push_str("fragment"),
+            Node::NodeList(_) => out.push_str("node_list"),
+            Node::Leaf(leaf) => {
+                let _ = write!(out, "{:?}", leaf);
+            }
+        }
+        out.push('\n');
+        pending.extend(
+            node.child_nodes()
+                .iter()
+                .rev()
+                .map(|child| (child, depth + 1)),
+        );
+    }
+    out
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+/// the details of the patch after its tag, such as the previews of its nodes
+fn details<Ns, Tag, Leaf, Att, Val>(
+    patch_type: &PatchType<Ns, Tag, Leaf, Att, Val>,
+) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    match patch_type {
+        PatchType::InsertBeforeNode { nodes }
+        | PatchType::InsertAfterNode { nodes }
+        | PatchType::AppendChildren { children: nodes }
+        | PatchType::ReplaceNode { replacement: nodes } => {
+            join(nodes.iter().map(|node| preview(node)))
+        }
+        PatchType::MoveBeforeNode { nodes_path }
+        | PatchType::MoveAfterNode { nodes_path }
+        | PatchType::MoveIntoNode { nodes_path } => {
+            join(nodes_path.iter().map(|path| format!("{}", path.display())))
+        }
+        PatchType::WrapNode { wrapper } => match wrapper {
+            Node::Element(element) => open_tag(element),
+            _ => preview(wrapper),
+        },
+        PatchType::ChangeLeaf { old, new }
+        | PatchType::SpliceLeaf { old, new, .. } => {
+            format!("{} -> {}", short(old), short(new))
+        }
+        PatchType::ChangeLeafRun { count, leaves } => {
+            format!("{} leaves -> {}", count, join(leaves.iter().map(short)))
+        }
+        PatchType::ChangeTag { old, new } => {
+            format!("<{}> -> <{}>", plain(old), plain(new))
+        }
+        PatchType::AddAttributes { attrs } => {
+            join(attrs.iter().map(|att| attribute(att)))
+        }
+        PatchType::RemoveAttributes { attrs } => {
+            join(attrs.iter().map(|att| plain(&att.name)))
+        }
+        PatchType::UpdateAttributes { add, remove } => {
+            join(add.iter().map(|att| format!("+{}", attribute(att))).chain(
+                remove.iter().map(|att| format!("-{}", plain(&att.name))),
+            ))
+        }
+        PatchType::UpdateAttributeParts { attr, parts } => {
+            format!("{} {:?}", plain(&attr.name), parts)
+        }
+        PatchType::ForEachChild { patch } => inner_details(patch),
+        PatchType::Broadcast { selector, patch } => {
+            let selector = match selector {
+                Selector::AllElements => String::from("*"),
+                Selector::Tag(tag) => format!("<{}>", plain(tag)),
+                Selector::HasAttribute(name) => format!("[{}]", plain(name)),
+                Selector::AttributeValue(name, value) => {
+                    format!("[{}={:?}]", plain(name), value)
+                }
+            };
+            format!("{} {}", selector, inner_details(patch))
+        }
+        PatchType::AdoptNode {
+            from_tree,
+            from_path,
+            placement,
+        } => format!(
+            "{:?} {} of tree {}",
+            placement,
+            from_path.display(),
+            from_tree.0
+        ),
+        PatchType::SetSlot { slot, node } => {
+            format!("{} {}", plain(slot), preview(node))
+        }
+        PatchType::ClearSlot { slot } => plain(slot),
+        PatchType::RemoveNode
+        | PatchType::UnwrapNode
+        | PatchType::SyncIsland => String::new(),
+    }
+}
+
+/// the kind and the details of the patch which is applied to other nodes
+fn inner_details<Ns, Tag, Leaf, Att, Val>(
+    patch_type: &PatchType<Ns, Tag, Leaf, Att, Val>,
+) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let details = details(patch_type);
+    if details.is_empty() {
+        format!("{}", patch_type.kind())
+    } else {
+        format!("{} {}", patch_type.kind(), details)
+    }
+}
+
+/// the opening tag of the element with its attributes, without its children,
+/// and the number of its children
+fn preview<Ns, Tag, Leaf, Att, Val>(
+    node: &Node<Ns, Tag, Leaf, Att, Val>,
+) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    match node {
+        Node::Element(element) => {
+            format!("{}{}", open_tag(element), count(element.children.len()))
+        }
+        Node::Fragment(nodes) => format!("fragment{}", count(nodes.len())),
+        Node::NodeList(nodes) => format!("node_list{}", count(nodes.len())),
+        Node::Leaf(leaf) => short(&leaf),
+    }
+}
+
+fn count(children: usize) -> String {
+    match children {
+        0 => String::new(),
+        1 => String::from(" (1 child)"),
+        n => format!(" ({} children)", n),
+    }
+}
+
+fn open_tag<Ns, Tag, Leaf, Att, Val>(
+    element: &Element<Ns, Tag, Leaf, Att, Val>,
+) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut out = format!("<{}", plain(&element.tag));
+    for att in element.attrs.iter() {
+        out.push(' ');
+        out.push_str(&attribute(att));
+    }
+    out.push('>');
+    out
+}
+
+/// the attribute as `name="value"`, the attributes with several values
+/// are rendered with the list of their values
+fn attribute<Ns, Att, Val>(att: &Attribute<Ns, Att, Val>) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    match att.value.as_slice() {
+        [] => plain(&att.name),
+        [value] => format!("{}={:?}", plain(&att.name), value),
+        values => format!("{}={:?}", plain(&att.name), values),
+    }
+}
+
+/// the `Debug` output of the value, truncated to `PREVIEW_LEN` characters
+fn short(value: &impl Debug) -> String {
+    let text = format!("{:?}", value);
+    if text.chars().count() <= PREVIEW_LEN {
+        return text;
+    }
+    let mut short: String = text.chars().take(PREVIEW_LEN).collect();
+    short.push('…');
+    short
+}
+
+/// the `Debug` output of the names, such as the tags and the attribute names,
+/// without the quotes of the strings
+fn plain(value: &impl Debug) -> String {
+    let text = format!("{:?}", value);
+    match text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+    {
+        Some(unquoted) => String::from(unquoted),
+        None => text,
+    }
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
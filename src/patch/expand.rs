@@ -0,0 +1,225 @@
+//! expand the nodes created by the patches into flat instructions
+use crate::{Attribute, Node, Patch, PatchType, TreePath};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// A flat instruction for building the nodes created by the patches,
+/// the created nodes are referred to by their `id` which is unique in a batch of instructions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instruction<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// create an element without its attributes and children
+    CreateElement {
+        /// the id of the created element
+        id: usize,
+        /// the namespace of the element
+        namespace: Option<&'a Ns>,
+        /// the tag of the element
+        tag: &'a Tag,
+        /// is the element has a self closing tag
+        self_closing: bool,
+    },
+    /// create a leaf node
+    CreateLeaf {
+        /// the id of the created leaf
+        id: usize,
+        /// the leaf
+        leaf: &'a Leaf,
+    },
+    /// set the attribute of a created element
+    SetAttribute {
+        /// the id of the created element
+        id: usize,
+        /// the attribute to be set
+        attr: &'a Attribute<Ns, Att, Val>,
+    },
+    /// append a created node into a created element
+    AppendChild {
+        /// the id of the parent element
+        parent: usize,
+        /// the id of the child node
+        child: usize,
+    },
+    /// insert the created nodes before the node at `path`
+    InsertBefore {
+        /// the path of the target node
+        path: TreePath,
+        /// the ids of the created nodes
+        ids: Vec<usize>,
+    },
+    /// insert the created nodes after the node at `path`
+    InsertAfter {
+        /// the path of the target node
+        path: TreePath,
+        /// the ids of the created nodes
+        ids: Vec<usize>,
+    },
+    /// append the created nodes into the node at `path`
+    Append {
+        /// the path of the target node
+        path: TreePath,
+        /// the ids of the created nodes
+        ids: Vec<usize>,
+    },
+    /// replace the node at `path` with the created nodes
+    Replace {
+        /// the path of the target node
+        path: TreePath,
+        /// the ids of the created nodes
+        ids: Vec<usize>,
+    },
+    /// a patch which does not create nodes
+    Patch(Patch<'a, Ns, Tag, Leaf, Att, Val>),
+}
+
+/// Expand the nodes referenced by the `InsertBeforeNode`, `InsertAfterNode`,
+/// `AppendChildren` and `ReplaceNode` patches into a flat list of
+/// `CreateElement`, `CreateLeaf`, `SetAttribute` and `AppendChild` instructions,
+/// so minimal appliers don't need to implement a recursive node construction.
+///
+/// The nodes of fragments and node lists are unrolled into their parent.
+/// The rest of the patches are passed as is in `Instruction::Patch`.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![], vec![]);
+/// let new: MyNode = element("main", vec![], vec![
+///     element("a", vec![attr("href", "/")], vec![leaf("home")]),
+/// ]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// let class = attr("href", "/");
+/// assert_eq!(
+///     expand_creations(&patches),
+///     vec![
+///         Instruction::CreateElement { id: 0, namespace: None, tag: &"a", self_closing: false },
+///         Instruction::SetAttribute { id: 0, attr: &class },
+///         Instruction::CreateLeaf { id: 1, leaf: &"home" },
+///         Instruction::AppendChild { parent: 0, child: 1 },
+///         Instruction::Append { path: TreePath::root(), ids: vec![0] },
+///     ]
+/// );
+/// ```
+pub fn expand_creations<'a, Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<'a, Ns, Tag, Leaf, Att, Val>],
+) -> Vec<Instruction<'a, Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut expander = Expander {
+        instructions: vec![],
+        next_id: 0,
+    };
+    for patch in patches {
+        let path = patch.path().clone();
+        let instruction = match &patch.patch_type {
+            PatchType::InsertBeforeNode { nodes } => {
+                Instruction::InsertBefore {
+                    path,
+                    ids: expander.create_all(nodes.iter().copied()),
+                }
+            }
+            PatchType::InsertAfterNode { nodes } => Instruction::InsertAfter {
+                path,
+                ids: expander.create_all(nodes.iter().copied()),
+            },
+            PatchType::AppendChildren { children } => Instruction::Append {
+                path,
+                ids: expander.create_all(children.iter().copied()),
+            },
+            PatchType::ReplaceNode { replacement } => Instruction::Replace {
+                path,
+                ids: expander.create_all(replacement.iter().copied()),
+            },
+            _ => Instruction::Patch(patch.clone()),
+        };
+        expander.instructions.push(instruction);
+    }
+    expander.instructions
+}
+
+struct Expander<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    instructions: Vec<Instruction<'a, Ns, Tag, Leaf, Att, Val>>,
+    next_id: usize,
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> Expander<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// create the nodes and return their ids, fragments and node lists are unrolled
+    fn create_all(
+        &mut self,
+        nodes: impl IntoIterator<Item = &'a Node<Ns, Tag, Leaf, Att, Val>>,
+    ) -> Vec<usize> {
+        let mut ids = vec![];
+        for node in nodes {
+            match node {
+                Node::Element(element) => {
+                    let id = self.next_id();
+                    self.instructions.push(Instruction::CreateElement {
+                        id,
+                        namespace: element.namespace.as_ref(),
+                        tag: &element.tag,
+                        self_closing: element.self_closing,
+                    });
+                    for attr in element.attributes() {
+                        self.instructions
+                            .push(Instruction::SetAttribute { id, attr });
+                    }
+                    for child in self.create_all(element.children()) {
+                        self.instructions.push(Instruction::AppendChild {
+                            parent: id,
+                            child,
+                        });
+                    }
+                    ids.push(id);
+                }
+                Node::Leaf(leaf) => {
+                    let id = self.next_id();
+                    self.instructions
+                        .push(Instruction::CreateLeaf { id, leaf });
+                    ids.push(id);
+                }
+                Node::Fragment(nodes) | Node::NodeList(nodes) => {
+                    ids.extend(self.create_all(nodes));
+                }
+            }
+        }
+        ids
+    }
+
+    fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
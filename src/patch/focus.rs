@@ -0,0 +1,119 @@
+//! checking whether a node, such as the focused element, is destroyed by the patches
+use crate::{Patch, PatchType, TreePath};
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// How the node at a path is destroyed by a patch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Destruction {
+    /// the node or one of its ancestors is removed
+    Removed,
+    /// the node is unwrapped, its children are kept
+    Unwrapped,
+    /// the node or one of its ancestors is replaced by other nodes
+    Replaced,
+}
+
+impl Destruction {
+    /// the past tense of the destruction, used in the messages
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Removed => "removed",
+            Self::Unwrapped => "unwrapped",
+            Self::Replaced => "replaced",
+        }
+    }
+}
+
+/// return how the node at `path` in the old tree is destroyed by the `patch`, if it is
+pub(crate) fn destruction<Ns, Tag, Leaf, Att, Val>(
+    patch: &Patch<'_, Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+) -> Option<Destruction>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let target = patch.path();
+    let covers = target == path || target.is_ancestor_of(path);
+    match patch.patch_type {
+        PatchType::RemoveNode if covers => Some(Destruction::Removed),
+        PatchType::ReplaceNode { .. } if covers => Some(Destruction::Replaced),
+        PatchType::UnwrapNode if target == path => Some(Destruction::Unwrapped),
+        // the run of leaves starting at the target are all replaced
+        PatchType::ChangeLeafRun { count, .. } => {
+            let start = target.last_index()?;
+            let in_run = (start..start + count).any(|idx| {
+                target.sibling_at(idx).is_some_and(|sibling| {
+                    sibling == *path || sibling.is_ancestor_of(path)
+                })
+            });
+            in_run.then_some(Destruction::Replaced)
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if the node at `path` in the old tree is removed by the patches,
+/// either by removing the node or one of its ancestors, or by unwrapping the node.
+///
+/// This is for the hosts which move the focus away from the focused element before
+/// the patches are applied, without pattern matching all of the patch variants.
+/// See `will_replace` for the nodes which are replaced by other nodes.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("form", vec![], vec![element("input", vec![], vec![])]);
+/// let new: MyNode = element("form", vec![], vec![]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// assert!(will_remove(&TreePath::new([0]), &patches));
+/// assert!(!will_replace(&TreePath::new([0]), &patches));
+/// ```
+pub fn will_remove<Ns, Tag, Leaf, Att, Val>(
+    path: &TreePath,
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+) -> bool
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    patches.iter().any(|patch| {
+        matches!(
+            destruction(patch, path),
+            Some(Destruction::Removed | Destruction::Unwrapped)
+        )
+    })
+}
+
+/// Returns true if the node at `path` in the old tree is replaced by other nodes,
+/// either by replacing the node or one of its ancestors, or by changing the run
+/// of leaves which the node is part of.
+///
+/// The host can move the focus to the replacement after the patches are applied.
+pub fn will_replace<Ns, Tag, Leaf, Att, Val>(
+    path: &TreePath,
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+) -> bool
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    patches
+        .iter()
+        .any(|patch| destruction(patch, path) == Some(Destruction::Replaced))
+}
@@ -0,0 +1,485 @@
+//! A stable JSON format of the patches, for the appliers which are not written in Rust,
+//! such as a javascript runtime or a flutter shell.
+//!
+//! The patches are a JSON array where each patch is an object with:
+//! - `op`: the name of the patch type in snake case, such as `"insert_before_node"`
+//! - `path`: the child indices of the target node, such as `[0, 1]`
+//! - `tag`: the tag of the target node, or `null`
+//! - the fields of the patch type, named the same as the fields of `PatchType`
+//!
+//! | op                       | fields                                  |
+//! |--------------------------|-----------------------------------------|
+//! | `insert_before_node`     | `nodes`                                 |
+//! | `insert_after_node`      | `nodes`                                 |
+//! | `append_children`        | `children`                              |
+//! | `remove_node`            |                                         |
+//! | `move_before_node`       | `nodes_path`                            |
+//! | `move_after_node`        | `nodes_path`                            |
+//! | `move_into_node`         | `nodes_path`                            |
+//! | `replace_node`           | `replacement`                           |
+//! | `wrap_node`              | `wrapper`                               |
+//! | `unwrap_node`            |                                         |
+//! | `change_leaf`            | `old`, `new`                            |
+//! | `splice_leaf`            | `old`, `new`, `splices`                 |
+//! | `change_tag`             | `old`, `new`                            |
+//! | `sync_island`            |                                         |
+//! | `change_leaf_run`        | `count`, `leaves`                       |
+//! | `add_attributes`         | `attrs`                                 |
+//! | `remove_attributes`      | `attrs`                                 |
+//! | `update_attributes`      | `add`, `remove`                         |
+//! | `update_attribute_parts` | `attr`, `parts`                         |
+//! | `for_each_child`         | `patch`                                 |
+//! | `broadcast`              | `selector`, `patch`                     |
+//! | `adopt_node`             | `from_tree`, `from_path`, `placement`   |
+//! | `set_slot`               | `slot`, `node`                          |
+//! | `clear_slot`             | `slot`                                  |
+//!
+//! The paths in `nodes_path` and `from_path` are arrays of child indices the same as `path`.
+//! The `patch` of `for_each_child` and `broadcast` is an object with the `op` and the fields
+//! of the inner patch type, without a `path` and a `tag`.
+//! The nodes, attributes, leaves, splices, attribute parts, selectors, tree ids and
+//! placements are in their serde format, such as:
+//! ```json
+//! {"Element": {"namespace": null, "tag": "li", "self_closing": false,
+//!     "attrs": [{"namespace": null, "name": "key", "value": ["1"]}],
+//!     "children": [{"Leaf": "1"}]}}
+//! ```
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{patch::*, *};
+//!
+//! pub type MyNode = Node<String, String, String, String, String>;
+//!
+//! let old: MyNode = element("div".to_string(), vec![], vec![leaf("old".to_string())]);
+//! let new: MyNode = element("div".to_string(), vec![], vec![leaf("new".to_string())]);
+//! let patches = diff(&old, &new);
+//!
+//! let json = to_json(&patches);
+//! assert_eq!(json[0]["op"], "replace_node");
+//! assert_eq!(json[0]["path"], serde_json::json!([0]));
+//!
+//! let owned: Vec<OwnedPatch<String, String, String, String, String>> =
+//!     from_json(&json).unwrap();
+//! assert_eq!(Node::from_patches(Some(old), &owned), new);
+//! ```
+use super::{OwnedPatch, OwnedPatchType, Patch, PatchType};
+use crate::TreePath;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The reason why a JSON value can not be read as patches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPatchError {
+    /// the value is not an array of patches
+    NotAnArray,
+    /// the patch at `index` is not an object
+    NotAnObject {
+        /// the index of the patch
+        index: usize,
+    },
+    /// the patch at `index` has an unknown `op`
+    UnknownOp {
+        /// the index of the patch
+        index: usize,
+        /// the unknown op
+        op: String,
+    },
+    /// the patch at `index` does not have the `field`
+    MissingField {
+        /// the index of the patch
+        index: usize,
+        /// the name of the missing field
+        field: &'static str,
+    },
+    /// the `field` of the patch at `index` can not be read
+    InvalidField {
+        /// the index of the patch
+        index: usize,
+        /// the name of the invalid field
+        field: &'static str,
+        /// why the field can not be read
+        message: String,
+    },
+}
+
+impl fmt::Display for JsonPatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotAnArray => write!(f, "the patches are not an array"),
+            Self::NotAnObject { index } => {
+                write!(f, "the patch at {} is not an object", index)
+            }
+            Self::UnknownOp { index, op } => {
+                write!(f, "the patch at {} has an unknown op `{}`", index, op)
+            }
+            Self::MissingField { index, field } => {
+                write!(f, "the patch at {} has no `{}`", index, field)
+            }
+            Self::InvalidField {
+                index,
+                field,
+                message,
+            } => {
+                write!(
+                    f,
+                    "the `{}` of the patch at {} is invalid: {}",
+                    field, index, message
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonPatchError {}
+
+/// convert the patches into the JSON format which is described in the module documentation
+///
+/// # Panics
+/// Panics if the nodes, attributes or leaves can not be serialized into JSON,
+/// such as when their `Serialize` implementation fails
+pub fn to_json<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+) -> Value
+where
+    Ns: PartialEq + Clone + Debug + Serialize,
+    Tag: PartialEq + Debug + Serialize,
+    Leaf: PartialEq + Clone + Debug + Serialize,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Serialize,
+    Val: PartialEq + Clone + Debug + Serialize,
+{
+    Value::Array(
+        patches
+            .iter()
+            .map(|patch| {
+                let mut object = patch_type_to_json(&patch.patch_type);
+                object.insert(
+                    "path".to_string(),
+                    to_value(&patch.patch_path.path),
+                );
+                object.insert("tag".to_string(), to_value(&patch.tag));
+                Value::Object(object)
+            })
+            .collect(),
+    )
+}
+
+/// read the patches from the JSON format which is described in the module documentation,
+/// such as the JSON which is created with `to_json`
+pub fn from_json<Ns, Tag, Leaf, Att, Val>(
+    json: &Value,
+) -> Result<Vec<OwnedPatch<Ns, Tag, Leaf, Att, Val>>, JsonPatchError>
+where
+    Ns: PartialEq + Clone + Debug + DeserializeOwned,
+    Tag: PartialEq + Debug + DeserializeOwned,
+    Leaf: PartialEq + Clone + Debug + DeserializeOwned,
+    Att: PartialEq + Eq + Hash + Clone + Debug + DeserializeOwned,
+    Val: PartialEq + Clone + Debug + DeserializeOwned,
+{
+    let patches = json.as_array().ok_or(JsonPatchError::NotAnArray)?;
+    patches
+        .iter()
+        .enumerate()
+        .map(|(index, patch)| {
+            let fields = Fields::new(patch, index)?;
+            Ok(OwnedPatch {
+                tag: fields.get("tag")?,
+                patch_path: fields.path("path")?,
+                patch_type: patch_type_from_json(&fields)?,
+            })
+        })
+        .collect()
+}
+
+fn to_value<T: Serialize + ?Sized>(value: &T) -> Value {
+    serde_json::to_value(value)
+        .unwrap_or_else(|e| panic!("the patch can not be serialized: {}", e))
+}
+
+fn paths_to_value(paths: &[TreePath]) -> Value {
+    Value::Array(paths.iter().map(|path| to_value(&path.path)).collect())
+}
+
+/// the `op` and the fields of the patch type
+fn patch_type_to_json<Ns, Tag, Leaf, Att, Val>(
+    patch_type: &PatchType<'_, Ns, Tag, Leaf, Att, Val>,
+) -> Map<String, Value>
+where
+    Ns: PartialEq + Clone + Debug + Serialize,
+    Tag: PartialEq + Debug + Serialize,
+    Leaf: PartialEq + Clone + Debug + Serialize,
+    Att: PartialEq + Eq + Hash + Clone + Debug + Serialize,
+    Val: PartialEq + Clone + Debug + Serialize,
+{
+    let (op, fields): (&str, Vec<(&str, Value)>) = match patch_type {
+        PatchType::InsertBeforeNode { nodes } => {
+            ("insert_before_node", vec![("nodes", to_value(nodes))])
+        }
+        PatchType::InsertAfterNode { nodes } => {
+            ("insert_after_node", vec![("nodes", to_value(nodes))])
+        }
+        PatchType::AppendChildren { children } => {
+            ("append_children", vec![("children", to_value(children))])
+        }
+        PatchType::RemoveNode => ("remove_node", vec![]),
+        PatchType::MoveBeforeNode { nodes_path } => (
+            "move_before_node",
+            vec![("nodes_path", paths_to_value(nodes_path))],
+        ),
+        PatchType::MoveAfterNode { nodes_path } => (
+            "move_after_node",
+            vec![("nodes_path", paths_to_value(nodes_path))],
+        ),
+        PatchType::MoveIntoNode { nodes_path } => (
+            "move_into_node",
+            vec![("nodes_path", paths_to_value(nodes_path))],
+        ),
+        PatchType::ReplaceNode { replacement } => {
+            ("replace_node", vec![("replacement", to_value(replacement))])
+        }
+        PatchType::WrapNode { wrapper } => {
+            ("wrap_node", vec![("wrapper", to_value(wrapper))])
+        }
+        PatchType::UnwrapNode => ("unwrap_node", vec![]),
+        PatchType::ChangeLeaf { old, new } => (
+            "change_leaf",
+            vec![("old", to_value(old)), ("new", to_value(new))],
+        ),
+        PatchType::SpliceLeaf { old, new, splices } => (
+            "splice_leaf",
+            vec![
+                ("old", to_value(old)),
+                ("new", to_value(new)),
+                ("splices", to_value(splices)),
+            ],
+        ),
+        PatchType::ChangeTag { old, new } => (
+            "change_tag",
+            vec![("old", to_value(old)), ("new", to_value(new))],
+        ),
+        PatchType::SyncIsland => ("sync_island", vec![]),
+        PatchType::ChangeLeafRun { count, leaves } => (
+            "change_leaf_run",
+            vec![("count", to_value(count)), ("leaves", to_value(leaves))],
+        ),
+        PatchType::AddAttributes { attrs } => {
+            ("add_attributes", vec![("attrs", to_value(attrs))])
+        }
+        PatchType::RemoveAttributes { attrs } => {
+            ("remove_attributes", vec![("attrs", to_value(attrs))])
+        }
+        PatchType::UpdateAttributes { add, remove } => (
+            "update_attributes",
+            vec![("add", to_value(add)), ("remove", to_value(remove))],
+        ),
+        PatchType::UpdateAttributeParts { attr, parts } => (
+            "update_attribute_parts",
+            vec![("attr", to_value(attr)), ("parts", to_value(parts))],
+        ),
+        PatchType::ForEachChild { patch } => (
+            "for_each_child",
+            vec![("patch", Value::Object(patch_type_to_json(patch)))],
+        ),
+        PatchType::Broadcast { selector, patch } => (
+            "broadcast",
+            vec![
+                ("selector", to_value(selector)),
+                ("patch", Value::Object(patch_type_to_json(patch))),
+            ],
+        ),
+        PatchType::AdoptNode {
+            from_tree,
+            from_path,
+            placement,
+        } => (
+            "adopt_node",
+            vec![
+                ("from_tree", to_value(from_tree)),
+                ("from_path", to_value(&from_path.path)),
+                ("placement", to_value(placement)),
+            ],
+        ),
+        PatchType::SetSlot { slot, node } => (
+            "set_slot",
+            vec![("slot", to_value(slot)), ("node", to_value(node))],
+        ),
+        PatchType::ClearSlot { slot } => {
+            ("clear_slot", vec![("slot", to_value(slot))])
+        }
+    };
+    let mut object = Map::new();
+    object.insert("op".to_string(), Value::String(op.to_string()));
+    for (name, value) in fields {
+        object.insert(name.to_string(), value);
+    }
+    object
+}
+
+/// the fields of a patch object, with the index of the patch for the errors
+struct Fields<'v> {
+    object: &'v Map<String, Value>,
+    index: usize,
+}
+
+impl<'v> Fields<'v> {
+    fn new(value: &'v Value, index: usize) -> Result<Self, JsonPatchError> {
+        let object = value
+            .as_object()
+            .ok_or(JsonPatchError::NotAnObject { index })?;
+        Ok(Self { object, index })
+    }
+
+    fn value(&self, field: &'static str) -> Result<&'v Value, JsonPatchError> {
+        self.object.get(field).ok_or(JsonPatchError::MissingField {
+            index: self.index,
+            field,
+        })
+    }
+
+    fn get<T: DeserializeOwned>(
+        &self,
+        field: &'static str,
+    ) -> Result<T, JsonPatchError> {
+        T::deserialize(self.value(field)?).map_err(|e| {
+            JsonPatchError::InvalidField {
+                index: self.index,
+                field,
+                message: e.to_string(),
+            }
+        })
+    }
+
+    fn path(&self, field: &'static str) -> Result<TreePath, JsonPatchError> {
+        self.get::<Vec<usize>>(field).map(TreePath::new)
+    }
+
+    fn paths(
+        &self,
+        field: &'static str,
+    ) -> Result<Vec<TreePath>, JsonPatchError> {
+        let paths = self.get::<Vec<Vec<usize>>>(field)?;
+        Ok(paths.into_iter().map(TreePath::new).collect())
+    }
+
+    /// the inner patch type of `for_each_child` and `broadcast`
+    fn patch<Ns, Tag, Leaf, Att, Val>(
+        &self,
+        field: &'static str,
+    ) -> Result<Box<OwnedPatchType<Ns, Tag, Leaf, Att, Val>>, JsonPatchError>
+    where
+        Ns: PartialEq + Clone + Debug + DeserializeOwned,
+        Tag: PartialEq + Debug + DeserializeOwned,
+        Leaf: PartialEq + Clone + Debug + DeserializeOwned,
+        Att: PartialEq + Eq + Hash + Clone + Debug + DeserializeOwned,
+        Val: PartialEq + Clone + Debug + DeserializeOwned,
+    {
+        let fields = Fields::new(self.value(field)?, self.index)?;
+        patch_type_from_json(&fields).map(Box::new)
+    }
+}
+
+fn patch_type_from_json<Ns, Tag, Leaf, Att, Val>(
+    fields: &Fields<'_>,
+) -> Result<OwnedPatchType<Ns, Tag, Leaf, Att, Val>, JsonPatchError>
+where
+    Ns: PartialEq + Clone + Debug + DeserializeOwned,
+    Tag: PartialEq + Debug + DeserializeOwned,
+    Leaf: PartialEq + Clone + Debug + DeserializeOwned,
+    Att: PartialEq + Eq + Hash + Clone + Debug + DeserializeOwned,
+    Val: PartialEq + Clone + Debug + DeserializeOwned,
+{
+    let op: String = fields.get("op")?;
+    let patch_type = match op.as_str() {
+        "insert_before_node" => OwnedPatchType::InsertBeforeNode {
+            nodes: fields.get("nodes")?,
+        },
+        "insert_after_node" => OwnedPatchType::InsertAfterNode {
+            nodes: fields.get("nodes")?,
+        },
+        "append_children" => OwnedPatchType::AppendChildren {
+            children: fields.get("children")?,
+        },
+        "remove_node" => OwnedPatchType::RemoveNode,
+        "move_before_node" => OwnedPatchType::MoveBeforeNode {
+            nodes_path: fields.paths("nodes_path")?,
+        },
+        "move_after_node" => OwnedPatchType::MoveAfterNode {
+            nodes_path: fields.paths("nodes_path")?,
+        },
+        "move_into_node" => OwnedPatchType::MoveIntoNode {
+            nodes_path: fields.paths("nodes_path")?,
+        },
+        "replace_node" => OwnedPatchType::ReplaceNode {
+            replacement: fields.get("replacement")?,
+        },
+        "wrap_node" => OwnedPatchType::WrapNode {
+            wrapper: fields.get("wrapper")?,
+        },
+        "unwrap_node" => OwnedPatchType::UnwrapNode,
+        "change_leaf" => OwnedPatchType::ChangeLeaf {
+            old: fields.get("old")?,
+            new: fields.get("new")?,
+        },
+        "splice_leaf" => OwnedPatchType::SpliceLeaf {
+            old: fields.get("old")?,
+            new: fields.get("new")?,
+            splices: fields.get("splices")?,
+        },
+        "change_tag" => OwnedPatchType::ChangeTag {
+            old: fields.get("old")?,
+            new: fields.get("new")?,
+        },
+        "sync_island" => OwnedPatchType::SyncIsland,
+        "change_leaf_run" => OwnedPatchType::ChangeLeafRun {
+            count: fields.get("count")?,
+            leaves: fields.get("leaves")?,
+        },
+        "add_attributes" => OwnedPatchType::AddAttributes {
+            attrs: fields.get("attrs")?,
+        },
+        "remove_attributes" => OwnedPatchType::RemoveAttributes {
+            attrs: fields.get("attrs")?,
+        },
+        "update_attributes" => OwnedPatchType::UpdateAttributes {
+            add: fields.get("add")?,
+            remove: fields.get("remove")?,
+        },
+        "update_attribute_parts" => OwnedPatchType::UpdateAttributeParts {
+            attr: fields.get("attr")?,
+            parts: fields.get("parts")?,
+        },
+        "for_each_child" => OwnedPatchType::ForEachChild {
+            patch: fields.patch("patch")?,
+        },
+        "broadcast" => OwnedPatchType::Broadcast {
+            selector: fields.get("selector")?,
+            patch: fields.patch("patch")?,
+        },
+        "adopt_node" => OwnedPatchType::AdoptNode {
+            from_tree: fields.get("from_tree")?,
+            from_path: fields.path("from_path")?,
+            placement: fields.get("placement")?,
+        },
+        "set_slot" => OwnedPatchType::SetSlot {
+            slot: fields.get("slot")?,
+            node: fields.get("node")?,
+        },
+        "clear_slot" => OwnedPatchType::ClearSlot {
+            slot: fields.get("slot")?,
+        },
+        _ => {
+            return Err(JsonPatchError::UnknownOp {
+                index: fields.index,
+                op,
+            })
+        }
+    };
+    Ok(patch_type)
+}
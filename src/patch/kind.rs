@@ -0,0 +1,225 @@
+//! the kind of the patches, without their payload
+use crate::{Patch, PatchType};
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::str::FromStr;
+
+/// The kind of a patch, which is the variant of its `PatchType` without the payload
+/// and without the generic types, so metrics, filters and wire formats can refer to
+/// the kind of the patches without matching on `PatchType`.
+///
+/// The kinds are displayed and parsed as the snake case name of their variant,
+/// such as `insert_before_node`, which is stable across the releases.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![], vec![leaf("hello")]);
+/// let new: MyNode = element("main", vec![attr("class", "container")], vec![leaf("hello")]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// assert_eq!(patches[0].kind(), PatchKind::AddAttributes);
+/// assert_eq!(patches[0].kind().to_string(), "add_attributes");
+/// assert_eq!("add_attributes".parse(), Ok(PatchKind::AddAttributes));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PatchKind {
+    /// `PatchType::InsertBeforeNode`
+    InsertBeforeNode,
+    /// `PatchType::InsertAfterNode`
+    InsertAfterNode,
+    /// `PatchType::AppendChildren`
+    AppendChildren,
+    /// `PatchType::RemoveNode`
+    RemoveNode,
+    /// `PatchType::MoveBeforeNode`
+    MoveBeforeNode,
+    /// `PatchType::MoveAfterNode`
+    MoveAfterNode,
+    /// `PatchType::MoveIntoNode`
+    MoveIntoNode,
+    /// `PatchType::ReplaceNode`
+    ReplaceNode,
+    /// `PatchType::WrapNode`
+    WrapNode,
+    /// `PatchType::UnwrapNode`
+    UnwrapNode,
+    /// `PatchType::ChangeLeaf`
+    ChangeLeaf,
+    /// `PatchType::SpliceLeaf`
+    SpliceLeaf,
+    /// `PatchType::ChangeTag`
+    ChangeTag,
+    /// `PatchType::SyncIsland`
+    SyncIsland,
+    /// `PatchType::ChangeLeafRun`
+    ChangeLeafRun,
+    /// `PatchType::AddAttributes`
+    AddAttributes,
+    /// `PatchType::RemoveAttributes`
+    RemoveAttributes,
+    /// `PatchType::UpdateAttributes`
+    UpdateAttributes,
+    /// `PatchType::UpdateAttributeParts`
+    UpdateAttributeParts,
+    /// `PatchType::ForEachChild`
+    ForEachChild,
+    /// `PatchType::Broadcast`
+    Broadcast,
+    /// `PatchType::AdoptNode`
+    AdoptNode,
+    /// `PatchType::SetSlot`
+    SetSlot,
+    /// `PatchType::ClearSlot`
+    ClearSlot,
+}
+
+impl PatchKind {
+    /// all of the kinds, in the order of their declaration
+    pub const ALL: [PatchKind; 24] = [
+        Self::InsertBeforeNode,
+        Self::InsertAfterNode,
+        Self::AppendChildren,
+        Self::RemoveNode,
+        Self::MoveBeforeNode,
+        Self::MoveAfterNode,
+        Self::MoveIntoNode,
+        Self::ReplaceNode,
+        Self::WrapNode,
+        Self::UnwrapNode,
+        Self::ChangeLeaf,
+        Self::SpliceLeaf,
+        Self::ChangeTag,
+        Self::SyncIsland,
+        Self::ChangeLeafRun,
+        Self::AddAttributes,
+        Self::RemoveAttributes,
+        Self::UpdateAttributes,
+        Self::UpdateAttributeParts,
+        Self::ForEachChild,
+        Self::Broadcast,
+        Self::AdoptNode,
+        Self::SetSlot,
+        Self::ClearSlot,
+    ];
+
+    /// the snake case name of the kind, such as `insert_before_node`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InsertBeforeNode => "insert_before_node",
+            Self::InsertAfterNode => "insert_after_node",
+            Self::AppendChildren => "append_children",
+            Self::RemoveNode => "remove_node",
+            Self::MoveBeforeNode => "move_before_node",
+            Self::MoveAfterNode => "move_after_node",
+            Self::MoveIntoNode => "move_into_node",
+            Self::ReplaceNode => "replace_node",
+            Self::WrapNode => "wrap_node",
+            Self::UnwrapNode => "unwrap_node",
+            Self::ChangeLeaf => "change_leaf",
+            Self::SpliceLeaf => "splice_leaf",
+            Self::ChangeTag => "change_tag",
+            Self::SyncIsland => "sync_island",
+            Self::ChangeLeafRun => "change_leaf_run",
+            Self::AddAttributes => "add_attributes",
+            Self::RemoveAttributes => "remove_attributes",
+            Self::UpdateAttributes => "update_attributes",
+            Self::UpdateAttributeParts => "update_attribute_parts",
+            Self::ForEachChild => "for_each_child",
+            Self::Broadcast => "broadcast",
+            Self::AdoptNode => "adopt_node",
+            Self::SetSlot => "set_slot",
+            Self::ClearSlot => "clear_slot",
+        }
+    }
+}
+
+impl fmt::Display for PatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The error of parsing a text which is not the name of a `PatchKind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsePatchKindError;
+
+impl fmt::Display for ParsePatchKindError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown patch kind")
+    }
+}
+
+impl std::error::Error for ParsePatchKindError {}
+
+impl FromStr for PatchKind {
+    type Err = ParsePatchKindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or(ParsePatchKindError)
+    }
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> PatchType<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// return the kind of this patch type
+    pub fn kind(&self) -> PatchKind {
+        match self {
+            Self::InsertBeforeNode { .. } => PatchKind::InsertBeforeNode,
+            Self::InsertAfterNode { .. } => PatchKind::InsertAfterNode,
+            Self::AppendChildren { .. } => PatchKind::AppendChildren,
+            Self::RemoveNode => PatchKind::RemoveNode,
+            Self::MoveBeforeNode { .. } => PatchKind::MoveBeforeNode,
+            Self::MoveAfterNode { .. } => PatchKind::MoveAfterNode,
+            Self::MoveIntoNode { .. } => PatchKind::MoveIntoNode,
+            Self::ReplaceNode { .. } => PatchKind::ReplaceNode,
+            Self::WrapNode { .. } => PatchKind::WrapNode,
+            Self::UnwrapNode => PatchKind::UnwrapNode,
+            Self::ChangeLeaf { .. } => PatchKind::ChangeLeaf,
+            Self::SpliceLeaf { .. } => PatchKind::SpliceLeaf,
+            Self::ChangeTag { .. } => PatchKind::ChangeTag,
+            Self::SyncIsland => PatchKind::SyncIsland,
+            Self::ChangeLeafRun { .. } => PatchKind::ChangeLeafRun,
+            Self::AddAttributes { .. } => PatchKind::AddAttributes,
+            Self::RemoveAttributes { .. } => PatchKind::RemoveAttributes,
+            Self::UpdateAttributes { .. } => PatchKind::UpdateAttributes,
+            Self::UpdateAttributeParts { .. } => {
+                PatchKind::UpdateAttributeParts
+            }
+            Self::ForEachChild { .. } => PatchKind::ForEachChild,
+            Self::Broadcast { .. } => PatchKind::Broadcast,
+            Self::AdoptNode { .. } => PatchKind::AdoptNode,
+            Self::SetSlot { .. } => PatchKind::SetSlot,
+            Self::ClearSlot { .. } => PatchKind::ClearSlot,
+        }
+    }
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> Patch<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// return the kind of this patch
+    pub fn kind(&self) -> PatchKind {
+        self.patch_type.kind()
+    }
+}
@@ -0,0 +1,182 @@
+//! the splices of a leaf, such as the text which is inserted and deleted
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A splice of the `SpliceLeaf` patch, where `old_len` units of the old leaf at `old_start`
+/// are replaced with the `new_len` units of the new leaf at `new_start`.
+///
+/// The units are decided by the `LeafDiff` of the leaf, such as the chars of a text leaf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LeafSplice {
+    /// the offset of the replaced units in the old leaf
+    pub old_start: usize,
+    /// the number of units of the old leaf which are deleted
+    pub old_len: usize,
+    /// the offset of the inserted units in the new leaf
+    pub new_start: usize,
+    /// the number of units of the new leaf which are inserted
+    pub new_len: usize,
+}
+
+impl LeafSplice {
+    /// return the single splice which turns the `old` units into the `new` units,
+    /// keeping their common prefix and suffix. None if the units are equal
+    ///
+    /// # Example
+    /// ```rust
+    /// use mt_dom::patch::LeafSplice;
+    ///
+    /// let old: Vec<char> = "hello world".chars().collect();
+    /// let new: Vec<char> = "hello big world".chars().collect();
+    /// assert_eq!(
+    ///     LeafSplice::between(&old, &new),
+    ///     Some(LeafSplice { old_start: 6, old_len: 0, new_start: 6, new_len: 4 })
+    /// );
+    /// ```
+    pub fn between<T: PartialEq>(old: &[T], new: &[T]) -> Option<Self> {
+        let prefix = old
+            .iter()
+            .zip(new)
+            .take_while(|(old, new)| old == new)
+            .count();
+        if prefix == old.len() && prefix == new.len() {
+            return None;
+        }
+        // the suffix can not overlap with the prefix
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(old, new)| old == new)
+            .count();
+        Some(Self {
+            old_start: prefix,
+            old_len: old.len() - prefix - suffix,
+            new_start: prefix,
+            new_len: new.len() - prefix - suffix,
+        })
+    }
+
+    /// return the fewest units to delete and insert to turn the `old` units into the `new`
+    /// units, using the myers diff, merged into splices in ascending order.
+    /// This falls back to the single splice of `between` when more than `max_edits` units
+    /// are to be deleted and inserted, since the cost grows with the square of the edits.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mt_dom::patch::LeafSplice;
+    ///
+    /// let old: Vec<char> = "a cat sat".chars().collect();
+    /// let new: Vec<char> = "a bat sat!".chars().collect();
+    /// assert_eq!(
+    ///     LeafSplice::minimal(&old, &new, 64),
+    ///     [
+    ///         LeafSplice { old_start: 2, old_len: 1, new_start: 2, new_len: 1 },
+    ///         LeafSplice { old_start: 9, old_len: 0, new_start: 9, new_len: 1 },
+    ///     ]
+    /// );
+    /// ```
+    pub fn minimal<T: PartialEq>(
+        old: &[T],
+        new: &[T],
+        max_edits: usize,
+    ) -> Vec<Self> {
+        let Some(outer) = Self::between(old, new) else {
+            return vec![];
+        };
+        let prefix = outer.old_start;
+        let old_mid = &old[prefix..prefix + outer.old_len];
+        let new_mid = &new[prefix..prefix + outer.new_len];
+        let Some(edits) = myers_edits(old_mid, new_mid, max_edits) else {
+            return vec![outer];
+        };
+        let mut splices: Vec<Self> = vec![];
+        for (old_idx, new_idx, is_delete) in edits {
+            let (old_idx, new_idx) = (prefix + old_idx, prefix + new_idx);
+            let splice = match splices.last_mut() {
+                Some(last)
+                    if last.old_start + last.old_len == old_idx
+                        && last.new_start + last.new_len == new_idx =>
+                {
+                    last
+                }
+                _ => {
+                    splices.push(Self {
+                        old_start: old_idx,
+                        old_len: 0,
+                        new_start: new_idx,
+                        new_len: 0,
+                    });
+                    splices.last_mut().expect("just pushed")
+                }
+            };
+            if is_delete {
+                splice.old_len += 1;
+            } else {
+                splice.new_len += 1;
+            }
+        }
+        splices
+    }
+}
+
+/// return the single unit edits of the myers diff in ascending order, as the position
+/// in the old and new units where the old unit is deleted or the new unit is inserted.
+/// None when there are more than `max_edits` edits
+fn myers_edits<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    max_edits: usize,
+) -> Option<Vec<(usize, usize, bool)>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    // the furthest x on each diagonal k = x - y, offset by `max`
+    let max = n + m;
+    let offset = |k: isize| (k + max) as usize;
+    let mut furthest = vec![0isize; 2 * max as usize + 2];
+    // the furthest x of the diagonals -d..=d after each d, for backtracking
+    let mut trace: Vec<Vec<isize>> = vec![];
+    let max_d = max.min(isize::try_from(max_edits).unwrap_or(isize::MAX));
+    let mut found = None;
+    'search: for d in 0..=max_d {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d
+                || (k != d && furthest[offset(k - 1)] < furthest[offset(k + 1)])
+            {
+                furthest[offset(k + 1)]
+            } else {
+                furthest[offset(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[offset(k)] = x;
+            if x >= n && y >= m {
+                trace.push(furthest[offset(-d)..=offset(d)].to_vec());
+                found = Some(d);
+                break 'search;
+            }
+        }
+        trace.push(furthest[offset(-d)..=offset(d)].to_vec());
+    }
+    let total = found?;
+    let mut edits = vec![];
+    let (mut x, mut y) = (n, m);
+    for d in (1..=total).rev() {
+        let prev = &trace[(d - 1) as usize];
+        let at = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+        let down = k == -d || (k != d && at(k - 1) < at(k + 1));
+        let prev_k = if down { k + 1 } else { k - 1 };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        // the old unit at prev_x is deleted, or the new unit at prev_y is inserted
+        edits.push((prev_x as usize, prev_y as usize, !down));
+        x = prev_x;
+        y = prev_y;
+    }
+    edits.reverse();
+    Some(edits)
+}
@@ -0,0 +1,91 @@
+//! ordering the patches for the application
+use crate::{Patch, PatchType};
+use core::fmt::Debug;
+use core::hash::Hash;
+
+impl<'a, Ns, Tag, Leaf, Att, Val> Patch<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// return the priority of this patch, the patches of lower priority are applied first.
+    ///
+    /// - 0: the changes in place, such as the attributes, the leaves and the tags
+    /// - 1: the insertions, moves and wrapping of the nodes
+    /// - 2: the destructive replacements, such as `ReplaceNode` and `UnwrapNode`
+    /// - 3: the removal of the nodes
+    /// - 4: the `SyncIsland` markers, which are applied after the siblings changed shape
+    pub fn priority(&self) -> usize {
+        match self.patch_type {
+            PatchType::AddAttributes { .. }
+            | PatchType::RemoveAttributes { .. }
+            | PatchType::UpdateAttributes { .. }
+            | PatchType::UpdateAttributeParts { .. }
+            | PatchType::ChangeLeaf { .. }
+            | PatchType::SpliceLeaf { .. }
+            | PatchType::ChangeTag { .. }
+            | PatchType::ForEachChild { .. }
+            | PatchType::Broadcast { .. } => 0,
+            PatchType::InsertBeforeNode { .. }
+            | PatchType::InsertAfterNode { .. }
+            | PatchType::AppendChildren { .. }
+            | PatchType::MoveBeforeNode { .. }
+            | PatchType::MoveAfterNode { .. }
+            | PatchType::MoveIntoNode { .. }
+            | PatchType::AdoptNode { .. }
+            | PatchType::SetSlot { .. }
+            | PatchType::WrapNode { .. }
+            | PatchType::ChangeLeafRun { .. } => 1,
+            PatchType::ReplaceNode { .. } | PatchType::UnwrapNode => 2,
+            PatchType::RemoveNode | PatchType::ClearSlot { .. } => 3,
+            PatchType::SyncIsland => 4,
+        }
+    }
+}
+
+/// Sort the patches by their `Patch::priority`, so the destructive patches such as
+/// `RemoveNode` and `ReplaceNode` are applied after the non-destructive patches.
+///
+/// The sort is stable, the patches of the same priority keep the order of the differ,
+/// which matters for the patches that place nodes relative to their siblings.
+/// The paths of the patches all point to the nodes of the old tree, so the sorted patches
+/// are applied the same as the patches in the order of the differ.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("ul", vec![], vec![leaf("a"), element("li", vec![], vec![])]);
+/// let new: MyNode = element("ul", vec![attr("class", "list")], vec![leaf("a")]);
+///
+/// let mut patches = diff_with_key(&old, &new, &"key");
+/// sort_patches_for_application(&mut patches);
+/// assert_eq!(
+///     patches.iter().map(Patch::kind).collect::<Vec<_>>(),
+///     [PatchKind::AddAttributes, PatchKind::RemoveNode]
+/// );
+///
+/// #[cfg(feature = "apply-patches")]
+/// {
+///     let mut root = old.clone();
+///     apply_patches(&mut root, &patches);
+///     assert_eq!(root, new);
+/// }
+/// ```
+pub fn sort_patches_for_application<Ns, Tag, Leaf, Att, Val>(
+    patches: &mut [Patch<'_, Ns, Tag, Leaf, Att, Val>],
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    patches.sort_by_key(Patch::priority);
+}
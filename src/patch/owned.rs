@@ -0,0 +1,493 @@
+//! patches which owns their nodes, attributes and leaves
+use super::{
+    AttributePart, LeafSplice, Patch, PatchType, Placement, Selector, TreeId,
+    TreePath,
+};
+use crate::{Attribute, Node};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// A `Patch` which owns the nodes, attributes and leaves it references,
+/// so it can outlive the node trees it is created from, such as when the patches
+/// are streamed from a server to a thin client.
+///
+/// Create it with `Patch::into_owned` and borrow it back as a `Patch` with `as_patch`,
+/// so it can be validated and applied the same as the borrowed patches.
+///
+/// With the `serde` feature, a serialized `Patch` is deserialized as an `OwnedPatch`,
+/// since both have the same shape.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedPatch<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the tag of the node at patch_path
+    pub tag: Option<Tag>,
+    /// the path to traverse to get to the target element
+    pub patch_path: TreePath,
+    /// the type of patch we are going to apply
+    pub patch_type: OwnedPatchType<Ns, Tag, Leaf, Att, Val>,
+}
+
+/// The owned counterpart of each `PatchType` variant,
+/// see `PatchType` for the meaning of each variant
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedPatchType<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the owned `PatchType::InsertBeforeNode`
+    InsertBeforeNode {
+        /// the nodes to be inserted
+        nodes: Vec<Node<Ns, Tag, Leaf, Att, Val>>,
+    },
+    /// the owned `PatchType::InsertAfterNode`
+    InsertAfterNode {
+        /// the nodes to be inserted
+        nodes: Vec<Node<Ns, Tag, Leaf, Att, Val>>,
+    },
+    /// the owned `PatchType::AppendChildren`
+    AppendChildren {
+        /// the children to be appended
+        children: Vec<Node<Ns, Tag, Leaf, Att, Val>>,
+    },
+    /// the owned `PatchType::RemoveNode`
+    RemoveNode,
+    /// the owned `PatchType::MoveBeforeNode`
+    MoveBeforeNode {
+        /// the paths of the nodes to be moved
+        nodes_path: Vec<TreePath>,
+    },
+    /// the owned `PatchType::MoveAfterNode`
+    MoveAfterNode {
+        /// the paths of the nodes to be moved
+        nodes_path: Vec<TreePath>,
+    },
+    /// the owned `PatchType::MoveIntoNode`
+    MoveIntoNode {
+        /// the paths of the nodes to be moved
+        nodes_path: Vec<TreePath>,
+    },
+    /// the owned `PatchType::ReplaceNode`
+    ReplaceNode {
+        /// the nodes that will replace the target node
+        replacement: Vec<Node<Ns, Tag, Leaf, Att, Val>>,
+    },
+    /// the owned `PatchType::WrapNode`
+    WrapNode {
+        /// the node which will wrap the target node
+        wrapper: Node<Ns, Tag, Leaf, Att, Val>,
+    },
+    /// the owned `PatchType::UnwrapNode`
+    UnwrapNode,
+    /// the owned `PatchType::ChangeLeaf`
+    ChangeLeaf {
+        /// the old leaf
+        old: Leaf,
+        /// the new leaf which will replace the old leaf
+        new: Leaf,
+    },
+    /// the owned `PatchType::SpliceLeaf`
+    SpliceLeaf {
+        /// the old leaf
+        old: Leaf,
+        /// the new leaf which is the result of the splices
+        new: Leaf,
+        /// the splices of the old leaf
+        splices: Vec<LeafSplice>,
+    },
+    /// the owned `PatchType::ChangeTag`
+    ChangeTag {
+        /// the old tag
+        old: Tag,
+        /// the new tag which will replace the old tag
+        new: Tag,
+    },
+    /// the owned `PatchType::SyncIsland`
+    SyncIsland,
+    /// the owned `PatchType::ChangeLeafRun`
+    ChangeLeafRun {
+        /// the number of adjacent leaves to be changed
+        count: usize,
+        /// the leaves that will replace the run of leaves
+        leaves: Vec<Leaf>,
+    },
+    /// the owned `PatchType::AddAttributes`
+    AddAttributes {
+        /// the attributes to be added
+        attrs: Vec<Attribute<Ns, Att, Val>>,
+    },
+    /// the owned `PatchType::RemoveAttributes`
+    RemoveAttributes {
+        /// the attributes to be removed
+        attrs: Vec<Attribute<Ns, Att, Val>>,
+    },
+    /// the owned `PatchType::UpdateAttributes`
+    UpdateAttributes {
+        /// the attributes to be added
+        add: Vec<Attribute<Ns, Att, Val>>,
+        /// the attributes to be removed
+        remove: Vec<Attribute<Ns, Att, Val>>,
+    },
+    /// the owned `PatchType::UpdateAttributeParts`
+    UpdateAttributeParts {
+        /// the new attribute
+        attr: Attribute<Ns, Att, Val>,
+        /// the changed parts of the attribute
+        parts: Vec<AttributePart<Val>>,
+    },
+    /// the owned `PatchType::ForEachChild`
+    ForEachChild {
+        /// the patch which is applied to each of the children
+        patch: Box<OwnedPatchType<Ns, Tag, Leaf, Att, Val>>,
+    },
+    /// the owned `PatchType::Broadcast`
+    Broadcast {
+        /// selects the nodes which receive the patch
+        selector: OwnedSelector<Tag, Att, Val>,
+        /// the patch which is applied to each of the selected nodes
+        patch: Box<OwnedPatchType<Ns, Tag, Leaf, Att, Val>>,
+    },
+    /// the owned `PatchType::AdoptNode`
+    AdoptNode {
+        /// the auxiliary tree which the node is adopted from
+        from_tree: TreeId,
+        /// the path of the adopted node in the auxiliary tree
+        from_path: TreePath,
+        /// where the adopted node is placed relative to the target node
+        placement: Placement,
+    },
+    /// the owned `PatchType::SetSlot`
+    SetSlot {
+        /// the slot name
+        slot: Att,
+        /// the node which is set into the slot
+        node: Node<Ns, Tag, Leaf, Att, Val>,
+    },
+    /// the owned `PatchType::ClearSlot`
+    ClearSlot {
+        /// the slot name
+        slot: Att,
+    },
+}
+
+/// The owned counterpart of `Selector`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedSelector<Tag, Att, Val> {
+    /// every element
+    AllElements,
+    /// the elements with this tag
+    Tag(Tag),
+    /// the elements which has this attribute
+    HasAttribute(Att),
+    /// the elements which has this attribute with this value
+    AttributeValue(Att, Val),
+}
+
+impl<Tag, Att, Val> OwnedSelector<Tag, Att, Val> {
+    /// borrow this selector as a `Selector`
+    pub fn as_selector(&self) -> Selector<'_, Tag, Att, Val> {
+        match self {
+            Self::AllElements => Selector::AllElements,
+            Self::Tag(tag) => Selector::Tag(tag),
+            Self::HasAttribute(name) => Selector::HasAttribute(name),
+            Self::AttributeValue(name, value) => {
+                Selector::AttributeValue(name, value)
+            }
+        }
+    }
+}
+
+impl<'a, Tag, Att, Val> Selector<'a, Tag, Att, Val>
+where
+    Tag: Clone,
+    Att: Clone,
+    Val: Clone,
+{
+    /// clone the referenced tag, attribute name and value into an `OwnedSelector`
+    pub fn to_owned_selector(&self) -> OwnedSelector<Tag, Att, Val> {
+        match self {
+            Self::AllElements => OwnedSelector::AllElements,
+            Self::Tag(tag) => OwnedSelector::Tag((*tag).clone()),
+            Self::HasAttribute(name) => {
+                OwnedSelector::HasAttribute((*name).clone())
+            }
+            Self::AttributeValue(name, value) => {
+                OwnedSelector::AttributeValue((*name).clone(), (*value).clone())
+            }
+        }
+    }
+}
+
+impl<Ns, Tag, Leaf, Att, Val> OwnedPatch<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// create the patch which replaces the root node with `node`,
+    /// this is the first patch sent to a client which has no tree yet
+    pub fn bootstrap(node: Node<Ns, Tag, Leaf, Att, Val>) -> Self
+    where
+        Tag: Clone,
+    {
+        OwnedPatch {
+            tag: None,
+            patch_path: TreePath::root(),
+            patch_type: OwnedPatchType::ReplaceNode {
+                replacement: vec![node],
+            },
+        }
+    }
+
+    /// return the path to traverse for this patch to get to the target Node
+    pub fn path(&self) -> &TreePath {
+        &self.patch_path
+    }
+
+    /// borrow this patch as a `Patch`
+    pub fn as_patch(&self) -> Patch<'_, Ns, Tag, Leaf, Att, Val> {
+        Patch {
+            tag: self.tag.as_ref(),
+            patch_path: self.patch_path.clone(),
+            patch_type: self.patch_type.as_patch_type(),
+        }
+    }
+}
+
+impl<Ns, Tag, Leaf, Att, Val> OwnedPatchType<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// borrow this patch type as a `PatchType`
+    pub fn as_patch_type(&self) -> PatchType<'_, Ns, Tag, Leaf, Att, Val> {
+        match self {
+            Self::InsertBeforeNode { nodes } => PatchType::InsertBeforeNode {
+                nodes: nodes.iter().collect(),
+            },
+            Self::InsertAfterNode { nodes } => PatchType::InsertAfterNode {
+                nodes: nodes.iter().collect(),
+            },
+            Self::AppendChildren { children } => PatchType::AppendChildren {
+                children: children.iter().collect(),
+            },
+            Self::RemoveNode => PatchType::RemoveNode,
+            Self::MoveBeforeNode { nodes_path } => PatchType::MoveBeforeNode {
+                nodes_path: nodes_path.clone(),
+            },
+            Self::MoveAfterNode { nodes_path } => PatchType::MoveAfterNode {
+                nodes_path: nodes_path.clone(),
+            },
+            Self::MoveIntoNode { nodes_path } => PatchType::MoveIntoNode {
+                nodes_path: nodes_path.clone(),
+            },
+            Self::ReplaceNode { replacement } => PatchType::ReplaceNode {
+                replacement: replacement.iter().collect(),
+            },
+            Self::WrapNode { wrapper } => PatchType::WrapNode { wrapper },
+            Self::UnwrapNode => PatchType::UnwrapNode,
+            Self::ChangeLeaf { old, new } => PatchType::ChangeLeaf { old, new },
+            Self::SpliceLeaf { old, new, splices } => PatchType::SpliceLeaf {
+                old,
+                new,
+                splices: splices.clone(),
+            },
+            Self::ChangeTag { old, new } => PatchType::ChangeTag { old, new },
+            Self::SyncIsland => PatchType::SyncIsland,
+            Self::ChangeLeafRun { count, leaves } => PatchType::ChangeLeafRun {
+                count: *count,
+                leaves: leaves.iter().collect(),
+            },
+            Self::AddAttributes { attrs } => PatchType::AddAttributes {
+                attrs: attrs.iter().collect(),
+            },
+            Self::RemoveAttributes { attrs } => PatchType::RemoveAttributes {
+                attrs: attrs.iter().collect(),
+            },
+            Self::UpdateAttributes { add, remove } => {
+                PatchType::UpdateAttributes {
+                    add: add.iter().collect(),
+                    remove: remove.iter().collect(),
+                }
+            }
+            Self::UpdateAttributeParts { attr, parts } => {
+                PatchType::UpdateAttributeParts {
+                    attr,
+                    parts: parts.clone(),
+                }
+            }
+            Self::ForEachChild { patch } => PatchType::ForEachChild {
+                patch: Box::new(patch.as_patch_type()),
+            },
+            Self::Broadcast { selector, patch } => PatchType::Broadcast {
+                selector: selector.as_selector(),
+                patch: Box::new(patch.as_patch_type()),
+            },
+            Self::AdoptNode {
+                from_tree,
+                from_path,
+                placement,
+            } => PatchType::AdoptNode {
+                from_tree: *from_tree,
+                from_path: from_path.clone(),
+                placement: *placement,
+            },
+            Self::SetSlot { slot, node } => PatchType::SetSlot { slot, node },
+            Self::ClearSlot { slot } => PatchType::ClearSlot { slot },
+        }
+    }
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> Patch<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// clone the nodes, attributes and leaves referenced by this patch into an `OwnedPatch`
+    pub fn into_owned(self) -> OwnedPatch<Ns, Tag, Leaf, Att, Val> {
+        OwnedPatch {
+            tag: self.tag.cloned(),
+            patch_path: self.patch_path,
+            patch_type: self.patch_type.into_owned(),
+        }
+    }
+}
+
+impl<'a, Ns, Tag, Leaf, Att, Val> PatchType<'a, Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// clone the nodes, attributes and leaves referenced by this patch type
+    /// into an `OwnedPatchType`
+    pub fn into_owned(self) -> OwnedPatchType<Ns, Tag, Leaf, Att, Val> {
+        fn cloned<T: Clone>(items: Vec<&T>) -> Vec<T> {
+            items.into_iter().cloned().collect()
+        }
+        match self {
+            Self::InsertBeforeNode { nodes } => {
+                OwnedPatchType::InsertBeforeNode {
+                    nodes: cloned(nodes),
+                }
+            }
+            Self::InsertAfterNode { nodes } => {
+                OwnedPatchType::InsertAfterNode {
+                    nodes: cloned(nodes),
+                }
+            }
+            Self::AppendChildren { children } => {
+                OwnedPatchType::AppendChildren {
+                    children: cloned(children),
+                }
+            }
+            Self::RemoveNode => OwnedPatchType::RemoveNode,
+            Self::MoveBeforeNode { nodes_path } => {
+                OwnedPatchType::MoveBeforeNode { nodes_path }
+            }
+            Self::MoveAfterNode { nodes_path } => {
+                OwnedPatchType::MoveAfterNode { nodes_path }
+            }
+            Self::MoveIntoNode { nodes_path } => {
+                OwnedPatchType::MoveIntoNode { nodes_path }
+            }
+            Self::ReplaceNode { replacement } => OwnedPatchType::ReplaceNode {
+                replacement: cloned(replacement),
+            },
+            Self::WrapNode { wrapper } => OwnedPatchType::WrapNode {
+                wrapper: wrapper.clone(),
+            },
+            Self::UnwrapNode => OwnedPatchType::UnwrapNode,
+            Self::ChangeLeaf { old, new } => OwnedPatchType::ChangeLeaf {
+                old: old.clone(),
+                new: new.clone(),
+            },
+            Self::SpliceLeaf { old, new, splices } => {
+                OwnedPatchType::SpliceLeaf {
+                    old: old.clone(),
+                    new: new.clone(),
+                    splices,
+                }
+            }
+            Self::ChangeTag { old, new } => OwnedPatchType::ChangeTag {
+                old: old.clone(),
+                new: new.clone(),
+            },
+            Self::SyncIsland => OwnedPatchType::SyncIsland,
+            Self::ChangeLeafRun { count, leaves } => {
+                OwnedPatchType::ChangeLeafRun {
+                    count,
+                    leaves: cloned(leaves),
+                }
+            }
+            Self::AddAttributes { attrs } => OwnedPatchType::AddAttributes {
+                attrs: cloned(attrs),
+            },
+            Self::RemoveAttributes { attrs } => {
+                OwnedPatchType::RemoveAttributes {
+                    attrs: cloned(attrs),
+                }
+            }
+            Self::UpdateAttributes { add, remove } => {
+                OwnedPatchType::UpdateAttributes {
+                    add: cloned(add),
+                    remove: cloned(remove),
+                }
+            }
+            Self::UpdateAttributeParts { attr, parts } => {
+                OwnedPatchType::UpdateAttributeParts {
+                    attr: attr.clone(),
+                    parts,
+                }
+            }
+            Self::ForEachChild { patch } => OwnedPatchType::ForEachChild {
+                patch: Box::new(patch.into_owned()),
+            },
+            Self::Broadcast { selector, patch } => OwnedPatchType::Broadcast {
+                selector: selector.to_owned_selector(),
+                patch: Box::new(patch.into_owned()),
+            },
+            Self::AdoptNode {
+                from_tree,
+                from_path,
+                placement,
+            } => OwnedPatchType::AdoptNode {
+                from_tree,
+                from_path,
+                placement,
+            },
+            Self::SetSlot { slot, node } => OwnedPatchType::SetSlot {
+                slot: slot.clone(),
+                node: node.clone(),
+            },
+            Self::ClearSlot { slot } => {
+                OwnedPatchType::ClearSlot { slot: slot.clone() }
+            }
+        }
+    }
+}
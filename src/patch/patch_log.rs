@@ -0,0 +1,134 @@
+//! an append-only log of the patch batches, which can be compacted
+use super::OwnedPatch;
+use crate::{apply_patches, diff_with_key, Node, Patch};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// An append-only log of the owned patch batches across the frames, starting from a base tree,
+/// so the remote clients which join late can catch up by applying the patches of the log
+/// to the base tree instead of receiving the whole current tree.
+///
+/// The log is compacted into the fewest patches which turn the base tree into the current tree,
+/// dropping the patches of the nodes which are later removed and merging the attribute changes.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let base: MyNode = element("ul", vec![], vec![]);
+/// let added: MyNode = element("ul", vec![], vec![element("li", vec![], vec![leaf("new")])]);
+/// let owned = |patches: Vec<Patch<_, _, _, _, _>>| {
+///     patches.into_iter().map(Patch::into_owned).collect::<Vec<_>>()
+/// };
+///
+/// let mut log = PatchLog::new(base.clone(), "key");
+/// log.push(owned(diff_with_key(&base, &added, &"key")));
+/// log.push(owned(diff_with_key(&added, &base, &"key")));
+/// assert_eq!(log.patches().count(), 2);
+///
+/// // the added node is removed again, so there is nothing to catch up
+/// log.compact();
+/// assert_eq!(log.patches().count(), 0);
+/// ```
+#[derive(Debug)]
+pub struct PatchLog<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the tree which the first batch is applied to
+    base: Node<Ns, Tag, Leaf, Att, Val>,
+    /// the base tree with all of the batches applied
+    head: Node<Ns, Tag, Leaf, Att, Val>,
+    /// the patch batches, oldest first
+    batches: Vec<Vec<OwnedPatch<Ns, Tag, Leaf, Att, Val>>>,
+    /// the key attribute used for diffing the compacted patches
+    key: Att,
+}
+
+impl<Ns, Tag, Leaf, Att, Val> PatchLog<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// create an empty log starting from the `base` tree, the keyed nodes are matched
+    /// with the `key` attribute when the log is compacted
+    pub fn new(base: Node<Ns, Tag, Leaf, Att, Val>, key: Att) -> Self {
+        Self {
+            head: base.clone(),
+            base,
+            batches: vec![],
+            key,
+        }
+    }
+
+    /// the tree which the patches of the log are applied to
+    pub fn base(&self) -> &Node<Ns, Tag, Leaf, Att, Val> {
+        &self.base
+    }
+
+    /// the base tree with all of the patches of the log applied
+    pub fn head(&self) -> &Node<Ns, Tag, Leaf, Att, Val> {
+        &self.head
+    }
+
+    /// the patch batches of the log, oldest first.
+    /// Each batch is applied to the tree with the previous batches applied
+    pub fn batches(&self) -> &[Vec<OwnedPatch<Ns, Tag, Leaf, Att, Val>>] {
+        &self.batches
+    }
+
+    /// all of the patches of the log in order
+    pub fn patches(
+        &self,
+    ) -> impl Iterator<Item = &OwnedPatch<Ns, Tag, Leaf, Att, Val>> + '_ {
+        self.batches.iter().flatten()
+    }
+
+    /// append the `batch`, whose paths point to the head tree, and apply it to the head tree.
+    /// The empty batches are not appended.
+    ///
+    /// # Panics
+    /// Panics if the path of a patch can not be resolved in the head tree
+    pub fn push(&mut self, batch: Vec<OwnedPatch<Ns, Tag, Leaf, Att, Val>>) {
+        if batch.is_empty() {
+            return;
+        }
+        let patches: Vec<Patch<Ns, Tag, Leaf, Att, Val>> =
+            batch.iter().map(OwnedPatch::as_patch).collect();
+        apply_patches(&mut self.head, &patches);
+        self.batches.push(batch);
+    }
+
+    /// replace the batches with a single batch of the fewest patches which turn
+    /// the base tree into the head tree, by diffing them
+    pub fn compact(&mut self) {
+        let compacted: Vec<OwnedPatch<Ns, Tag, Leaf, Att, Val>> =
+            diff_with_key(&self.base, &self.head, &self.key)
+                .into_iter()
+                .map(Patch::into_owned)
+                .collect();
+        self.batches.clear();
+        if !compacted.is_empty() {
+            self.batches.push(compacted);
+        }
+    }
+
+    /// make the head tree the new base tree, discarding all of the batches.
+    /// This is for when every client has caught up
+    pub fn checkpoint(&mut self) {
+        self.base = self.head.clone();
+        self.batches.clear();
+    }
+}
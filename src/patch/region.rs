@@ -0,0 +1,124 @@
+//! the regions of the tree affected by the patches
+use crate::{Patch, PatchType, TreePath};
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// Return the minimal set of subtree roots, in the old tree, which covers all of the
+/// changes of the patches, sorted by path. Renderers can invalidate the layout or paint of
+/// just these regions instead of the whole window.
+///
+/// Patches which only change the target node, such as attribute changes, affects the
+/// subtree of the target node, while patches which change the siblings of the target node,
+/// such as inserting, removing or moving nodes, affects the subtree of the parent.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![], vec![
+///     element("nav", vec![attr("class", "a")], vec![element("b", vec![], vec![])]),
+///     element("ul", vec![], vec![element("li", vec![], vec![])]),
+/// ]);
+/// let new: MyNode = element("main", vec![], vec![
+///     element("nav", vec![attr("class", "b")], vec![element("i", vec![], vec![])]),
+///     element("ul", vec![], vec![]),
+/// ]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// assert_eq!(
+///     affected_region(&patches),
+///     vec![TreePath::new([0]), TreePath::new([1])]
+/// );
+/// ```
+pub fn affected_region<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+) -> Vec<TreePath>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut paths: Vec<TreePath> = patches
+        .iter()
+        // the `SyncIsland` markers does not change anything
+        .filter(|patch| patch.patch_type != PatchType::SyncIsland)
+        .flat_map(|patch| {
+            let target = if patch.changes_siblings() {
+                patch.path().backtrack()
+            } else {
+                patch.path().clone()
+            };
+            // the moved nodes are removed from their parents
+            core::iter::once(target)
+                .chain(patch.node_paths().iter().map(TreePath::backtrack))
+        })
+        .collect();
+    minimize(&mut paths);
+    paths
+}
+
+/// Coalesce the `affected_region` of the patches up to `depth` levels from the root,
+/// the regions deeper than `depth` are replaced by their ancestor at `depth`.
+/// This gives coarse invalidation sets for layout engines which can only
+/// invalidate at the granularity of containers.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let patches: Vec<Patch<&str, &str, &str, &str, &str>> = vec![
+///     Patch::change_leaf(TreePath::new([0, 1, 0]), &"a", &"b"),
+///     Patch::change_leaf(TreePath::new([0, 2, 5]), &"a", &"b"),
+///     Patch::change_leaf(TreePath::new([1]), &"a", &"b"),
+/// ];
+/// assert_eq!(
+///     aggregate_to_depth(&patches, 1),
+///     vec![TreePath::new([0]), TreePath::new([1])]
+/// );
+/// ```
+pub fn aggregate_to_depth<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+    depth: usize,
+) -> Vec<TreePath>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let mut paths = affected_region(patches);
+    for path in paths.iter_mut() {
+        path.path.truncate(depth);
+    }
+    minimize(&mut paths);
+    paths
+}
+
+/// sort the paths and remove the duplicates and the paths
+/// which are in the subtree of the other paths
+fn minimize(paths: &mut Vec<TreePath>) {
+    paths.sort();
+    paths.dedup();
+    let mut roots: Vec<TreePath> = Vec::with_capacity(paths.len());
+    for path in paths.drain(..) {
+        // the sorted order puts a path right after its ancestors
+        // or after a descendant of its ancestors
+        let covered = roots
+            .last()
+            .is_some_and(|root| path.path.starts_with(&root.path));
+        if !covered {
+            roots.push(path);
+        }
+    }
+    *paths = roots;
+}
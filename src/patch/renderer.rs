@@ -0,0 +1,384 @@
+//! apply the patches to any backend which implements `Renderer`
+use super::{
+    AttributePart, InvalidPatch, LeafSplice, Placement, Selector, TreeId,
+};
+use crate::{Attribute, Node, Patch, PatchType, TreePath};
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// A backend which the patches are applied to, such as the html DOM, a terminal UI
+/// or a native widget toolkit, so each backend only implements the operations
+/// on its own nodes instead of re-implementing the interpretation of the patches.
+///
+/// The nodes are referred to by their `Handle`. The paths of the patches all point to the
+/// nodes of the old tree, so `apply` resolves every path into a handle before any of
+/// the patches are applied, and the handles must stay valid while the patches are applied.
+/// The handle of a node which is replaced or unwrapped must then refer to the nodes which
+/// took its place, since the later patches can still be placed before or after it.
+///
+/// The operations have the same meaning as the `PatchType` variants of the same name,
+/// see `PatchType` for the details, such as how many siblings a `WrapNode` wraps.
+pub trait Renderer<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the reference to a node of the backend
+    type Handle: Clone;
+
+    /// return the handle of the node at `path` before any of the patches are applied,
+    /// None if the path does not resolve to a node
+    fn resolve(&mut self, path: &TreePath) -> Option<Self::Handle>;
+
+    /// return the handles of the children of the node, used by `ForEachChild`
+    fn children(&mut self, target: &Self::Handle) -> Vec<Self::Handle>;
+
+    /// return the handles of the nodes selected by the `selector` in the subtree
+    /// of the node, including the node itself, used by `Broadcast`
+    fn select(
+        &mut self,
+        target: &Self::Handle,
+        selector: &Selector<'_, Tag, Att, Val>,
+    ) -> Vec<Self::Handle>;
+
+    /// create the nodes and insert them before the target node
+    fn insert_before(
+        &mut self,
+        target: &Self::Handle,
+        nodes: &[&Node<Ns, Tag, Leaf, Att, Val>],
+    );
+
+    /// create the nodes and insert them after the target node
+    fn insert_after(
+        &mut self,
+        target: &Self::Handle,
+        nodes: &[&Node<Ns, Tag, Leaf, Att, Val>],
+    );
+
+    /// create the nodes and append them to the children of the target node
+    fn append_children(
+        &mut self,
+        target: &Self::Handle,
+        children: &[&Node<Ns, Tag, Leaf, Att, Val>],
+    );
+
+    /// remove the target node
+    fn remove(&mut self, target: &Self::Handle);
+
+    /// move the existing `nodes` before the target node
+    fn move_before(&mut self, target: &Self::Handle, nodes: &[Self::Handle]);
+
+    /// move the existing `nodes` after the target node
+    fn move_after(&mut self, target: &Self::Handle, nodes: &[Self::Handle]);
+
+    /// move the existing `nodes` to the end of the children of the target node
+    fn move_into(&mut self, target: &Self::Handle, nodes: &[Self::Handle]);
+
+    /// replace the target node with the created nodes
+    fn replace(
+        &mut self,
+        target: &Self::Handle,
+        replacement: &[&Node<Ns, Tag, Leaf, Att, Val>],
+    );
+
+    /// wrap the target node, and as many of its siblings as the children of the `wrapper`,
+    /// with the `wrapper` element, which is created without its children
+    fn wrap(
+        &mut self,
+        target: &Self::Handle,
+        wrapper: &Node<Ns, Tag, Leaf, Att, Val>,
+    );
+
+    /// remove the target node but put its children in place of it
+    fn unwrap(&mut self, target: &Self::Handle);
+
+    /// change the leaf of the target leaf node
+    fn change_leaf(&mut self, target: &Self::Handle, old: &Leaf, new: &Leaf);
+
+    /// splice the leaf of the target leaf node into the `new` leaf, such as inserting
+    /// and deleting text. The leaf is changed as a whole with `change_leaf` by default
+    fn splice_leaf(
+        &mut self,
+        target: &Self::Handle,
+        old: &Leaf,
+        new: &Leaf,
+        _splices: &[LeafSplice],
+    ) {
+        self.change_leaf(target, old, new)
+    }
+
+    /// change the tag of the target element, keeping its attributes and children
+    fn change_tag(&mut self, target: &Self::Handle, old: &Tag, new: &Tag);
+
+    /// change the run of `count` adjacent leaves starting at the target node into `leaves`
+    fn change_leaf_run(
+        &mut self,
+        target: &Self::Handle,
+        count: usize,
+        leaves: &[&Leaf],
+    );
+
+    /// set the attributes of the target element, replacing the attributes of the same name
+    fn add_attributes(
+        &mut self,
+        target: &Self::Handle,
+        attrs: &[&Attribute<Ns, Att, Val>],
+    );
+
+    /// remove the attributes from the target element
+    fn remove_attributes(
+        &mut self,
+        target: &Self::Handle,
+        attrs: &[&Attribute<Ns, Att, Val>],
+    );
+
+    /// add and remove the attributes of the target element in one update,
+    /// the attributes are removed with `remove_attributes` then added with
+    /// `add_attributes` by default
+    fn update_attributes(
+        &mut self,
+        target: &Self::Handle,
+        add: &[&Attribute<Ns, Att, Val>],
+        remove: &[&Attribute<Ns, Att, Val>],
+    ) {
+        self.remove_attributes(target, remove);
+        self.add_attributes(target, add);
+    }
+
+    /// update the parts of the attribute of the target element, such as the changed
+    /// properties of a style. The new `attr` is added with `add_attributes` by default
+    fn update_attribute_parts(
+        &mut self,
+        target: &Self::Handle,
+        attr: &Attribute<Ns, Att, Val>,
+        _parts: &[AttributePart<Val>],
+    ) {
+        self.add_attributes(target, &[attr]);
+    }
+
+    /// set the created `node` into the `slot` of the target element, replacing the child
+    /// in the slot and moving the slot after the other slots. The renderers which do not
+    /// use the slotted elements never receive it, so this does nothing by default
+    fn set_slot(
+        &mut self,
+        _target: &Self::Handle,
+        _slot: &Att,
+        _node: &Node<Ns, Tag, Leaf, Att, Val>,
+    ) {
+    }
+
+    /// remove the child in the `slot` of the target element,
+    /// this does nothing by default the same as `set_slot`
+    fn clear_slot(&mut self, _target: &Self::Handle, _slot: &Att) {}
+
+    /// re-anchor the ignored subtree at the target node after its siblings changed shape,
+    /// this does nothing by default
+    fn sync_island(&mut self, _target: &Self::Handle) {}
+
+    /// return the handle of the node at `path` of the auxiliary tree `tree`,
+    /// used by `AdoptNode`. None by default, so the patches which adopt nodes
+    /// are rejected by the renderers which do not know of any auxiliary tree
+    fn resolve_adopted(
+        &mut self,
+        _tree: TreeId,
+        _path: &TreePath,
+    ) -> Option<Self::Handle> {
+        None
+    }
+
+    /// move the `adopted` node, resolved with `resolve_adopted`, out of its auxiliary tree
+    /// and place it relative to the target node. This is only called with the handles
+    /// returned by `resolve_adopted`, so it does nothing by default
+    fn adopt(
+        &mut self,
+        _target: &Self::Handle,
+        _adopted: &Self::Handle,
+        _placement: Placement,
+    ) {
+    }
+}
+
+/// Apply the patches to the `renderer`.
+///
+/// Every path of the patches is resolved into a handle first, so the malformed patches
+/// are rejected without applying any of the patches.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// /// a backend which prints the operations, using the paths as the handles
+/// #[derive(Default)]
+/// struct Printer {
+///     lines: Vec<String>,
+/// }
+///
+/// impl Renderer<&'static str, &'static str, &'static str, &'static str, &'static str> for Printer {
+///     type Handle = TreePath;
+///     fn resolve(&mut self, path: &TreePath) -> Option<TreePath> { Some(path.clone()) }
+///     fn children(&mut self, _: &TreePath) -> Vec<TreePath> { vec![] }
+///     fn select(&mut self, _: &TreePath, _: &Selector<&'static str, &'static str, &'static str>) -> Vec<TreePath> { vec![] }
+///     fn insert_before(&mut self, _: &TreePath, _: &[&MyNode]) {}
+///     fn insert_after(&mut self, _: &TreePath, _: &[&MyNode]) {}
+///     fn append_children(&mut self, _: &TreePath, _: &[&MyNode]) {}
+///     fn remove(&mut self, target: &TreePath) { self.lines.push(format!("remove {}", target.display())) }
+///     fn move_before(&mut self, _: &TreePath, _: &[TreePath]) {}
+///     fn move_after(&mut self, _: &TreePath, _: &[TreePath]) {}
+///     fn move_into(&mut self, _: &TreePath, _: &[TreePath]) {}
+///     fn replace(&mut self, _: &TreePath, _: &[&MyNode]) {}
+///     fn wrap(&mut self, _: &TreePath, _: &MyNode) {}
+///     fn unwrap(&mut self, _: &TreePath) {}
+///     fn change_leaf(&mut self, _: &TreePath, _: &&'static str, _: &&'static str) {}
+///     fn change_tag(&mut self, _: &TreePath, _: &&'static str, _: &&'static str) {}
+///     fn change_leaf_run(&mut self, _: &TreePath, _: usize, _: &[&&'static str]) {}
+///     fn add_attributes(&mut self, _: &TreePath, _: &[&Attribute<&'static str, &'static str, &'static str>]) {}
+///     fn remove_attributes(&mut self, _: &TreePath, _: &[&Attribute<&'static str, &'static str, &'static str>]) {}
+/// }
+///
+/// let old: MyNode = element("ul", vec![], vec![element("li", vec![], vec![]), leaf("end")]);
+/// let new: MyNode = element("ul", vec![], vec![element("li", vec![], vec![])]);
+///
+/// let mut printer = Printer::default();
+/// apply(&diff_with_key(&old, &new, &"key"), &mut printer).unwrap();
+/// assert_eq!(printer.lines, ["remove [1]"]);
+/// ```
+pub fn apply<Ns, Tag, Leaf, Att, Val, R>(
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+    renderer: &mut R,
+) -> Result<(), InvalidPatch>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+    R: Renderer<Ns, Tag, Leaf, Att, Val>,
+{
+    let resolve = |renderer: &mut R, patch_index: usize, path: &TreePath| {
+        renderer
+            .resolve(path)
+            .ok_or_else(|| InvalidPatch::UnresolvedPath {
+                patch_index,
+                path: path.clone(),
+            })
+    };
+    let targets = patches
+        .iter()
+        .enumerate()
+        .map(|(patch_index, patch)| {
+            let target = resolve(renderer, patch_index, patch.path())?;
+            let mut nodes = patch
+                .node_paths()
+                .iter()
+                .map(|path| resolve(renderer, patch_index, path))
+                .collect::<Result<Vec<_>, _>>()?;
+            if let PatchType::AdoptNode {
+                from_tree,
+                from_path,
+                ..
+            } = &patch.patch_type
+            {
+                let adopted =
+                    renderer
+                        .resolve_adopted(*from_tree, from_path)
+                        .ok_or_else(|| InvalidPatch::UnresolvedAdoption {
+                            patch_index,
+                            from_tree: *from_tree,
+                            from_path: from_path.clone(),
+                        })?;
+                nodes.push(adopted);
+            }
+            Ok((target, nodes))
+        })
+        .collect::<Result<Vec<_>, InvalidPatch>>()?;
+
+    for (patch, (target, nodes)) in patches.iter().zip(targets) {
+        trace!("rendering patch at {}", patch.path().display());
+        apply_patch_type(renderer, &patch.patch_type, &target, &nodes);
+    }
+    Ok(())
+}
+
+fn apply_patch_type<Ns, Tag, Leaf, Att, Val, R>(
+    renderer: &mut R,
+    patch_type: &PatchType<'_, Ns, Tag, Leaf, Att, Val>,
+    target: &R::Handle,
+    nodes: &[R::Handle],
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+    R: Renderer<Ns, Tag, Leaf, Att, Val>,
+{
+    match patch_type {
+        PatchType::InsertBeforeNode { nodes: new_nodes } => {
+            renderer.insert_before(target, new_nodes)
+        }
+        PatchType::InsertAfterNode { nodes: new_nodes } => {
+            renderer.insert_after(target, new_nodes)
+        }
+        PatchType::AppendChildren { children } => {
+            renderer.append_children(target, children)
+        }
+        PatchType::RemoveNode => renderer.remove(target),
+        PatchType::MoveBeforeNode { .. } => renderer.move_before(target, nodes),
+        PatchType::MoveAfterNode { .. } => renderer.move_after(target, nodes),
+        PatchType::MoveIntoNode { .. } => renderer.move_into(target, nodes),
+        PatchType::ReplaceNode { replacement } => {
+            renderer.replace(target, replacement)
+        }
+        PatchType::WrapNode { wrapper } => renderer.wrap(target, wrapper),
+        PatchType::UnwrapNode => renderer.unwrap(target),
+        PatchType::ChangeLeaf { old, new } => {
+            renderer.change_leaf(target, old, new)
+        }
+        PatchType::SpliceLeaf { old, new, splices } => {
+            renderer.splice_leaf(target, old, new, splices)
+        }
+        PatchType::ChangeTag { old, new } => {
+            renderer.change_tag(target, old, new)
+        }
+        PatchType::SyncIsland => renderer.sync_island(target),
+        PatchType::ChangeLeafRun { count, leaves } => {
+            renderer.change_leaf_run(target, *count, leaves)
+        }
+        PatchType::AddAttributes { attrs } => {
+            renderer.add_attributes(target, attrs)
+        }
+        PatchType::RemoveAttributes { attrs } => {
+            renderer.remove_attributes(target, attrs)
+        }
+        PatchType::UpdateAttributes { add, remove } => {
+            renderer.update_attributes(target, add, remove)
+        }
+        PatchType::UpdateAttributeParts { attr, parts } => {
+            renderer.update_attribute_parts(target, attr, parts)
+        }
+        PatchType::ForEachChild { patch } => {
+            for child in renderer.children(target) {
+                apply_patch_type(renderer, patch, &child, &[]);
+            }
+        }
+        PatchType::Broadcast { selector, patch } => {
+            for selected in renderer.select(target, selector) {
+                apply_patch_type(renderer, patch, &selected, &[]);
+            }
+        }
+        PatchType::AdoptNode { placement, .. } => {
+            renderer.adopt(target, &nodes[0], *placement)
+        }
+        PatchType::SetSlot { slot, node } => {
+            renderer.set_slot(target, slot, node)
+        }
+        PatchType::ClearSlot { slot } => renderer.clear_slot(target, slot),
+    }
+}
@@ -0,0 +1,197 @@
+//! client-side prediction of the patches with rollback
+use super::{validate, OwnedPatch};
+use crate::{apply_patches, Node, Patch};
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// The tree of a client which applies its locally predicted patches right away,
+/// such as the optimistic update of a click, and reconciles them later with the
+/// authoritative patches of the server.
+///
+/// Two trees are kept: the confirmed tree which only has the authoritative patches applied,
+/// and the predicted tree which is the confirmed tree with the pending predictions applied.
+/// Rolling back is done by rebuilding the predicted tree from the confirmed tree,
+/// so the patches need not be invertible.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("button", vec![], vec![leaf("like")]);
+/// let liked: MyNode = element("button", vec![attr("class", "liked")], vec![leaf("like")]);
+/// let owned = |patches: Vec<Patch<_, _, _, _, _>>| {
+///     patches.into_iter().map(Patch::into_owned).collect::<Vec<_>>()
+/// };
+///
+/// let mut state = SpeculativeState::new(old.clone());
+/// let generation = state.predict(owned(diff_with_key(&old, &liked, &"key")));
+/// assert_eq!(state.predicted(), &liked);
+///
+/// // the server rejected the like, and sent no patches for it
+/// let reconciled = state.reconcile(Some(generation), &[]);
+/// assert_eq!(reconciled.rolled_back, vec![generation]);
+/// assert_eq!(state.predicted(), &old);
+/// ```
+#[derive(Debug)]
+pub struct SpeculativeState<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// the tree with only the authoritative patches applied
+    confirmed: Node<Ns, Tag, Leaf, Att, Val>,
+    /// the confirmed tree with the pending predictions applied
+    predicted: Node<Ns, Tag, Leaf, Att, Val>,
+    /// the predictions which are not yet acknowledged, oldest first
+    pending: Vec<(u64, Vec<OwnedPatch<Ns, Tag, Leaf, Att, Val>>)>,
+    /// the generation of the next prediction
+    next_generation: u64,
+}
+
+/// The result of reconciling the authoritative patches with the pending predictions
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Reconciliation {
+    /// the generations of the predictions which were rolled back, either because
+    /// they were acknowledged with different patches or because they no longer
+    /// apply to the updated tree
+    pub rolled_back: Vec<u64>,
+    /// true if the predicted tree was rebuilt from the confirmed tree
+    pub rebuilt: bool,
+}
+
+impl<Ns, Tag, Leaf, Att, Val> SpeculativeState<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    /// create the state from the authoritative tree, with no predictions
+    pub fn new(confirmed: Node<Ns, Tag, Leaf, Att, Val>) -> Self {
+        Self {
+            predicted: confirmed.clone(),
+            confirmed,
+            pending: Vec::new(),
+            next_generation: 0,
+        }
+    }
+
+    /// the tree with only the authoritative patches applied
+    pub fn confirmed(&self) -> &Node<Ns, Tag, Leaf, Att, Val> {
+        &self.confirmed
+    }
+
+    /// the tree with the pending predictions applied, this is what the user sees
+    pub fn predicted(&self) -> &Node<Ns, Tag, Leaf, Att, Val> {
+        &self.predicted
+    }
+
+    /// the generations of the predictions which are not yet acknowledged, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = u64> + '_ {
+        self.pending.iter().map(|(generation, _)| *generation)
+    }
+
+    /// apply the predicted patches, whose paths point to the current predicted tree,
+    /// and return the generation which the server acknowledges in `reconcile`
+    ///
+    /// # Panics
+    /// Panics if the path of a patch can not be resolved in the predicted tree
+    pub fn predict(
+        &mut self,
+        patches: Vec<OwnedPatch<Ns, Tag, Leaf, Att, Val>>,
+    ) -> u64 {
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        apply_owned(&mut self.predicted, &patches);
+        self.pending.push((generation, patches));
+        generation
+    }
+
+    /// Apply the authoritative patches, whose paths point to the confirmed tree,
+    /// and acknowledge the predictions up to and including the `acknowledged` generation.
+    /// Use `None` for the patches which the server sent on its own.
+    ///
+    /// When the acknowledged prediction is the oldest pending one and its patches
+    /// are the same as the authoritative patches, the predicted tree is kept as is.
+    /// Otherwise the predicted tree is rebuilt from the confirmed tree by replaying
+    /// the remaining predictions, rolling back the ones which no longer apply.
+    ///
+    /// # Panics
+    /// Panics if the path of an authoritative patch can not be resolved in the confirmed tree
+    pub fn reconcile(
+        &mut self,
+        acknowledged: Option<u64>,
+        authoritative: &[OwnedPatch<Ns, Tag, Leaf, Att, Val>],
+    ) -> Reconciliation {
+        apply_owned(&mut self.confirmed, authoritative);
+
+        let split = acknowledged.map_or(0, |acknowledged| {
+            self.pending
+                .iter()
+                .take_while(|(generation, _)| *generation <= acknowledged)
+                .count()
+        });
+        let acknowledged: Vec<_> = self.pending.drain(..split).collect();
+        let mut reconciliation = Reconciliation::default();
+        if let [(_, predicted)] = acknowledged.as_slice() {
+            if predicted.as_slice() == authoritative {
+                return reconciliation;
+            }
+        }
+        if acknowledged.is_empty() && authoritative.is_empty() {
+            return reconciliation;
+        }
+        reconciliation.rolled_back = acknowledged
+            .iter()
+            .map(|(generation, _)| *generation)
+            .collect();
+
+        self.predicted = self.confirmed.clone();
+        let mut kept = Vec::with_capacity(self.pending.len());
+        for (generation, patches) in self.pending.drain(..) {
+            let borrowed: Vec<Patch<Ns, Tag, Leaf, Att, Val>> =
+                patches.iter().map(OwnedPatch::as_patch).collect();
+            if validate(&self.predicted, &borrowed).is_ok() {
+                apply_patches(&mut self.predicted, &borrowed);
+                kept.push((generation, patches));
+            } else {
+                reconciliation.rolled_back.push(generation);
+            }
+        }
+        self.pending = kept;
+        reconciliation.rebuilt = true;
+        reconciliation
+    }
+
+    /// discard all of the pending predictions, the predicted tree becomes the confirmed tree.
+    /// Returns the generations of the discarded predictions
+    pub fn rollback(&mut self) -> Vec<u64> {
+        self.predicted = self.confirmed.clone();
+        self.pending
+            .drain(..)
+            .map(|(generation, _)| generation)
+            .collect()
+    }
+}
+
+fn apply_owned<Ns, Tag, Leaf, Att, Val>(
+    root: &mut Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[OwnedPatch<Ns, Tag, Leaf, Att, Val>],
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let patches: Vec<_> = patches.iter().map(OwnedPatch::as_patch).collect();
+    apply_patches(root, &patches);
+}
@@ -0,0 +1,164 @@
+//! a single line summary of a batch of patches
+use crate::{Patch, PatchType};
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// The number of patches of each kind in a batch of patches,
+/// and the depth of the deepest patch.
+///
+/// This is intended for frame by frame logging, where dumping the full `Debug` output
+/// of the patches is too noisy.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![attr("class", "a")], vec![leaf("hello")]);
+/// let new: MyNode = element("main", vec![attr("class", "b")], vec![leaf("world")]);
+///
+/// let patches = diff_with_key(&old, &new, &"key");
+/// let summary = PatchBatchSummary::from(patches.as_slice());
+/// assert_eq!(summary.to_string(), "1 attr, 1 replace @ depth≤1");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PatchBatchSummary {
+    /// the number of `AddAttributes`, `RemoveAttributes`, `UpdateAttributes`
+    /// and `UpdateAttributeParts` patches
+    pub attr: usize,
+    /// the number of `InsertBeforeNode` and `InsertAfterNode` patches
+    pub insert: usize,
+    /// the number of `AppendChildren` patches
+    pub append: usize,
+    /// the number of `MoveBeforeNode`, `MoveAfterNode`, `MoveIntoNode` and `AdoptNode` patches
+    pub moves: usize,
+    /// the number of `RemoveNode` patches
+    pub remove: usize,
+    /// the number of `ReplaceNode` patches
+    pub replace: usize,
+    /// the number of `WrapNode` and `UnwrapNode` patches
+    pub wrap: usize,
+    /// the number of `ChangeLeaf`, `SpliceLeaf` and `ChangeLeafRun` patches
+    pub leaf: usize,
+    /// the number of `ChangeTag` patches
+    pub tag: usize,
+    /// the number of `SyncIsland` patches
+    pub island: usize,
+    /// the number of `SetSlot` and `ClearSlot` patches
+    pub slot: usize,
+    /// the length of the longest patch path
+    pub max_depth: usize,
+}
+
+impl PatchBatchSummary {
+    /// the total number of patches
+    pub fn total(&self) -> usize {
+        self.attr
+            + self.insert
+            + self.append
+            + self.moves
+            + self.remove
+            + self.replace
+            + self.wrap
+            + self.leaf
+            + self.tag
+            + self.island
+            + self.slot
+    }
+}
+
+impl<'p, 'a, Ns, Tag, Leaf, Att, Val>
+    From<&'p [Patch<'a, Ns, Tag, Leaf, Att, Val>]> for PatchBatchSummary
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    fn from(patches: &'p [Patch<'a, Ns, Tag, Leaf, Att, Val>]) -> Self {
+        let mut summary = Self::default();
+        for patch in patches {
+            *summary.count_of(&patch.patch_type) += 1;
+            summary.max_depth = summary.max_depth.max(patch.path().path.len());
+        }
+        summary
+    }
+}
+
+impl PatchBatchSummary {
+    /// the count of the kind of the patch, a `ForEachChild` is counted as its inner patch
+    fn count_of<Ns, Tag, Leaf, Att, Val>(
+        &mut self,
+        patch_type: &PatchType<Ns, Tag, Leaf, Att, Val>,
+    ) -> &mut usize
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        match patch_type {
+            PatchType::AddAttributes { .. }
+            | PatchType::RemoveAttributes { .. }
+            | PatchType::UpdateAttributes { .. }
+            | PatchType::UpdateAttributeParts { .. } => &mut self.attr,
+            PatchType::InsertBeforeNode { .. }
+            | PatchType::InsertAfterNode { .. } => &mut self.insert,
+            PatchType::AppendChildren { .. } => &mut self.append,
+            PatchType::MoveBeforeNode { .. }
+            | PatchType::MoveAfterNode { .. }
+            | PatchType::MoveIntoNode { .. }
+            | PatchType::AdoptNode { .. } => &mut self.moves,
+            PatchType::RemoveNode => &mut self.remove,
+            PatchType::ReplaceNode { .. } => &mut self.replace,
+            PatchType::WrapNode { .. } | PatchType::UnwrapNode => {
+                &mut self.wrap
+            }
+            PatchType::ChangeLeaf { .. }
+            | PatchType::SpliceLeaf { .. }
+            | PatchType::ChangeLeafRun { .. } => &mut self.leaf,
+            PatchType::ChangeTag { .. } => &mut self.tag,
+            PatchType::SyncIsland => &mut self.island,
+            PatchType::SetSlot { .. } | PatchType::ClearSlot { .. } => {
+                &mut self.slot
+            }
+            PatchType::ForEachChild { patch }
+            | PatchType::Broadcast { patch, .. } => self.count_of(patch),
+        }
+    }
+}
+
+impl fmt::Display for PatchBatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.total() == 0 {
+            return write!(f, "no patches");
+        }
+        let counts = [
+            (self.attr, "attr"),
+            (self.insert, "insert"),
+            (self.append, "append"),
+            (self.moves, "move"),
+            (self.remove, "remove"),
+            (self.replace, "replace"),
+            (self.wrap, "wrap"),
+            (self.leaf, "leaf"),
+            (self.tag, "tag"),
+            (self.island, "island"),
+            (self.slot, "slot"),
+        ];
+        let mut first = true;
+        for (count, name) in counts.iter().filter(|(count, _)| *count > 0) {
+            if !first {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {}", count, name)?;
+            first = false;
+        }
+        write!(f, " @ depth≤{}", self.max_depth)
+    }
+}
@@ -1,9 +1,26 @@
 use crate::Node;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::fmt;
 use core::fmt::Debug;
 use core::hash::Hash;
 
+/// The type of the child index stored at each level of a `TreePath`.
+///
+/// This is `u32` when the `compact-paths` feature is enabled, which halves the size
+/// of the paths on 64-bit targets, otherwise this is `usize`. It is only used for the
+/// storage, the child indices are always `usize` in the API of `TreePath`.
+#[cfg(feature = "compact-paths")]
+pub(crate) type PathIndex = u32;
+
+/// The type of the child index stored at each level of a `TreePath`.
+///
+/// This is `u32` when the `compact-paths` feature is enabled, which halves the size
+/// of the paths on 64-bit targets, otherwise this is `usize`. It is only used for the
+/// storage, the child indices are always `usize` in the API of `TreePath`.
+#[cfg(not(feature = "compact-paths"))]
+pub(crate) type PathIndex = usize;
+
 /// Describe the path traversal of a Node starting from the root node
 ///
 /// The figure below shows `node_idx` in a depth first traversal.
@@ -60,7 +77,11 @@ use core::hash::Hash;
 ///    6 = [1,1]
 ///    7 = [1,2]
 /// ```
+///
+/// The child indices are stored privately, since they are `u32` with the `compact-paths`
+/// feature, and are read as `usize` with `indices`, `get`, `len` and `to_vec`.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TreePath {
     /// An array of child index at each level of the dom tree.
     /// The children of the nodes at each child index is traverse
@@ -69,25 +90,123 @@ pub struct TreePath {
     /// If the path has become empty the node is said to be found.
     ///
     /// Empty path means root node
-    pub path: Vec<usize>,
+    pub(crate) path: Vec<PathIndex>,
+}
+
+/// The child index does not fit in the stored path index, which only happens
+/// for the indices past `u32::MAX` when the `compact-paths` feature is enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathIndexOverflow {
+    /// the child index which does not fit
+    pub index: usize,
+}
+
+impl fmt::Display for PathIndexOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "child index {} is too large for a path", self.index)
+    }
+}
+
+impl std::error::Error for PathIndexOverflow {}
+
+/// convert a child index into the stored path index
+fn to_path_index(node_idx: usize) -> Result<PathIndex, PathIndexOverflow> {
+    PathIndex::try_from(node_idx)
+        .map_err(|_| PathIndexOverflow { index: node_idx })
+}
+
+/// convert a child index into the stored path index
+///
+/// # Panics
+/// Panics if the index does not fit in a `PathIndex`
+fn expect_path_index(node_idx: usize) -> PathIndex {
+    to_path_index(node_idx).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// convert a stored path index back into a child index
+#[cfg(feature = "compact-paths")]
+fn to_node_idx(path_idx: PathIndex) -> usize {
+    path_idx as usize
+}
+
+/// convert a stored path index back into a child index
+#[cfg(not(feature = "compact-paths"))]
+fn to_node_idx(path_idx: PathIndex) -> usize {
+    path_idx
 }
 
 impl TreePath {
     /// create a TreePath with node index `node_idx` and traversal path `path`
+    ///
+    /// # Panics
+    /// Panics if a child index is past `u32::MAX` with the `compact-paths` feature,
+    /// see `try_new` for the panic-free alternative
     pub fn new(path: impl IntoIterator<Item = usize>) -> Self {
         Self {
-            path: path.into_iter().collect(),
+            path: path.into_iter().map(expect_path_index).collect(),
         }
     }
 
+    /// create a TreePath the same as `new`, returning an error
+    /// instead of panicking when a child index does not fit in the path
+    pub fn try_new(
+        path: impl IntoIterator<Item = usize>,
+    ) -> Result<Self, PathIndexOverflow> {
+        Ok(Self {
+            path: path
+                .into_iter()
+                .map(to_path_index)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
     /// create a TreePath which starts at empty vec which is the root node of a DOM tree
     pub fn root() -> Self {
         Self { path: vec![] }
     }
 
     /// add a path node idx
+    ///
+    /// # Panics
+    /// Panics if the child index is past `u32::MAX` with the `compact-paths` feature,
+    /// see `try_push` for the panic-free alternative
     pub fn push(&mut self, node_idx: usize) {
-        self.path.push(node_idx)
+        self.path.push(expect_path_index(node_idx))
+    }
+
+    /// add a path node idx, returning an error instead of panicking
+    /// when the child index does not fit in the path
+    pub fn try_push(
+        &mut self,
+        node_idx: usize,
+    ) -> Result<(), PathIndexOverflow> {
+        self.path.push(to_path_index(node_idx)?);
+        Ok(())
+    }
+
+    /// return the child index at each level of this path
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.path.iter().map(|idx| to_node_idx(*idx))
+    }
+
+    /// return the number of levels of this path, which is 0 for the root node
+    pub fn len(&self) -> usize {
+        self.path.len()
+    }
+
+    /// return the child index at the `level` of this path
+    pub fn get(&self, level: usize) -> Option<usize> {
+        self.path.get(level).map(|idx| to_node_idx(*idx))
+    }
+
+    /// return the child indices of this path
+    pub fn to_vec(&self) -> Vec<usize> {
+        self.indices().collect()
+    }
+
+    /// returns true if this path is the same as or under the `prefix` path
+    pub fn starts_with(&self, prefix: &TreePath) -> bool {
+        self.path.starts_with(&prefix.path)
     }
 
     /// create a new TreePath with an added node_index
@@ -105,11 +224,94 @@ impl TreePath {
         new_path
     }
 
+    /// return the path of the parent node, None for the root node
+    pub fn parent(&self) -> Option<Self> {
+        if self.path.is_empty() {
+            None
+        } else {
+            Some(self.backtrack())
+        }
+    }
+
+    /// iterate over the paths of the ancestors, from the parent up to the root node
+    pub fn ancestors(&self) -> impl Iterator<Item = TreePath> + '_ {
+        (0..self.path.len()).rev().map(|len| Self {
+            path: self.path[..len].to_vec(),
+        })
+    }
+
+    /// return the child index of this node in its parent, None for the root node
+    pub fn last_index(&self) -> Option<usize> {
+        self.path.last().map(|idx| to_node_idx(*idx))
+    }
+
+    /// return the path of the next sibling, None for the root node.
+    /// The sibling may not exist in the tree
+    pub fn next_sibling(&self) -> Option<Self> {
+        self.sibling_at(self.last_index()?.checked_add(1)?)
+    }
+
+    /// return the path of the previous sibling,
+    /// None for the root node and for the first child
+    pub fn prev_sibling(&self) -> Option<Self> {
+        self.sibling_at(self.last_index()?.checked_sub(1)?)
+    }
+
+    /// return the path of the sibling at `node_idx` of the same parent
+    pub(crate) fn sibling_at(&self, node_idx: usize) -> Option<Self> {
+        self.parent().map(|parent| parent.traverse(node_idx))
+    }
+
+    /// returns true if this path is strictly under the `ancestor` path,
+    /// a path is not a descendant of itself
+    pub fn is_descendant_of(&self, ancestor: &TreePath) -> bool {
+        self.path.len() > ancestor.path.len()
+            && self.path.starts_with(&ancestor.path)
+    }
+
+    /// returns true if the `descendant` path is strictly under this path
+    pub fn is_ancestor_of(&self, descendant: &TreePath) -> bool {
+        descendant.is_descendant_of(self)
+    }
+
+    /// return the path of the deepest node which is an ancestor of, or the same as,
+    /// both this path and the `other` path
+    pub fn common_ancestor(&self, other: &TreePath) -> Self {
+        let len = self
+            .path
+            .iter()
+            .zip(other.path.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Self {
+            path: self.path[..len].to_vec(),
+        }
+    }
+
+    /// returns true if this node comes before the `other` node in the depth first
+    /// traversal of the tree, which is also the order of `Ord` for the paths,
+    /// so an ancestor comes before its descendants
+    pub fn is_before(&self, other: &TreePath) -> bool {
+        self < other
+    }
+
     /// remove first node index of this treepath
     /// Everytime a node is traversed, the first element should be removed
     /// until no more index is in this path
+    ///
+    /// # Panics
+    /// Panics if the path is empty, see `pop_first` for the panic-free alternative
     pub fn remove_first(&mut self) -> usize {
-        self.path.remove(0)
+        to_node_idx(self.path.remove(0))
+    }
+
+    /// remove first node index of this treepath, returns None if the path is empty
+    pub fn pop_first(&mut self) -> Option<usize> {
+        if self.path.is_empty() {
+            None
+        } else {
+            Some(to_node_idx(self.path.remove(0)))
+        }
     }
 
     /// pluck the next in line node index in this treepath
@@ -130,51 +332,156 @@ impl TreePath {
     ) -> Option<&'a Node<Ns, Tag, Leaf, Att, Val>>
     where
         Ns: PartialEq + Clone + Debug,
-        Tag: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
         Leaf: PartialEq + Clone + Debug,
         Att: PartialEq + Eq + Hash + Clone + Debug,
         Val: PartialEq + Clone + Debug,
     {
-        let mut path = self.clone();
-        traverse_node_by_path(node, &mut path)
+        traverse_node_by_path(node, self).ok()
+    }
+
+    /// find the node using the path of this tree path,
+    /// returning the reason when the path can not be traversed.
+    /// This never panics, so it is safe to use with the paths from external patch sources.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mt_dom::{patch::*, *};
+    ///
+    /// pub type MyNode =
+    ///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+    ///
+    /// let node: MyNode = element("main", vec![], vec![leaf("hello")]);
+    /// assert_eq!(TreePath::new([0]).try_find_node_by_path(&node), Ok(&leaf("hello")));
+    /// assert_eq!(
+    ///     TreePath::new([1]).try_find_node_by_path(&node),
+    ///     Err(TraverseError::IndexOutOfBounds { at_path: TreePath::new([1]) })
+    /// );
+    /// assert_eq!(
+    ///     TreePath::new([0, 0]).try_find_node_by_path(&node),
+    ///     Err(TraverseError::NotAnElement { at_path: TreePath::new([0]) })
+    /// );
+    /// ```
+    pub fn try_find_node_by_path<'a, Ns, Tag, Leaf, Att, Val>(
+        &self,
+        node: &'a Node<Ns, Tag, Leaf, Att, Val>,
+    ) -> Result<&'a Node<Ns, Tag, Leaf, Att, Val>, TraverseError>
+    where
+        Ns: PartialEq + Clone + Debug,
+        Tag: PartialEq + Debug,
+        Leaf: PartialEq + Clone + Debug,
+        Att: PartialEq + Eq + Hash + Clone + Debug,
+        Val: PartialEq + Clone + Debug,
+    {
+        traverse_node_by_path(node, self)
+    }
+
+    /// write the path as `[1,0,2]` into `w` without allocating,
+    /// this is intended for high frequency tracing
+    pub fn write_to(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        w.write_char('[')?;
+        for (i, idx) in self.path.iter().enumerate() {
+            if i > 0 {
+                w.write_char(',')?;
+            }
+            write!(w, "{}", idx)?;
+        }
+        w.write_char(']')
+    }
+
+    /// return a formatter which displays the path as `[1,0,2]` without allocating
+    pub fn display(&self) -> PathDisplay<'_> {
+        PathDisplay { path: self }
+    }
+}
+
+/// The reason why a `TreePath` can not be traversed in a node tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraverseError {
+    /// the node at the parent of `at_path` has no child at the last index of `at_path`
+    IndexOutOfBounds {
+        /// the path up to and including the index which is out of bounds
+        at_path: TreePath,
+    },
+    /// the node at `at_path` is a leaf, so it has no children to traverse into
+    NotAnElement {
+        /// the path of the leaf node
+        at_path: TreePath,
+    },
+}
+
+impl fmt::Display for TraverseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IndexOutOfBounds { at_path } => {
+                write!(f, "there is no node at {}", at_path.display())
+            }
+            Self::NotAnElement { at_path } => write!(
+                f,
+                "the node at {} is a leaf which has no children",
+                at_path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TraverseError {}
+
+/// Displays a `TreePath` as `[1,0,2]`, returned by `TreePath::display`
+#[derive(Debug, Clone, Copy)]
+pub struct PathDisplay<'p> {
+    path: &'p TreePath,
+}
+
+impl fmt::Display for PathDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.path.write_to(f)
     }
 }
 
 impl<const N: usize> From<[usize; N]> for TreePath {
     fn from(array: [usize; N]) -> Self {
-        Self {
-            path: array.to_vec(),
-        }
+        Self::new(array)
     }
 }
 
 impl From<Vec<usize>> for TreePath {
     fn from(vec: Vec<usize>) -> Self {
-        Self { path: vec }
+        Self::new(vec)
     }
 }
 
+/// walk the path from `node` without panicking, the error contains the path
+/// up to and including the index which could not be traversed
 fn traverse_node_by_path<'a, Ns, Tag, Leaf, Att, Val>(
     node: &'a Node<Ns, Tag, Leaf, Att, Val>,
-    path: &mut TreePath,
-) -> Option<&'a Node<Ns, Tag, Leaf, Att, Val>>
+    path: &TreePath,
+) -> Result<&'a Node<Ns, Tag, Leaf, Att, Val>, TraverseError>
 where
     Ns: PartialEq + Clone + Debug,
-    Tag: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
     Leaf: PartialEq + Clone + Debug,
     Att: PartialEq + Eq + Hash + Clone + Debug,
     Val: PartialEq + Clone + Debug,
 {
-    if path.path.is_empty() {
-        Some(node)
-    } else {
-        let idx = path.path.remove(0);
-        if let Some(child) = node.children().get(idx) {
-            traverse_node_by_path(child, path)
-        } else {
-            None
+    let mut found = node;
+    for (depth, idx) in path.indices().enumerate() {
+        if found.is_leaf() {
+            return Err(TraverseError::NotAnElement {
+                at_path: TreePath {
+                    path: path.path[..depth].to_vec(),
+                },
+            });
         }
+        found = found.child_nodes().get(idx).ok_or_else(|| {
+            TraverseError::IndexOutOfBounds {
+                at_path: TreePath {
+                    path: path.path[..=depth].to_vec(),
+                },
+            }
+        })?;
     }
+    Ok(found)
 }
 
 #[cfg(test)]
@@ -193,6 +500,44 @@ mod tests {
         &'static str,
     >;
 
+    /// a fixed size buffer, so writing into it does not allocate
+    struct Buffer {
+        bytes: [u8; 32],
+        len: usize,
+    }
+
+    impl fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.bytes
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut buffer = Buffer {
+            bytes: [0; 32],
+            len: 0,
+        };
+        TreePath::from([1, 0, 12]).write_to(&mut buffer).unwrap();
+        assert_eq!(&buffer.bytes[..buffer.len], b"[1,0,12]");
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(TreePath::root().display().to_string(), "[]");
+        assert_eq!(TreePath::from([0]).display().to_string(), "[0]");
+        assert_eq!(
+            format!("at {}", TreePath::from([1, 2]).display()),
+            "at [1,2]"
+        );
+    }
+
     #[test]
     fn test_traverse() {
         let path = TreePath::from([0]);
@@ -200,6 +545,76 @@ mod tests {
         assert_eq!(path.traverse(1), TreePath::from([0, 1]));
     }
 
+    #[test]
+    fn test_parent_and_ancestors() {
+        let path = TreePath::from([1, 2, 0]);
+        assert_eq!(path.parent(), Some(TreePath::from([1, 2])));
+        assert_eq!(TreePath::root().parent(), None);
+        assert_eq!(
+            path.ancestors().collect::<Vec<_>>(),
+            [
+                TreePath::from([1, 2]),
+                TreePath::from([1]),
+                TreePath::root()
+            ]
+        );
+        assert_eq!(TreePath::root().ancestors().count(), 0);
+    }
+
+    #[test]
+    fn test_siblings() {
+        let path = TreePath::from([1, 2]);
+        assert_eq!(path.next_sibling(), Some(TreePath::from([1, 3])));
+        assert_eq!(path.prev_sibling(), Some(TreePath::from([1, 1])));
+        assert_eq!(TreePath::from([1, 0]).prev_sibling(), None);
+        assert_eq!(TreePath::root().next_sibling(), None);
+        assert_eq!(TreePath::root().prev_sibling(), None);
+    }
+
+    #[test]
+    fn test_descendants() {
+        let removed = TreePath::from([1]);
+        assert!(TreePath::from([1, 0]).is_descendant_of(&removed));
+        assert!(TreePath::from([1, 0, 4]).is_descendant_of(&removed));
+        assert!(!removed.is_descendant_of(&removed));
+        assert!(!TreePath::from([10]).is_descendant_of(&removed));
+        assert!(!TreePath::from([0, 1]).is_descendant_of(&removed));
+        assert!(TreePath::root().is_ancestor_of(&removed));
+        assert_eq!(
+            TreePath::from([1, 2, 3])
+                .common_ancestor(&TreePath::from([1, 2, 5])),
+            TreePath::from([1, 2])
+        );
+        assert_eq!(
+            TreePath::from([0]).common_ancestor(&TreePath::from([1])),
+            TreePath::root()
+        );
+    }
+
+    #[test]
+    fn test_document_order() {
+        let mut paths = vec![
+            TreePath::from([1]),
+            TreePath::from([0, 1]),
+            TreePath::root(),
+            TreePath::from([1, 0]),
+            TreePath::from([0]),
+        ];
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                TreePath::root(),
+                TreePath::from([0]),
+                TreePath::from([0, 1]),
+                TreePath::from([1]),
+                TreePath::from([1, 0]),
+            ]
+        );
+        assert!(TreePath::from([0, 5]).is_before(&TreePath::from([1])));
+        assert!(TreePath::from([1]).is_before(&TreePath::from([1, 0])));
+    }
+
     fn sample_node() -> MyNode {
         let node: MyNode = element(
             "div",
@@ -273,11 +688,14 @@ mod tests {
         let id = node.attribute_value(&"id").unwrap()[0];
         let class = node.attribute_value(&"class").unwrap()[0];
         assert_eq!(id.to_string(), node_idx.to_string());
-        assert_eq!(class.to_string(), format_vec(&path.path));
+        assert_eq!(
+            class.to_string(),
+            format_vec(&path.indices().collect::<Vec<_>>())
+        );
         for (i, child) in node.children().iter().enumerate() {
             *node_idx += 1;
             let mut child_path = path.clone();
-            child_path.path.push(i);
+            child_path.push(i);
             traverse_tree_path(child, &child_path, node_idx);
         }
     }
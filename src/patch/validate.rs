@@ -0,0 +1,300 @@
+//! validate the paths of the patches against the old node tree
+use super::{Placement, TreeId};
+use crate::{Node, Patch, PatchType, TreePath};
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// The reason why a patch is not valid for the old node tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidPatch {
+    /// the path does not resolve to a node in the old tree
+    UnresolvedPath {
+        /// the index of the invalid patch
+        patch_index: usize,
+        /// the path which does not resolve
+        path: TreePath,
+    },
+    /// the patch needs the position of the target in its parent,
+    /// such as inserting before the node, but the target is the root node
+    NoParent {
+        /// the index of the invalid patch
+        patch_index: usize,
+        /// the path of the target node
+        path: TreePath,
+    },
+    /// the run of siblings starting at the target node goes past the end of the children
+    OutOfRange {
+        /// the index of the invalid patch
+        patch_index: usize,
+        /// the path of the target node
+        path: TreePath,
+        /// the number of siblings, starting at the target node, used by the patch
+        count: usize,
+    },
+    /// the adopted node of an `AdoptNode` patch can not be resolved,
+    /// such as when its auxiliary tree is not registered
+    UnresolvedAdoption {
+        /// the index of the invalid patch
+        patch_index: usize,
+        /// the auxiliary tree of the adopted node
+        from_tree: TreeId,
+        /// the path of the adopted node in the auxiliary tree
+        from_path: TreePath,
+    },
+    /// the target of a `SetSlot` or `ClearSlot` patch is not an element
+    /// whose children are keyed by their slots, with a slot name for each child
+    NotSlotted {
+        /// the index of the invalid patch
+        patch_index: usize,
+        /// the path of the target node
+        path: TreePath,
+    },
+    /// the target node is removed by a previous patch, without any node
+    /// which took its place
+    DetachedTarget {
+        /// the index of the invalid patch
+        patch_index: usize,
+        /// the path of the target node
+        path: TreePath,
+    },
+    /// the patch changes the tag or the attributes of a node which is not an element
+    NotAnElement {
+        /// the index of the invalid patch
+        patch_index: usize,
+        /// the path of the target node
+        path: TreePath,
+    },
+}
+
+impl fmt::Display for InvalidPatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnresolvedPath { patch_index, path } => write!(
+                f,
+                "patch {} has a path {:?} which does not resolve to a node",
+                patch_index, path.path
+            ),
+            Self::NoParent { patch_index, path } => write!(
+                f,
+                "patch {} targets the node at {:?} which has no parent",
+                patch_index, path.path
+            ),
+            Self::OutOfRange {
+                patch_index,
+                path,
+                count,
+            } => write!(
+                f,
+                "patch {} uses {} siblings starting at {:?} which is past the end of the children",
+                patch_index, count, path.path
+            ),
+            Self::UnresolvedAdoption {
+                patch_index,
+                from_tree,
+                from_path,
+            } => write!(
+                f,
+                "patch {} adopts the node at {:?} of the tree {:?} which does not resolve to a node",
+                patch_index, from_path.path, from_tree
+            ),
+            Self::NotSlotted { patch_index, path } => write!(
+                f,
+                "patch {} sets the slots of the node at {:?} which has no slots",
+                patch_index, path.path
+            ),
+            Self::DetachedTarget { patch_index, path } => write!(
+                f,
+                "patch {} targets the node at {:?} which is removed by a previous patch",
+                patch_index, path.path
+            ),
+            Self::NotAnElement { patch_index, path } => write!(
+                f,
+                "patch {} targets the node at {:?} which is not an element",
+                patch_index, path.path
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidPatch {}
+
+/// Check that every path in the patches resolves to a node in the `old` node tree,
+/// and that the targets of the patches which are placed relative to the siblings
+/// such as `InsertBeforeNode` resolves to a valid position.
+///
+/// This is run in debug builds after every diff.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{patch::*, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("main", vec![], vec![leaf("hello")]);
+/// let patches = vec![Patch::remove_node(None, TreePath::new([1]))];
+/// assert_eq!(
+///     validate(&old, &patches),
+///     Err(InvalidPatch::UnresolvedPath {
+///         patch_index: 0,
+///         path: TreePath::new([1])
+///     })
+/// );
+/// ```
+pub fn validate<Ns, Tag, Leaf, Att, Val>(
+    old: &Node<Ns, Tag, Leaf, Att, Val>,
+    patches: &[Patch<Ns, Tag, Leaf, Att, Val>],
+) -> Result<(), InvalidPatch>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    for (patch_index, patch) in patches.iter().enumerate() {
+        let path = patch.path();
+        let target =
+            resolve(old, path).ok_or_else(|| InvalidPatch::UnresolvedPath {
+                patch_index,
+                path: path.clone(),
+            })?;
+
+        for node_path in patch.node_paths() {
+            resolve(old, node_path).ok_or_else(|| {
+                InvalidPatch::UnresolvedPath {
+                    patch_index,
+                    path: node_path.clone(),
+                }
+            })?;
+            check_has_parent(patch_index, node_path)?;
+        }
+
+        match &patch.patch_type {
+            PatchType::InsertBeforeNode { .. }
+            | PatchType::InsertAfterNode { .. }
+            | PatchType::MoveBeforeNode { .. }
+            | PatchType::MoveAfterNode { .. }
+            | PatchType::RemoveNode => {
+                check_has_parent(patch_index, path)?;
+            }
+            // the root node can only be wrapped by itself
+            PatchType::WrapNode { wrapper } if path.is_empty() => {
+                let count = wrapper.children().len();
+                if count != 1 {
+                    return Err(InvalidPatch::OutOfRange {
+                        patch_index,
+                        path: path.clone(),
+                        count,
+                    });
+                }
+            }
+            PatchType::WrapNode { wrapper } => {
+                check_siblings(
+                    old,
+                    patch_index,
+                    path,
+                    wrapper.children().len(),
+                )?;
+            }
+            PatchType::ChangeLeafRun { count, .. } => {
+                check_siblings(old, patch_index, path, *count)?;
+            }
+            // the auxiliary tree is not known here, so only its shape is checked
+            PatchType::AdoptNode {
+                from_path,
+                placement,
+                ..
+            } => {
+                if *placement != Placement::Append {
+                    check_has_parent(patch_index, path)?;
+                }
+                check_has_parent(patch_index, from_path)?;
+            }
+            PatchType::SetSlot { .. } | PatchType::ClearSlot { .. } => {
+                if !target.element_ref().is_some_and(|element| {
+                    element.is_slotted() && element.has_slot_per_child()
+                }) {
+                    return Err(InvalidPatch::NotSlotted {
+                        patch_index,
+                        path: path.clone(),
+                    });
+                }
+            }
+            PatchType::AppendChildren { .. }
+            | PatchType::MoveIntoNode { .. }
+            | PatchType::ReplaceNode { .. }
+            | PatchType::UnwrapNode
+            | PatchType::ChangeLeaf { .. }
+            | PatchType::SpliceLeaf { .. }
+            | PatchType::ChangeTag { .. }
+            | PatchType::SyncIsland
+            | PatchType::AddAttributes { .. }
+            | PatchType::RemoveAttributes { .. }
+            | PatchType::UpdateAttributes { .. }
+            | PatchType::UpdateAttributeParts { .. }
+            | PatchType::ForEachChild { .. }
+            | PatchType::Broadcast { .. } => (),
+        }
+    }
+    Ok(())
+}
+
+fn check_has_parent(
+    patch_index: usize,
+    path: &TreePath,
+) -> Result<(), InvalidPatch> {
+    if path.is_empty() {
+        Err(InvalidPatch::NoParent {
+            patch_index,
+            path: path.clone(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// check that there are `count` siblings starting at the node in `path`
+fn check_siblings<Ns, Tag, Leaf, Att, Val>(
+    old: &Node<Ns, Tag, Leaf, Att, Val>,
+    patch_index: usize,
+    path: &TreePath,
+    count: usize,
+) -> Result<(), InvalidPatch>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    check_has_parent(patch_index, path)?;
+    let position = path.indices().last().expect("must have a parent");
+    let siblings = resolve(old, &path.backtrack())
+        .map(Node::child_nodes)
+        .unwrap_or(&[]);
+    if position + count <= siblings.len() {
+        Ok(())
+    } else {
+        Err(InvalidPatch::OutOfRange {
+            patch_index,
+            path: path.clone(),
+            count,
+        })
+    }
+}
+
+fn resolve<'n, Ns, Tag, Leaf, Att, Val>(
+    node: &'n Node<Ns, Tag, Leaf, Att, Val>,
+    path: &TreePath,
+) -> Option<&'n Node<Ns, Tag, Leaf, Att, Val>>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    path.find_node_by_path(node)
+}
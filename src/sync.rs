@@ -0,0 +1,106 @@
+//! pulling only the changed subtrees of a node tree from another process
+//!
+//! The client compares the `merkle` hash tree of its node tree with the hash tree of the server,
+//! requests the changed paths, and the server `respond`s with the subtrees at those paths.
+//! The client `integrate`s the subtrees into a copy of its tree, then diffs its tree
+//! against the copy locally. This is a middle ground between shipping the whole trees
+//! and shipping the patches, since the server does not need to keep the old tree of each client.
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{merkle::*, sync::*, *};
+//!
+//! pub type MyNode =
+//!     Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+//!
+//! let client: MyNode = element(
+//!     "main",
+//!     vec![],
+//!     vec![element("p", vec![], vec![leaf("same")]), leaf("old")],
+//! );
+//! let server: MyNode = element(
+//!     "main",
+//!     vec![],
+//!     vec![element("p", vec![], vec![leaf("same")]), leaf("new")],
+//! );
+//!
+//! // the client sends the changed paths to the server
+//! let paths = changed_paths(&hash_tree(&client), &hash_tree(&server));
+//! let responses = respond(&server, &paths);
+//!
+//! let mut synced = client.clone();
+//! integrate(&mut synced, responses).unwrap();
+//! assert_eq!(synced, server);
+//! assert_eq!(diff_with_key(&client, &synced, &"key").len(), 1);
+//! ```
+use crate::patch::TraverseError;
+use crate::{Node, TreePath};
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// return the copies of the subtrees of `node` at the requested `paths`,
+/// together with their paths. The paths which do not exist in `node` are skipped
+pub fn respond<Ns, Tag, Leaf, Att, Val>(
+    node: &Node<Ns, Tag, Leaf, Att, Val>,
+    paths: &[TreePath],
+) -> Vec<(TreePath, Node<Ns, Tag, Leaf, Att, Val>)>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    paths
+        .iter()
+        .filter_map(|path| {
+            path.find_node_by_path(node)
+                .map(|subtree| (path.clone(), subtree.clone()))
+        })
+        .collect()
+}
+
+/// replace the subtrees of `node` at the paths of the `responses` with the subtrees
+/// of the responses, in order.
+///
+/// This stops at the first path which can not be traversed in `node`,
+/// the responses before it are already integrated.
+pub fn integrate<Ns, Tag, Leaf, Att, Val>(
+    node: &mut Node<Ns, Tag, Leaf, Att, Val>,
+    responses: impl IntoIterator<Item = (TreePath, Node<Ns, Tag, Leaf, Att, Val>)>,
+) -> Result<(), TraverseError>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    for (path, subtree) in responses {
+        path.try_find_node_by_path(node)?;
+        let target = path
+            .indices()
+            .fold(&mut *node, |found, idx| &mut child_nodes_mut(found)[idx]);
+        *target = subtree;
+    }
+    Ok(())
+}
+
+/// the mutable `Node::child_nodes`, which are addressed by the tree paths
+fn child_nodes_mut<Ns, Tag, Leaf, Att, Val>(
+    node: &mut Node<Ns, Tag, Leaf, Att, Val>,
+) -> &mut [Node<Ns, Tag, Leaf, Att, Val>]
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    match node {
+        Node::Element(element) => element.children_mut(),
+        Node::Fragment(nodes) | Node::NodeList(nodes) => nodes,
+        Node::Leaf(_) => &mut [],
+    }
+}
@@ -0,0 +1,224 @@
+//! helpers for differential testing of the diffing strategies
+//!
+//! Every strategy registered in `strategies` must produce patches which turns
+//! the old node into the new node when applied, so a new reconciliation strategy can be
+//! landed incrementally by registering it here and running `assert_algorithms_agree`
+//! against the existing scenarios.
+#[cfg(feature = "apply-patches")]
+use crate::diff::diff_with_options;
+use crate::diff::{DefaultKey, DiffAlgorithm, DiffOptions};
+#[cfg(feature = "apply-patches")]
+use crate::{apply_patches, patch};
+use crate::{diff, Node};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+pub use fixture::tree_from_str;
+pub use snapshot::{format_patches, snapshot_patches, UPDATE_SNAPSHOTS};
+
+#[cfg(feature = "testing")]
+pub mod arbitrary;
+#[cfg(all(feature = "keyed", feature = "apply-patches"))]
+pub mod conformance;
+mod fixture;
+mod snapshot;
+
+/// return the registered diffing strategies and their names
+pub fn strategies<'o, Ns, Tag, Leaf, Att, Val>(
+) -> Vec<(&'static str, DiffOptions<'o, Ns, Tag, Leaf, Att, Val>)>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    vec![
+        ("default", DiffOptions::default()),
+        (
+            "change_leaf",
+            DiffOptions {
+                change_leaf: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "detect_wrapping",
+            DiffOptions {
+                detect_wrapping: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "merge_text_runs",
+            DiffOptions {
+                merge_text_runs: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "keyed_tag_change",
+            DiffOptions {
+                keyed_tag_change: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "for_each_child",
+            DiffOptions {
+                for_each_child: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "positional",
+            DiffOptions {
+                algorithm: DiffAlgorithm::Positional,
+                ..Default::default()
+            },
+        ),
+        (
+            "auto",
+            DiffOptions {
+                algorithm: DiffAlgorithm::Auto,
+                ..Default::default()
+            },
+        ),
+        (
+            "all_options",
+            DiffOptions {
+                detect_wrapping: true,
+                change_leaf: true,
+                merge_text_runs: true,
+                keyed_tag_change: true,
+                for_each_child: true,
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+/// Diff `old` and `new` with every registered strategy, and assert that
+/// applying the patches of each of the strategies into `old` results to `new`.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{testing::assert_algorithms_agree, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let old: MyNode = element("ul", vec![], vec![
+///     element("li", vec![attr("key", "1")], vec![leaf("one")]),
+///     element("li", vec![attr("key", "2")], vec![leaf("two")]),
+/// ]);
+/// let new: MyNode = element("ul", vec![], vec![
+///     element("li", vec![attr("key", "2")], vec![leaf("two")]),
+///     element("li", vec![attr("key", "1")], vec![leaf("uno")]),
+/// ]);
+/// assert_algorithms_agree(&old, &new, &"key");
+/// ```
+///
+/// # Panics
+/// Panics with the name of the strategy which produced invalid patches
+/// or patches which did not result to `new`
+#[cfg(feature = "apply-patches")]
+pub fn assert_algorithms_agree<Ns, Tag, Leaf, Att, Val>(
+    old: &Node<Ns, Tag, Leaf, Att, Val>,
+    new: &Node<Ns, Tag, Leaf, Att, Val>,
+    key: &Att,
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Clone + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    for (name, options) in strategies() {
+        let patches = diff_with_options(old, new, key, &options);
+        if let Err(e) = patch::validate(old, &patches) {
+            panic!("strategy `{}` produced invalid patches: {}", name, e);
+        }
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        assert_eq!(
+            &root, new,
+            "strategy `{}` diverged, patches: {:#?}",
+            name, patches
+        );
+    }
+}
+
+/// Describe the differences of the nodes with the patches which turns `left` into `right`,
+/// one patch per line with its path and the tag of its target node.
+/// This is used by `assert_nodes_eq!` in place of the Debug output of both trees.
+pub fn describe_differences<Ns, Tag, Leaf, Att, Val>(
+    left: &Node<Ns, Tag, Leaf, Att, Val>,
+    right: &Node<Ns, Tag, Leaf, Att, Val>,
+) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug + DefaultKey,
+    Val: PartialEq + Clone + Debug,
+{
+    let patches = diff(left, right);
+    let mut description = format!(
+        "{} patch(es) turns the left node into the right node:",
+        patches.len()
+    );
+    for line in format_patches(&patches).lines() {
+        description.push_str(&format!("\n  {line}"));
+    }
+    description
+}
+
+/// Asserts that the 2 nodes are equal, the same as `assert_eq!`, but on failure
+/// the structural differences of the nodes are printed with `describe_differences`
+/// instead of the Debug output of both trees.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{assert_nodes_eq, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let left: MyNode = element("main", vec![], vec![leaf("hello")]);
+/// let right: MyNode = element("main", vec![], vec![leaf("hello")]);
+/// assert_nodes_eq!(left, right);
+/// assert_nodes_eq!(left, right, "rendering {}", "the greeting");
+/// ```
+#[macro_export]
+macro_rules! assert_nodes_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if left != right {
+                    panic!(
+                        "assertion `left == right` failed, {}",
+                        $crate::testing::describe_differences(left, right)
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if left != right {
+                    panic!(
+                        "assertion `left == right` failed: {}, {}",
+                        format_args!($($arg)+),
+                        $crate::testing::describe_differences(left, right)
+                    );
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,146 @@
+//! proptest strategies generating random keyed and non-keyed trees
+//!
+//! The values are drawn from small sets, so 2 generated trees often share tags, attributes,
+//! leaves and keys, which exercises the matching of the differ instead of replacing
+//! everything. Downstream crates can use the strategies to fuzz the renderers of the patches.
+//!
+//! The attributes are generated in any order, while the patches keep the attributes
+//! of an element which are not removed in their place and append the added attributes.
+//! So the tree which the patches are applied to is only equal to the new tree
+//! once the attributes are sorted with `sort_attributes`, the same as the order of
+//! the attributes does not matter in html.
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::{testing::arbitrary::*, *};
+//! use proptest::test_runner::TestRunner;
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&(arb_tree(), arb_tree()), |(old, new)| {
+//!         let patches = diff_with_key(&old, &new, &KEY);
+//!         let mut root = old.clone();
+//!         apply_patches(&mut root, &patches);
+//!         assert_eq!(sort_attributes(&root), sort_attributes(&new));
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+use crate::{attr, element, leaf, Attribute, Node};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use proptest::prelude::*;
+use proptest::{collection, sample};
+
+/// The node type of the generated trees
+pub type ArbitraryNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+/// the name of the key attribute in the generated keyed lists
+pub const KEY: &str = "key";
+
+/// the tags of the generated elements
+pub const TAGS: [&str; 4] = ["div", "span", "p", "section"];
+
+/// the tags of the children of the generated keyed lists, so the children which
+/// share a key can have different tags
+pub const KEYED_TAGS: [&str; 2] = ["li", "span"];
+
+/// the names of the generated attributes, which does not include the `KEY`
+pub const ATTRIBUTES: [&str; 3] = ["class", "id", "title"];
+
+/// the number of distinct keys in the generated keyed lists
+pub const KEYS: usize = 8;
+
+/// generate a leaf with one of a few texts
+pub fn arb_leaf() -> impl Strategy<Value = ArbitraryNode> {
+    (0..4_u8).prop_map(|text| leaf(format!("text{text}")))
+}
+
+/// generate the attributes of an element, with any subset of the `ATTRIBUTES`
+/// in any order
+pub fn arb_attributes(
+) -> impl Strategy<Value = Vec<Attribute<&'static str, &'static str, String>>> {
+    sample::subsequence(ATTRIBUTES.to_vec(), 0..=ATTRIBUTES.len())
+        .prop_shuffle()
+        .prop_flat_map(|names| {
+            let len = names.len();
+            (Just(names), collection::vec(0..3_u8, len))
+        })
+        .prop_map(|(names, values)| {
+            names
+                .into_iter()
+                .zip(values)
+                .map(|(name, value)| attr(name, format!("{name}{value}")))
+                .collect()
+        })
+}
+
+/// generate a keyed child with one of the `KEYED_TAGS`, where the key is
+/// anywhere among the other attributes
+fn arb_keyed_child(key: usize) -> impl Strategy<Value = ArbitraryNode> {
+    (
+        sample::select(KEYED_TAGS.to_vec()),
+        arb_attributes(),
+        0..=ATTRIBUTES.len(),
+        arb_leaf(),
+    )
+        .prop_map(move |(tag, mut attrs, position, leaf)| {
+            attrs.insert(position.min(attrs.len()), attr(KEY, key.to_string()));
+            element(tag, attrs, [leaf])
+        })
+}
+
+/// generate a list whose children are keyed with unique keys out of `KEYS` keys,
+/// in a random order
+pub fn arb_keyed_list() -> impl Strategy<Value = ArbitraryNode> {
+    sample::subsequence((0..KEYS).collect::<Vec<_>>(), 0..=KEYS)
+        .prop_shuffle()
+        .prop_flat_map(|keys| {
+            keys.into_iter().map(arb_keyed_child).collect::<Vec<_>>()
+        })
+        .prop_map(|children| element("ul", [], children))
+}
+
+/// generate a tree of up to 4 levels deep, where the lowest level are leaves
+/// and keyed lists
+pub fn arb_tree() -> impl Strategy<Value = ArbitraryNode> {
+    prop_oneof![arb_leaf(), arb_keyed_list()].prop_recursive(
+        4,
+        64,
+        4,
+        |children| {
+            (
+                sample::select(TAGS.to_vec()),
+                arb_attributes(),
+                collection::vec(children, 0..4),
+            )
+                .prop_map(|(tag, attrs, children)| {
+                    element(tag, attrs, children)
+                })
+        },
+    )
+}
+
+/// sort the attributes of every element in the tree by their names, which keeps the order
+/// of the attributes with the same name, so 2 trees which only differ in the order of
+/// their attributes are equal once sorted
+pub fn sort_attributes(node: &ArbitraryNode) -> ArbitraryNode {
+    let mut node = node.clone();
+    let mut stack = vec![&mut node];
+    while let Some(node) = stack.pop() {
+        match node {
+            Node::Element(element) => {
+                element.attrs.sort_by_key(|att| att.name);
+                stack.extend(element.children.iter_mut());
+            }
+            Node::Fragment(nodes) | Node::NodeList(nodes) => {
+                stack.extend(nodes.iter_mut())
+            }
+            Node::Leaf(_) => (),
+        }
+    }
+    node
+}
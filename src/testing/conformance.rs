@@ -0,0 +1,374 @@
+//! A conformance suite for the keyed reconciliation algorithms
+//!
+//! Every `KeyedReconciler` must produce patches which turns the old list into the new list,
+//! and must never recreate a keyed child whose key is in both of the old and new list,
+//! when the keys are unique. The scenarios covers the ordering of the children,
+//! duplicate keys, mixed keyed and unkeyed children and large shuffles.
+//!
+//! # Example
+//! ```rust
+//! use mt_dom::testing::conformance::{self, LisReconciler, NumericKeyReconciler};
+//!
+//! conformance::run(&LisReconciler);
+//! conformance::run(&NumericKeyReconciler);
+//! ```
+use crate::diff::{diff_with_key, diff_with_options, DiffOptions};
+use crate::{apply_patches, attr, element, leaf, patch, Node, Patch};
+use crate::{PatchType, TreePath};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+/// The node type used in the conformance scenarios
+pub type ConformanceNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+/// The patches of the `ConformanceNode`s
+pub type ConformancePatch<'a> =
+    Patch<'a, &'static str, &'static str, String, &'static str, String>;
+
+/// the name of the key attribute in the conformance scenarios
+pub const KEY: &str = "key";
+
+/// A keyed reconciliation algorithm which is checked by the conformance suite
+pub trait KeyedReconciler: Debug {
+    /// the name of this reconciler in the failure messages
+    fn name(&self) -> &str;
+
+    /// return the patches which turns `old` into `new`,
+    /// the children are keyed with the `KEY` attribute
+    fn diff<'a>(
+        &self,
+        old: &'a ConformanceNode,
+        new: &'a ConformanceNode,
+    ) -> Vec<ConformancePatch<'a>>;
+}
+
+/// The default keyed algorithm, which keeps the longest increasing subsequence
+/// of the matched children in place
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LisReconciler;
+
+impl KeyedReconciler for LisReconciler {
+    fn name(&self) -> &str {
+        "lis"
+    }
+
+    fn diff<'a>(
+        &self,
+        old: &'a ConformanceNode,
+        new: &'a ConformanceNode,
+    ) -> Vec<ConformancePatch<'a>> {
+        diff_with_key(old, new, &KEY)
+    }
+}
+
+/// The keyed algorithm which matches the numeric keys with integer sorting,
+/// see `DiffOptions::with_numeric_keys`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NumericKeyReconciler;
+
+impl KeyedReconciler for NumericKeyReconciler {
+    fn name(&self) -> &str {
+        "numeric_key"
+    }
+
+    fn diff<'a>(
+        &self,
+        old: &'a ConformanceNode,
+        new: &'a ConformanceNode,
+    ) -> Vec<ConformancePatch<'a>> {
+        let options = DiffOptions::default().with_numeric_keys();
+        diff_with_options(old, new, &KEY, &options)
+    }
+}
+
+/// a child in a scenario, keyed or unkeyed, with its text content
+#[derive(Debug, Clone)]
+enum Child {
+    Keyed(usize, &'static str),
+    Unkeyed(&'static str),
+}
+
+fn keyed(keys: impl IntoIterator<Item = usize>) -> Vec<Child> {
+    keys.into_iter()
+        .map(|key| Child::Keyed(key, "item"))
+        .collect()
+}
+
+fn list(children: &[Child]) -> ConformanceNode {
+    element(
+        "ul",
+        vec![attr("class", "list".to_string())],
+        children.iter().map(|child| match child {
+            Child::Keyed(key, text) => element(
+                "li",
+                vec![attr(KEY, key.to_string())],
+                vec![leaf(format!("{text} {key}"))],
+            ),
+            Child::Unkeyed(text) => {
+                element("li", vec![], vec![leaf(text.to_string())])
+            }
+        }),
+    )
+}
+
+/// the named scenarios of the old and new children
+fn scenarios() -> Vec<(String, Vec<Child>, Vec<Child>)> {
+    let mut scenarios = vec![];
+    let mut add = |name: &str, old: Vec<Child>, new: Vec<Child>| {
+        scenarios.push((name.to_string(), old, new));
+    };
+
+    // ordering
+    add("unchanged", keyed(0..10), keyed(0..10));
+    add("empty_to_many", keyed([]), keyed(0..10));
+    add("many_to_empty", keyed(0..10), keyed([]));
+    add("reverse", keyed(0..10), keyed((0..10).rev()));
+    add("rotate_left", keyed(0..10), keyed((1..10).chain([0])));
+    add(
+        "rotate_right",
+        keyed(0..10),
+        keyed([9].into_iter().chain(0..9)),
+    );
+    add(
+        "swap_ends",
+        keyed(0..10),
+        keyed([9, 1, 2, 3, 4, 5, 6, 7, 8, 0]),
+    );
+    add(
+        "swap_rows",
+        keyed(0..10),
+        keyed([0, 8, 2, 3, 4, 5, 6, 7, 1, 9]),
+    );
+    add("insert_front", keyed(0..5), keyed([100, 0, 1, 2, 3, 4]));
+    add(
+        "insert_middle",
+        keyed(0..5),
+        keyed([0, 1, 100, 101, 2, 3, 4]),
+    );
+    add("insert_back", keyed(0..5), keyed([0, 1, 2, 3, 4, 100]));
+    add("remove_front", keyed(0..5), keyed(1..5));
+    add("remove_middle", keyed(0..5), keyed([0, 1, 4]));
+    add("remove_back", keyed(0..5), keyed(0..4));
+    add("replace_all", keyed(0..5), keyed(10..15));
+    add(
+        "move_insert_and_remove",
+        keyed(0..8),
+        keyed([7, 100, 2, 0, 5, 101, 3]),
+    );
+    add(
+        "changed_content",
+        keyed(0..4),
+        vec![
+            Child::Keyed(3, "changed"),
+            Child::Keyed(1, "item"),
+            Child::Keyed(0, "changed"),
+            Child::Keyed(2, "item"),
+        ],
+    );
+
+    // duplicates
+    add("duplicate_old", keyed([1, 1, 2, 3]), keyed([3, 2, 1]));
+    add("duplicate_new", keyed([1, 2, 3]), keyed([3, 1, 1, 2]));
+    add(
+        "duplicate_both",
+        keyed([1, 2, 2, 3, 1]),
+        keyed([2, 1, 3, 1, 2]),
+    );
+
+    // mixed keyed and unkeyed children
+    add(
+        "mixed_unkeyed_front",
+        vec![Child::Unkeyed("header")]
+            .into_iter()
+            .chain(keyed(0..5))
+            .collect(),
+        vec![Child::Unkeyed("header")]
+            .into_iter()
+            .chain(keyed([4, 3, 2, 1, 0]))
+            .collect(),
+    );
+    add(
+        "mixed_interleaved",
+        vec![
+            Child::Keyed(0, "item"),
+            Child::Unkeyed("a"),
+            Child::Keyed(1, "item"),
+            Child::Unkeyed("b"),
+            Child::Keyed(2, "item"),
+        ],
+        vec![
+            Child::Keyed(2, "item"),
+            Child::Unkeyed("b"),
+            Child::Keyed(0, "item"),
+            Child::Unkeyed("c"),
+        ],
+    );
+    add(
+        "mixed_unkeyed_to_keyed",
+        vec![Child::Unkeyed("a"), Child::Unkeyed("b")],
+        keyed(0..3),
+    );
+
+    // large shuffles
+    for seed in 1..=4 {
+        let (old, new) = large_shuffle(seed, 1000);
+        add(&format!("large_shuffle_{seed}"), old, new);
+    }
+    scenarios
+}
+
+/// shuffle `size` keyed children, then remove and insert about a tenth of them
+fn large_shuffle(seed: u64, size: usize) -> (Vec<Child>, Vec<Child>) {
+    let mut state = seed;
+    let mut next = |bound: usize| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) as usize) % bound
+    };
+    let old: Vec<usize> = (0..size).collect();
+    let mut new = old.clone();
+    for i in (1..new.len()).rev() {
+        let j = next(i + 1);
+        new.swap(i, j);
+    }
+    for _ in 0..size / 10 {
+        let at = next(new.len());
+        new.remove(at);
+    }
+    for key in size..size + size / 10 {
+        let at = next(new.len() + 1);
+        new.insert(at, key);
+    }
+    (keyed(old), keyed(new))
+}
+
+/// Run the conformance suite against the `reconciler`
+///
+/// # Panics
+/// Panics with the name of the reconciler and the scenario which failed
+pub fn run(reconciler: &impl KeyedReconciler) {
+    for (scenario, old_children, new_children) in scenarios() {
+        let old = list(&old_children);
+        let new = list(&new_children);
+        let patches = reconciler.diff(&old, &new);
+        if let Err(e) = check(&old, &new, &patches) {
+            panic!(
+                "reconciler `{}` failed the `{}` scenario: {}",
+                reconciler.name(),
+                scenario,
+                e
+            );
+        }
+    }
+}
+
+/// check the patches of a single scenario
+fn check(
+    old: &ConformanceNode,
+    new: &ConformanceNode,
+    patches: &[ConformancePatch],
+) -> Result<(), String> {
+    patch::validate(old, patches)
+        .map_err(|e| format!("invalid patches: {e}"))?;
+
+    let mut root = old.clone();
+    apply_patches(&mut root, patches);
+    if &root != new {
+        return Err(format!(
+            "the patches did not result to the new list, patches: {patches:#?}"
+        ));
+    }
+
+    let old_keys = keys(old.children().iter());
+    let new_keys = keys(new.children().iter());
+    if has_duplicates(&old_keys) || has_duplicates(&new_keys) {
+        // there is no identity to preserve when the keys are not unique
+        return Ok(());
+    }
+    for patch in patches {
+        if patch.path().path.len() != 1 {
+            continue;
+        }
+        let removed = match &patch.patch_type {
+            PatchType::RemoveNode | PatchType::ReplaceNode { .. } => {
+                old_child(old, patch.path())
+            }
+            _ => None,
+        };
+        if let Some(key) = removed.and_then(key_of) {
+            if new_keys.contains(&Some(key)) {
+                return Err(format!(
+                    "the child with key {key:?} which is in both lists is removed"
+                ));
+            }
+        }
+        let inserted: &[&ConformanceNode] = match &patch.patch_type {
+            PatchType::InsertBeforeNode { nodes }
+            | PatchType::InsertAfterNode { nodes }
+            | PatchType::ReplaceNode { replacement: nodes } => nodes,
+            _ => &[],
+        };
+        for key in inserted.iter().filter_map(|node| key_of(node)) {
+            if old_keys.contains(&Some(key)) {
+                return Err(format!(
+                    "the child with key {key:?} which is in both lists is recreated"
+                ));
+            }
+        }
+    }
+    // the patches at the list itself, such as appending children or replacing the list
+    for patch in patches.iter().filter(|patch| patch.path().is_empty()) {
+        match &patch.patch_type {
+            PatchType::AppendChildren { children } => {
+                for key in children.iter().filter_map(|node| key_of(node)) {
+                    if old_keys.contains(&Some(key)) {
+                        return Err(format!(
+                            "the child with key {key:?} which is in both lists is recreated"
+                        ));
+                    }
+                }
+            }
+            PatchType::ReplaceNode { .. }
+                if old_keys.iter().any(|key| new_keys.contains(key)) =>
+            {
+                return Err(
+                    "the whole list is replaced while it has children which are in both lists"
+                        .to_string(),
+                );
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn old_child<'a>(
+    old: &'a ConformanceNode,
+    path: &TreePath,
+) -> Option<&'a ConformanceNode> {
+    path.indices()
+        .next()
+        .and_then(|idx| old.children().get(idx))
+}
+
+fn key_of(node: &ConformanceNode) -> Option<&String> {
+    node.attribute_value(&KEY)
+        .and_then(|values| values.first().copied())
+}
+
+fn keys<'a>(
+    children: impl Iterator<Item = &'a ConformanceNode>,
+) -> Vec<Option<&'a String>> {
+    children.map(key_of).filter(Option::is_some).collect()
+}
+
+fn has_duplicates(keys: &[Option<&String>]) -> bool {
+    let mut sorted = keys.to_vec();
+    sorted.sort();
+    sorted.windows(2).any(|pair| pair[0] == pair[1])
+}
@@ -0,0 +1,192 @@
+//! building the node trees of the tests from an indented text
+use crate::{element, leaf, Attribute, Node};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// Build a node tree from an indented text, one node per line,
+/// where the children are indented deeper than their parent.
+///
+/// Each element line is a tag followed by its attributes and an optional leaf child:
+/// - `.name` adds `name` to the values of the `class` attribute
+/// - `#name` adds an `id` attribute
+/// - `[name=value]` adds an attribute, `[name]` adds an empty attribute
+/// - `'text'` or `"text"` after a space adds a leaf child before the indented children
+///
+/// A line which is only a quoted text is a leaf.
+///
+/// # Example
+/// ```rust
+/// use mt_dom::{testing::tree_from_str, *};
+///
+/// pub type MyNode =
+///    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+///
+/// let tree: MyNode = tree_from_str(
+///     "main.container
+///        div[key=1] 'line1'
+///        div[key=2]
+///          'line2'",
+/// );
+/// assert_eq!(tree, element("main", vec![attr("class", "container")], vec![
+///     element("div", vec![attr("key", "1")], vec![leaf("line1")]),
+///     element("div", vec![attr("key", "2")], vec![leaf("line2")]),
+/// ]));
+/// ```
+///
+/// # Panics
+/// Panics with the line number when the text is malformed, has more than one root,
+/// or when a leaf has children
+pub fn tree_from_str<'s, Ns, Tag, Leaf, Att, Val>(
+    source: &'s str,
+) -> Node<Ns, Tag, Leaf, Att, Val>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug + From<&'s str>,
+    Leaf: PartialEq + Clone + Debug + From<&'s str>,
+    Att: PartialEq + Eq + Hash + Clone + Debug + From<&'s str>,
+    Val: PartialEq + Clone + Debug + From<&'s str>,
+{
+    // the indentation and the node of the ancestors of the current line
+    let mut stack: Vec<(usize, usize, Node<Ns, Tag, Leaf, Att, Val>)> = vec![];
+    let mut root = None;
+    for (number, line) in source.lines().enumerate().map(|(i, l)| (i + 1, l)) {
+        let content = line.trim_start();
+        if content.is_empty() {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        close_deeper(&mut stack, &mut root, indent);
+        if stack.is_empty() && root.is_some() {
+            panic!("tree_from_str: line {number}: more than one root");
+        }
+        if let Some((_, parent_line, parent)) = stack.last() {
+            if parent.is_leaf() {
+                panic!(
+                    "tree_from_str: line {number}: the leaf at line {parent_line} can not have children"
+                );
+            }
+        }
+        let node = parse_line(content)
+            .unwrap_or_else(|e| panic!("tree_from_str: line {number}: {e}"));
+        stack.push((indent, number, node));
+    }
+    close_deeper(&mut stack, &mut root, 0);
+    root.expect("tree_from_str: the text has no nodes")
+}
+
+/// pop the nodes which are indented at least as deep as `indent`,
+/// adding each of them to the children of its parent
+fn close_deeper<Ns, Tag, Leaf, Att, Val>(
+    stack: &mut Vec<(usize, usize, Node<Ns, Tag, Leaf, Att, Val>)>,
+    root: &mut Option<Node<Ns, Tag, Leaf, Att, Val>>,
+    indent: usize,
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    while stack
+        .last()
+        .is_some_and(|(deepest, _, _)| *deepest >= indent)
+    {
+        let (_, _, node) = stack.pop().expect("must have a node");
+        match stack.last_mut() {
+            Some((_, _, parent)) => parent
+                .add_children([node])
+                .expect("the leaves are checked to not have children"),
+            None => *root = Some(node),
+        }
+    }
+}
+
+/// parse an element or a leaf line without its indentation
+fn parse_line<'s, Ns, Tag, Leaf, Att, Val>(
+    line: &'s str,
+) -> Result<Node<Ns, Tag, Leaf, Att, Val>, &'static str>
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug + From<&'s str>,
+    Leaf: PartialEq + Clone + Debug + From<&'s str>,
+    Att: PartialEq + Eq + Hash + Clone + Debug + From<&'s str>,
+    Val: PartialEq + Clone + Debug + From<&'s str>,
+{
+    if line.starts_with(['\'', '"']) {
+        return Ok(leaf(parse_text(line)?.into()));
+    }
+    let is_delimiter = |c: char| matches!(c, '.' | '#' | '[' | ' ');
+    let end = line.find(is_delimiter).unwrap_or(line.len());
+    let (tag, mut rest) = line.split_at(end);
+    if tag.is_empty() {
+        return Err("expecting a tag");
+    }
+    // the index of the class attribute in `attrs`, which has the values of all the classes
+    let mut class_index = None;
+    let mut attrs: Vec<Attribute<Ns, Att, Val>> = vec![];
+    let mut children = vec![];
+    while let Some(delimiter) = rest.chars().next() {
+        let after = &rest[1..];
+        match delimiter {
+            '.' | '#' => {
+                let end = after.find(is_delimiter).unwrap_or(after.len());
+                if end == 0 {
+                    return Err("expecting a name after `.` or `#`");
+                }
+                if delimiter == '.' {
+                    let index = *class_index.get_or_insert_with(|| {
+                        attrs.push(Attribute::empty(None, "class".into()));
+                        attrs.len() - 1
+                    });
+                    attrs[index].value.push(after[..end].into());
+                } else {
+                    attrs.push(Attribute::new(
+                        None,
+                        "id".into(),
+                        after[..end].into(),
+                    ));
+                }
+                rest = &after[end..];
+            }
+            '[' => {
+                let end = after.find(']').ok_or("unclosed `[`")?;
+                let attribute = &after[..end];
+                attrs.push(match attribute.split_once('=') {
+                    Some((name, value)) => Attribute::new(
+                        None,
+                        name.into(),
+                        parse_value(value).into(),
+                    ),
+                    None => Attribute::empty(None, attribute.into()),
+                });
+                rest = &after[end + 1..];
+            }
+            _ => {
+                let text = rest.trim();
+                if !text.is_empty() {
+                    children.push(leaf(parse_text(text)?.into()));
+                }
+                rest = "";
+            }
+        }
+    }
+    Ok(element(tag.into(), attrs, children))
+}
+
+/// return the text in between the quotes
+fn parse_text(text: &str) -> Result<&str, &'static str> {
+    let quote = match text.chars().next() {
+        Some(quote @ ('\'' | '"')) => quote,
+        _ => return Err("expecting a quoted text"),
+    };
+    text[1..]
+        .strip_suffix(quote)
+        .ok_or("expecting a closing quote")
+}
+
+/// return the value without its quotes, if it is quoted
+fn parse_value(value: &str) -> &str {
+    parse_text(value).unwrap_or(value)
+}
@@ -0,0 +1,116 @@
+//! golden-file snapshots of the patches
+use crate::Patch;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use std::path::PathBuf;
+use std::{env, fs};
+
+/// the environment variable which overwrites the snapshots with the current patches
+pub const UPDATE_SNAPSHOTS: &str = "MT_DOM_UPDATE_SNAPSHOTS";
+
+/// Format the patches into their canonical textual form, one patch per line
+/// with its path and the tag of its target node, the same as `describe_differences`.
+pub fn format_patches<Ns, Tag, Leaf, Att, Val>(
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+) -> String
+where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    patches
+        .iter()
+        .map(|patch| {
+            let tag = patch
+                .tag()
+                .map(|tag| format!(" {tag:?}"))
+                .unwrap_or_default();
+            format!(
+                "at {}{}: {:?}\n",
+                patch.path().display(),
+                tag,
+                patch.patch_type
+            )
+        })
+        .collect()
+}
+
+/// Compare the patches with the golden file `tests/snapshots/{name}.snap` of the crate
+/// being tested, so the behavioral changes of the differ show up as reviewable changes
+/// of the golden files instead of opaque assertion failures.
+///
+/// The golden file is written when it does not exist yet, or when the
+/// `MT_DOM_UPDATE_SNAPSHOTS` environment variable is set, such as with
+/// `MT_DOM_UPDATE_SNAPSHOTS=1 cargo test`.
+///
+/// # Panics
+/// Panics with the changed lines when the patches differ from the golden file,
+/// or when the golden file can not be read or written
+pub fn snapshot_patches<Ns, Tag, Leaf, Att, Val>(
+    name: &str,
+    patches: &[Patch<'_, Ns, Tag, Leaf, Att, Val>],
+) where
+    Ns: PartialEq + Clone + Debug,
+    Tag: PartialEq + Debug,
+    Leaf: PartialEq + Clone + Debug,
+    Att: PartialEq + Eq + Hash + Clone + Debug,
+    Val: PartialEq + Clone + Debug,
+{
+    let dir = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join("tests")
+        .join("snapshots");
+    let file = dir.join(format!("{name}.snap"));
+    let actual = format_patches(patches);
+
+    if file.exists() && env::var_os(UPDATE_SNAPSHOTS).is_none() {
+        let expected = fs::read_to_string(&file).unwrap_or_else(|e| {
+            panic!("can not read the snapshot {}: {e}", file.display())
+        });
+        if expected != actual {
+            panic!(
+                "the patches differ from the snapshot {}, rerun with {}=1 to update it:\n{}",
+                file.display(),
+                UPDATE_SNAPSHOTS,
+                changed_lines(&expected, &actual)
+            );
+        }
+    } else {
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&file, actual))
+            .unwrap_or_else(|e| {
+                panic!("can not write the snapshot {}: {e}", file.display())
+            });
+    }
+}
+
+/// the lines in between the common prefix and suffix of the texts,
+/// prefixed with `-` for the expected lines and `+` for the actual lines
+fn changed_lines(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let prefix = expected
+        .iter()
+        .zip(&actual)
+        .take_while(|(e, a)| e == a)
+        .count();
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(actual[prefix..].iter().rev())
+        .take_while(|(e, a)| e == a)
+        .count();
+    let removed = expected[prefix..expected.len() - suffix]
+        .iter()
+        .map(|line| format!("-{line}\n"));
+    let added = actual[prefix..actual.len() - suffix]
+        .iter()
+        .map(|line| format!("+{line}\n"));
+    removed.chain(added).collect()
+}
@@ -0,0 +1,138 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn item(key: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(key)])
+}
+
+fn list(keys: &[&'static str]) -> MyNode {
+    element("ul", vec![], keys.iter().map(|key| item(key)))
+}
+
+/// move the items between the lists, adopting the items of the `from` list into the `to` list
+fn assert_adopted(
+    from: &MyNode,
+    new_from: &MyNode,
+    to: &MyNode,
+    new_to: &MyNode,
+    adopted: usize,
+) {
+    let trees = [(TreeId(7), from)];
+    let options = DiffOptions {
+        adoptable_trees: &trees,
+        ..Default::default()
+    };
+    let patches = diff_with_options(to, new_to, &"key", &options);
+    let count = patches
+        .iter()
+        .filter(|patch| patch.kind() == PatchKind::AdoptNode)
+        .count();
+    assert_eq!(count, adopted, "patches: {:#?}", patches);
+
+    let mut to_tree = to.clone();
+    let mut from_tree = from.clone();
+    apply_patches_with_trees(
+        &mut to_tree,
+        &patches,
+        &mut [(TreeId(7), &mut from_tree)],
+    );
+    assert_eq!(&to_tree, new_to);
+
+    // the adopted items are already gone from the other list
+    let patches = diff_with_key(&from_tree, new_from, &"key");
+    let mut root = from_tree.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new_from);
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn dragged_into_the_middle() {
+    assert_adopted(
+        &list(&["a", "b", "c"]),
+        &list(&["a", "c"]),
+        &list(&["x", "y", "z"]),
+        &list(&["x", "b", "y", "z"]),
+        1,
+    );
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn dragged_into_the_front_and_the_end() {
+    assert_adopted(
+        &list(&["a", "b", "c"]),
+        &list(&["b"]),
+        &list(&["x", "y"]),
+        &list(&["c", "x", "y", "a"]),
+        2,
+    );
+}
+
+#[test]
+fn dragged_with_new_items_in_between() {
+    assert_adopted(
+        &list(&["a", "b", "c"]),
+        &list(&["b"]),
+        &list(&["x"]),
+        &list(&["x", "a", "new", "c", "other"]),
+        2,
+    );
+}
+
+#[test]
+fn dragged_into_an_empty_list() {
+    assert_adopted(
+        &list(&["a", "b"]),
+        &list(&["a"]),
+        &list(&[]),
+        &list(&["b"]),
+        1,
+    );
+}
+
+#[test]
+fn changed_items_are_created() {
+    let from = list(&["a", "b"]);
+    let to = list(&["x"]);
+    let new_to = element(
+        "ul",
+        vec![],
+        vec![
+            item("x"),
+            element("li", vec![attr("key", "b")], vec![leaf("changed")]),
+        ],
+    );
+    let trees = [(TreeId(7), &from)];
+    let options = DiffOptions {
+        adoptable_trees: &trees,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&to, &new_to, &"key", &options);
+    assert_eq!(patches, diff_with_key(&to, &new_to, &"key"));
+}
+
+#[test]
+fn adoption_needs_the_trees() {
+    let mut root = list(&["x"]);
+    let patches = vec![Patch::adopt_node(
+        Some(&"ul"),
+        TreePath::root(),
+        TreeId(7),
+        TreePath::new([0]),
+        Placement::Append,
+    )];
+    assert_eq!(
+        try_apply_patches(&mut root, &patches),
+        Err(InvalidPatch::UnresolvedAdoption {
+            patch_index: 0,
+            from_tree: TreeId(7),
+            from_path: TreePath::new([0]),
+        })
+    );
+    assert_eq!(root, list(&["x"]));
+}
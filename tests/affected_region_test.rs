@@ -0,0 +1,86 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn no_patches_no_region() {
+    let patches: Vec<Patch<&str, &str, &str, &str, &str>> = vec![];
+    assert!(affected_region(&patches).is_empty());
+}
+
+#[test]
+fn descendants_are_covered_by_their_ancestor() {
+    let li: MyNode = element("li", vec![], vec![]);
+    let patches: Vec<Patch<&str, &str, &str, &str, &str>> = vec![
+        Patch::change_leaf(TreePath::new([0, 1, 0]), &"a", &"b"),
+        Patch::append_children(Some(&"ul"), TreePath::new([0]), vec![&li]),
+        Patch::change_leaf(TreePath::new([1, 0]), &"a", &"b"),
+        Patch::change_leaf(TreePath::new([1, 0]), &"b", &"c"),
+    ];
+    assert_eq!(
+        affected_region(&patches),
+        vec![TreePath::new([0]), TreePath::new([1, 0])]
+    );
+}
+
+#[test]
+fn sibling_changes_affect_the_parent() {
+    let li: MyNode = element("li", vec![], vec![]);
+    let patches: Vec<Patch<&str, &str, &str, &str, &str>> = vec![
+        Patch::insert_before_node(Some(&"li"), TreePath::new([2, 3]), [&li]),
+        Patch::remove_node(Some(&"li"), TreePath::new([4, 0, 1])),
+        Patch::move_after_node(
+            Some(&"li"),
+            TreePath::new([5, 0]),
+            [TreePath::new([5, 2])],
+        ),
+        Patch::replace_node(Some(&"li"), TreePath::new([6, 0]), [&li]),
+        Patch::replace_node(Some(&"li"), TreePath::new([7, 0]), [&li, &li]),
+    ];
+    assert_eq!(
+        affected_region(&patches),
+        vec![
+            TreePath::new([2]),
+            TreePath::new([4, 0]),
+            TreePath::new([5]),
+            TreePath::new([6, 0]),
+            TreePath::new([7]),
+        ]
+    );
+}
+
+#[test]
+fn root_changes_cover_everything() {
+    let old: MyNode =
+        element("main", vec![attr("class", "a")], vec![leaf("x")]);
+    let new: MyNode =
+        element("main", vec![attr("class", "b")], vec![leaf("y")]);
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(affected_region(&patches), vec![TreePath::root()]);
+}
+
+#[test]
+fn aggregate_to_depth_coalesces_deep_regions() {
+    let patches: Vec<Patch<&str, &str, &str, &str, &str>> = vec![
+        Patch::change_leaf(TreePath::new([0, 1, 0, 3]), &"a", &"b"),
+        Patch::change_leaf(TreePath::new([0, 1, 2]), &"a", &"b"),
+        Patch::change_leaf(TreePath::new([0, 2]), &"a", &"b"),
+        Patch::change_leaf(TreePath::new([3]), &"a", &"b"),
+    ];
+    assert_eq!(
+        aggregate_to_depth(&patches, 2),
+        vec![
+            TreePath::new([0, 1]),
+            TreePath::new([0, 2]),
+            TreePath::new([3])
+        ]
+    );
+    assert_eq!(
+        aggregate_to_depth(&patches, 1),
+        vec![TreePath::new([0]), TreePath::new([3])]
+    );
+    assert_eq!(aggregate_to_depth(&patches, 0), vec![TreePath::root()]);
+    assert_eq!(aggregate_to_depth(&patches, 10), affected_region(&patches));
+}
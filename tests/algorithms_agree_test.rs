@@ -0,0 +1,68 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{testing::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn item(key: &'static str, text: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(text)])
+}
+
+#[test]
+fn keyed_reorder() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            item("a", "1"),
+            item("b", "2"),
+            item("c", "3"),
+            item("d", "4"),
+        ],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![attr("class", "list")],
+        vec![
+            item("d", "4"),
+            item("x", "0"),
+            item("b", "two"),
+            item("a", "1"),
+        ],
+    );
+    assert_algorithms_agree(&old, &new, &"key");
+}
+
+#[test]
+fn text_runs_and_wrappers() {
+    let old: MyNode = element(
+        "p",
+        vec![],
+        vec![leaf("hello "), leaf("world"), element("i", vec![], vec![])],
+    );
+    let new: MyNode = element(
+        "p",
+        vec![],
+        vec![
+            leaf("hello world"),
+            element("b", vec![], vec![element("i", vec![], vec![])]),
+        ],
+    );
+    assert_algorithms_agree(&old, &new, &"key");
+}
+
+#[test]
+fn every_strategy_is_named() {
+    let strategies = strategies::<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >();
+    let mut names: Vec<&str> =
+        strategies.iter().map(|(name, _)| *name).collect();
+    names.dedup();
+    assert_eq!(names.len(), strategies.len());
+}
@@ -0,0 +1,457 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn change_leaf<'o>() -> DiffOptions<
+    'o,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+> {
+    DiffOptions {
+        change_leaf: true,
+        ..Default::default()
+    }
+}
+
+fn assert_round_trip(
+    old: &MyNode,
+    new: &MyNode,
+    patches: &[Patch<
+        '_,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >],
+) {
+    let mut root = old.clone();
+    apply_patches(&mut root, patches);
+    assert_eq!(&root, new);
+}
+
+#[test]
+fn change_leaf_patch() {
+    let old: MyNode = element("p", vec![], vec![leaf("hello")]);
+    let new: MyNode = element("p", vec![], vec![leaf("world")]);
+
+    let diff = diff_with_options(&old, &new, &"key", &change_leaf());
+    assert_eq!(
+        diff,
+        vec![Patch::change_leaf(
+            TreePath::new(vec![0]),
+            &"hello",
+            &"world"
+        )]
+    );
+    assert_round_trip(&old, &new, &diff);
+}
+
+#[test]
+fn leaf_root_changed() {
+    let old: MyNode = leaf("hello");
+    let new: MyNode = leaf("world");
+
+    let diff = diff_with_options(&old, &new, &"key", &change_leaf());
+    assert_eq!(
+        diff,
+        vec![Patch::change_leaf(TreePath::root(), &"hello", &"world")]
+    );
+    assert_round_trip(&old, &new, &diff);
+}
+
+#[test]
+fn attributes_and_children() {
+    let old: MyNode = element(
+        "main",
+        vec![attr("class", "container"), attr("id", "main")],
+        vec![
+            element("div", vec![], vec![leaf("line1")]),
+            element("div", vec![], vec![leaf("line2")]),
+            element("div", vec![], vec![leaf("line3")]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![attr("class", "container"), attr("class", "dark")],
+        vec![
+            element("div", vec![], vec![leaf("line1")]),
+            element("span", vec![], vec![leaf("line2")]),
+        ],
+    );
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_round_trip(&old, &new, &diff);
+
+    let diff = diff_with_key(&new, &old, &"key");
+    assert_round_trip(&new, &old, &diff);
+}
+
+#[test]
+fn keyed_insert_and_remove() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element("li", vec![attr("key", "1")], vec![leaf("item1")]),
+            element("li", vec![attr("key", "2")], vec![leaf("item2")]),
+            element("li", vec![attr("key", "3")], vec![leaf("item3")]),
+        ],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element("li", vec![attr("key", "0")], vec![leaf("item0")]),
+            element("li", vec![attr("key", "1")], vec![leaf("item1")]),
+            element("li", vec![attr("key", "3")], vec![leaf("item3")]),
+            element("li", vec![attr("key", "4")], vec![leaf("item4")]),
+        ],
+    );
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_round_trip(&old, &new, &diff);
+}
+
+#[test]
+fn wrap_and_unwrap() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("div", vec![], vec![leaf("line1")]),
+            element("div", vec![], vec![leaf("line2")]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![element(
+            "section",
+            vec![],
+            vec![
+                element("div", vec![], vec![leaf("line1")]),
+                element("div", vec![], vec![leaf("line2")]),
+            ],
+        )],
+    );
+    let options = DiffOptions {
+        detect_wrapping: true,
+        ..Default::default()
+    };
+
+    let diff = diff_with_options(&old, &new, &"key", &options);
+    assert_round_trip(&old, &new, &diff);
+
+    let diff = diff_with_options(&new, &old, &"key", &options);
+    assert_round_trip(&new, &old, &diff);
+}
+
+#[test]
+fn text_split_into_2_leaves() {
+    let old: MyNode = element(
+        "p",
+        vec![],
+        vec![
+            leaf("hello world"),
+            element("br", vec![], vec![]),
+            leaf("bye"),
+        ],
+    );
+    let new: MyNode = element(
+        "p",
+        vec![],
+        vec![
+            leaf("hello "),
+            leaf("world"),
+            element("br", vec![], vec![]),
+            leaf("bye!"),
+        ],
+    );
+    let options = DiffOptions {
+        merge_text_runs: true,
+        change_leaf: true,
+        ..Default::default()
+    };
+
+    let diff = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        diff,
+        vec![
+            Patch::change_leaf_run(
+                TreePath::new(vec![0]),
+                1,
+                vec![&"hello ", &"world"]
+            ),
+            Patch::change_leaf(TreePath::new(vec![2]), &"bye", &"bye!"),
+        ]
+    );
+    assert_round_trip(&old, &new, &diff);
+
+    let diff = diff_with_options(&new, &old, &"key", &options);
+    assert_eq!(
+        diff,
+        vec![
+            Patch::change_leaf_run(
+                TreePath::new(vec![0]),
+                2,
+                vec![&"hello world"]
+            ),
+            Patch::change_leaf(TreePath::new(vec![3]), &"bye!", &"bye"),
+        ]
+    );
+    assert_round_trip(&new, &old, &diff);
+}
+
+#[test]
+fn keyed_fragment_root() {
+    let old: MyNode = fragment(vec![
+        element("div", vec![attr("key", "1")], vec![leaf("line1")]),
+        element("div", vec![attr("key", "2")], vec![leaf("line2")]),
+        element("div", vec![attr("key", "3")], vec![leaf("line3")]),
+    ]);
+    let new: MyNode = fragment(vec![
+        element("div", vec![attr("key", "0")], vec![leaf("line0")]),
+        element("div", vec![attr("key", "3")], vec![leaf("line3")]),
+        element("div", vec![attr("key", "1")], vec![leaf("changed")]),
+    ]);
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_round_trip(&old, &new, &diff);
+}
+
+#[test]
+fn non_keyed_fragment_root() {
+    let old: MyNode = fragment(vec![
+        element("p", vec![], vec![leaf("hello")]),
+        leaf("world"),
+    ]);
+    let new: MyNode = fragment(vec![
+        element("p", vec![attr("class", "x")], vec![leaf("hi")]),
+        leaf("world"),
+        element("span", vec![], vec![]),
+    ]);
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_round_trip(&old, &new, &diff);
+}
+
+#[test]
+fn node_list_in_fragment() {
+    let old: MyNode =
+        fragment(vec![node_list(vec![leaf("a"), leaf("b")]), leaf("c")]);
+    let new: MyNode = fragment(vec![
+        node_list(vec![leaf("a"), leaf("b"), leaf("x")]),
+        leaf("c"),
+    ]);
+    let new_leaf = leaf("x");
+    let patches = vec![Patch::append_children(
+        None,
+        TreePath::new([0]),
+        vec![&new_leaf],
+    )];
+    assert_eq!(validate(&old, &patches), Ok(()));
+    assert_round_trip(&old, &new, &patches);
+    assert_eq!(
+        TreePath::new([0, 1]).find_node_by_path(&new),
+        Some(&leaf("b"))
+    );
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn keyed_moves() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element("li", vec![attr("key", "1")], vec![leaf("item1")]),
+            element("li", vec![attr("key", "2")], vec![leaf("item2")]),
+            element("li", vec![attr("key", "3")], vec![leaf("item3")]),
+            element("li", vec![attr("key", "4")], vec![leaf("item4")]),
+            element("li", vec![attr("key", "5")], vec![leaf("item5")]),
+        ],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element("li", vec![attr("key", "5")], vec![leaf("item5")]),
+            element("li", vec![attr("key", "2")], vec![leaf("item2")]),
+            element("li", vec![attr("key", "0")], vec![leaf("item0")]),
+            element("li", vec![attr("key", "3")], vec![leaf("item3")]),
+            element("li", vec![attr("key", "1")], vec![leaf("item1")]),
+        ],
+    );
+
+    let diff = diff_with_key(&old, &new, &"key");
+    let kinds: Vec<PatchKind> = diff.iter().map(|patch| patch.kind()).collect();
+    assert!(kinds.iter().any(|kind| matches!(
+        kind,
+        PatchKind::MoveBeforeNode | PatchKind::MoveAfterNode
+    )));
+    assert_round_trip(&old, &new, &diff);
+}
+
+#[test]
+fn changed_attributes_keep_their_order() {
+    let old: MyNode = element(
+        "div",
+        vec![attr("class", "a"), attr("id", "b"), attr("title", "c")],
+        vec![],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![
+            attr("class", "x"),
+            attr("id", "b"),
+            attr("title", "c"),
+            attr("lang", "en"),
+        ],
+        vec![],
+    );
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_round_trip(&old, &new, &diff);
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn insert_before_a_replaced_node() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![element("li", vec![attr("key", "5")], vec![leaf("text0")])],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element("li", vec![attr("key", "4")], vec![leaf("text0")]),
+            element("span", vec![attr("key", "5")], vec![leaf("text1")]),
+        ],
+    );
+    let span: MyNode =
+        element("span", vec![attr("key", "5")], vec![leaf("text1")]);
+    let li: MyNode = element("li", vec![attr("key", "4")], vec![leaf("text0")]);
+
+    // the nodes are inserted before the node which replaced the `li`
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        diff,
+        vec![
+            Patch::replace_node(Some(&"li"), TreePath::new([0]), vec![&span]),
+            Patch::insert_before_node(
+                Some(&"li"),
+                TreePath::new([0]),
+                vec![&li]
+            ),
+        ]
+    );
+    assert_round_trip(&old, &new, &diff);
+
+    let mut root = old.clone();
+    compile(&diff).apply(&mut root);
+    assert_eq!(root, new);
+
+    let mut root = old.clone();
+    for patch in remap_paths(&old, &diff) {
+        apply_patches(&mut root, &[patch]);
+    }
+    assert_eq!(root, new);
+
+    let owned: Vec<_> = diff.into_iter().map(Patch::into_owned).collect();
+    assert_eq!(Node::from_patches(Some(old), &owned), new);
+}
+
+#[test]
+#[should_panic(expected = "which is removed by a previous patch")]
+fn insert_before_a_removed_node() {
+    let mut root: MyNode = element("ul", vec![], vec![leaf("a")]);
+    apply_patches(
+        &mut root,
+        &[
+            Patch::remove_node(None, TreePath::new([0])),
+            Patch::insert_before_node(
+                None,
+                TreePath::new([0]),
+                vec![&leaf("b")],
+            ),
+        ],
+    );
+}
+
+/// a keyed `ul` of the items, each with its tag, key and text
+fn keyed_items(items: &[(&'static str, &'static str, &'static str)]) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        items.iter().map(|(tag, key, text)| {
+            element(*tag, vec![attr("key", *key)], vec![leaf(*text)])
+        }),
+    )
+}
+
+#[test]
+fn keyed_siblings_of_mixed_tags() {
+    let fixtures = [
+        // the li and the span share the key 5
+        (
+            vec![("li", "5", "text0")],
+            vec![("li", "4", "text0"), ("span", "5", "text1")],
+        ),
+        (
+            vec![("li", "1", "a"), ("span", "2", "b"), ("li", "3", "c")],
+            vec![("span", "3", "c"), ("li", "2", "b"), ("li", "1", "a")],
+        ),
+        (
+            vec![
+                ("li", "1", "a"),
+                ("li", "2", "b"),
+                ("li", "3", "c"),
+                ("li", "4", "d"),
+            ],
+            vec![
+                ("span", "4", "d"),
+                ("li", "1", "a"),
+                ("p", "5", "e"),
+                ("span", "2", "b"),
+            ],
+        ),
+        (
+            vec![("span", "1", "a"), ("li", "2", "b")],
+            vec![
+                ("li", "0", "z"),
+                ("li", "1", "a"),
+                ("span", "2", "b"),
+                ("p", "3", "c"),
+            ],
+        ),
+        (
+            vec![("li", "1", "a"), ("span", "2", "b"), ("p", "3", "c")],
+            vec![("p", "2", "b")],
+        ),
+    ];
+    for (old, new) in fixtures.iter() {
+        let old = keyed_items(old);
+        let new = keyed_items(new);
+        let diff = diff_with_key(&old, &new, &"key");
+        assert_round_trip(&old, &new, &diff);
+
+        let mut root = old.clone();
+        compile(&diff).apply(&mut root);
+        assert_eq!(root, new);
+
+        let mut root = old.clone();
+        for patch in remap_paths(&old, &diff) {
+            apply_patches(&mut root, &[patch]);
+        }
+        assert_eq!(root, new);
+    }
+}
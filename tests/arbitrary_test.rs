@@ -0,0 +1,290 @@
+#![deny(warnings)]
+#![cfg(feature = "testing")]
+use mt_dom::testing::{arbitrary::*, strategies};
+use mt_dom::{diff::*, patch::*, *};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn random_trees_round_trip(old in arb_tree(), new in arb_tree()) {
+        prop_assert!(algorithms_agree(&old, &new));
+    }
+
+    #[test]
+    fn random_keyed_lists_round_trip(old in arb_keyed_list(), new in arb_keyed_list()) {
+        prop_assert!(algorithms_agree(&old, &new));
+        let patches = diff_with_key(&old, &new, &KEY);
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_hashed_keys_match_as_scanned_keys(old in arb_keyed_list(), new in arb_keyed_list()) {
+        let scanned = DiffOptions {
+            scan_keys: true,
+            ..Default::default()
+        };
+        prop_assert_eq!(
+            diff_with_key(&old, &new, &KEY),
+            diff_with_options(&old, &new, &KEY, &scanned)
+        );
+    }
+
+    #[test]
+    fn random_max_depth_round_trip(
+        old in arb_tree(),
+        new in arb_tree(),
+        max_depth in 0..5usize,
+    ) {
+        let options = DiffOptions {
+            max_depth: Some(max_depth),
+            ..Default::default()
+        };
+        let patches = diff_with_options(&old, &new, &KEY, &options);
+        prop_assert!(validate(&old, &patches).is_ok());
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_attributes_keep_their_order(
+        old in arb_attributes(),
+        new in arb_attributes(),
+        update_attributes in any::<bool>(),
+    ) {
+        let old = element("div", old, []);
+        let new = element("div", new, []);
+        let options = DiffOptions {
+            update_attributes,
+            ..Default::default()
+        };
+        let patches = diff_with_options(&old, &new, &KEY, &options);
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+
+        // the kept attributes are in their old place with their new values,
+        // followed by the added attributes in their new order
+        let new_attrs = new.attributes().unwrap();
+        let old_attrs = old.attributes().unwrap();
+        let kept = old_attrs.iter().filter_map(|old_att| {
+            new_attrs.iter().find(|new_att| new_att.name == old_att.name)
+        });
+        let added = new_attrs
+            .iter()
+            .filter(|new_att| old_attrs.iter().all(|old_att| old_att.name != new_att.name));
+        let expected: Vec<_> = kept.chain(added).cloned().collect();
+        prop_assert_eq!(root.attributes().unwrap(), &expected[..]);
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_cross_parent_moves_round_trip(old in arb_tree(), new in arb_tree()) {
+        let options = DiffOptions {
+            cross_parent_moves: Some(MoveScope::Anywhere),
+            ..Default::default()
+        };
+        let patches = diff_with_options(&old, &new, &KEY, &options);
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_sorted_patches_round_trip(
+        old in arb_tree(),
+        new in arb_tree(),
+        flags in any::<[bool; 5]>(),
+    ) {
+        let options = DiffOptions {
+            detect_wrapping: flags[0],
+            change_leaf: flags[1],
+            merge_text_runs: flags[2],
+            detect_moves: flags[3],
+            update_attributes: flags[4],
+            ..Default::default()
+        };
+        let mut patches = diff_with_options(&old, &new, &KEY, &options);
+        sort_patches_for_application(&mut patches);
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_similar_children_round_trip(
+        old in arb_tree(),
+        new in arb_tree(),
+        threshold in 0..=100u8,
+        flags in any::<[bool; 5]>(),
+    ) {
+        let options = DiffOptions {
+            match_similar: Some(threshold),
+            change_leaf: flags[0],
+            keyed_tag_change: flags[1],
+            detect_moves: flags[2],
+            cross_parent_moves: flags[3].then_some(MoveScope::Anywhere),
+            ..Default::default()
+        };
+        let options = if flags[4] {
+            options.with_leaf_similarity()
+        } else {
+            options
+        };
+        let patches = diff_with_options(&old, &new, &KEY, &options);
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_key_index_is_updated(
+        old in arb_tree(),
+        new in arb_tree(),
+        update_attributes in any::<bool>(),
+        for_each_child in any::<bool>(),
+        cross_parent_moves in any::<bool>(),
+        detect_moves in any::<bool>(),
+    ) {
+        let options = DiffOptions {
+            update_attributes,
+            for_each_child,
+            cross_parent_moves: cross_parent_moves.then_some(MoveScope::Anywhere),
+            detect_moves,
+            ..Default::default()
+        };
+        let patches = diff_with_options(&old, &new, &KEY, &options);
+        let mut root = old.clone();
+        let mut index = apply_patches::KeyIndex::new(&old, KEY);
+        apply_patches_with_index(&mut root, &patches, &mut index);
+        prop_assert_eq!(index, apply_patches::KeyIndex::new(&new, KEY));
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_equality_checks_agree(
+        old in arb_tree(),
+        new in arb_tree(),
+        check in prop::sample::select(vec![
+            EqualityCheck::AfterReplace,
+            EqualityCheck::BeforeReplace,
+            EqualityCheck::Never,
+        ]),
+    ) {
+        let options = DiffOptions {
+            equality_check: check,
+            ..Default::default()
+        };
+        let patches = diff_with_options(&old, &new, &KEY, &options);
+        prop_assert_eq!(&patches, &diff_with_key(&old, &new, &KEY));
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_remapped_patches_apply_sequentially(old in arb_tree(), new in arb_tree()) {
+        let patches = diff_with_key(&old, &new, &KEY);
+        let mut root = old.clone();
+        for patch in remap_paths(&old, &patches).iter() {
+            apply_patches(&mut root, core::slice::from_ref(patch));
+        }
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_leaf_splice_rebuilds_the_new_units(
+        old in prop::collection::vec(0..3u8, 0..8),
+        new in prop::collection::vec(0..3u8, 0..8),
+    ) {
+        match LeafSplice::between(&old, &new) {
+            Some(splice) => {
+                let mut spliced = old.clone();
+                spliced.splice(
+                    splice.old_start..splice.old_start + splice.old_len,
+                    new[splice.new_start..splice.new_start + splice.new_len].iter().copied(),
+                );
+                prop_assert_eq!(spliced, new);
+            }
+            None => prop_assert_eq!(old, new),
+        }
+    }
+
+    #[test]
+    fn random_minimal_splices_rebuild_the_new_units(
+        old in prop::collection::vec(0..3u8, 0..12),
+        new in prop::collection::vec(0..3u8, 0..12),
+        max_edits in 0..30usize,
+    ) {
+        let splices = LeafSplice::minimal(&old, &new, max_edits);
+        let mut spliced = old.clone();
+        for splice in splices.iter().rev() {
+            spliced.splice(
+                splice.old_start..splice.old_start + splice.old_len,
+                new[splice.new_start..splice.new_start + splice.new_len].iter().copied(),
+            );
+        }
+        prop_assert_eq!(spliced, new);
+        // the splices are separated by at least one kept unit
+        let separated = splices.windows(2).all(|pair| {
+            pair[0].old_start + pair[0].old_len < pair[1].old_start
+                && pair[0].new_start + pair[0].new_len < pair[1].new_start
+        });
+        prop_assert!(separated);
+    }
+
+    #[test]
+    fn random_trees_sync_the_changed_subtrees(old in arb_tree(), new in arb_tree()) {
+        let paths = merkle::changed_paths(&merkle::hash_tree(&old), &merkle::hash_tree(&new));
+        let mut synced = old.clone();
+        prop_assert!(sync::integrate(&mut synced, sync::respond(&new, &paths)).is_ok());
+        prop_assert_eq!(synced, new);
+    }
+
+    #[test]
+    fn random_slotted_elements_round_trip(old in arb_slotted(), new in arb_slotted()) {
+        let patches = diff_with_key(&old, &new, &KEY);
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        prop_assert_eq!(sort_attributes(&root), sort_attributes(&new));
+    }
+
+    #[test]
+    fn random_stepwise_diffs_agree(
+        old in arb_tree(),
+        new in arb_tree(),
+        max_nodes in 1..8usize,
+    ) {
+        let options = DiffOptions::default();
+        let mut stepper = DiffStepper::new(&old, &new, KEY, &options);
+        while !stepper.step(max_nodes).done {}
+        prop_assert_eq!(stepper.finish(), diff_with_key(&old, &new, &KEY));
+    }
+}
+
+/// the patches of every registered diffing strategy turn `old` into `new`,
+/// up to the order of the attributes
+fn algorithms_agree(old: &ArbitraryNode, new: &ArbitraryNode) -> bool {
+    strategies().into_iter().all(|(_, options)| {
+        let patches = diff_with_options(old, new, &KEY, &options);
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        validate(old, &patches).is_ok()
+            && sort_attributes(&root) == sort_attributes(new)
+    })
+}
+
+/// generate an element whose children are in a random subset of the slots, in a random order
+fn arb_slotted() -> impl Strategy<Value = ArbitraryNode> {
+    let slots = vec!["north", "south", "east", "west"];
+    prop::sample::subsequence(slots, 0..=4)
+        .prop_shuffle()
+        .prop_flat_map(|slots| {
+            let len = slots.len();
+            (Just(slots), prop::collection::vec(arb_tree(), len))
+        })
+        .prop_map(|(slots, children)| {
+            slotted_element("split", [], slots.into_iter().zip(children))
+        })
+}
@@ -0,0 +1,49 @@
+#![deny(warnings)]
+use mt_dom::{assert_nodes_eq, testing::describe_differences, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn table(rows: usize, highlighted: usize) -> MyNode {
+    element(
+        "table",
+        vec![],
+        (0..rows).map(|row| {
+            let class = if row == highlighted {
+                "highlight"
+            } else {
+                "row"
+            };
+            element("tr", vec![attr("class", class)], vec![leaf("cell")])
+        }),
+    )
+}
+
+#[test]
+fn equal_nodes() {
+    assert_nodes_eq!(table(100, 3), table(100, 3));
+    assert_nodes_eq!(table(100, 3), table(100, 3), "with {} rows", 100);
+}
+
+#[test]
+fn describes_only_the_differences() {
+    let description = describe_differences(&table(100, 3), &table(100, 4));
+    assert_eq!(
+        description,
+        "2 patch(es) turns the left node into the right node:\n  \
+         at [3] \"tr\": AddAttributes { attrs: [Attribute { namespace: None, name: \"class\", value: [\"row\"] }] }\n  \
+         at [4] \"tr\": AddAttributes { attrs: [Attribute { namespace: None, name: \"class\", value: [\"highlight\"] }] }"
+    );
+}
+
+#[test]
+#[should_panic(expected = "at [4] \"tr\": AddAttributes")]
+fn panics_with_the_differences() {
+    assert_nodes_eq!(table(100, 3), table(100, 4));
+}
+
+#[test]
+#[should_panic(expected = "failed: rendering the table, 2 patch(es)")]
+fn panics_with_the_message() {
+    assert_nodes_eq!(table(100, 3), table(100, 4), "rendering the {}", "table");
+}
@@ -0,0 +1,116 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+type MyAttribute = Attribute<&'static str, &'static str, &'static str>;
+
+/// the properties of a style such as `color:red;width:1px`
+fn properties(style: &'static str) -> Vec<(&'static str, &'static str)> {
+    style
+        .split(';')
+        .filter_map(|property| property.split_once(':'))
+        .collect()
+}
+
+/// only the changed properties of the style are patched
+fn style_differ(
+    old: &MyAttribute,
+    new: &MyAttribute,
+) -> Option<Vec<AttributePart<&'static str>>> {
+    if new.name != "style" {
+        return None;
+    }
+    let old = properties(old.value()[0]);
+    let new = properties(new.value()[0]);
+    let mut parts: Vec<_> = new
+        .iter()
+        .filter(|property| !old.contains(property))
+        .map(|&(key, value)| AttributePart::Set { key, value })
+        .collect();
+    parts.extend(
+        old.iter()
+            .filter(|(key, _)| new.iter().all(|(new_key, _)| new_key != key))
+            .map(|&(key, _)| AttributePart::Remove { key }),
+    );
+    Some(parts)
+}
+
+#[test]
+fn changed_style_properties_are_patched() {
+    let old: MyNode = element(
+        "div",
+        vec![
+            attr("class", "old"),
+            attr("style", "color:red;width:1px;height:2px"),
+        ],
+        vec![],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![attr("class", "new"), attr("style", "color:blue;width:1px")],
+        vec![],
+    );
+
+    let diff = diff_with_attr_differ(&old, &new, &"key", &style_differ);
+    let new_style = attr("style", "color:blue;width:1px");
+    assert_eq!(
+        diff,
+        vec![
+            Patch::add_attributes(
+                &"div",
+                TreePath::root(),
+                vec![&attr("class", "new")]
+            ),
+            Patch::update_attribute_parts(
+                &"div",
+                TreePath::root(),
+                &new_style,
+                [
+                    AttributePart::Set {
+                        key: "color",
+                        value: "blue"
+                    },
+                    AttributePart::Remove { key: "height" },
+                ]
+            ),
+        ]
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &diff);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn added_attributes_are_not_split_into_parts() {
+    let old: MyNode = element("div", vec![], vec![]);
+    let new: MyNode = element("div", vec![attr("style", "color:red")], vec![]);
+
+    let diff = diff_with_attr_differ(&old, &new, &"key", &style_differ);
+    assert_eq!(
+        diff,
+        vec![Patch::add_attributes(
+            &"div",
+            TreePath::root(),
+            vec![&attr("style", "color:red")]
+        )]
+    );
+}
+
+#[test]
+fn parts_are_owned() {
+    let old: MyNode = element("div", vec![attr("style", "color:red")], vec![]);
+    let new: MyNode = element("div", vec![attr("style", "color:blue")], vec![]);
+
+    let options = DiffOptions {
+        attr_differ: Some(&style_differ),
+        ..Default::default()
+    };
+    let diff = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(diff[0].kind(), PatchKind::UpdateAttributeParts);
+    let owned = diff[0].clone().into_owned();
+    assert_eq!(owned.as_patch(), diff[0]);
+}
@@ -0,0 +1,84 @@
+#![deny(warnings)]
+use mt_dom::{
+    diff::{attributes::*, *},
+    patch::*,
+    *,
+};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn elements() -> (MyNode, MyNode) {
+    let old = element(
+        "div",
+        vec![attr("class", "a"), attr("id", "x"), attr("title", "same")],
+        vec![leaf("old")],
+    );
+    let new = element(
+        "div",
+        vec![attr("class", "b"), attr("title", "same")],
+        vec![leaf("new")],
+    );
+    (old, new)
+}
+
+#[test]
+fn the_attributes_are_diffed_the_same_as_the_differ() {
+    let (old, new) = elements();
+    let path = TreePath::new([2, 1]);
+    let patches = create_attribute_patches(
+        old.element_ref().unwrap(),
+        new.element_ref().unwrap(),
+        &path,
+    );
+    let class = attr("class", "b");
+    let id = attr("id", "x");
+    assert_eq!(
+        patches,
+        vec![
+            Patch::add_attributes(&"div", path.clone(), vec![&class]),
+            Patch::remove_attributes(&"div", path, vec![&id]),
+        ]
+    );
+
+    // the differ emits the same patches, followed by the patches of the children
+    let diffed = diff_with_key(&old, &new, &"key");
+    assert_eq!(diffed.len(), 3);
+    assert_eq!(
+        diffed[..2],
+        create_attribute_patches(
+            old.element_ref().unwrap(),
+            new.element_ref().unwrap(),
+            &TreePath::root(),
+        )
+    );
+}
+
+#[test]
+fn the_options_of_the_differ_are_used() {
+    let (old, new) = elements();
+    let options = DiffOptions {
+        update_attributes: true,
+        ..Default::default()
+    };
+    let patches = create_attribute_patches_with_options(
+        old.element_ref().unwrap(),
+        new.element_ref().unwrap(),
+        &TreePath::root(),
+        &options,
+    );
+    assert_eq!(
+        patches.iter().map(Patch::kind).collect::<Vec<_>>(),
+        [PatchKind::UpdateAttributes]
+    );
+}
+
+#[test]
+fn the_same_attributes_have_no_patches() {
+    let (old, _) = elements();
+    let element = old.element_ref().unwrap();
+    assert!(
+        create_attribute_patches(element, element, &TreePath::root())
+            .is_empty()
+    );
+}
@@ -0,0 +1,82 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn iterates_the_values_of_the_repeated_attributes() {
+    let node: MyNode = element(
+        "div",
+        vec![attr("class", "a"), attr("id", "x"), attr("class", "b")],
+        vec![],
+    );
+    assert_eq!(
+        node.attribute_values_iter(&"class").collect::<Vec<_>>(),
+        node.attribute_value(&"class").expect("has class")
+    );
+    assert_eq!(node.attribute_values_iter(&"title").count(), 0);
+    assert_eq!(
+        leaf::<&str, &str, &str, &str, &str>("text")
+            .attribute_values_iter(&"class")
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn slice_is_only_returned_for_a_single_attribute() {
+    let node: MyNode = element(
+        "div",
+        vec![
+            Attribute::with_multiple_values(None, "key", ["1", "2"]),
+            attr("class", "a"),
+            attr("class", "b"),
+        ],
+        vec![],
+    );
+    assert_eq!(node.attribute_values_slice(&"key"), Some(&["1", "2"][..]));
+    assert_eq!(node.attribute_values_slice(&"class"), None);
+    assert_eq!(node.attribute_values_slice(&"title"), None);
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn keys_from_repeated_attributes_are_still_matched() {
+    let item = |key: &'static str, text: &'static str| -> MyNode {
+        element(
+            "li",
+            vec![attr("key", key), attr("class", "row"), attr("key", "x")],
+            vec![leaf(text)],
+        )
+    };
+    let old: MyNode =
+        element("ul", vec![], vec![item("1", "one"), item("2", "two")]);
+    let new: MyNode =
+        element("ul", vec![], vec![item("2", "two"), item("1", "one")]);
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(patches.len(), 1);
+    assert!(matches!(
+        patches[0].patch_type,
+        PatchType::MoveBeforeNode { .. } | PatchType::MoveAfterNode { .. }
+    ));
+}
+
+#[test]
+fn numeric_keys_use_the_borrowed_values() {
+    let list = |keys: &[&'static str]| -> MyNode {
+        element(
+            "ul",
+            vec![],
+            keys.iter()
+                .map(|key| element("li", vec![attr("key", *key)], vec![])),
+        )
+    };
+    let old = list(&["1", "2", "3"]);
+    let new = list(&["3", "1", "2"]);
+    let options = DiffOptions::default().with_numeric_keys();
+    assert_eq!(
+        diff_with_options(&old, &new, &"key", &options),
+        diff_with_key(&old, &new, &"key")
+    );
+}
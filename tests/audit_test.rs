@@ -0,0 +1,162 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+type MyRule<'r> = AuditRule<
+    'r,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+>;
+
+fn form(fields: &[&'static str]) -> MyNode {
+    element(
+        "form",
+        vec![],
+        vec![element(
+            "fieldset",
+            vec![],
+            fields.iter().map(|name| {
+                element("input", vec![attr("name", *name)], vec![])
+            }),
+        )],
+    )
+}
+
+#[test]
+fn removing_the_focused_element_is_reported() {
+    let old = form(&["first", "second"]);
+    let new = form(&["first"]);
+
+    let patches = diff_with_key(&old, &new, &"key");
+    let findings =
+        audit(&patches, &[MyRule::FocusedRemoval(TreePath::new([0, 1]))]);
+    assert_eq!(
+        findings,
+        vec![AuditFinding {
+            rule: "focused_removal",
+            patch_index: 0,
+            path: TreePath::new([0, 1]),
+            message: "the focused element is removed".to_string(),
+        }]
+    );
+    assert_eq!(
+        findings[0].to_string(),
+        "focused_removal at [0,1] (patch 0): the focused element is removed"
+    );
+
+    // the other element stays focused
+    let findings =
+        audit(&patches, &[MyRule::FocusedRemoval(TreePath::new([0, 0]))]);
+    assert!(findings.is_empty());
+}
+
+#[test]
+fn replacing_an_ancestor_of_the_focused_element_is_reported() {
+    let old = form(&["first", "second"]);
+    let new: MyNode =
+        element("form", vec![], vec![element("div", vec![], vec![])]);
+
+    let patches = diff_with_key(&old, &new, &"key");
+    let findings =
+        audit(&patches, &[MyRule::FocusedRemoval(TreePath::new([0, 1]))]);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(
+        findings[0].message,
+        "the ancestor [0] of the focused element is replaced"
+    );
+}
+
+#[test]
+fn live_region_churn_is_reported() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element(
+                "ul",
+                vec![attr("aria-live", "polite")],
+                (0..5).map(|_| leaf("old")),
+            ),
+            element("p", vec![], vec![leaf("old")]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element(
+                "ul",
+                vec![attr("aria-live", "polite")],
+                (0..5).map(|_| leaf("new")),
+            ),
+            element("p", vec![], vec![leaf("new")]),
+        ],
+    );
+
+    let patches = diff_with_key(&old, &new, &"key");
+    let regions = [TreePath::new([0])];
+    let findings = audit(
+        &patches,
+        &[MyRule::LiveRegionChurn {
+            regions: &regions,
+            max_patches: 3,
+        }],
+    );
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "live_region_churn");
+    assert_eq!(findings[0].path, TreePath::new([0]));
+    assert_eq!(findings[0].patch_index, 3);
+
+    let findings = audit(
+        &patches,
+        &[MyRule::LiveRegionChurn {
+            regions: &regions,
+            max_patches: 5,
+        }],
+    );
+    assert!(findings.is_empty());
+}
+
+#[test]
+fn custom_rules_are_checked() {
+    let old = form(&["first"]);
+    let new = form(&[]);
+
+    // every removal is reported
+    let no_removal = |patches: &[Patch<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >]| {
+        patches
+            .iter()
+            .enumerate()
+            .filter(|(_, patch)| patch.kind() == PatchKind::RemoveNode)
+            .map(|(patch_index, patch)| AuditFinding {
+                rule: "no_removal",
+                patch_index,
+                path: patch.path().clone(),
+                message: "a node is removed".to_string(),
+            })
+            .collect()
+    };
+    let patches = diff_with_key(&old, &new, &"key");
+    let findings = audit(
+        &patches,
+        &[
+            MyRule::FocusedRemoval(TreePath::new([0, 0])),
+            MyRule::Custom(&no_removal),
+        ],
+    );
+    assert_eq!(
+        findings.iter().map(|f| f.rule).collect::<Vec<_>>(),
+        ["focused_removal", "no_removal"]
+    );
+}
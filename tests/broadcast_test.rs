@@ -0,0 +1,92 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn page(theme: Option<&'static str>) -> MyNode {
+    let themed = |tag: &'static str, children: Vec<MyNode>| -> MyNode {
+        let attrs = theme.map(|theme| attr("theme", theme)).into_iter();
+        element(tag, attrs, children)
+    };
+    element(
+        "main",
+        vec![attr("theme", "none")],
+        vec![
+            themed("header", vec![leaf("title")]),
+            element(
+                "section",
+                vec![],
+                vec![
+                    themed("button", vec![]),
+                    leaf("text"),
+                    themed("button", vec![]),
+                ],
+            ),
+        ],
+    )
+}
+
+#[test]
+fn broadcast_to_the_selected_nodes() {
+    let old = page(Some("light"));
+    let new = page(Some("dark"));
+    let dark = attr("theme", "dark");
+    let light = "light";
+    let patches = vec![Patch::broadcast(
+        old.tag(),
+        TreePath::root(),
+        Selector::AttributeValue(&"theme", &light),
+        PatchType::AddAttributes { attrs: vec![&dark] },
+    )];
+    assert!(validate(&old, &patches).is_ok());
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+    assert_eq!(PatchBatchSummary::from(patches.as_slice()).attr, 1);
+}
+
+#[test]
+fn broadcast_within_a_subtree() {
+    let old = page(None);
+    let blue = attr("color", "blue");
+    let patches = vec![Patch::broadcast(
+        Some(&"section"),
+        TreePath::new([1]),
+        Selector::Tag(&"button"),
+        PatchType::AddAttributes { attrs: vec![&blue] },
+    )];
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    let button = element("button", vec![attr("color", "blue")], vec![]);
+    assert_eq!(
+        root,
+        element(
+            "main",
+            vec![attr("theme", "none")],
+            vec![
+                element("header", vec![], vec![leaf("title")]),
+                element(
+                    "section",
+                    vec![],
+                    vec![button.clone(), leaf("text"), button]
+                ),
+            ],
+        )
+    );
+}
+
+#[test]
+fn selectors() {
+    let node: MyNode = element("div", vec![attr("class", "a")], vec![]);
+    let text: MyNode = leaf("text");
+    assert!(Selector::AllElements.matches(&node));
+    assert!(!Selector::AllElements.matches(&text));
+    assert!(Selector::Tag(&"div").matches(&node));
+    assert!(!Selector::Tag(&"span").matches(&node));
+    assert!(Selector::HasAttribute(&"class").matches(&node));
+    assert!(!Selector::HasAttribute(&"id").matches(&node));
+    assert!(Selector::AttributeValue(&"class", &"a").matches(&node));
+    assert!(!Selector::AttributeValue(&"class", &"b").matches(&node));
+}
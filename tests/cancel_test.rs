@@ -0,0 +1,79 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, *};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub type MyNode = Node<&'static str, &'static str, String, &'static str, u64>;
+
+fn table(text: &str) -> MyNode {
+    element(
+        "tbody",
+        vec![],
+        (0..100).map(|row| {
+            element("tr", vec![], vec![leaf(format!("{text} {row}"))])
+        }),
+    )
+}
+
+#[test]
+fn a_cancelled_diff_is_an_error() {
+    let old = table("old");
+    let new = table("new");
+    let cancel = AtomicBool::new(true);
+    let options = DiffOptions {
+        cancel: Some(&cancel),
+        ..Default::default()
+    };
+    assert_eq!(
+        try_diff_with_options(&old, &new, &"key", &options),
+        Err(DiffCancelled)
+    );
+    assert!(diff_with_options(&old, &new, &"key", &options).is_empty());
+}
+
+#[test]
+fn the_diff_stops_when_it_is_cancelled_midway() {
+    let old = table("old");
+    let new = table("new");
+    let cancel = AtomicBool::new(false);
+    let visited = Cell::new(0);
+    // cancel once 10 rows are diffed, such as when a newer state arrived
+    let is_ignored = |node: &MyNode| {
+        if node.tag() == Some(&"tr") {
+            visited.set(visited.get() + 1);
+            if visited.get() == 10 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        }
+        false
+    };
+    let options = DiffOptions {
+        cancel: Some(&cancel),
+        is_ignored: Some(&is_ignored),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    // the leaves of the first 9 rows are replaced,
+    // the diff stops before the leaf of the 10th row
+    assert_eq!(patches.len(), 9);
+    assert_eq!(visited.get(), 10);
+    assert_eq!(
+        try_diff_with_options(&old, &new, &"key", &options),
+        Err(DiffCancelled)
+    );
+}
+
+#[test]
+fn an_uncancelled_diff_is_complete() {
+    let old = table("old");
+    let new = table("new");
+    let cancel = AtomicBool::new(false);
+    let options = DiffOptions {
+        cancel: Some(&cancel),
+        ..Default::default()
+    };
+    assert_eq!(
+        try_diff_with_options(&old, &new, &"key", &options),
+        Ok(diff_with_key(&old, &new, &"key"))
+    );
+}
@@ -0,0 +1,125 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, testing::assert_algorithms_agree, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn options() -> DiffOptions<
+    'static,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+> {
+    DiffOptions {
+        keyed_tag_change: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn keyed_element_changes_its_tag() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("rect", vec![attr("key", "1")], vec![leaf("one")]),
+            element("rect", vec![attr("key", "2")], vec![leaf("two")]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("rect", vec![attr("key", "1")], vec![leaf("one")]),
+            element("circle", vec![attr("key", "2")], vec![leaf("2")]),
+        ],
+    );
+
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    assert_eq!(
+        patches,
+        vec![
+            Patch::change_tag(TreePath::new([1]), &"rect", &"circle"),
+            Patch::replace_node(None, TreePath::new([1, 0]), vec![&leaf("2")]),
+        ]
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+    assert_algorithms_agree(&old, &new, &"key");
+}
+
+#[test]
+fn keyed_element_is_replaced_by_default() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![element("rect", vec![attr("key", "1")], vec![])],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![element("circle", vec![attr("key", "1")], vec![])],
+    );
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        patches,
+        vec![Patch::replace_node(
+            Some(&"rect"),
+            TreePath::new([0]),
+            vec![&new.children()[0]]
+        )]
+    );
+}
+
+#[test]
+fn unkeyed_element_is_still_replaced() {
+    let old: MyNode =
+        element("main", vec![], vec![element("rect", vec![], vec![])]);
+    let new: MyNode =
+        element("main", vec![], vec![element("circle", vec![], vec![])]);
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    assert_eq!(
+        patches,
+        vec![Patch::replace_node(
+            Some(&"rect"),
+            TreePath::new([0]),
+            vec![&new.children()[0]]
+        )]
+    );
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn moved_keyed_element_changes_its_tag() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("rect", vec![attr("key", "1")], vec![]),
+            element("rect", vec![attr("key", "2")], vec![]),
+            element("rect", vec![attr("key", "3")], vec![]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("circle", vec![attr("key", "3"), attr("r", "5")], vec![]),
+            element("rect", vec![attr("key", "1")], vec![]),
+            element("rect", vec![attr("key", "2")], vec![]),
+        ],
+    );
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    let summary = PatchBatchSummary::from(patches.as_slice());
+    assert_eq!(summary.tag, 1);
+    assert_eq!(summary.replace, 0);
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
@@ -0,0 +1,39 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn cloned_nodes_outlive_the_new_tree() {
+    let old: MyNode = element("main", vec![], vec![leaf("hello")]);
+    let pending: Vec<(TreePath, Vec<MyNode>)> = {
+        let new: MyNode = element(
+            "main",
+            vec![],
+            vec![leaf("hello"), element("div", vec![], vec![leaf("world")])],
+        );
+        diff_with_key(&old, &new, &"key")
+            .iter()
+            .map(|patch| (patch.path().clone(), patch.cloned_nodes()))
+            .collect()
+    };
+    assert_eq!(
+        pending,
+        vec![(
+            TreePath::root(),
+            vec![element("div", vec![], vec![leaf("world")])]
+        )]
+    );
+}
+
+#[test]
+fn patches_without_nodes() {
+    let patch: Patch<&str, &str, &str, &str, &str> =
+        Patch::remove_node(None, TreePath::new([0]));
+    assert!(patch.cloned_nodes().is_empty());
+
+    let wrapper: MyNode = element("b", vec![], vec![leaf("bold")]);
+    let patch = Patch::wrap_node(Some(&"b"), TreePath::new([0]), &wrapper);
+    assert_eq!(patch.cloned_nodes(), vec![wrapper.clone()]);
+}
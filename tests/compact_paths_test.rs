@@ -0,0 +1,133 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::mem::size_of;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+/// counts the heap bytes allocated by the current thread
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|n| n.set(n.get() + layout.size()));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// return the heap bytes allocated by the current thread while running `f`
+fn allocated_by<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.with(Cell::get);
+    let value = f();
+    (value, ALLOCATED.with(Cell::get) - before)
+}
+
+/// the number of bytes of one child index of a path
+fn index_size() -> usize {
+    if cfg!(feature = "compact-paths") {
+        4
+    } else {
+        size_of::<usize>()
+    }
+}
+
+/// the nodes of a batch which changes the leaf of each of the 1000 children
+fn large_nodes() -> (MyNode, MyNode) {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        (0..1000).map(|_| element("ul", vec![], vec![leaf("a")])),
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        (0..1000).map(|_| element("ul", vec![], vec![leaf("b")])),
+    );
+    (old, new)
+}
+
+#[test]
+fn path_indices_round_trip() {
+    let path = TreePath::new(vec![0, 3, 1]);
+    assert_eq!(path.indices().collect::<Vec<_>>(), vec![0, 3, 1]);
+    assert_eq!(path.to_vec(), vec![0, 3, 1]);
+    assert_eq!(path.len(), 3);
+    assert_eq!(path.get(1), Some(3));
+    assert_eq!(path.get(3), None);
+    assert!(path.starts_with(&TreePath::new([0, 3])));
+    assert!(!path.starts_with(&TreePath::new([3])));
+    assert_eq!(path.traverse(7), TreePath::from([0, 3, 1, 7]));
+}
+
+#[test]
+fn large_patch_batch_path_bytes() {
+    let (old, new) = large_nodes();
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(patches.len(), 1000);
+    // each of the patches points to `[i, 0]`, measure the heap used by copies of the paths
+    let (paths, bytes) = allocated_by(|| {
+        patches
+            .iter()
+            .map(|patch| patch.path().clone())
+            .collect::<Vec<_>>()
+    });
+    let vec_bytes = paths.capacity() * size_of::<TreePath>();
+    assert!(paths.iter().all(|path| path.len() == 2));
+    assert_eq!(bytes - vec_bytes, 1000 * 2 * index_size());
+}
+
+#[test]
+fn large_patch_batch_diff_bytes() {
+    let (old, new) = large_nodes();
+    let (patches, bytes) = allocated_by(|| diff_with_key(&old, &new, &"key"));
+    let path_bytes = patches.len() * 2 * index_size();
+    // the paths are a part of what the diff allocates for the batch
+    assert!(bytes >= path_bytes);
+    println!(
+        "diff of 1000 changed leaves: {} bytes allocated, {} of them in the patch paths",
+        bytes, path_bytes
+    );
+}
+
+#[test]
+fn child_index_overflow() {
+    let too_large = u32::MAX as usize + 1;
+    if cfg!(feature = "compact-paths") && usize::BITS > 32 {
+        assert_eq!(
+            TreePath::try_new([0, too_large]),
+            Err(PathIndexOverflow { index: too_large })
+        );
+        let mut path = TreePath::root();
+        assert!(path.try_push(too_large).is_err());
+        assert!(path.is_empty());
+    } else {
+        assert_eq!(
+            TreePath::try_new([0, too_large]).map(|path| path.to_vec()),
+            Ok(vec![0, too_large])
+        );
+    }
+}
+
+#[cfg(feature = "compact-paths")]
+#[test]
+#[should_panic]
+fn child_index_must_fit_in_u32() {
+    if usize::BITS > 32 {
+        TreePath::new(vec![u32::MAX as usize + 1]);
+    } else {
+        panic!("usize always fits in u32");
+    }
+}
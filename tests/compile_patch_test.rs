@@ -0,0 +1,66 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+fn item(key: usize) -> MyNode {
+    element(
+        "li",
+        vec![attr("key", key.to_string())],
+        vec![
+            leaf(format!("item{key}")),
+            element("span", vec![], vec![leaf(format!("detail{key}"))]),
+        ],
+    )
+}
+
+fn view(keys: &[usize], class: &str) -> MyNode {
+    element(
+        "main",
+        vec![attr("class", class.to_string())],
+        vec![
+            element("header", vec![], vec![leaf("title".to_string())]),
+            element("ul", vec![], keys.iter().map(|key| item(*key))),
+        ],
+    )
+}
+
+#[test]
+fn compiled_patches_are_applied_to_many_trees() {
+    let old = view(&[0, 1, 2, 3, 4, 5], "a");
+    let new = view(&[5, 1, 2, 10, 3, 0, 11], "b");
+    let patches = diff_with_key(&old, &new, &"key");
+    let compiled = compile(&patches);
+    assert_eq!(compiled.patches(), &patches[..]);
+
+    for _ in 0..3 {
+        let mut root = old.clone();
+        compiled.apply(&mut root);
+        assert_eq!(root, new);
+    }
+}
+
+#[test]
+fn compiled_patches_agree_with_apply_patches() {
+    let old = view(&[0, 1, 2, 3], "a");
+    let new = element("section", vec![], vec![view(&[3, 2, 1, 0], "a")]);
+    let patches = diff_with_key(&old, &new, &"key");
+
+    let mut expected = old.clone();
+    apply_patches(&mut expected, &patches);
+
+    let mut root = old.clone();
+    compile(&patches).apply(&mut root);
+    assert_eq!(root, expected);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn empty_patches_keeps_the_tree() {
+    let patches: Vec<Patch<&str, &str, String, &str, String>> = vec![];
+    let mut root = view(&[0, 1], "a");
+    compile(&patches).apply(&mut root);
+    assert_eq!(root, view(&[0, 1], "a"));
+}
@@ -0,0 +1,157 @@
+#![deny(warnings)]
+//! the bounds of the "Complexity" section of the crate documentation
+use mt_dom::{diff::*, *};
+
+pub type MyNode = Node<&'static str, &'static str, String, &'static str, u64>;
+
+/// an element with `size` leaves
+fn block(text: &str, size: usize) -> MyNode {
+    element(
+        "div",
+        vec![],
+        (0..size).map(|i| leaf(format!("{text} {i}"))),
+    )
+}
+
+/// a tree of `depth` levels, where each level has a block of `size` leaves
+/// before the next level, and the leaf at the bottom has `text`.
+/// The number of nodes is `depth * (size + 2) + 1`
+fn comb(depth: usize, size: usize, text: &str) -> MyNode {
+    (0..depth).fold(leaf(text.to_string()), |next, level| {
+        element(
+            "section",
+            vec![attr("class", level as u64)],
+            vec![block("block", size), next],
+        )
+    })
+}
+
+fn keyed_list(keys: impl IntoIterator<Item = u64>) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        keys.into_iter().map(|key| {
+            element("li", vec![attr("key", key)], vec![leaf(key.to_string())])
+        }),
+    )
+}
+
+#[test]
+fn unchanged_trees_are_compared_once() {
+    let old = comb(50, 20, "same");
+    let new = comb(50, 20, "same");
+    let (patches, stats) = diff_with_stats(&old, &new, &"key");
+    assert!(patches.is_empty());
+    assert_eq!(stats.nodes_visited, 1);
+    assert_eq!(stats.nodes_compared, 50 * 22 + 1);
+}
+
+#[test]
+fn each_node_is_compared_at_most_once() {
+    let (depth, size) = (100, 50);
+    let nodes = depth * (size + 2) + 1;
+    let old = comb(depth, size, "old");
+    let new = comb(depth, size, "new");
+    let (patches, stats) = diff_with_stats(&old, &new, &"key");
+    assert_eq!(patches.len(), 1);
+    // the blocks are known to be equal from comparing the root,
+    // comparing them again at each level would be quadratic in the depth
+    assert_eq!(stats.nodes_compared, nodes);
+    // the sections, their blocks and the leaf at the bottom
+    assert_eq!(stats.nodes_visited, 2 * depth + 1);
+}
+
+#[test]
+fn keyed_reorders_are_linear_in_the_nodes() {
+    let m = 1000;
+    let nodes = 2 * m + 1;
+    let old = keyed_list(0..m as u64);
+    // move every other child to the end
+    let new = keyed_list(
+        (0..m as u64)
+            .filter(|key| key % 2 == 0)
+            .chain((0..m as u64).filter(|key| key % 2 == 1)),
+    );
+    let (patches, stats) = diff_with_stats(&old, &new, &"key");
+    assert!(!patches.is_empty());
+    // the list and each of the matched children
+    assert_eq!(stats.nodes_visited, m + 1);
+    // the children before the first moved child are compared twice,
+    // by the list and by their own pair
+    assert!(stats.nodes_compared <= 2 * nodes);
+}
+
+/// the number of the key comparisons of diffing the reordered keyed list of `m` children
+#[cfg(feature = "keyed")]
+fn keys_compared(m: u64, scan_keys: bool) -> usize {
+    let old = keyed_list(0..m);
+    // reverse the children and move every other child to the end
+    let new = keyed_list(
+        (0..m)
+            .rev()
+            .filter(|key| key % 2 == 0)
+            .chain((0..m).filter(|key| key % 2 == 1)),
+    );
+    let stats = std::cell::RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        stats: Some(&stats),
+        scan_keys,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert!(!patches.is_empty());
+    let compared = stats.borrow().keys_compared;
+    compared
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn keyed_reorders_compare_the_keys_m_log_m_times() {
+    for m in [1000u64, 10_000, 40_000] {
+        let log_m = (u64::BITS - m.leading_zeros()) as usize;
+        let compared = keys_compared(m, false);
+        assert!(
+            compared <= 3 * m as usize * log_m,
+            "{} key comparisons for {} children",
+            compared,
+            m
+        );
+    }
+    // scanning the old keys for each of the new keys is quadratic
+    let m = 1000u64;
+    assert!(keys_compared(m, true) >= (m * m / 4) as usize);
+}
+
+#[test]
+fn memoized_trees_are_diffed_in_the_changed_nodes() {
+    let (width, size) = (200, 50);
+    let tree = |changed: u64| -> MyNode {
+        Node::memoized(
+            changed,
+            element(
+                "main",
+                vec![],
+                (0..width as u64).map(|i| {
+                    let text = if i == 7 {
+                        changed.to_string()
+                    } else {
+                        String::new()
+                    };
+                    // the host hashes the props of the blocks
+                    Node::memoized(
+                        if i == 7 { changed } else { i + 100 },
+                        block(&text, size),
+                    )
+                }),
+            ),
+        )
+    };
+    let old = tree(1);
+    let new = tree(2);
+    let (patches, stats) = diff_with_stats(&old, &new, &"key");
+    assert_eq!(patches.len(), size);
+    // the root, its children, and the leaves of the changed block,
+    // regardless of the number of nodes of the unchanged blocks
+    assert_eq!(stats.nodes_visited, 1 + width + size);
+    assert_eq!(stats.nodes_compared, size);
+}
@@ -0,0 +1,44 @@
+#![deny(warnings)]
+#![cfg(all(feature = "keyed", feature = "apply-patches"))]
+use mt_dom::testing::conformance::{self, *};
+use mt_dom::*;
+
+#[test]
+fn lis_reconciler_conforms() {
+    conformance::run(&LisReconciler);
+}
+
+#[test]
+fn numeric_key_reconciler_conforms() {
+    conformance::run(&NumericKeyReconciler);
+}
+
+/// replaces every list, which produces the correct tree but recreates the keyed children
+#[derive(Debug)]
+struct ReplaceEverything;
+
+impl KeyedReconciler for ReplaceEverything {
+    fn name(&self) -> &str {
+        "replace_everything"
+    }
+
+    fn diff<'a>(
+        &self,
+        old: &'a ConformanceNode,
+        new: &'a ConformanceNode,
+    ) -> Vec<ConformancePatch<'a>> {
+        if old == new {
+            vec![]
+        } else {
+            vec![Patch::replace_node(old.tag(), TreePath::root(), [new])]
+        }
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "reconciler `replace_everything` failed the `reverse` scenario"
+)]
+fn recreating_keyed_children_fails() {
+    conformance::run(&ReplaceEverything);
+}
@@ -0,0 +1,164 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode = Node<&'static str, &'static str, String, &'static str, u64>;
+
+fn card(id: u64) -> MyNode {
+    element(
+        "li",
+        vec![attr("key", id)],
+        vec![leaf(format!("card {id}"))],
+    )
+}
+
+/// a board with a column of cards for each of the `columns`
+fn board(columns: &[&[u64]]) -> MyNode {
+    element(
+        "main",
+        vec![],
+        columns.iter().map(|cards| {
+            element("ul", vec![], cards.iter().map(|id| card(*id)))
+        }),
+    )
+}
+
+fn diff_moves<'a>(
+    old: &'a MyNode,
+    new: &'a MyNode,
+    scope: MoveScope,
+) -> Vec<Patch<'a, &'static str, &'static str, String, &'static str, u64>> {
+    let options = DiffOptions {
+        cross_parent_moves: Some(scope),
+        ..Default::default()
+    };
+    let patches = diff_with_options(old, new, &"key", &options);
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new);
+    patches
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn card_moved_between_columns() {
+    let old = board(&[&[1, 2, 3], &[4, 5]]);
+    let new = board(&[&[1, 3], &[4, 2, 5]]);
+
+    let patches = diff_moves(&old, &new, MoveScope::Siblings);
+    assert_eq!(
+        patches,
+        vec![Patch::move_after_node(
+            Some(&"li"),
+            TreePath::new([1, 0]),
+            [TreePath::new([0, 1])]
+        )]
+    );
+}
+
+#[test]
+fn card_moved_into_an_empty_column() {
+    let old = board(&[&[1, 2], &[]]);
+    let new = board(&[&[1], &[2]]);
+
+    let patches = diff_moves(&old, &new, MoveScope::Siblings);
+    assert_eq!(
+        patches,
+        vec![Patch::move_into_node(
+            Some(&"ul"),
+            TreePath::new([1]),
+            [TreePath::new([0, 1])]
+        )]
+    );
+}
+
+#[test]
+fn changes_of_the_moved_card_are_patched() {
+    let old = board(&[&[1, 2], &[3]]);
+    let highlighted: MyNode = element(
+        "li",
+        vec![attr("key", 2), attr("class", 0)],
+        vec![leaf("card 2".to_string())],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("ul", vec![], vec![card(1)]),
+            element("ul", vec![], vec![card(3), highlighted]),
+        ],
+    );
+
+    let patches = diff_moves(&old, &new, MoveScope::Siblings);
+    let moved = &new.children()[1].children()[1];
+    assert_eq!(
+        patches,
+        vec![
+            Patch::move_into_node(
+                Some(&"ul"),
+                TreePath::new([1]),
+                [TreePath::new([0, 1])]
+            ),
+            Patch::add_attributes(
+                &"li",
+                TreePath::new([0, 1]),
+                moved
+                    .attributes()
+                    .unwrap()
+                    .iter()
+                    .skip(1)
+                    .collect::<Vec<_>>()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn moves_are_limited_to_the_scope() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            board(&[&[1, 2], &[3]]),
+            element("aside", vec![], vec![element("ul", vec![], vec![])]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            board(&[&[1], &[3]]),
+            element(
+                "aside",
+                vec![],
+                vec![element("ul", vec![], vec![card(2)])],
+            ),
+        ],
+    );
+
+    let patches = diff_moves(&old, &new, MoveScope::Siblings);
+    assert!(patches
+        .iter()
+        .any(|patch| patch.kind() == PatchKind::RemoveNode));
+
+    let patches = diff_moves(&old, &new, MoveScope::Anywhere);
+    assert_eq!(
+        patches,
+        vec![Patch::move_into_node(
+            Some(&"ul"),
+            TreePath::new([1, 0]),
+            [TreePath::new([0, 0, 1])]
+        )]
+    );
+}
+
+#[test]
+fn cards_are_recreated_by_default() {
+    let old = board(&[&[1, 2, 3], &[4, 5]]);
+    let new = board(&[&[1, 3], &[4, 2, 5]]);
+
+    let patches = diff(&old, &new);
+    assert!(patches
+        .iter()
+        .all(|patch| patch.kind() != PatchKind::MoveAfterNode));
+}
@@ -0,0 +1,71 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+const DEPTH: usize = 100_000;
+
+/// a chain of `DEPTH` nested divs with the `text` at the bottom
+fn chain(text: &'static str) -> MyNode {
+    (0..DEPTH).fold(leaf(text), |child, _| element("div", vec![], vec![child]))
+}
+
+#[test]
+fn diffing_a_deep_chain_does_not_overflow_the_stack() {
+    let old = chain("old");
+    let new = chain("new");
+    let bottom = TreePath::new(vec![0; DEPTH]);
+
+    // comparing the whole subtrees at each level would take quadratic time
+    let options = DiffOptions {
+        equality_check: EqualityCheck::Never,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].path(), &bottom);
+    assert_eq!(patches[0].kind(), PatchKind::ReplaceNode);
+    drop(patches);
+
+    // the equal chains are compared without recursing
+    let same = chain("new");
+    assert!(new == same);
+    assert!(diff_with_key(&new, &same, &"key").is_empty());
+    same.dismantle();
+
+    let options = DiffOptions {
+        equality_check: EqualityCheck::Never,
+        change_leaf: true,
+        max_patches: Some(10),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].kind(), PatchKind::ChangeLeaf);
+    drop(patches);
+
+    old.dismantle();
+    new.dismantle();
+}
+
+#[cfg(feature = "apply-patches")]
+#[test]
+fn applying_the_patches_of_a_deep_chain_does_not_overflow_the_stack() {
+    let old = chain("old");
+    let new = chain("new");
+    // cloning the chain would recurse per level
+    let mut root = chain("old");
+    let options = DiffOptions {
+        equality_check: EqualityCheck::Never,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    apply_patches(&mut root, &patches);
+    assert!(root == new);
+    drop(patches);
+
+    root.dismantle();
+    old.dismantle();
+    new.dismantle();
+}
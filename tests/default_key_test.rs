@@ -0,0 +1,37 @@
+#![deny(warnings)]
+#![cfg(feature = "keyed")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+// the default key is global, so this is tested in a single test
+#[test]
+fn configurable_default_key() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element("li", vec![attr("data-key", "1")], vec![]),
+            element("li", vec![attr("data-key", "2")], vec![]),
+        ],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![element("li", vec![attr("data-key", "2")], vec![])],
+    );
+
+    assert_eq!(default_key(), "key");
+    assert_eq!(diff(&old, &new), diff_with_key(&old, &new, &"key"));
+
+    set_default_key("data-key");
+    assert_eq!(default_key(), "data-key");
+    assert_eq!(
+        diff(&old, &new),
+        vec![Patch::remove_node(Some(&"li"), TreePath::new(vec![0]))]
+    );
+    assert_eq!(diff(&old, &new), diff_with_key(&old, &new, &"data-key"));
+
+    set_default_key("key");
+}
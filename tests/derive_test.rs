@@ -0,0 +1,67 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::*;
+use mt_dom_derive::IntoNode;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[derive(IntoNode)]
+#[node(tag = "span")]
+struct Tag {
+    #[node(leaf)]
+    label: &'static str,
+}
+
+#[derive(IntoNode)]
+#[node(tag = "li")]
+struct Todo {
+    #[node(attr = "key")]
+    id: &'static str,
+    #[node(attr)]
+    class: &'static str,
+    #[node(leaf)]
+    title: &'static str,
+    #[node(child)]
+    tag: Tag,
+    #[node(children)]
+    subtasks: Vec<Tag>,
+    #[allow(dead_code)]
+    not_rendered: u32,
+}
+
+fn todo(title: &'static str, subtasks: Vec<&'static str>) -> Todo {
+    Todo {
+        id: "1",
+        class: "todo",
+        title,
+        tag: Tag { label: "urgent" },
+        subtasks: subtasks.into_iter().map(|label| Tag { label }).collect(),
+        not_rendered: 0,
+    }
+}
+
+#[test]
+fn struct_into_node() {
+    let node: MyNode = todo("buy milk", vec!["go to store"]).into();
+    let expected: MyNode = element(
+        "li",
+        [attr("key", "1"), attr("class", "todo")],
+        [
+            leaf("buy milk"),
+            element("span", [], [leaf("urgent")]),
+            element("span", [], [leaf("go to store")]),
+        ],
+    );
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn diff_data_models_directly() {
+    let old: MyNode = todo("buy milk", vec![]).into();
+    let new: MyNode = todo("buy bread", vec!["go to bakery"]).into();
+    let patches = diff_with_key(&old, &new, &"key");
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
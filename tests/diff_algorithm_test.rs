@@ -0,0 +1,99 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, testing::assert_algorithms_agree, *};
+use std::cell::RefCell;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn keyed(key: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(key)])
+}
+
+fn unkeyed(text: &'static str) -> MyNode {
+    element("li", vec![], vec![leaf(text)])
+}
+
+fn auto_strategies(
+    old: &MyNode,
+    new: &MyNode,
+) -> Vec<(TreePath, ListStrategy)> {
+    let stats = RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        algorithm: DiffAlgorithm::Auto,
+        stats: Some(&stats),
+        ..Default::default()
+    };
+    let patches = diff_with_options(old, new, &"key", &options);
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new);
+    assert_algorithms_agree(old, new, &"key");
+    stats.into_inner().lists
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn reordered_keys_are_diffed_as_keyed() {
+    let old: MyNode =
+        element("ul", vec![], vec![keyed("1"), keyed("2"), keyed("3")]);
+    let new: MyNode =
+        element("ul", vec![], vec![keyed("3"), keyed("1"), keyed("2")]);
+    let lists = auto_strategies(&old, &new);
+    assert_eq!(lists[0], (TreePath::root(), ListStrategy::Keyed));
+}
+
+#[test]
+fn appended_keys_are_diffed_by_position() {
+    let old: MyNode = element("ul", vec![], vec![keyed("1"), keyed("2")]);
+    let new: MyNode =
+        element("ul", vec![], vec![keyed("1"), keyed("2"), keyed("3")]);
+    let lists = auto_strategies(&old, &new);
+    assert_eq!(lists[0], (TreePath::root(), ListStrategy::Positional));
+}
+
+#[test]
+fn mostly_unkeyed_children_are_diffed_by_position() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![unkeyed("a"), keyed("1"), unkeyed("b"), unkeyed("c")],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![unkeyed("a"), unkeyed("b"), keyed("1"), unkeyed("d")],
+    );
+    let lists = auto_strategies(&old, &new);
+    assert_eq!(lists[0], (TreePath::root(), ListStrategy::Positional));
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn stats_are_recorded_for_each_list() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![element("ul", vec![], vec![keyed("1"), keyed("2")])],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![element("ul", vec![], vec![keyed("2"), keyed("1")])],
+    );
+    let stats = RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        stats: Some(&stats),
+        ..Default::default()
+    };
+    diff_with_options(&old, &new, &"key", &options);
+    let stats = stats.into_inner();
+    assert_eq!(
+        stats.lists,
+        vec![
+            (TreePath::root(), ListStrategy::Positional),
+            (TreePath::new([0]), ListStrategy::Keyed),
+        ]
+    );
+    assert_eq!(stats.count(ListStrategy::Keyed), 1);
+}
@@ -0,0 +1,206 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+/// a chain of nested divs, with the leaf at the bottom
+fn chain(depth: usize, text: &'static str) -> MyNode {
+    (0..depth).fold(leaf(text), |child, _| element("div", vec![], vec![child]))
+}
+
+pub type MyPatch<'a> = Patch<
+    'a,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+>;
+
+fn assert_round_trip(old: &MyNode, new: &MyNode, patches: &[MyPatch]) {
+    let mut root = old.clone();
+    apply_patches(&mut root, patches);
+    assert_eq!(&root, new);
+}
+
+#[test]
+fn changed_node_past_the_max_depth_is_replaced() {
+    let old = chain(5, "old");
+    let new = chain(5, "new");
+
+    let options = DiffOptions {
+        max_depth: Some(2),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    let replaced = &new.children()[0].children()[0];
+    assert_eq!(
+        patches,
+        vec![Patch::replace_node(
+            Some(&"div"),
+            TreePath::new([0, 0]),
+            vec![replaced]
+        )]
+    );
+    assert_round_trip(&old, &new, &patches);
+}
+
+#[test]
+fn nodes_above_the_max_depth_are_diffed() {
+    let old: MyNode =
+        element("main", vec![attr("class", "old")], vec![chain(3, "same")]);
+    let new: MyNode =
+        element("main", vec![attr("class", "new")], vec![chain(3, "same")]);
+
+    let options = DiffOptions {
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches, diff(&old, &new));
+    assert_round_trip(&old, &new, &patches);
+}
+
+#[test]
+fn max_depth_of_zero_replaces_the_root() {
+    let old = chain(3, "old");
+    let new = chain(3, "new");
+
+    let options = DiffOptions {
+        max_depth: Some(0),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::replace_node(
+            Some(&"div"),
+            TreePath::root(),
+            vec![&new]
+        )]
+    );
+}
+
+#[test]
+fn element_over_the_patch_budget_is_replaced() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("ul", vec![], vec![leaf("a"), leaf("b"), leaf("c")]),
+            element("p", vec![], vec![leaf("d")]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("ul", vec![], vec![leaf("x"), leaf("y"), leaf("z")]),
+            element("p", vec![], vec![leaf("w")]),
+        ],
+    );
+
+    let options = DiffOptions {
+        max_patches: Some(2),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![
+            Patch::replace_node(
+                Some(&"ul"),
+                TreePath::new([0]),
+                vec![&new.children()[0]]
+            ),
+            Patch::replace_node(
+                None,
+                TreePath::new([1, 0]),
+                vec![&new.children()[1].children()[0]]
+            ),
+        ]
+    );
+    assert_round_trip(&old, &new, &patches);
+}
+
+#[test]
+fn patches_never_exceed_the_budget() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        (0..10).map(|_| element("li", vec![], vec![leaf("old")])),
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        (0..10).map(|_| element("li", vec![], vec![leaf("new")])),
+    );
+
+    for max_patches in 1..12 {
+        let options = DiffOptions {
+            max_patches: Some(max_patches),
+            ..Default::default()
+        };
+        let patches = diff_with_options(&old, &new, &"key", &options);
+        assert!(patches.len() <= max_patches);
+        assert_round_trip(&old, &new, &patches);
+    }
+}
+
+#[test]
+fn keyed_children_at_the_max_depth_are_diffed_by_position() {
+    let old: MyNode = element(
+        "div",
+        vec![],
+        vec![element(
+            "ul",
+            vec![],
+            vec![element("li", vec![attr("key", "5")], vec![leaf("text0")])],
+        )],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![],
+        vec![element(
+            "ul",
+            vec![],
+            vec![
+                element("li", vec![attr("key", "4")], vec![leaf("text0")]),
+                element("li", vec![attr("key", "5")], vec![leaf("text1")]),
+            ],
+        )],
+    );
+    let options = DiffOptions {
+        max_depth: Some(2),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    let ul = &new.children()[0];
+    assert_eq!(
+        patches,
+        vec![
+            Patch::replace_node(
+                Some(&"li"),
+                TreePath::new([0, 0]),
+                vec![&ul.children()[0]]
+            ),
+            Patch::append_children(
+                Some(&"ul"),
+                TreePath::new([0]),
+                vec![&ul.children()[1]]
+            ),
+        ]
+    );
+    assert_round_trip(&old, &new, &patches);
+
+    // the keyed children above the maximum depth are still moved
+    let options = DiffOptions {
+        max_depth: Some(3),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+    assert_round_trip(&old, &new, &patches);
+}
@@ -0,0 +1,73 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, String>;
+
+fn row(key: usize, selected: bool) -> MyNode {
+    let class = if selected { "row selected" } else { "row" };
+    element(
+        "tr",
+        vec![
+            attr("key", key.to_string()),
+            attr("class", class.to_string()),
+        ],
+        vec![element(
+            "td",
+            vec![attr("class", "cell".to_string())],
+            vec![],
+        )],
+    )
+}
+
+fn table(rows: impl IntoIterator<Item = MyNode>) -> MyNode {
+    element("table", vec![], rows)
+}
+
+#[test]
+fn repeated_values_are_interned_once() {
+    let old = table([]);
+    let new = table((0..1000).map(|i| row(i, false)));
+    let mut context = DiffContext::new();
+    let patches = context.diff(&old, &new, &"key");
+    assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+
+    let stats = context.stats();
+    // 1000 keys, 1000 row classes and 1000 cell classes
+    assert_eq!(stats.total, 3000);
+    // 1000 keys, "row" and "cell"
+    assert_eq!(stats.unique, 1002);
+    assert!(stats.dedup_ratio() > 0.66);
+    assert_eq!(context.index_of(&"row".to_string()), Some(1));
+    assert_eq!(context.index_of(&"cell".to_string()), Some(2));
+    assert_eq!(context.values()[1], "row");
+}
+
+#[test]
+fn interned_across_diffs_of_the_session() {
+    let first = table((0..3).map(|i| row(i, false)));
+    let second = table((0..3).map(|i| row(i, i == 1)));
+    let third = table((0..3).map(|i| row(i, i == 2)));
+
+    let mut context = DiffContext::new();
+    context.diff(&first, &second, &"key");
+    let index = context.index_of(&"row selected".to_string());
+    assert_eq!(index, Some(0));
+    context.diff(&second, &third, &"key");
+    assert_eq!(context.index_of(&"row selected".to_string()), index);
+    assert_eq!(
+        context.stats(),
+        InternStats {
+            // the newly selected row, the deselected row is back to "row"
+            total: 3,
+            unique: 2
+        }
+    );
+}
+
+#[test]
+fn no_values() {
+    let context: DiffContext<String> = DiffContext::default();
+    assert_eq!(context.stats().dedup_ratio(), 0.0);
+    assert!(context.values().is_empty());
+}
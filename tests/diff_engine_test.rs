@@ -0,0 +1,120 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn row(class: &'static str) -> MyNode {
+    element("li", vec![attr("class", class)], vec![])
+}
+
+#[test]
+fn patches_are_the_same_as_the_free_function() {
+    let old: MyNode = element("ul", vec![], vec![row("a"), row("b")]);
+    let new: MyNode = element("ul", vec![], vec![row("b"), row("c"), row("d")]);
+    let mut engine = DiffEngine::new("key");
+    assert_eq!(engine.diff(&old, &new), diff_with_key(&old, &new, &"key"));
+}
+
+#[test]
+fn interned_values_are_kept_across_diffs() {
+    let frames: Vec<MyNode> = vec![
+        element("ul", vec![], vec![]),
+        element("ul", vec![], vec![row("a")]),
+        element("ul", vec![], vec![row("a"), row("b")]),
+        element("ul", vec![], vec![row("c"), row("b")]),
+    ];
+    let mut engine = DiffEngine::new("key");
+    for pair in frames.windows(2) {
+        engine.diff(&pair[0], &pair[1]);
+    }
+    assert_eq!(engine.values(), &["a", "b", "c"]);
+    assert_eq!(engine.index_of(&"c"), Some(2));
+    assert_eq!(engine.generation(), 0);
+}
+
+#[test]
+fn stats_are_accumulated_until_cleared() {
+    let old: MyNode = element("ul", vec![], vec![row("a")]);
+    let new: MyNode = element("ul", vec![], vec![row("b")]);
+    let mut engine =
+        DiffEngine::new("key").with_stats(DiffStats::with_profile());
+    engine.diff(&old, &new);
+    engine.diff(&new, &old);
+    let stats = engine.stats().expect("collected");
+    assert_eq!(stats.lists.len(), 4);
+    assert_eq!(stats.profile.as_ref().map(|profile| profile.lists), Some(4));
+
+    engine.clear();
+    let stats = engine.stats().expect("still collected");
+    assert!(stats.lists.is_empty());
+    assert_eq!(stats.profile, Some(DiffProfile::default()));
+}
+
+#[test]
+fn caches_are_cleared_when_over_the_memory_limit() {
+    let mut engine = DiffEngine::new("key").with_memory_limit(256);
+    let classes = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+    let mut old: MyNode = element("ul", vec![], vec![]);
+    for class in classes {
+        let new: MyNode = element("ul", vec![], vec![row(class)]);
+        engine.diff(&old, &new);
+        // the values of the latest patches are always interned
+        assert!(engine.index_of(&class).is_some());
+        old = new;
+    }
+    assert!(engine.generation() > 0);
+    assert!(engine.values().len() < classes.len());
+}
+
+#[test]
+fn memory_usage_is_zero_when_cleared() {
+    let old: MyNode = element("ul", vec![], vec![]);
+    let new: MyNode = element("ul", vec![], vec![row("a")]);
+    let mut engine = DiffEngine::new("key");
+    engine.diff(&old, &new);
+    assert!(engine.memory_usage() > 0);
+    engine.clear();
+    assert_eq!(engine.memory_usage(), 0);
+    assert_eq!(engine.memory_limit(), None);
+}
+
+#[test]
+fn only_the_latest_values_are_kept_when_they_exceed_the_memory_limit() {
+    let classes = ["b", "c", "d", "e", "f", "g", "h", "i", "j", "k"];
+    let old: MyNode = element("ul", vec![], vec![]);
+    let small: MyNode = element("ul", vec![], vec![row("a")]);
+    let large: MyNode = element("ul", vec![], classes.map(row));
+    let mut engine = DiffEngine::new("key").with_memory_limit(256);
+    engine.diff(&old, &small);
+    assert!(engine.memory_usage() <= 256);
+    assert_eq!(engine.generation(), 0);
+
+    // the limit is exceeded once the values of the diff are interned
+    engine.diff(&old, &large);
+    assert_eq!(engine.generation(), 1);
+    assert_eq!(engine.index_of(&"a"), None);
+    assert_eq!(engine.values(), &classes);
+}
+
+#[test]
+fn the_heap_data_of_the_values_is_counted_with_heap_size() {
+    type StringNode = Node<String, String, String, String, String>;
+    let text = "x".repeat(10_000);
+    let old: StringNode = element("p".to_string(), vec![], vec![]);
+    let new: StringNode = element(
+        "p".to_string(),
+        vec![attr("title".to_string(), text.clone())],
+        vec![],
+    );
+    let mut engine = DiffEngine::new("key".to_string());
+    engine.diff(&old, &new);
+    assert!(engine.memory_usage() < text.len());
+
+    let mut engine = DiffEngine::new("key".to_string())
+        .with_heap_size(|value: &String| value.capacity());
+    engine.diff(&old, &new);
+    assert!(engine.memory_usage() >= text.len());
+    engine.clear();
+    assert_eq!(engine.memory_usage(), 0);
+}
@@ -73,6 +73,7 @@ fn mixed_key_and_no_key_with_2_matched() {
     );
 }
 
+#[cfg(feature = "keyed")]
 #[test]
 fn mixed_key_and_no_key_with_misordered_2_matched() {
     let old: MyNode = element(
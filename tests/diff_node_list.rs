@@ -1,4 +1,5 @@
 #![deny(warnings)]
+#![cfg(feature = "keyed")]
 use mt_dom::{patch::*, *};
 
 pub type MyNode =
@@ -0,0 +1,66 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, *};
+use std::cell::RefCell;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn list(items: &[(&'static str, &'static str)]) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        items.iter().map(|(key, text)| {
+            element("li", vec![attr("key", *key)], vec![leaf(*text)])
+        }),
+    )
+}
+
+#[test]
+fn profile_is_not_collected_by_default() {
+    let stats = RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        stats: Some(&stats),
+        ..Default::default()
+    };
+    let old = list(&[("1", "a")]);
+    let new = list(&[("1", "b")]);
+    diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(stats.into_inner().profile, None);
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn profile_is_accumulated_across_diffs() {
+    let stats = RefCell::new(DiffStats::with_profile());
+    let options = DiffOptions {
+        stats: Some(&stats),
+        ..Default::default()
+    };
+    let frames = [
+        list(&[("1", "a"), ("2", "b"), ("3", "c")]),
+        list(&[("3", "c"), ("1", "a"), ("2", "B")]),
+        list(&[("3", "c"), ("3", "a")]),
+    ];
+    for pair in frames.windows(2) {
+        diff_with_options(&pair[0], &pair[1], &"key", &options);
+    }
+    let profile = stats.into_inner().profile.expect("must have a profile");
+    assert_eq!(profile.keyed_lists, 2);
+    assert_eq!(profile.keys, 5);
+    assert_eq!(profile.distinct_keys, 4);
+    assert_eq!(profile.average_key_cardinality(), 2.0);
+    assert_eq!(profile.duplicate_key_ratio(), 0.2);
+    // the unchanged children are skipped, only the changed `li` is diffed
+    assert_eq!(profile.tags.get("\"ul\""), Some(&2));
+    assert_eq!(profile.tags.get("\"li\""), Some(&1));
+    assert_eq!(profile.lists, 3);
+    assert_eq!(profile.average_child_count(), 2.0);
+}
+
+#[test]
+fn empty_profile() {
+    let profile = DiffProfile::default();
+    assert_eq!(profile.average_child_count(), 0.0);
+    assert_eq!(profile.average_key_cardinality(), 0.0);
+    assert_eq!(profile.duplicate_key_ratio(), 0.0);
+}
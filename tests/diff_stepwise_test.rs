@@ -0,0 +1,175 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, *};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub type MyNode = Node<&'static str, &'static str, String, &'static str, u64>;
+
+fn table(text: &str) -> MyNode {
+    element(
+        "tbody",
+        vec![],
+        (0..100).map(|row| {
+            element("tr", vec![], vec![leaf(format!("{text} {row}"))])
+        }),
+    )
+}
+
+fn keyed(keys: &[u64]) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        keys.iter().map(|key| {
+            element("li", vec![attr("key", *key)], vec![leaf(key.to_string())])
+        }),
+    )
+}
+
+#[test]
+fn each_step_diffs_at_most_max_nodes() {
+    let old = table("old");
+    let new = table("new");
+    let options = DiffOptions::default();
+    let mut stepper = diff_stepwise(&old, &new, &options);
+    assert_eq!(
+        stepper.progress(),
+        DiffProgress {
+            nodes_visited: 0,
+            patches: 0,
+            done: false,
+        }
+    );
+    let mut visited = 0;
+    loop {
+        let progress = stepper.step(25);
+        assert!(progress.nodes_visited - visited <= 25);
+        assert!(progress.nodes_visited >= visited);
+        visited = progress.nodes_visited;
+        if progress.done {
+            break;
+        }
+    }
+    // the tbody, its 100 rows and their leaves
+    assert_eq!(visited, 201);
+    assert_eq!(stepper.progress().patches, 100);
+    assert_eq!(stepper.finish(), diff_with_key(&old, &new, &"key"));
+}
+
+#[test]
+fn the_steps_after_the_diff_is_done_do_nothing() {
+    let old = table("old");
+    let new = table("new");
+    let options = DiffOptions::default();
+    let mut stepper = DiffStepper::new(&old, &new, "key", &options);
+    let done = stepper.step(usize::MAX);
+    assert!(done.done);
+    assert_eq!(stepper.step(10), done);
+    assert!(stepper.is_done());
+}
+
+#[test]
+fn a_stepper_can_be_finished_at_any_step() {
+    let old = keyed(&[1, 2, 3, 4, 5]);
+    let new = keyed(&[5, 3, 1, 6, 2]);
+    let expected = diff_with_key(&old, &new, &"key");
+    let options = DiffOptions::default();
+    for steps in 0..4 {
+        let mut stepper = DiffStepper::new(&old, &new, "key", &options);
+        for _ in 0..steps {
+            stepper.step(1);
+        }
+        assert_eq!(stepper.finish(), expected);
+    }
+}
+
+#[test]
+fn the_options_are_used_by_the_steps() {
+    let old = table("old");
+    let new = table("new");
+    let options = DiffOptions {
+        max_patches: Some(10),
+        path_prefix: TreePath::new([3]),
+        ..Default::default()
+    };
+    let mut stepper = DiffStepper::new(&old, &new, "key", &options);
+    while !stepper.step(7).done {}
+    assert_eq!(
+        stepper.finish(),
+        diff_with_options(&old, &new, &"key", &options)
+    );
+}
+
+#[test]
+fn a_cancelled_stepper_is_done() {
+    let old = table("old");
+    let new = table("new");
+    let cancel = AtomicBool::new(false);
+    let options = DiffOptions {
+        cancel: Some(&cancel),
+        ..Default::default()
+    };
+    let mut stepper = DiffStepper::new(&old, &new, "key", &options);
+    assert!(!stepper.step(10).done);
+    cancel.store(true, Ordering::Relaxed);
+    assert!(stepper.step(10).done);
+    assert!(stepper.finish().len() < 100);
+}
+
+#[test]
+fn hydration_is_done_by_finish() {
+    let old: MyNode = element("button", vec![], vec![leaf("ok".to_string())]);
+    let new: MyNode = element(
+        "button",
+        vec![attr("on_click", 1)],
+        vec![leaf("ok".to_string())],
+    );
+    let is_hydrated = |att: &&'static str| att.starts_with("on_");
+    let options = DiffOptions {
+        hydrate: Some(&is_hydrated),
+        ..Default::default()
+    };
+    let mut stepper = DiffStepper::new(&old, &new, "key", &options);
+    assert!(stepper.step(1).done);
+    assert_eq!(
+        stepper.finish(),
+        diff_with_options(&old, &new, &"key", &options)
+    );
+}
+
+#[test]
+fn the_subtrees_are_compared_within_the_steps() {
+    // the rows are equal up to the last one, so the whole tree is compared
+    // before the root nodes are expanded
+    let rows = |last: &str| -> MyNode {
+        element(
+            "tbody",
+            vec![],
+            (0..100_000).map(|row| {
+                let text = if row == 99_999 { last } else { "row" };
+                element("tr", vec![], vec![leaf(format!("{text} {row}"))])
+            }),
+        )
+    };
+    let old = rows("old");
+    let new = rows("new");
+    let stats = std::cell::RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        stats: Some(&stats),
+        ..DiffOptions::default()
+    };
+    let mut stepper = diff_stepwise(&old, &new, &options);
+    let mut steps = 0;
+    let mut counted = 0;
+    loop {
+        let progress = stepper.step(1_000);
+        let stats = stats.borrow();
+        let count = stats.nodes_compared + progress.nodes_visited;
+        assert!(count - counted <= 1_000);
+        counted = count;
+        steps += 1;
+        if progress.done {
+            break;
+        }
+    }
+    assert!(steps > 200);
+    assert_eq!(stepper.finish(), diff_with_key(&old, &new, &"key"));
+}
@@ -1,5 +1,5 @@
 #![deny(warnings)]
-use mt_dom::{patch::*, *};
+use mt_dom::{diff::*, patch::*, *};
 
 pub type MyNode =
     Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
@@ -375,3 +375,89 @@ fn test_append_at_sub_level() {
         )]
     )
 }
+
+#[test]
+fn diff_elements_directly() {
+    let old: Element<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    > = Element::new(
+        None,
+        "div",
+        vec![attr("class", "old")],
+        vec![leaf("hello")],
+        false,
+    );
+    let new = Element::new(
+        None,
+        "div",
+        vec![attr("class", "new")],
+        vec![leaf("world")],
+        false,
+    );
+
+    let diff = diff_element(
+        &old,
+        &new,
+        &TreePath::new(vec![1]),
+        &DiffOptions::default(),
+    );
+    assert_eq!(
+        diff,
+        vec![
+            Patch::add_attributes(
+                &"div",
+                TreePath::new(vec![1]),
+                vec![&attr("class", "new")]
+            ),
+            Patch::replace_node(
+                None,
+                TreePath::new(vec![1, 0]),
+                vec![&leaf("world")]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn diff_elements_directly_with_the_finishing_options() {
+    let old: Element<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    > = Element::new(
+        None,
+        "div",
+        vec![attr("class", "old"), attr("title", "hello")],
+        vec![],
+        false,
+    );
+    let new =
+        Element::new(None, "div", vec![attr("class", "new")], vec![], false);
+    let stats = std::cell::RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        update_attributes: true,
+        path_prefix: TreePath::new([2]),
+        stats: Some(&stats),
+        ..DiffOptions::default()
+    };
+
+    let diff = diff_element(&old, &new, &TreePath::new([1]), &options);
+    let kinds: Vec<_> = diff
+        .iter()
+        .map(|patch| (patch.kind(), patch.path()))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![(PatchKind::UpdateAttributes, &TreePath::new([2, 1]))]
+    );
+    assert_eq!(
+        stats.into_inner().patches.into_iter().collect::<Vec<_>>(),
+        vec![(PatchKind::UpdateAttributes, 1)]
+    );
+}
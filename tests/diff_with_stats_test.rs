@@ -0,0 +1,78 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode = Node<&'static str, &'static str, String, &'static str, u64>;
+
+fn row(id: u64, text: &str) -> MyNode {
+    element(
+        "tr",
+        vec![attr("key", id)],
+        vec![element("td", vec![], vec![leaf(text.to_string())])],
+    )
+}
+
+#[test]
+fn the_visited_and_skipped_nodes_are_counted() {
+    let old: MyNode =
+        element("tbody", vec![], vec![row(1, "a"), row(2, "b"), row(3, "c")]);
+    let new: MyNode = element(
+        "tbody",
+        vec![],
+        vec![row(1, "a"), row(2, "changed"), row(3, "c")],
+    );
+    let (patches, stats) = diff_with_stats(&old, &new, &"key");
+    assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+    // the table, the 3 rows, the changed cell and its leaf
+    assert_eq!(stats.nodes_visited, 6);
+    // the equal rows
+    assert_eq!(stats.nodes_skipped, 2);
+    assert_eq!(stats.max_depth, 3);
+    assert_eq!(stats.patch_count(PatchKind::ReplaceNode), 1);
+    assert_eq!(stats.patches.values().sum::<usize>(), patches.len());
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn the_patches_are_counted_per_kind() {
+    let old: MyNode = element("tbody", vec![], vec![row(1, "a"), row(2, "b")]);
+    let new: MyNode = element(
+        "tbody",
+        vec![attr("class", 1)],
+        vec![row(2, "b"), row(3, "c"), row(4, "d")],
+    );
+    let (patches, stats) = diff_with_stats(&old, &new, &"key");
+    assert_eq!(stats.patch_count(PatchKind::AddAttributes), 1);
+    assert_eq!(stats.patch_count(PatchKind::RemoveNode), 1);
+    assert_eq!(stats.patch_count(PatchKind::MoveBeforeNode), 0);
+    assert_eq!(stats.patches.values().sum::<usize>(), patches.len());
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn the_skip_function_is_counted_as_skipped() {
+    let old: MyNode = element("div", vec![], vec![row(1, "a")]);
+    let new: MyNode = element("div", vec![], vec![row(1, "b")]);
+    let stats = std::cell::RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        stats: Some(&stats),
+        ..Default::default()
+    };
+    let patches = diff_element_with_functions(
+        old.element_ref().unwrap(),
+        new.element_ref().unwrap(),
+        &TreePath::root(),
+        &"key",
+        &options,
+        &|old, _| old.tag() == Some(&"tr"),
+        &|_, _| false,
+    );
+    assert!(patches.is_empty());
+    let stats = stats.into_inner();
+    // the row, the root elements are diffed without being visited as a pair of nodes
+    assert_eq!(stats.nodes_visited, 1);
+    assert_eq!(stats.nodes_skipped, 1);
+}
@@ -0,0 +1,96 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+const KEYS: [&str; 8] = ["1", "2", "3", "4", "5", "6", "7", "8"];
+
+/// a keyed list whose items also have a nested keyed list
+fn list(order: &[usize]) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        order.iter().map(|i| {
+            let nested = order.iter().rev().map(|j| {
+                element(
+                    "span",
+                    vec![attr("key", KEYS[*j])],
+                    vec![leaf(KEYS[*j])],
+                )
+            });
+            element("li", vec![attr("key", KEYS[*i])], nested)
+        }),
+    )
+}
+
+fn frames() -> Vec<MyNode> {
+    vec![
+        list(&[0, 1, 2, 3, 4, 5, 6, 7]),
+        list(&[7, 0, 1, 2, 3, 4, 5, 6]),
+        list(&[3, 1, 6, 0, 7, 2]),
+        list(&[6, 5, 4, 3, 2, 1, 0]),
+        list(&[0, 2, 4, 6, 1, 3, 5, 7]),
+    ]
+}
+
+#[test]
+fn patches_are_the_same_as_the_free_function() {
+    let frames = frames();
+    let mut differ = Differ::new("key");
+    for pair in frames.windows(2) {
+        assert_eq!(
+            differ.diff(&pair[0], &pair[1]),
+            diff_with_key(&pair[0], &pair[1], &"key")
+        );
+    }
+}
+
+#[test]
+fn patches_are_the_same_with_options() {
+    let frames = frames();
+    let is_pinned =
+        |node: &MyNode| node.attribute_value(&"key") == Some(vec![&"4"]);
+    let options = DiffOptions {
+        is_pinned: Some(&is_pinned),
+        ..DiffOptions::default().with_numeric_keys()
+    };
+    let mut differ = Differ::new("key");
+    for pair in frames.windows(2) {
+        assert_eq!(
+            differ.diff_with_options(&pair[0], &pair[1], &options),
+            diff_with_options(&pair[0], &pair[1], &"key", &options)
+        );
+    }
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn buffers_are_reused_across_diffs() {
+    let old = list(&[0, 1, 2, 3, 4, 5, 6, 7]);
+    let new = list(&[7, 6, 5, 4, 3, 2, 1, 0]);
+    let mut differ = Differ::new("key");
+    assert_eq!(differ.allocated_bytes(), 0);
+    differ.diff(&old, &new);
+    let allocated = differ.allocated_bytes();
+    assert!(allocated > 0);
+    for _ in 0..10 {
+        differ.diff(&old, &new);
+        differ.diff(&new, &old);
+    }
+    assert_eq!(differ.allocated_bytes(), allocated);
+
+    differ.clear();
+    assert_eq!(differ.allocated_bytes(), 0);
+}
+
+#[test]
+fn engine_owns_the_buffers() {
+    let old = list(&[0, 1, 2, 3]);
+    let new = list(&[3, 2, 1, 0]);
+    let mut engine = DiffEngine::new("key");
+    engine.diff(&old, &new);
+    assert!(engine.memory_usage() > 0);
+    engine.clear();
+    assert_eq!(engine.memory_usage(), 0);
+}
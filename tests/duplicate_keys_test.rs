@@ -0,0 +1,103 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, *};
+use std::cell::RefCell;
+
+pub type MyNode = Node<&'static str, &'static str, String, &'static str, u64>;
+
+fn list(ids: impl IntoIterator<Item = u64>) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        ids.into_iter().enumerate().map(|(index, id)| {
+            element(
+                "li",
+                vec![attr("key", id)],
+                vec![leaf(format!("{id} at {index}"))],
+            )
+        }),
+    )
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn duplicates_are_matched_in_order_by_default() {
+    let old = list([1, 2, 2, 3]);
+    let new = list([2, 3, 2, 1, 2]);
+
+    let warnings = RefCell::new(vec![]);
+    let on_warning = |warning| warnings.borrow_mut().push(warning);
+    let stats = RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        on_warning: Some(&on_warning),
+        stats: Some(&stats),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert!(warnings.into_inner().is_empty());
+    assert_eq!(
+        stats.into_inner().lists[0],
+        (TreePath::root(), ListStrategy::Keyed)
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn duplicates_are_diffed_by_position_with_the_positional_policy() {
+    let old = list([1, 2, 2, 3]);
+    let new = list([2, 3, 2, 1, 2]);
+
+    let warnings = RefCell::new(vec![]);
+    let on_warning = |warning| warnings.borrow_mut().push(warning);
+    let stats = RefCell::new(DiffStats::default());
+    let options = DiffOptions {
+        duplicate_keys: DuplicateKeyPolicy::Positional,
+        on_warning: Some(&on_warning),
+        stats: Some(&stats),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        warnings.into_inner(),
+        vec![DiffWarning::DuplicateKeys {
+            path: TreePath::root()
+        }]
+    );
+    assert_eq!(
+        stats.into_inner().lists[0],
+        (TreePath::root(), ListStrategy::Positional)
+    );
+    assert!(!patches.iter().any(|patch| matches!(
+        patch.patch_type,
+        PatchType::MoveBeforeNode { .. }
+    )));
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn unique_keys_are_still_matched_with_the_positional_policy() {
+    let old = list([1, 2, 3]);
+    let new = list([3, 2, 1]);
+
+    let warnings = RefCell::new(vec![]);
+    let on_warning = |warning| warnings.borrow_mut().push(warning);
+    let options = DiffOptions {
+        duplicate_keys: DuplicateKeyPolicy::Positional,
+        on_warning: Some(&on_warning),
+        key_order: Some(u64::cmp),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert!(warnings.into_inner().is_empty());
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
@@ -0,0 +1,63 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn same_empty_attributes_has_no_patches() {
+    let old: MyNode = element("input", vec![empty_attr("disabled")], vec![]);
+    let new: MyNode = element("input", vec![empty_attr("disabled")], vec![]);
+
+    assert!(old.has_attribute(&"disabled"));
+    assert_eq!(old.attribute_value(&"disabled"), None);
+    assert_eq!(diff_with_key(&old, &new, &"key"), vec![]);
+}
+
+#[test]
+fn toggle_on_empty_attribute() {
+    let old: MyNode = element("input", vec![], vec![]);
+    let new: MyNode = element("input", vec![empty_attr("disabled")], vec![]);
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        diff,
+        vec![Patch::add_attributes(
+            &"input",
+            TreePath::new(vec![]),
+            vec![&empty_attr("disabled")]
+        )]
+    );
+}
+
+#[test]
+fn toggle_off_empty_attribute() {
+    let old: MyNode = element("input", vec![empty_attr("disabled")], vec![]);
+    let new: MyNode = element("input", vec![], vec![]);
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        diff,
+        vec![Patch::remove_attributes(
+            &"input",
+            TreePath::new(vec![]),
+            vec![&empty_attr("disabled")]
+        )]
+    );
+}
+
+#[test]
+fn empty_attribute_given_a_value() {
+    let old: MyNode = element("input", vec![empty_attr("disabled")], vec![]);
+    let new: MyNode = element("input", vec![attr("disabled", "true")], vec![]);
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        diff,
+        vec![Patch::add_attributes(
+            &"input",
+            TreePath::new(vec![]),
+            vec![&attr("disabled", "true")]
+        )]
+    );
+}
@@ -0,0 +1,140 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{codec::*, diff::*, *};
+
+pub type MyNode = Node<String, String, String, String, String>;
+
+fn item(key: usize, text: &str) -> MyNode {
+    element(
+        "li".to_string(),
+        vec![attr("key".to_string(), key.to_string())],
+        vec![leaf(format!("{text} {key}"))],
+    )
+}
+
+fn list(keys: &[usize], text: &str) -> MyNode {
+    element_ns(
+        Some("ns".to_string()),
+        "ul".to_string(),
+        vec![
+            attr("class".to_string(), "list".to_string()),
+            Attribute::empty(None, "hidden".to_string()),
+        ],
+        keys.iter().map(|key| item(*key, text)),
+        false,
+    )
+}
+
+#[test]
+fn encode_and_decode_round_trip() {
+    let node = fragment([list(&[0, 1, 2], "item"), leaf("footer".to_string())]);
+    let bytes = encode_node(&node);
+    assert_eq!(decode_node(&bytes), Ok(node));
+}
+
+#[test]
+fn slotted_elements_round_trip() {
+    let node: MyNode = slotted_element(
+        "split".to_string(),
+        vec![],
+        vec![
+            ("north".to_string(), item(0, "top")),
+            ("south".to_string(), leaf("bottom".to_string())),
+        ],
+    );
+    let bytes = encode_node(&node);
+    assert_eq!(decode_node(&bytes), Ok(node));
+}
+
+#[test]
+fn diff_against_encoded_is_the_same_as_diff() {
+    let old = list(&[0, 1, 2, 3], "item");
+    let new = list(&[3, 1, 4, 0], "entry");
+    let old_bytes = encode_node(&old);
+    let key = "key".to_string();
+    let patches = diff_with_key(&old, &new, &key);
+    let result =
+        diff_against_encoded(&old_bytes, &new, &key, |encoded_patches| {
+            assert_eq!(encoded_patches, patches);
+            let mut root = old.clone();
+            apply_patches(&mut root, &encoded_patches);
+            root
+        });
+    assert_eq!(result, Ok(new));
+}
+
+#[test]
+fn unchanged_tree_has_no_patches() {
+    let node = list(&[0, 1, 2], "item");
+    let bytes = encode_node(&node);
+    let count =
+        diff_against_encoded(&bytes, &node, &"key".to_string(), |patches| {
+            patches.len()
+        });
+    assert_eq!(count, Ok(0));
+}
+
+#[test]
+fn invalid_bytes_are_errors() {
+    let node = list(&[0, 1, 2], "item");
+    let bytes = encode_node(&node);
+    let key = "key".to_string();
+    let truncated =
+        diff_against_encoded(&bytes[..bytes.len() - 1], &node, &key, |_| ());
+    assert_eq!(truncated, Err(DecodeError::UnexpectedEnd));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        decode_node::<String, String, String, String, String>(&trailing),
+        Err(DecodeError::TrailingBytes)
+    );
+
+    let mut invalid_marker = bytes;
+    invalid_marker[0] = 9;
+    assert!(matches!(
+        decode_node::<String, String, String, String, String>(&invalid_marker),
+        Err(DecodeError::Invalid { .. })
+    ));
+}
+
+/// `depth` nested elements around a leaf
+fn chain(depth: usize) -> MyNode {
+    (0..depth).fold(leaf("bottom".to_string()), |child, _| {
+        element("div".to_string(), vec![], vec![child])
+    })
+}
+
+#[test]
+fn deeply_nested_nodes_are_rejected() {
+    let node = chain(MAX_DEPTH - 1);
+    let bytes = encode_node(&node);
+    assert_eq!(decode_node(&bytes), Ok(node));
+
+    let bytes = encode_node(&chain(MAX_DEPTH));
+    assert!(matches!(
+        decode_node::<String, String, String, String, String>(&bytes),
+        Err(DecodeError::Invalid { .. })
+    ));
+
+    // a million nested elements of 6 bytes each
+    let mut bytes = [ELEMENT_BYTES; 1_000_000].concat();
+    bytes.extend([3, 0]);
+    let key = "key".to_string();
+    let result = diff_against_encoded(&bytes, &chain(1), &key, |_| ());
+    assert!(matches!(result, Err(DecodeError::Invalid { .. })));
+}
+
+/// an element without a namespace, tag and attributes which has a single child
+const ELEMENT_BYTES: [u8; 6] = [0, 0, 0, 0, 0, 1];
+
+#[test]
+fn varints() {
+    for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+        let mut buf = vec![];
+        encode_varint(value, &mut buf);
+        let mut bytes = &buf[..];
+        assert_eq!(decode_varint(&mut bytes), Ok(value));
+        assert!(bytes.is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, *};
+use std::cell::Cell;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn rows(texts: [&'static str; 3]) -> MyNode {
+    element(
+        "table",
+        vec![attr("key", "table")],
+        texts.map(|text| {
+            element("tr", vec![attr("key", text)], vec![leaf(text)])
+        }),
+    )
+}
+
+#[test]
+fn every_check_produces_the_same_patches() {
+    let old = rows(["a", "b", "c"]);
+    let new = rows(["a", "x", "c"]);
+    let checks = [
+        EqualityCheck::AfterReplace,
+        EqualityCheck::BeforeReplace,
+        EqualityCheck::Never,
+    ];
+    let expected = diff_with_key(&old, &new, &"key");
+    for equality_check in checks {
+        let options = DiffOptions {
+            equality_check,
+            ..Default::default()
+        };
+        let patches = diff_with_options(&old, &new, &"key", &options);
+        assert_eq!(patches, expected, "{equality_check:?}");
+
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        assert_eq!(root, new);
+    }
+}
+
+#[test]
+fn equal_trees_have_no_patches_without_the_equality_walk() {
+    let old = rows(["a", "b", "c"]);
+    let options = DiffOptions {
+        equality_check: EqualityCheck::Never,
+        ..Default::default()
+    };
+    assert!(diff_with_options(&old, &old.clone(), &"key", &options).is_empty());
+}
+
+#[test]
+fn equal_nodes_past_the_maximum_depth_are_not_replaced() {
+    let old = rows(["a", "b", "c"]);
+    let new = rows(["a", "b", "x"]);
+    let options = DiffOptions {
+        equality_check: EqualityCheck::Never,
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].path(), &TreePath::new([2]));
+}
+
+#[test]
+fn fingerprints_replace_the_equality_walk() {
+    let old = rows(["a", "b", "c"]);
+    let new = rows(["a", "b", "c"]);
+    let calls = Cell::new(0);
+    // every node has the same fingerprint, so the nodes are equal without comparing them
+    let fingerprint = |_: &MyNode| {
+        calls.set(calls.get() + 1);
+        Some(7)
+    };
+    let options = DiffOptions {
+        fingerprint: Some(&fingerprint),
+        ..Default::default()
+    };
+    assert!(diff_with_options(&old, &new, &"key", &options).is_empty());
+    assert_eq!(calls.get(), 2);
+
+    // the fingerprints are trusted over the content of the nodes
+    let changed = rows(["a", "b", "x"]);
+    assert!(diff_with_options(&old, &changed, &"key", &options).is_empty());
+}
@@ -0,0 +1,129 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+use std::collections::BTreeMap;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+/// a minimal applier which only knows how to build the created nodes
+/// from the flat instructions
+fn build_created(
+    instructions: &[Instruction<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >],
+) -> Vec<Vec<MyNode>> {
+    let mut created: BTreeMap<usize, MyNode> = BTreeMap::new();
+    let mut batches = vec![];
+    for instruction in instructions {
+        match instruction {
+            Instruction::CreateElement {
+                id,
+                namespace,
+                tag,
+                self_closing,
+            } => {
+                created.insert(
+                    *id,
+                    Node::Element(Element::new(
+                        namespace.copied(),
+                        **tag,
+                        vec![],
+                        vec![],
+                        *self_closing,
+                    )),
+                );
+            }
+            Instruction::CreateLeaf { id, leaf } => {
+                created.insert(*id, Node::Leaf(**leaf));
+            }
+            Instruction::SetAttribute { id, attr } => {
+                created
+                    .get_mut(id)
+                    .unwrap()
+                    .add_attributes(vec![(*attr).clone()])
+                    .unwrap();
+            }
+            Instruction::AppendChild { parent, child } => {
+                let child = created.remove(child).unwrap();
+                created
+                    .get_mut(parent)
+                    .unwrap()
+                    .add_children(vec![child])
+                    .unwrap();
+            }
+            Instruction::InsertBefore { ids, .. }
+            | Instruction::InsertAfter { ids, .. }
+            | Instruction::Append { ids, .. }
+            | Instruction::Replace { ids, .. } => {
+                batches.push(
+                    ids.iter().map(|id| created.remove(id).unwrap()).collect(),
+                );
+            }
+            Instruction::Patch(_) => (),
+        }
+    }
+    assert!(created.is_empty());
+    batches
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn nested_creations_are_flattened() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("div", vec![attr("key", "1")], vec![]),
+            element("div", vec![attr("key", "3")], vec![]),
+        ],
+    );
+    let inserted: MyNode = element(
+        "div",
+        vec![attr("key", "2"), attr("class", "new")],
+        vec![
+            element("ul", vec![], vec![element("li", vec![], vec![leaf("a")])]),
+            leaf("b"),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![attr("class", "changed")],
+        vec![
+            element("div", vec![attr("key", "1")], vec![]),
+            inserted.clone(),
+            element("div", vec![attr("key", "3")], vec![]),
+        ],
+    );
+
+    let patches = diff_with_key(&old, &new, &"key");
+    let instructions = expand_creations(&patches);
+    assert_eq!(build_created(&instructions), vec![vec![inserted]]);
+    assert!(instructions.iter().any(|i| matches!(
+        i,
+        Instruction::Patch(Patch {
+            patch_type: PatchType::AddAttributes { .. },
+            ..
+        })
+    )));
+}
+
+#[test]
+fn fragments_are_unrolled() {
+    let old: MyNode = element("main", vec![], vec![leaf("old")]);
+    let replacement: MyNode =
+        fragment(vec![leaf("a"), element("b", vec![], vec![])]);
+    let patches = vec![Patch::replace_node(
+        None,
+        TreePath::new([0]),
+        vec![&replacement],
+    )];
+    assert_eq!(validate(&old, &patches), Ok(()));
+    assert_eq!(
+        build_created(&expand_creations(&patches)),
+        vec![vec![leaf("a"), element("b", vec![], vec![])]]
+    );
+}
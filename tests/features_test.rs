@@ -0,0 +1,45 @@
+#![deny(warnings)]
+//! this test is also run with `--no-default-features`
+use mt_dom::*;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn list(keys: &[&'static str]) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        keys.iter().map(|key| {
+            element("li", vec![attr("key", *key)], vec![leaf(*key)])
+        }),
+    )
+}
+
+#[test]
+fn keyed_children_are_moved_only_with_the_keyed_feature() {
+    let old = list(&["1", "2", "3", "4"]);
+    let new = list(&["4", "3", "2", "1"]);
+    let patches = diff_with_key(&old, &new, &"key");
+    let moved = patches.iter().any(|patch| {
+        matches!(
+            patch.patch_type,
+            PatchType::MoveBeforeNode { .. } | PatchType::MoveAfterNode { .. }
+        )
+    });
+    assert_eq!(moved, cfg!(feature = "keyed"));
+    if !cfg!(feature = "keyed") {
+        // the children are diffed by their position
+        assert_eq!(patches.len(), 4);
+    }
+}
+
+#[cfg(feature = "apply-patches")]
+#[test]
+fn patches_are_applied_with_the_apply_patches_feature() {
+    let old = list(&["1", "2", "3", "4"]);
+    let new = list(&["4", "3", "5"]);
+    let patches = diff_with_key(&old, &new, &"key");
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
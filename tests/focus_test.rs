@@ -0,0 +1,92 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+type MyPatch<'a> = Patch<
+    'a,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+>;
+
+fn form(fields: &[&'static str]) -> MyNode {
+    element(
+        "form",
+        vec![],
+        vec![element(
+            "fieldset",
+            vec![],
+            fields.iter().map(|name| {
+                element("input", vec![attr("name", *name)], vec![])
+            }),
+        )],
+    )
+}
+
+#[test]
+fn removed_element_and_its_descendants() {
+    let old = form(&["first", "second"]);
+    let new = form(&["first"]);
+
+    let patches = diff_with_key(&old, &new, &"key");
+    assert!(will_remove(&TreePath::new([0, 1]), &patches));
+    assert!(!will_replace(&TreePath::new([0, 1]), &patches));
+    assert!(!will_remove(&TreePath::new([0, 0]), &patches));
+    assert!(!will_remove(&TreePath::new([0]), &patches));
+
+    let patches: Vec<MyPatch> =
+        vec![Patch::remove_node(Some(&"fieldset"), TreePath::new([0]))];
+    assert!(will_remove(&TreePath::new([0, 0]), &patches));
+    assert!(will_remove(&TreePath::new([0]), &patches));
+    assert!(!will_remove(&TreePath::root(), &patches));
+}
+
+#[test]
+fn replaced_ancestor_replaces_the_element() {
+    let old = form(&["first", "second"]);
+    let new: MyNode =
+        element("form", vec![], vec![element("div", vec![], vec![])]);
+
+    let patches = diff_with_key(&old, &new, &"key");
+    assert!(will_replace(&TreePath::new([0, 1]), &patches));
+    assert!(will_replace(&TreePath::new([0]), &patches));
+    assert!(!will_remove(&TreePath::new([0, 1]), &patches));
+    assert!(!will_replace(&TreePath::root(), &patches));
+}
+
+#[test]
+fn unwrapped_element_is_removed() {
+    let patches: Vec<MyPatch> =
+        vec![Patch::unwrap_node(Some(&"fieldset"), TreePath::new([0]))];
+    assert!(will_remove(&TreePath::new([0]), &patches));
+    // the children of the unwrapped node are kept
+    assert!(!will_remove(&TreePath::new([0, 0]), &patches));
+}
+
+#[test]
+fn leaves_in_a_changed_run_are_replaced() {
+    let patches: Vec<MyPatch> = vec![Patch::change_leaf_run(
+        TreePath::new([1]),
+        2,
+        vec![&"joined"],
+    )];
+    assert!(!will_replace(&TreePath::new([0]), &patches));
+    assert!(will_replace(&TreePath::new([1]), &patches));
+    assert!(will_replace(&TreePath::new([2]), &patches));
+    assert!(!will_replace(&TreePath::new([3]), &patches));
+}
+
+#[test]
+fn attribute_changes_keep_the_element() {
+    let old = form(&["first"]);
+    let new = form(&["renamed"]);
+
+    let patches = diff_with_key(&old, &new, &"key");
+    assert!(!patches.is_empty());
+    assert!(!will_remove(&TreePath::new([0, 0]), &patches));
+    assert!(!will_replace(&TreePath::new([0, 0]), &patches));
+}
@@ -0,0 +1,118 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, testing::assert_algorithms_agree, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn table(theme: &'static str, rows: usize) -> MyNode {
+    element(
+        "table",
+        vec![],
+        (0..rows).map(|_| {
+            element("tr", vec![attr("class", theme)], vec![leaf("cell")])
+        }),
+    )
+}
+
+fn options() -> DiffOptions<
+    'static,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+> {
+    DiffOptions {
+        for_each_child: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn same_attribute_patch_on_every_row() {
+    let old = table("light", 100);
+    let new = table("dark", 100);
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    let expected_attr = attr("class", "dark");
+    assert_eq!(
+        patches,
+        vec![Patch::for_each_child(
+            Some(&"table"),
+            TreePath::root(),
+            PatchType::AddAttributes {
+                attrs: vec![&expected_attr]
+            },
+        )]
+    );
+    assert_eq!(PatchBatchSummary::from(patches.as_slice()).attr, 1);
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+    assert_algorithms_agree(&old, &new, &"key");
+}
+
+#[test]
+fn removed_attribute_on_every_row() {
+    let old = table("light", 3);
+    let new: MyNode = element(
+        "table",
+        vec![],
+        (0..3).map(|_| element("tr", vec![], vec![leaf("cell")])),
+    );
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    assert_eq!(patches.len(), 1);
+    assert!(matches!(
+        &patches[0].patch_type,
+        PatchType::ForEachChild { patch } if matches!(**patch, PatchType::RemoveAttributes { .. })
+    ));
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn not_grouped_when_a_row_differs() {
+    let old = table("light", 3);
+    let new: MyNode = element(
+        "table",
+        vec![],
+        vec![
+            element("tr", vec![attr("class", "dark")], vec![leaf("cell")]),
+            element("tr", vec![attr("class", "light")], vec![leaf("cell")]),
+            element("tr", vec![attr("class", "dark")], vec![leaf("cell")]),
+        ],
+    );
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+}
+
+#[test]
+fn not_grouped_when_rows_are_inserted() {
+    let old = table("light", 3);
+    let new = table("dark", 4);
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn deeper_patches_are_kept() {
+    let old = table("light", 3);
+    let new: MyNode = element(
+        "table",
+        vec![],
+        (0..3).map(|i| {
+            let text = if i == 1 { "changed" } else { "cell" };
+            element("tr", vec![attr("class", "dark")], vec![leaf(text)])
+        }),
+    );
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    assert_eq!(patches.len(), 2);
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
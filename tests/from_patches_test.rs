@@ -0,0 +1,111 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn owned(
+    old: &MyNode,
+    new: &MyNode,
+) -> Vec<
+    OwnedPatch<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >,
+> {
+    diff_with_key(old, new, &"key")
+        .into_iter()
+        .map(Patch::into_owned)
+        .collect()
+}
+
+#[test]
+fn bootstrap_then_updates() {
+    let frames: Vec<MyNode> = vec![
+        element("ul", vec![], vec![]),
+        element(
+            "ul",
+            vec![attr("class", "list")],
+            vec![
+                element("li", vec![attr("key", "1")], vec![leaf("one")]),
+                element("li", vec![attr("key", "2")], vec![leaf("two")]),
+            ],
+        ),
+        element(
+            "ul",
+            vec![attr("class", "list")],
+            vec![
+                element("li", vec![attr("key", "2")], vec![leaf("two")]),
+                element("li", vec![attr("key", "3")], vec![leaf("three")]),
+                element("li", vec![attr("key", "1")], vec![leaf("one")]),
+            ],
+        ),
+        element("ol", vec![], vec![leaf("done")]),
+    ];
+    let mut tree =
+        Node::from_patches(None, &[OwnedPatch::bootstrap(frames[0].clone())]);
+    assert_eq!(tree, frames[0]);
+    for pair in frames.windows(2) {
+        tree = Node::from_patches(Some(tree), &owned(&pair[0], &pair[1]));
+        assert_eq!(tree, pair[1]);
+    }
+}
+
+#[test]
+fn empty_batch_keeps_the_base() {
+    let base: MyNode = element("div", vec![], vec![leaf("text")]);
+    let tree = Node::from_patches(Some(base.clone()), &[]);
+    assert_eq!(tree, base);
+
+    let empty: MyNode = Node::from_patches(None, &[]);
+    assert_eq!(empty, fragment([]));
+}
+
+#[test]
+fn owned_patches_borrow_back_to_the_same_patches() {
+    let old: MyNode = element(
+        "main",
+        vec![attr("theme", "light")],
+        vec![element(
+            "div",
+            vec![attr("theme", "light")],
+            vec![leaf("a")],
+        )],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("div", vec![attr("theme", "light")], vec![leaf("b")]),
+            leaf("c"),
+        ],
+    );
+    let patches = diff_with_key(&old, &new, &"key");
+    let owned: Vec<_> =
+        patches.iter().cloned().map(Patch::into_owned).collect();
+    let borrowed: Vec<_> = owned.iter().map(OwnedPatch::as_patch).collect();
+    assert_eq!(borrowed, patches);
+
+    let dark = attr("theme", "dark");
+    let broadcast = Patch::broadcast(
+        Some(&"main"),
+        TreePath::root(),
+        Selector::HasAttribute(&"theme"),
+        PatchType::AddAttributes { attrs: vec![&dark] },
+    );
+    let owned = broadcast.clone().into_owned();
+    assert_eq!(owned.as_patch(), broadcast);
+    let tree = Node::from_patches(Some(old), &[owned]);
+    assert_eq!(
+        tree,
+        element(
+            "main",
+            vec![attr("theme", "dark")],
+            vec![element("div", vec![attr("theme", "dark")], vec![leaf("a")])],
+        )
+    );
+}
@@ -0,0 +1,161 @@
+#![deny(warnings)]
+#![cfg(feature = "html")]
+use mt_dom::{html::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn escape_text_and_attributes() {
+    let node: MyNode = element(
+        "a",
+        vec![attr("title", "say \"<hi>\" & go")],
+        vec![leaf("1 < 2 & \"quoted\"")],
+    );
+    assert_eq!(
+        node_to_html_string(&node, false),
+        r#"<a title="say &quot;&lt;hi&gt;&quot; &amp; go">1 &lt; 2 &amp; "quoted"</a>"#
+    );
+}
+
+#[test]
+fn merge_attributes_with_the_same_name() {
+    let node: MyNode = element(
+        "input",
+        vec![
+            attr("class", "big"),
+            empty_attr("disabled"),
+            attr("class", "red"),
+        ],
+        vec![],
+    );
+    assert_eq!(
+        node_to_html_string(&node, false),
+        r#"<input class="big red" disabled>"#
+    );
+}
+
+#[test]
+fn self_closing_and_namespaces() {
+    let svg = "http://www.w3.org/2000/svg";
+    let node: MyNode = element(
+        "div",
+        vec![],
+        vec![element_ns(
+            Some(svg),
+            "svg",
+            vec![],
+            vec![
+                element_ns(
+                    Some(svg),
+                    "circle",
+                    vec![attr("r", "4")],
+                    vec![],
+                    true,
+                ),
+                element_ns(Some(svg), "g", vec![], vec![], false),
+            ],
+            false,
+        )],
+    );
+    assert_eq!(
+        node_to_html_string(&node, false),
+        concat!(
+            "<div>",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\">",
+            "<circle r=\"4\"/>",
+            "<g></g>",
+            "</svg>",
+            "</div>"
+        )
+    );
+}
+
+#[test]
+fn fragments_and_node_lists_are_inlined() {
+    let node: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            fragment(vec![element("li", vec![], vec![leaf("a")])]),
+            node_list(vec![
+                element("li", vec![], vec![leaf("b")]),
+                element("li", vec![], vec![leaf("c")]),
+            ]),
+        ],
+    );
+    assert_eq!(
+        node_to_html_string(&node, false),
+        "<ul><li>a</li><li>b</li><li>c</li></ul>"
+    );
+    assert_eq!(
+        node_to_html_string(&node, true),
+        concat!(
+            "<ul>\n",
+            "  <li>\n",
+            "    a\n",
+            "  </li>\n",
+            "  <li>\n",
+            "    b\n",
+            "  </li>\n",
+            "  <li>\n",
+            "    c\n",
+            "  </li>\n",
+            "</ul>\n"
+        )
+    );
+}
+
+#[test]
+fn deep_tree() {
+    let node: MyNode = (0..1000)
+        .fold(leaf("deep"), |child, _| element("div", vec![], vec![child]));
+    let html = node_to_html_string(&node, false);
+    assert!(html.starts_with("<div><div>"));
+    assert_eq!(html.len(), 1000 * "<div></div>".len() + "deep".len());
+}
+
+#[test]
+fn raw_text_is_not_escaped() {
+    let node: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            element(
+                "script",
+                vec![],
+                vec![leaf("if (a < b && c) { f(\"x\"); }")],
+            ),
+            element("style", vec![], vec![leaf("ul > li { color: red; }")]),
+            element("p", vec![], vec![leaf("a < b && c")]),
+        ],
+    );
+    assert_eq!(
+        node_to_html_string(&node, false),
+        r#"<div><script>if (a < b && c) { f("x"); }</script><style>ul > li { color: red; }</style><p>a &lt; b &amp;&amp; c</p></div>"#
+    );
+}
+
+#[cfg(feature = "html-parser")]
+#[test]
+fn serialized_raw_text_is_parsed_back() {
+    use mt_dom::parse::*;
+    let node: Node<String, String, String, String, String> = element(
+        "div".to_string(),
+        vec![],
+        vec![
+            element(
+                "script".to_string(),
+                vec![],
+                vec![leaf("if (a < b && c) { f(\"&amp;\"); }".to_string())],
+            ),
+            element(
+                "p".to_string(),
+                vec![],
+                vec![leaf("a < b && \"&amp;\"".to_string())],
+            ),
+        ],
+    );
+    let html = node_to_html_string(&node, false);
+    assert_eq!(parse_owned(&html, &ParseOptions::default()), Ok(vec![node]));
+}
@@ -0,0 +1,157 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn is_event(name: &&'static str) -> bool {
+    name.starts_with("on")
+}
+
+fn hydrate<'a>(
+    old: &'a MyNode,
+    new: &'a MyNode,
+) -> Vec<
+    Patch<
+        'a,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >,
+> {
+    let options = DiffOptions {
+        hydrate: Some(&is_event),
+        ..Default::default()
+    };
+    diff_with_options(old, new, &"key", &options)
+}
+
+#[test]
+fn only_the_event_attributes_are_patched() {
+    // the markup rendered by the server, without the event listeners
+    let old: MyNode = element(
+        "main",
+        vec![attr("class", "server")],
+        vec![
+            element(
+                "button",
+                vec![attr("class", "primary")],
+                vec![leaf("save")],
+            ),
+            element("input", vec![], vec![]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![attr("class", "client")],
+        vec![
+            element(
+                "button",
+                vec![attr("class", "secondary"), attr("onclick", "save")],
+                vec![leaf("Save")],
+            ),
+            element("input", vec![attr("oninput", "update")], vec![]),
+        ],
+    );
+
+    let patches = hydrate(&old, &new);
+    assert_eq!(
+        patches,
+        vec![
+            Patch::add_attributes(
+                &"button",
+                TreePath::new([0]),
+                vec![&attr("onclick", "save")]
+            ),
+            Patch::add_attributes(
+                &"input",
+                TreePath::new([1]),
+                vec![&attr("oninput", "update")]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn structural_changes_are_suppressed() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![element("li", vec![], vec![leaf("server")])],
+    );
+    let new: MyNode = element(
+        "ol",
+        vec![attr("onclick", "select")],
+        vec![
+            element("li", vec![attr("onclick", "first")], vec![]),
+            element("li", vec![attr("onclick", "second")], vec![]),
+        ],
+    );
+
+    let patches = hydrate(&old, &new);
+    assert_eq!(
+        patches,
+        vec![
+            Patch::add_attributes(
+                &"ul",
+                TreePath::root(),
+                vec![&attr("onclick", "select")]
+            ),
+            Patch::add_attributes(
+                &"li",
+                TreePath::new([0]),
+                vec![&attr("onclick", "first")]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn stale_event_attributes_are_removed() {
+    let old: MyNode = element(
+        "div",
+        vec![attr("onclick", "stale"), attr("class", "server")],
+        vec![],
+    );
+    let new: MyNode = element("div", vec![], vec![]);
+
+    let patches = hydrate(&old, &new);
+    assert_eq!(
+        patches,
+        vec![Patch::remove_attributes(
+            &"div",
+            TreePath::root(),
+            vec![&attr("onclick", "stale")]
+        )]
+    );
+}
+
+#[test]
+fn hydrated_tree_matches_the_client_attributes() {
+    let old: MyNode = element(
+        "div",
+        vec![],
+        vec![element(
+            "a",
+            vec![attr("onclick", "old")],
+            vec![leaf("link")],
+        )],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![],
+        vec![element(
+            "a",
+            vec![attr("onclick", "new")],
+            vec![leaf("link")],
+        )],
+    );
+
+    let patches = hydrate(&old, &new);
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
@@ -0,0 +1,160 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn page(embed: &'static str, footer: &'static str) -> MyNode {
+    element(
+        "main",
+        vec![],
+        vec![
+            element("div", vec![attr("class", "embed")], vec![leaf(embed)]),
+            element(
+                "div",
+                vec![attr("contenteditable", "true")],
+                vec![leaf(embed)],
+            ),
+            element("footer", vec![], vec![leaf(footer)]),
+        ],
+    )
+}
+
+#[test]
+fn ignored_paths_never_produce_patches() {
+    let old = page("third party", "old");
+    let new = page("changed externally", "new");
+
+    let ignore_paths = [TreePath::new([0]), TreePath::new([1])];
+    let options = DiffOptions {
+        ignore_paths: &ignore_paths,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::replace_node(
+            None,
+            TreePath::new([2, 0]),
+            vec![&leaf("new")]
+        )]
+    );
+}
+
+#[test]
+fn ignored_by_predicate() {
+    let old = page("third party", "old");
+    let new = page("changed externally", "old");
+
+    let is_editable =
+        |node: &MyNode| node.attribute_value(&"contenteditable").is_some();
+    let options = DiffOptions {
+        is_ignored: Some(&is_editable),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::replace_node(
+            None,
+            TreePath::new([0, 0]),
+            vec![&leaf("changed externally")]
+        )]
+    );
+}
+
+fn item(key: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(key)])
+}
+
+fn island(key: &'static str, content: &'static str) -> MyNode {
+    element(
+        "li",
+        vec![attr("key", key), attr("contenteditable", "true")],
+        vec![leaf(content)],
+    )
+}
+
+fn is_editable(node: &MyNode) -> bool {
+    node.attribute_value(&"contenteditable").is_some()
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn sync_island_when_siblings_change_shape() {
+    let old: MyNode =
+        element("ul", vec![], vec![item("1"), island("2", "a"), item("3")]);
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![item("0"), item("1"), island("2", "b"), item("3")],
+    );
+    let options = DiffOptions {
+        is_ignored: Some(&is_editable),
+        sync_islands: true,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![
+            Patch::insert_before_node(
+                Some(&"li"),
+                TreePath::new([0]),
+                vec![&new.children()[0]]
+            ),
+            Patch::sync_island(Some(&"li"), TreePath::new([1])),
+        ]
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    // the content of the island is left as is
+    assert_eq!(
+        root,
+        element(
+            "ul",
+            vec![],
+            vec![item("0"), item("1"), island("2", "a"), item("3")]
+        )
+    );
+}
+
+#[test]
+fn no_sync_island_when_shape_is_unchanged() {
+    let old: MyNode =
+        element("ul", vec![], vec![item("1"), island("2", "a"), item("3")]);
+    let new: MyNode = element(
+        "ul",
+        vec![attr("class", "list")],
+        vec![item("1"), island("2", "b"), item("3"), item("4")],
+    );
+    let options = DiffOptions {
+        is_ignored: Some(&is_editable),
+        sync_islands: true,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert!(patches
+        .iter()
+        .all(|patch| patch.patch_type != PatchType::SyncIsland));
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn removed_island_is_not_synced() {
+    let old: MyNode =
+        element("ul", vec![], vec![item("1"), island("2", "a"), item("3")]);
+    let new: MyNode = element("ul", vec![], vec![item("1"), item("3")]);
+    let options = DiffOptions {
+        is_ignored: Some(&is_editable),
+        sync_islands: true,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::remove_node(Some(&"li"), TreePath::new([1]))]
+    );
+}
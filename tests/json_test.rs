@@ -0,0 +1,178 @@
+#![deny(warnings)]
+#![cfg(feature = "json")]
+use mt_dom::{patch::*, *};
+use serde_json::json;
+
+pub type MyNode = Node<String, String, String, String, String>;
+pub type MyOwnedPatch = OwnedPatch<String, String, String, String, String>;
+
+fn s(text: &str) -> String {
+    text.to_string()
+}
+
+fn list(items: &[&str]) -> MyNode {
+    element(
+        s("ul"),
+        vec![attr(s("class"), s("list"))],
+        items.iter().map(|item| {
+            element(s("li"), vec![attr(s("key"), s(item))], vec![leaf(s(item))])
+        }),
+    )
+}
+
+#[test]
+fn schema_of_the_patches() {
+    let li = element(s("li"), vec![], vec![leaf(s("x"))]);
+    let class = attr(s("class"), s("big"));
+    let (ul, old, new) = (s("ul"), s("a"), s("b"));
+    let patches: Vec<Patch<String, String, String, String, String>> = vec![
+        Patch::append_children(Some(&ul), TreePath::new([0]), vec![&li]),
+        Patch::move_before_node(
+            Some(&ul),
+            TreePath::new([0, 2]),
+            [TreePath::new([0, 0]), TreePath::new([0, 1])],
+        ),
+        Patch::change_leaf(TreePath::new([1, 0]), &old, &new),
+        Patch::add_attributes(&ul, TreePath::root(), vec![&class]),
+        Patch::remove_node(None, TreePath::new([3])),
+    ];
+    assert_eq!(
+        to_json(&patches),
+        json!([
+            {
+                "op": "append_children",
+                "path": [0],
+                "tag": "ul",
+                "children": [{"Element": {
+                    "namespace": null,
+                    "tag": "li",
+                    "attrs": [],
+                    "children": [{"Leaf": "x"}],
+                    "self_closing": false,
+                }}],
+            },
+            {
+                "op": "move_before_node",
+                "path": [0, 2],
+                "tag": "ul",
+                "nodes_path": [[0, 0], [0, 1]],
+            },
+            {
+                "op": "change_leaf",
+                "path": [1, 0],
+                "tag": null,
+                "old": "a",
+                "new": "b",
+            },
+            {
+                "op": "add_attributes",
+                "path": [],
+                "tag": "ul",
+                "attrs": [{"namespace": null, "name": "class", "value": ["big"]}],
+            },
+            {"op": "remove_node", "path": [3], "tag": null},
+        ])
+    );
+}
+
+#[test]
+fn diffed_patches_round_trip() {
+    let old = list(&["1", "2", "3", "4"]);
+    let new = list(&["4", "1", "5", "3"]);
+    let patches = diff_with_key(&old, &new, &s("key"));
+    let json = to_json(&patches);
+
+    let decoded: Vec<MyOwnedPatch> = from_json(&json).unwrap();
+    let owned: Vec<_> = patches.into_iter().map(Patch::into_owned).collect();
+    assert_eq!(decoded, owned);
+    assert_eq!(Node::from_patches(Some(old), &decoded), new);
+
+    // the JSON is sent as text to the client
+    let text = serde_json::to_string(&json).unwrap();
+    let received: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        from_json::<String, String, String, String, String>(&received),
+        Ok(owned)
+    );
+}
+
+#[test]
+fn nested_patches_round_trip() {
+    let dark = attr(s("theme"), s("dark"));
+    let name = s("theme");
+    let patch: Patch<String, String, String, String, String> = Patch::broadcast(
+        None,
+        TreePath::new([0, 1]),
+        Selector::HasAttribute(&name),
+        PatchType::ForEachChild {
+            patch: Box::new(PatchType::AddAttributes { attrs: vec![&dark] }),
+        },
+    );
+    let json = to_json(std::slice::from_ref(&patch));
+    assert_eq!(
+        json[0],
+        json!({
+            "op": "broadcast",
+            "path": [0, 1],
+            "tag": null,
+            "selector": {"HasAttribute": "theme"},
+            "patch": {
+                "op": "for_each_child",
+                "patch": {
+                    "op": "add_attributes",
+                    "attrs": [{"namespace": null, "name": "theme", "value": ["dark"]}],
+                },
+            },
+        })
+    );
+    assert_eq!(from_json(&json), Ok(vec![patch.into_owned()]));
+}
+
+#[test]
+fn errors() {
+    let read =
+        |json| from_json::<String, String, String, String, String>(&json);
+    assert_eq!(
+        read(json!({"op": "remove_node"})),
+        Err(JsonPatchError::NotAnArray)
+    );
+    assert_eq!(
+        read(json!([1])),
+        Err(JsonPatchError::NotAnObject { index: 0 })
+    );
+    assert_eq!(
+        read(json!([
+            {"op": "remove_node", "path": [], "tag": null},
+            {"op": "explode", "path": [], "tag": null},
+        ])),
+        Err(JsonPatchError::UnknownOp {
+            index: 1,
+            op: s("explode"),
+        })
+    );
+    assert_eq!(
+        read(json!([{"op": "remove_node", "tag": null}])),
+        Err(JsonPatchError::MissingField {
+            index: 0,
+            field: "path",
+        })
+    );
+    let error = read(json!([{"op": "remove_node", "path": [-1], "tag": null}]))
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        JsonPatchError::InvalidField {
+            index: 0,
+            field: "path",
+            ..
+        }
+    ));
+    assert_eq!(
+        JsonPatchError::MissingField {
+            index: 2,
+            field: "nodes",
+        }
+        .to_string(),
+        "the patch at 2 has no `nodes`"
+    );
+}
@@ -0,0 +1,99 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{apply_patches::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn item(key: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(key)])
+}
+
+fn apply(old: &MyNode, new: &MyNode) -> KeyIndex<&'static str, &'static str> {
+    let patches = diff_with_key(old, new, &"key");
+    let mut root = old.clone();
+    let mut index = KeyIndex::new(old, "key");
+    apply_patches_with_index(&mut root, &patches, &mut index);
+    assert_eq!(&root, new);
+    assert_eq!(index, KeyIndex::new(new, "key"));
+    index
+}
+
+#[test]
+fn moved_and_inserted_nodes_are_indexed() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("header", vec![attr("key", "header")], vec![]),
+            element("ul", vec![], vec![item("1"), item("2"), item("3")]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("header", vec![attr("key", "header")], vec![]),
+            element(
+                "ul",
+                vec![],
+                vec![item("3"), item("1"), item("4"), item("2")],
+            ),
+        ],
+    );
+    let index = apply(&old, &new);
+    assert_eq!(index.len(), 5);
+    assert_eq!(index.get(&["header"]), Some(&TreePath::new([0])));
+    assert_eq!(index.get(&["3"]), Some(&TreePath::new([1, 0])));
+    assert_eq!(index.get(&["4"]), Some(&TreePath::new([1, 2])));
+    assert_eq!(index.get(&["2"]), Some(&TreePath::new([1, 3])));
+}
+
+#[test]
+fn changed_keys_are_indexed() {
+    let old: MyNode = element("div", vec![attr("key", "old")], vec![]);
+    let new: MyNode =
+        element("div", vec![attr("key", "old"), attr("class", "a")], vec![]);
+    let index = apply(&old, &new);
+    assert_eq!(index.get(&["old"]), Some(&TreePath::root()));
+
+    let old: MyNode = element("ul", vec![], vec![item("1")]);
+    let new: MyNode = element("ul", vec![], vec![item("2")]);
+    let index = apply(&old, &new);
+    assert_eq!(index.get(&["1"]), None);
+    assert_eq!(index.key_at(&TreePath::new([0])), Some(&["2"][..]));
+}
+
+#[test]
+fn a_replaced_root_is_indexed_again() {
+    let old: MyNode = element("ul", vec![], vec![item("1")]);
+    let new: MyNode = element("ol", vec![], vec![item("1"), item("2")]);
+    let index = apply(&old, &new);
+    assert_eq!(index.get(&["2"]), Some(&TreePath::new([1])));
+}
+
+#[test]
+fn duplicate_keys_are_all_indexed() {
+    let old: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            element("ul", vec![], vec![item("1")]),
+            element("ul", vec![], vec![item("2")]),
+        ],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            element("ul", vec![], vec![item("1")]),
+            element("ul", vec![], vec![item("2"), item("1")]),
+        ],
+    );
+    let index = apply(&old, &new);
+    assert_eq!(index.get(&["1"]), Some(&TreePath::new([0, 0])));
+    assert_eq!(
+        index.get_all(&["1"]),
+        &[TreePath::new([0, 0]), TreePath::new([1, 1])]
+    );
+}
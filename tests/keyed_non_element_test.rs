@@ -0,0 +1,81 @@
+#![deny(warnings)]
+#![cfg(all(feature = "keyed", feature = "apply-patches"))]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+/// the leaves are keyed by their text, the elements by their `key` attribute
+fn key_of(node: &MyNode) -> Option<Vec<&&'static str>> {
+    match node {
+        Node::Leaf(leaf) => Some(vec![leaf]),
+        _ => node.attribute_value(&"key"),
+    }
+}
+
+fn assert_round_trip(
+    old: &MyNode,
+    new: &MyNode,
+    patches: &[Patch<&str, &str, &str, &str, &str>],
+) {
+    let mut root = old.clone();
+    apply_patches(&mut root, patches);
+    assert_eq!(&root, new);
+}
+
+#[test]
+fn keyed_leaves_are_moved() {
+    let old: MyNode =
+        element("div", vec![], vec![leaf("a"), leaf("b"), leaf("c")]);
+    let new: MyNode =
+        element("div", vec![], vec![leaf("c"), leaf("a"), leaf("b")]);
+
+    let options = DiffOptions {
+        key_of: Some(&key_of),
+        ..Default::default()
+    };
+    let diff = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        diff,
+        vec![Patch::move_before_node(
+            None,
+            TreePath::new([0]),
+            [TreePath::new([2])]
+        )]
+    );
+    assert_round_trip(&old, &new, &diff);
+
+    // without the key_of, the leaves are replaced in place
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_eq!(diff.len(), 3);
+    assert_round_trip(&old, &new, &diff);
+}
+
+#[test]
+fn mixed_keyed_leaves_and_elements() {
+    let old: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            leaf("a"),
+            element("span", vec![attr("key", "s")], vec![leaf("x")]),
+            leaf("b"),
+        ],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            leaf("b"),
+            leaf("new"),
+            element("span", vec![attr("key", "s")], vec![leaf("y")]),
+        ],
+    );
+
+    let options = DiffOptions {
+        key_of: Some(&key_of),
+        ..Default::default()
+    };
+    let diff = diff_with_options(&old, &new, &"key", &options);
+    assert_round_trip(&old, &new, &diff);
+}
@@ -0,0 +1,83 @@
+#![deny(warnings)]
+#![cfg(all(feature = "keyed", feature = "apply-patches"))]
+use mt_dom::{diff::*, *};
+use std::cell::RefCell;
+
+pub type MyNode = Node<&'static str, &'static str, String, &'static str, u64>;
+
+fn list(ids: impl IntoIterator<Item = u64>) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        ids.into_iter().map(|id| {
+            element("li", vec![attr("key", id)], vec![leaf(format!("{id}"))])
+        }),
+    )
+}
+
+#[test]
+fn exceeding_the_limit_replaces_the_middle() {
+    let old = list(0..100);
+    let new = list((0..100).rev());
+
+    let warnings = RefCell::new(vec![]);
+    let on_warning = |warning| warnings.borrow_mut().push(warning);
+    let options = DiffOptions {
+        keyed_scan_limit: Some(10),
+        on_warning: Some(&on_warning),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        warnings.into_inner(),
+        vec![DiffWarning::KeyedScanLimitExceeded {
+            path: TreePath::root(),
+            old_len: 100,
+            new_len: 100,
+        }]
+    );
+    // 99 removes and a single replace, no moves
+    assert_eq!(patches.len(), 100);
+    assert!(patches.iter().all(|p| matches!(
+        p.patch_type,
+        PatchType::RemoveNode
+    ) || matches!(
+        p.patch_type,
+        PatchType::ReplaceNode { .. }
+    )));
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn within_the_limit_matches_the_keys() {
+    let old = list(0..100);
+    let new = list((0..100).rev());
+
+    let warnings = RefCell::new(vec![]);
+    let on_warning = |warning| warnings.borrow_mut().push(warning);
+    let options = DiffOptions {
+        keyed_scan_limit: Some(100),
+        on_warning: Some(&on_warning),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert!(warnings.into_inner().is_empty());
+    assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+}
+
+#[test]
+fn only_the_unmatched_middle_is_counted() {
+    // the common prefix and suffix are not counted against the limit
+    let old = list(0..100);
+    let new = list((0..48).chain([49, 48]).chain(50..100));
+
+    let options = DiffOptions {
+        keyed_scan_limit: Some(2),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+}
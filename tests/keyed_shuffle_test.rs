@@ -0,0 +1,124 @@
+#![deny(warnings)]
+#![cfg(all(feature = "keyed", feature = "apply-patches"))]
+use mt_dom::{patch::*, testing::assert_algorithms_agree, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+/// a small linear congruential generator, so the scenarios are reproducible
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound
+    }
+}
+
+fn item(key: usize, content: &str) -> MyNode {
+    element(
+        "li",
+        vec![attr("key", key.to_string())],
+        vec![leaf(format!("{content}{key}"))],
+    )
+}
+
+fn list(items: impl IntoIterator<Item = MyNode>) -> MyNode {
+    element("ul", vec![attr("class", "list".to_string())], items)
+}
+
+fn assert_round_trip(old: &MyNode, new: &MyNode) {
+    let patches = diff_with_key(old, new, &"key");
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new, "patches: {:#?}", patches);
+}
+
+#[test]
+fn move_and_insert_after_the_last_stable_node() {
+    let old = list((0..6).map(|i| item(i, "line")));
+    let new = list(
+        [0, 2, 3, 100, 1, 101, 4, 5]
+            .into_iter()
+            .map(|i| item(i, "line")),
+    );
+    assert_round_trip(&old, &new);
+}
+
+#[test]
+fn moves_are_anchored_to_stable_nodes() {
+    let old = list((0..6).map(|i| item(i, "line")));
+    let new = list([0, 4, 2, 3, 1, 5].into_iter().map(|i| item(i, "line")));
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        patches,
+        vec![
+            Patch::move_before_node(
+                Some(&"li"),
+                TreePath::new([2]),
+                [TreePath::new([4])]
+            ),
+            Patch::move_after_node(
+                Some(&"li"),
+                TreePath::new([3]),
+                [TreePath::new([1])]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn moved_nodes_are_diffed_at_their_own_path() {
+    let old = list((0..5).map(|i| item(i, "line")));
+    let new = list(
+        [0, 3, 1, 2, 4]
+            .into_iter()
+            .map(|i| item(i, if i == 3 { "changed" } else { "line" })),
+    );
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        patches[0],
+        Patch::replace_node(
+            None,
+            TreePath::new([3, 0]),
+            [&leaf("changed3".to_string())]
+        )
+    );
+    assert_round_trip(&old, &new);
+}
+
+#[test]
+fn shuffled_keyed_children() {
+    let mut rng = Lcg(7);
+    for _ in 0..500 {
+        let old_len = rng.next(12);
+        let old_keys: Vec<usize> = (0..old_len).collect();
+        // shuffle, then remove and insert some keys
+        let mut new_keys = old_keys.clone();
+        for i in (1..new_keys.len()).rev() {
+            let j = rng.next(i + 1);
+            new_keys.swap(i, j);
+        }
+        for _ in 0..rng.next(3) {
+            if !new_keys.is_empty() {
+                new_keys.remove(rng.next(new_keys.len()));
+            }
+        }
+        for n in 0..rng.next(4) {
+            let at = rng.next(new_keys.len() + 1);
+            new_keys.insert(at, 100 + n);
+        }
+        let changed = rng.next(old_len + 1);
+
+        let old = list(old_keys.iter().map(|i| item(*i, "line")));
+        let new =
+            list(new_keys.iter().map(|i| {
+                item(*i, if *i == changed { "changed" } else { "line" })
+            }));
+        assert_round_trip(&old, &new);
+        assert_algorithms_agree(&old, &new, &"key");
+    }
+}
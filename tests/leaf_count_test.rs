@@ -0,0 +1,48 @@
+#![deny(warnings)]
+use mt_dom::*;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn leaf_count_and_text_len() {
+    let node: MyNode = element(
+        "main",
+        [attr("class", "container")],
+        [
+            leaf("hello"),
+            element(
+                "ul",
+                [],
+                [element("li", [], [leaf("one")]), element("li", [], [])],
+            ),
+            fragment([leaf("two"), leaf("three")]),
+        ],
+    );
+    assert_eq!(node.leaf_count(), 4);
+    assert_eq!(
+        node.text_len(),
+        "hello".len() + "one".len() + "two".len() + "three".len()
+    );
+}
+
+#[test]
+fn single_leaf() {
+    let node: MyNode = leaf("hello");
+    assert_eq!(node.leaf_count(), 1);
+    assert_eq!(node.text_len(), 5);
+}
+
+#[test]
+fn text_len_of_tokened_leaves() {
+    let node: Node<&str, &str, TokenedLeaf<&str, u32>, &str, &str> = element(
+        "p",
+        [],
+        [
+            leaf(TokenedLeaf::with_token("hello", 1)),
+            leaf(TokenedLeaf::new("!")),
+        ],
+    );
+    assert_eq!(node.leaf_count(), 2);
+    assert_eq!(node.text_len(), 6);
+}
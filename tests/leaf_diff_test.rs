@@ -0,0 +1,197 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn with_leaf_diff<'o>() -> DiffOptions<
+    'o,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+> {
+    DiffOptions::default().with_leaf_diff()
+}
+
+#[test]
+fn changed_text_is_spliced() {
+    let old: MyNode = element("p", vec![], vec![leaf("hello world")]);
+    let new: MyNode = element("p", vec![], vec![leaf("hello big world")]);
+
+    let diff = diff_with_options(&old, &new, &"key", &with_leaf_diff());
+    assert_eq!(
+        diff,
+        vec![Patch::splice_leaf(
+            TreePath::new([0]),
+            &"hello world",
+            &"hello big world",
+            [LeafSplice {
+                old_start: 6,
+                old_len: 0,
+                new_start: 6,
+                new_len: 4,
+            }]
+        )]
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &diff);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn splices_are_counted_in_chars() {
+    let old: MyNode = leaf("café au lait");
+    let new: MyNode = leaf("café noir");
+
+    let diff = diff_with_options(&old, &new, &"key", &with_leaf_diff());
+    assert_eq!(
+        diff,
+        vec![Patch::splice_leaf(
+            TreePath::root(),
+            &"café au lait",
+            &"café noir",
+            [LeafSplice {
+                old_start: 5,
+                old_len: 7,
+                new_start: 5,
+                new_len: 4,
+            }]
+        )]
+    );
+}
+
+#[test]
+fn leaves_are_replaced_without_leaf_diff() {
+    let old: MyNode = leaf("hello");
+    let new: MyNode = leaf("world");
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        diff,
+        vec![Patch::replace_node(None, TreePath::root(), [&new])]
+    );
+}
+
+#[test]
+fn leaf_diff_can_fallback_to_change_leaf() {
+    let old: MyNode = leaf("hello");
+    let new: MyNode = leaf("world");
+
+    // only the appended text is spliced
+    let options = DiffOptions {
+        leaf_diff: Some(|old: &&'static str, new: &&'static str| {
+            new.strip_prefix(*old).map(|appended| {
+                vec![LeafSplice {
+                    old_start: old.len(),
+                    old_len: 0,
+                    new_start: old.len(),
+                    new_len: appended.len(),
+                }]
+            })
+        }),
+        change_leaf: true,
+        ..Default::default()
+    };
+    let diff = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        diff,
+        vec![Patch::change_leaf(TreePath::root(), &"hello", &"world")]
+    );
+}
+
+#[test]
+fn spliced_leaf_is_owned() {
+    let old: MyNode = leaf("abc");
+    let new: MyNode = leaf("axc");
+
+    let diff = diff_with_options(&old, &new, &"key", &with_leaf_diff());
+    let owned: Vec<_> = diff.iter().cloned().map(Patch::into_owned).collect();
+    assert_eq!(owned[0].as_patch(), diff[0]);
+    assert_eq!(diff[0].kind(), PatchKind::SpliceLeaf);
+}
+
+#[test]
+fn splice_between_units() {
+    assert_eq!(LeafSplice::between(&[1, 2, 3], &[1, 2, 3]), None);
+    assert_eq!(
+        LeafSplice::between(&[1, 2, 3], &[1, 3]),
+        Some(LeafSplice {
+            old_start: 1,
+            old_len: 1,
+            new_start: 1,
+            new_len: 0,
+        })
+    );
+    // the repeated units are not counted twice in the prefix and suffix
+    assert_eq!(
+        LeafSplice::between(&[1, 1], &[1, 1, 1]),
+        Some(LeafSplice {
+            old_start: 2,
+            old_len: 0,
+            new_start: 2,
+            new_len: 1,
+        })
+    );
+}
+
+#[test]
+fn one_char_change_in_a_large_text_is_one_splice() {
+    let old_text: &'static str = "lorem ipsum ".repeat(1000).leak();
+    let mut new_text = String::from(old_text);
+    new_text.replace_range(6000..6001, "X");
+    let new_text: &'static str = new_text.leak();
+
+    let old: MyNode = leaf(old_text);
+    let new: MyNode = leaf(new_text);
+    let options = DiffOptions {
+        leaf_diff: Some(text_splices),
+        ..Default::default()
+    };
+    let diff = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        diff,
+        vec![Patch::splice_leaf(
+            TreePath::root(),
+            &old_text,
+            &new_text,
+            [LeafSplice {
+                old_start: 6000,
+                old_len: 1,
+                new_start: 6000,
+                new_len: 1,
+            }]
+        )]
+    );
+}
+
+#[test]
+fn scattered_edits_are_spliced_separately() {
+    let old: Vec<char> = "the cat sat on the mat".chars().collect();
+    let new: Vec<char> = "the bat sat on a mat!".chars().collect();
+
+    let splices = LeafSplice::minimal(&old, &new, TEXT_SPLICE_EDITS);
+    assert_eq!(splices.len(), 3);
+    // the splices are applied from the last, so the offsets are not shifted
+    let mut spliced = old.clone();
+    for splice in splices.iter().rev() {
+        spliced.splice(
+            splice.old_start..splice.old_start + splice.old_len,
+            new[splice.new_start..splice.new_start + splice.new_len]
+                .iter()
+                .copied(),
+        );
+    }
+    assert_eq!(spliced, new);
+
+    // too many edits falls back to the changed middle
+    assert_eq!(
+        LeafSplice::minimal(&old, &new, 2),
+        LeafSplice::between(&old, &new)
+            .into_iter()
+            .collect::<Vec<_>>()
+    );
+}
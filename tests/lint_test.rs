@@ -0,0 +1,142 @@
+#![deny(warnings)]
+use mt_dom::{lint::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn html_options() -> LintOptions<&'static str, &'static str> {
+    LintOptions {
+        key: Some("key"),
+        id: Some("id"),
+        is_void: Some(|tag| HTML_VOID_ELEMENTS.contains(tag)),
+        max_depth: Some(8),
+    }
+}
+
+#[test]
+fn a_clean_tree_has_no_findings() {
+    let node: MyNode = element(
+        "main",
+        vec![attr("id", "main")],
+        vec![
+            element(
+                "ul",
+                vec![],
+                vec![
+                    element("li", vec![attr("key", "1")], vec![leaf("one")]),
+                    element("li", vec![attr("key", "2")], vec![leaf("two")]),
+                ],
+            ),
+            element("img", vec![attr("id", "logo")], vec![]),
+        ],
+    );
+    assert_eq!(check(&node, &html_options()), vec![]);
+}
+
+#[test]
+fn keys_of_the_siblings_are_checked() {
+    let node: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            element(
+                "ul",
+                vec![],
+                vec![
+                    element("li", vec![attr("key", "1")], vec![]),
+                    element("li", vec![attr("key", "2")], vec![]),
+                    element("li", vec![attr("key", "1")], vec![]),
+                ],
+            ),
+            element(
+                "ul",
+                vec![],
+                vec![
+                    element("li", vec![attr("key", "1")], vec![]),
+                    element("li", vec![], vec![]),
+                ],
+            ),
+        ],
+    );
+    assert_eq!(
+        check(&node, &html_options()),
+        vec![
+            LintFinding::DuplicateKey {
+                path: TreePath::new([0, 2]),
+                first: TreePath::new([0, 0]),
+            },
+            LintFinding::PartiallyKeyed {
+                path: TreePath::new([1]),
+                keyed: 1,
+                len: 2,
+            },
+        ]
+    );
+}
+
+#[test]
+fn nested_identical_ids_are_reported() {
+    let node: MyNode = element(
+        "section",
+        vec![attr("id", "a")],
+        vec![element(
+            "div",
+            vec![],
+            vec![element("span", vec![attr("id", "a")], vec![])],
+        )],
+    );
+    assert_eq!(
+        check(&node, &html_options()),
+        vec![LintFinding::DuplicateId {
+            path: TreePath::new([0, 0]),
+            first: TreePath::root(),
+        }]
+    );
+}
+
+#[test]
+fn void_elements_with_children_are_reported() {
+    let node: MyNode = element(
+        "form",
+        vec![],
+        vec![element("input", vec![], vec![leaf("text")])],
+    );
+    let findings = check(&node, &html_options());
+    assert_eq!(
+        findings,
+        vec![LintFinding::VoidWithChildren {
+            path: TreePath::new([0]),
+        }]
+    );
+    assert_eq!(
+        findings[0].to_string(),
+        "the element at [0] is a void element, which can not have children"
+    );
+}
+
+#[test]
+fn only_the_first_node_which_is_too_deep_is_reported() {
+    let node: MyNode = (0..20)
+        .fold(leaf("deep"), |child, _| element("div", vec![], vec![child]));
+    assert_eq!(
+        check(&node, &html_options()),
+        vec![LintFinding::TooDeep {
+            path: TreePath::new([0; 9]),
+            max_depth: 8,
+        }]
+    );
+}
+
+#[test]
+fn the_checks_are_skipped_by_default() {
+    let node: MyNode = element(
+        "br",
+        vec![attr("id", "a")],
+        vec![
+            element("li", vec![attr("id", "a"), attr("key", "1")], vec![]),
+            element("li", vec![attr("key", "1")], vec![]),
+            leaf("text"),
+        ],
+    );
+    assert_eq!(check(&node, &LintOptions::default()), vec![]);
+}
@@ -1,41 +1,36 @@
-use mt_dom::{diff::*, patch::*, *};
+#![cfg(feature = "keyed")]
+use mt_dom::{diff::*, patch::*, testing::tree_from_str, *};
 
 pub type MyNode =
     Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
 
 #[test]
 fn key_inserted_at_start() {
-    let old: MyNode = element(
-        "main",
-        vec![attr("class", "container")],
-        vec![
-            element("div", vec![attr("key", "1")], vec![leaf("line1")]),
-            element("div", vec![attr("key", "2")], vec![leaf("line2")]),
-            element("div", vec![attr("key", "3")], vec![leaf("line3")]),
-            element("div", vec![attr("key", "4")], vec![leaf("line4")]),
-            element("div", vec![attr("key", "5")], vec![leaf("line5")]),
-            element("div", vec![attr("key", "6")], vec![leaf("line6")]),
-            element("div", vec![attr("key", "7")], vec![leaf("line7")]),
-            element("div", vec![attr("key", "8")], vec![leaf("line8")]),
-            element("div", vec![attr("key", "9")], vec![leaf("line9")]),
-        ],
+    let old: MyNode = tree_from_str(
+        "main.container
+         div[key=1] 'line1'
+         div[key=2] 'line2'
+         div[key=3] 'line3'
+         div[key=4] 'line4'
+         div[key=5] 'line5'
+         div[key=6] 'line6'
+         div[key=7] 'line7'
+         div[key=8] 'line8'
+         div[key=9] 'line9'",
     );
 
-    let new: MyNode = element(
-        "main",
-        vec![attr("class", "container")],
-        vec![
-            element("div", vec![attr("key", "XXX")], vec![leaf("lineXXX")]),
-            element("div", vec![attr("key", "1")], vec![leaf("line1")]),
-            element("div", vec![attr("key", "2")], vec![leaf("line2")]),
-            element("div", vec![attr("key", "3")], vec![leaf("line3")]),
-            element("div", vec![attr("key", "4")], vec![leaf("line4")]),
-            element("div", vec![attr("key", "5")], vec![leaf("line5")]),
-            element("div", vec![attr("key", "6")], vec![leaf("line6")]),
-            element("div", vec![attr("key", "7")], vec![leaf("line7")]),
-            element("div", vec![attr("key", "8")], vec![leaf("line8")]),
-            element("div", vec![attr("key", "9")], vec![leaf("line9")]),
-        ],
+    let new: MyNode = tree_from_str(
+        "main.container
+         div[key=XXX] 'lineXXX'
+         div[key=1] 'line1'
+         div[key=2] 'line2'
+         div[key=3] 'line3'
+         div[key=4] 'line4'
+         div[key=5] 'line5'
+         div[key=6] 'line6'
+         div[key=7] 'line7'
+         div[key=8] 'line8'
+         div[key=9] 'line9'",
     );
 
     let diff = diff_with_key(&old, &new, &"key");
@@ -56,37 +51,31 @@ fn key_inserted_at_start() {
 #[test]
 fn key_inserted_at_middle() {
     pretty_env_logger::try_init().ok();
-    let old: MyNode = element(
-        "main",
-        vec![attr("class", "container")],
-        vec![
-            element("div", vec![attr("key", "1")], vec![leaf("line1")]),
-            element("div", vec![attr("key", "2")], vec![leaf("line2")]),
-            element("div", vec![attr("key", "3")], vec![leaf("line3")]),
-            element("div", vec![attr("key", "4")], vec![leaf("line4")]),
-            element("div", vec![attr("key", "5")], vec![leaf("line5")]),
-            element("div", vec![attr("key", "6")], vec![leaf("line6")]),
-            element("div", vec![attr("key", "7")], vec![leaf("line7")]),
-            element("div", vec![attr("key", "8")], vec![leaf("line8")]),
-            element("div", vec![attr("key", "9")], vec![leaf("line9")]),
-        ],
+    let old: MyNode = tree_from_str(
+        "main.container
+         div[key=1] 'line1'
+         div[key=2] 'line2'
+         div[key=3] 'line3'
+         div[key=4] 'line4'
+         div[key=5] 'line5'
+         div[key=6] 'line6'
+         div[key=7] 'line7'
+         div[key=8] 'line8'
+         div[key=9] 'line9'",
     );
 
-    let new: MyNode = element(
-        "main",
-        vec![attr("class", "container")],
-        vec![
-            element("div", vec![attr("key", "1")], vec![leaf("line1")]),
-            element("div", vec![attr("key", "2")], vec![leaf("line2")]),
-            element("div", vec![attr("key", "3")], vec![leaf("line3")]),
-            element("div", vec![attr("key", "4")], vec![leaf("line4")]),
-            element("div", vec![attr("key", "5")], vec![leaf("line5")]),
-            element("div", vec![attr("key", "XXX")], vec![leaf("lineXXX")]),
-            element("div", vec![attr("key", "6")], vec![leaf("line6")]),
-            element("div", vec![attr("key", "7")], vec![leaf("line7")]),
-            element("div", vec![attr("key", "8")], vec![leaf("line8")]),
-            element("div", vec![attr("key", "9")], vec![leaf("line9")]),
-        ],
+    let new: MyNode = tree_from_str(
+        "main.container
+         div[key=1] 'line1'
+         div[key=2] 'line2'
+         div[key=3] 'line3'
+         div[key=4] 'line4'
+         div[key=5] 'line5'
+         div[key=XXX] 'lineXXX'
+         div[key=6] 'line6'
+         div[key=7] 'line7'
+         div[key=8] 'line8'
+         div[key=9] 'line9'",
     );
 
     let diff = diff_with_key(&old, &new, &"key");
@@ -109,45 +98,33 @@ fn key_inserted_at_middle() {
 #[test]
 fn wrapped_elements() {
     pretty_env_logger::try_init().ok();
-    let old: MyNode = element(
-        "article",
-        vec![],
-        vec![element(
-            "main",
-            vec![attr("class", "container")],
-            vec![
-                element("div", vec![attr("key", "1")], vec![leaf("line1")]),
-                element("div", vec![attr("key", "2")], vec![leaf("line2")]),
-                element("div", vec![attr("key", "3")], vec![leaf("line3")]),
-                element("div", vec![attr("key", "4")], vec![leaf("line4")]),
-                element("div", vec![attr("key", "5")], vec![leaf("line5")]),
-                element("div", vec![attr("key", "6")], vec![leaf("line6")]),
-                element("div", vec![attr("key", "7")], vec![leaf("line7")]),
-                element("div", vec![attr("key", "8")], vec![leaf("line8")]),
-                element("div", vec![attr("key", "9")], vec![leaf("line9")]),
-            ],
-        )],
+    let old: MyNode = tree_from_str(
+        "article
+           main.container
+             div[key=1] 'line1'
+             div[key=2] 'line2'
+             div[key=3] 'line3'
+             div[key=4] 'line4'
+             div[key=5] 'line5'
+             div[key=6] 'line6'
+             div[key=7] 'line7'
+             div[key=8] 'line8'
+             div[key=9] 'line9'",
     );
 
-    let new: MyNode = element(
-        "article",
-        vec![],
-        vec![element(
-            "main",
-            vec![attr("class", "container")],
-            vec![
-                element("div", vec![attr("key", "1")], vec![leaf("line1")]),
-                element("div", vec![attr("key", "2")], vec![leaf("line2")]),
-                element("div", vec![attr("key", "3")], vec![leaf("line3")]),
-                element("div", vec![attr("key", "4")], vec![leaf("line4")]),
-                element("div", vec![attr("key", "5")], vec![leaf("line5")]),
-                element("div", vec![attr("key", "XXX")], vec![leaf("lineXXX")]),
-                element("div", vec![attr("key", "6")], vec![leaf("line6")]),
-                element("div", vec![attr("key", "7")], vec![leaf("line7")]),
-                element("div", vec![attr("key", "8")], vec![leaf("line8")]),
-                element("div", vec![attr("key", "9")], vec![leaf("line9")]),
-            ],
-        )],
+    let new: MyNode = tree_from_str(
+        "article
+           main.container
+             div[key=1] 'line1'
+             div[key=2] 'line2'
+             div[key=3] 'line3'
+             div[key=4] 'line4'
+             div[key=5] 'line5'
+             div[key=XXX] 'lineXXX'
+             div[key=6] 'line6'
+             div[key=7] 'line7'
+             div[key=8] 'line8'
+             div[key=9] 'line9'",
     );
 
     let diff = diff_with_key(&old, &new, &"key");
@@ -169,48 +146,32 @@ fn wrapped_elements() {
 #[test]
 fn text_changed() {
     pretty_env_logger::try_init().ok();
-    let old: MyNode = element(
-        "article",
-        vec![],
-        vec![element(
-            "main",
-            vec![attr("class", "container")],
-            vec![
-                element("div", vec![attr("key", "1")], vec![leaf("line1")]),
-                element("div", vec![attr("key", "2")], vec![leaf("line2")]),
-                element("div", vec![attr("key", "3")], vec![leaf("line3")]),
-                element("div", vec![attr("key", "4")], vec![leaf("line4")]),
-                element("div", vec![attr("key", "5")], vec![leaf("line5")]),
-                element("div", vec![attr("key", "6")], vec![leaf("line6")]),
-                element("div", vec![attr("key", "7")], vec![leaf("line7")]),
-                element("div", vec![attr("key", "8")], vec![leaf("line8")]),
-                element("div", vec![attr("key", "9")], vec![leaf("line9")]),
-            ],
-        )],
+    let old: MyNode = tree_from_str(
+        "article
+           main.container
+             div[key=1] 'line1'
+             div[key=2] 'line2'
+             div[key=3] 'line3'
+             div[key=4] 'line4'
+             div[key=5] 'line5'
+             div[key=6] 'line6'
+             div[key=7] 'line7'
+             div[key=8] 'line8'
+             div[key=9] 'line9'",
     );
 
-    let new: MyNode = element(
-        "article",
-        vec![],
-        vec![element(
-            "main",
-            vec![attr("class", "container")],
-            vec![
-                element("div", vec![attr("key", "1")], vec![leaf("line1")]),
-                element("div", vec![attr("key", "2")], vec![leaf("line2")]),
-                element("div", vec![attr("key", "3")], vec![leaf("line3")]),
-                element("div", vec![attr("key", "4")], vec![leaf("line4")]),
-                element("div", vec![attr("key", "5")], vec![leaf("line5")]),
-                element("div", vec![attr("key", "6")], vec![leaf("line6")]),
-                element(
-                    "div",
-                    vec![attr("key", "7")],
-                    vec![leaf("line7_changed")],
-                ),
-                element("div", vec![attr("key", "8")], vec![leaf("line8")]),
-                element("div", vec![attr("key", "9")], vec![leaf("line9")]),
-            ],
-        )],
+    let new: MyNode = tree_from_str(
+        "article
+           main.container
+             div[key=1] 'line1'
+             div[key=2] 'line2'
+             div[key=3] 'line3'
+             div[key=4] 'line4'
+             div[key=5] 'line5'
+             div[key=6] 'line6'
+             div[key=7] 'line7_changed'
+             div[key=8] 'line8'
+             div[key=9] 'line9'",
     );
 
     let diff = diff_with_key(&old, &new, &"key");
@@ -0,0 +1,214 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[cfg(feature = "keyed")]
+fn item(text: &'static str) -> MyNode {
+    element("li", vec![attr("class", "item")], vec![leaf(text)])
+}
+
+#[cfg(feature = "keyed")]
+fn list(items: &[&'static str]) -> MyNode {
+    element("ul", vec![], items.iter().map(|text| item(text)))
+}
+
+fn assert_patches_apply(
+    old: &MyNode,
+    new: &MyNode,
+    options: &DiffOptions<
+        '_,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >,
+) -> Vec<PatchKind> {
+    let patches = diff_with_options(old, new, &"key", options);
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new);
+    patches.iter().map(Patch::kind).collect()
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn an_inserted_child_is_inserted_instead_of_shifting_the_others() {
+    let old = list(&["apple", "banana"]);
+    let new = list(&["cherry", "apple", "banana"]);
+    let options = DiffOptions {
+        match_similar: Some(90),
+        ..Default::default()
+    };
+    assert_eq!(
+        assert_patches_apply(&old, &new, &options),
+        [PatchKind::InsertBeforeNode]
+    );
+    // diffing by the position changes each of the items
+    assert_eq!(
+        assert_patches_apply(&old, &new, &DiffOptions::default()).len(),
+        3
+    );
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn the_similar_children_are_moved_and_diffed() {
+    let old = list(&["first item", "second item"]);
+    let new = list(&["second item!", "first item"]);
+    let options = DiffOptions {
+        match_similar: Some(80),
+        ..Default::default()
+    }
+    .with_leaf_similarity();
+    let kinds = assert_patches_apply(&old, &new, &options);
+    assert_eq!(kinds.len(), 2);
+    assert!(kinds.contains(&PatchKind::ReplaceNode));
+    assert!(kinds.iter().any(|kind| matches!(
+        kind,
+        PatchKind::MoveBeforeNode | PatchKind::MoveAfterNode
+    )));
+
+    // the changed leaves are not similar at all without the leaf similarity
+    let options = DiffOptions {
+        match_similar: Some(80),
+        ..Default::default()
+    };
+    assert_eq!(assert_patches_apply(&old, &new, &options).len(), 2);
+}
+
+#[test]
+fn the_children_of_different_tags_are_not_matched() {
+    let old: MyNode = element(
+        "div",
+        vec![],
+        vec![element("p", vec![], vec![]), leaf("text")],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![],
+        vec![leaf("text"), element("span", vec![], vec![])],
+    );
+    let options = DiffOptions {
+        match_similar: Some(50),
+        ..Default::default()
+    };
+    let kinds = assert_patches_apply(&old, &new, &options);
+    assert!(!kinds.iter().any(|kind| matches!(
+        kind,
+        PatchKind::MoveBeforeNode | PatchKind::MoveAfterNode
+    )));
+}
+
+/// diff with `match_similar` at a few thresholds, with and without the
+/// keyed tag changes and the move detection, and check that the patches apply
+fn assert_similar_round_trip(old: &MyNode, new: &MyNode) {
+    for threshold in [0, 50, 90] {
+        for flags in 0..4 {
+            let options = DiffOptions {
+                match_similar: Some(threshold),
+                keyed_tag_change: flags & 1 != 0,
+                detect_moves: flags & 2 != 0,
+                ..Default::default()
+            };
+            assert_patches_apply(old, new, &options);
+            assert_patches_apply(old, new, &options.with_leaf_similarity());
+        }
+    }
+}
+
+#[cfg(feature = "keyed")]
+fn keyed(tag: &'static str, key: &'static str, text: &'static str) -> MyNode {
+    element(tag, vec![attr("key", key)], vec![leaf(text)])
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn the_keyed_siblings_whose_tag_changed_round_trip() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![keyed("li", "a", "apple"), keyed("li", "b", "banana")],
+    );
+    let retagged: MyNode = element(
+        "ul",
+        vec![],
+        vec![keyed("span", "a", "apple"), keyed("li", "b", "banana")],
+    );
+    let moved: MyNode = element(
+        "ul",
+        vec![],
+        vec![keyed("li", "b", "banana"), keyed("span", "a", "apple")],
+    );
+    let swapped: MyNode = element(
+        "ul",
+        vec![],
+        vec![keyed("span", "b", "banana"), keyed("span", "a", "apple")],
+    );
+    for new in [&retagged, &moved, &swapped] {
+        assert_similar_round_trip(&old, new);
+        assert_similar_round_trip(new, &old);
+    }
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn the_keyed_siblings_mixed_with_similar_children_round_trip() {
+    let old: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            keyed("li", "a", "apple"),
+            element("p", vec![attr("class", "note")], vec![leaf("text")]),
+            keyed("li", "b", "banana"),
+            leaf("tail"),
+        ],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            leaf("tail!"),
+            keyed("li", "b", "banana"),
+            element("p", vec![attr("class", "note")], vec![leaf("text!")]),
+            keyed("span", "a", "apple"),
+        ],
+    );
+    assert_similar_round_trip(&old, &new);
+    assert_similar_round_trip(&new, &old);
+}
+
+#[test]
+fn the_similar_siblings_whose_tag_changed_round_trip() {
+    let old: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            element("div", vec![attr("class", "x")], vec![leaf("a")]),
+            element("p", vec![attr("class", "x")], vec![leaf("b")]),
+            leaf("c"),
+        ],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![],
+        vec![
+            leaf("c"),
+            element("p", vec![attr("class", "x")], vec![leaf("b")]),
+            element("section", vec![attr("class", "x")], vec![leaf("a")]),
+        ],
+    );
+    assert_similar_round_trip(&old, &new);
+    assert_similar_round_trip(&new, &old);
+}
+
+#[test]
+fn the_text_is_as_similar_as_its_common_prefix() {
+    assert_eq!("hello".similarity(&"hello"), 100);
+    assert_eq!("hello".similarity(&"help"), 60);
+    assert_eq!("".similarity(&""), 100);
+    assert_eq!("abc".similarity(&"xyz"), 0);
+}
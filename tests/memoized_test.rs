@@ -0,0 +1,59 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn component(hash: u64, text: &'static str) -> MyNode {
+    Node::memoized(
+        hash,
+        element(
+            "section",
+            vec![],
+            vec![element("p", vec![], vec![leaf(text)])],
+        ),
+    )
+}
+
+#[test]
+fn the_memoized_subtrees_with_the_same_hash_are_skipped() {
+    // the framework vouches that the same hash is the same content
+    let old = component(1, "old");
+    let new = component(1, "new");
+    assert!(diff_with_key(&old, &new, &"key").is_empty());
+
+    let options = DiffOptions {
+        equality_check: EqualityCheck::Never,
+        ..Default::default()
+    };
+    assert!(diff_with_options(&old, &new, &"key", &options).is_empty());
+}
+
+#[test]
+fn the_memoized_subtrees_with_different_hashes_are_diffed() {
+    let old =
+        element("main", vec![], vec![component(1, "a"), component(2, "b")]);
+    let new =
+        element("main", vec![], vec![component(1, "a"), component(3, "c")]);
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].path(), &TreePath::new([1, 0, 0]));
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn the_memo_is_not_part_of_the_content() {
+    let node: MyNode = element("p", vec![], vec![leaf("text")]);
+    let memoized = Node::memoized(7, node.clone());
+    assert_eq!(memoized.memo(), Some(7));
+    assert_eq!(node.memo(), None);
+    assert_eq!(memoized, node);
+
+    // only the elements are memoized
+    let text: MyNode = Node::memoized(7, leaf("text"));
+    assert_eq!(text.memo(), None);
+}
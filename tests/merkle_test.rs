@@ -0,0 +1,79 @@
+#![deny(warnings)]
+use mt_dom::{merkle::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn list(items: &[&'static str]) -> MyNode {
+    element(
+        "ul",
+        vec![attr("class", "list")],
+        items
+            .iter()
+            .map(|item| element("li", vec![], vec![leaf(*item)])),
+    )
+}
+
+#[test]
+fn same_trees_have_no_changes() {
+    let old = list(&["a", "b"]);
+    let new = list(&["a", "b"]);
+    assert_eq!(hash_tree(&old), hash_tree(&new));
+    assert!(changed_paths(&hash_tree(&old), &hash_tree(&new)).is_empty());
+}
+
+#[test]
+fn changed_leaves_are_the_changed_paths() {
+    let old = list(&["a", "b", "c"]);
+    let new = list(&["a", "x", "y"]);
+    assert_eq!(
+        changed_paths(&hash_tree(&old), &hash_tree(&new)),
+        vec![TreePath::new([1, 0]), TreePath::new([2, 0])]
+    );
+}
+
+#[test]
+fn changed_element_is_changed_as_a_whole() {
+    let old = list(&["a", "b"]);
+    let new: MyNode = element(
+        "ul",
+        vec![attr("class", "other")],
+        vec![element("li", vec![], vec![leaf("a")])],
+    );
+    assert_eq!(
+        changed_paths(&hash_tree(&old), &hash_tree(&new)),
+        vec![TreePath::root()]
+    );
+
+    // the number of children changed
+    let new = list(&["a", "b", "c"]);
+    assert_eq!(
+        changed_paths(&hash_tree(&old), &hash_tree(&new)),
+        vec![TreePath::root()]
+    );
+}
+
+#[test]
+fn subtrees_are_addressed_by_the_tree_paths() {
+    let node: MyNode = element(
+        "div",
+        vec![],
+        vec![fragment([leaf("a"), element("b", vec![], vec![leaf("c")])])],
+    );
+    let tree = hash_tree(&node);
+    let path = TreePath::new([0, 1, 0]);
+    assert_eq!(
+        tree.get(&path),
+        Some(&hash_tree(path.find_node_by_path(&node).unwrap()))
+    );
+    assert_eq!(tree.get(&TreePath::new([0, 2])), None);
+}
+
+#[test]
+fn hashes_are_stable_across_processes() {
+    // the 64-bit FNV-1a of the leaf marker and the hash of the str
+    let node: MyNode = leaf("a");
+    let tree = hash_tree(&node);
+    assert_eq!(tree.node_hash, 0xe33f_6018_720d_da62);
+    assert!(tree.children.is_empty());
+}
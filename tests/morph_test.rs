@@ -0,0 +1,82 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn is_heading(tag: &str) -> bool {
+    matches!(tag, "h1" | "h2" | "h3")
+}
+
+fn can_morph(old: &MyNode, new: &MyNode) -> bool {
+    match (old.tag(), new.tag()) {
+        (Some(old), Some(new)) => is_heading(old) && is_heading(new),
+        _ => false,
+    }
+}
+
+#[test]
+fn morph_keeps_the_children() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![element(
+            "h1",
+            vec![attr("class", "title")],
+            vec![element("input", vec![attr("value", "typed")], vec![])],
+        )],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![element(
+            "h2",
+            vec![attr("class", "subtitle")],
+            vec![element("input", vec![attr("value", "typed")], vec![])],
+        )],
+    );
+
+    let options = DiffOptions {
+        can_morph: Some(&can_morph),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![
+            Patch::change_tag(TreePath::new([0]), &"h1", &"h2"),
+            Patch::add_attributes(
+                &"h1",
+                TreePath::new([0]),
+                vec![&attr("class", "subtitle")]
+            ),
+        ]
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn elements_which_can_not_be_morphed_are_replaced() {
+    let old: MyNode =
+        element("main", vec![], vec![element("h1", vec![], vec![])]);
+    let new: MyNode =
+        element("main", vec![], vec![element("p", vec![], vec![])]);
+
+    let options = DiffOptions {
+        can_morph: Some(&can_morph),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::replace_node(
+            Some(&"h1"),
+            TreePath::new([0]),
+            vec![&new.children()[0]]
+        )]
+    );
+}
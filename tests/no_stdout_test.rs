@@ -0,0 +1,61 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, *};
+use std::process::Command;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+const CHILD: &str = "MT_DOM_NO_STDOUT_CHILD";
+const START: &str = "--start--";
+const END: &str = "--end--";
+
+fn list(keys: &[&'static str]) -> MyNode {
+    element(
+        "ul",
+        vec![attr("class", "list")],
+        keys.iter().map(|key| {
+            element("li", vec![attr("key", *key)], vec![leaf(*key)])
+        }),
+    )
+}
+
+/// diff and apply the patches between the markers, this is only run in the child process
+#[test]
+fn diff_and_apply() {
+    if std::env::var(CHILD).is_err() {
+        return;
+    }
+    println!("{START}");
+    let old = list(&["1", "2", "3", "4"]);
+    let new = list(&["4", "2", "5", "1"]);
+    let patches = diff_with_key(&old, &new, &"key");
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+    let mut root = old.clone();
+    compile(&patches).apply(&mut root);
+    assert_eq!(root, new);
+    assert!(validate(&old, &patches).is_ok());
+    assert!(TreePath::new([9]).find_node_by_path(&old).is_none());
+    println!("{END}");
+}
+
+#[test]
+fn diff_and_apply_prints_nothing() {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "diff_and_apply",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env(CHILD, "1")
+        .output()
+        .expect("must run the test binary");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let start = stdout.find(START).expect("must have the start marker");
+    let end = stdout.find(END).expect("must have the end marker");
+    assert_eq!(stdout[start + START.len()..end].trim(), "");
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "node-cache")]
+use mt_dom::*;
+use std::sync::Arc;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn view(label: &'static str) -> MyNode {
+    element(
+        "main",
+        vec![attr("class", "container")],
+        vec![element("div", vec![attr("key", "1")], vec![leaf(label)])],
+    )
+}
+
+#[test]
+fn identical_nodes_are_shared() {
+    let mut cache = NodeCache::new();
+    let old = cache.intern(view("line1"));
+    let new = cache.intern(view("line1"));
+    assert!(Arc::ptr_eq(&old, &new));
+    assert_eq!(cache.len(), 1);
+    assert_eq!(diff_with_key(&old, &new, &"key"), vec![]);
+}
+
+#[test]
+fn different_nodes_are_not_shared() {
+    let mut cache = NodeCache::new();
+    let old = cache.intern(view("line1"));
+    let new = cache.intern(view("line2"));
+    assert!(!Arc::ptr_eq(&old, &new));
+    assert_eq!(cache.len(), 2);
+    assert_eq!(diff_with_key(&old, &new, &"key").len(), 1);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn unused_nodes_are_evicted() {
+    let mut cache = NodeCache::new();
+    let kept = cache.intern(view("line1"));
+    drop(cache.intern(view("line2")));
+    assert_eq!(cache.len(), 2);
+
+    cache.evict_unused();
+    assert_eq!(cache.len(), 1);
+    assert!(Arc::ptr_eq(&kept, &cache.intern(view("line1"))));
+
+    drop(kept);
+    cache.evict_unused();
+    assert!(cache.is_empty());
+}
@@ -0,0 +1,94 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::*;
+
+pub type MyNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+fn view(
+    pool: &mut NodePool<
+        &'static str,
+        &'static str,
+        String,
+        &'static str,
+        String,
+    >,
+    frame: usize,
+) -> MyNode {
+    let items: Vec<MyNode> = (0..10)
+        .map(|i| {
+            let key = pool.attr("key", i.to_string());
+            let text = pool.leaf(format!("item {i} at frame {frame}"));
+            pool.element("li", [key], [text])
+        })
+        .collect();
+    let class = pool.attr("class", "list".to_string());
+    let list = pool.element("ul", [class], items);
+    let footer = pool.leaf(format!("frame {frame}"));
+    pool.element("main", [], [list, node_list([footer])])
+}
+
+fn expected(frame: usize) -> MyNode {
+    element(
+        "main",
+        [],
+        [
+            element(
+                "ul",
+                [attr("class", "list".to_string())],
+                (0..10).map(|i| {
+                    element(
+                        "li",
+                        [attr("key", i.to_string())],
+                        [leaf(format!("item {i} at frame {frame}"))],
+                    )
+                }),
+            ),
+            leaf(format!("frame {frame}")),
+        ],
+    )
+}
+
+#[test]
+fn pooled_nodes_are_the_same_as_the_plain_nodes() {
+    let mut pool = NodePool::new();
+    assert_eq!(view(&mut pool, 0), expected(0));
+    let views = [view(&mut pool, 1), view(&mut pool, 2)];
+    assert_eq!(pool.fragment(views), fragment([expected(1), expected(2)]));
+}
+
+#[test]
+fn recycled_vectors_are_reused_across_frames() {
+    let mut pool = NodePool::new();
+    let mut old = view(&mut pool, 0);
+    for frame in 1..5 {
+        let new = view(&mut pool, frame);
+        let patches = diff_with_key(&old, &new, &"key");
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        assert_eq!(root, new);
+
+        pool.recycle(old);
+        let recycled = pool.len();
+        assert!(recycled > 0);
+        old = new;
+        // the next frame takes the vectors out of the pool
+        let next = view(&mut pool, frame);
+        assert!(pool.len() < recycled);
+        assert_eq!(next, expected(frame));
+        pool.recycle(next);
+    }
+    pool.clear();
+    assert!(pool.is_empty());
+}
+
+#[test]
+fn recycle_deep_tree() {
+    let mut pool = NodePool::new();
+    let mut node: MyNode = leaf("bottom".to_string());
+    for _ in 0..100_000 {
+        node = element("div", [], [node]);
+    }
+    pool.recycle(node);
+    assert_eq!(pool.len(), 100_000);
+}
@@ -0,0 +1,119 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn list(items: &[&'static str]) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        items.iter().map(|item| {
+            element("li", vec![attr("class", *item)], vec![leaf(*item)])
+        }),
+    )
+}
+
+fn options() -> DiffOptions<
+    'static,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+> {
+    DiffOptions {
+        detect_moves: true,
+        ..Default::default()
+    }
+}
+
+pub type MyPatch<'a> = Patch<
+    'a,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+>;
+
+fn assert_applies(old: &MyNode, new: &MyNode, patches: &[MyPatch<'_>]) {
+    let mut root = old.clone();
+    apply_patches(&mut root, patches);
+    assert_eq!(&root, new);
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn relocated_child_is_moved() {
+    let old = list(&["a", "b", "c", "d"]);
+    let new = list(&["d", "a", "b", "c"]);
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    assert_eq!(
+        patches,
+        vec![Patch::move_before_node(
+            Some(&"li"),
+            TreePath::new([0]),
+            [TreePath::new([3])],
+        )]
+    );
+    assert_applies(&old, &new, &patches);
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn reordering_is_cheaper_than_positional_diff() {
+    let old = list(&["a", "b", "c", "d", "e", "f"]);
+    let new = list(&["f", "e", "d", "c", "b", "a"]);
+    let moved = diff_with_options(&old, &new, &"key", &options());
+    let positional = diff_with_key(&old, &new, &"key");
+    assert!(moved.len() < positional.len());
+    assert!(moved.iter().all(|patch| matches!(
+        patch.patch_type,
+        PatchType::MoveBeforeNode { .. } | PatchType::MoveAfterNode { .. }
+    )));
+    assert_applies(&old, &new, &moved);
+}
+
+#[test]
+fn moves_with_inserted_and_removed_children() {
+    let old = list(&["a", "b", "c", "d"]);
+    let new = list(&["c", "x", "a", "d"]);
+    let patches = diff_with_options(&old, &new, &"key", &options());
+    assert_applies(&old, &new, &patches);
+}
+
+#[test]
+fn unmoved_children_are_diffed_by_position() {
+    let old = list(&["a", "b", "c"]);
+    let new = list(&["a", "x", "c", "d"]);
+    assert_eq!(
+        diff_with_options(&old, &new, &"key", &options()),
+        diff_with_key(&old, &new, &"key")
+    );
+}
+
+#[test]
+fn duplicate_children_are_not_moved() {
+    let old = list(&["a", "a", "b"]);
+    let new = list(&["a", "a", "c"]);
+    assert_eq!(
+        diff_with_options(&old, &new, &"key", &options()),
+        diff_with_key(&old, &new, &"key")
+    );
+}
+
+#[test]
+fn moves_are_not_detected_over_the_scan_limit() {
+    let old = list(&["a", "b", "c"]);
+    let new = list(&["c", "a", "b"]);
+    let options = DiffOptions {
+        keyed_scan_limit: Some(2),
+        ..options()
+    };
+    assert_eq!(
+        diff_with_options(&old, &new, &"key", &options),
+        diff_with_key(&old, &new, &"key")
+    );
+}
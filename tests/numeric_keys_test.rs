@@ -0,0 +1,64 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, *};
+
+pub type IdNode = Node<&'static str, &'static str, String, &'static str, u64>;
+pub type StrNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+fn shuffled(len: u64) -> Vec<u64> {
+    // a deterministic shuffle which moves, removes and inserts some of the ids
+    let mut ids: Vec<u64> = (0..len).filter(|id| id % 7 != 3).collect();
+    ids.reverse();
+    ids.swap(1, 10);
+    ids.insert(20, len + 1);
+    ids.insert(5, len + 2);
+    ids
+}
+
+#[test]
+fn numeric_keys_produce_the_same_patches() {
+    let item = |id: u64| -> IdNode {
+        element("li", vec![attr("key", id)], vec![leaf(format!("{id}"))])
+    };
+    let old: IdNode = element("ul", vec![], (0..1000).map(item));
+    let new: IdNode =
+        element("ul", vec![], shuffled(1000).into_iter().map(item));
+
+    let expected = diff_with_key(&old, &new, &"key");
+    let options = DiffOptions::default().with_numeric_keys();
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches, expected);
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn string_ids_and_fallback() {
+    let item = |id: &str| -> StrNode {
+        element("li", vec![attr("key", id.to_string())], vec![])
+    };
+    let old: StrNode =
+        element("ul", vec![], ["1", "2", "3", "4"].into_iter().map(item));
+    // "01" is not a numeric key, so the keys are compared as is
+    let new: StrNode =
+        element("ul", vec![], ["4", "01", "2", "1"].into_iter().map(item));
+
+    let expected = diff_with_key(&old, &new, &"key");
+    let options = DiffOptions::default().with_numeric_keys();
+    assert_eq!(diff_with_options(&old, &new, &"key", &options), expected);
+}
+
+#[test]
+fn canonical_numeric_keys() {
+    assert_eq!("0".numeric_key(), Some(0));
+    assert_eq!("42".numeric_key(), Some(42));
+    assert_eq!("042".numeric_key(), None);
+    assert_eq!("+42".numeric_key(), None);
+    assert_eq!("-1".numeric_key(), None);
+    assert_eq!("abc".numeric_key(), None);
+    assert_eq!(String::from("7").numeric_key(), Some(7));
+    assert_eq!(7u32.numeric_key(), Some(7));
+}
@@ -0,0 +1,66 @@
+#![deny(warnings)]
+#![cfg(feature = "rayon")]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+const ROWS: usize = 5_000;
+
+/// a table of rows with a cell of `text` in each, keyed when `keyed`
+fn table(rows: impl Iterator<Item = usize>, text: &str, keyed: bool) -> MyNode {
+    element(
+        "tbody",
+        vec![],
+        rows.map(|row| {
+            let attrs = if keyed {
+                vec![attr("key", row.to_string())]
+            } else {
+                vec![]
+            };
+            element(
+                "tr",
+                attrs,
+                vec![
+                    element("td", vec![], vec![leaf(row.to_string())]),
+                    element("td", vec![], vec![leaf(format!("{text} {row}"))]),
+                ],
+            )
+        })
+        .collect::<Vec<_>>(),
+    )
+}
+
+fn assert_same_as_diff_with_key(old: &MyNode, new: &MyNode) {
+    let patches = diff_parallel(old, new, &"key", 16, &DiffOptions::default);
+    assert_eq!(patches, diff_with_key(old, new, &"key"));
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new);
+}
+
+#[test]
+fn the_rows_are_diffed_the_same_as_in_a_single_thread() {
+    let old = table(0..ROWS, "old", false);
+    let new = table(0..ROWS, "new", false);
+    assert_same_as_diff_with_key(&old, &new);
+
+    let shorter = table(0..ROWS / 2, "new", false);
+    assert_same_as_diff_with_key(&old, &shorter);
+}
+
+#[test]
+fn the_keyed_rows_are_diffed_the_same_as_in_a_single_thread() {
+    let old = table(0..ROWS, "old", true);
+    let new = table((1..ROWS - 1).chain([ROWS + 1, 0]), "new", true);
+    assert_same_as_diff_with_key(&old, &new);
+}
+
+#[test]
+fn the_small_lists_are_not_diffed_in_parallel() {
+    let old = table(0..10, "old", false);
+    let new = table(0..10, "new", false);
+    let patches =
+        diff_parallel(&old, &new, &"key", usize::MAX, &DiffOptions::default);
+    assert_eq!(patches, diff_with_key(&old, &new, &"key"));
+}
@@ -0,0 +1,273 @@
+#![deny(warnings)]
+#![cfg(feature = "html-parser")]
+use mt_dom::{parse::*, *};
+
+pub type MyNode<'a> = Node<&'a str, &'a str, &'a str, &'a str, &'a str>;
+
+fn parse_one(markup: &str) -> MyNode<'_> {
+    let mut nodes = parse(markup, &ParseOptions::default()).unwrap();
+    assert_eq!(nodes.len(), 1);
+    nodes.remove(0)
+}
+
+#[test]
+fn attributes() {
+    let node = parse_one(
+        r#"<input type="checkbox" checked value='a "b"' name=done data-x = "1">"#,
+    );
+    assert_eq!(
+        node,
+        element(
+            "input",
+            vec![
+                attr("type", "checkbox"),
+                empty_attr("checked"),
+                attr("value", "a \"b\""),
+                attr("name", "done"),
+                attr("data-x", "1"),
+            ],
+            vec![],
+        )
+    );
+}
+
+#[test]
+fn void_and_self_closing_elements() {
+    let node = parse_one(r#"<p>a<br>b<img src="x.png"/><hr/></p>"#);
+    assert_eq!(
+        node,
+        element(
+            "p",
+            vec![],
+            vec![
+                leaf("a"),
+                element("br", vec![], vec![]),
+                leaf("b"),
+                element_ns(
+                    None,
+                    "img",
+                    vec![attr("src", "x.png")],
+                    vec![],
+                    true,
+                ),
+                element_ns(None, "hr", vec![], vec![], true),
+            ],
+        )
+    );
+}
+
+#[test]
+fn namespaces_are_inherited() {
+    let svg = "http://www.w3.org/2000/svg";
+    let node = parse_one(
+        r#"<div><svg xmlns="http://www.w3.org/2000/svg"><circle r="4"/><g></g></svg></div>"#,
+    );
+    assert_eq!(
+        node,
+        element(
+            "div",
+            vec![],
+            vec![element_ns(
+                Some(svg),
+                "svg",
+                vec![],
+                vec![
+                    element_ns(
+                        Some(svg),
+                        "circle",
+                        vec![attr("r", "4")],
+                        vec![],
+                        true,
+                    ),
+                    element_ns(Some(svg), "g", vec![], vec![], false),
+                ],
+                false,
+            )],
+        )
+    );
+}
+
+#[test]
+fn comments_doctype_and_raw_text() {
+    let nodes = parse(
+        "<!DOCTYPE html><!-- <p>skipped</p> --><script>if (a < b) { x(\"</p>\") }</script><p>1 < 2</p>",
+        &ParseOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        nodes,
+        vec![
+            element("script", vec![], vec![leaf("if (a < b) { x(\"</p>\") }")]),
+            element("p", vec![], vec![leaf("1 < 2")]),
+        ]
+    );
+}
+
+#[test]
+fn whitespace() {
+    let markup = "<ul>\n  <li>a</li>\n  <li> b </li>\n</ul>";
+    assert_eq!(
+        parse(markup, &ParseOptions::default()).unwrap(),
+        vec![element(
+            "ul",
+            vec![],
+            vec![
+                element("li", vec![], vec![leaf("a")]),
+                element("li", vec![], vec![leaf(" b ")]),
+            ],
+        )]
+    );
+    let options = ParseOptions {
+        keep_whitespace: true,
+        ..Default::default()
+    };
+    assert_eq!(parse(markup, &options).unwrap()[0].children().len(), 5);
+}
+
+#[test]
+fn xml_has_no_void_elements() {
+    let markup = "<?xml version=\"1.0\"?><rss><link>https://example.com</link><script>a &lt; b</script></rss>";
+    let options = ParseOptions {
+        xml: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        parse(markup, &options).unwrap(),
+        vec![element(
+            "rss",
+            vec![],
+            vec![
+                element("link", vec![], vec![leaf("https://example.com")]),
+                element("script", vec![], vec![leaf("a &lt; b")]),
+            ],
+        )]
+    );
+    assert_eq!(
+        parse(markup, &ParseOptions::default()),
+        Err(ParseError::MismatchedCloseTag {
+            expected: "rss".to_string(),
+            found: "link".to_string(),
+            position: 51,
+        })
+    );
+}
+
+#[test]
+fn owned_nodes_decode_the_entities() {
+    let nodes = parse_owned(
+        r#"<a title="&quot;x&quot; &amp; y">&lt;&#65;&#x42;&gt; &unknown; & &nbsp;</a>"#,
+        &ParseOptions::default(),
+    )
+    .unwrap();
+    let expected: Node<String, String, String, String, String> = element(
+        "a".to_string(),
+        vec![attr("title".to_string(), "\"x\" & y".to_string())],
+        vec![leaf("<AB> &unknown; & \u{a0}".to_string())],
+    );
+    assert_eq!(nodes, vec![expected]);
+}
+
+#[test]
+fn errors() {
+    let options = ParseOptions::default();
+    assert_eq!(
+        parse("<div><p>a</div>", &options),
+        Err(ParseError::MismatchedCloseTag {
+            expected: "p".to_string(),
+            found: "div".to_string(),
+            position: 9,
+        })
+    );
+    assert_eq!(
+        parse("<div><p>a</p>", &options),
+        Err(ParseError::UnclosedElement {
+            tag: "div".to_string(),
+            position: 0,
+        })
+    );
+    assert_eq!(
+        parse("<p>a</p></p>", &options),
+        Err(ParseError::UnexpectedCloseTag {
+            tag: "p".to_string(),
+            position: 8,
+        })
+    );
+    assert_eq!(
+        parse("<p><a href=\"x>b</a></p>", &options),
+        Err(ParseError::UnexpectedEnd { position: 3 })
+    );
+    assert_eq!(
+        parse("<p><!-- a </p>", &options),
+        Err(ParseError::UnexpectedEnd { position: 3 })
+    );
+    assert_eq!(parse("<p></p>", &options).map(|nodes| nodes.len()), Ok(1));
+    assert_eq!(
+        ParseError::UnclosedElement {
+            tag: "div".to_string(),
+            position: 0,
+        }
+        .to_string(),
+        "the element <div> at 0 is never closed"
+    );
+}
+
+#[test]
+fn hydrate_the_parsed_markup() {
+    let server = parse_one(r#"<button class="big">ok</button>"#);
+    let client: MyNode = element(
+        "button",
+        vec![attr("class", "big"), attr("on_click", "save")],
+        vec![leaf("ok")],
+    );
+    let is_hydrated = |att: &&str| att.starts_with("on_");
+    let options = diff::DiffOptions {
+        hydrate: Some(&is_hydrated),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&server, &client, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::add_attributes(
+            &"button",
+            TreePath::root(),
+            vec![&attr("on_click", "save")],
+        )]
+    );
+}
+
+#[cfg(feature = "html")]
+#[test]
+fn rendered_html_round_trips() {
+    let node: MyNode = element(
+        "main",
+        vec![attr("class", "a"), attr("class", "b")],
+        vec![
+            element("p", vec![], vec![leaf("x")]),
+            element("br", vec![], vec![]),
+            element_ns(
+                Some("http://www.w3.org/2000/svg"),
+                "svg",
+                vec![],
+                vec![],
+                false,
+            ),
+        ],
+    );
+    let rendered = html::node_to_html_string(&node, false);
+    let expected: MyNode = element(
+        "main",
+        vec![attr("class", "a b")],
+        vec![
+            element("p", vec![], vec![leaf("x")]),
+            element("br", vec![], vec![]),
+            element_ns(
+                Some("http://www.w3.org/2000/svg"),
+                "svg",
+                vec![],
+                vec![],
+                false,
+            ),
+        ],
+    );
+    assert_eq!(parse_one(&rendered), expected);
+}
@@ -0,0 +1,181 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{
+    codec::DecodeError,
+    patch::{codec::*, *},
+    *,
+};
+
+pub type MyNode = Node<String, String, String, String, String>;
+pub type MyOwnedPatch = OwnedPatch<String, String, String, String, String>;
+
+fn s(text: &str) -> String {
+    text.to_string()
+}
+
+/// a keyed list of `len` items rotated by `rotate`, where the text of every other item
+/// has the `text`
+fn list(len: usize, rotate: usize, text: &str) -> MyNode {
+    element(
+        s("ul"),
+        vec![attr(s("class"), s("list"))],
+        (0..len).map(|i| {
+            let key = (i + rotate) % len;
+            let text = if key.is_multiple_of(2) { text } else { "item" };
+            element(
+                s("li"),
+                vec![attr(s("key"), key.to_string())],
+                vec![leaf(format!("{text} {key}"))],
+            )
+        }),
+    )
+}
+
+#[test]
+fn diffed_patches_round_trip() {
+    let old = list(100, 0, "old");
+    let new = list(100, 1, "new");
+    let patches = diff_with_key(&old, &new, &s("key"));
+    let bytes = encode_patches(&patches);
+
+    let decoded: Vec<MyOwnedPatch> = decode_patches(&bytes).unwrap();
+    let owned: Vec<_> = patches.into_iter().map(Patch::into_owned).collect();
+    assert_eq!(decoded, owned);
+    assert_eq!(Node::from_patches(Some(old), &decoded), new);
+}
+
+#[test]
+fn every_patch_type_round_trips() {
+    let node: MyNode = element(s("p"), vec![], vec![leaf(s("x"))]);
+    let class = attr(s("class"), s("big"));
+    let (p, old, new, slot) = (s("p"), s("a"), s("b"), s("north"));
+    let name = s("theme");
+    let patches: Vec<Patch<String, String, String, String, String>> = vec![
+        Patch::insert_before_node(Some(&p), TreePath::new([0, 1]), vec![&node]),
+        Patch::insert_after_node(Some(&p), TreePath::new([0, 1]), vec![&node]),
+        Patch::append_children(Some(&p), TreePath::new([0]), vec![&node]),
+        Patch::remove_node(None, TreePath::new([300, 70_000])),
+        Patch::move_before_node(None, TreePath::new([2]), [TreePath::new([0])]),
+        Patch::move_after_node(None, TreePath::new([0]), [TreePath::new([2])]),
+        Patch::replace_node(None, TreePath::root(), vec![&node, &node]),
+        Patch::change_leaf(TreePath::new([0, 0]), &old, &new),
+        Patch::change_leaf_run(TreePath::new([1]), 2, [&old, &new]),
+        Patch::add_attributes(&p, TreePath::root(), vec![&class]),
+        Patch::remove_attributes(&p, TreePath::root(), vec![&class]),
+        Patch {
+            tag: Some(&p),
+            patch_path: TreePath::new([4]),
+            patch_type: PatchType::SpliceLeaf {
+                old: &old,
+                new: &new,
+                splices: vec![LeafSplice {
+                    old_start: 0,
+                    old_len: 1,
+                    new_start: 0,
+                    new_len: 1,
+                }],
+            },
+        },
+        Patch {
+            tag: None,
+            patch_path: TreePath::new([5]),
+            patch_type: PatchType::UpdateAttributeParts {
+                attr: &class,
+                parts: vec![
+                    AttributePart::Set {
+                        key: s("color"),
+                        value: s("red"),
+                    },
+                    AttributePart::Remove { key: s("margin") },
+                ],
+            },
+        },
+        Patch {
+            tag: None,
+            patch_path: TreePath::new([6]),
+            patch_type: PatchType::AdoptNode {
+                from_tree: TreeId(3),
+                from_path: TreePath::new([0, 2]),
+                placement: Placement::Append,
+            },
+        },
+        Patch {
+            tag: Some(&p),
+            patch_path: TreePath::new([7]),
+            patch_type: PatchType::SetSlot {
+                slot: &slot,
+                node: &node,
+            },
+        },
+        Patch {
+            tag: Some(&p),
+            patch_path: TreePath::new([7]),
+            patch_type: PatchType::ClearSlot { slot: &slot },
+        },
+        Patch::broadcast(
+            None,
+            TreePath::root(),
+            Selector::AttributeValue(&name, &new),
+            PatchType::ForEachChild {
+                patch: Box::new(PatchType::UpdateAttributes {
+                    add: vec![&class],
+                    remove: vec![],
+                }),
+            },
+        ),
+    ];
+    let bytes = encode_patches(&patches);
+    let owned: Vec<_> = patches.into_iter().map(Patch::into_owned).collect();
+    assert_eq!(decode_patches(&bytes), Ok(owned));
+}
+
+#[test]
+fn invalid_bytes_are_errors() {
+    let old = list(3, 0, "old");
+    let new = list(3, 1, "new");
+    let patches = diff_with_key(&old, &new, &s("key"));
+    let bytes = encode_patches(&patches);
+    let decode = |bytes: &[u8]| {
+        decode_patches::<String, String, String, String, String>(bytes)
+    };
+
+    assert_eq!(
+        decode(&bytes[..bytes.len() - 1]),
+        Err(DecodeError::UnexpectedEnd)
+    );
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(decode(&trailing), Err(DecodeError::TrailingBytes));
+    // the marker of the first patch, after the count and the length of the patch
+    let mut unknown = bytes.clone();
+    unknown[2] = 200;
+    assert!(matches!(decode(&unknown), Err(DecodeError::Invalid { .. })));
+    assert_eq!(decode(&[0]), Ok(vec![]));
+}
+
+#[test]
+fn deeply_nested_for_each_child_is_rejected() {
+    let nesting = 1_000_000;
+    let mut patch = vec![19, 0, 0];
+    patch.extend(std::iter::repeat_n(19, nesting));
+    patch.push(3);
+    let mut bytes = vec![1];
+    mt_dom::codec::encode_varint(patch.len() as u64, &mut bytes);
+    bytes.extend(patch);
+    assert!(matches!(
+        decode_patches::<String, String, String, String, String>(&bytes),
+        Err(DecodeError::Invalid { .. })
+    ));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn smaller_than_the_json_encoding() {
+    let old = list(100, 0, "old");
+    let new = list(100, 1, "new");
+    let patches = diff_with_key(&old, &new, &s("key"));
+    let bytes = encode_patches(&patches);
+    let json = serde_json::to_string(&to_json(&patches)).unwrap();
+    // the sizes in the documentation of `patch::codec`
+    assert_eq!((bytes.len(), json.len()), (757, 4_108));
+}
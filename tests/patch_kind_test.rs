@@ -0,0 +1,57 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn kinds_round_trip_through_their_names() {
+    for kind in PatchKind::ALL {
+        assert_eq!(kind.to_string().parse(), Ok(kind));
+    }
+    assert_eq!(PatchKind::MoveBeforeNode.to_string(), "move_before_node");
+    assert_eq!(
+        "MoveBeforeNode".parse::<PatchKind>(),
+        Err(ParsePatchKindError)
+    );
+    assert_eq!("".parse::<PatchKind>(), Err(ParsePatchKindError));
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn count_the_patches_per_kind() {
+    use mt_dom::testing::tree_from_str;
+    use std::collections::BTreeMap;
+
+    let old: MyNode = tree_from_str(
+        "ul
+           li[key=1] 'one'
+           li[key=2] 'two'
+           li[key=3] 'three'
+           li[key=4] 'four'
+           li[key=5] 'five'",
+    );
+    let new: MyNode = tree_from_str(
+        "ul.list
+           li[key=5] 'five'
+           li[key=2] 'two'
+           li[key=3] 'three'
+           li[key=4] 'four'
+           li[key=6] 'six'",
+    );
+    let patches = diff_with_key(&old, &new, &"key");
+    let mut counts: BTreeMap<PatchKind, usize> = BTreeMap::new();
+    for patch in patches.iter() {
+        *counts.entry(patch.kind()).or_default() += 1;
+        assert_eq!(patch.kind(), patch.patch_type.kind());
+    }
+    assert_eq!(
+        counts,
+        BTreeMap::from([
+            (PatchKind::InsertAfterNode, 1),
+            (PatchKind::RemoveNode, 1),
+            (PatchKind::MoveBeforeNode, 1),
+            (PatchKind::AddAttributes, 1),
+        ])
+    );
+}
@@ -0,0 +1,132 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+pub type MyOwnedPatch = OwnedPatch<
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+>;
+
+fn owned(old: &MyNode, new: &MyNode) -> Vec<MyOwnedPatch> {
+    diff_with_key(old, new, &"key")
+        .into_iter()
+        .map(Patch::into_owned)
+        .collect()
+}
+
+fn catch_up(
+    log: &PatchLog<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >,
+) -> MyNode {
+    let mut late = log.base().clone();
+    for batch in log.batches() {
+        let patches: Vec<_> = batch.iter().map(OwnedPatch::as_patch).collect();
+        apply_patches(&mut late, &patches);
+    }
+    late
+}
+
+fn list(items: Vec<MyNode>) -> MyNode {
+    element("ul", vec![attr("class", "list")], items)
+}
+
+fn item(key: &'static str, text: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(text)])
+}
+
+#[test]
+fn pushed_batches_are_applied_to_the_head() {
+    let base = list(vec![item("1", "one")]);
+    let next = list(vec![item("1", "one"), item("2", "two")]);
+    let last = list(vec![item("1", "uno"), item("2", "two")]);
+
+    let mut log = PatchLog::new(base.clone(), "key");
+    log.push(owned(&base, &next));
+    log.push(owned(&next, &last));
+    // the empty batches are not appended
+    log.push(owned(&last, &last));
+
+    assert_eq!(log.base(), &base);
+    assert_eq!(log.head(), &last);
+    assert_eq!(log.batches().len(), 2);
+    assert_eq!(catch_up(&log), last);
+}
+
+#[test]
+fn compact_drops_the_patches_of_removed_nodes() {
+    let base = list(vec![item("1", "one")]);
+    let added = list(vec![item("1", "one"), item("2", "two")]);
+    let edited = list(vec![item("1", "one"), item("2", "deux")]);
+    let removed = list(vec![item("1", "uno")]);
+
+    let mut log = PatchLog::new(base.clone(), "key");
+    log.push(owned(&base, &added));
+    log.push(owned(&added, &edited));
+    log.push(owned(&edited, &removed));
+    assert_eq!(log.patches().count(), 4);
+
+    log.compact();
+    assert_eq!(log.batches().len(), 1);
+    assert_eq!(log.patches().count(), 1);
+    assert_eq!(
+        log.patches().next().map(OwnedPatch::path),
+        Some(&TreePath::new([0, 0]))
+    );
+    assert_eq!(log.head(), &removed);
+    assert_eq!(catch_up(&log), removed);
+}
+
+#[test]
+fn compact_merges_the_attribute_changes() {
+    let base = list(vec![]);
+    let dark = element("ul", vec![attr("class", "dark")], vec![]);
+    let light = element("ul", vec![attr("class", "light")], vec![]);
+
+    let mut log = PatchLog::new(base.clone(), "key");
+    log.push(owned(&base, &dark));
+    log.push(owned(&dark, &light));
+    assert_eq!(log.patches().count(), 2);
+
+    log.compact();
+    assert_eq!(log.patches().count(), 1);
+    assert_eq!(catch_up(&log), light);
+}
+
+#[test]
+fn compacting_back_to_the_base_empties_the_log() {
+    let base = list(vec![item("1", "one")]);
+    let next = list(vec![item("1", "one"), item("2", "two")]);
+
+    let mut log = PatchLog::new(base.clone(), "key");
+    log.push(owned(&base, &next));
+    log.push(owned(&next, &base));
+    log.compact();
+
+    assert!(log.batches().is_empty());
+    assert_eq!(catch_up(&log), base);
+}
+
+#[test]
+fn checkpoint_makes_the_head_the_base() {
+    let base = list(vec![item("1", "one")]);
+    let next = list(vec![item("1", "uno")]);
+
+    let mut log = PatchLog::new(base.clone(), "key");
+    log.push(owned(&base, &next));
+    log.checkpoint();
+
+    assert_eq!(log.base(), &next);
+    assert_eq!(log.head(), &next);
+    assert!(log.batches().is_empty());
+}
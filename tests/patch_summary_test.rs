@@ -0,0 +1,59 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn empty_batch() {
+    let patches: Vec<Patch<&str, &str, &str, &str, &str>> = vec![];
+    let summary = PatchBatchSummary::from(patches.as_slice());
+    assert_eq!(summary, PatchBatchSummary::default());
+    assert_eq!(summary.to_string(), "no patches");
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn keyed_batch() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        (1..=5).map(|i| {
+            element(
+                "li",
+                vec![attr("key", ["a", "b", "c", "d", "e"][i - 1])],
+                vec![element("span", vec![attr("class", "x")], vec![])],
+            )
+        }),
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element(
+                "li",
+                vec![attr("key", "a")],
+                vec![element("span", vec![attr("class", "y")], vec![])],
+            ),
+            element(
+                "li",
+                vec![attr("key", "d")],
+                vec![element("span", vec![attr("class", "x")], vec![])],
+            ),
+            element(
+                "li",
+                vec![attr("key", "c")],
+                vec![element("span", vec![attr("class", "x")], vec![])],
+            ),
+            element(
+                "li",
+                vec![attr("key", "e")],
+                vec![element("span", vec![attr("class", "x")], vec![])],
+            ),
+        ],
+    );
+    let patches = diff_with_key(&old, &new, &"key");
+    let summary = PatchBatchSummary::from(patches.as_slice());
+    assert_eq!(summary.total(), patches.len());
+    assert_eq!(summary.to_string(), "1 attr, 1 move, 1 remove @ depth≤2");
+}
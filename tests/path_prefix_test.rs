@@ -0,0 +1,73 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn list(keys: &[&'static str]) -> MyNode {
+    element(
+        "ul",
+        vec![],
+        keys.iter().map(|key| {
+            element("li", vec![attr("key", *key)], vec![leaf(*key)])
+        }),
+    )
+}
+
+fn host(subtree: MyNode) -> MyNode {
+    element(
+        "body",
+        vec![],
+        vec![
+            element("header", vec![], vec![]),
+            element("main", vec![], vec![leaf("intro"), subtree]),
+        ],
+    )
+}
+
+#[test]
+fn patches_are_prefixed_with_the_mount_path() {
+    let old = list(&["1", "2", "3", "4"]);
+    let new = list(&["4", "2", "3", "5"]);
+    let options = DiffOptions {
+        path_prefix: TreePath::new([1, 1]),
+        ..Default::default()
+    };
+    let relative = diff_with_key(&old, &new, &"key");
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches.len(), relative.len());
+    for (patch, relative) in patches.iter().zip(relative.iter()) {
+        let mut path = patch.path().clone();
+        assert_eq!(path.pop_first(), Some(1));
+        assert_eq!(path.pop_first(), Some(1));
+        assert_eq!(&path, relative.path());
+    }
+    assert!(patches
+        .iter()
+        .flat_map(|patch| patch.node_paths())
+        .all(|path| path.starts_with(&TreePath::new([1, 1]))));
+
+    let mut root = host(old.clone());
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, host(new.clone()));
+}
+
+#[test]
+fn root_patches_are_prefixed() {
+    let old: MyNode = element("div", vec![], vec![]);
+    let new: MyNode = element("span", vec![], vec![]);
+    let options = DiffOptions {
+        path_prefix: TreePath::new([1, 1]),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::replace_node(
+            Some(&"div"),
+            TreePath::new([1, 1]),
+            vec![&new]
+        )]
+    );
+}
@@ -0,0 +1,137 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+/// a small linear congruential generator, so the scenarios are reproducible
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound
+    }
+}
+
+fn row(key: usize) -> MyNode {
+    let attrs = if key.is_multiple_of(3) {
+        vec![
+            attr("key", key.to_string()),
+            attr("sticky", "true".to_string()),
+        ]
+    } else {
+        vec![attr("key", key.to_string())]
+    };
+    element("tr", attrs, vec![leaf(format!("{key}"))])
+}
+
+fn is_sticky(node: &MyNode) -> bool {
+    node.attribute_value(&"sticky").is_some()
+}
+
+/// the old paths of the pinned children which are moved or removed
+fn disturbed_pinned(
+    old: &MyNode,
+    patches: &[Patch<&str, &str, String, &str, String>],
+) -> Vec<TreePath> {
+    patches
+        .iter()
+        .flat_map(|patch| match patch.patch_type {
+            PatchType::RemoveNode => vec![patch.path().clone()],
+            _ => patch.node_paths().to_vec(),
+        })
+        .filter(|path| is_sticky(path.find_node_by_path(old).unwrap()))
+        .collect()
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn pinned_header_is_not_moved() {
+    let old: MyNode = element("table", vec![], [0, 1, 2, 4, 5].map(row));
+    let new: MyNode = element("table", vec![], [1, 2, 4, 5, 0].map(row));
+
+    let options = DiffOptions {
+        is_pinned: Some(&is_sticky),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert!(
+        disturbed_pinned(&old, &patches).is_empty(),
+        "{:#?}",
+        patches
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+
+    // without pinning, the header is moved
+    let patches = diff_with_key(&old, &new, &"key");
+    assert!(!disturbed_pinned(&old, &patches).is_empty());
+}
+
+#[test]
+fn shuffled_rows_around_pinned_rows() {
+    let mut rng = Lcg(11);
+    let options = DiffOptions {
+        is_pinned: Some(&is_sticky),
+        ..Default::default()
+    };
+    for _ in 0..500 {
+        let len = rng.next(14);
+        let old_keys: Vec<usize> = (0..len).collect();
+        // shuffle only the rows which are not pinned
+        let mut new_keys = old_keys.clone();
+        let movable: Vec<usize> = (0..len)
+            .filter(|i| !old_keys[*i].is_multiple_of(3))
+            .collect();
+        for i in (1..movable.len()).rev() {
+            let j = rng.next(i + 1);
+            new_keys.swap(movable[i], movable[j]);
+        }
+        if rng.next(2) == 0 && !movable.is_empty() {
+            new_keys.remove(movable[rng.next(movable.len())]);
+        }
+        new_keys.insert(rng.next(new_keys.len() + 1), 100 + len);
+
+        let old: MyNode =
+            element("table", vec![], old_keys.iter().map(|k| row(*k)));
+        let new: MyNode =
+            element("table", vec![], new_keys.iter().map(|k| row(*k)));
+        let patches = diff_with_options(&old, &new, &"key", &options);
+        assert!(
+            disturbed_pinned(&old, &patches).is_empty(),
+            "old: {:?}, new: {:?}, patches: {:#?}",
+            old_keys,
+            new_keys,
+            patches
+        );
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        assert_eq!(root, new, "old: {:?}, new: {:?}", old_keys, new_keys);
+    }
+}
+
+#[test]
+fn reordered_pinned_rows_are_recreated() {
+    let old: MyNode = element("table", vec![], [0, 1, 3, 4].map(row));
+    let new: MyNode = element("table", vec![], [3, 1, 0, 4].map(row));
+    let options = DiffOptions {
+        is_pinned: Some(&is_sticky),
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    // no pinned child is moved, one of the swapped pinned children is recreated
+    assert!(patches.iter().all(|patch| patch
+        .node_paths()
+        .iter()
+        .all(|path| !is_sticky(path.find_node_by_path(&old).unwrap()))));
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
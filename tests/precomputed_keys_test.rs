@@ -0,0 +1,86 @@
+#![deny(warnings)]
+#![cfg(all(feature = "keyed", feature = "apply-patches"))]
+use mt_dom::{diff::*, *};
+
+pub type MyNode = Node<&'static str, &'static str, String, &'static str, u64>;
+
+fn row(id: u64) -> MyNode {
+    element("li", vec![attr("key", id)], vec![leaf(format!("{id}"))])
+}
+
+fn ids() -> (Vec<u64>, Vec<u64>) {
+    let old: Vec<u64> = (0..200).collect();
+    let mut new: Vec<u64> = (0..200).filter(|id| id % 9 != 4).collect();
+    new.reverse();
+    new.swap(3, 70);
+    new.insert(40, 500);
+    (old, new)
+}
+
+#[test]
+fn precomputed_keys_produce_the_same_patches() {
+    let (old_ids, new_ids) = ids();
+    let old: MyNode = element("ul", vec![], old_ids.iter().map(|id| row(*id)));
+    let new: MyNode = element("ul", vec![], new_ids.iter().map(|id| row(*id)));
+
+    let expected = diff_with_key(&old, &new, &"key");
+    for options in [
+        DiffOptions::default(),
+        DiffOptions::default().with_numeric_keys(),
+    ] {
+        let patches = diff_keyed_children_with_keys(
+            old.children(),
+            &old_ids,
+            new.children(),
+            &new_ids,
+            &TreePath::root(),
+            &"key",
+            &options,
+        );
+        assert_eq!(patches.len(), expected.len());
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        assert_eq!(root, new);
+    }
+}
+
+#[test]
+fn precomputed_keys_are_used_instead_of_the_attributes() {
+    // the children have no key attribute, but the keys are supplied
+    let old: MyNode =
+        element("ul", vec![], [leaf("a".to_string()), leaf("b".to_string())]);
+    let new: MyNode =
+        element("ul", vec![], [leaf("b".to_string()), leaf("a".to_string())]);
+    let patches = diff_keyed_children_with_keys(
+        old.children(),
+        &[1, 2],
+        new.children(),
+        &[2, 1],
+        &TreePath::root(),
+        &"key",
+        &DiffOptions::default(),
+    );
+    assert_eq!(
+        patches,
+        vec![Patch::move_before_node(
+            None,
+            TreePath::new([0]),
+            [TreePath::new([1])]
+        )]
+    );
+}
+
+#[test]
+#[should_panic(expected = "one key per old child")]
+fn mismatched_keys_panics() {
+    let old: MyNode = element("ul", vec![], [row(1)]);
+    diff_keyed_children_with_keys(
+        old.children(),
+        &[],
+        old.children(),
+        &[1],
+        &TreePath::root(),
+        &"key",
+        &DiffOptions::default(),
+    );
+}
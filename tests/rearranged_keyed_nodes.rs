@@ -1,3 +1,4 @@
+#![cfg(feature = "keyed")]
 use mt_dom::{diff::*, patch::*, *};
 
 pub type MyNode =
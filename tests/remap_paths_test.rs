@@ -0,0 +1,108 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn keyed(key: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(key)])
+}
+
+/// apply the remapped patches one by one, each against the tree as it is so far
+fn assert_sequential_round_trip(old: &MyNode, new: &MyNode) {
+    let options = DiffOptions {
+        detect_wrapping: true,
+        merge_text_runs: true,
+        ..Default::default()
+    };
+    let patches = diff_with_options(old, new, &"key", &options);
+    let remapped = remap_paths(old, &patches);
+    assert_eq!(remapped.len(), patches.len());
+
+    let mut root = old.clone();
+    for patch in remapped.iter() {
+        apply_patches(&mut root, std::slice::from_ref(patch));
+    }
+    assert_eq!(&root, new);
+}
+
+#[test]
+fn later_siblings_are_shifted_by_the_removals_and_insertions() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![leaf("a"), leaf("b"), leaf("c"), leaf("d")],
+    );
+    let x: MyNode = leaf("x");
+    let patches = vec![
+        Patch::remove_node(None, TreePath::new([0])),
+        Patch::insert_before_node(None, TreePath::new([2]), [&x]),
+        Patch::remove_node(None, TreePath::new([3])),
+        Patch::remove_node(None, TreePath::new([1])),
+    ];
+    let remapped = remap_paths(&old, &patches);
+    assert_eq!(
+        remapped
+            .iter()
+            .map(|patch| patch.path().clone())
+            .collect::<Vec<_>>(),
+        [
+            TreePath::new([0]),
+            TreePath::new([1]),
+            TreePath::new([3]),
+            TreePath::new([0]),
+        ]
+    );
+
+    let mut root = old.clone();
+    for patch in remapped.iter() {
+        apply_patches(&mut root, std::slice::from_ref(patch));
+    }
+    assert_eq!(root, element("ul", vec![], vec![leaf("x"), leaf("c")]));
+}
+
+#[test]
+fn moved_nodes_are_remapped() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        ["a", "b", "c", "d", "e"].into_iter().map(keyed),
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        ["e", "c", "a", "x", "b"].into_iter().map(keyed),
+    );
+    assert_sequential_round_trip(&old, &new);
+}
+
+#[test]
+fn wrapped_and_unwrapped_nodes_are_remapped() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            leaf("hello"),
+            element("b", vec![], vec![leaf("bold")]),
+            element("p", vec![attr("class", "old")], vec![leaf("text")]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("i", vec![], vec![leaf("hello")]),
+            leaf("bold"),
+            element("p", vec![attr("class", "new")], vec![leaf("text")]),
+        ],
+    );
+    assert_sequential_round_trip(&old, &new);
+}
+
+#[test]
+fn paths_under_a_replaced_root_are_remapped() {
+    let old: MyNode = leaf("hello");
+    let new: MyNode = element("div", vec![], vec![leaf("hello")]);
+    assert_sequential_round_trip(&old, &new);
+}
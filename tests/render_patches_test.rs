@@ -0,0 +1,93 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn item(key: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(key)])
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn keyed_patches_are_rendered_one_per_line() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![item("1"), item("2"), item("3"), item("4")],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![item("4"), item("1"), item("3"), item("5")],
+    );
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        render_patches(&patches),
+        concat!(
+            "  [1] remove_node <li>\n",
+            "  [0] move_before_node <li> [3]\n",
+            "  [2] insert_after_node <li> <li key=\"5\"> (1 child)\n",
+        )
+    );
+}
+
+#[test]
+fn long_leaves_are_truncated() {
+    let old: MyNode = element("p", vec![], vec![leaf("short")]);
+    let new: MyNode = element(
+        "p",
+        vec![],
+        vec![leaf("a very long text which is longer than the preview")],
+    );
+    let options = DiffOptions {
+        change_leaf: true,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        render_patches(&patches),
+        "  [0] change_leaf \"short\" -> \"a very long text which is longer than t…\n"
+    );
+}
+
+#[test]
+fn attribute_updates_are_rendered() {
+    let old: MyNode =
+        element("div", vec![attr("class", "a"), attr("hidden", "")], vec![]);
+    let new: MyNode =
+        element("div", vec![attr("class", "b"), attr("id", "main")], vec![]);
+    let options = DiffOptions {
+        update_attributes: true,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(
+        render_patches(&patches),
+        "[] update_attributes <div> +class=\"b\", +id=\"main\", -hidden\n"
+    );
+}
+
+#[test]
+fn the_tree_is_rendered_with_its_depth() {
+    let node: MyNode = element(
+        "main",
+        vec![attr("id", "app")],
+        vec![
+            fragment([leaf("a"), leaf("b")]),
+            element("ul", vec![], vec![item("1")]),
+        ],
+    );
+    assert_eq!(
+        node.render_tree(),
+        concat!(
+            "<main id=\"app\">\n",
+            "  fragment\n",
+            "    \"a\"\n",
+            "    \"b\"\n",
+            "  <ul>\n",
+            "    <li key=\"1\">\n",
+            "      \"1\"\n",
+        )
+    );
+}
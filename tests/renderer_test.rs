@@ -0,0 +1,335 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, patch::*, testing::tree_from_str, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+pub type MyAttribute = Attribute<&'static str, &'static str, &'static str>;
+pub type MySelector<'a> =
+    Selector<'a, &'static str, &'static str, &'static str>;
+pub type MyOptions<'o> = DiffOptions<
+    'o,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+>;
+
+/// a retained mode backend, such as the DOM, whose nodes are referred to by their id
+struct Dom {
+    /// each node without its children, and the ids of its parent and children
+    nodes: Vec<(MyNode, Option<usize>, Vec<usize>)>,
+    root: usize,
+}
+
+impl Dom {
+    fn mount(node: &MyNode) -> Self {
+        let mut dom = Dom {
+            nodes: vec![],
+            root: 0,
+        };
+        dom.root = dom.create(node);
+        dom
+    }
+
+    fn create(&mut self, node: &MyNode) -> usize {
+        let shallow = match node {
+            Node::Element(element) => {
+                let mut element = element.clone();
+                element.children.clear();
+                Node::Element(element)
+            }
+            _ => node.clone(),
+        };
+        let id = self.nodes.len();
+        self.nodes.push((shallow, None, vec![]));
+        let children: Vec<usize> = node
+            .children()
+            .iter()
+            .map(|child| self.create(child))
+            .collect();
+        self.attach(id, 0, &children);
+        id
+    }
+
+    fn create_all(&mut self, nodes: &[&MyNode]) -> Vec<usize> {
+        nodes.iter().map(|node| self.create(node)).collect()
+    }
+
+    fn to_node(&self, id: usize) -> MyNode {
+        let (node, _, children) = &self.nodes[id];
+        let mut node = node.clone();
+        if node.is_element() {
+            node.add_children(
+                children.iter().map(|child| self.to_node(*child)),
+            )
+            .unwrap();
+        }
+        node
+    }
+
+    fn position(&self, id: usize) -> (usize, usize) {
+        let parent = self.nodes[id].1.expect("must have a parent");
+        let position = self.nodes[parent].2.iter().position(|c| *c == id);
+        (parent, position.expect("must be a child of its parent"))
+    }
+
+    fn attach(&mut self, parent: usize, position: usize, ids: &[usize]) {
+        for id in ids {
+            self.nodes[*id].1 = Some(parent);
+        }
+        self.nodes[parent]
+            .2
+            .splice(position..position, ids.iter().copied());
+    }
+
+    fn detach(&mut self, id: usize) {
+        let (parent, position) = self.position(id);
+        self.nodes[parent].2.remove(position);
+        self.nodes[id].1 = None;
+    }
+
+    fn place(&mut self, target: usize, offset: usize, ids: &[usize]) {
+        let (parent, position) = self.position(target);
+        self.attach(parent, position + offset, ids);
+    }
+}
+
+impl
+    Renderer<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    > for Dom
+{
+    type Handle = usize;
+
+    fn resolve(&mut self, path: &TreePath) -> Option<usize> {
+        path.indices().try_fold(self.root, |id, index| {
+            self.nodes[id].2.get(index).copied()
+        })
+    }
+
+    fn children(&mut self, target: &usize) -> Vec<usize> {
+        self.nodes[*target].2.clone()
+    }
+
+    fn select(&mut self, target: &usize, selector: &MySelector) -> Vec<usize> {
+        let mut selected = vec![];
+        let mut stack = vec![*target];
+        while let Some(id) = stack.pop() {
+            if selector.matches(&self.nodes[id].0) {
+                selected.push(id);
+            }
+            stack.extend(self.nodes[id].2.iter().rev());
+        }
+        selected
+    }
+
+    fn insert_before(&mut self, target: &usize, nodes: &[&MyNode]) {
+        let ids = self.create_all(nodes);
+        self.place(*target, 0, &ids);
+    }
+
+    fn insert_after(&mut self, target: &usize, nodes: &[&MyNode]) {
+        let ids = self.create_all(nodes);
+        self.place(*target, 1, &ids);
+    }
+
+    fn append_children(&mut self, target: &usize, children: &[&MyNode]) {
+        let ids = self.create_all(children);
+        let position = self.nodes[*target].2.len();
+        self.attach(*target, position, &ids);
+    }
+
+    fn remove(&mut self, target: &usize) {
+        self.detach(*target);
+    }
+
+    fn move_before(&mut self, target: &usize, nodes: &[usize]) {
+        nodes.iter().for_each(|id| self.detach(*id));
+        self.place(*target, 0, nodes);
+    }
+
+    fn move_after(&mut self, target: &usize, nodes: &[usize]) {
+        nodes.iter().for_each(|id| self.detach(*id));
+        self.place(*target, 1, nodes);
+    }
+
+    fn move_into(&mut self, target: &usize, nodes: &[usize]) {
+        nodes.iter().for_each(|id| self.detach(*id));
+        let position = self.nodes[*target].2.len();
+        self.attach(*target, position, nodes);
+    }
+
+    fn replace(&mut self, target: &usize, replacement: &[&MyNode]) {
+        let ids = self.create_all(replacement);
+        if *target == self.root {
+            self.root = ids[0];
+        } else {
+            self.place(*target, 0, &ids);
+            self.detach(*target);
+        }
+    }
+
+    fn wrap(&mut self, target: &usize, wrapper: &MyNode) {
+        let (parent, position) = self.position(*target);
+        let count = wrapper.children().len();
+        let wrapped: Vec<usize> = self.nodes[parent]
+            .2
+            .drain(position..position + count)
+            .collect();
+        let wrapper_id = self.create(&element(*wrapper.tag().unwrap(), [], []));
+        self.attach(wrapper_id, 0, &wrapped);
+        self.attach(parent, position, &[wrapper_id]);
+    }
+
+    fn unwrap(&mut self, target: &usize) {
+        let children = std::mem::take(&mut self.nodes[*target].2);
+        self.place(*target, 0, &children);
+        self.detach(*target);
+    }
+
+    fn change_leaf(&mut self, target: &usize, _old: &&str, new: &&'static str) {
+        self.nodes[*target].0 = leaf(*new);
+    }
+
+    fn change_tag(&mut self, target: &usize, _old: &&str, new: &&'static str) {
+        self.nodes[*target].0.element_mut().unwrap().set_tag(new);
+    }
+
+    fn change_leaf_run(
+        &mut self,
+        target: &usize,
+        count: usize,
+        leaves: &[&&'static str],
+    ) {
+        let (parent, position) = self.position(*target);
+        self.nodes[parent].2.drain(position..position + count);
+        let ids: Vec<usize> = leaves
+            .iter()
+            .map(|text| self.create(&leaf(**text)))
+            .collect();
+        self.attach(parent, position, &ids);
+    }
+
+    fn add_attributes(&mut self, target: &usize, attrs: &[&MyAttribute]) {
+        let element = self.nodes[*target].0.element_mut().unwrap();
+        attrs
+            .iter()
+            .for_each(|attr| element.remove_attribute(&attr.name));
+        element.add_attributes(attrs.iter().map(|attr| (*attr).clone()));
+    }
+
+    fn remove_attributes(&mut self, target: &usize, attrs: &[&MyAttribute]) {
+        let element = self.nodes[*target].0.element_mut().unwrap();
+        attrs
+            .iter()
+            .for_each(|attr| element.remove_attribute(&attr.name));
+    }
+}
+
+fn render(old: &MyNode, new: &MyNode, options: &MyOptions) {
+    let patches = diff_with_options(old, new, &"key", options);
+    let mut dom = Dom::mount(old);
+    apply(&patches, &mut dom).unwrap();
+    assert_eq!(dom.to_node(dom.root), *new);
+}
+
+#[test]
+fn keyed_rows() {
+    let old: MyNode = tree_from_str(
+        "ul.list
+           li[key=1] 'one'
+           li[key=2] 'two'
+           li[key=3] 'three'
+           li[key=4] 'four'
+           li[key=5] 'five'",
+    );
+    let new: MyNode = tree_from_str(
+        "ul.list.done
+           li[key=5] 'five'
+           li[key=2] 'two'
+           li[key=6] 'six'
+           li[key=1] 'uno'
+           li[key=4] 'four'",
+    );
+    render(&old, &new, &DiffOptions::default());
+}
+
+#[test]
+fn wrap_and_change_leaves() {
+    let old: MyNode = tree_from_str(
+        "main
+           span 'hello'
+           p 'text'
+           'world'",
+    );
+    let new: MyNode = tree_from_str(
+        "main
+           section
+             span 'hello'
+           p 'text'
+           'everyone'",
+    );
+    let options = DiffOptions {
+        detect_wrapping: true,
+        change_leaf: true,
+        ..DiffOptions::default()
+    };
+    render(&old, &new, &options);
+    render(&new, &old, &options);
+}
+
+#[test]
+fn for_each_child_and_broadcast() {
+    let old: MyNode = tree_from_str(
+        "table
+           tr.light 'a'
+           tr.light 'b'",
+    );
+    let new: MyNode = tree_from_str(
+        "table
+           tr.dark 'a'
+           tr.dark 'b'",
+    );
+    let options = DiffOptions {
+        for_each_child: true,
+        ..DiffOptions::default()
+    };
+    render(&old, &new, &options);
+
+    let dark = attr("class", "dark");
+    let patch = Patch::broadcast(
+        Some(&"table"),
+        TreePath::root(),
+        Selector::Tag(&"tr"),
+        PatchType::AddAttributes { attrs: vec![&dark] },
+    );
+    let mut dom = Dom::mount(&old);
+    apply(&[patch], &mut dom).unwrap();
+    assert_eq!(dom.to_node(dom.root), new);
+}
+
+#[test]
+fn unresolved_paths_are_rejected_before_rendering() {
+    let old: MyNode = tree_from_str(
+        "ul
+           li 'one'",
+    );
+    let patches = vec![
+        Patch::remove_node(Some(&"li"), TreePath::new([0])),
+        Patch::remove_node(None, TreePath::new([3])),
+    ];
+    let mut dom = Dom::mount(&old);
+    assert_eq!(
+        apply(&patches, &mut dom),
+        Err(InvalidPatch::UnresolvedPath {
+            patch_index: 1,
+            path: TreePath::new([3])
+        })
+    );
+    assert_eq!(dom.to_node(dom.root), old);
+}
@@ -0,0 +1,69 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn assert_applied(
+    old: &MyNode,
+    expected: &MyNode,
+    patches: &[Patch<&str, &str, &str, &str, &str>],
+) {
+    let mut root = old.clone();
+    apply_patches(&mut root, patches);
+    assert_eq!(&root, expected);
+}
+
+#[test]
+fn replace_root_of_every_variant() {
+    let nodes: Vec<MyNode> = vec![
+        leaf("hello"),
+        element("div", vec![attr("class", "a")], vec![leaf("hi")]),
+        fragment(vec![leaf("a"), element("b", vec![], vec![])]),
+        element("span", vec![], vec![]),
+    ];
+    for old in nodes.iter() {
+        for new in nodes.iter() {
+            let patches = diff_with_key(old, new, &"key");
+            assert_applied(old, new, &patches);
+        }
+    }
+}
+
+#[test]
+fn replace_root_with_multiple_nodes() {
+    let old: MyNode = element("div", vec![], vec![]);
+    let a = leaf("a");
+    let b = element("b", vec![], vec![]);
+    let patches = vec![Patch::replace_node(
+        Some(&"div"),
+        TreePath::root(),
+        [&a, &b],
+    )];
+    assert_applied(&old, &fragment(vec![a.clone(), b.clone()]), &patches);
+}
+
+#[test]
+fn wrap_and_unwrap_root() {
+    let options = DiffOptions {
+        detect_wrapping: true,
+        ..Default::default()
+    };
+    let plain: MyNode = leaf("hello");
+    let bold: MyNode = element("b", vec![], vec![leaf("hello")]);
+
+    let patches = diff_with_options(&plain, &bold, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::wrap_node(None, TreePath::root(), &bold)]
+    );
+    assert_applied(&plain, &bold, &patches);
+
+    let patches = diff_with_options(&bold, &plain, &"key", &options);
+    assert_eq!(
+        patches,
+        vec![Patch::unwrap_node(Some(&"b"), TreePath::root())]
+    );
+    assert_applied(&bold, &plain, &patches);
+}
@@ -0,0 +1,62 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, *};
+use std::sync::mpsc;
+use std::thread;
+
+pub type MyNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn patches_are_send_and_sync() {
+    assert_send_sync::<TreePath>();
+    assert_send_sync::<MyNode>();
+    assert_send_sync::<
+        Patch<
+            'static,
+            &'static str,
+            &'static str,
+            String,
+            &'static str,
+            String,
+        >,
+    >();
+    assert_send_sync::<
+        PatchType<
+            'static,
+            &'static str,
+            &'static str,
+            String,
+            &'static str,
+            String,
+        >,
+    >();
+}
+
+#[test]
+fn diff_in_worker_thread() {
+    let old: MyNode = element(
+        "main",
+        vec![attr("class", "container".to_string())],
+        vec![leaf("hello".to_string())],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![attr("class", "dark".to_string())],
+        vec![leaf("world".to_string())],
+    );
+
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        let (old, new) = (&old, &new);
+        scope.spawn(move || {
+            tx.send(diff_with_key(old, new, &"key")).expect("must send");
+        });
+        let patches = rx.recv().expect("must receive the patches");
+        let mut root = old.clone();
+        apply_patches(&mut root, &patches);
+        assert_eq!(&root, new);
+    });
+}
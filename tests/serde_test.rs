@@ -0,0 +1,70 @@
+#![deny(warnings)]
+#![cfg(feature = "serde")]
+use mt_dom::{patch::*, *};
+
+pub type MyNode = Node<String, String, String, String, String>;
+
+fn list(items: &[&str]) -> MyNode {
+    element(
+        "ul".to_string(),
+        vec![attr("class".to_string(), "list".to_string())],
+        items.iter().map(|item| {
+            element(
+                "li".to_string(),
+                vec![attr("key".to_string(), item.to_string())],
+                vec![leaf(item.to_string())],
+            )
+        }),
+    )
+}
+
+#[test]
+fn node_round_trips() {
+    let node = list(&["1", "2"]);
+    let json = serde_json::to_string(&node).expect("serialize");
+    let decoded: MyNode = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(decoded, node);
+}
+
+#[test]
+fn tree_path_round_trips() {
+    let path = TreePath::new([0, 3, 1]);
+    let json = serde_json::to_string(&path).expect("serialize");
+    assert_eq!(
+        serde_json::from_str::<TreePath>(&json).expect("deserialize"),
+        path
+    );
+}
+
+#[test]
+fn patches_are_deserialized_as_owned_patches() {
+    let old = list(&["1", "2", "3"]);
+    let new = list(&["3", "1", "4"]);
+    let patches = diff_with_key(&old, &new, &"key".to_string());
+    let json = serde_json::to_string(&patches).expect("serialize");
+
+    let decoded: Vec<OwnedPatch<String, String, String, String, String>> =
+        serde_json::from_str(&json).expect("deserialize");
+    let owned: Vec<_> = patches.into_iter().map(Patch::into_owned).collect();
+    assert_eq!(decoded, owned);
+
+    // the patches computed on the server are applied on the client
+    let client = Node::from_patches(Some(old.clone()), &decoded);
+    assert_eq!(client, new);
+}
+
+#[test]
+fn broadcast_patch_round_trips() {
+    let dark = attr("theme".to_string(), "dark".to_string());
+    let name = "theme".to_string();
+    let patch: Patch<String, String, String, String, String> = Patch::broadcast(
+        None,
+        TreePath::root(),
+        Selector::HasAttribute(&name),
+        PatchType::AddAttributes { attrs: vec![&dark] },
+    );
+    let json = serde_json::to_string(&patch).expect("serialize");
+    let decoded: OwnedPatch<String, String, String, String, String> =
+        serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(decoded, patch.into_owned());
+}
@@ -0,0 +1,135 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn pane(text: &'static str) -> MyNode {
+    element("pane", vec![], vec![leaf(text)])
+}
+
+fn split(panes: &[(&'static str, &'static str)]) -> MyNode {
+    slotted_element(
+        "split",
+        vec![],
+        panes.iter().map(|(slot, text)| (*slot, pane(text))),
+    )
+}
+
+fn assert_patches_apply(old: &MyNode, new: &MyNode) -> Vec<PatchKind> {
+    let patches = diff_with_key(old, new, &"key");
+    assert_eq!(validate(old, &patches), Ok(()));
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new);
+    patches.iter().map(Patch::kind).collect()
+}
+
+#[test]
+fn the_children_of_the_same_slot_are_diffed() {
+    let old = split(&[("north", "top"), ("south", "bottom")]);
+    let new = split(&[("north", "top"), ("south", "changed")]);
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].path(), &TreePath::new([1, 0]));
+    assert_patches_apply(&old, &new);
+}
+
+#[test]
+fn the_slots_are_set_and_cleared() {
+    let old = split(&[("north", "top"), ("south", "bottom")]);
+    let new = split(&[("north", "top"), ("east", "side")]);
+    let patches = diff_with_key(&old, &new, &"key");
+    let east = pane("side");
+    assert_eq!(
+        patches,
+        vec![
+            Patch::clear_slot(&"split", TreePath::root(), &"south"),
+            Patch::set_slot(&"split", TreePath::root(), &"east", &east),
+        ]
+    );
+    assert_patches_apply(&old, &new);
+}
+
+#[test]
+fn the_reordered_slots_are_set_in_the_new_order() {
+    let old = split(&[("north", "top"), ("south", "bottom"), ("east", "side")]);
+    let new = split(&[("east", "side"), ("north", "top"), ("west", "left")]);
+    assert_eq!(
+        assert_patches_apply(&old, &new),
+        [PatchKind::ClearSlot, PatchKind::SetSlot, PatchKind::SetSlot]
+    );
+}
+
+#[test]
+fn the_elements_are_replaced_when_only_one_is_slotted() {
+    let old = split(&[("north", "top")]);
+    let new = element("split", vec![], vec![pane("top")]);
+    assert_eq!(assert_patches_apply(&old, &new), [PatchKind::ReplaceNode]);
+}
+
+#[test]
+fn the_slots_of_an_element_are_set_and_cleared() {
+    let mut element = Element::new_slotted(
+        None,
+        "split",
+        vec![],
+        vec![("north", pane("top")), ("south", pane("bottom"))],
+        false,
+    );
+    element.set_slot("north", pane("changed"));
+    assert_eq!(element.slots, Some(vec!["south", "north"]));
+    assert_eq!(element.slot(&"north"), Some(&pane("changed")));
+    assert_eq!(element.clear_slot(&"south"), Some(pane("bottom")));
+    assert_eq!(element.clear_slot(&"south"), None);
+    assert_eq!(
+        element.slotted_children().collect::<Vec<_>>(),
+        [(&"north", &pane("changed"))]
+    );
+}
+
+#[test]
+fn the_slot_patches_need_a_slotted_target() {
+    let old = element("split", vec![], vec![pane("top")]);
+    let patches = vec![Patch::clear_slot(&"split", TreePath::root(), &"north")];
+    assert_eq!(
+        validate(&old, &patches),
+        Err(InvalidPatch::NotSlotted {
+            patch_index: 0,
+            path: TreePath::root()
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "use `set_slot` instead")]
+fn children_are_not_added_to_a_slotted_element() {
+    let mut element = Element::new_slotted(
+        None,
+        "split",
+        vec![],
+        vec![("north", pane("top"))],
+        false,
+    );
+    element.add_children([pane("bottom")]);
+}
+
+#[test]
+fn the_elements_are_replaced_when_the_slots_are_not_one_per_child() {
+    let mut old = split(&[("north", "top")]);
+    assert!(old.add_children([pane("bottom")]).is_err());
+    // the invariant is only broken by changing the children directly
+    old.element_mut().unwrap().children.push(pane("bottom"));
+    let new = split(&[("north", "top"), ("south", "bottom")]);
+    assert_eq!(assert_patches_apply(&old, &new), [PatchKind::ReplaceNode]);
+
+    let patches = vec![Patch::clear_slot(&"split", TreePath::root(), &"north")];
+    assert_eq!(
+        validate(&old, &patches),
+        Err(InvalidPatch::NotSlotted {
+            patch_index: 0,
+            path: TreePath::root()
+        })
+    );
+}
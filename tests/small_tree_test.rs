@@ -0,0 +1,73 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, testing::assert_algorithms_agree, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+type Attrs = &'static [(&'static str, &'static str)];
+
+/// the attributes, padded with distinct unchanged attributes
+/// so the element is diffed with or without the small element fast path
+fn div(attrs: &[(&'static str, &'static str)], padding: usize) -> MyNode {
+    const PADDING: [&str; 5] = ["p0", "p1", "p2", "p3", "p4"];
+    element(
+        "div",
+        attrs
+            .iter()
+            .map(|(name, value)| attr(*name, *value))
+            .chain(PADDING[..padding].iter().map(|name| attr(*name, "x"))),
+        vec![],
+    )
+}
+
+#[test]
+fn small_and_large_attributes_are_diffed_the_same() {
+    let cases: &[(Attrs, Attrs)] = &[
+        (&[("class", "a")], &[("class", "b")]),
+        (&[("class", "a")], &[("id", "a")]),
+        (&[("class", "a"), ("class", "b")], &[("class", "a")]),
+        (&[("class", "a")], &[("class", "a"), ("class", "b")]),
+        (
+            &[("class", "a"), ("id", "x"), ("class", "b")],
+            &[("class", "a"), ("class", "b"), ("title", "t")],
+        ),
+        (&[("class", "a"), ("id", "x")], &[]),
+        (&[], &[("class", "a"), ("id", "x"), ("class", "c")]),
+    ];
+    for (old_attrs, new_attrs) in cases {
+        let small_old = div(old_attrs, 0);
+        let small_new = div(new_attrs, 0);
+        let large_old = div(old_attrs, 5);
+        let large_new = div(new_attrs, 5);
+        assert!(large_old.attributes().unwrap().len() > 4);
+
+        let small = diff_with_key(&small_old, &small_new, &"key");
+        let large = diff_with_key(&large_old, &large_new, &"key");
+        assert_eq!(small, large, "{old_attrs:?} -> {new_attrs:?}");
+    }
+}
+
+#[test]
+fn small_keyed_lists() {
+    let li = |key: &'static str, text: &'static str| -> MyNode {
+        element("li", vec![attr("key", key)], vec![leaf(text)])
+    };
+    let old: MyNode =
+        element("ul", vec![], vec![li("1", "a"), li("2", "b"), li("3", "c")]);
+    let new: MyNode =
+        element("ul", vec![], vec![li("1", "a"), li("2", "B"), li("3", "c")]);
+    assert_eq!(
+        diff_with_key(&old, &new, &"key"),
+        vec![Patch::replace_node(
+            None,
+            TreePath::new([1, 0]),
+            [&leaf("B")]
+        )]
+    );
+    assert_algorithms_agree(&old, &new, &"key");
+
+    let reordered: MyNode =
+        element("ul", vec![], vec![li("3", "c"), li("1", "a"), li("2", "b")]);
+    assert_algorithms_agree(&old, &reordered, &"key");
+}
@@ -0,0 +1,50 @@
+#![deny(warnings)]
+use mt_dom::{testing::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn shuffled() -> (MyNode, MyNode) {
+    let old = tree_from_str(
+        "ul.list
+           li[key=1] 'one'
+           li[key=2] 'two'
+           li[key=3] 'three'
+           li[key=4] 'four'",
+    );
+    let new = tree_from_str(
+        "ul.list
+           li[key=4] 'four'
+           li[key=2] 'two'
+           li[key=5] 'five'
+           li[key=1] 'uno'",
+    );
+    (old, new)
+}
+
+#[cfg(feature = "keyed")]
+#[test]
+fn keyed_shuffle_matches_its_snapshot() {
+    let (old, new) = shuffled();
+    snapshot_patches("keyed_shuffle", &diff_with_key(&old, &new, &"key"));
+}
+
+#[test]
+#[should_panic(expected = "the patches differ from the snapshot")]
+fn changed_patches_do_not_match_the_snapshot() {
+    if std::env::var_os(UPDATE_SNAPSHOTS).is_some() {
+        panic!("the patches differ from the snapshot, skipped while updating");
+    }
+    let (old, new) = shuffled();
+    let patches = diff_with_key(&old, &new, &"key");
+    snapshot_patches("keyed_shuffle", &patches[1..]);
+}
+
+#[test]
+fn format_has_one_line_per_patch() {
+    let (old, new) = shuffled();
+    let patches = diff_with_key(&old, &new, &"key");
+    let text = format_patches(&patches);
+    assert_eq!(text.lines().count(), patches.len());
+    assert!(text.lines().all(|line| line.starts_with("at [")));
+}
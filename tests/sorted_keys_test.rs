@@ -0,0 +1,91 @@
+#![deny(warnings)]
+#![cfg(all(feature = "keyed", feature = "apply-patches"))]
+use mt_dom::{diff::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, String, &'static str, String>;
+
+fn item(key: &str) -> MyNode {
+    element(
+        "li",
+        vec![attr("key", key.to_string())],
+        vec![leaf(key.to_string())],
+    )
+}
+
+fn list(keys: &[String]) -> MyNode {
+    element("ul", vec![], keys.iter().map(|key| item(key)))
+}
+
+#[test]
+fn ordered_keys_produce_the_same_patches() {
+    let old_keys: Vec<String> =
+        (0..500).map(|id| format!("row-{id}")).collect();
+    // a deterministic shuffle which moves, removes and inserts some of the rows
+    let mut new_keys: Vec<String> = old_keys
+        .iter()
+        .enumerate()
+        .filter(|(id, _)| id % 7 != 3)
+        .map(|(_, key)| key.clone())
+        .rev()
+        .collect();
+    new_keys.swap(1, 10);
+    new_keys.insert(20, "inserted".to_string());
+    let old = list(&old_keys);
+    let new = list(&new_keys);
+
+    let expected = diff_with_key(&old, &new, &"key");
+    let options = DiffOptions::default().with_ordered_keys();
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches, expected);
+    let scanned = DiffOptions {
+        scan_keys: true,
+        ..Default::default()
+    };
+    assert_eq!(diff_with_options(&old, &new, &"key", &scanned), expected);
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn duplicate_and_missing_keys() {
+    let keys = |keys: &[&str]| -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    };
+    let mut old = list(&keys(&["a", "b", "a", "c", "b", "d", "e", "f", "g"]));
+    let new = list(&keys(&["g", "b", "a", "x", "a", "c", "b", "e", "d"]));
+    // an unkeyed child is never matched
+    old.add_children([element("li", vec![], vec![])]).unwrap();
+
+    let expected = diff_with_key(&old, &new, &"key");
+    let options = DiffOptions::default().with_ordered_keys();
+    assert_eq!(diff_with_options(&old, &new, &"key", &options), expected);
+}
+
+#[test]
+fn ordered_keys_of_precomputed_keys() {
+    let old_keys: Vec<String> =
+        ["a", "b", "c", "d", "e", "f"].map(String::from).to_vec();
+    let new_keys: Vec<String> =
+        ["f", "e", "c", "d", "b", "a"].map(String::from).to_vec();
+    let old: Vec<MyNode> = old_keys.iter().map(|key| item(key)).collect();
+    let new: Vec<MyNode> = new_keys.iter().map(|key| item(key)).collect();
+
+    let diff = |options: &DiffOptions<_, _, _, _, _>| {
+        diff_keyed_children_with_keys(
+            &old,
+            &old_keys,
+            &new,
+            &new_keys,
+            &TreePath::root(),
+            &"key",
+            options,
+        )
+    };
+    assert_eq!(
+        diff(&DiffOptions::default().with_ordered_keys()),
+        diff(&DiffOptions::default())
+    );
+}
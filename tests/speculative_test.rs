@@ -0,0 +1,112 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+pub type MyOwnedPatch = OwnedPatch<
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+>;
+
+fn owned(old: &MyNode, new: &MyNode) -> Vec<MyOwnedPatch> {
+    diff_with_key(old, new, &"key")
+        .into_iter()
+        .map(Patch::into_owned)
+        .collect()
+}
+
+fn counter(count: &'static str) -> MyNode {
+    element(
+        "div",
+        vec![],
+        vec![element("span", vec![], vec![leaf(count)])],
+    )
+}
+
+#[test]
+fn confirmed_prediction_keeps_the_predicted_tree() {
+    let mut state = SpeculativeState::new(counter("0"));
+    let patches = owned(&counter("0"), &counter("1"));
+    let generation = state.predict(patches.clone());
+    assert_eq!(state.predicted(), &counter("1"));
+    assert_eq!(state.confirmed(), &counter("0"));
+
+    let reconciled = state.reconcile(Some(generation), &patches);
+    assert_eq!(reconciled, Reconciliation::default());
+    assert_eq!(state.confirmed(), &counter("1"));
+    assert_eq!(state.predicted(), &counter("1"));
+    assert_eq!(state.pending().count(), 0);
+}
+
+#[test]
+fn mismatched_prediction_is_rolled_back() {
+    let mut state = SpeculativeState::new(counter("0"));
+    let generation = state.predict(owned(&counter("0"), &counter("1")));
+
+    // the server counted someone else's click too
+    let reconciled =
+        state.reconcile(Some(generation), &owned(&counter("0"), &counter("2")));
+    assert_eq!(reconciled.rolled_back, vec![generation]);
+    assert!(reconciled.rebuilt);
+    assert_eq!(state.predicted(), &counter("2"));
+    assert_eq!(state.confirmed(), &counter("2"));
+}
+
+#[test]
+fn later_predictions_are_replayed() {
+    let old: MyNode = element("ul", vec![], vec![]);
+    let one: MyNode = element("ul", vec![attr("class", "busy")], vec![]);
+    let two: MyNode = element(
+        "ul",
+        vec![attr("class", "busy")],
+        vec![element("li", vec![], vec![leaf("draft")])],
+    );
+    let mut state = SpeculativeState::new(old.clone());
+    let first = state.predict(owned(&old, &one));
+    let second = state.predict(owned(&one, &two));
+    assert_eq!(state.predicted(), &two);
+
+    // the server sends an unrelated update before acknowledging anything
+    let server: MyNode = element("ul", vec![attr("id", "list")], vec![]);
+    let reconciled = state.reconcile(None, &owned(&old, &server));
+    assert!(reconciled.rebuilt);
+    assert!(reconciled.rolled_back.is_empty());
+    assert_eq!(state.pending().collect::<Vec<_>>(), vec![first, second]);
+    assert_eq!(state.confirmed(), &server);
+    assert_eq!(
+        state.predicted(),
+        &element(
+            "ul",
+            vec![attr("id", "list"), attr("class", "busy")],
+            vec![element("li", vec![], vec![leaf("draft")])],
+        )
+    );
+}
+
+#[test]
+fn predictions_which_no_longer_apply_are_rolled_back() {
+    let old: MyNode = element("ul", vec![], vec![leaf("a"), leaf("b")]);
+    let edited: MyNode = element("ul", vec![], vec![leaf("a"), leaf("c")]);
+    let mut state = SpeculativeState::new(old.clone());
+    let generation = state.predict(owned(&old, &edited));
+
+    let cleared: MyNode = element("ul", vec![], vec![]);
+    let reconciled = state.reconcile(None, &owned(&old, &cleared));
+    assert_eq!(reconciled.rolled_back, vec![generation]);
+    assert_eq!(state.predicted(), &cleared);
+    assert_eq!(state.pending().count(), 0);
+}
+
+#[test]
+fn rollback_discards_the_predictions() {
+    let mut state = SpeculativeState::new(counter("0"));
+    let first = state.predict(owned(&counter("0"), &counter("1")));
+    let second = state.predict(owned(&counter("1"), &counter("2")));
+    assert_eq!(state.rollback(), vec![first, second]);
+    assert_eq!(state.predicted(), &counter("0"));
+}
@@ -0,0 +1,81 @@
+#![deny(warnings)]
+use mt_dom::*;
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn the_order_of_the_attributes_does_not_matter() {
+    let a: MyNode = element(
+        "div",
+        vec![attr("id", "a"), attr("class", "b")],
+        vec![leaf("text")],
+    );
+    let b: MyNode = element(
+        "div",
+        vec![attr("class", "b"), attr("id", "a")],
+        vec![leaf("text")],
+    );
+    assert_eq!(a.structural_hash(), b.structural_hash());
+
+    let c: MyNode = element(
+        "div",
+        vec![attr("class", "a"), attr("id", "b")],
+        vec![leaf("text")],
+    );
+    assert_ne!(a.structural_hash(), c.structural_hash());
+}
+
+#[test]
+fn the_tags_leaves_and_shapes_are_hashed() {
+    let node: MyNode = element(
+        "div",
+        vec![],
+        vec![element("p", vec![], vec![leaf("a")]), leaf("b")],
+    );
+    let tag: MyNode = element(
+        "div",
+        vec![],
+        vec![element("span", vec![], vec![leaf("a")]), leaf("b")],
+    );
+    let text: MyNode = element(
+        "div",
+        vec![],
+        vec![element("p", vec![], vec![leaf("a")]), leaf("c")],
+    );
+    let shape: MyNode = element(
+        "div",
+        vec![],
+        vec![element("p", vec![], vec![leaf("a"), leaf("b")])],
+    );
+    for other in [tag, text, shape] {
+        assert_ne!(node.structural_hash(), other.structural_hash());
+    }
+    assert_eq!(node.structural_hash(), node.clone().structural_hash());
+}
+
+#[test]
+fn the_excluded_attributes_are_not_hashed() {
+    let a: MyNode = element(
+        "button",
+        vec![attr("class", "ok"), attr("on_click", "1")],
+        vec![],
+    );
+    let b: MyNode = element(
+        "button",
+        vec![attr("on_click", "2"), attr("class", "ok")],
+        vec![],
+    );
+    assert_ne!(a.structural_hash(), b.structural_hash());
+    assert_eq!(
+        a.structural_hash_excluding(&["on_click"]),
+        b.structural_hash_excluding(&["on_click"])
+    );
+}
+
+#[test]
+fn the_memo_is_not_hashed() {
+    let node: MyNode = element("p", vec![], vec![leaf("text")]);
+    let memoized = Node::memoized(7, node.clone());
+    assert_eq!(node.structural_hash(), memoized.structural_hash());
+}
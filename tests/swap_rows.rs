@@ -1,3 +1,4 @@
+#![cfg(feature = "keyed")]
 use mt_dom::{diff::*, patch::*, *};
 
 pub type MyNode =
@@ -44,7 +45,7 @@ fn swap_999() {
         vec![
             Patch::move_before_node(
                 Some(&"li"),
-                TreePath::new([1]),
+                TreePath::new([2]),
                 [TreePath::new([998])]
             ),
             Patch::move_after_node(
@@ -151,7 +152,7 @@ fn move_key_2_to_after_node_index_6() {
         diff,
         vec![Patch::move_after_node(
             Some(&"div",),
-            TreePath::new([5]),
+            TreePath::new([6]),
             [TreePath::new([1])]
         ),]
     );
@@ -248,7 +249,7 @@ fn swap_rows_keyed() {
         vec![
             Patch::move_before_node(
                 Some(&"div"),
-                TreePath::new([1]),
+                TreePath::new([2]),
                 [TreePath::new([6])]
             ),
             Patch::move_after_node(
@@ -297,7 +298,7 @@ fn swap_rows_keyed_6_items() {
         vec![
             Patch::move_before_node(
                 Some(&"div",),
-                TreePath::new([1]),
+                TreePath::new([2]),
                 [TreePath::new([4])]
             ),
             Patch::move_after_node(
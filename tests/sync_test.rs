@@ -0,0 +1,81 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{merkle::*, patch::*, sync::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn table(rows: &[&'static str]) -> MyNode {
+    element(
+        "table",
+        vec![],
+        rows.iter().map(|row| {
+            element("tr", vec![], vec![element("td", vec![], vec![leaf(*row)])])
+        }),
+    )
+}
+
+#[test]
+fn only_the_changed_subtrees_are_pulled() {
+    let client = table(&["a", "b", "c", "d"]);
+    let server = table(&["a", "x", "c", "y"]);
+
+    let paths = changed_paths(&hash_tree(&client), &hash_tree(&server));
+    let responses = respond(&server, &paths);
+    assert_eq!(
+        responses,
+        vec![
+            (TreePath::new([1, 0, 0]), leaf("x")),
+            (TreePath::new([3, 0, 0]), leaf("y")),
+        ]
+    );
+
+    let mut synced = client.clone();
+    integrate(&mut synced, responses).unwrap();
+    assert_eq!(synced, server);
+
+    // the client diffs locally against the synced tree
+    let patches = diff_with_key(&client, &synced, &"key");
+    let mut patched = client.clone();
+    apply_patches(&mut patched, &patches);
+    assert_eq!(patched, server);
+}
+
+#[test]
+fn missing_paths_are_skipped() {
+    let server = table(&["a"]);
+    let responses = respond(&server, &[TreePath::new([5]), TreePath::new([0])]);
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].0, TreePath::new([0]));
+}
+
+#[test]
+fn responses_which_can_not_be_traversed_are_rejected() {
+    let mut client = table(&["a"]);
+    let result = integrate(
+        &mut client,
+        [
+            (TreePath::new([0, 0, 0]), leaf("b")),
+            (TreePath::new([0, 0, 0, 1]), leaf("c")),
+        ],
+    );
+    assert_eq!(
+        result,
+        Err(TraverseError::NotAnElement {
+            at_path: TreePath::new([0, 0, 0])
+        })
+    );
+    // the responses before the rejected response are integrated
+    assert_eq!(client, table(&["b"]));
+}
+
+#[test]
+fn changed_root_is_replaced() {
+    let mut client = table(&["a"]);
+    let server: MyNode = element("div", vec![], vec![]);
+
+    let paths = changed_paths(&hash_tree(&client), &hash_tree(&server));
+    assert_eq!(paths, vec![TreePath::root()]);
+    integrate(&mut client, respond(&server, &paths)).unwrap();
+    assert_eq!(client, server);
+}
@@ -0,0 +1,97 @@
+#![deny(warnings)]
+#![cfg(all(feature = "keyed", feature = "apply-patches"))]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, String, &'static str, &'static str>;
+
+fn cell(id: &'static str, column: &'static str) -> MyNode {
+    element(
+        "td",
+        vec![attr("column", column)],
+        vec![leaf(format!("{id}{column}"))],
+    )
+}
+
+fn row(id: &'static str, columns: &[&'static str]) -> MyNode {
+    element(
+        "tr",
+        vec![attr("id", id)],
+        columns.iter().map(|column| cell(id, column)),
+    )
+}
+
+fn table(ids: &[&'static str], columns: &[&'static str]) -> MyNode {
+    element("tbody", vec![], ids.iter().map(|id| row(id, columns)))
+}
+
+fn assert_patches_apply(old: &MyNode, new: &MyNode) -> Vec<PatchKind> {
+    let patches =
+        diff_table(old, new, &"id", &"column", &DiffOptions::default());
+    assert_eq!(validate(old, &patches), Ok(()));
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new);
+    patches.iter().map(Patch::kind).collect()
+}
+
+#[test]
+fn the_rows_and_columns_are_moved() {
+    let old = table(&["1", "2", "3"], &["a", "b", "c"]);
+    let new = table(&["3", "1", "2"], &["c", "a", "b"]);
+    let kinds = assert_patches_apply(&old, &new);
+    assert_eq!(kinds.len(), 4);
+    assert!(kinds.iter().all(|kind| *kind == PatchKind::MoveBeforeNode));
+}
+
+#[test]
+fn an_inserted_column_is_inserted_in_each_row() {
+    let old = table(&["1", "2"], &["a", "c"]);
+    let new = table(&["1", "2"], &["a", "b", "c"]);
+    assert_eq!(
+        assert_patches_apply(&old, &new),
+        [PatchKind::InsertAfterNode, PatchKind::InsertAfterNode]
+    );
+
+    // the cells have no `id`, so diffing with a single key changes the cells in place
+    let patches = diff_with_key(&old, &new, &"id");
+    assert!(patches.len() > 2);
+}
+
+#[test]
+fn the_rows_and_columns_are_removed() {
+    let old = table(&["1", "2", "3"], &["a", "b", "c"]);
+    let new = table(&["1", "3"], &["a", "c"]);
+    assert_eq!(
+        assert_patches_apply(&old, &new),
+        [
+            PatchKind::RemoveNode,
+            PatchKind::RemoveNode,
+            PatchKind::RemoveNode
+        ]
+    );
+}
+
+#[test]
+fn the_contents_of_the_matched_cells_are_diffed() {
+    let old = table(&["1", "2"], &["a", "b"]);
+    let changed = element(
+        "tr",
+        vec![attr("id", "1")],
+        vec![
+            cell("1", "b"),
+            element("td", vec![attr("column", "a")], vec![leaf("x".into())]),
+        ],
+    );
+    let new = element("tbody", vec![], vec![row("2", &["b", "a"]), changed]);
+    let patches =
+        diff_table(&old, &new, &"id", &"column", &DiffOptions::default());
+    let replaced: Vec<_> = patches
+        .iter()
+        .filter(|patch| patch.kind() == PatchKind::ReplaceNode)
+        .map(Patch::path)
+        .collect();
+    // the paths are in the old table, where the row and the cell are the first ones
+    assert_eq!(replaced, [&TreePath::new([0, 0, 0])]);
+    assert_patches_apply(&old, &new);
+}
@@ -0,0 +1,141 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+pub type MyPatch<'a> = Patch<
+    'a,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+>;
+
+fn keyed(key: &'static str) -> MyNode {
+    element("li", vec![attr("key", key)], vec![leaf(key)])
+}
+
+/// sort the patches and check that they still turn the old node into the new node
+fn assert_sorted_round_trip(
+    old: &MyNode,
+    new: &MyNode,
+    options: &DiffOptions<
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    >,
+) {
+    let mut patches = diff_with_options(old, new, &"key", options);
+    sort_patches_for_application(&mut patches);
+    assert!(patches
+        .windows(2)
+        .all(|pair| pair[0].priority() <= pair[1].priority()));
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(&root, new);
+}
+
+#[test]
+fn destructive_patches_are_applied_last() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("div", vec![], vec![]),
+            element("p", vec![attr("class", "old")], vec![]),
+            leaf("removed"),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("span", vec![], vec![]),
+            element("p", vec![attr("class", "new")], vec![]),
+        ],
+    );
+
+    let mut patches = diff_with_key(&old, &new, &"key");
+    sort_patches_for_application(&mut patches);
+    assert_eq!(
+        patches.iter().map(Patch::kind).collect::<Vec<_>>(),
+        [
+            PatchKind::AddAttributes,
+            PatchKind::ReplaceNode,
+            PatchKind::RemoveNode
+        ]
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
+
+#[test]
+fn priorities_of_the_patches() {
+    let node: MyNode = leaf("a");
+    assert_eq!(
+        MyPatch::add_attributes(&"div", TreePath::root(), []).priority(),
+        0
+    );
+    assert_eq!(
+        MyPatch::insert_before_node(None, TreePath::new([0]), [&node])
+            .priority(),
+        1
+    );
+    assert_eq!(
+        MyPatch::replace_node(None, TreePath::new([0]), [&node]).priority(),
+        2
+    );
+    assert_eq!(MyPatch::remove_node(None, TreePath::new([0])).priority(), 3);
+}
+
+#[test]
+fn sorted_keyed_patches_round_trip() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        ["a", "b", "c", "d", "e"].into_iter().map(keyed),
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![attr("class", "list")],
+        ["e", "x", "c", "a", "y"].into_iter().map(keyed),
+    );
+    assert_sorted_round_trip(&old, &new, &DiffOptions::default());
+}
+
+#[test]
+fn sorted_wrap_and_text_run_patches_round_trip() {
+    let old: MyNode = element(
+        "p",
+        vec![],
+        vec![
+            leaf("hello world"),
+            element("i", vec![], vec![]),
+            leaf("bold"),
+            leaf("gone"),
+        ],
+    );
+    let new: MyNode = element(
+        "p",
+        vec![],
+        vec![
+            leaf("hello "),
+            leaf("world"),
+            element("i", vec![], vec![]),
+            element("b", vec![], vec![leaf("bold")]),
+        ],
+    );
+    let options = DiffOptions {
+        detect_wrapping: true,
+        merge_text_runs: true,
+        ..Default::default()
+    };
+    assert_sorted_round_trip(&old, &new, &options);
+}
@@ -1,3 +1,4 @@
+#![cfg(feature = "keyed")]
 use mt_dom::{diff::*, patch::*, *};
 
 pub type MyNode =
@@ -50,44 +51,44 @@ fn key_lis_1_to_9() {
     assert_eq!(
         diff,
         vec![
-            Patch::insert_after_node(
+            Patch::insert_before_node(
                 Some(&"div"),
-                TreePath::new(vec![8]),
+                TreePath::new(vec![0]),
                 vec![
                     &element(
                         "div",
-                        vec![attr("key", "XXX4")],
+                        vec![attr("key", "XXX1")],
                         vec![leaf("lineXXX")]
                     ),
                     &element(
                         "div",
-                        vec![attr("key", "XXX5")],
+                        vec![attr("key", "XXX2")],
                         vec![leaf("lineXXX")]
                     ),
                     &element(
                         "div",
-                        vec![attr("key", "XXX6")],
+                        vec![attr("key", "XXX3")],
                         vec![leaf("lineXXX")]
                     ),
                 ]
             ),
-            Patch::insert_before_node(
+            Patch::insert_after_node(
                 Some(&"div"),
-                TreePath::new(vec![0]),
+                TreePath::new(vec![8]),
                 vec![
                     &element(
                         "div",
-                        vec![attr("key", "XXX1")],
+                        vec![attr("key", "XXX4")],
                         vec![leaf("lineXXX")]
                     ),
                     &element(
                         "div",
-                        vec![attr("key", "XXX2")],
+                        vec![attr("key", "XXX5")],
                         vec![leaf("lineXXX")]
                     ),
                     &element(
                         "div",
-                        vec![attr("key", "XXX3")],
+                        vec![attr("key", "XXX6")],
                         vec![leaf("lineXXX")]
                     ),
                 ]
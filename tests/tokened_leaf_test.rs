@@ -0,0 +1,60 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, *};
+
+pub type MyNode = Node<
+    &'static str,
+    &'static str,
+    TokenedLeaf<String, usize>,
+    &'static str,
+    &'static str,
+>;
+
+fn text(s: &str) -> TokenedLeaf<String, usize> {
+    TokenedLeaf::new(s.to_string())
+}
+
+fn measured(s: &str, token: usize) -> TokenedLeaf<String, usize> {
+    TokenedLeaf::with_token(s.to_string(), token)
+}
+
+#[test]
+fn tokens_are_ignored_by_equality() {
+    let old: MyNode = element("p", vec![], vec![leaf(measured("hello", 1))]);
+    let new: MyNode = element("p", vec![], vec![leaf(text("hello"))]);
+    assert_eq!(old, new);
+    assert!(diff_with_key(&old, &new, &"key").is_empty());
+}
+
+#[test]
+fn change_leaf_carries_both_tokens() {
+    let old: MyNode = element(
+        "p",
+        vec![],
+        vec![leaf(measured("hello", 1)), leaf(measured("world", 2))],
+    );
+    let new: MyNode = element(
+        "p",
+        vec![],
+        vec![leaf(text("hello")), leaf(measured("world!", 3))],
+    );
+    let options = DiffOptions {
+        change_leaf: true,
+        ..Default::default()
+    };
+    let patches = diff_with_options(&old, &new, &"key", &options);
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].path(), &TreePath::new([1]));
+    match &patches[0].patch_type {
+        PatchType::ChangeLeaf { old, new } => {
+            assert_eq!(old.token, Some(2));
+            assert_eq!(new.token, Some(3));
+            assert_eq!(new.leaf, "world!");
+        }
+        patch => panic!("expecting a ChangeLeaf, got: {:?}", patch),
+    }
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &patches);
+    assert_eq!(root, new);
+}
@@ -0,0 +1,154 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn sample() -> MyNode {
+    element(
+        "main",
+        vec![],
+        vec![
+            element("div", vec![], vec![leaf("a"), leaf("b")]),
+            leaf("c"),
+        ],
+    )
+}
+
+#[test]
+fn found_nodes() {
+    let node = sample();
+    assert_eq!(TreePath::root().try_find_node_by_path(&node), Ok(&node));
+    assert_eq!(
+        TreePath::new([0, 1]).try_find_node_by_path(&node),
+        Ok(&leaf("b"))
+    );
+    assert_eq!(
+        TreePath::new([1]).try_find_node_by_path(&node),
+        Ok(&leaf("c"))
+    );
+}
+
+#[test]
+fn malformed_paths() {
+    let node = sample();
+    assert_eq!(
+        TreePath::new([0, 5, 1]).try_find_node_by_path(&node),
+        Err(TraverseError::IndexOutOfBounds {
+            at_path: TreePath::new([0, 5])
+        })
+    );
+    assert_eq!(
+        TreePath::new([1, 0, 0]).try_find_node_by_path(&node),
+        Err(TraverseError::NotAnElement {
+            at_path: TreePath::new([1])
+        })
+    );
+    assert_eq!(
+        TreePath::new([1000]).try_find_node_by_path(&node),
+        Err(TraverseError::IndexOutOfBounds {
+            at_path: TreePath::new([1000])
+        })
+    );
+    assert_eq!(TreePath::new([0, 5]).find_node_by_path(&node), None);
+    assert_eq!(
+        TraverseError::NotAnElement {
+            at_path: TreePath::new([1])
+        }
+        .to_string(),
+        "the node at [1] is a leaf which has no children"
+    );
+}
+
+#[test]
+fn pop_first() {
+    let mut path = TreePath::new([2, 1]);
+    assert_eq!(path.pop_first(), Some(2));
+    assert_eq!(path.pop_first(), Some(1));
+    assert_eq!(path.pop_first(), None);
+}
+
+#[test]
+fn malformed_patches_are_rejected() {
+    let old = sample();
+    let mut root = old.clone();
+    let text = leaf("d");
+    let patches = vec![
+        Patch::append_children(Some(&"div"), TreePath::new([0]), vec![&text]),
+        Patch::insert_before_node(None, TreePath::root(), [&text]),
+    ];
+    assert_eq!(
+        try_apply_patches(&mut root, &patches),
+        Err(InvalidPatch::NoParent {
+            patch_index: 1,
+            path: TreePath::root()
+        })
+    );
+    assert_eq!(root, old);
+
+    assert_eq!(try_apply_patches(&mut root, &patches[..1]), Ok(()));
+    assert_eq!(
+        root,
+        element(
+            "main",
+            vec![],
+            vec![
+                element("div", vec![], vec![leaf("a"), leaf("b"), leaf("d")]),
+                leaf("c"),
+            ],
+        )
+    );
+}
+
+#[test]
+fn patches_on_replaced_or_removed_nodes() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![element("li", vec![attr("key", "5")], vec![leaf("text0")])],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element("li", vec![attr("key", "4")], vec![leaf("text0")]),
+            element("span", vec![attr("key", "5")], vec![leaf("text1")]),
+        ],
+    );
+    // the nodes are inserted before the node which replaced the target
+    let patches = diff_with_key(&old, &new, &"key");
+    let mut root = old.clone();
+    assert_eq!(try_apply_patches(&mut root, &patches), Ok(()));
+    assert_eq!(root, new);
+
+    let text = leaf("d");
+    let class = attr("class", "big");
+    let patches = vec![
+        Patch::remove_node(None, TreePath::new([0])),
+        Patch::insert_before_node(None, TreePath::new([0]), [&text]),
+    ];
+    let mut root = old.clone();
+    assert_eq!(
+        try_apply_patches(&mut root, &patches),
+        Err(InvalidPatch::DetachedTarget {
+            patch_index: 1,
+            path: TreePath::new([0])
+        })
+    );
+    assert_eq!(root, old);
+
+    let patches = vec![Patch::add_attributes(
+        &"li",
+        TreePath::new([0, 0]),
+        [&class],
+    )];
+    assert_eq!(
+        try_apply_patches(&mut root, &patches),
+        Err(InvalidPatch::NotAnElement {
+            patch_index: 0,
+            path: TreePath::new([0, 0])
+        })
+    );
+    assert_eq!(root, old);
+}
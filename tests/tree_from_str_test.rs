@@ -0,0 +1,90 @@
+#![deny(warnings)]
+use mt_dom::{testing::tree_from_str, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+#[test]
+fn nested_elements_and_leaves() {
+    let tree: MyNode = tree_from_str(
+        "main.container
+           div[key=1] 'line1'
+           div[key=2]
+             span 'line2'
+             'tail'",
+    );
+    assert_eq!(
+        tree,
+        element(
+            "main",
+            vec![attr("class", "container")],
+            vec![
+                element("div", vec![attr("key", "1")], vec![leaf("line1")]),
+                element(
+                    "div",
+                    vec![attr("key", "2")],
+                    vec![
+                        element("span", vec![], vec![leaf("line2")]),
+                        leaf("tail"),
+                    ]
+                ),
+            ]
+        )
+    );
+}
+
+#[test]
+fn classes_are_merged_into_one_attribute() {
+    let tree: MyNode =
+        tree_from_str("div.a#main.b[title='hello world'][hidden]");
+    assert_eq!(
+        tree,
+        element(
+            "div",
+            vec![
+                Attribute::with_multiple_values(None, "class", ["a", "b"]),
+                attr("id", "main"),
+                attr("title", "hello world"),
+                empty_attr("hidden"),
+            ],
+            vec![]
+        )
+    );
+}
+
+#[test]
+fn owned_types_are_supported() {
+    let tree: Node<String, String, String, String, String> =
+        tree_from_str("p \"text\"");
+    assert_eq!(
+        tree,
+        element("p".to_string(), vec![], vec![leaf("text".to_string())])
+    );
+}
+
+#[test]
+fn blank_lines_are_skipped() {
+    let tree: MyNode = tree_from_str("\nul\n\n  li\n");
+    assert_eq!(
+        tree,
+        element("ul", vec![], vec![element("li", vec![], vec![])])
+    );
+}
+
+#[test]
+#[should_panic(expected = "line 2: more than one root")]
+fn more_than_one_root_panics() {
+    let _: MyNode = tree_from_str("div\nspan");
+}
+
+#[test]
+#[should_panic(expected = "line 2: the leaf at line 1 can not have children")]
+fn leaf_with_children_panics() {
+    let _: MyNode = tree_from_str("'text'\n  div");
+}
+
+#[test]
+#[should_panic(expected = "line 1: expecting a closing quote")]
+fn unclosed_quote_panics() {
+    let _: MyNode = tree_from_str("div 'text");
+}
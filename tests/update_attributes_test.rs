@@ -0,0 +1,112 @@
+#![deny(warnings)]
+#![cfg(feature = "apply-patches")]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn update_attributes<'o>() -> DiffOptions<
+    'o,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+> {
+    DiffOptions {
+        update_attributes: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn added_and_removed_attributes_are_one_patch() {
+    let old: MyNode = element(
+        "div",
+        vec![attr("class", "old"), attr("hidden", "true")],
+        vec![],
+    );
+    let new: MyNode = element(
+        "div",
+        vec![attr("class", "new"), attr("id", "main")],
+        vec![],
+    );
+
+    let diff = diff_with_options(&old, &new, &"key", &update_attributes());
+    assert_eq!(
+        diff,
+        vec![Patch::update_attributes(
+            &"div",
+            TreePath::root(),
+            vec![&attr("class", "new"), &attr("id", "main")],
+            vec![&attr("hidden", "true")],
+        )]
+    );
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &diff);
+    assert_eq!(root, new);
+
+    // the attributes are still patched separately by default
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        diff.iter().map(|patch| patch.kind()).collect::<Vec<_>>(),
+        [PatchKind::AddAttributes, PatchKind::RemoveAttributes]
+    );
+}
+
+#[test]
+fn only_added_attributes_are_not_grouped() {
+    let old: MyNode = element("div", vec![], vec![]);
+    let new: MyNode = element("div", vec![attr("id", "main")], vec![]);
+
+    let diff = diff_with_options(&old, &new, &"key", &update_attributes());
+    assert_eq!(
+        diff,
+        vec![Patch::add_attributes(
+            &"div",
+            TreePath::root(),
+            vec![&attr("id", "main")]
+        )]
+    );
+}
+
+#[test]
+fn each_element_is_updated_once() {
+    let old: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element(
+                "li",
+                vec![attr("class", "a"), attr("hidden", "1")],
+                vec![],
+            ),
+            element("li", vec![attr("hidden", "1")], vec![]),
+        ],
+    );
+    let new: MyNode = element(
+        "ul",
+        vec![],
+        vec![
+            element("li", vec![attr("class", "b")], vec![]),
+            element("li", vec![attr("class", "c")], vec![]),
+        ],
+    );
+
+    let diff = diff_with_options(&old, &new, &"key", &update_attributes());
+    assert_eq!(
+        diff.iter()
+            .map(|patch| (patch.path().clone(), patch.kind()))
+            .collect::<Vec<_>>(),
+        [
+            (TreePath::new([0]), PatchKind::UpdateAttributes),
+            (TreePath::new([1]), PatchKind::UpdateAttributes),
+        ]
+    );
+    assert_eq!(PatchBatchSummary::from(diff.as_slice()).attr, 2);
+
+    let mut root = old.clone();
+    apply_patches(&mut root, &diff);
+    assert_eq!(root, new);
+}
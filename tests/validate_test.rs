@@ -0,0 +1,98 @@
+#![deny(warnings)]
+use mt_dom::{patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn sample() -> MyNode {
+    element(
+        "main",
+        vec![],
+        vec![element("div", vec![], vec![leaf("hello")]), leaf("world")],
+    )
+}
+
+#[test]
+fn diff_patches_are_valid() {
+    let old = sample();
+    let new: MyNode = element(
+        "main",
+        vec![attr("class", "container")],
+        vec![
+            leaf("world"),
+            element("div", vec![], vec![leaf("hi")]),
+            leaf("!"),
+        ],
+    );
+    let patches = diff_with_key(&old, &new, &"key");
+    assert_eq!(validate(&old, &patches), Ok(()));
+}
+
+#[test]
+fn path_past_the_end_of_children() {
+    let old = sample();
+    let patches = vec![
+        Patch::remove_node(None, TreePath::new([1])),
+        Patch::replace_node(None, TreePath::new([0, 1]), vec![&old]),
+    ];
+    assert_eq!(
+        validate(&old, &patches),
+        Err(InvalidPatch::UnresolvedPath {
+            patch_index: 1,
+            path: TreePath::new([0, 1]),
+        })
+    );
+}
+
+#[test]
+fn moved_node_path_must_resolve() {
+    let old = sample();
+    let patches: Vec<Patch<_, _, _, _, _>> = vec![Patch::move_before_node(
+        Some(&"div"),
+        TreePath::new([0]),
+        [TreePath::new([2])],
+    )];
+    assert_eq!(
+        validate(&old, &patches),
+        Err(InvalidPatch::UnresolvedPath {
+            patch_index: 0,
+            path: TreePath::new([2]),
+        })
+    );
+}
+
+#[test]
+fn insert_anchor_must_have_a_parent() {
+    let old = sample();
+    let new: MyNode = leaf("new");
+    let patches = vec![Patch::insert_before_node(
+        Some(&"main"),
+        TreePath::root(),
+        vec![&new],
+    )];
+    assert_eq!(
+        validate(&old, &patches),
+        Err(InvalidPatch::NoParent {
+            patch_index: 0,
+            path: TreePath::root(),
+        })
+    );
+}
+
+#[test]
+fn leaf_run_past_the_end_of_children() {
+    let old = sample();
+    let patches: Vec<Patch<_, _, _, _, _>> = vec![Patch::change_leaf_run(
+        TreePath::new([1]),
+        2,
+        vec![&"world"],
+    )];
+    assert_eq!(
+        validate(&old, &patches),
+        Err(InvalidPatch::OutOfRange {
+            patch_index: 0,
+            path: TreePath::new([1]),
+            count: 2,
+        })
+    );
+}
@@ -0,0 +1,274 @@
+#![deny(warnings)]
+use mt_dom::{diff::*, patch::*, *};
+
+pub type MyNode =
+    Node<&'static str, &'static str, &'static str, &'static str, &'static str>;
+
+fn wrap_or_unwrap<'o>() -> DiffOptions<
+    'o,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+> {
+    DiffOptions {
+        variant_change: Some(&|_old, _new| VariantChange::WrapOrUnwrap),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn leaf_replaced_by_default() {
+    let old: MyNode = element("p", vec![], vec![leaf("hello")]);
+    let new: MyNode =
+        element("p", vec![], vec![element("b", vec![], vec![leaf("hello")])]);
+
+    let diff = diff_with_key(&old, &new, &"key");
+    assert_eq!(
+        diff,
+        vec![Patch::replace_node(
+            None,
+            TreePath::new(vec![0]),
+            vec![&element("b", vec![], vec![leaf("hello")])]
+        )]
+    );
+}
+
+#[test]
+fn leaf_wrapped_with_element() {
+    let old: MyNode = element("p", vec![], vec![leaf("hello")]);
+    let new: MyNode =
+        element("p", vec![], vec![element("b", vec![], vec![leaf("hello")])]);
+
+    let diff = diff_with_options(&old, &new, &"key", &wrap_or_unwrap());
+    assert_eq!(
+        diff,
+        vec![Patch::wrap_node(
+            None,
+            TreePath::new(vec![0]),
+            &element("b", vec![], vec![leaf("hello")])
+        )]
+    );
+}
+
+#[test]
+fn leaf_unwrapped_from_element() {
+    let old: MyNode =
+        element("p", vec![], vec![element("b", vec![], vec![leaf("hello")])]);
+    let new: MyNode = element("p", vec![], vec![leaf("hello")]);
+
+    let diff = diff_with_options(&old, &new, &"key", &wrap_or_unwrap());
+    assert_eq!(
+        diff,
+        vec![Patch::unwrap_node(Some(&"b"), TreePath::new(vec![0]))]
+    );
+}
+
+#[test]
+fn fallback_to_replace_when_content_differs() {
+    let old: MyNode = element("p", vec![], vec![leaf("hello")]);
+    let new: MyNode =
+        element("p", vec![], vec![element("b", vec![], vec![leaf("world")])]);
+
+    let diff = diff_with_options(&old, &new, &"key", &wrap_or_unwrap());
+    assert_eq!(
+        diff,
+        vec![Patch::replace_node(
+            None,
+            TreePath::new(vec![0]),
+            vec![&element("b", vec![], vec![leaf("world")])]
+        )]
+    );
+}
+
+fn detect_wrapping<'o>() -> DiffOptions<
+    'o,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+> {
+    DiffOptions {
+        detect_wrapping: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn element_wrapped_with_another_element() {
+    let old: MyNode = element(
+        "p",
+        vec![],
+        vec![element(
+            "span",
+            vec![attr("class", "word")],
+            vec![leaf("hello")],
+        )],
+    );
+    let new: MyNode = element(
+        "p",
+        vec![],
+        vec![element(
+            "b",
+            vec![],
+            vec![element(
+                "span",
+                vec![attr("class", "word")],
+                vec![leaf("hello")],
+            )],
+        )],
+    );
+
+    let diff = diff_with_options(&old, &new, &"key", &detect_wrapping());
+    assert_eq!(
+        diff,
+        vec![Patch::wrap_node(
+            Some(&"span"),
+            TreePath::new(vec![0]),
+            &new.children()[0]
+        )]
+    );
+}
+
+#[test]
+fn element_unwrapped_from_another_element() {
+    let old: MyNode = element(
+        "p",
+        vec![],
+        vec![element(
+            "b",
+            vec![],
+            vec![element(
+                "span",
+                vec![attr("class", "word")],
+                vec![leaf("hello")],
+            )],
+        )],
+    );
+    let new: MyNode = element(
+        "p",
+        vec![],
+        vec![element(
+            "span",
+            vec![attr("class", "word")],
+            vec![leaf("hello")],
+        )],
+    );
+
+    let diff = diff_with_options(&old, &new, &"key", &detect_wrapping());
+    assert_eq!(
+        diff,
+        vec![Patch::unwrap_node(Some(&"b"), TreePath::new(vec![0]))]
+    );
+}
+
+#[test]
+fn leaf_wrapped_when_detect_wrapping() {
+    let old: MyNode = element("p", vec![], vec![leaf("hello")]);
+    let new: MyNode =
+        element("p", vec![], vec![element("b", vec![], vec![leaf("hello")])]);
+
+    let diff = diff_with_options(&old, &new, &"key", &detect_wrapping());
+    assert_eq!(
+        diff,
+        vec![Patch::wrap_node(
+            None,
+            TreePath::new(vec![0]),
+            &element("b", vec![], vec![leaf("hello")])
+        )]
+    );
+}
+
+#[test]
+fn explicit_replace_is_respected() {
+    let old: MyNode = element("p", vec![], vec![leaf("hello")]);
+    let new: MyNode =
+        element("p", vec![], vec![element("b", vec![], vec![leaf("hello")])]);
+
+    let diff = diff_with_options(&old, &new, &"key", &detect_wrapping());
+    assert_eq!(diff.len(), 1);
+    let diff = diff_with_functions(
+        &old,
+        &new,
+        &"key",
+        &|_old, _new| false,
+        &|_old, new| new.tag() == Some(&"b"),
+    );
+    assert_eq!(
+        diff,
+        vec![Patch::replace_node(
+            None,
+            TreePath::new(vec![0]),
+            vec![&element("b", vec![], vec![leaf("hello")])]
+        )]
+    );
+}
+
+#[test]
+fn children_hoisted_from_removed_level() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![element(
+            "section",
+            vec![],
+            vec![
+                element("div", vec![], vec![leaf("line1")]),
+                element("div", vec![], vec![leaf("line2")]),
+                element("div", vec![], vec![leaf("line3")]),
+            ],
+        )],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("div", vec![], vec![leaf("line1")]),
+            element("div", vec![], vec![leaf("line2")]),
+            element("div", vec![], vec![leaf("line3")]),
+        ],
+    );
+
+    assert_eq!(diff_with_key(&old, &new, &"key").len(), 2);
+    let diff = diff_with_options(&old, &new, &"key", &detect_wrapping());
+    assert_eq!(
+        diff,
+        vec![Patch::unwrap_node(Some(&"section"), TreePath::new(vec![0]))]
+    );
+}
+
+#[test]
+fn children_lowered_into_added_level() {
+    let old: MyNode = element(
+        "main",
+        vec![],
+        vec![
+            element("div", vec![], vec![leaf("line1")]),
+            element("div", vec![], vec![leaf("line2")]),
+        ],
+    );
+    let new: MyNode = element(
+        "main",
+        vec![],
+        vec![element(
+            "section",
+            vec![],
+            vec![
+                element("div", vec![], vec![leaf("line1")]),
+                element("div", vec![], vec![leaf("line2")]),
+            ],
+        )],
+    );
+
+    let diff = diff_with_options(&old, &new, &"key", &detect_wrapping());
+    assert_eq!(
+        diff,
+        vec![Patch::wrap_node(
+            Some(&"div"),
+            TreePath::new(vec![0]),
+            &new.children()[0]
+        )]
+    );
+}
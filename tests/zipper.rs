@@ -75,7 +75,7 @@ impl Zipper {
     }
 
     pub fn finish(mut self) -> MyNode {
-        while let Some(_) = self.parent {
+        while self.parent.is_some() {
             self = self.parent();
         }
         self.node
@@ -108,7 +108,6 @@ fn find_node_by_zipper(node: MyNode, path: &[usize]) -> Option<MyNode> {
 mod tests {
 
     use super::*;
-    use crate::*;
 
     fn sample_node() -> MyNode {
         let node: MyNode = element(